@@ -0,0 +1,47 @@
+//! Benchmarks for .ibt replay: reading samples and converting them to frames
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ost_adapters::ibt_parser::IbtFile;
+
+fn open_fixture() -> IbtFile {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt");
+    IbtFile::open(&path).unwrap()
+}
+
+fn bench_read_samples_range(c: &mut Criterion) {
+    let ibt = open_fixture();
+    let mut group = c.benchmark_group("ibt_read_samples_range");
+    // One frame, a second, and a ten second scrub
+    for count in [1, 60, 600] {
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            b.iter(|| black_box(ibt.read_samples_range(black_box(10_000), count).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_read_raw_samples(c: &mut Criterion) {
+    let ibt = open_fixture();
+    // One chunk of the lap, session and timeline scans
+    c.bench_function("ibt_read_raw_samples_4096", |b| {
+        b.iter(|| black_box(ibt.read_raw_samples(black_box(10_000), 4096).unwrap()))
+    });
+}
+
+fn bench_sample_to_frame(c: &mut Criterion) {
+    let ibt = open_fixture();
+    let sample = ibt.read_raw_samples(10_000, 1).unwrap();
+    c.bench_function("ibt_sample_to_frame", |b| {
+        b.iter(|| black_box(ibt.sample_to_frame(black_box(&sample))))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_read_samples_range,
+    bench_read_raw_samples,
+    bench_sample_to_frame,
+);
+criterion_main!(benches);
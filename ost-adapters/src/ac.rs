@@ -0,0 +1,1124 @@
+//! Assetto Corsa adapter
+//!
+//! Reads the original Assetto Corsa (not ACC) from one of two sources:
+//!
+//! - Shared memory (Windows, same machine): the `acpmf_physics`,
+//!   `acpmf_graphics` and `acpmf_static` pages, which carry the most data.
+//! - Remote telemetry: the UDP server AC runs on port 9996, which streams the
+//!   player's car to anyone who handshakes with it, including from another
+//!   machine. Used when `OST_AC_REMOTE` names the AC machine (`host` or
+//!   `host:port`), and on platforms without AC's shared memory.
+//!
+//! With `OST_CAPTURE_DIR` set, the pages (one snapshot record per frame) or
+//! datagrams are written to a capture that [`replay`] turns back into frames.
+
+use crate::capture::{Capture, RecordKind, Recording};
+use anyhow::{bail, Context, Result};
+use ost_core::adapter::{AdapterError, PollHint, TelemetryAdapter};
+use ost_core::coords::{Axis, CoordinateTransform};
+use ost_core::PlatformFeature;
+use ost_core::{model::*, units::*};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+const GAME: &str = "Assetto Corsa";
+
+/// AC's car-local axes: X left, Y up, Z forward
+const AC_AXES: CoordinateTransform = CoordinateTransform::new(Axis::NegX, Axis::Y, Axis::Z);
+
+const PSI_TO_KPA: f32 = 6.894_757;
+
+// =============================================================================
+// Shared memory pages
+// =============================================================================
+
+/// Bytes read from each page: the fields up to the last one mapped, as laid
+/// out by AC 1.16 (4-byte packing, UTF-16 strings)
+pub(crate) const PHYSICS_LEN: usize = 580;
+pub(crate) const GRAPHICS_LEN: usize = 296;
+pub(crate) const STATIC_LEN: usize = 590;
+
+/// `AC_STATUS`: 0 off, 1 replay, 2 live, 3 paused
+const STATUS_OFF: i32 = 0;
+
+/// `iBestTime` and friends before a lap has been set
+const NO_LAP_TIME: i32 = i32::MAX;
+
+/// What shared memory frames can contain, see
+/// [`TelemetryAdapter::capabilities`]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+const SHARED_MEMORY_CAPABILITIES: &[&str] = &[
+    "motion.position",
+    "motion.velocity",
+    "motion.g_force",
+    "motion.rotation",
+    "motion.pitch_rate",
+    "motion.yaw_rate",
+    "motion.roll_rate",
+    "vehicle.speed",
+    "vehicle.rpm",
+    "vehicle.max_rpm",
+    "vehicle.gear",
+    "vehicle.throttle",
+    "vehicle.brake",
+    "vehicle.clutch",
+    "vehicle.on_track",
+    "vehicle.in_garage",
+    "vehicle.car_name",
+    "engine.fuel_level",
+    "engine.fuel_level_pct",
+    "engine.fuel_capacity",
+    "engine.warnings",
+    "wheels.*.suspension_travel",
+    "wheels.*.tyre_pressure",
+    "wheels.*.surface_temp_inner",
+    "wheels.*.surface_temp_middle",
+    "wheels.*.surface_temp_outer",
+    "wheels.*.carcass_temp_middle",
+    "wheels.*.tyre_wear",
+    "wheels.*.wheel_speed",
+    "wheels.*.slip_ratio",
+    "wheels.*.load",
+    "wheels.*.brake_temp",
+    "wheels.*.tyre_compound",
+    "timing.current_lap_time",
+    "timing.last_lap_time",
+    "timing.best_lap_time",
+    "timing.lap_number",
+    "timing.laps_completed",
+    "timing.lap_distance",
+    "timing.lap_distance_pct",
+    "timing.race_position",
+    "timing.num_cars",
+    "session.session_type",
+    "session.session_time_remaining",
+    "session.session_laps",
+    "session.flags",
+    "session.track_name",
+    "session.track_config",
+    "session.track_length",
+    "weather.air_temp",
+    "weather.track_temp",
+    "weather.air_density",
+    "pit.on_pit_road",
+    "pit.pit_active",
+    "electronics.abs",
+    "electronics.traction_control",
+    "electronics.brake_bias",
+    "electronics.drs_status",
+    "driver.name",
+];
+
+/// A fixed-layout struct read by byte offset
+struct Page<'a>(&'a [u8]);
+
+impl<'a> Page<'a> {
+    /// Fails unless the page holds at least `len` bytes, so the reads below
+    /// stay in bounds
+    fn new(bytes: &'a [u8], len: usize, name: &str) -> Result<Self> {
+        if bytes.len() < len {
+            bail!(
+                "AC {} page is {} bytes, expected {}",
+                name,
+                bytes.len(),
+                len
+            );
+        }
+        Ok(Self(bytes))
+    }
+
+    fn i32(&self, offset: usize) -> i32 {
+        i32::from_le_bytes(self.0[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn f32(&self, offset: usize) -> f32 {
+        f32::from_le_bytes(self.0[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn f32s<const N: usize>(&self, offset: usize) -> [f32; N] {
+        std::array::from_fn(|i| self.f32(offset + i * 4))
+    }
+
+    /// A NUL-terminated `wchar_t[chars]` string
+    fn wstr(&self, offset: usize, chars: usize) -> String {
+        let units: Vec<u16> = self.0[offset..offset + chars * 2]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|&c| c != 0)
+            .collect();
+        String::from_utf16_lossy(&units)
+    }
+}
+
+fn lap_time(ms: i32) -> Option<Seconds> {
+    (ms > 0 && ms != NO_LAP_TIME).then(|| Seconds(ms as f32 / 1000.0))
+}
+
+/// World position, which keeps AC's own axes (y is already up)
+fn position([x, y, z]: [f32; 3]) -> Vector3<Meters> {
+    Vector3::new(Meters(x), Meters(y), Meters(z))
+}
+
+fn non_empty(s: String) -> Option<String> {
+    let s = s.trim().to_string();
+    (!s.is_empty()).then_some(s)
+}
+
+/// AC counts gears from reverse: 0 = R, 1 = N, 2 = first
+fn gear(value: i32) -> i8 {
+    (value - 1).clamp(-1, i8::MAX as i32) as i8
+}
+
+fn session_type(value: i32) -> SessionType {
+    match value {
+        0 => SessionType::Practice,
+        1 => SessionType::Qualifying,
+        2 => SessionType::Race,
+        3 => SessionType::Hotlap,
+        4 => SessionType::TimeTrial,
+        5 => SessionType::Drift,
+        _ => SessionType::Other,
+    }
+}
+
+fn flags(value: i32) -> FlagState {
+    let mut flags = FlagState::default();
+    match value {
+        1 => flags.blue = true,
+        2 => flags.yellow = true,
+        3 => flags.black = true,
+        4 => flags.white = true,
+        5 => flags.checkered = true,
+        _ => {}
+    }
+    flags
+}
+
+/// A frame from the three shared memory pages, or `None` while AC isn't
+/// running a session
+pub(crate) fn shared_memory_frame(
+    physics: &[u8],
+    graphics: &[u8],
+    statics: &[u8],
+) -> Result<Option<TelemetryFrame>> {
+    let p = Page::new(physics, PHYSICS_LEN, "physics")?;
+    let g = Page::new(graphics, GRAPHICS_LEN, "graphics")?;
+    let s = Page::new(statics, STATIC_LEN, "static")?;
+    if g.i32(4) == STATUS_OFF {
+        return Ok(None);
+    }
+
+    let mut frame = TelemetryFrame::new(GAME);
+    frame.meta.tick = Some(p.i32(0) as u32);
+
+    let max_rpm = s.i32(412);
+    let max_fuel = s.f32(416);
+    let fuel = p.f32(12);
+    let in_pit_lane = g.i32(276) != 0;
+    frame.vehicle = Some(VehicleData {
+        speed: Some(MetersPerSecond(p.f32(28) / 3.6)),
+        rpm: Some(Rpm(p.i32(20) as f32)),
+        max_rpm: (max_rpm > 0).then_some(Rpm(max_rpm as f32)),
+        gear: Some(gear(p.i32(16))),
+        throttle: Some(Percentage::new(p.f32(4))),
+        brake: Some(Percentage::new(p.f32(8))),
+        // AC reports 1 with the clutch engaged
+        clutch: Some(Percentage::new(1.0 - p.f32(364))),
+        in_garage: Some(g.i32(160) != 0),
+        on_track: Some(g.i32(160) == 0 && !in_pit_lane),
+        car_name: non_empty(s.wstr(68, 33)),
+        ..Default::default()
+    });
+
+    let [yaw, pitch, roll] = p.f32s::<3>(208);
+    let [pitch, yaw, roll] = AC_AXES.angular([pitch, yaw, roll]);
+    let [pitch_rate, yaw_rate, roll_rate] = AC_AXES.angular(p.f32s(296));
+    frame.motion = Some(MotionData {
+        position: Some(position(g.f32s(252))),
+        velocity: Some(AC_AXES.vector(p.f32s(568), MetersPerSecond)),
+        g_force: Some(AC_AXES.vector(p.f32s(44), GForce)),
+        rotation: Some(Vector3::new(
+            Degrees::from_radians(pitch),
+            Degrees::from_radians(yaw),
+            Degrees::from_radians(roll),
+        )),
+        pitch_rate: Some(DegreesPerSecond::from_radians(pitch_rate)),
+        yaw_rate: Some(DegreesPerSecond::from_radians(yaw_rate)),
+        roll_rate: Some(DegreesPerSecond::from_radians(roll_rate)),
+        ..Default::default()
+    });
+
+    frame.engine = Some(EngineData {
+        fuel_level: Some(Liters(fuel)),
+        fuel_level_pct: (max_fuel > 0.0).then(|| Percentage::new(fuel / max_fuel)),
+        fuel_capacity: (max_fuel > 0.0).then_some(Liters(max_fuel)),
+        warnings: Some(EngineWarnings {
+            pit_speed_limiter: p.i32(248) != 0,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let compound = non_empty(g.wstr(176, 33));
+    let wheel = |i: usize| WheelInfo {
+        tyre_pressure: Some(Kilopascals(p.f32(88 + i * 4) * PSI_TO_KPA)),
+        surface_temp_inner: Some(Celsius(p.f32(368 + i * 4))),
+        surface_temp_middle: Some(Celsius(p.f32(384 + i * 4))),
+        surface_temp_outer: Some(Celsius(p.f32(400 + i * 4))),
+        carcass_temp_middle: Some(Celsius(p.f32(152 + i * 4))),
+        // AC reports remaining tread as 0-100, 100 when new
+        tyre_wear: Some(Percentage::new(1.0 - p.f32(120 + i * 4) / 100.0)),
+        wheel_speed: Some(Rpm::from_radians_per_sec(p.f32(104 + i * 4))),
+        slip_ratio: Some(p.f32(56 + i * 4)),
+        load: Some(Newtons(p.f32(72 + i * 4))),
+        suspension_travel: Some(Millimeters(p.f32(184 + i * 4) * 1000.0)),
+        brake_temp: Some(Celsius(p.f32(348 + i * 4))),
+        tyre_compound: compound.clone(),
+        ..Default::default()
+    };
+    frame.wheels = Some(WheelData::new(wheel(0), wheel(1), wheel(2), wheel(3)));
+
+    let completed = g.i32(132).max(0) as u32;
+    let track_length = s.f32(520);
+    let lap_pct = g.f32(248);
+    let num_cars = s.i32(64);
+    frame.timing = Some(TimingData {
+        current_lap_time: Some(Seconds(g.i32(140).max(0) as f32 / 1000.0)),
+        last_lap_time: lap_time(g.i32(144)),
+        best_lap_time: lap_time(g.i32(148)),
+        lap_number: Some(completed + 1),
+        laps_completed: Some(completed),
+        lap_distance_pct: Some(Percentage::new(lap_pct)),
+        lap_distance: (track_length > 0.0).then_some(Meters(track_length * lap_pct)),
+        race_position: (g.i32(136) > 0).then_some(g.i32(136) as u32),
+        num_cars: (num_cars > 0).then_some(num_cars as u32),
+        ..Default::default()
+    });
+
+    let laps = g.i32(172);
+    frame.session = Some(SessionData {
+        session_type: Some(session_type(g.i32(8))),
+        session_time_remaining: Some(Seconds(g.f32(152).max(0.0) / 1000.0)),
+        session_laps: (laps > 0).then_some(laps as u32),
+        flags: Some(flags(g.i32(268))),
+        track_name: non_empty(s.wstr(134, 33)),
+        track_config: non_empty(s.wstr(524, 33)),
+        track_length: (track_length > 0.0).then_some(Meters(track_length)),
+        ..Default::default()
+    });
+
+    frame.weather = Some(WeatherData {
+        air_temp: Some(Celsius(p.f32(288))),
+        track_temp: Some(Celsius(p.f32(292))),
+        air_density: Some(KilogramsPerCubicMeter(p.f32(284))),
+        ..Default::default()
+    });
+
+    frame.pit = Some(PitData {
+        on_pit_road: Some(in_pit_lane),
+        pit_active: Some(g.i32(160) != 0),
+        ..Default::default()
+    });
+
+    frame.electronics = Some(ElectronicsData {
+        abs: Some(p.f32(252)),
+        traction_control: Some(p.f32(204)),
+        brake_bias: Some(Percentage::new(p.f32(564))),
+        drs_status: Some(p.f32(200).round() as u32),
+        ..Default::default()
+    });
+
+    let name = [s.wstr(200, 33), s.wstr(266, 33)].join(" ");
+    frame.driver = Some(DriverData {
+        name: non_empty(name),
+        ..Default::default()
+    });
+
+    Ok(Some(frame))
+}
+
+// =============================================================================
+// Remote telemetry (UDP)
+// =============================================================================
+
+const REMOTE_PORT: u16 = 9996;
+
+/// Handshake operations
+const HANDSHAKE: i32 = 0;
+const SUBSCRIBE_UPDATE: i32 = 1;
+const DISMISS: i32 = 3;
+
+/// `handshakerResponse`: four `wchar_t[50]` strings and two ints
+const HANDSHAKE_RESPONSE_LEN: usize = 408;
+/// `RTCarInfo`
+const CAR_INFO_LEN: usize = 328;
+
+/// What remote telemetry frames can contain: `RTCarInfo` has inputs, wheel
+/// slip and lap times, but no temperatures, fuel or session state
+const REMOTE_CAPABILITIES: &[&str] = &[
+    "motion.position",
+    "motion.g_force",
+    "vehicle.speed",
+    "vehicle.rpm",
+    "vehicle.gear",
+    "vehicle.throttle",
+    "vehicle.brake",
+    "vehicle.clutch",
+    "vehicle.car_name",
+    "engine.warnings",
+    "wheels.*.wheel_speed",
+    "wheels.*.slip_ratio",
+    "wheels.*.slip_angle",
+    "wheels.*.load",
+    "timing.current_lap_time",
+    "timing.last_lap_time",
+    "timing.best_lap_time",
+    "timing.lap_number",
+    "timing.laps_completed",
+    "timing.lap_distance_pct",
+    "session.track_name",
+    "session.track_config",
+    "pit.on_pit_road",
+    "electronics.abs_active",
+    "driver.name",
+];
+
+const READ_TIMEOUT: Duration = Duration::from_millis(50);
+const DETECT_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// How often to poll shared memory; AC's graphics page updates at 60 Hz
+#[cfg(target_os = "windows")]
+const SHARED_MEMORY_INTERVAL: Duration = Duration::from_millis(16);
+
+/// A `handshaker` message: identifier, version, operation
+fn handshake_message(operation: i32) -> Vec<u8> {
+    [1i32, 1, operation]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect()
+}
+
+/// What the handshake response told us about the session
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RemoteSession {
+    car: Option<String>,
+    driver: Option<String>,
+    track: Option<String>,
+    track_config: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct RemoteState {
+    session: Option<RemoteSession>,
+}
+
+/// What a datagram turned out to be
+#[derive(Debug)]
+enum Received {
+    Handshake,
+    Frame(Box<TelemetryFrame>),
+    /// Lap ("spot") updates and anything else
+    Other,
+}
+
+impl RemoteState {
+    fn apply(&mut self, packet: &[u8]) -> Result<Received> {
+        match packet.len() {
+            HANDSHAKE_RESPONSE_LEN => {
+                let page = Page(packet);
+                self.session = Some(RemoteSession {
+                    car: non_empty(page.wstr(0, 50)),
+                    driver: non_empty(page.wstr(100, 50)),
+                    track: non_empty(page.wstr(208, 50)),
+                    track_config: non_empty(page.wstr(308, 50)),
+                });
+                Ok(Received::Handshake)
+            }
+            CAR_INFO_LEN => Ok(Received::Frame(Box::new(self.frame(Page(packet))))),
+            _ => Ok(Received::Other),
+        }
+    }
+
+    fn frame(&self, p: Page) -> TelemetryFrame {
+        let session = self.session.clone().unwrap_or_default();
+        let mut frame = TelemetryFrame::new(GAME);
+        let flag = |offset: usize| p.0[offset] != 0;
+
+        frame.vehicle = Some(VehicleData {
+            speed: Some(MetersPerSecond(p.f32(16))),
+            rpm: Some(Rpm(p.f32(68))),
+            gear: Some(gear(p.i32(76))),
+            throttle: Some(Percentage::new(p.f32(56))),
+            brake: Some(Percentage::new(p.f32(60))),
+            clutch: Some(Percentage::new(1.0 - p.f32(64))),
+            car_name: session.car,
+            ..Default::default()
+        });
+        // accG_vertical, accG_horizontal, accG_frontal
+        let [vertical, horizontal, frontal] = p.f32s::<3>(28);
+        frame.motion = Some(MotionData {
+            position: Some(position(p.f32s(316))),
+            g_force: Some(AC_AXES.vector([horizontal, vertical, frontal], GForce)),
+            ..Default::default()
+        });
+        frame.engine = Some(EngineData {
+            warnings: Some(EngineWarnings {
+                rev_limiter: flag(25),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+
+        let wheel = |i: usize| WheelInfo {
+            wheel_speed: Some(Rpm::from_radians_per_sec(p.f32(84 + i * 4))),
+            slip_angle: Some(Degrees(p.f32(100 + i * 4))),
+            slip_ratio: Some(p.f32(132 + i * 4)),
+            load: Some(Newtons(p.f32(180 + i * 4))),
+            ..Default::default()
+        };
+        frame.wheels = Some(WheelData::new(wheel(0), wheel(1), wheel(2), wheel(3)));
+
+        let laps = p.i32(52).max(0) as u32;
+        frame.timing = Some(TimingData {
+            current_lap_time: Some(Seconds(p.i32(40).max(0) as f32 / 1000.0)),
+            last_lap_time: lap_time(p.i32(44)),
+            best_lap_time: lap_time(p.i32(48)),
+            lap_number: Some(laps + 1),
+            laps_completed: Some(laps),
+            lap_distance_pct: Some(Percentage::new(p.f32(308))),
+            ..Default::default()
+        });
+        frame.session = Some(SessionData {
+            track_name: session.track,
+            track_config: session.track_config,
+            ..Default::default()
+        });
+        frame.pit = Some(PitData {
+            on_pit_road: Some(flag(24)),
+            ..Default::default()
+        });
+        frame.electronics = Some(ElectronicsData {
+            abs_active: Some(flag(21)),
+            ..Default::default()
+        });
+        frame.driver = Some(DriverData {
+            name: session.driver,
+            ..Default::default()
+        });
+        frame
+    }
+}
+
+/// The frames the adapter would have produced from a captured session
+pub fn replay(capture: &Capture) -> Result<Vec<TelemetryFrame>> {
+    let mut remote = RemoteState::default();
+    let mut frames = Vec::new();
+    for record in &capture.records {
+        match record.kind {
+            RecordKind::Packet => {
+                if let Received::Frame(frame) = remote.apply(&record.data)? {
+                    frames.push(*frame);
+                }
+            }
+            RecordKind::Snapshot => {
+                let (physics, rest) = record.data.split_at(PHYSICS_LEN.min(record.data.len()));
+                let (graphics, statics) = rest.split_at(GRAPHICS_LEN.min(rest.len()));
+                frames.extend(shared_memory_frame(physics, graphics, statics)?);
+            }
+        }
+    }
+    Ok(frames)
+}
+
+// =============================================================================
+// Adapter
+// =============================================================================
+
+enum Source {
+    Remote {
+        addr: SocketAddr,
+        socket: Option<UdpSocket>,
+        state: RemoteState,
+    },
+    #[cfg(target_os = "windows")]
+    SharedMemory(Option<windows_impl::SharedMemory>),
+}
+
+pub struct AcAdapter {
+    source: Source,
+    capture: Recording,
+    active: bool,
+}
+
+impl AcAdapter {
+    /// Shared memory on Windows, unless `OST_AC_REMOTE` names a machine to
+    /// get remote telemetry from
+    pub fn new() -> Self {
+        let remote = std::env::var("OST_AC_REMOTE").ok().and_then(|host| {
+            let with_port = if host.contains(':') {
+                host
+            } else {
+                format!("{}:{}", host, REMOTE_PORT)
+            };
+            with_port.to_socket_addrs().ok()?.next()
+        });
+        match remote {
+            Some(addr) => Self::remote(addr),
+            #[cfg(target_os = "windows")]
+            None => Self::with_source(Source::SharedMemory(None)),
+            #[cfg(not(target_os = "windows"))]
+            None => Self::remote(SocketAddr::from(([127, 0, 0, 1], REMOTE_PORT))),
+        }
+    }
+
+    /// Get remote telemetry from AC's UDP server at `addr`
+    pub fn remote(addr: SocketAddr) -> Self {
+        Self::with_source(Source::Remote {
+            addr,
+            socket: None,
+            state: RemoteState::default(),
+        })
+    }
+
+    fn with_source(source: Source) -> Self {
+        Self {
+            source,
+            capture: Recording::new("ac"),
+            active: false,
+        }
+    }
+
+    fn bind(addr: SocketAddr) -> Result<UdpSocket> {
+        let local: SocketAddr = if addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        Ok(socket)
+    }
+}
+
+impl Default for AcAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TelemetryAdapter for AcAdapter {
+    fn key(&self) -> &str {
+        "ac"
+    }
+
+    fn name(&self) -> &str {
+        GAME
+    }
+
+    fn detect(&self) -> bool {
+        match &self.source {
+            // Handshake briefly and see whether AC answers
+            Source::Remote { addr, .. } => {
+                let probe = || -> Result<bool> {
+                    let socket = Self::bind(*addr)?;
+                    socket.set_read_timeout(Some(DETECT_TIMEOUT))?;
+                    socket.send(&handshake_message(HANDSHAKE))?;
+                    let mut buf = [0u8; 512];
+                    let len = socket.recv(&mut buf)?;
+                    let answered = len == HANDSHAKE_RESPONSE_LEN;
+                    if answered {
+                        socket.send(&handshake_message(DISMISS))?;
+                    }
+                    Ok(answered)
+                };
+                probe().unwrap_or(false)
+            }
+            #[cfg(target_os = "windows")]
+            Source::SharedMemory(_) => windows_impl::detect(),
+        }
+    }
+
+    fn start(&mut self) -> Result<(), AdapterError> {
+        match &mut self.source {
+            Source::Remote {
+                addr,
+                socket,
+                state,
+            } => {
+                let s = Self::bind(*addr).context("Failed to open AC remote telemetry socket")?;
+                s.set_read_timeout(Some(READ_TIMEOUT))?;
+                s.send(&handshake_message(HANDSHAKE))?;
+                *socket = Some(s);
+                *state = RemoteState::default();
+            }
+            #[cfg(target_os = "windows")]
+            Source::SharedMemory(memory) => {
+                *memory = Some(windows_impl::SharedMemory::open()?);
+            }
+        }
+        self.capture.start()?;
+        self.active = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), AdapterError> {
+        match &mut self.source {
+            Source::Remote { socket, .. } => {
+                if let Some(socket) = socket.take() {
+                    // Best effort: AC stops sending to silent clients anyway
+                    let _ = socket.send(&handshake_message(DISMISS));
+                }
+            }
+            #[cfg(target_os = "windows")]
+            Source::SharedMemory(memory) => *memory = None,
+        }
+        self.active = false;
+        self.capture.finish()?;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>, AdapterError> {
+        match &mut self.source {
+            Source::Remote { socket, state, .. } => {
+                let Some(socket) = socket else {
+                    return Ok(None);
+                };
+                let mut buf = [0u8; 512];
+                let len = match socket.recv(&mut buf) {
+                    Ok(len) => len,
+                    Err(e)
+                        if matches!(
+                            e.kind(),
+                            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                        ) =>
+                    {
+                        return Ok(None)
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+                let received = state
+                    .apply(&buf[..len])
+                    .map_err(|e| AdapterError::Parse(format!("{:#}", e)))?;
+                if matches!(received, Received::Handshake) {
+                    socket.send(&handshake_message(SUBSCRIBE_UPDATE))?;
+                }
+                self.capture.record(RecordKind::Packet, &buf[..len])?;
+                match received {
+                    Received::Frame(frame) => Ok(Some(*frame)),
+                    _ => Ok(None),
+                }
+            }
+            #[cfg(target_os = "windows")]
+            Source::SharedMemory(memory) => {
+                let Some(snapshot) = memory.as_mut().and_then(|m| m.read_if_changed()) else {
+                    return Ok(None);
+                };
+                self.capture.record(RecordKind::Snapshot, &snapshot)?;
+                let (physics, rest) = snapshot.split_at(PHYSICS_LEN);
+                let (graphics, statics) = rest.split_at(GRAPHICS_LEN);
+                shared_memory_frame(physics, graphics, statics)
+                    .map_err(|e| AdapterError::Parse(format!("{:#}", e)))
+            }
+        }
+    }
+
+    fn poll_hint(&self) -> PollHint {
+        match &self.source {
+            Source::Remote { .. } => PollHint::Blocking,
+            #[cfg(target_os = "windows")]
+            Source::SharedMemory(_) => PollHint::Interval(SHARED_MEMORY_INTERVAL),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn capabilities(&self) -> &'static [&'static str] {
+        match self.source {
+            Source::Remote { .. } => REMOTE_CAPABILITIES,
+            #[cfg(target_os = "windows")]
+            Source::SharedMemory(_) => SHARED_MEMORY_CAPABILITIES,
+        }
+    }
+
+    fn platform_requirements(&self) -> &'static [PlatformFeature] {
+        match self.source {
+            Source::Remote { .. } => &[PlatformFeature::Udp],
+            #[cfg(target_os = "windows")]
+            Source::SharedMemory(_) => &[PlatformFeature::SharedMemory],
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use super::{GRAPHICS_LEN, PHYSICS_LEN, STATIC_LEN};
+    use anyhow::{bail, Result};
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+        TH32CS_SNAPPROCESS,
+    };
+    use windows::Win32::System::Memory::{
+        MapViewOfFile, OpenFileMappingW, UnmapViewOfFile, FILE_MAP_READ, MEMORY_MAPPED_VIEW_ADDRESS,
+    };
+
+    /// AC's game process. ACC publishes pages under the same names, so the
+    /// mappings alone don't tell the two apart.
+    const PROCESS: &str = "acs.exe";
+
+    pub fn detect() -> bool {
+        process_running(PROCESS) && Page::open(w!("Local\\acpmf_static"), STATIC_LEN).is_ok()
+    }
+
+    fn process_running(name: &str) -> bool {
+        // SAFETY: the snapshot handle is closed below, and PROCESSENTRY32W is
+        // initialised with its size as the API requires
+        unsafe {
+            let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else {
+                return false;
+            };
+            let mut entry = PROCESSENTRY32W {
+                dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+                ..Default::default()
+            };
+            let mut found = false;
+            let mut ok = Process32FirstW(snapshot, &mut entry).is_ok();
+            while ok && !found {
+                let len = entry
+                    .szExeFile
+                    .iter()
+                    .position(|&c| c == 0)
+                    .unwrap_or(entry.szExeFile.len());
+                found =
+                    String::from_utf16_lossy(&entry.szExeFile[..len]).eq_ignore_ascii_case(name);
+                ok = Process32NextW(snapshot, &mut entry).is_ok();
+            }
+            let _ = CloseHandle(snapshot);
+            found
+        }
+    }
+
+    /// One mapped page
+    struct Page {
+        handle: HANDLE,
+        view: MEMORY_MAPPED_VIEW_ADDRESS,
+        len: usize,
+    }
+
+    // SAFETY: the view is only read, and the handle is only closed on drop
+    unsafe impl Send for Page {}
+
+    impl Page {
+        fn open(name: PCWSTR, len: usize) -> Result<Self> {
+            // SAFETY: the handle is closed if mapping fails, and on drop
+            unsafe {
+                let handle = OpenFileMappingW(FILE_MAP_READ.0, false, name)?;
+                let view = MapViewOfFile(handle, FILE_MAP_READ, 0, 0, len);
+                if view.Value.is_null() {
+                    let _ = CloseHandle(handle);
+                    bail!("Failed to map AC shared memory");
+                }
+                Ok(Self { handle, view, len })
+            }
+        }
+
+        fn bytes(&self) -> &[u8] {
+            // SAFETY: the view is at least `len` bytes, as mapped in `open`
+            unsafe { std::slice::from_raw_parts(self.view.Value as *const u8, self.len) }
+        }
+    }
+
+    impl Drop for Page {
+        fn drop(&mut self) {
+            // SAFETY: both were created in `open` and are released once
+            unsafe {
+                let _ = UnmapViewOfFile(self.view);
+                let _ = CloseHandle(self.handle);
+            }
+        }
+    }
+
+    pub struct SharedMemory {
+        physics: Page,
+        graphics: Page,
+        statics: Page,
+        /// Packet ids of the last pages read
+        last_ids: Option<(i32, i32)>,
+    }
+
+    impl SharedMemory {
+        pub fn open() -> Result<Self> {
+            Ok(Self {
+                physics: Page::open(w!("Local\\acpmf_physics"), PHYSICS_LEN)?,
+                graphics: Page::open(w!("Local\\acpmf_graphics"), GRAPHICS_LEN)?,
+                statics: Page::open(w!("Local\\acpmf_static"), STATIC_LEN)?,
+                last_ids: None,
+            })
+        }
+
+        /// The three pages back to back, unless neither packet id moved
+        /// (paused, or not yet updated since the last read)
+        pub fn read_if_changed(&mut self) -> Option<Vec<u8>> {
+            let mut snapshot = Vec::with_capacity(PHYSICS_LEN + GRAPHICS_LEN + STATIC_LEN);
+            snapshot.extend_from_slice(self.physics.bytes());
+            snapshot.extend_from_slice(self.graphics.bytes());
+            snapshot.extend_from_slice(self.statics.bytes());
+            let id = |offset: usize| {
+                i32::from_le_bytes(snapshot[offset..offset + 4].try_into().unwrap())
+            };
+            let ids = (id(0), id(PHYSICS_LEN));
+            if self.last_ids == Some(ids) {
+                return None;
+            }
+            self.last_ids = Some(ids);
+            Some(snapshot)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::CaptureWriter;
+    use ost_core::adapter::Capabilities;
+
+    /// Writes values into a zeroed page by offset
+    struct PageWriter(Vec<u8>);
+
+    impl PageWriter {
+        fn new(len: usize) -> Self {
+            Self(vec![0; len])
+        }
+
+        fn i32(mut self, offset: usize, v: i32) -> Self {
+            self.0[offset..offset + 4].copy_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        fn f32(mut self, offset: usize, v: f32) -> Self {
+            self.0[offset..offset + 4].copy_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        fn wstr(mut self, offset: usize, s: &str) -> Self {
+            for (i, c) in s.encode_utf16().enumerate() {
+                self.0[offset + i * 2..offset + i * 2 + 2].copy_from_slice(&c.to_le_bytes());
+            }
+            self
+        }
+    }
+
+    fn pages() -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let physics = PageWriter::new(PHYSICS_LEN)
+            .i32(0, 1234)
+            .f32(4, 0.75)
+            .f32(12, 30.0)
+            .i32(16, 4)
+            .i32(20, 7200)
+            .f32(28, 180.0)
+            .f32(44, 1.5)
+            .f32(88, 27.0)
+            .f32(120, 95.0)
+            .f32(368, 80.0)
+            .f32(384, 85.0)
+            .f32(400, 90.0)
+            .i32(248, 1)
+            .f32(564, 0.58);
+        let graphics = PageWriter::new(GRAPHICS_LEN)
+            .i32(4, 2)
+            .i32(8, 2)
+            .i32(132, 3)
+            .i32(136, 5)
+            .i32(144, 92_345)
+            .i32(148, NO_LAP_TIME)
+            .wstr(176, "Medium")
+            .f32(248, 0.5)
+            .i32(268, 2);
+        let statics = PageWriter::new(STATIC_LEN)
+            .i32(64, 18)
+            .wstr(68, "ks_porsche_911_gt3_r_2016")
+            .wstr(134, "ks_nurburgring")
+            .wstr(200, "Sam")
+            .wstr(266, "Doe")
+            .i32(412, 9000)
+            .f32(416, 120.0)
+            .f32(520, 5100.0)
+            .wstr(524, "layout_gp");
+        (physics.0, graphics.0, statics.0)
+    }
+
+    #[test]
+    fn test_shared_memory_frame() {
+        let (physics, graphics, statics) = pages();
+        let frame = shared_memory_frame(&physics, &graphics, &statics)
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.meta.game, "Assetto Corsa");
+
+        let vehicle = frame.vehicle.as_ref().unwrap();
+        assert_eq!(vehicle.gear, Some(3));
+        assert_eq!(vehicle.speed, Some(MetersPerSecond(50.0)));
+        assert_eq!(vehicle.max_rpm, Some(Rpm(9000.0)));
+        assert_eq!(
+            vehicle.car_name.as_deref(),
+            Some("ks_porsche_911_gt3_r_2016")
+        );
+        // X is left in AC
+        assert_eq!(
+            frame.motion.as_ref().unwrap().g_force.unwrap().x,
+            GForce(-1.5)
+        );
+
+        let engine = frame.engine.as_ref().unwrap();
+        assert_eq!(engine.fuel_level_pct, Some(Percentage(0.25)));
+        assert!(engine.warnings.unwrap().pit_speed_limiter);
+
+        let fl = &frame.wheels.as_ref().unwrap().front_left;
+        assert!((fl.tyre_pressure.unwrap().0 - 186.158).abs() < 0.01);
+        assert_eq!(fl.surface_temp_outer, Some(Celsius(90.0)));
+        assert!((fl.tyre_wear.unwrap().0 - 0.05).abs() < 1e-6);
+        assert_eq!(fl.tyre_compound.as_deref(), Some("Medium"));
+
+        let timing = frame.timing.as_ref().unwrap();
+        assert_eq!(timing.lap_number, Some(4));
+        assert_eq!(timing.last_lap_time, Some(Seconds(92.345)));
+        assert_eq!(timing.best_lap_time, None);
+        assert_eq!(timing.lap_distance, Some(Meters(2550.0)));
+        assert_eq!(timing.num_cars, Some(18));
+
+        let session = frame.session.as_ref().unwrap();
+        assert_eq!(session.session_type, Some(SessionType::Race));
+        assert_eq!(session.track_config.as_deref(), Some("layout_gp"));
+        assert!(session.flags.unwrap().yellow);
+        assert_eq!(
+            frame.driver.as_ref().unwrap().name.as_deref(),
+            Some("Sam Doe")
+        );
+        let undeclared = Capabilities::from_patterns(SHARED_MEMORY_CAPABILITIES)
+            .unwrap()
+            .undeclared(&frame);
+        assert!(undeclared.is_empty(), "{:?}", undeclared);
+
+        // Not in a session
+        let off = PageWriter::new(GRAPHICS_LEN).0;
+        assert!(shared_memory_frame(&physics, &off, &statics)
+            .unwrap()
+            .is_none());
+        assert!(shared_memory_frame(&physics[..100], &graphics, &statics).is_err());
+    }
+
+    #[test]
+    fn test_right_turn_is_canonical() {
+        // Steady right-hander in AC's axes (X left, Y up, Z forward): lateral
+        // G towards -X, clockwise yaw negative about Y-up
+        let (physics, graphics, statics) = pages();
+        let physics = PageWriter(physics)
+            .f32(44, -2.0)
+            .f32(48, 1.0)
+            .f32(52, 0.0)
+            .f32(300, -0.5)
+            .0;
+        let frame = shared_memory_frame(&physics, &graphics, &statics)
+            .unwrap()
+            .unwrap();
+        let motion = frame.motion.unwrap();
+        assert_eq!(
+            motion.g_force.unwrap().x,
+            GForce(2.0),
+            "right turn is positive lateral G"
+        );
+        assert_eq!(motion.g_force.unwrap().y, GForce(1.0), "+1 g vertical");
+        assert!(
+            motion.yaw_rate.unwrap().0 > 0.0,
+            "right turn is positive yaw"
+        );
+
+        // Remote telemetry: accG vertical, horizontal, frontal
+        let car_info = PageWriter::new(CAR_INFO_LEN)
+            .f32(28, 1.0)
+            .f32(32, -2.0)
+            .f32(36, 0.0)
+            .0;
+        let Received::Frame(frame) = RemoteState::default().apply(&car_info).unwrap() else {
+            panic!("car info is a frame");
+        };
+        let g_force = frame.motion.unwrap().g_force.unwrap();
+        assert_eq!(g_force.x, GForce(2.0), "right turn is positive lateral G");
+        assert_eq!(g_force.y, GForce(1.0), "+1 g vertical");
+    }
+
+    #[test]
+    fn test_snapshot_replay() {
+        let (physics, graphics, statics) = pages();
+        let mut writer = CaptureWriter::new(Vec::new(), "ac").unwrap();
+        writer
+            .record(RecordKind::Snapshot, &[physics, graphics, statics].concat())
+            .unwrap();
+        let capture = Capture::read(writer.finish().unwrap().as_slice()).unwrap();
+        let frames = replay(&capture).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].timing.as_ref().unwrap().race_position, Some(5));
+    }
+
+    #[test]
+    fn test_remote_telemetry_over_udp() {
+        let ac = UdpSocket::bind("127.0.0.1:0").unwrap();
+        ac.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let mut adapter = AcAdapter::remote(ac.local_addr().unwrap());
+        adapter.start().unwrap();
+
+        let mut buf = [0u8; 64];
+        let (len, client) = ac.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..len], handshake_message(HANDSHAKE).as_slice());
+
+        let response = PageWriter::new(HANDSHAKE_RESPONSE_LEN)
+            .wstr(0, "abarth500")
+            .wstr(100, "Sam")
+            .i32(200, 1)
+            .i32(204, 1)
+            .wstr(208, "magione")
+            .0;
+        ac.send_to(&response, client).unwrap();
+        assert!(adapter.read_frame().unwrap().is_none());
+        // The handshake is followed by a subscription
+        let (len, _) = ac.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..len], handshake_message(SUBSCRIBE_UPDATE).as_slice());
+
+        let mut car_info = PageWriter::new(CAR_INFO_LEN)
+            .i32(4, CAR_INFO_LEN as i32)
+            .f32(16, 40.0)
+            .i32(44, 75_500)
+            .i32(52, 2)
+            .f32(68, 6100.0)
+            .i32(76, 0)
+            .f32(308, 0.25)
+            .0;
+        car_info[24] = 1;
+        ac.send_to(&car_info, client).unwrap();
+        let frame = adapter.read_frame().unwrap().unwrap();
+        assert_eq!(frame.vehicle.as_ref().unwrap().gear, Some(-1));
+        assert_eq!(
+            frame.vehicle.as_ref().unwrap().car_name.as_deref(),
+            Some("abarth500")
+        );
+        let timing = frame.timing.as_ref().unwrap();
+        assert_eq!(timing.last_lap_time, Some(Seconds(75.5)));
+        assert_eq!(timing.lap_number, Some(3));
+        assert_eq!(frame.pit.as_ref().unwrap().on_pit_road, Some(true));
+        assert_eq!(
+            frame.session.as_ref().unwrap().track_name.as_deref(),
+            Some("magione")
+        );
+        let undeclared = Capabilities::from_patterns(adapter.capabilities())
+            .unwrap()
+            .undeclared(&frame);
+        assert!(undeclared.is_empty(), "{:?}", undeclared);
+
+        adapter.stop().unwrap();
+        let (len, _) = ac.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..len], handshake_message(DISMISS).as_slice());
+    }
+}
@@ -0,0 +1,924 @@
+//! Assetto Corsa Competizione broadcasting adapter
+//!
+//! Reads ACC's Broadcasting SDK, a UDP protocol meant for spectator tools,
+//! which reports every car in the session: positions, laps, lap times, pit
+//! state and the entry list. Frames follow the car ACC has in focus. The
+//! listener is configured in ACC's `Documents/Assetto Corsa
+//! Competizione/Config/broadcasting.json` (port 9000, password "asd" by
+//! default); `OST_ACC_BROADCAST` and `OST_ACC_PASSWORD` override them here.
+//! With `OST_CAPTURE_DIR` set, every datagram received is also written to a
+//! capture that [`replay`] turns back into frames.
+
+use crate::capture::{Capture, RecordKind, Recording};
+use anyhow::{bail, Context, Result};
+use ost_core::{
+    adapter::{AdapterError, TelemetryAdapter},
+    model::*,
+    units::*,
+    PlatformFeature,
+};
+use std::collections::BTreeMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+const PROTOCOL_VERSION: u8 = 4;
+/// How often ACC sends realtime updates, in milliseconds
+const UPDATE_INTERVAL_MS: i32 = 100;
+const READ_TIMEOUT: Duration = Duration::from_millis(50);
+/// How long [`AccAdapter::detect`] waits for ACC to answer a registration
+const DETECT_TIMEOUT: Duration = Duration::from_millis(50);
+/// Minimum gap between entry list requests when unknown cars show up
+const ENTRY_LIST_RETRY: Duration = Duration::from_secs(1);
+/// Lap times ACC reports for laps without one
+const NO_LAP_TIME: i32 = i32::MAX;
+
+// Outbound message types
+const REGISTER_COMMAND_APPLICATION: u8 = 1;
+const UNREGISTER_COMMAND_APPLICATION: u8 = 9;
+const REQUEST_ENTRY_LIST: u8 = 10;
+const REQUEST_TRACK_DATA: u8 = 11;
+
+// Inbound message types
+const REGISTRATION_RESULT: u8 = 1;
+const REALTIME_UPDATE: u8 = 2;
+const REALTIME_CAR_UPDATE: u8 = 3;
+const ENTRY_LIST: u8 = 4;
+const TRACK_DATA: u8 = 5;
+const ENTRY_LIST_CAR: u8 = 6;
+
+// =============================================================================
+// Wire format — little-endian, strings prefixed with a u16 byte length
+// =============================================================================
+
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.buf.len() < len {
+            return Err(AdapterError::Parse("ACC broadcast message truncated".into()).into());
+        }
+        let (head, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+
+    fn lap(&mut self) -> Result<Lap> {
+        let lap_time_ms = self.i32()?;
+        let _car_index = self.u16()?;
+        let _driver_index = self.u16()?;
+        let splits = self.u8()? as usize;
+        self.bytes(splits * 4)?;
+        let invalid = self.u8()? != 0;
+        let _valid_for_best = self.u8()?;
+        let _out_lap = self.u8()?;
+        let _in_lap = self.u8()?;
+        Ok(Lap {
+            time: (lap_time_ms != NO_LAP_TIME).then(|| Seconds(lap_time_ms as f32 / 1000.0)),
+            invalid,
+        })
+    }
+}
+
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn new(message_type: u8) -> Self {
+        Self {
+            buf: vec![message_type],
+        }
+    }
+
+    fn u8(mut self, v: u8) -> Self {
+        self.buf.push(v);
+        self
+    }
+
+    fn i32(mut self, v: i32) -> Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    fn string(mut self, s: &str) -> Self {
+        self.buf.extend_from_slice(&(s.len() as u16).to_le_bytes());
+        self.buf.extend_from_slice(s.as_bytes());
+        self
+    }
+}
+
+fn register_message(password: &str) -> Vec<u8> {
+    Writer::new(REGISTER_COMMAND_APPLICATION)
+        .u8(PROTOCOL_VERSION)
+        .string("OpenSimTelemetry")
+        .string(password)
+        .i32(UPDATE_INTERVAL_MS)
+        // No command password: read-only
+        .string("")
+        .buf
+}
+
+fn connection_message(message_type: u8, connection_id: i32) -> Vec<u8> {
+    Writer::new(message_type).i32(connection_id).buf
+}
+
+// =============================================================================
+// Broadcast state
+// =============================================================================
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Lap {
+    time: Option<Seconds>,
+    invalid: bool,
+}
+
+/// Session-wide state from a realtime update
+#[derive(Clone, Debug, Default)]
+struct SessionUpdate {
+    session_type: u8,
+    phase: u8,
+    session_time_ms: f32,
+    remaining_ms: f32,
+    focused_car: i32,
+    time_of_day_ms: f32,
+    ambient_temp: u8,
+    track_temp: u8,
+    rain_level: f32,
+    wetness: f32,
+}
+
+/// One car's realtime update
+#[derive(Clone, Debug, Default)]
+struct CarUpdate {
+    gear: i8,
+    location: u8,
+    kmh: u16,
+    position: u16,
+    cup_position: u16,
+    spline_position: f32,
+    laps: u16,
+    delta_ms: i32,
+    best_lap: Lap,
+    last_lap: Lap,
+    current_lap: Lap,
+}
+
+/// A car from the entry list
+#[derive(Clone, Debug, Default)]
+struct CarEntry {
+    team_name: String,
+    race_number: i32,
+    cup_category: u8,
+    current_driver: usize,
+    drivers: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct TrackInfo {
+    name: String,
+    meters: i32,
+}
+
+/// What the broadcast has said so far
+#[derive(Debug, Default)]
+struct BroadcastState {
+    connection_id: Option<i32>,
+    session: Option<SessionUpdate>,
+    track: Option<TrackInfo>,
+    entries: BTreeMap<u16, CarEntry>,
+    cars: BTreeMap<u16, CarUpdate>,
+    /// A car without an entry was seen, so the entry list is stale
+    entries_stale: bool,
+}
+
+/// What a message asks the adapter to do next
+#[derive(Debug, PartialEq)]
+enum Applied {
+    /// Registered with this connection ID
+    Registered(i32),
+    /// A realtime update: time to publish a frame
+    Session,
+    Other,
+}
+
+impl BroadcastState {
+    fn apply(&mut self, message: &[u8]) -> Result<Applied> {
+        let mut r = Reader { buf: message };
+        match r.u8()? {
+            REGISTRATION_RESULT => {
+                let connection_id = r.i32()?;
+                let success = r.u8()? != 0;
+                let _read_only = r.u8()?;
+                let error = r.string()?;
+                if !success {
+                    bail!("ACC rejected the broadcast connection: {}", error);
+                }
+                self.connection_id = Some(connection_id);
+                Ok(Applied::Registered(connection_id))
+            }
+            REALTIME_UPDATE => {
+                let _event_index = r.u16()?;
+                let _session_index = r.u16()?;
+                let session_type = r.u8()?;
+                let phase = r.u8()?;
+                let session_time_ms = r.f32()?;
+                let remaining_ms = r.f32()?;
+                let focused_car = r.i32()?;
+                let _camera_set = r.string()?;
+                let _camera = r.string()?;
+                let _hud_page = r.string()?;
+                if r.u8()? != 0 {
+                    let _replay_session_time = r.f32()?;
+                    let _replay_remaining_time = r.f32()?;
+                }
+                let time_of_day_ms = r.f32()?;
+                let ambient_temp = r.u8()?;
+                let track_temp = r.u8()?;
+                let _clouds = r.u8()?;
+                let rain_level = r.u8()? as f32 / 10.0;
+                let wetness = r.u8()? as f32 / 10.0;
+                self.session = Some(SessionUpdate {
+                    session_type,
+                    phase,
+                    session_time_ms,
+                    remaining_ms,
+                    focused_car,
+                    time_of_day_ms,
+                    ambient_temp,
+                    track_temp,
+                    rain_level,
+                    wetness,
+                });
+                Ok(Applied::Session)
+            }
+            REALTIME_CAR_UPDATE => {
+                let car_index = r.u16()?;
+                let _driver_index = r.u16()?;
+                let _driver_count = r.u8()?;
+                // 0 is reverse, 1 neutral
+                let gear = (r.u8()? as i16 - 2) as i8;
+                let _world_x = r.f32()?;
+                let _world_y = r.f32()?;
+                let _yaw = r.f32()?;
+                let location = r.u8()?;
+                let kmh = r.u16()?;
+                let position = r.u16()?;
+                let cup_position = r.u16()?;
+                let _track_position = r.u16()?;
+                let spline_position = r.f32()?;
+                let laps = r.u16()?;
+                let delta_ms = r.i32()?;
+                let update = CarUpdate {
+                    gear,
+                    location,
+                    kmh,
+                    position,
+                    cup_position,
+                    spline_position,
+                    laps,
+                    delta_ms,
+                    best_lap: r.lap()?,
+                    last_lap: r.lap()?,
+                    current_lap: r.lap()?,
+                };
+                self.entries_stale |= !self.entries.contains_key(&car_index);
+                self.cars.insert(car_index, update);
+                Ok(Applied::Other)
+            }
+            ENTRY_LIST => {
+                let _connection_id = r.i32()?;
+                let count = r.u16()?;
+                let indexes = (0..count).map(|_| r.u16()).collect::<Result<Vec<_>>>()?;
+                // Cars that left the session drop out of the list
+                self.entries.retain(|i, _| indexes.contains(i));
+                self.cars.retain(|i, _| indexes.contains(i));
+                self.entries_stale = false;
+                Ok(Applied::Other)
+            }
+            ENTRY_LIST_CAR => {
+                let car_index = r.u16()?;
+                let _car_model = r.u8()?;
+                let team_name = r.string()?;
+                let race_number = r.i32()?;
+                let cup_category = r.u8()?;
+                let current_driver = r.u8()? as usize;
+                let _nationality = r.u16()?;
+                let driver_count = r.u8()?;
+                let mut drivers = Vec::with_capacity(driver_count as usize);
+                for _ in 0..driver_count {
+                    let first = r.string()?;
+                    let last = r.string()?;
+                    let _short = r.string()?;
+                    let _category = r.u8()?;
+                    let _nationality = r.u16()?;
+                    drivers.push(format!("{} {}", first, last).trim().to_string());
+                }
+                self.entries.insert(
+                    car_index,
+                    CarEntry {
+                        team_name,
+                        race_number,
+                        cup_category,
+                        current_driver,
+                        drivers,
+                    },
+                );
+                Ok(Applied::Other)
+            }
+            TRACK_DATA => {
+                let _connection_id = r.i32()?;
+                let name = r.string()?;
+                let _track_id = r.i32()?;
+                let meters = r.i32()?;
+                self.track = Some(TrackInfo { name, meters });
+                Ok(Applied::Other)
+            }
+            // Broadcasting events (accidents, best laps) aren't used
+            _ => Ok(Applied::Other),
+        }
+    }
+
+    fn competitor(&self, car_index: u16, car: &CarUpdate) -> CompetitorData {
+        let entry = self.entries.get(&car_index);
+        CompetitorData {
+            driver_name: entry.and_then(|e| e.drivers.get(e.current_driver).cloned()),
+            car_class: entry.map(|e| cup_category_name(e.cup_category).to_string()),
+            team_name: entry.map(|e| e.team_name.clone()).filter(|t| !t.is_empty()),
+            car_number: entry.map(|e| e.race_number.to_string()),
+            lap: Some(car.laps as u32 + 1),
+            laps_completed: Some(car.laps as u32),
+            lap_distance_pct: Some(Percentage(car.spline_position)),
+            position: (car.position > 0).then_some(car.position as u32),
+            class_position: (car.cup_position > 0).then_some(car.cup_position as u32),
+            on_pit_road: Some(car.location != CAR_LOCATION_TRACK),
+            best_lap_time: car.best_lap.time,
+            last_lap_time: car.last_lap.time,
+            gear: Some(car.gear),
+            ..CompetitorData::new(car_index as u32)
+        }
+    }
+
+    /// A frame from the latest updates, following the focused car
+    fn frame(&self) -> Option<TelemetryFrame> {
+        let session = self.session.as_ref()?;
+        let focused = u16::try_from(session.focused_car).ok();
+        let car = focused.and_then(|i| self.cars.get(&i));
+        let entry = focused.and_then(|i| self.entries.get(&i));
+
+        let mut frame = TelemetryFrame::new("Assetto Corsa Competizione");
+        frame.meta.sim_time = Some(session.session_time_ms as f64 / 1000.0);
+        Some(TelemetryFrame {
+            vehicle: car.map(|car| VehicleData {
+                speed: Some(MetersPerSecond(car.kmh as f32 / 3.6)),
+                gear: Some(car.gear),
+                on_track: Some(true),
+                car_class: entry.map(|e| cup_category_name(e.cup_category).to_string()),
+                ..Default::default()
+            }),
+            timing: car.map(|car| TimingData {
+                current_lap_time: car.current_lap.time,
+                last_lap_time: car.last_lap.time,
+                best_lap_time: car.best_lap.time,
+                lap_number: Some(car.laps as u32 + 1),
+                laps_completed: Some(car.laps as u32),
+                lap_distance: self
+                    .track
+                    .as_ref()
+                    .map(|t| Meters(t.meters as f32 * car.spline_position)),
+                lap_distance_pct: Some(Percentage(car.spline_position)),
+                race_position: (car.position > 0).then_some(car.position as u32),
+                class_position: (car.cup_position > 0).then_some(car.cup_position as u32),
+                num_cars: Some(self.cars.len() as u32),
+                delta_best: Some(Seconds(car.delta_ms as f32 / 1000.0)),
+                lap_valid: Some(!car.current_lap.invalid),
+                ..Default::default()
+            }),
+            session: Some(SessionData {
+                session_type: Some(session_type(session.session_type)),
+                session_state: Some(session_state(session.phase)),
+                session_time: Some(Seconds(session.session_time_ms / 1000.0)),
+                session_time_remaining: Some(Seconds(session.remaining_ms / 1000.0)),
+                session_time_of_day: Some(Seconds(session.time_of_day_ms / 1000.0)),
+                track_name: self.track.as_ref().map(|t| t.name.clone()),
+                track_length: self.track.as_ref().map(|t| Meters(t.meters as f32)),
+                ..Default::default()
+            }),
+            weather: Some(WeatherData {
+                air_temp: Some(Celsius(session.ambient_temp as f32)),
+                track_temp: Some(Celsius(session.track_temp as f32)),
+                precipitation: Some(Percentage(session.rain_level)),
+                track_wetness: Some(track_wetness(session.wetness)),
+                ..Default::default()
+            }),
+            competitors: Some(
+                self.cars
+                    .iter()
+                    .map(|(&i, car)| self.competitor(i, car))
+                    .collect(),
+            ),
+            driver: focused.map(|i| DriverData {
+                name: entry.and_then(|e| e.drivers.get(e.current_driver).cloned()),
+                car_index: Some(i as u32),
+                car_number: entry.map(|e| e.race_number.to_string()),
+                team_name: entry.map(|e| e.team_name.clone()).filter(|t| !t.is_empty()),
+                ..Default::default()
+            }),
+            ..frame
+        })
+    }
+}
+
+/// The frames the adapter would have produced from a captured session
+pub fn replay(capture: &Capture) -> Result<Vec<TelemetryFrame>> {
+    let mut state = BroadcastState::default();
+    let mut frames = Vec::new();
+    for packet in capture.packets() {
+        if state.apply(packet)? == Applied::Session {
+            frames.extend(state.frame());
+        }
+    }
+    Ok(frames)
+}
+
+/// `CarLocationEnum::Track`; the others are pit lane, pit entry and pit exit
+const CAR_LOCATION_TRACK: u8 = 1;
+
+fn session_type(value: u8) -> SessionType {
+    match value {
+        0 => SessionType::Practice,
+        4 | 9 => SessionType::Qualifying,
+        10 => SessionType::Race,
+        11 | 13 => SessionType::Hotlap,
+        12 => SessionType::TimeTrial,
+        _ => SessionType::Other,
+    }
+}
+
+fn session_state(phase: u8) -> SessionState {
+    match phase {
+        1 | 2 | 4 => SessionState::GetInCar,
+        3 => SessionState::ParadeLaps,
+        5 => SessionState::Racing,
+        6 => SessionState::Checkered,
+        7 | 8 => SessionState::Cooldown,
+        _ => SessionState::Invalid,
+    }
+}
+
+fn track_wetness(wetness: f32) -> TrackWetness {
+    match wetness {
+        w if w <= 0.0 => TrackWetness::Dry,
+        w if w < 0.25 => TrackWetness::SlightlyWet,
+        w if w < 0.5 => TrackWetness::Wet,
+        w if w < 0.75 => TrackWetness::VeryWet,
+        _ => TrackWetness::Flooded,
+    }
+}
+
+fn cup_category_name(category: u8) -> &'static str {
+    match category {
+        0 => "Pro",
+        1 => "Pro-Am",
+        2 => "Am",
+        3 => "Silver",
+        4 => "National",
+        _ => "Unknown",
+    }
+}
+
+// =============================================================================
+// Adapter
+// =============================================================================
+
+pub struct AccAdapter {
+    addr: SocketAddr,
+    password: String,
+    socket: Option<UdpSocket>,
+    state: BroadcastState,
+    last_entry_request: Option<Instant>,
+    capture: Recording,
+    active: bool,
+}
+
+impl AccAdapter {
+    /// Connect to ACC on this machine, or `OST_ACC_BROADCAST` if set
+    pub fn new() -> Self {
+        let addr = std::env::var("OST_ACC_BROADCAST")
+            .ok()
+            .and_then(|a| a.parse().ok())
+            .unwrap_or_else(|| SocketAddr::from(([127, 0, 0, 1], 9000)));
+        let password = std::env::var("OST_ACC_PASSWORD").unwrap_or_else(|_| "asd".to_string());
+        Self::with_address(addr, password)
+    }
+
+    /// Connect to the broadcasting listener at `addr`
+    pub fn with_address(addr: SocketAddr, password: impl Into<String>) -> Self {
+        Self {
+            addr,
+            password: password.into(),
+            socket: None,
+            state: BroadcastState::default(),
+            last_entry_request: None,
+            capture: Recording::new("acc"),
+            active: false,
+        }
+    }
+
+    fn bind(&self) -> Result<UdpSocket> {
+        let local: SocketAddr = if self.addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(self.addr)?;
+        Ok(socket)
+    }
+
+    fn send(&self, message: &[u8]) -> Result<()> {
+        if let Some(socket) = &self.socket {
+            socket.send(message)?;
+        }
+        Ok(())
+    }
+
+    /// Ask for the entry list and track once registered
+    fn request_session_data(&mut self, connection_id: i32) -> Result<()> {
+        self.send(&connection_message(REQUEST_ENTRY_LIST, connection_id))?;
+        self.send(&connection_message(REQUEST_TRACK_DATA, connection_id))?;
+        self.last_entry_request = Some(Instant::now());
+        Ok(())
+    }
+}
+
+impl Default for AccAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TelemetryAdapter for AccAdapter {
+    fn key(&self) -> &str {
+        "acc"
+    }
+
+    fn name(&self) -> &str {
+        "Assetto Corsa Competizione"
+    }
+
+    /// Register briefly and see whether ACC answers
+    fn detect(&self) -> bool {
+        let probe = || -> Result<bool> {
+            let socket = self.bind()?;
+            socket.set_read_timeout(Some(DETECT_TIMEOUT))?;
+            socket.send(&register_message(&self.password))?;
+            let mut buf = [0u8; 2048];
+            let len = socket.recv(&mut buf)?;
+            let mut state = BroadcastState::default();
+            match state.apply(&buf[..len]) {
+                Ok(Applied::Registered(id)) => {
+                    socket.send(&connection_message(UNREGISTER_COMMAND_APPLICATION, id))?;
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        };
+        probe().unwrap_or(false)
+    }
+
+    fn start(&mut self) -> Result<(), AdapterError> {
+        let socket = self.bind().context("Failed to open ACC broadcast socket")?;
+        socket.set_read_timeout(Some(READ_TIMEOUT))?;
+        socket.send(&register_message(&self.password))?;
+        self.socket = Some(socket);
+        self.state = BroadcastState::default();
+        self.last_entry_request = None;
+        self.capture.start()?;
+        self.active = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), AdapterError> {
+        if let Some(id) = self.state.connection_id {
+            // Best effort: ACC drops silent connections eventually anyway
+            let _ = self.send(&connection_message(UNREGISTER_COMMAND_APPLICATION, id));
+        }
+        self.socket = None;
+        self.state = BroadcastState::default();
+        self.active = false;
+        self.capture.finish()?;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>, AdapterError> {
+        let Some(socket) = &self.socket else {
+            return Ok(None);
+        };
+        let mut buf = [0u8; 2048];
+        let len = match socket.recv(&mut buf) {
+            Ok(len) => len,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(None)
+            }
+            Err(e) => return Err(e.into()),
+        };
+        self.capture.record(RecordKind::Packet, &buf[..len])?;
+
+        match self.state.apply(&buf[..len])? {
+            Applied::Registered(id) => {
+                self.request_session_data(id)?;
+                Ok(None)
+            }
+            Applied::Session => {
+                // New cars joined since the last entry list
+                if let Some(id) = self.state.connection_id {
+                    let due = self
+                        .last_entry_request
+                        .is_none_or(|at| at.elapsed() >= ENTRY_LIST_RETRY);
+                    if self.state.entries_stale && due {
+                        self.send(&connection_message(REQUEST_ENTRY_LIST, id))?;
+                        self.last_entry_request = Some(Instant::now());
+                    }
+                }
+                Ok(self.state.frame())
+            }
+            Applied::Other => Ok(None),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn capabilities(&self) -> &'static [&'static str] {
+        // The broadcasting API has no inputs or physics, only positions and timing
+        &[
+            "vehicle.speed",
+            "vehicle.gear",
+            "vehicle.on_track",
+            "vehicle.car_class",
+            "timing",
+            "session",
+            "weather",
+            "competitors",
+            "driver",
+        ]
+    }
+
+    fn platform_requirements(&self) -> &'static [PlatformFeature] {
+        &[PlatformFeature::Udp]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::{assert_golden, golden_frames, CAPTURE_EXTENSION};
+
+    impl Writer {
+        fn u16(mut self, v: u16) -> Self {
+            self.buf.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        fn f32(mut self, v: f32) -> Self {
+            self.buf.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        fn lap(self, ms: i32, invalid: bool) -> Self {
+            self.i32(ms)
+                .u16(0)
+                .u16(0)
+                .u8(3)
+                .i32(30_000)
+                .i32(40_000)
+                .i32(30_000)
+                .u8(invalid as u8)
+                .u8(1)
+                .u8(0)
+                .u8(0)
+        }
+    }
+
+    fn car_update(car: u16, position: u16, laps: u16, location: u8) -> Vec<u8> {
+        Writer::new(REALTIME_CAR_UPDATE)
+            .u16(car)
+            .u16(0)
+            .u8(1)
+            .u8(5) // 3rd gear
+            .f32(0.0)
+            .f32(0.0)
+            .f32(0.0)
+            .u8(location)
+            .u16(180)
+            .u16(position)
+            .u16(position)
+            .u16(position)
+            .f32(0.25)
+            .u16(laps)
+            .i32(-350)
+            .lap(100_000, false)
+            .lap(101_500, false)
+            .lap(NO_LAP_TIME, true)
+            .buf
+    }
+
+    fn realtime_update(focused: i32) -> Vec<u8> {
+        Writer::new(REALTIME_UPDATE)
+            .u16(0)
+            .u16(2)
+            .u8(10)
+            .u8(5)
+            .f32(600_000.0)
+            .f32(1_200_000.0)
+            .i32(focused)
+            .string("Drivable")
+            .string("Chase")
+            .string("Basic HUD")
+            .u8(0)
+            .f32(14.0 * 3600.0 * 1000.0)
+            .u8(22)
+            .u8(31)
+            .u8(2)
+            .u8(0)
+            .u8(0)
+            .buf
+    }
+
+    fn entry(car: u16, number: i32, driver: (&str, &str)) -> Vec<u8> {
+        Writer::new(ENTRY_LIST_CAR)
+            .u16(car)
+            .u8(30)
+            .string("Team")
+            .i32(number)
+            .u8(2)
+            .u8(0)
+            .u16(0)
+            .u8(1)
+            .string(driver.0)
+            .string(driver.1)
+            .string("XXX")
+            .u8(1)
+            .u16(0)
+            .buf
+    }
+
+    #[test]
+    fn test_broadcast_to_frame() {
+        let mut state = BroadcastState::default();
+        let registered = Writer::new(REGISTRATION_RESULT)
+            .i32(7)
+            .u8(1)
+            .u8(1)
+            .string("")
+            .buf;
+        assert_eq!(state.apply(&registered).unwrap(), Applied::Registered(7));
+        let track = Writer::new(TRACK_DATA)
+            .i32(7)
+            .string("Spa")
+            .i32(18)
+            .i32(7004)
+            .u8(0)
+            .u8(0)
+            .buf;
+        state.apply(&track).unwrap();
+
+        // Two cars, one of them not in the entry list yet
+        state
+            .apply(&car_update(3, 1, 12, CAR_LOCATION_TRACK))
+            .unwrap();
+        state.apply(&car_update(8, 2, 11, 2)).unwrap();
+        state.apply(&entry(3, 91, ("Ada", "Lovelace"))).unwrap();
+        assert!(state.entries_stale);
+        assert_eq!(state.apply(&realtime_update(3)).unwrap(), Applied::Session);
+
+        let frame = state.frame().unwrap();
+        let session = frame.session.unwrap();
+        assert_eq!(session.session_type, Some(SessionType::Race));
+        assert_eq!(session.session_state, Some(SessionState::Racing));
+        assert_eq!(session.track_name.as_deref(), Some("Spa"));
+        assert_eq!(frame.weather.unwrap().air_temp.unwrap().0, 22.0);
+
+        let timing = frame.timing.unwrap();
+        assert_eq!(timing.race_position, Some(1));
+        assert_eq!(timing.lap_number, Some(13));
+        assert_eq!(timing.last_lap_time.unwrap().0, 101.5);
+        assert_eq!(timing.current_lap_time, None);
+        assert_eq!(timing.lap_valid, Some(false));
+        assert_eq!(timing.lap_distance.unwrap().0, 7004.0 * 0.25);
+        assert_eq!(frame.vehicle.unwrap().gear, Some(3));
+        assert_eq!(frame.driver.unwrap().name.as_deref(), Some("Ada Lovelace"));
+
+        let competitors = frame.competitors.unwrap();
+        assert_eq!(competitors.len(), 2);
+        assert_eq!(competitors[0].car_number.as_deref(), Some("91"));
+        assert_eq!(competitors[0].on_pit_road, Some(false));
+        assert_eq!(competitors[1].driver_name, None);
+        assert_eq!(competitors[1].on_pit_road, Some(true));
+        assert_eq!(competitors[1].best_lap_time.unwrap().0, 100.0);
+
+        // The entry list drops cars that left
+        let list = Writer::new(ENTRY_LIST).i32(7).u16(1).u16(3).buf;
+        state.apply(&list).unwrap();
+        assert!(!state.entries_stale);
+        assert_eq!(state.frame().unwrap().competitors.unwrap().len(), 1);
+    }
+
+    /// Every ACC capture in fixtures/captures replays to the frames in the
+    /// JSON file next to it
+    #[test]
+    fn test_captures_match_golden() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures/captures");
+        let mut replayed = 0;
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|e| e != CAPTURE_EXTENSION) {
+                continue;
+            }
+            let capture = Capture::open(&path).unwrap();
+            if capture.source != "acc" {
+                continue;
+            }
+            let frames = replay(&capture).unwrap();
+            assert_golden(path.with_extension("json"), &golden_frames(&frames));
+            replayed += 1;
+        }
+        assert!(replayed > 0, "no ACC captures in {}", dir.display());
+    }
+
+    #[test]
+    fn test_truncated_message_is_an_error() {
+        let mut state = BroadcastState::default();
+        let mut message = car_update(3, 1, 12, CAR_LOCATION_TRACK);
+        message.truncate(20);
+        assert!(state.apply(&message).is_err());
+        assert!(state.cars.is_empty());
+    }
+
+    #[test]
+    fn test_adapter_registers_over_udp() {
+        let acc = UdpSocket::bind("127.0.0.1:0").unwrap();
+        acc.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let mut adapter = AccAdapter::with_address(acc.local_addr().unwrap(), "asd");
+        adapter.start().unwrap();
+
+        let mut buf = [0u8; 256];
+        let (len, client) = acc.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..len], register_message("asd").as_slice());
+
+        let registered = Writer::new(REGISTRATION_RESULT)
+            .i32(4)
+            .u8(1)
+            .u8(1)
+            .string("")
+            .buf;
+        acc.send_to(&registered, client).unwrap();
+        assert!(adapter.read_frame().unwrap().is_none());
+        // Registration is followed by entry list and track requests
+        let (len, _) = acc.recv_from(&mut buf).unwrap();
+        assert_eq!(
+            &buf[..len],
+            connection_message(REQUEST_ENTRY_LIST, 4).as_slice()
+        );
+        let (len, _) = acc.recv_from(&mut buf).unwrap();
+        assert_eq!(
+            &buf[..len],
+            connection_message(REQUEST_TRACK_DATA, 4).as_slice()
+        );
+
+        acc.send_to(&realtime_update(-1), client).unwrap();
+        let frame = adapter.read_frame().unwrap().unwrap();
+        assert_eq!(frame.meta.game, "Assetto Corsa Competizione");
+        assert!(frame.timing.is_none());
+        adapter.stop().unwrap();
+    }
+}
@@ -0,0 +1,310 @@
+//! Raw adapter input captures
+//!
+//! A capture records what an adapter received from the game (UDP packets,
+//! shared memory snapshots) with the time it arrived, so the adapter's
+//! mapping can be replayed and checked without the game. Adapters record
+//! one when `OST_CAPTURE_DIR` is set; tests feed the records back in and
+//! compare the frames they produce to a golden file with [`assert_golden`].
+//!
+//! The format is little-endian: the magic `OSTCAP` and a version byte pair,
+//! the source adapter key (u16 length + UTF-8), then records of
+//! `u64 microseconds since the capture started, u8 kind, u32 length, data`.
+//!
+//! iRacing isn't captured: the sim writes its own `.ibt` recordings of the
+//! same variables as its shared memory, and those are what its tests replay.
+
+use anyhow::{bail, Context, Result};
+use ost_core::adapter::AdapterError;
+use ost_core::model::TelemetryFrame;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// File extension of captures written by [`CaptureWriter::from_env`]
+pub const CAPTURE_EXTENSION: &str = "ostcap";
+
+const MAGIC: &[u8; 8] = b"OSTCAP\x00\x01";
+
+/// What a record holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    /// One datagram as received
+    Packet,
+    /// A copy of a shared memory region
+    Snapshot,
+}
+
+impl RecordKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            RecordKind::Packet => 0,
+            RecordKind::Snapshot => 1,
+        }
+    }
+
+    fn from_byte(b: u8) -> Result<Self> {
+        match b {
+            0 => Ok(RecordKind::Packet),
+            1 => Ok(RecordKind::Snapshot),
+            _ => bail!("Unknown capture record kind {}", b),
+        }
+    }
+}
+
+/// One piece of raw input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Time since the capture started
+    pub offset: Duration,
+    pub kind: RecordKind,
+    pub data: Vec<u8>,
+}
+
+/// A capture read back into memory
+#[derive(Debug, Clone)]
+pub struct Capture {
+    /// Key of the adapter that recorded it
+    pub source: String,
+    pub records: Vec<Record>,
+}
+
+impl Capture {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open capture {}", path.display()))?;
+        Self::read(BufReader::new(file))
+    }
+
+    pub fn read(mut input: impl Read) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic).context("Not a capture file")?;
+        if &magic != MAGIC {
+            bail!("Not a capture file (or an unsupported version)");
+        }
+        let mut len = [0u8; 2];
+        input.read_exact(&mut len)?;
+        let mut source = vec![0u8; u16::from_le_bytes(len) as usize];
+        input.read_exact(&mut source)?;
+        let source = String::from_utf8(source).context("Capture source is not UTF-8")?;
+
+        let mut records = Vec::new();
+        loop {
+            // Only a clean end between records is the end of the capture
+            let mut header = [0u8; 13];
+            match input.read(&mut header[..1]) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+            input
+                .read_exact(&mut header[1..])
+                .context("Capture ends mid-record")?;
+            let offset = u64::from_le_bytes(header[0..8].try_into().unwrap());
+            let kind = RecordKind::from_byte(header[8])?;
+            let len = u32::from_le_bytes(header[9..13].try_into().unwrap());
+            let mut data = vec![0u8; len as usize];
+            input
+                .read_exact(&mut data)
+                .context("Capture ends mid-record")?;
+            records.push(Record {
+                offset: Duration::from_micros(offset),
+                kind,
+                data,
+            });
+        }
+        Ok(Self { source, records })
+    }
+
+    /// The data of the packet records, in order
+    pub fn packets(&self) -> impl Iterator<Item = &[u8]> {
+        self.records
+            .iter()
+            .filter(|r| r.kind == RecordKind::Packet)
+            .map(|r| r.data.as_slice())
+    }
+}
+
+/// Appends records to a capture as they arrive
+pub struct CaptureWriter<W: Write> {
+    out: W,
+    started: Instant,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(mut out: W, source: &str) -> Result<Self> {
+        out.write_all(MAGIC)?;
+        out.write_all(&(source.len() as u16).to_le_bytes())?;
+        out.write_all(source.as_bytes())?;
+        Ok(Self {
+            out,
+            started: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, kind: RecordKind, data: &[u8]) -> Result<()> {
+        self.record_at(self.started.elapsed(), kind, data)
+    }
+
+    /// Append a record with an explicit offset, e.g. when building captures
+    /// for tests
+    pub fn record_at(&mut self, offset: Duration, kind: RecordKind, data: &[u8]) -> Result<()> {
+        self.out
+            .write_all(&(offset.as_micros() as u64).to_le_bytes())?;
+        self.out.write_all(&[kind.to_byte()])?;
+        self.out.write_all(&(data.len() as u32).to_le_bytes())?;
+        self.out.write_all(data)?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl CaptureWriter<BufWriter<File>> {
+    /// Start a capture in `OST_CAPTURE_DIR`, if set, named after the source
+    /// and the current time
+    pub fn from_env(source: &str) -> Result<Option<Self>> {
+        let Some(dir) = std::env::var_os("OST_CAPTURE_DIR").map(PathBuf::from) else {
+            return Ok(None);
+        };
+        std::fs::create_dir_all(&dir)?;
+        let name = format!(
+            "{}-{}.{}",
+            source,
+            chrono::Local::now().format("%Y-%m-%d %H-%M-%S"),
+            CAPTURE_EXTENSION
+        );
+        let path = dir.join(name);
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create capture {}", path.display()))?;
+        Self::new(BufWriter::new(file), source).map(Some)
+    }
+}
+
+/// The capture a running adapter is recording, if `OST_CAPTURE_DIR` is set
+pub struct Recording {
+    source: &'static str,
+    writer: Option<CaptureWriter<BufWriter<File>>>,
+}
+
+impl Recording {
+    /// Recording for the adapter with key `source`, not yet started
+    pub fn new(source: &'static str) -> Self {
+        Self {
+            source,
+            writer: None,
+        }
+    }
+
+    /// Start a capture, if enabled
+    pub fn start(&mut self) -> Result<()> {
+        self.writer = CaptureWriter::from_env(self.source)?;
+        Ok(())
+    }
+
+    /// Record raw input. After a failed write the recording stops, rather
+    /// than failing every read.
+    pub fn record(&mut self, kind: RecordKind, data: &[u8]) -> Result<(), AdapterError> {
+        let Some(writer) = &mut self.writer else {
+            return Ok(());
+        };
+        if let Err(e) = writer.record(kind, data) {
+            self.writer = None;
+            return Err(AdapterError::Other(
+                e.context(format!("Failed to write {} capture", self.source)),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Flush and close the capture, if recording
+    pub fn finish(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.take() {
+            writer.finish()?;
+        }
+        Ok(())
+    }
+}
+
+/// Frames as JSON for golden files, without the wall-clock timestamps that
+/// differ between runs
+pub fn golden_frames(frames: &[TelemetryFrame]) -> serde_json::Value {
+    frames
+        .iter()
+        .map(|frame| {
+            let mut value = serde_json::to_value(frame).unwrap_or_default();
+            if let Some(meta) = value.get_mut("meta").and_then(|m| m.as_object_mut()) {
+                meta.remove("timestamp");
+                meta.remove("acquired_at");
+            }
+            value
+        })
+        .collect()
+}
+
+/// Compare `actual` to the JSON in the golden file at `path`, for tests.
+/// With `OST_BLESS=1` the file is (re)written instead.
+pub fn assert_golden(path: impl AsRef<Path>, actual: &serde_json::Value) {
+    let path = path.as_ref();
+    if std::env::var_os("OST_BLESS").is_some_and(|v| v == "1") {
+        let json = serde_json::to_string_pretty(actual).unwrap() + "\n";
+        std::fs::write(path, json)
+            .unwrap_or_else(|e| panic!("Failed to write {}: {}", path.display(), e));
+        return;
+    }
+    let expected = std::fs::read_to_string(path).unwrap_or_else(|e| {
+        panic!(
+            "Failed to read golden file {} ({}); run with OST_BLESS=1 to create it",
+            path.display(),
+            e
+        )
+    });
+    let expected: serde_json::Value = serde_json::from_str(&expected).unwrap();
+    assert!(
+        &expected == actual,
+        "{} differs from the output; run with OST_BLESS=1 to update it\nexpected: {}\nactual: {}",
+        path.display(),
+        expected,
+        actual
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut writer = CaptureWriter::new(Vec::new(), "acc").unwrap();
+        writer
+            .record_at(Duration::from_millis(5), RecordKind::Packet, b"one")
+            .unwrap();
+        writer
+            .record_at(Duration::from_millis(9), RecordKind::Snapshot, &[0; 300])
+            .unwrap();
+        let bytes = writer.finish().unwrap();
+
+        let capture = Capture::read(bytes.as_slice()).unwrap();
+        assert_eq!(capture.source, "acc");
+        assert_eq!(capture.records.len(), 2);
+        assert_eq!(capture.records[0].offset, Duration::from_millis(5));
+        assert_eq!(capture.records[1].kind, RecordKind::Snapshot);
+        assert_eq!(capture.packets().collect::<Vec<_>>(), [b"one".as_slice()]);
+
+        // A torn final record is reported rather than silently dropped,
+        // whether it ends in the data or the header
+        assert!(Capture::read(&bytes[..bytes.len() - 1]).is_err());
+        let header_end = MAGIC.len() + 2 + "acc".len();
+        assert!(Capture::read(&bytes[..header_end])
+            .unwrap()
+            .records
+            .is_empty());
+        assert!(Capture::read(&bytes[..header_end + 5]).is_err());
+        assert!(Capture::read(&b"OSTCAP\x00\x02"[..]).is_err());
+    }
+}
@@ -2,13 +2,29 @@
 //!
 //! Simulates laps around a circuit with straights, braking zones, corners,
 //! and acceleration phases. Produces realistic-looking telemetry at 60Hz
-//! without requiring an actual game.
+//! without requiring an actual game. A [`DemoScenario`] (constructor,
+//! `OST_DEMO_SCENARIO` or [`TelemetryAdapter::set_scenario`]) layers flags,
+//! weather, pit stops, damage or a fuel run-out over the lap.
+//!
+//! Noise comes from a seeded hash of the frame number, and with
+//! [`DemoAdapter::with_fixed_clock`] time advances by exactly one 60Hz step
+//! per frame from a fixed start, so a seeded demo reproduces the same
+//! frames, timestamps included, on every run.
+//!
+//! The player races a field of simulated opponents (19 by default, see
+//! [`DemoAdapter::with_field_size`]) with their own pace and pit stops, so
+//! positions change over a session.
 
 use anyhow::Result;
-use chrono::Utc;
-use ost_core::{adapter::TelemetryAdapter, model::*, units::*};
+use chrono::{DateTime, Utc};
+use ost_core::{
+    adapter::{AdapterError, PollHint, TelemetryAdapter},
+    model::*,
+    units::*,
+};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 // =============================================================================
 // Track definition — a sequence of segments that form a lap
@@ -330,15 +346,505 @@ fn speed_to_rpm(speed_ms: f32, gear: i8) -> f32 {
     rpm.clamp(1200.0, 8000.0)
 }
 
-/// Simple deterministic noise from a seed
-fn noise(seed: f32) -> f32 {
-    let x = (seed * 12.9898 + 78.233).sin() * 43_758.547;
-    x - x.floor()
+/// Deterministic noise in 0..1 for a channel/frame key, varied by the RNG seed
+fn noise(rng_seed: u64, key: f32) -> f32 {
+    // splitmix64 finalizer
+    let mut z = rng_seed ^ (key.to_bits() as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 24) as f32
 }
 
 /// Small jitter centered around 0
-fn jitter(seed: f32, amplitude: f32) -> f32 {
-    (noise(seed) - 0.5) * 2.0 * amplitude
+fn jitter(rng_seed: u64, key: f32, amplitude: f32) -> f32 {
+    (noise(rng_seed, key) - 0.5) * 2.0 * amplitude
+}
+
+/// Player lap times reported before the first lap is completed
+const INITIAL_BEST_LAP: f32 = 85.1;
+const INITIAL_LAST_LAP: f32 = 87.3;
+
+/// Time step per frame with a fixed clock (60Hz)
+const FIXED_STEP: f64 = 1.0 / 60.0;
+
+// =============================================================================
+// Scenarios — state transitions layered over the base lap
+// =============================================================================
+
+/// A situation the demo simulates on top of its lap, for testing how
+/// consumers handle state changes. Each one is a function of session time,
+/// so it replays the same way every run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DemoScenario {
+    /// Green-flag race laps
+    #[default]
+    Race,
+    /// A 15 minute qualifying session ending in the checkered flag
+    Qualifying,
+    /// A full-course yellow every 150s: speed capped, one lap to green,
+    /// then racing again
+    FullCourseYellow,
+    /// Rain starting after 30s and building over three minutes
+    RainOnset,
+    /// A pit stop every other lap: limiter in, service, limiter out
+    PitStop,
+    /// An incident every 45s adding body, engine and gearbox damage
+    Damage,
+    /// Starting on 8 liters and stalling when the tank runs dry
+    FuelRunOut,
+}
+
+impl DemoScenario {
+    pub const ALL: [DemoScenario; 7] = [
+        DemoScenario::Race,
+        DemoScenario::Qualifying,
+        DemoScenario::FullCourseYellow,
+        DemoScenario::RainOnset,
+        DemoScenario::PitStop,
+        DemoScenario::Damage,
+        DemoScenario::FuelRunOut,
+    ];
+
+    /// Names accepted by [`TelemetryAdapter::set_scenario`]
+    pub const NAMES: [&'static str; 7] = [
+        "race",
+        "qualifying",
+        "full_course_yellow",
+        "rain_onset",
+        "pit_stop",
+        "damage",
+        "fuel_run_out",
+    ];
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+
+    /// Layer the scenario onto a frame generated `t` seconds into the session
+    fn apply(self, frame: &mut TelemetryFrame, t: f32, lap_duration: f32) {
+        match self {
+            DemoScenario::Race => {}
+            DemoScenario::Qualifying => qualifying(frame, t),
+            DemoScenario::FullCourseYellow => full_course_yellow(frame, t),
+            DemoScenario::RainOnset => rain_onset(frame, t),
+            DemoScenario::PitStop => pit_stop(frame, t, lap_duration),
+            DemoScenario::Damage => damage(frame, t),
+            DemoScenario::FuelRunOut => fuel_run_out(frame, t),
+        }
+    }
+}
+
+impl std::str::FromStr for DemoScenario {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::NAMES
+            .iter()
+            .position(|name| *name == s)
+            .map(|i| Self::ALL[i])
+            .ok_or_else(|| anyhow::anyhow!("Unknown demo scenario '{}'", s))
+    }
+}
+
+const QUALIFYING_LENGTH: f32 = 900.0;
+const FCY_PERIOD: f32 = 150.0;
+const FCY_SPEED: f32 = 35.0;
+const RAIN_START: f32 = 30.0;
+const RAIN_BUILD: f32 = 180.0;
+/// Pit lane in, stationary service and pit lane out, in seconds
+const PIT_PHASES: (f32, f32, f32) = (8.0, 14.0, 8.0);
+const PIT_SPEED: f32 = 80.0 / 3.6;
+const INCIDENT_INTERVAL: f32 = 45.0;
+const FUEL_START: f32 = 8.0;
+const FUEL_BURN: f32 = 0.1;
+
+/// Scale the car's speed, keeping the motion and wheel speeds consistent
+fn scale_speed(frame: &mut TelemetryFrame, factor: f32) {
+    if let Some(vehicle) = frame.vehicle.as_mut() {
+        if let Some(speed) = vehicle.speed.as_mut() {
+            speed.0 *= factor;
+            let gear = speed_to_gear(speed.0);
+            vehicle.gear = Some(gear);
+            vehicle.rpm = Some(Rpm(speed_to_rpm(speed.0, gear)));
+        }
+    }
+    if let Some(velocity) = frame.motion.as_mut().and_then(|m| m.velocity.as_mut()) {
+        velocity.z.0 *= factor;
+    }
+    if let Some(wheels) = frame.wheels.as_mut() {
+        for wheel in [
+            &mut wheels.front_left,
+            &mut wheels.front_right,
+            &mut wheels.rear_left,
+            &mut wheels.rear_right,
+        ] {
+            if let Some(wheel_speed) = wheel.wheel_speed.as_mut() {
+                wheel_speed.0 *= factor;
+            }
+        }
+    }
+}
+
+fn set_flags(frame: &mut TelemetryFrame, flags: FlagState) {
+    if let Some(session) = frame.session.as_mut() {
+        session.flags = Some(flags);
+    }
+}
+
+fn qualifying(frame: &mut TelemetryFrame, t: f32) {
+    let over = t >= QUALIFYING_LENGTH;
+    if let Some(session) = frame.session.as_mut() {
+        session.session_type = Some(SessionType::Qualifying);
+        session.session_state = Some(if over {
+            SessionState::Checkered
+        } else {
+            SessionState::Racing
+        });
+        session.session_time_remaining = Some(Seconds((QUALIFYING_LENGTH - t).max(0.0)));
+        session.session_laps = None;
+        session.session_laps_remaining = None;
+    }
+    if let Some(timing) = frame.timing.as_mut() {
+        timing.race_laps = None;
+    }
+    set_flags(
+        frame,
+        FlagState {
+            green: !over,
+            checkered: over,
+            ..Default::default()
+        },
+    );
+}
+
+fn full_course_yellow(frame: &mut TelemetryFrame, t: f32) {
+    let phase = t % FCY_PERIOD;
+    let flags = match phase {
+        p if p < 40.0 => FlagState {
+            green: true,
+            ..Default::default()
+        },
+        p if p < 100.0 => FlagState {
+            yellow: true,
+            caution: true,
+            caution_waving: p < 50.0,
+            ..Default::default()
+        },
+        p if p < 110.0 => FlagState {
+            caution: true,
+            one_lap_to_green: true,
+            ..Default::default()
+        },
+        _ => FlagState {
+            green: true,
+            ..Default::default()
+        },
+    };
+    if flags.caution {
+        let speed = frame
+            .vehicle
+            .as_ref()
+            .and_then(|v| v.speed)
+            .map_or(0.0, |s| s.0);
+        if speed > FCY_SPEED {
+            scale_speed(frame, FCY_SPEED / speed);
+        }
+    }
+    set_flags(frame, flags);
+}
+
+fn rain_onset(frame: &mut TelemetryFrame, t: f32) {
+    let rain = ((t - RAIN_START) / RAIN_BUILD).clamp(0.0, 1.0);
+    if let Some(weather) = frame.weather.as_mut() {
+        weather.precipitation = Some(Percentage::new(rain * 0.8));
+        weather.humidity = Some(Percentage::new(0.55 + rain * 0.4));
+        weather.track_wetness = Some(match rain {
+            r if r <= 0.0 => TrackWetness::Dry,
+            r if r < 0.3 => TrackWetness::SlightlyWet,
+            r if r < 0.7 => TrackWetness::Wet,
+            _ => TrackWetness::VeryWet,
+        });
+        weather.skies = Some(
+            match rain {
+                r if r <= 0.0 => "Clear",
+                r if r < 0.3 => "Mostly Cloudy",
+                _ => "Overcast",
+            }
+            .to_string(),
+        );
+        weather.declared_wet = Some(rain >= 0.3);
+        for temp in [
+            weather.air_temp.as_mut(),
+            weather.track_temp.as_mut(),
+            weather.track_surface_temp.as_mut(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            temp.0 -= rain * 6.0;
+        }
+    }
+    if rain > 0.0 {
+        scale_speed(frame, 1.0 - rain * 0.15);
+    }
+}
+
+fn pit_stop(frame: &mut TelemetryFrame, t: f32, lap_duration: f32) {
+    let (lane_in, service, lane_out) = PIT_PHASES;
+    let cycle = lap_duration * 2.0;
+    // Stop `n` is centred on the line at the end of lap 2n
+    let shifted = t + lane_in + service / 2.0;
+    let stop = (shifted / cycle) as u32;
+    let phase = shifted % cycle;
+    let in_pit = stop > 0 && phase < lane_in + service + lane_out;
+    let stationary = in_pit && (lane_in..lane_in + service).contains(&phase);
+    // Time since the car last left its box, for fuel and tyre wear
+    let last_stop = if phase >= lane_in + service {
+        stop
+    } else {
+        stop.saturating_sub(1)
+    };
+    let since_service = if last_stop == 0 {
+        t
+    } else {
+        t - (last_stop as f32 * cycle + service / 2.0)
+    };
+
+    if in_pit {
+        let speed = frame
+            .vehicle
+            .as_ref()
+            .and_then(|v| v.speed)
+            .map_or(0.0, |s| s.0)
+            .max(1.0);
+        let target = if stationary { 0.0 } else { PIT_SPEED };
+        scale_speed(frame, target / speed);
+        if let Some(vehicle) = frame.vehicle.as_mut() {
+            vehicle.throttle = Some(Percentage::new(if stationary { 0.0 } else { 0.3 }));
+            vehicle.brake = Some(Percentage::new(0.0));
+            if stationary {
+                vehicle.gear = Some(0);
+                vehicle.rpm = Some(Rpm(1200.0));
+            }
+        }
+    }
+    if let Some(pit) = frame.pit.as_mut() {
+        pit.on_pit_road = Some(in_pit);
+        pit.pit_active = Some(stationary);
+        pit.pit_service_status = Some(stationary as u32);
+    }
+    if let Some(warnings) = frame.engine.as_mut().and_then(|e| e.warnings.as_mut()) {
+        warnings.pit_speed_limiter = in_pit && !stationary;
+    }
+
+    // Refuelled and fresh tyres at every stop
+    let fuel = 60.0 * (1.0 - since_service * 0.00015);
+    let fuel = if stationary {
+        fuel + (60.0 - fuel) * (phase - lane_in) / service
+    } else {
+        fuel
+    };
+    if let Some(engine) = frame.engine.as_mut() {
+        engine.fuel_level = Some(Liters(fuel));
+        engine.fuel_level_pct = Some(Percentage::new(fuel / 60.0));
+    }
+    if let Some(wheels) = frame.wheels.as_mut() {
+        for wheel in [
+            &mut wheels.front_left,
+            &mut wheels.front_right,
+            &mut wheels.rear_left,
+            &mut wheels.rear_right,
+        ] {
+            let wear = (0.02 + since_service * 0.0001).min(0.3);
+            wheel.tyre_wear = Some(Percentage::new(wear));
+            wheel.tyre_wear_middle = Some(Percentage::new(wear));
+            wheel.tyre_wear_inner = Some(Percentage::new(wear * 1.2));
+            wheel.tyre_wear_outer = Some(Percentage::new(wear * 0.8));
+        }
+    }
+}
+
+fn damage(frame: &mut TelemetryFrame, t: f32) {
+    // Body panel, amount; the engine and gearbox take a share of every hit
+    const HITS: [(usize, f32); 4] = [(0, 0.15), (2, 0.1), (1, 0.1), (3, 0.12)];
+    let incidents = (t / INCIDENT_INTERVAL) as usize;
+    let mut body = [0.0_f32; 4];
+    for i in 0..incidents {
+        let (panel, amount) = HITS[i % HITS.len()];
+        body[panel] += amount;
+    }
+    let engine_damage = (incidents as f32 * 0.04).min(1.0);
+    let gearbox_damage = (incidents as f32 * 0.03).min(1.0);
+    let total: f32 = body.iter().sum::<f32>() + engine_damage + gearbox_damage;
+
+    if let Some(damage) = frame.damage.as_mut() {
+        damage.front = Some(Percentage::new(body[0].min(1.0)));
+        damage.rear = Some(Percentage::new(body[1].min(1.0)));
+        damage.left = Some(Percentage::new(body[2].min(1.0)));
+        damage.right = Some(Percentage::new(body[3].min(1.0)));
+        damage.engine = Some(Percentage::new(engine_damage));
+        damage.transmission = Some(Percentage::new(gearbox_damage));
+    }
+    if let Some(pit) = frame.pit.as_mut() {
+        pit.repair_time_left = Some(Seconds(total * 60.0));
+        pit.optional_repair_time_left = Some(Seconds(total * 30.0));
+    }
+    if let Some(engine) = frame.engine.as_mut() {
+        if let Some(oil) = engine.oil_temp.as_mut() {
+            oil.0 += engine_damage * 25.0;
+        }
+        if let Some(water) = engine.water_temp.as_mut() {
+            water.0 += engine_damage * 20.0;
+        }
+        if let Some(warnings) = engine.warnings.as_mut() {
+            warnings.water_temp_high = engine_damage >= 0.4;
+        }
+    }
+    if incidents > 0 {
+        scale_speed(frame, 1.0 - engine_damage * 0.3);
+    }
+    if total >= 0.5 {
+        if let Some(flags) = frame.session.as_mut().and_then(|s| s.flags.as_mut()) {
+            flags.repair = true;
+        }
+    }
+}
+
+fn fuel_run_out(frame: &mut TelemetryFrame, t: f32) {
+    let fuel = (FUEL_START - t * FUEL_BURN).max(0.0);
+    let dry_for = t - FUEL_START / FUEL_BURN;
+    if let Some(engine) = frame.engine.as_mut() {
+        engine.fuel_level = Some(Liters(fuel));
+        engine.fuel_level_pct = Some(Percentage::new(fuel / 60.0));
+        if dry_for >= 0.0 {
+            engine.fuel_pressure = Some(Kilopascals(0.0));
+            engine.fuel_use_per_hour = Some(LitersPerHour(0.0));
+            if let Some(warnings) = engine.warnings.as_mut() {
+                warnings.fuel_pressure_low = true;
+                warnings.engine_stalled = true;
+                warnings.rev_limiter = false;
+            }
+        }
+    }
+    if dry_for >= 0.0 {
+        // Coasting to a stop
+        scale_speed(frame, (-dry_for / 8.0).exp());
+        if let Some(vehicle) = frame.vehicle.as_mut() {
+            vehicle.rpm = Some(Rpm(0.0));
+            vehicle.throttle = Some(Percentage::new(0.0));
+        }
+    }
+}
+
+// =============================================================================
+// Field — simulated opponents
+// =============================================================================
+
+/// Opponents in the default field, making 20 cars with the player
+const DEFAULT_FIELD_SIZE: usize = 19;
+/// Grid slot of the player (0 = pole)
+const PLAYER_GRID_SLOT: usize = 2;
+/// Gap between consecutive grid slots at the start, in seconds
+const GRID_GAP: f32 = 0.6;
+/// Share of a lap spent on pit road per stop
+const PIT_LANE_LAPS: f32 = 0.04;
+/// Time a pit stop costs over driving past, in seconds
+const PIT_LOSS: f32 = 25.0;
+
+const FIRST_NAMES: [&str; 12] = [
+    "Alex", "Sam", "Jordan", "Robin", "Kai", "Morgan", "Noa", "Casey", "Riley", "Jamie", "Avery",
+    "Drew",
+];
+const LAST_NAMES: [&str; 12] = [
+    "Rivera",
+    "Chen",
+    "Okafor",
+    "Lindqvist",
+    "Moreau",
+    "Tanaka",
+    "Novak",
+    "Silva",
+    "Brennan",
+    "Haddad",
+    "Kowalski",
+    "Ferreira",
+];
+const TEAMS: [&str; 6] = [
+    "Apex Racing",
+    "Velocity Motorsport",
+    "Redline GP",
+    "Northern Lights Racing",
+    "Torque Works",
+    "Slipstream Engineering",
+];
+
+/// A simulated opponent
+struct FieldCar {
+    car_index: u32,
+    driver_name: String,
+    team_name: &'static str,
+    car_number: String,
+    /// Seconds per lap at this car's pace
+    lap_time: f32,
+    /// Starting position, 0 = pole
+    grid_slot: usize,
+    /// Distance ahead of the player at the green flag, in seconds
+    head_start: f32,
+    /// Laps between pit stops
+    stint: u32,
+}
+
+impl FieldCar {
+    /// Laps covered `t` seconds into the session, and whether the car is on
+    /// pit road
+    fn progress(&self, t: f32) -> (f32, bool) {
+        let pit_lane_time = PIT_LANE_LAPS * self.lap_time + PIT_LOSS;
+        let mut lost = 0.0;
+        for stop in 1.. {
+            let entry = (stop * self.stint) as f32 - PIT_LANE_LAPS / 2.0;
+            let entry_time = entry * self.lap_time - self.head_start + lost;
+            if t < entry_time {
+                break;
+            }
+            if t < entry_time + pit_lane_time {
+                let lane = PIT_LANE_LAPS * (t - entry_time) / pit_lane_time;
+                return (entry + lane, true);
+            }
+            lost += PIT_LOSS;
+        }
+        (
+            ((t + self.head_start - lost) / self.lap_time).max(0.0),
+            false,
+        )
+    }
+}
+
+/// `size` opponents with paces and pit windows drawn from the seed
+fn demo_field(size: usize, lap_duration: f32, rng_seed: u64) -> Vec<FieldCar> {
+    let player_slot = PLAYER_GRID_SLOT.min(size);
+    (0..size)
+        .map(|i| {
+            let key = i as f32 * 17.0;
+            let slot = if i < player_slot { i } else { i + 1 };
+            FieldCar {
+                car_index: i as u32 + 1,
+                driver_name: format!(
+                    "{} {}",
+                    FIRST_NAMES[i % FIRST_NAMES.len()],
+                    LAST_NAMES[(i + i / LAST_NAMES.len()) % LAST_NAMES.len()]
+                ),
+                team_name: TEAMS[i % TEAMS.len()],
+                car_number: ((i * 7 + 3) % 99 + 1).to_string(),
+                // Between 1.2% faster and 1.8% slower than the player
+                lap_time: lap_duration * (1.0 + (noise(rng_seed, key) - 0.4) * 0.03),
+                grid_slot: slot,
+                head_start: (player_slot as f32 - slot as f32) * GRID_GAP,
+                stint: 6 + (noise(rng_seed, key + 1.0) * 6.0) as u32,
+            }
+        })
+        .collect()
 }
 
 // =============================================================================
@@ -354,12 +860,64 @@ pub struct DemoAdapter {
     laps_completed: u32,
     best_lap: f32,
     last_lap: f32,
-    /// Last frame time for pacing (DemoAdapter runs at ~60Hz)
-    last_frame_time: Option<Instant>,
+    scenario: DemoScenario,
+    rng_seed: u64,
+    field: Vec<FieldCar>,
+    /// Session start for fixed-clock mode, where frames are [`FIXED_STEP`]
+    /// apart instead of following the wall clock
+    fixed_start: Option<DateTime<Utc>>,
 }
 
 impl DemoAdapter {
+    /// Demo running `OST_DEMO_SCENARIO` if set, otherwise a plain race.
+    /// `OST_DEMO_SEED` seeds the noise and `OST_DEMO_CARS` sets the number
+    /// of opponents.
     pub fn new() -> Self {
+        let scenario = std::env::var("OST_DEMO_SCENARIO")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default();
+        let seed = std::env::var("OST_DEMO_SEED")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default();
+        let field_size = std::env::var("OST_DEMO_CARS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_FIELD_SIZE);
+        Self::with_scenario(scenario)
+            .with_seed(seed)
+            .with_field_size(field_size)
+    }
+
+    /// Seed the noise added to every channel and the field's pace
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = seed;
+        self.field = demo_field(self.field.len(), self.lap_duration, seed);
+        self
+    }
+
+    /// Race against `size` simulated opponents
+    pub fn with_field_size(mut self, size: usize) -> Self {
+        self.field = demo_field(size, self.lap_duration, self.rng_seed);
+        self
+    }
+
+    /// Generate frames 1/60s apart starting at `start`, however fast they
+    /// are read, instead of following the wall clock
+    pub fn with_fixed_clock(mut self, start: DateTime<Utc>) -> Self {
+        self.fixed_start = Some(start);
+        self
+    }
+
+    /// Seeded noise and a fixed clock: the same frames on every run
+    pub fn deterministic(seed: u64, start: DateTime<Utc>) -> Self {
+        Self::with_scenario(DemoScenario::default())
+            .with_seed(seed)
+            .with_fixed_clock(start)
+    }
+
+    pub fn with_scenario(scenario: DemoScenario) -> Self {
         let track = demo_track();
         let lap_duration: f32 = track.iter().map(|s| s.duration).sum();
         Self {
@@ -368,22 +926,38 @@ impl DemoAdapter {
             frame_count: 0,
             track,
             lap_duration,
-            last_frame_time: None,
             laps_completed: 0,
-            best_lap: 85.1,
-            last_lap: 87.3,
+            best_lap: INITIAL_BEST_LAP,
+            last_lap: INITIAL_LAST_LAP,
+            scenario,
+            rng_seed: 0,
+            field: demo_field(DEFAULT_FIELD_SIZE, lap_duration, 0),
+            fixed_start: None,
         }
     }
 
+    pub fn scenario(&self) -> DemoScenario {
+        self.scenario
+    }
+
     fn generate_frame(&mut self) -> TelemetryFrame {
-        let elapsed = self
-            .start_time
-            .map(|t| t.elapsed().as_secs_f32())
-            .unwrap_or(0.0);
+        let elapsed = if self.fixed_start.is_some() {
+            (self.frame_count as f64 * FIXED_STEP) as f32
+        } else {
+            self.start_time
+                .map(|t| t.elapsed().as_secs_f32())
+                .unwrap_or(0.0)
+        };
+        self.frame_at(elapsed)
+    }
 
+    /// Generate the frame for `elapsed` seconds into the session
+    fn frame_at(&mut self, elapsed: f32) -> TelemetryFrame {
         self.frame_count += 1;
         let t = elapsed; // shorthand
-        let n = self.frame_count as f32; // noise seed
+        let n = self.frame_count as f32; // noise key
+        let rng_seed = self.rng_seed;
+        let jitter = move |key: f32, amplitude: f32| jitter(rng_seed, key, amplitude);
 
         // Track position
         let lap_time = t % self.lap_duration;
@@ -463,6 +1037,8 @@ impl DemoAdapter {
                     200.0 + brake * 300.0 + speed * 1.5 + jitter(n * 3.4, 5.0),
                 )),
                 tyre_compound: Some("Soft".to_string()),
+                brake_pad_wear: Some(Percentage::new((0.01 + elapsed * 0.00005).min(0.2))),
+                brake_temp_estimated: None,
             }
         };
 
@@ -480,12 +1056,12 @@ impl DemoAdapter {
             )),
             acceleration: Some(Vector3::new(
                 MetersPerSecondSquared(lat_g * 9.81),
-                MetersPerSecondSquared(-9.81),
+                MetersPerSecondSquared(9.81),
                 MetersPerSecondSquared(long_g * 9.81),
             )),
             g_force: Some(Vector3::new(
                 GForce(lat_g),
-                GForce(-1.0 + jitter(n * 4.0, 0.02)),
+                GForce(1.0 + jitter(n * 4.0, 0.02)),
                 GForce(long_g),
             )),
             rotation: Some(Vector3::new(
@@ -571,6 +1147,26 @@ impl DemoAdapter {
             rear_right: make_wheel(false, false),
         });
 
+        // --- Field ---
+        // Positions by distance covered, the player included. Cars that
+        // haven't reached the line yet are all at zero, so ties go by grid slot.
+        let player_progress = t / self.lap_duration;
+        let player_slot = PLAYER_GRID_SLOT.min(self.field.len());
+        let progress: Vec<(f32, bool)> = self.field.iter().map(|car| car.progress(t)).collect();
+        let standings: Vec<(f32, usize)> = progress
+            .iter()
+            .zip(&self.field)
+            .map(|(&(laps, _), car)| (laps, car.grid_slot))
+            .chain([(player_progress, player_slot)])
+            .collect();
+        let position_of = |laps: f32, slot: usize| {
+            1 + standings
+                .iter()
+                .filter(|&&(l, s)| l > laps || (l == laps && s < slot))
+                .count() as u32
+        };
+        let player_position = position_of(player_progress, player_slot);
+
         // --- Timing ---
         let lap_dist_pct = lap_time / self.lap_duration;
         let timing = Some(TimingData {
@@ -584,9 +1180,9 @@ impl DemoAdapter {
             laps_completed: Some(self.laps_completed),
             lap_distance: Some(Meters(lap_dist_pct * 4500.0)),
             lap_distance_pct: Some(Percentage::new(lap_dist_pct)),
-            race_position: Some(3),
-            class_position: Some(2),
-            num_cars: Some(20),
+            race_position: Some(player_position),
+            class_position: Some(player_position),
+            num_cars: Some(self.field.len() as u32 + 1),
             delta_best: Some(Seconds(jitter(n * 6.0, 0.8))),
             delta_best_ok: Some(true),
             delta_session_best: Some(Seconds(0.5 + jitter(n * 6.1, 0.6))),
@@ -595,6 +1191,8 @@ impl DemoAdapter {
             delta_optimal_ok: Some(true),
             estimated_lap_time: Some(Seconds(self.lap_duration)),
             race_laps: Some(current_lap_num),
+            lap_valid: None,
+            corner_name: None,
         });
 
         // --- Session ---
@@ -614,6 +1212,10 @@ impl DemoAdapter {
             track_config: Some("Grand Prix".to_string()),
             track_length: Some(Meters(4500.0)),
             track_type: Some("Road".to_string()),
+            track_id: Some("demo:demo_circuit".to_string()),
+            sector_starts: None,
+            pit_entry_pct: None,
+            pit_exit_pct: None,
         });
 
         // --- Weather ---
@@ -688,53 +1290,40 @@ impl DemoAdapter {
             transmission: Some(Percentage::new(0.0)),
         });
 
-        // --- Competitors (simulate two other cars on track) ---
-        let comp_a_pct = ((elapsed + 10.0) % (self.lap_duration - 1.5)) / (self.lap_duration - 1.5);
-        let comp_b_pct = ((elapsed + 25.0) % (self.lap_duration + 1.0)) / (self.lap_duration + 1.0);
-        let competitors = Some(vec![
-            CompetitorData {
-                car_index: 1,
-                driver_name: Some("Alex Rivera".to_string()),
-                car_name: Some("Formula Demo".to_string()),
-                car_class: Some("Open Wheel".to_string()),
-                team_name: Some("Apex Racing".to_string()),
-                car_number: Some("7".to_string()),
-                lap: Some((elapsed / (self.lap_duration - 1.5)) as u32 + 1),
-                laps_completed: Some((elapsed / (self.lap_duration - 1.5)) as u32),
-                lap_distance_pct: Some(Percentage::new(comp_a_pct)),
-                position: Some(1),
-                class_position: Some(1),
-                on_pit_road: Some(false),
-                track_surface: Some(TrackSurface::Asphalt),
-                best_lap_time: Some(Seconds(self.best_lap - 0.8)),
-                last_lap_time: Some(Seconds(self.lap_duration - 1.2)),
-                estimated_time: Some(Seconds(self.lap_duration - 1.0)),
-                gear: Some(4),
-                rpm: Some(Rpm(6200.0)),
-                steering: Some(Degrees::from_radians(0.05)),
-            },
-            CompetitorData {
-                car_index: 2,
-                driver_name: Some("Sam Chen".to_string()),
-                car_name: Some("Formula Demo".to_string()),
-                car_class: Some("Open Wheel".to_string()),
-                team_name: Some("Velocity Motorsport".to_string()),
-                car_number: Some("22".to_string()),
-                lap: Some((elapsed / (self.lap_duration + 1.0)) as u32 + 1),
-                laps_completed: Some((elapsed / (self.lap_duration + 1.0)) as u32),
-                lap_distance_pct: Some(Percentage::new(comp_b_pct)),
-                position: Some(2),
-                class_position: Some(2),
-                on_pit_road: Some(false),
-                track_surface: Some(TrackSurface::Asphalt),
-                best_lap_time: Some(Seconds(self.best_lap + 0.3)),
-                last_lap_time: Some(Seconds(self.lap_duration + 0.8)),
-                estimated_time: Some(Seconds(self.lap_duration + 0.5)),
-                gear: Some(5),
-                rpm: Some(Rpm(5800.0)),
-                steering: Some(Degrees::from_radians(-0.03)),
-            },
-        ]);
+        // --- Competitors ---
+        let competitors = Some(
+            self.field
+                .iter()
+                .zip(&progress)
+                .map(|(car, &(laps, on_pit_road))| {
+                    let laps_completed = laps as u32;
+                    let pct = laps.fract();
+                    let state = compute_lap_state(&self.track, pct * self.lap_duration);
+                    let lap_key = car.car_index as f32 * 31.0 + laps_completed as f32;
+                    CompetitorData {
+                        car_index: car.car_index,
+                        driver_name: Some(car.driver_name.clone()),
+                        car_name: Some("Formula Demo".to_string()),
+                        car_class: Some("Open Wheel".to_string()),
+                        team_name: Some(car.team_name.to_string()),
+                        car_number: Some(car.car_number.clone()),
+                        lap: Some(laps_completed + 1),
+                        laps_completed: Some(laps_completed),
+                        lap_distance_pct: Some(Percentage::new(pct)),
+                        position: Some(position_of(laps, car.grid_slot)),
+                        class_position: Some(position_of(laps, car.grid_slot)),
+                        on_pit_road: Some(on_pit_road),
+                        track_surface: Some(TrackSurface::Asphalt),
+                        best_lap_time: Some(Seconds(car.lap_time - 0.4)),
+                        last_lap_time: Some(Seconds(car.lap_time + jitter(lap_key, 0.6))),
+                        estimated_time: Some(Seconds(pct * car.lap_time)),
+                        gear: Some(state.gear),
+                        rpm: Some(Rpm(state.rpm)),
+                        steering: Some(Degrees::from_radians(state.steering)),
+                    }
+                })
+                .collect(),
+        );
 
         // --- Driver ---
         let driver = Some(DriverData {
@@ -758,11 +1347,24 @@ impl DemoAdapter {
         let mut extras = HashMap::new();
         extras.insert("demo".to_string(), serde_json::Value::Object(demo_data));
 
-        TelemetryFrame {
+        let now = Utc::now();
+        let mut frame = TelemetryFrame {
             meta: MetaData {
-                timestamp: Utc::now(),
+                timestamp: match self.fixed_start {
+                    Some(start) => {
+                        start + chrono::Duration::microseconds((elapsed as f64 * 1e6) as i64)
+                    }
+                    None => now,
+                },
                 game: "Demo".to_string(),
                 tick: Some(self.frame_count as u32),
+                sim_time: Some(elapsed as f64),
+                invalid_fields: None,
+                seq: None,
+                // Left to the server on a fixed clock, so frames are reproducible
+                acquired_at: self.fixed_start.is_none().then_some(now),
+                broadcast_at: None,
+                adapter: None,
             },
             motion,
             vehicle,
@@ -774,10 +1376,15 @@ impl DemoAdapter {
             pit,
             electronics,
             damage,
+            energy: None,
             competitors,
             driver,
+            relative: None,
+            predictions: None,
             extras,
-        }
+        };
+        self.scenario.apply(&mut frame, elapsed, self.lap_duration);
+        frame
     }
 }
 
@@ -800,40 +1407,339 @@ impl TelemetryAdapter for DemoAdapter {
         true
     }
 
-    fn start(&mut self) -> Result<()> {
+    fn start(&mut self) -> Result<(), AdapterError> {
         self.active = true;
         self.start_time = Some(Instant::now());
         self.frame_count = 0;
         self.laps_completed = 0;
+        self.best_lap = INITIAL_BEST_LAP;
+        self.last_lap = INITIAL_LAST_LAP;
         Ok(())
     }
 
-    fn stop(&mut self) -> Result<()> {
+    fn stop(&mut self) -> Result<(), AdapterError> {
         self.active = false;
         self.start_time = None;
         Ok(())
     }
 
-    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>> {
+    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>, AdapterError> {
         if !self.active {
             return Ok(None);
         }
 
-        // Pace at ~60Hz to avoid busy-spinning when the manager loop has no fixed sleep
-        let now = Instant::now();
-        if let Some(last) = self.last_frame_time {
-            let elapsed = now.duration_since(last);
-            let frame_interval = std::time::Duration::from_millis(16); // ~60Hz
-            if elapsed < frame_interval {
-                std::thread::sleep(frame_interval - elapsed);
-            }
+        Ok(Some(self.generate_frame()))
+    }
+
+    fn scenarios(&self) -> &'static [&'static str] {
+        &DemoScenario::NAMES
+    }
+
+    /// Switch scenario and restart the session so it plays from the start
+    fn set_scenario(&mut self, name: &str) -> Result<(), AdapterError> {
+        self.scenario = name.parse()?;
+        if self.active {
+            self.start()?;
         }
-        self.last_frame_time = Some(Instant::now());
+        Ok(())
+    }
 
-        Ok(Some(self.generate_frame()))
+    fn poll_hint(&self) -> PollHint {
+        // Frames are generated on demand; the manager paces us at ~60Hz
+        PollHint::Interval(Duration::from_millis(16))
     }
 
     fn is_active(&self) -> bool {
         self.active
     }
+
+    fn capabilities(&self) -> &'static [&'static str] {
+        // No GPS, handbrake, hybrid system or driver aids beyond ABS and TC
+        &[
+            "motion.position",
+            "motion.velocity",
+            "motion.acceleration",
+            "motion.g_force",
+            "motion.rotation",
+            "motion.pitch_rate",
+            "motion.yaw_rate",
+            "motion.roll_rate",
+            "motion.angular_acceleration",
+            "vehicle.speed",
+            "vehicle.rpm",
+            "vehicle.max_rpm",
+            "vehicle.idle_rpm",
+            "vehicle.gear",
+            "vehicle.max_gears",
+            "vehicle.throttle",
+            "vehicle.brake",
+            "vehicle.clutch",
+            "vehicle.steering_angle",
+            "vehicle.steering_torque",
+            "vehicle.steering_torque_pct",
+            "vehicle.shift_indicator",
+            "vehicle.steering_angle_max",
+            "vehicle.on_track",
+            "vehicle.in_garage",
+            "vehicle.track_surface",
+            "vehicle.car_name",
+            "vehicle.car_class",
+            "vehicle.setup_name",
+            "engine",
+            "wheels.*.suspension_travel",
+            "wheels.*.suspension_travel_avg",
+            "wheels.*.shock_velocity",
+            "wheels.*.shock_velocity_avg",
+            "wheels.*.ride_height",
+            "wheels.*.tyre_pressure",
+            "wheels.*.tyre_cold_pressure",
+            "wheels.*.surface_temp_inner",
+            "wheels.*.surface_temp_middle",
+            "wheels.*.surface_temp_outer",
+            "wheels.*.carcass_temp_inner",
+            "wheels.*.carcass_temp_middle",
+            "wheels.*.carcass_temp_outer",
+            "wheels.*.tyre_wear",
+            "wheels.*.tyre_wear_inner",
+            "wheels.*.tyre_wear_middle",
+            "wheels.*.tyre_wear_outer",
+            "wheels.*.wheel_speed",
+            "wheels.*.slip_ratio",
+            "wheels.*.slip_angle",
+            "wheels.*.load",
+            "wheels.*.brake_line_pressure",
+            "wheels.*.brake_temp",
+            "wheels.*.tyre_compound",
+            "wheels.*.brake_pad_wear",
+            "timing.current_lap_time",
+            "timing.last_lap_time",
+            "timing.best_lap_time",
+            "timing.best_n_lap_time",
+            "timing.best_n_lap_num",
+            "timing.sector_times",
+            "timing.lap_number",
+            "timing.laps_completed",
+            "timing.lap_distance",
+            "timing.lap_distance_pct",
+            "timing.race_position",
+            "timing.class_position",
+            "timing.num_cars",
+            "timing.delta_best",
+            "timing.delta_best_ok",
+            "timing.delta_session_best",
+            "timing.delta_session_best_ok",
+            "timing.delta_optimal",
+            "timing.delta_optimal_ok",
+            "timing.estimated_lap_time",
+            "timing.race_laps",
+            "session.session_type",
+            "session.session_state",
+            "session.session_time",
+            "session.session_time_remaining",
+            "session.session_time_of_day",
+            "session.session_laps",
+            "session.session_laps_remaining",
+            "session.flags",
+            "session.track_name",
+            "session.track_config",
+            "session.track_length",
+            "session.track_type",
+            "session.track_id",
+            "weather",
+            "pit",
+            "electronics.abs",
+            "electronics.abs_active",
+            "electronics.traction_control",
+            "electronics.brake_bias",
+            "electronics.shift_light_first_rpm",
+            "electronics.shift_light_shift_rpm",
+            "electronics.shift_light_last_rpm",
+            "electronics.shift_light_blink_rpm",
+            "damage",
+            "competitors",
+            "driver",
+            "extras.demo",
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ost_core::adapter::Capabilities;
+
+    #[test]
+    fn test_demo_motion_is_canonical() {
+        let mut adapter = DemoAdapter::new();
+        let mut right_turns = 0;
+        let mut left_turns = 0;
+        let mut t = 0.0;
+        while t < adapter.lap_duration {
+            let frame = adapter.frame_at(t);
+            let motion = frame.motion.unwrap();
+            let steering = frame.vehicle.unwrap().steering_angle.unwrap().0;
+            let lat_g = motion.g_force.unwrap().x.0;
+            let yaw_rate = motion.yaw_rate.unwrap().0;
+
+            // Clear of the noise floor, all three agree on the direction
+            if steering.abs() > 5.0 && lat_g.abs() > 0.5 {
+                assert_eq!(
+                    steering.signum(),
+                    lat_g.signum(),
+                    "steering vs lateral G at {t}s"
+                );
+                assert_eq!(
+                    yaw_rate.signum(),
+                    lat_g.signum(),
+                    "yaw rate vs lateral G at {t}s"
+                );
+                if lat_g > 0.0 {
+                    right_turns += 1;
+                } else {
+                    left_turns += 1;
+                }
+            }
+            assert!(motion.g_force.unwrap().y.0 > 0.9, "+1 g vertical");
+            t += 0.1;
+        }
+        assert!(right_turns > 0 && left_turns > 0);
+    }
+
+    #[test]
+    fn test_capabilities_cover_frames() {
+        let mut adapter = DemoAdapter::new();
+        let caps = Capabilities::from_patterns(adapter.capabilities()).unwrap();
+        let mut t = 0.0;
+        while t < adapter.lap_duration {
+            let undeclared = caps.undeclared(&adapter.frame_at(t));
+            assert!(undeclared.is_empty(), "{:?} at {t}s", undeclared);
+            t += 0.5;
+        }
+    }
+
+    fn flags(frame: &TelemetryFrame) -> FlagState {
+        frame.session.as_ref().unwrap().flags.unwrap()
+    }
+
+    fn speed(frame: &TelemetryFrame) -> f32 {
+        frame.vehicle.as_ref().unwrap().speed.unwrap().0
+    }
+
+    #[test]
+    fn test_scenario_names_round_trip() {
+        for scenario in DemoScenario::ALL {
+            assert_eq!(scenario.name().parse::<DemoScenario>().unwrap(), scenario);
+            assert_eq!(
+                serde_json::to_value(scenario).unwrap(),
+                serde_json::json!(scenario.name())
+            );
+        }
+        assert!("monsoon".parse::<DemoScenario>().is_err());
+    }
+
+    #[test]
+    fn test_scenarios() {
+        let mut fcy = DemoAdapter::with_scenario(DemoScenario::FullCourseYellow);
+        assert!(flags(&fcy.frame_at(10.0)).green);
+        let caution = fcy.frame_at(60.0);
+        assert!(flags(&caution).caution && !flags(&caution).green);
+        assert!(speed(&caution) <= FCY_SPEED + 0.01);
+        assert!(flags(&fcy.frame_at(105.0)).one_lap_to_green);
+
+        let mut rain = DemoAdapter::with_scenario(DemoScenario::RainOnset);
+        let dry = rain.frame_at(10.0).weather.unwrap();
+        let wet = rain.frame_at(300.0).weather.unwrap();
+        assert_eq!(dry.track_wetness, Some(TrackWetness::Dry));
+        assert_eq!(wet.track_wetness, Some(TrackWetness::VeryWet));
+        assert_eq!(wet.declared_wet, Some(true));
+
+        // In the box at the end of lap 2, then back out with a full tank
+        let mut pit = DemoAdapter::with_scenario(DemoScenario::PitStop);
+        let lap = pit.lap_duration;
+        assert_eq!(pit.frame_at(5.0).pit.unwrap().on_pit_road, Some(false));
+        let stopped = pit.frame_at(lap * 2.0);
+        assert_eq!(stopped.pit.as_ref().unwrap().pit_active, Some(true));
+        assert_eq!(speed(&stopped), 0.0);
+        let lane = pit.frame_at(lap * 2.0 + 12.0);
+        assert!(lane.engine.unwrap().warnings.unwrap().pit_speed_limiter);
+        let out = pit.frame_at(lap * 2.0 + 30.0);
+        assert_eq!(out.pit.unwrap().on_pit_road, Some(false));
+        assert!(out.engine.unwrap().fuel_level.unwrap().0 > 59.5);
+
+        let mut damage = DemoAdapter::with_scenario(DemoScenario::Damage);
+        assert_eq!(damage.frame_at(10.0).damage.unwrap().front.unwrap().0, 0.0);
+        let hit = damage.frame_at(50.0).damage.unwrap();
+        assert!(hit.front.unwrap().0 > 0.0 && hit.engine.unwrap().0 > 0.0);
+
+        let mut fuel = DemoAdapter::with_scenario(DemoScenario::FuelRunOut);
+        let running = fuel.frame_at(40.0);
+        assert!(!running.engine.unwrap().warnings.unwrap().engine_stalled);
+        let stalled = fuel.frame_at(120.0);
+        assert_eq!(stalled.engine.as_ref().unwrap().fuel_level.unwrap().0, 0.0);
+        assert!(speed(&stalled) < 1.0);
+        assert!(stalled.engine.unwrap().warnings.unwrap().engine_stalled);
+
+        let mut quali = DemoAdapter::with_scenario(DemoScenario::Qualifying);
+        assert!(flags(&quali.frame_at(100.0)).green);
+        let end = quali.frame_at(QUALIFYING_LENGTH + 1.0);
+        assert!(flags(&end).checkered);
+        assert_eq!(
+            end.session.unwrap().session_state,
+            Some(SessionState::Checkered)
+        );
+    }
+
+    #[test]
+    fn test_field_positions_change() {
+        let mut adapter = DemoAdapter::new().with_field_size(5);
+        let order = |frame: &TelemetryFrame| {
+            let mut cars: Vec<(u32, u32)> = frame
+                .competitors
+                .as_ref()
+                .unwrap()
+                .iter()
+                .map(|c| (c.position.unwrap(), c.car_index))
+                .collect();
+            cars.push((frame.timing.as_ref().unwrap().race_position.unwrap(), 0));
+            cars.sort();
+            cars
+        };
+
+        let positions =
+            |frame: &TelemetryFrame| order(frame).iter().map(|c| c.0).collect::<Vec<_>>();
+
+        let start = adapter.frame_at(0.0);
+        assert_eq!(start.competitors.as_ref().unwrap().len(), 5);
+        assert_eq!(start.timing.as_ref().unwrap().num_cars, Some(6));
+        // Grid order, the player third, with the cars behind the line
+        // ranked by grid slot rather than tied
+        assert_eq!(positions(&start), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            order(&start).iter().map(|c| c.1).collect::<Vec<_>>(),
+            [1, 2, 0, 3, 4, 5]
+        );
+        // Positions stay a permutation of 1..=6, through the pit stops
+        for quarter in 0..60 {
+            let frame = adapter.frame_at(adapter.lap_duration * quarter as f32 / 4.0);
+            assert_eq!(
+                positions(&frame),
+                [1, 2, 3, 4, 5, 6],
+                "at lap {}",
+                quarter / 4
+            );
+        }
+        let later = adapter.frame_at(adapter.lap_duration * 12.0);
+        assert_ne!(order(&start), order(&later));
+
+        // Every car makes a pit stop within its stint
+        for car in &adapter.field {
+            let stop = (car.stint as f32 * car.lap_time) - car.head_start;
+            assert!(
+                car.progress(stop).1,
+                "car {} should be in the pits",
+                car.car_index
+            );
+            assert!(!car.progress(stop + PIT_LOSS + 10.0).1);
+        }
+    }
 }
@@ -4,9 +4,10 @@
 //! to TelemetryFrame for replay. Works on all platforms.
 
 use anyhow::{bail, Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use ost_core::adapter::VariableInfo;
 use ost_core::{model::*, units::*};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 #[cfg(unix)]
@@ -14,6 +15,7 @@ use std::os::unix::fs::FileExt;
 #[cfg(windows)]
 use std::os::windows::fs::FileExt;
 use std::path::Path;
+use std::sync::Arc;
 
 // ============================================================================
 // Binary format types
@@ -44,7 +46,19 @@ impl VarType {
     }
 
     /// Size in bytes for a single element of this type
-    fn element_size(&self) -> usize {
+    /// Type name as in `irsdk_VarType`, without the prefix
+    pub fn name(&self) -> &'static str {
+        match self {
+            VarType::Char => "char",
+            VarType::Bool => "bool",
+            VarType::Int => "int",
+            VarType::BitField => "bitfield",
+            VarType::Float => "float",
+            VarType::Double => "double",
+        }
+    }
+
+    pub fn element_size(&self) -> usize {
         match self {
             VarType::Char => 1,
             VarType::Bool => 1,
@@ -56,6 +70,11 @@ impl VarType {
     }
 }
 
+/// A decoded sample: each variable's value by name. The names are shared
+/// with the [`IbtFile`] they were read from, so decoding one allocates no
+/// strings.
+pub type Sample = HashMap<Arc<str>, VarValue>;
+
 /// A parsed variable value from a sample
 #[derive(Debug, Clone)]
 pub enum VarValue {
@@ -118,12 +137,271 @@ impl VarValue {
     }
 }
 
-/// Lap boundary info for replay seeking
-#[derive(Debug, Clone, serde::Serialize)]
+/// One lap of a session, from [`IbtFile::build_lap_index`]
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
 pub struct LapInfo {
     pub lap_number: i32,
     pub start_frame: usize,
+    /// Last frame of the lap, inclusive
+    pub end_frame: usize,
+    /// `SessionTime` from this lap's first frame to the next lap's; `None`
+    /// for the last lap in the file
     pub lap_time_secs: Option<f64>,
+    /// Started and finished by crossing the line on to the next lap number,
+    /// without going off track, out of the world or on to pit road between
+    pub valid: bool,
+    /// Started on pit road, i.e. the lap leaving the pits
+    pub out_lap: bool,
+    /// Ended on pit road, i.e. the lap coming into the pits
+    pub in_lap: bool,
+}
+
+/// `PlayerTrackSurface` (`irsdk_TrkLoc`) values that void a lap
+pub const TRK_LOC_NOT_IN_WORLD: i32 = -1;
+pub const TRK_LOC_OFF_TRACK: i32 = 0;
+
+/// Whether a `PlayerTrackSurface` value has the car off the track, for lap
+/// validity and off-track events alike. Out of the world counts too: the
+/// car was towed or reset.
+pub fn is_off_track(surface: Option<i32>) -> bool {
+    matches!(surface, Some(TRK_LOC_NOT_IN_WORLD | TRK_LOC_OFF_TRACK))
+}
+
+/// What [`LapScan`] needs from one frame
+#[derive(Debug, Clone, Copy, Default)]
+struct LapSample {
+    lap: i32,
+    session_time: Option<f64>,
+    on_pit_road: bool,
+    off_track: bool,
+}
+
+/// Splits frames into [`LapInfo`]s wherever the lap number changes
+#[derive(Debug, Default)]
+struct LapScan {
+    laps: Vec<LapInfo>,
+    /// `SessionTime` at the start of each lap
+    start_times: Vec<Option<f64>>,
+    /// Whether the lap in progress has been voided
+    voided: bool,
+    on_pit_road: bool,
+}
+
+impl LapScan {
+    fn add(&mut self, frame: usize, sample: LapSample) {
+        let voids = sample.on_pit_road || sample.off_track;
+        match self.laps.last() {
+            Some(lap) if lap.lap_number == sample.lap => self.voided |= voids,
+            last => {
+                // A lap only counts if it started by crossing the line from
+                // the lap before, not at the start of the file or after a reset
+                let crossed = last.is_some_and(|lap| lap.lap_number + 1 == sample.lap);
+                self.close(frame, crossed);
+                self.laps.push(LapInfo {
+                    lap_number: sample.lap,
+                    start_frame: frame,
+                    out_lap: sample.on_pit_road,
+                    ..Default::default()
+                });
+                self.start_times.push(sample.session_time);
+                self.voided = !crossed || voids;
+            }
+        }
+        self.on_pit_road = sample.on_pit_road;
+    }
+
+    /// End the lap in progress before `frame`; `completed` if it ended by
+    /// crossing the line
+    fn close(&mut self, frame: usize, completed: bool) {
+        if let Some(lap) = self.laps.last_mut() {
+            lap.end_frame = frame.saturating_sub(1);
+            lap.in_lap = self.on_pit_road;
+            lap.valid = completed && !self.voided;
+        }
+    }
+
+    /// `frames` is the total number of frames scanned
+    fn finish(mut self, frames: usize) -> Vec<LapInfo> {
+        self.close(frames, false);
+
+        // Lap N's time = SessionTime at start of lap N+1 - SessionTime at start of lap N
+        for i in 0..self.laps.len().saturating_sub(1) {
+            if let (Some(t_start), Some(t_end)) = (self.start_times[i], self.start_times[i + 1]) {
+                let dt = t_end - t_start;
+                if dt > 0.0 && dt < 3600.0 {
+                    self.laps[i].lap_time_secs = Some(dt);
+                }
+            }
+        }
+        self.laps
+    }
+}
+
+/// A stretch of a file in one session of a weekend, from
+/// [`IbtFile::build_session_index`]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct IbtSession {
+    /// `SessionNum`, counting the weekend's sessions from 0
+    pub session_num: i32,
+    /// As given in the session info, e.g. `Practice` or `Race`
+    pub session_type: Option<String>,
+    pub start_frame: usize,
+    /// Last frame of the session, inclusive
+    pub end_frame: usize,
+}
+
+/// Split frames with these `SessionNum`s wherever it changes. Frames
+/// without one stay with the session before, or the first session if they
+/// come before it.
+fn split_sessions(
+    session_nums: impl IntoIterator<Item = Option<i32>>,
+    info: &IbtSessionInfo,
+) -> Vec<IbtSession> {
+    let mut sessions: Vec<IbtSession> = Vec::new();
+    for (frame, num) in session_nums.into_iter().enumerate() {
+        if let Some(session) = sessions.last_mut() {
+            if num.is_none_or(|num| num == session.session_num) {
+                session.end_frame = frame;
+                continue;
+            }
+        }
+        let Some(num) = num else {
+            continue;
+        };
+        sessions.push(IbtSession {
+            session_num: num,
+            session_type: info.session_types.get(&num).cloned(),
+            start_frame: if sessions.is_empty() { 0 } else { frame },
+            end_frame: frame,
+        });
+    }
+    sessions
+}
+
+/// Samples missing from a file, as found by [`Timeline`]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct TickGap {
+    /// First frame after the gap
+    pub frame: usize,
+    /// Time missing before it, beyond one sample period
+    pub secs: f64,
+}
+
+/// When each sample of a file was taken, from `SessionTick`
+///
+/// Samples are usually one tick apart, but a file can be logged at less than
+/// the sim's tick rate, and has ticks missing wherever logging stopped and
+/// started again or the sim hitched. Frames are split into runs of evenly
+/// spaced samples, so times and frames convert either way without assuming
+/// every tick is there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    /// Samples per second within a run
+    sample_rate: f64,
+    /// First frame and its time in seconds of each run, the first at 0
+    runs: Vec<(usize, f64)>,
+    frames: usize,
+}
+
+impl Timeline {
+    /// `frames` samples at `sample_rate` Hz, none missing
+    pub fn contiguous(sample_rate: u32, frames: usize) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1) as f64,
+            runs: vec![(0, 0.0)],
+            frames,
+        }
+    }
+
+    /// Timeline of samples with these `SessionTick`s, counting `tick_rate`
+    /// a second. Samples are taken to be as many ticks apart as is most
+    /// common; one without a tick, or with a tick going backwards (a new
+    /// session), follows on from the one before.
+    pub fn from_ticks(tick_rate: u32, ticks: &[Option<i32>]) -> Self {
+        let tick_rate = tick_rate.max(1) as f64;
+        let deltas = || {
+            ticks.windows(2).filter_map(|pair| match pair {
+                [Some(a), Some(b)] if b > a => Some(*b as i64 - *a as i64),
+                _ => None,
+            })
+        };
+        let mut counts: HashMap<i64, usize> = HashMap::new();
+        for delta in deltas() {
+            *counts.entry(delta).or_default() += 1;
+        }
+        let stride = counts
+            .into_iter()
+            .max_by_key(|&(delta, count)| (count, std::cmp::Reverse(delta)))
+            .map_or(1, |(delta, _)| delta);
+
+        let mut timeline = Self {
+            sample_rate: tick_rate / stride as f64,
+            runs: vec![(0, 0.0)],
+            frames: ticks.len(),
+        };
+        for (i, pair) in ticks.windows(2).enumerate() {
+            if let [Some(a), Some(b)] = pair {
+                let delta = *b as i64 - *a as i64;
+                if delta > stride {
+                    let frame = i + 1;
+                    let time = timeline.time_of(i) + delta as f64 / tick_rate;
+                    timeline.runs.push((frame, time));
+                }
+            }
+        }
+        timeline
+    }
+
+    /// Samples per second, between gaps
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Seconds from the first sample to `frame`
+    pub fn time_of(&self, frame: usize) -> f64 {
+        let run = self.runs.partition_point(|&(start, _)| start <= frame) - 1;
+        let (start, time) = self.runs[run];
+        time + (frame - start) as f64 / self.sample_rate
+    }
+
+    /// The frame showing `secs` after the first: the nearest one, or within
+    /// a gap the last one before it
+    pub fn frame_at(&self, secs: f64) -> usize {
+        let run = self.runs.partition_point(|&(_, time)| time <= secs).max(1) - 1;
+        let (start, time) = self.runs[run];
+        let end = self
+            .runs
+            .get(run + 1)
+            .map_or(self.frames, |&(next, _)| next);
+        let offset = ((secs - time) * self.sample_rate).round().max(0.0) as usize;
+        (start + offset).min(end.saturating_sub(1))
+    }
+
+    /// Time from the first sample to the end of the last
+    pub fn duration_secs(&self) -> f64 {
+        match self.frames {
+            0 => 0.0,
+            frames => self.time_of(frames - 1) + 1.0 / self.sample_rate,
+        }
+    }
+
+    /// Where samples are missing
+    pub fn gaps(&self) -> Vec<TickGap> {
+        self.runs
+            .windows(2)
+            .map(|pair| {
+                let (start, time) = pair[1];
+                TickGap {
+                    frame: start,
+                    secs: time - self.time_of(start - 1) - 1.0 / self.sample_rate,
+                }
+            })
+            .collect()
+    }
 }
 
 /// Main .ibt file header (48 bytes at offset 0)
@@ -170,6 +448,232 @@ pub struct VarHeader {
     pub unit: String,
 }
 
+impl VarHeader {
+    /// Size of an `irsdk_varHeader`
+    pub const LEN: usize = 144;
+
+    /// Parse an `irsdk_varHeader`, as found in .ibt files and shared memory
+    pub fn parse(buf: &[u8; Self::LEN]) -> Result<Self> {
+        Ok(Self {
+            var_type: VarType::from_i32(i32::from_le_bytes(buf[0..4].try_into()?))?,
+            offset: i32::from_le_bytes(buf[4..8].try_into()?),
+            count: i32::from_le_bytes(buf[8..12].try_into()?),
+            count_as_time: buf[12] != 0,
+            name: read_null_terminated_string(&buf[16..48]),
+            desc: read_null_terminated_string(&buf[48..112]),
+            unit: read_null_terminated_string(&buf[112..144]),
+        })
+    }
+
+    /// Name, type, unit and description, for listing to clients
+    pub fn info(&self) -> VariableInfo {
+        VariableInfo {
+            name: self.name.clone(),
+            var_type: self.var_type.name().to_string(),
+            count: self.count.max(1) as usize,
+            unit: self.unit.clone(),
+            description: self.desc.clone(),
+        }
+    }
+
+    /// A variable with `count` elements (1 for scalars), laid out by
+    /// [`IbtWriter`](crate::ibt_writer::IbtWriter)
+    pub fn new(name: &str, var_type: VarType, count: i32, unit: &str, desc: &str) -> Self {
+        Self {
+            var_type,
+            offset: 0,
+            count,
+            count_as_time: false,
+            name: name.to_string(),
+            desc: desc.to_string(),
+            unit: unit.to_string(),
+        }
+    }
+
+    /// Where the variable's value (all elements) sits in a sample buffer
+    pub fn byte_range(&self) -> std::ops::Range<usize> {
+        let start = self.offset.max(0) as usize;
+        start..start + self.var_type.element_size() * self.count.max(1) as usize
+    }
+
+    /// Read the variable (its first element, for arrays) from a raw sample
+    /// buffer as returned by [`IbtFile::read_raw_samples`]
+    pub fn read_scalar(&self, sample: &[u8]) -> Option<VarValue> {
+        read_scalar_value(sample, self.offset.max(0) as usize, self.var_type)
+    }
+}
+
+// ============================================================================
+// Variable units
+// ============================================================================
+
+/// What a variable measures, each kept in one unit in the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    /// kPa
+    Pressure,
+    /// Liters
+    Volume,
+    /// Kilograms
+    Mass,
+    /// Liters per hour
+    VolumeRate,
+    /// Kilograms per hour
+    MassRate,
+    /// °C
+    Temperature,
+    /// Meters
+    Length,
+}
+
+/// Unit strings found in [`VarHeader::unit`], with what they measure and
+/// the scale and offset taking them to the model's unit
+const UNITS: &[(&str, Quantity, f32, f32)] = &[
+    ("Pa", Quantity::Pressure, 0.001, 0.0),
+    ("kPa", Quantity::Pressure, 1.0, 0.0),
+    ("bar", Quantity::Pressure, 100.0, 0.0),
+    ("psi", Quantity::Pressure, 6.894_757, 0.0),
+    ("l", Quantity::Volume, 1.0, 0.0),
+    ("L", Quantity::Volume, 1.0, 0.0),
+    ("gal", Quantity::Volume, 3.785_412, 0.0),
+    ("kg", Quantity::Mass, 1.0, 0.0),
+    ("l/h", Quantity::VolumeRate, 1.0, 0.0),
+    ("kg/h", Quantity::MassRate, 1.0, 0.0),
+    ("C", Quantity::Temperature, 1.0, 0.0),
+    ("F", Quantity::Temperature, 5.0 / 9.0, -160.0 / 9.0),
+    ("K", Quantity::Temperature, 1.0, -273.15),
+    ("m", Quantity::Length, 1.0, 0.0),
+    ("cm", Quantity::Length, 0.01, 0.0),
+    ("mm", Quantity::Length, 0.001, 0.0),
+];
+
+/// A variable's unit, and how to convert its values to the model's
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitConversion {
+    pub quantity: Quantity,
+    scale: f32,
+    offset: f32,
+}
+
+impl UnitConversion {
+    /// Look up a unit string, `None` if it isn't one of the model's
+    /// quantities (`%`, `rad`, `irsdk_Flags`, no unit, ...)
+    pub fn parse(unit: &str) -> Option<Self> {
+        let unit = unit.trim();
+        UNITS
+            .iter()
+            .find(|(name, ..)| *name == unit)
+            .map(|&(_, quantity, scale, offset)| Self {
+                quantity,
+                scale,
+                offset,
+            })
+    }
+
+    pub fn apply(&self, value: f32) -> f32 {
+        value * self.scale + self.offset
+    }
+}
+
+// ============================================================================
+// Frame plan: where sample_to_frame finds each variable
+// ============================================================================
+
+/// Wheel variable prefixes, in the order of [`FramePlan::wheels`]
+const WHEEL_PREFIXES: [&str; 4] = ["LF", "RF", "LR", "RR"];
+
+/// How a variable's values are brought to the model's unit, for `.ibt`
+/// files and the live adapter alike
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum SlotUnit {
+    /// No unit in the header: taken to be in the model's already
+    Model,
+    Known(UnitConversion),
+    /// Not one of [`UNITS`]
+    Unknown,
+}
+
+impl SlotUnit {
+    /// The unit of a variable header's unit string
+    pub(crate) fn parse(unit: &str) -> Self {
+        match unit.trim() {
+            "" => Self::Model,
+            unit => UnitConversion::parse(unit).map_or(Self::Unknown, Self::Known),
+        }
+    }
+
+    /// `value` converted to the model's unit for `quantity`. Taken to be in
+    /// the model's unit already without a unit; `None` if it measures
+    /// something else or the unit isn't in [`UNITS`]. Fuel given by mass is
+    /// converted by the car's fuel density, `DriverCarFuelKgPerLtr`.
+    pub(crate) fn convert(
+        self,
+        value: f32,
+        quantity: Quantity,
+        fuel_kg_per_liter: Option<f32>,
+    ) -> Option<f32> {
+        let unit = match self {
+            Self::Model => return Some(value),
+            Self::Known(unit) => unit,
+            Self::Unknown => return None,
+        };
+        let value = unit.apply(value);
+        match (unit.quantity, quantity) {
+            (have, want) if have == want => Some(value),
+            (Quantity::Mass, Quantity::Volume) | (Quantity::MassRate, Quantity::VolumeRate) => {
+                Some(value / fuel_kg_per_liter?)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A scalar variable located in the sample buffer
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    offset: usize,
+    var_type: VarType,
+    unit: SlotUnit,
+}
+
+impl Slot {
+    fn read(&self, sample: &[u8]) -> Option<VarValue> {
+        read_scalar_value(sample, self.offset, self.var_type)
+    }
+}
+
+/// A file's scalar variables by name, resolved once from its headers so
+/// [`IbtFile::sample_to_frame`] reads values straight out of sample buffers.
+/// Arrays are left out: they only go into the frame's extras.
+#[derive(Default)]
+struct FramePlan {
+    vars: HashMap<Arc<str>, Slot>,
+    /// Each wheel's variables by what follows its prefix (`pressure` for
+    /// `LFpressure`), in [`WHEEL_PREFIXES`] order
+    wheels: [HashMap<Box<str>, Slot>; 4],
+}
+
+impl FramePlan {
+    fn new(var_headers: &[VarHeader], var_names: &[Arc<str>]) -> Self {
+        let mut plan = Self::default();
+        for (vh, name) in var_headers.iter().zip(var_names) {
+            if vh.count != 1 {
+                continue;
+            }
+            let slot = Slot {
+                offset: vh.offset as usize,
+                var_type: vh.var_type,
+                unit: SlotUnit::parse(&vh.unit),
+            };
+            if let Some(wheel) = WHEEL_PREFIXES.iter().position(|p| name.starts_with(*p)) {
+                plan.wheels[wheel].insert(name[2..].into(), slot);
+            }
+            plan.vars.insert(name.clone(), slot);
+        }
+        plan
+    }
+}
+
 // ============================================================================
 // Session info parsed from YAML
 // ============================================================================
@@ -186,6 +690,12 @@ pub struct IbtSessionInfo {
     pub driver_name: String,
     pub driver_car_idx: i32,
     pub session_type: String,
+    /// `SessionType` of each of the weekend's sessions, by `SessionNum`
+    pub session_types: BTreeMap<i32, String>,
+    /// `SplitTimeInfo` sector starts, as lap distance
+    pub sector_starts: Vec<f32>,
+    /// `DriverCarFuelKgPerLtr`, for fuel given by mass
+    pub fuel_kg_per_liter: Option<f32>,
 }
 
 impl IbtSessionInfo {
@@ -193,11 +703,14 @@ impl IbtSessionInfo {
     /// Uses simple line-based parsing to avoid adding a YAML dependency.
     pub fn from_yaml(yaml: &str) -> Result<Self> {
         let mut info = IbtSessionInfo::default();
+        let mut session_num = None;
 
         for line in yaml.lines() {
             let trimmed = line.trim();
 
-            if let Some(val) = try_extract_yaml_value(trimmed, "TrackName:") {
+            if let Some(val) = try_extract_yaml_value(trimmed, "- SessionNum:") {
+                session_num = val.parse::<i32>().ok();
+            } else if let Some(val) = try_extract_yaml_value(trimmed, "TrackName:") {
                 info.track_name = val;
             } else if let Some(val) = try_extract_yaml_value(trimmed, "TrackDisplayName:") {
                 info.track_display_name = val;
@@ -217,10 +730,19 @@ impl IbtSessionInfo {
                 if let Ok(idx) = val.parse::<i32>() {
                     info.driver_car_idx = idx;
                 }
+            } else if let Some(val) = try_extract_yaml_value(trimmed, "DriverCarFuelKgPerLtr:") {
+                info.fuel_kg_per_liter = val.parse::<f32>().ok().filter(|d| *d > 0.0);
             } else if let Some(val) = try_extract_yaml_value(trimmed, "SessionType:") {
+                if let Some(num) = session_num {
+                    info.session_types.entry(num).or_insert_with(|| val.clone());
+                }
                 if info.session_type.is_empty() {
                     info.session_type = val;
                 }
+            } else if let Some(val) = try_extract_yaml_value(trimmed, "SectorStartPct:") {
+                if let Ok(pct) = val.parse::<f32>() {
+                    info.sector_starts.push(pct);
+                }
             }
         }
 
@@ -235,7 +757,38 @@ impl IbtSessionInfo {
 }
 
 fn try_extract_yaml_value(line: &str, key: &str) -> Option<String> {
-    line.strip_prefix(key).map(|rest| rest.trim().to_string())
+    line.strip_prefix(key).map(|rest| unquote_yaml(rest.trim()))
+}
+
+/// A YAML scalar without its quotes and escapes. iRacing leaves names
+/// unquoted, but files written from other games' frames quote them.
+fn unquote_yaml(value: &str) -> String {
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        let mut unquoted = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unquoted.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => unquoted.push('\n'),
+                Some('r') => unquoted.push('\r'),
+                Some('t') => unquoted.push('\t'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    unquoted.extend(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32));
+                }
+                Some(c) => unquoted.push(c),
+                None => {}
+            }
+        }
+        unquoted
+    } else if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        inner.replace("''", "'")
+    } else {
+        value.to_string()
+    }
 }
 
 // ============================================================================
@@ -252,8 +805,37 @@ pub struct IbtFile {
     pub session_info: IbtSessionInfo,
     sample_data_offset: u64,
     file_size: u64,
-    #[allow(dead_code)]
-    var_index: HashMap<String, usize>,
+    /// Samples the header claims, if more than the file holds
+    expected_records: Option<usize>,
+    /// Variable names, in `var_headers` order, shared by every sample
+    var_names: Vec<Arc<str>>,
+    var_index: HashMap<Arc<str>, usize>,
+    plan: FramePlan,
+}
+
+/// How much of a truncated file could be read, from [`IbtFile::recovery`]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct IbtRecovery {
+    /// Samples the header says the file has
+    pub expected_records: usize,
+    /// Complete samples found, which are all that's read
+    pub records: usize,
+    /// `records` as a percentage of `expected_records`
+    pub percent: f64,
+}
+
+/// Fail unless `len` bytes at `offset` lie within a file of `file_size`
+fn check_range(what: &str, offset: i32, len: i64, file_size: u64) -> Result<()> {
+    if offset < 0 || len < 0 || offset as u64 + len as u64 > file_size {
+        bail!(
+            "Corrupt .ibt file: {} at {}..{} lie outside its {} bytes",
+            what,
+            offset,
+            offset as i64 + len,
+            file_size
+        );
+    }
+    Ok(())
 }
 
 impl IbtFile {
@@ -285,11 +867,19 @@ impl IbtFile {
 
     /// Open and parse an .ibt file from disk.
     /// Reads headers and session info, but does NOT load sample data into memory.
+    ///
+    /// The format has no checksums, so headers are checked against the file
+    /// instead: offsets pointing outside it or variables outside the sample
+    /// are errors. A file cut short keeps its complete samples, see
+    /// [`recovery`](Self::recovery).
     pub fn open(path: &Path) -> Result<Self> {
         let mut file = File::open(path)
             .with_context(|| format!("Failed to open .ibt file: {}", path.display()))?;
 
         let file_size = file.metadata()?.len();
+        if file_size < 144 {
+            bail!("Not an .ibt file: only {} bytes", file_size);
+        }
 
         let header = Self::read_header(&mut file)?;
 
@@ -297,30 +887,72 @@ impl IbtFile {
         let var_buf = Self::read_var_buf(&mut file)?;
 
         file.seek(SeekFrom::Start(112))?;
-        let disk_sub_header = Self::read_disk_sub_header(&mut file)?;
+        let mut disk_sub_header = Self::read_disk_sub_header(&mut file)?;
+
+        if header.num_vars < 0 || header.buf_len <= 0 {
+            bail!(
+                "Corrupt .ibt file: {} variables in {}-byte samples",
+                header.num_vars,
+                header.buf_len
+            );
+        }
+        check_range(
+            "variable headers",
+            header.var_header_offset,
+            header.num_vars as i64 * VarHeader::LEN as i64,
+            file_size,
+        )?;
+        check_range(
+            "session info",
+            header.session_info_offset,
+            header.session_info_len as i64,
+            file_size,
+        )?;
+        check_range("samples", var_buf.buf_offset, 0, file_size)?;
 
         file.seek(SeekFrom::Start(header.var_header_offset as u64))?;
         let var_headers = Self::read_var_headers(&mut file, header.num_vars as usize)?;
+        if let Some(vh) = var_headers
+            .iter()
+            .find(|vh| vh.offset < 0 || vh.byte_range().end > header.buf_len as usize)
+        {
+            bail!(
+                "Corrupt .ibt file: variable {} lies outside the {}-byte sample",
+                vh.name,
+                header.buf_len
+            );
+        }
 
-        let var_index: HashMap<String, usize> = var_headers
+        let var_names: Vec<Arc<str>> = var_headers
+            .iter()
+            .map(|vh| Arc::from(vh.name.as_str()))
+            .collect();
+        let var_index = var_names
             .iter()
             .enumerate()
-            .map(|(i, vh)| (vh.name.clone(), i))
+            .map(|(i, name)| (name.clone(), i))
             .collect();
+        let plan = FramePlan::new(&var_headers, &var_names);
 
         file.seek(SeekFrom::Start(header.session_info_offset as u64))?;
         let mut yaml_buf = vec![0u8; header.session_info_len as usize];
         file.read_exact(&mut yaml_buf)?;
-        let yaml_end = yaml_buf
-            .iter()
-            .position(|&b| b == 0)
-            .unwrap_or(yaml_buf.len());
-        let session_info_yaml = String::from_utf8_lossy(&yaml_buf[..yaml_end]).to_string();
+        let session_info_yaml = crate::iracing::iracing_session_yaml(&yaml_buf);
 
         let session_info = IbtSessionInfo::from_yaml(&session_info_yaml).unwrap_or_default();
 
         let sample_data_offset = var_buf.buf_offset as u64;
 
+        // Only whole samples are read from a file cut short. iRacing fills
+        // the count in when it closes the file, so one left by a crash has
+        // none: it gets what's there.
+        let on_disk = (file_size - sample_data_offset) / header.buf_len as u64;
+        let claimed = disk_sub_header.session_record_count.max(0) as u64;
+        let expected_records = (claimed > on_disk).then_some(claimed as usize);
+        if claimed == 0 || expected_records.is_some() {
+            disk_sub_header.session_record_count = on_disk as i32;
+        }
+
         Ok(IbtFile {
             file,
             header,
@@ -330,7 +962,10 @@ impl IbtFile {
             session_info,
             sample_data_offset,
             file_size,
+            expected_records,
+            var_names,
             var_index,
+            plan,
         })
     }
 
@@ -380,28 +1015,10 @@ impl IbtFile {
         let mut headers = Vec::with_capacity(count);
 
         for i in 0..count {
-            let mut buf = [0u8; 144];
+            let mut buf = [0u8; VarHeader::LEN];
             file.read_exact(&mut buf)
                 .with_context(|| format!("Failed to read variable header {}", i))?;
-
-            let var_type = VarType::from_i32(i32::from_le_bytes(buf[0..4].try_into()?))?;
-            let offset = i32::from_le_bytes(buf[4..8].try_into()?);
-            let count = i32::from_le_bytes(buf[8..12].try_into()?);
-            let count_as_time = buf[12] != 0;
-
-            let name = read_null_terminated_string(&buf[16..48]);
-            let desc = read_null_terminated_string(&buf[48..112]);
-            let unit = read_null_terminated_string(&buf[112..144]);
-
-            headers.push(VarHeader {
-                var_type,
-                offset,
-                count,
-                count_as_time,
-                name,
-                desc,
-                unit,
-            });
+            headers.push(VarHeader::parse(&buf)?);
         }
 
         Ok(headers)
@@ -431,96 +1048,157 @@ impl IbtFile {
         &self.var_headers
     }
 
-    pub fn file_size(&self) -> u64 {
-        self.file_size
+    pub fn var_header(&self, name: &str) -> Option<&VarHeader> {
+        self.var_index.get(name).map(|&i| &self.var_headers[i])
+    }
+
+    /// Size in bytes of one sample buffer
+    pub fn sample_len(&self) -> usize {
+        self.header.buf_len as usize
     }
 
-    /// Scan all frames to build a lap index for replay seeking.
-    /// Efficiently reads only the `Lap` and `LapLastLapTime` variables
-    /// from each frame buffer instead of parsing all ~200 variables.
-    pub fn build_lap_index(&mut self) -> Result<Vec<LapInfo>> {
+    /// Read `count` consecutive samples from `start` as raw buffers of
+    /// [`sample_len`](Self::sample_len) bytes each, without decoding them.
+    /// For scans that only need a few variables; see [`VarHeader::read_scalar`].
+    pub fn read_raw_samples(&self, start: usize, count: usize) -> Result<Vec<u8>> {
         let record_count = self.record_count();
-        if record_count == 0 {
+        if start >= record_count {
             return Ok(Vec::new());
         }
+        let count = count.min(record_count - start);
+        let buf_len = self.sample_len();
+        let mut buf = vec![0u8; buf_len * count];
+        self.read_at(&mut buf, self.sample_data_offset + (start * buf_len) as u64)?;
+        Ok(buf)
+    }
 
-        let lap_vh = self.var_index.get("Lap").map(|&i| &self.var_headers[i]);
-        let session_time_vh = self
-            .var_index
-            .get("SessionTime")
-            .map(|&i| &self.var_headers[i]);
-
-        let lap_vh = match lap_vh {
-            Some(vh) => vh.clone(),
-            None => return Ok(Vec::new()),
-        };
-        let session_time_vh = session_time_vh.cloned();
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
 
-        // Bulk read all sample buffers
-        let buf_len = self.header.buf_len as usize;
-        let total_bytes = buf_len * record_count;
-        self.file.seek(SeekFrom::Start(self.sample_data_offset))?;
-        let mut bulk_buf = vec![0u8; total_bytes];
-        self.file.read_exact(&mut bulk_buf)?;
+    /// For a file shorter than its header says, how many samples it has
+    /// left. `None` when it's whole.
+    pub fn recovery(&self) -> Option<IbtRecovery> {
+        let expected_records = self.expected_records?;
+        let records = self.record_count();
+        Some(IbtRecovery {
+            expected_records,
+            records,
+            percent: records as f64 * 100.0 / expected_records as f64,
+        })
+    }
 
-        // Helper to read SessionTime (f64) from a frame buffer
-        let read_session_time = |frame_buf: &[u8]| -> Option<f64> {
-            let vh = session_time_vh.as_ref()?;
-            let offset = vh.offset as usize;
-            match vh.var_type {
-                VarType::Double if offset + 8 <= frame_buf.len() => Some(f64::from_le_bytes(
-                    frame_buf[offset..offset + 8].try_into().unwrap(),
-                )),
-                VarType::Float if offset + 4 <= frame_buf.len() => Some(f32::from_le_bytes(
-                    frame_buf[offset..offset + 4].try_into().unwrap(),
-                ) as f64),
-                _ => None,
-            }
+    /// Pick up samples appended since the file was opened. iRacing only
+    /// fills in the record count when it closes the file, so while a session
+    /// is being written this counts the complete samples on disk instead.
+    /// Returns the new [`record_count`](Self::record_count).
+    pub fn refresh(&mut self) -> Result<usize> {
+        self.file_size = self.file.metadata()?.len();
+        let on_disk = match self.sample_len() {
+            0 => 0,
+            len => self.file_size.saturating_sub(self.sample_data_offset) as usize / len,
         };
+        if on_disk > self.record_count() {
+            self.disk_sub_header.session_record_count = on_disk as i32;
+        }
+        if self
+            .expected_records
+            .is_some_and(|n| n <= self.record_count())
+        {
+            self.expected_records = None;
+        }
+        Ok(self.record_count())
+    }
 
-        // Find lap transitions and record SessionTime at each transition
-        let mut laps: Vec<LapInfo> = Vec::new();
-        let mut prev_lap: Option<i32> = None;
-        let mut transition_times: Vec<Option<f64>> = Vec::new(); // SessionTime at each transition
+    /// Split the session into laps wherever `Lap` changes, with each lap's
+    /// time, validity and whether it is an in or out lap. Reads only the
+    /// handful of variables needed from each sample, a chunk at a time.
+    /// Empty if the file has no `Lap` variable.
+    pub fn build_lap_index(&self) -> Result<Vec<LapInfo>> {
+        let Some(lap) = self.var_header("Lap") else {
+            return Ok(Vec::new());
+        };
+        let session_time = self.var_header("SessionTime");
+        let on_pit_road = self.var_header("OnPitRoad");
+        let surface = self.var_header("PlayerTrackSurface");
 
-        for i in 0..record_count {
-            let frame_buf = &bulk_buf[i * buf_len..(i + 1) * buf_len];
-            let lap_offset = lap_vh.offset as usize;
-            if lap_offset + 4 > frame_buf.len() {
-                continue;
-            }
-            let lap_num =
-                i32::from_le_bytes(frame_buf[lap_offset..lap_offset + 4].try_into().unwrap());
-
-            if prev_lap.is_none() || prev_lap != Some(lap_num) {
-                let session_time = read_session_time(frame_buf);
-                laps.push(LapInfo {
-                    lap_number: lap_num,
-                    start_frame: i,
-                    lap_time_secs: None,
-                });
-                transition_times.push(session_time);
-                prev_lap = Some(lap_num);
+        const CHUNK: usize = 4096;
+        let record_count = self.record_count();
+        let buf_len = self.sample_len();
+        let mut scan = LapScan::default();
+        let mut start = 0;
+        while start < record_count && buf_len > 0 {
+            let bulk_buf = self.read_raw_samples(start, CHUNK)?;
+            for (i, frame_buf) in bulk_buf.chunks_exact(buf_len).enumerate() {
+                let read = |vh: Option<&VarHeader>| vh.and_then(|vh| vh.read_scalar(frame_buf));
+                let Some(lap_num) = read(Some(lap)).and_then(|v| v.as_i32()) else {
+                    continue;
+                };
+                scan.add(
+                    start + i,
+                    LapSample {
+                        lap: lap_num,
+                        session_time: read(session_time).and_then(|v| v.as_f64()),
+                        on_pit_road: read(on_pit_road).and_then(|v| v.as_bool()) == Some(true),
+                        off_track: is_off_track(read(surface).and_then(|v| v.as_i32())),
+                    },
+                );
             }
+            start += CHUNK;
         }
+        Ok(scan.finish(record_count))
+    }
 
-        // Compute lap times from SessionTime deltas between consecutive transitions
-        // Lap N's time = SessionTime at start of lap N+1 - SessionTime at start of lap N
-        for i in 0..laps.len().saturating_sub(1) {
-            if let (Some(t_start), Some(t_end)) = (transition_times[i], transition_times[i + 1]) {
-                let dt = t_end - t_start;
-                if dt > 0.0 && dt < 3600.0 {
-                    laps[i].lap_time_secs = Some(dt);
-                }
-            }
+    /// When each sample was taken, from `SessionTick`. Contiguous samples at
+    /// the file's tick rate if it has no `SessionTick` variable.
+    pub fn build_timeline(&self) -> Result<Timeline> {
+        let Some(tick) = self.var_header("SessionTick") else {
+            return Ok(Timeline::contiguous(self.tick_rate(), self.record_count()));
+        };
+        const CHUNK: usize = 4096;
+        let record_count = self.record_count();
+        let buf_len = self.sample_len();
+        let mut ticks = Vec::with_capacity(record_count);
+        let mut start = 0;
+        while start < record_count && buf_len > 0 {
+            let bulk_buf = self.read_raw_samples(start, CHUNK)?;
+            ticks.extend(
+                bulk_buf
+                    .chunks_exact(buf_len)
+                    .map(|sample| tick.read_scalar(sample).and_then(|v| v.as_i32())),
+            );
+            start += CHUNK;
         }
+        Ok(Timeline::from_ticks(self.tick_rate(), &ticks))
+    }
 
-        Ok(laps)
+    /// Split the file into the sessions of the weekend it was recorded over,
+    /// wherever `SessionNum` changes. Empty if the file has no `SessionNum`
+    /// variable.
+    pub fn build_session_index(&self) -> Result<Vec<IbtSession>> {
+        let Some(session_num) = self.var_header("SessionNum") else {
+            return Ok(Vec::new());
+        };
+        const CHUNK: usize = 4096;
+        let record_count = self.record_count();
+        let buf_len = self.sample_len();
+        let mut nums = Vec::with_capacity(record_count);
+        let mut start = 0;
+        while start < record_count && buf_len > 0 {
+            let bulk_buf = self.read_raw_samples(start, CHUNK)?;
+            nums.extend(
+                bulk_buf
+                    .chunks_exact(buf_len)
+                    .map(|sample| session_num.read_scalar(sample).and_then(|v| v.as_i32())),
+            );
+            start += CHUNK;
+        }
+        Ok(split_sessions(nums, &self.session_info))
     }
 
     /// Efficiently scan all frames to extract the track outline as lat/lng pairs.
     /// Only includes points where the car is on-track (`IsOnTrack == true`).
-    /// Uses bulk binary reads (like `build_lap_index`) to avoid parsing all ~200 variables.
+    /// Uses bulk binary reads to avoid parsing all ~200 variables.
     pub fn build_track_outline(&mut self) -> Result<Vec<[f64; 2]>> {
         let record_count = self.record_count();
         if record_count == 0 {
@@ -596,14 +1274,47 @@ impl IbtFile {
         Ok(points)
     }
 
+    /// Read scalar variables as `f32` for every sample, one series per name
+    /// (`None` where a variable is missing or not numeric). Reads only the
+    /// bytes of each sample buffer that are needed, without building frames.
+    pub fn read_f32_channels(&self, names: &[&str]) -> Result<Vec<Vec<Option<f32>>>> {
+        let record_count = self.record_count();
+        let headers: Vec<Option<&VarHeader>> = names
+            .iter()
+            .map(|name| self.var_index.get(*name).map(|&i| &self.var_headers[i]))
+            .collect();
+        let mut series = vec![Vec::with_capacity(record_count); names.len()];
+
+        // Bulk read in chunks to bound memory on long sessions
+        const CHUNK: usize = 4096;
+        let buf_len = self.header.buf_len as usize;
+        let mut start = 0;
+        while start < record_count {
+            let count = CHUNK.min(record_count - start);
+            let mut bulk_buf = vec![0u8; buf_len * count];
+            let offset = self.sample_data_offset + (start as u64) * (buf_len as u64);
+            self.read_at(&mut bulk_buf, offset)?;
+            for frame_buf in bulk_buf.chunks_exact(buf_len) {
+                for (series, vh) in series.iter_mut().zip(&headers) {
+                    let value = vh.and_then(|vh| {
+                        let var_offset = vh.offset as usize;
+                        if var_offset + vh.var_type.element_size() > frame_buf.len() {
+                            return None;
+                        }
+                        read_scalar_value(frame_buf, var_offset, vh.var_type)?.as_f32()
+                    });
+                    series.push(value);
+                }
+            }
+            start += count;
+        }
+        Ok(series)
+    }
+
     /// Read a contiguous range of samples in a single disk operation.
     /// Much faster than calling `read_sample()` in a loop because it avoids
     /// per-frame seek overhead.
-    pub fn read_samples_range(
-        &self,
-        start: usize,
-        count: usize,
-    ) -> Result<Vec<HashMap<String, VarValue>>> {
+    pub fn read_samples_range(&self, start: usize, count: usize) -> Result<Vec<Sample>> {
         let record_count = self.record_count();
         if start >= record_count {
             bail!("Start index {} out of range (0..{})", start, record_count);
@@ -622,71 +1333,68 @@ impl IbtFile {
         self.read_at(&mut bulk_buf, offset)?;
 
         // Parse each frame from the in-memory buffer
-        let mut results = Vec::with_capacity(clamped_count);
-        for i in 0..clamped_count {
-            let frame_buf = &bulk_buf[i * buf_len..(i + 1) * buf_len];
-            let mut sample = HashMap::with_capacity(self.var_headers.len());
-            for vh in &self.var_headers {
-                let var_offset = vh.offset as usize;
-                let count = vh.count as usize;
-                let end = var_offset + count * vh.var_type.element_size();
-                if end > frame_buf.len() {
-                    continue;
-                }
-                let value = if count == 1 {
-                    read_scalar_value(frame_buf, var_offset, vh.var_type)
-                } else {
-                    read_array_value(frame_buf, var_offset, vh.var_type, count)
-                };
-                if let Some(val) = value {
-                    sample.insert(vh.name.clone(), val);
-                }
-            }
-            results.push(sample);
-        }
-        Ok(results)
+        Ok(bulk_buf
+            .chunks_exact(buf_len)
+            .map(|frame_buf| self.decode_sample(frame_buf))
+            .collect())
     }
 
-    /// Read a single sample by index, returning a HashMap of variable name -> VarValue
-    pub fn read_sample(&self, index: usize) -> Result<HashMap<String, VarValue>> {
+    /// Read a single sample by index
+    pub fn read_sample(&self, index: usize) -> Result<Sample> {
+        let mut buf = Vec::new();
+        self.read_raw_sample(index, &mut buf)?;
+        Ok(self.decode_sample(&buf))
+    }
+
+    /// Read sample `index` undecoded into `buf`, resized to
+    /// [`sample_len`](Self::sample_len), for [`sample_to_frame`](Self::sample_to_frame)
+    pub fn read_raw_sample(&self, index: usize, buf: &mut Vec<u8>) -> Result<()> {
         let record_count = self.record_count();
         if index >= record_count {
             bail!("Sample index {} out of range (0..{})", index, record_count);
         }
 
-        let buf_len = self.header.buf_len as u64;
-        let offset = self.sample_data_offset + (index as u64) * buf_len;
-
-        let mut sample_buf = vec![0u8; buf_len as usize];
-        self.read_at(&mut sample_buf, offset)?;
-
-        let mut result = HashMap::with_capacity(self.var_headers.len());
+        let buf_len = self.header.buf_len as usize;
+        let offset = self.sample_data_offset + (index as u64) * (buf_len as u64);
 
-        for vh in &self.var_headers {
-            let var_offset = vh.offset as usize;
-            let count = vh.count as usize;
+        buf.resize(buf_len, 0);
+        self.read_at(buf, offset)
+    }
 
-            let end = var_offset + count * vh.var_type.element_size();
-            if end > sample_buf.len() {
-                continue;
-            }
+    /// Read sample `index` as a frame
+    pub fn read_frame(&self, index: usize) -> Result<TelemetryFrame> {
+        let mut buf = Vec::new();
+        self.read_raw_sample(index, &mut buf)?;
+        Ok(self.sample_to_frame(&buf))
+    }
 
-            let value = if count == 1 {
-                read_scalar_value(&sample_buf, var_offset, vh.var_type)
-            } else {
-                read_array_value(&sample_buf, var_offset, vh.var_type, count)
-            };
+    /// Read a contiguous range of samples as frames, in a single disk
+    /// operation like [`read_samples_range`](Self::read_samples_range)
+    pub fn read_frames_range(&self, start: usize, count: usize) -> Result<Vec<TelemetryFrame>> {
+        let record_count = self.record_count();
+        if start >= record_count {
+            bail!("Start index {} out of range (0..{})", start, record_count);
+        }
+        Ok(self
+            .read_raw_samples(start, count)?
+            .chunks_exact(self.sample_len())
+            .map(|sample| self.sample_to_frame(sample))
+            .collect())
+    }
 
-            if let Some(val) = value {
-                result.insert(vh.name.clone(), val);
+    /// Decode the sample in `buf`
+    fn decode_sample(&self, buf: &[u8]) -> Sample {
+        let mut sample = Sample::with_capacity(self.var_headers.len());
+        for (vh, name) in self.var_headers.iter().zip(&self.var_names) {
+            if let Some(val) = read_var(buf, vh) {
+                sample.insert(name.clone(), val);
             }
         }
-
-        Ok(result)
+        sample
     }
 
     /// Convert a VarValue to a serde_json::Value for extras.
-    fn var_value_to_json(value: &VarValue) -> serde_json::Value {
+    pub(crate) fn var_value_to_json(value: &VarValue) -> serde_json::Value {
         match value {
             VarValue::Char(c) => serde_json::json!(*c),
             VarValue::Bool(b) => serde_json::json!(*b),
@@ -712,93 +1420,40 @@ impl IbtFile {
         }
     }
 
-    /// Convert a raw sample HashMap to a TelemetryFrame.
+    /// Convert a raw sample buffer, as read by [`read_raw_sample`](Self::read_raw_sample),
+    /// to a TelemetryFrame. Variables are read where the file's plan puts
+    /// them rather than decoded first.
     /// Mirrors the conversion logic from IRacingAdapter::convert_sample(),
     /// producing the nested sub-struct model.
-    pub fn sample_to_frame(&self, sample: &HashMap<String, VarValue>) -> TelemetryFrame {
-        let get_f32 = |name: &str| -> Option<f32> { sample.get(name).and_then(|v| v.as_f32()) };
-        let get_f64 = |name: &str| -> Option<f64> { sample.get(name).and_then(|v| v.as_f64()) };
-        let get_i32 = |name: &str| -> Option<i32> { sample.get(name).and_then(|v| v.as_i32()) };
-        let get_u32 = |name: &str| -> Option<u32> { sample.get(name).and_then(|v| v.as_u32()) };
-        let get_bool = |name: &str| -> Option<bool> { sample.get(name).and_then(|v| v.as_bool()) };
+    pub fn sample_to_frame(&self, sample: &[u8]) -> TelemetryFrame {
+        let var = |name: &str| self.plan.vars.get(name)?.read(sample);
+        let get_f32 = |name: &str| -> Option<f32> { var(name)?.as_f32() };
+        let get_f64 = |name: &str| -> Option<f64> { var(name)?.as_f64() };
+        let get_i32 = |name: &str| -> Option<i32> { var(name)?.as_i32() };
+        let get_u32 = |name: &str| -> Option<u32> { var(name)?.as_u32() };
+        let get_bool = |name: &str| -> Option<bool> { var(name)?.as_bool() };
+        // In the model's unit, whatever the file's is
+        let get_in = |name: &str, quantity: Quantity| {
+            let slot = self.plan.vars.get(name)?;
+            let fuel_density = self.session_info.fuel_kg_per_liter;
+            slot.unit
+                .convert(slot.read(sample)?.as_f32()?, quantity, fuel_density)
+        };
 
         let tick = get_i32("SessionTick").map(|t| t as u32);
 
         // =================================================================
         // Motion
         // =================================================================
-        let velocity = match (
-            get_f32("VelocityX"),
-            get_f32("VelocityY"),
-            get_f32("VelocityZ"),
-        ) {
-            (Some(vx), Some(vy), Some(vz)) => Some(Vector3::new(
-                MetersPerSecond(vx),
-                MetersPerSecond(vy),
-                MetersPerSecond(vz),
-            )),
-            _ => None,
-        };
-
-        let acceleration = match (
-            get_f32("LatAccel"),
-            get_f32("LongAccel"),
-            get_f32("VertAccel"),
-        ) {
-            (Some(lat), Some(long), Some(vert)) => Some(Vector3::new(
-                MetersPerSecondSquared(lat),
-                MetersPerSecondSquared(vert),
-                MetersPerSecondSquared(long),
-            )),
-            _ => None,
-        };
-
-        let g_force = acceleration.as_ref().map(|a| {
-            Vector3::new(
-                GForce::from_acceleration(a.x),
-                GForce::from_acceleration(a.y),
-                GForce::from_acceleration(a.z),
-            )
-        });
-
-        let rotation = match (get_f32("Pitch"), get_f32("Yaw"), get_f32("Roll")) {
-            (Some(p), Some(y), Some(r)) => Some(Vector3::new(
-                Degrees::from_radians(p),
-                Degrees::from_radians(y),
-                Degrees::from_radians(r),
-            )),
-            _ => None,
-        };
-
-        // YawNorth: yaw relative to geographic north (radians, CCW positive)
-        // Convert to compass heading (degrees, CW from north)
-        let heading = get_f32("YawNorth").map(|yn| {
-            let deg = -yn * (180.0 / std::f32::consts::PI);
-            Degrees(deg.rem_euclid(360.0))
-        });
-
-        let motion = Some(MotionData {
-            position: None,
-            velocity,
-            acceleration,
-            g_force,
-            rotation,
-            pitch_rate: get_f32("PitchRate").map(DegreesPerSecond::from_radians),
-            yaw_rate: get_f32("YawRate").map(DegreesPerSecond::from_radians),
-            roll_rate: get_f32("RollRate").map(DegreesPerSecond::from_radians),
-            angular_acceleration: None,
-            latitude: get_f64("Lat"),
-            longitude: get_f64("Lon"),
-            altitude: get_f32("Alt").map(Meters),
-            heading,
-        });
+        let motion = Some(crate::iracing::iracing_motion(get_f32, get_f64));
 
         // =================================================================
         // Vehicle
         // =================================================================
         let speed = get_f32("Speed").map(MetersPerSecond).or_else(|| {
-            velocity
+            motion
                 .as_ref()
+                .and_then(|m| m.velocity.as_ref())
                 .map(|v| MetersPerSecond((v.x.0.powi(2) + v.y.0.powi(2) + v.z.0.powi(2)).sqrt()))
         });
 
@@ -815,7 +1470,8 @@ impl IbtFile {
             throttle: get_f32("Throttle").map(Percentage::new),
             brake: get_f32("Brake").map(Percentage::new),
             clutch: get_f32("Clutch").map(Percentage::new),
-            steering_angle: get_f32("SteeringWheelAngle").map(Degrees::from_radians),
+            steering_angle: get_f32("SteeringWheelAngle")
+                .map(crate::iracing::iracing_steering_angle),
             steering_torque: get_f32("SteeringWheelTorque").map(NewtonMeters),
             steering_torque_pct: get_f32("SteeringWheelPctTorque").map(Percentage::new),
             handbrake: get_f32("HandbrakeRaw").map(Percentage::new),
@@ -832,32 +1488,16 @@ impl IbtFile {
         // =================================================================
         // Engine
         // =================================================================
-        let engine_warnings = get_u32("EngineWarnings").map(EngineWarnings::from_iracing_bits);
-
-        let engine = Some(EngineData {
-            water_temp: get_f32("WaterTemp").map(Celsius),
-            oil_temp: get_f32("OilTemp").map(Celsius),
-            oil_pressure: get_f32("OilPress").map(Kilopascals),
-            oil_level: get_f32("OilLevel").map(Percentage::new),
-            fuel_level: get_f32("FuelLevel").map(Liters),
-            fuel_level_pct: get_f32("FuelLevelPct").map(Percentage::new),
-            fuel_capacity: None,
-            fuel_pressure: get_f32("FuelPress").map(Kilopascals),
-            fuel_use_per_hour: get_f32("FuelUsePerHour").map(LitersPerHour),
-            voltage: get_f32("Voltage").map(Volts),
-            manifold_pressure: get_f32("ManifoldPress").map(Bar),
-            water_level: get_f32("WaterLevel").map(Liters),
-            warnings: engine_warnings,
-        });
+        let engine = Some(crate::iracing::iracing_engine(get_f32, get_u32, get_in));
 
         // =================================================================
         // Wheels
         // =================================================================
         let wheels = Some(WheelData {
-            front_left: self.extract_wheel(sample, "LF", true),
-            front_right: self.extract_wheel(sample, "RF", false),
-            rear_left: self.extract_wheel(sample, "LR", true),
-            rear_right: self.extract_wheel(sample, "RR", false),
+            front_left: self.extract_wheel(sample, &self.plan.wheels[0], true),
+            front_right: self.extract_wheel(sample, &self.plan.wheels[1], false),
+            rear_left: self.extract_wheel(sample, &self.plan.wheels[2], true),
+            rear_right: self.extract_wheel(sample, &self.plan.wheels[3], false),
         });
 
         // =================================================================
@@ -872,7 +1512,7 @@ impl IbtFile {
             sector_times: None,
             lap_number: get_i32("Lap").map(|l| l as u32),
             laps_completed: get_i32("LapCompleted").map(|l| l as u32),
-            lap_distance: get_f32("LapDist").map(Meters),
+            lap_distance: get_in("LapDist", Quantity::Length).map(Meters),
             lap_distance_pct: get_f32("LapDistPct").map(Percentage::new),
             race_position: get_i32("PlayerCarPosition").map(|p| p as u32),
             class_position: get_i32("PlayerCarClassPosition").map(|p| p as u32),
@@ -885,6 +1525,8 @@ impl IbtFile {
             delta_optimal_ok: get_bool("LapDeltaToOptimalLap_OK"),
             estimated_lap_time: None,
             race_laps: None,
+            lap_valid: None,
+            corner_name: None,
         });
 
         // =================================================================
@@ -892,7 +1534,7 @@ impl IbtFile {
         // =================================================================
         let session_state = get_i32("SessionState").map(SessionState::from_iracing);
         let flags = get_u32("SessionFlags").map(FlagState::from_iracing_bits);
-        let session_type = self.parse_session_type();
+        let session_type = self.parse_session_type(get_i32("SessionNum"));
 
         let track_length = self
             .session_info
@@ -918,16 +1560,24 @@ impl IbtFile {
                 .filter(|s| !s.is_empty()),
             track_length,
             track_type: None,
+            track_id: Some(&self.session_info.track_name)
+                .filter(|s| !s.is_empty())
+                .map(|s| format!("iracing:{}", s)),
+            sector_starts: Some(&self.session_info.sector_starts)
+                .filter(|s| !s.is_empty())
+                .map(|s| s.iter().map(|&p| Percentage::new(p)).collect()),
+            pit_entry_pct: None,
+            pit_exit_pct: None,
         });
 
         // =================================================================
         // Weather
         // =================================================================
         let weather = Some(WeatherData {
-            air_temp: get_f32("AirTemp").map(Celsius),
-            track_temp: get_f32("TrackTempCrew").map(Celsius),
-            track_surface_temp: get_f32("TrackTemp").map(Celsius),
-            air_pressure: get_f32("AirPressure").map(|v| Kilopascals(v / 1000.0)),
+            air_temp: get_in("AirTemp", Quantity::Temperature).map(Celsius),
+            track_temp: get_in("TrackTempCrew", Quantity::Temperature).map(Celsius),
+            track_surface_temp: get_in("TrackTemp", Quantity::Temperature).map(Celsius),
+            air_pressure: get_in("AirPressure", Quantity::Pressure).map(Kilopascals),
             air_density: get_f32("AirDensity").map(KilogramsPerCubicMeter),
             humidity: get_f32("RelativeHumidity").map(|h| Percentage::new(h / 100.0)),
             wind_speed: get_f32("WindVel").map(MetersPerSecond),
@@ -948,19 +1598,7 @@ impl IbtFile {
         // =================================================================
         // Pit
         // =================================================================
-        let requested_services = Some(PitServices {
-            fuel_to_add: get_f32("dpFuelFill").map(Liters),
-            change_tyre_fl: get_f32("dpLFTireChange").is_some_and(|v| v > 0.0),
-            change_tyre_fr: get_f32("dpRFTireChange").is_some_and(|v| v > 0.0),
-            change_tyre_rl: get_f32("dpLRTireChange").is_some_and(|v| v > 0.0),
-            change_tyre_rr: get_f32("dpRRTireChange").is_some_and(|v| v > 0.0),
-            windshield_tearoff: get_f32("dpWindshieldTearoff").is_some_and(|v| v > 0.0),
-            fast_repair: get_f32("dpFastRepair").is_some_and(|v| v > 0.0),
-            tyre_pressure_fl: get_f32("dpLFTireColdPress").map(Kilopascals),
-            tyre_pressure_fr: get_f32("dpRFTireColdPress").map(Kilopascals),
-            tyre_pressure_rl: get_f32("dpLRTireColdPress").map(Kilopascals),
-            tyre_pressure_rr: get_f32("dpRRTireColdPress").map(Kilopascals),
-        });
+        let requested_services = Some(crate::iracing::iracing_requested_services(get_f32, get_in));
 
         let pit = Some(PitData {
             on_pit_road: get_bool("OnPitRoad"),
@@ -1000,12 +1638,14 @@ impl IbtFile {
         // =================================================================
         let mut iracing_data = serde_json::Map::new();
 
-        for (name, value) in sample {
+        for (vh, name) in self.var_headers.iter().zip(&self.var_names) {
             // Skip CarIdx arrays (large per-car arrays, already in competitors)
             if name.starts_with("CarIdx") {
                 continue;
             }
-            iracing_data.insert(name.clone(), Self::var_value_to_json(value));
+            if let Some(value) = read_var(sample, vh) {
+                iracing_data.insert(name.to_string(), Self::var_value_to_json(&value));
+            }
         }
 
         let mut extras = HashMap::new();
@@ -1014,11 +1654,30 @@ impl IbtFile {
             serde_json::Value::Object(iracing_data),
         );
 
+        // SessionTick counts at the file's tick rate from session start
+        let sim_time = get_f64("SessionTime")
+            .or_else(|| tick.map(|t| t as f64 / self.tick_rate().max(1) as f64));
+        let timestamp = sim_time
+            .and_then(|t| {
+                sim_timestamp(
+                    self.disk_sub_header.session_start_date,
+                    self.disk_sub_header.session_start_time,
+                    t,
+                )
+            })
+            .unwrap_or_else(Utc::now);
+
         TelemetryFrame {
             meta: MetaData {
-                timestamp: Utc::now(),
+                timestamp,
                 game: "iRacing Replay".to_string(),
                 tick,
+                sim_time,
+                invalid_fields: None,
+                seq: None,
+                acquired_at: None,
+                broadcast_at: None,
+                adapter: None,
             },
             motion,
             vehicle,
@@ -1030,6 +1689,7 @@ impl IbtFile {
             pit,
             electronics,
             damage: None,
+            energy: crate::iracing::iracing_energy(get_f32),
             competitors: None,
             driver: if !self.session_info.driver_name.is_empty() {
                 Some(DriverData {
@@ -1042,82 +1702,39 @@ impl IbtFile {
             } else {
                 None
             },
+            relative: None,
+            predictions: None,
             extras,
         }
     }
 
     /// Extract per-wheel data.
-    /// `prefix` is "LF", "RF", "LR", or "RR".
+    /// `vars` are the wheel's from [`FramePlan::wheels`].
     /// `is_left_side` determines inner/outer mapping for temperatures.
     fn extract_wheel(
         &self,
-        sample: &HashMap<String, VarValue>,
-        prefix: &str,
+        sample: &[u8],
+        vars: &HashMap<Box<str>, Slot>,
         is_left_side: bool,
     ) -> WheelInfo {
-        let get_f32 = |suffix: &str| -> Option<f32> {
-            let key = format!("{}{}", prefix, suffix);
-            sample.get(&key).and_then(|v| v.as_f32())
-        };
-
-        // Inner/outer mapping: for left wheels, CL=outer edge, CR=inner edge.
-        // For right wheels, CL=inner edge, CR=outer edge.
-        let (surface_temp_inner, surface_temp_outer) = if is_left_side {
-            (
-                get_f32("tempCR").map(Celsius),
-                get_f32("tempCL").map(Celsius),
-            )
-        } else {
-            (
-                get_f32("tempCL").map(Celsius),
-                get_f32("tempCR").map(Celsius),
-            )
+        let get_f32 = |suffix: &str| -> Option<f32> { vars.get(suffix)?.read(sample)?.as_f32() };
+        let get_in = |suffix: &str, quantity: Quantity| {
+            let slot = vars.get(suffix)?;
+            let fuel_density = self.session_info.fuel_kg_per_liter;
+            slot.unit
+                .convert(slot.read(sample)?.as_f32()?, quantity, fuel_density)
         };
-
-        let (carcass_temp_inner, carcass_temp_outer) = if is_left_side {
-            (get_f32("tempR").map(Celsius), get_f32("tempL").map(Celsius))
-        } else {
-            (get_f32("tempL").map(Celsius), get_f32("tempR").map(Celsius))
-        };
-
-        WheelInfo {
-            suspension_travel: get_f32("shockDefl").map(|v| Millimeters(v * 1000.0)),
-            suspension_travel_avg: None, // shockDefl_ST is an array in iRacing, not a scalar
-            shock_velocity: get_f32("shockVel").map(|v| MillimetersPerSecond(v * 1000.0)),
-            shock_velocity_avg: None, // shockVel_ST is an array in iRacing, not a scalar
-            ride_height: get_f32("rideHeight").map(|v| Millimeters(v * 1000.0)),
-            tyre_pressure: get_f32("pressure").map(Kilopascals),
-            tyre_cold_pressure: get_f32("coldPressure").map(Kilopascals),
-            surface_temp_inner,
-            surface_temp_middle: get_f32("tempCM").map(Celsius),
-            surface_temp_outer,
-            carcass_temp_inner,
-            carcass_temp_middle: get_f32("tempM").map(Celsius),
-            carcass_temp_outer,
-            tyre_wear: None, // iRacing only has per-zone wearL/M/R, no overall wear
-            tyre_wear_inner: if is_left_side {
-                get_f32("wearR").map(Percentage::new)
-            } else {
-                get_f32("wearL").map(Percentage::new)
-            },
-            tyre_wear_middle: get_f32("wearM").map(Percentage::new),
-            tyre_wear_outer: if is_left_side {
-                get_f32("wearL").map(Percentage::new)
-            } else {
-                get_f32("wearR").map(Percentage::new)
-            },
-            wheel_speed: get_f32("speed").map(Rpm::from_radians_per_sec),
-            slip_ratio: None,
-            slip_angle: None,
-            load: None,
-            brake_line_pressure: get_f32("brakeLinePress").map(Kilopascals),
-            brake_temp: None,
-            tyre_compound: None,
-        }
+        crate::iracing::iracing_wheel(get_f32, get_in, is_left_side)
     }
 
-    fn parse_session_type(&self) -> Option<SessionType> {
-        let st = self.session_info.session_type.to_lowercase();
+    /// Type of session `session_num`, or of the first session if the file
+    /// doesn't say which it is in
+    fn parse_session_type(&self, session_num: Option<i32>) -> Option<SessionType> {
+        let info = &self.session_info;
+        let st = session_num
+            .and_then(|num| info.session_types.get(&num))
+            .unwrap_or(&info.session_type)
+            .to_lowercase();
         if st.contains("race") {
             Some(SessionType::Race)
         } else if st.contains("qualify") || st.contains("qual") {
@@ -1142,6 +1759,17 @@ impl IbtFile {
 // Binary reading helpers
 // ============================================================================
 
+/// Wall-clock time of a sample: the recording started at `start_date` (unix
+/// seconds) when the sim clock read `start_time`. `None` if the file has no
+/// start date.
+fn sim_timestamp(start_date: i64, start_time: f64, sim_time: f64) -> Option<DateTime<Utc>> {
+    if start_date <= 0 {
+        return None;
+    }
+    let offset = chrono::Duration::microseconds(((sim_time - start_time) * 1e6) as i64);
+    DateTime::from_timestamp(start_date, 0).map(|start| start + offset)
+}
+
 fn read_null_terminated_string(buf: &[u8]) -> String {
     let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
     String::from_utf8_lossy(&buf[..end]).to_string()
@@ -1202,56 +1830,58 @@ fn read_scalar_value(buf: &[u8], offset: usize, var_type: VarType) -> Option<Var
     }
 }
 
+/// Read variable `vh` from a sample buffer
+pub(crate) fn read_var(buf: &[u8], vh: &VarHeader) -> Option<VarValue> {
+    let var_offset = vh.offset as usize;
+    let count = vh.count as usize;
+    if var_offset + count * vh.var_type.element_size() > buf.len() {
+        return None;
+    }
+    if count == 1 {
+        read_scalar_value(buf, var_offset, vh.var_type)
+    } else {
+        read_array_value(buf, var_offset, vh.var_type, count)
+    }
+}
+
+/// Read an array variable
 fn read_array_value(
     buf: &[u8],
     offset: usize,
     var_type: VarType,
     count: usize,
 ) -> Option<VarValue> {
-    match var_type {
-        VarType::Char | VarType::Bool => {
-            if offset + count <= buf.len() {
-                Some(VarValue::CharArray(buf[offset..offset + count].to_vec()))
-            } else {
-                None
-            }
-        }
-        VarType::Int | VarType::BitField => {
-            let mut vals = Vec::with_capacity(count);
-            for i in 0..count {
-                let off = offset + i * 4;
-                if off + 4 <= buf.len() {
-                    vals.push(i32::from_le_bytes(buf[off..off + 4].try_into().ok()?));
-                }
-            }
-            Some(VarValue::IntArray(vals))
-        }
-        VarType::Float => {
-            let mut vals = Vec::with_capacity(count);
-            for i in 0..count {
-                let off = offset + i * 4;
-                if off + 4 <= buf.len() {
-                    vals.push(f32::from_le_bytes(buf[off..off + 4].try_into().ok()?));
-                }
-            }
-            Some(VarValue::FloatArray(vals))
-        }
-        VarType::Double => {
-            let mut vals = Vec::with_capacity(count);
-            for i in 0..count {
-                let off = offset + i * 8;
-                if off + 8 <= buf.len() {
-                    vals.push(f64::from_le_bytes(buf[off..off + 8].try_into().ok()?));
-                }
-            }
-            Some(VarValue::DoubleArray(vals))
-        }
-    }
+    let bytes = buf.get(offset..).unwrap_or_default();
+    Some(match var_type {
+        VarType::Char | VarType::Bool => VarValue::CharArray(bytes.get(..count)?.to_vec()),
+        VarType::Int | VarType::BitField => VarValue::IntArray(
+            bytes
+                .chunks_exact(4)
+                .take(count)
+                .map(|b| i32::from_le_bytes(b.try_into().unwrap()))
+                .collect(),
+        ),
+        VarType::Float => VarValue::FloatArray(
+            bytes
+                .chunks_exact(4)
+                .take(count)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                .collect(),
+        ),
+        VarType::Double => VarValue::DoubleArray(
+            bytes
+                .chunks_exact(8)
+                .take(count)
+                .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+                .collect(),
+        ),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ost_core::adapter::Capabilities;
 
     #[test]
     fn test_var_type_from_i32() {
@@ -1273,6 +1903,15 @@ mod tests {
         assert_eq!(VarValue::BitField(0xFF).as_u32(), Some(0xFF));
     }
 
+    #[test]
+    fn test_sim_timestamp() {
+        // Recording started at 2024-01-01T12:00:00Z, 100s into the session
+        let start = 1_704_110_400;
+        let ts = sim_timestamp(start, 100.0, 161.5).unwrap();
+        assert_eq!(ts.to_rfc3339(), "2024-01-01T12:01:01.500+00:00");
+        assert!(sim_timestamp(0, 100.0, 161.5).is_none());
+    }
+
     #[test]
     fn test_read_null_terminated_string() {
         let buf = b"hello\0\0\0\0\0";
@@ -1300,6 +1939,14 @@ SessionInfo:
  Sessions:
  - SessionNum: 0
    SessionType: Lone Qualify
+ - SessionNum: 1
+   SessionType: Race
+SplitTimeInfo:
+ Sectors:
+ - SectorNum: 0
+   SectorStartPct: 0.000000
+ - SectorNum: 1
+   SectorStartPct: 0.412500
 "#;
         let info = IbtSessionInfo::from_yaml(yaml).unwrap();
         assert_eq!(info.track_name, "spielberg gp");
@@ -1308,6 +1955,38 @@ SessionInfo:
         assert_eq!(info.driver_name, "Test Driver");
         assert_eq!(info.car_screen_name, "Formula Test");
         assert_eq!(info.session_type, "Lone Qualify");
+        assert_eq!(info.session_types[&1], "Race");
+        assert_eq!(info.sector_starts, [0.0, 0.4125]);
+    }
+
+    #[test]
+    fn test_split_sessions() {
+        let info = IbtSessionInfo {
+            session_types: BTreeMap::from([(0, "Practice".into()), (2, "Race".into())]),
+            ..Default::default()
+        };
+        let nums = [None, Some(0), Some(0), None, Some(1), Some(2), Some(2)];
+        let sessions = split_sessions(nums, &info);
+        let summary: Vec<_> = sessions
+            .iter()
+            .map(|s| {
+                (
+                    s.session_num,
+                    s.session_type.as_deref(),
+                    s.start_frame,
+                    s.end_frame,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (0, Some("Practice"), 0, 3),
+                (1, None, 4, 4),
+                (2, Some("Race"), 5, 6),
+            ]
+        );
+        assert!(split_sessions([None, None], &info).is_empty());
     }
 
     #[test]
@@ -1339,6 +2018,131 @@ SessionInfo:
         }
     }
 
+    #[test]
+    fn test_timeline_gaps_and_rate() {
+        // Logged at 30 Hz of 60, paused for 10s after the third sample, with
+        // one sample missing its tick
+        let ticks = [
+            Some(100),
+            Some(102),
+            Some(104),
+            Some(704),
+            None,
+            Some(708),
+            Some(710),
+        ];
+        let timeline = Timeline::from_ticks(60, &ticks);
+        assert_eq!(timeline.sample_rate(), 30.0);
+        assert_eq!(
+            timeline.gaps(),
+            [TickGap {
+                frame: 3,
+                secs: 9.0 + 29.0 / 30.0
+            }]
+        );
+        assert!((timeline.time_of(2) - 2.0 / 30.0).abs() < 1e-9);
+        assert!((timeline.time_of(3) - (10.0 + 2.0 / 30.0)).abs() < 1e-9);
+        assert!((timeline.time_of(6) - (10.0 + 5.0 / 30.0)).abs() < 1e-9);
+        assert!((timeline.duration_secs() - (10.0 + 6.0 / 30.0)).abs() < 1e-9);
+
+        // Nearest frame, holding the last one before the gap until it ends
+        assert_eq!(timeline.frame_at(-1.0), 0);
+        assert_eq!(timeline.frame_at(1.0 / 30.0 + 0.01), 1);
+        assert_eq!(timeline.frame_at(5.0), 2);
+        assert_eq!(timeline.frame_at(10.0), 2);
+        assert_eq!(timeline.frame_at(10.0 + 2.0 / 30.0), 3);
+        assert_eq!(timeline.frame_at(60.0), 6);
+
+        // A new session's ticks start over without a gap
+        let timeline = Timeline::from_ticks(60, &[Some(500), Some(501), Some(0), Some(1)]);
+        assert!(timeline.gaps().is_empty());
+        assert_eq!(timeline, Timeline::contiguous(60, 4));
+    }
+
+    #[test]
+    fn test_lap_scan_in_and_out_laps() {
+        let sample = |lap, on_pit_road, off_track| LapSample {
+            lap,
+            session_time: None,
+            on_pit_road,
+            off_track,
+        };
+        let mut scan = LapScan::default();
+        let frames = [
+            // Leaving the pits
+            sample(1, true, false),
+            sample(1, false, false),
+            // Clean lap
+            sample(2, false, false),
+            sample(2, false, false),
+            // Off track
+            sample(3, false, true),
+            sample(3, false, false),
+            // Clean, then into the pits
+            sample(4, false, false),
+            sample(4, true, false),
+            // Out again, file ends mid-lap
+            sample(5, true, false),
+            sample(5, false, false),
+        ];
+        for (i, frame) in frames.into_iter().enumerate() {
+            scan.add(i, frame);
+        }
+        let laps = scan.finish(frames.len());
+        let summary: Vec<_> = laps
+            .iter()
+            .map(|l| (l.lap_number, l.end_frame, l.valid, l.out_lap, l.in_lap))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1, 1, false, true, false),
+                (2, 3, true, false, false),
+                (3, 5, false, false, false),
+                (4, 7, false, false, true),
+                (5, 9, false, true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lap_index_bathurst_fixture() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt");
+        let ibt = IbtFile::open(&path).expect("Failed to open .ibt file");
+        let laps = ibt.build_lap_index().unwrap();
+
+        // Four laps, then a reset to the pits (with a one-frame blip to lap 0)
+        let numbers: Vec<_> = laps.iter().map(|l| l.lap_number).collect();
+        assert_eq!(numbers, [0, 1, 2, 3, 4, 0, 4]);
+        assert_eq!(laps[0].start_frame, 0);
+        for pair in laps.windows(2) {
+            assert_eq!(pair[0].end_frame + 1, pair[1].start_frame);
+        }
+        assert_eq!(laps.last().unwrap().end_frame, ibt.record_count() - 1);
+
+        // Laps 2 and 4 went off track; lap 0 started mid-lap
+        let valid: Vec<_> = laps.iter().map(|l| l.valid).collect();
+        assert_eq!(valid, [false, true, false, true, false, false, false]);
+        for lap in &laps[1..=3] {
+            let time = lap.lap_time_secs.expect("complete lap has a time");
+            assert!(
+                time > 120.0 && time < 140.0,
+                "lap {} took {}",
+                lap.lap_number,
+                time
+            );
+        }
+        assert_eq!(laps.last().unwrap().lap_time_secs, None);
+
+        // Only the reset lands on pit road
+        let last = laps.last().unwrap();
+        assert!(last.out_lap && last.in_lap);
+        assert!(laps[..laps.len() - 1]
+            .iter()
+            .all(|l| !l.out_lap && !l.in_lap));
+    }
+
     // ========================================================================
     // Integration test: load the real fixtures/race.ibt file
     // ========================================================================
@@ -1352,6 +2156,331 @@ SessionInfo:
         fixture_path().exists()
     }
 
+    #[test]
+    fn test_session_index_bathurst_fixture() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt");
+        let ibt = IbtFile::open(&path).expect("Failed to open .ibt file");
+
+        // Qualifying was skipped: the whole file is the race
+        assert_eq!(
+            ibt.build_session_index().unwrap(),
+            [IbtSession {
+                session_num: 1,
+                session_type: Some("Race".into()),
+                start_frame: 0,
+                end_frame: ibt.record_count() - 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_capabilities_cover_fixture() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt");
+        let ibt = IbtFile::open(&path).expect("Failed to open .ibt file");
+        let caps = Capabilities::from_patterns(crate::iracing::IRACING_CAPABILITIES).unwrap();
+        for index in (0..ibt.record_count()).step_by(500) {
+            let undeclared = caps.undeclared(&ibt.read_frame(index).unwrap());
+            assert!(undeclared.is_empty(), "{:?} in frame {}", undeclared, index);
+        }
+    }
+
+    #[test]
+    fn test_fixture_turns_are_canonical() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt");
+        let ibt = IbtFile::open(&path).expect("Failed to open .ibt file");
+        // Frames cornering at speed, by direction, and those whose yaw rate
+        // and steering agree with the lateral G
+        let (mut right_turns, mut left_turns) = (0, 0);
+        let (mut yaw_agrees, mut steering_agrees) = (0, 0);
+        for index in (0..ibt.record_count()).step_by(10) {
+            let frame = ibt.read_frame(index).unwrap();
+            let (Some(motion), Some(vehicle)) = (frame.motion, frame.vehicle) else {
+                continue;
+            };
+            let lat_g = motion.g_force.unwrap().x.0;
+            if vehicle.speed.unwrap().0 < 20.0 || lat_g.abs() < 1.0 {
+                continue;
+            }
+            if lat_g > 0.0 {
+                right_turns += 1;
+            } else {
+                left_turns += 1;
+            }
+            yaw_agrees += (motion.yaw_rate.unwrap().0.signum() == lat_g.signum()) as usize;
+            steering_agrees +=
+                (vehicle.steering_angle.unwrap().0.signum() == lat_g.signum()) as usize;
+        }
+        let turns = right_turns + left_turns;
+        assert!(right_turns > 0 && left_turns > 0);
+        // Slides and kerbs account for the odd frame where they don't
+        assert!(yaw_agrees * 20 > turns * 19, "{} of {}", yaw_agrees, turns);
+        assert!(
+            steering_agrees * 20 > turns * 19,
+            "{} of {}",
+            steering_agrees,
+            turns
+        );
+    }
+
+    #[test]
+    fn test_frame_plan() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt");
+        let ibt = IbtFile::open(&path).expect("Failed to open .ibt file");
+
+        let speed = ibt.var_header("Speed").unwrap();
+        assert_eq!(ibt.plan.vars["Speed"].offset, speed.offset as usize);
+        let pressure = ibt.var_header("RRpressure").unwrap();
+        assert_eq!(
+            ibt.plan.wheels[3]["pressure"].offset,
+            pressure.offset as usize
+        );
+        assert!(matches!(
+            ibt.plan.wheels[3]["pressure"].unit,
+            SlotUnit::Known(UnitConversion {
+                quantity: Quantity::Pressure,
+                ..
+            })
+        ));
+        // Arrays aren't read as scalars
+        assert!(!ibt.plan.vars.contains_key("SteeringWheelTorque_ST"));
+
+        // Frames read in bulk match ones read alone
+        let frames = ibt.read_frames_range(9000, 30).unwrap();
+        assert_eq!(frames.len(), 30);
+        for (i, frame) in frames.iter().enumerate() {
+            assert_eq!(
+                serde_json::to_value(frame).unwrap(),
+                serde_json::to_value(ibt.read_frame(9000 + i).unwrap()).unwrap()
+            );
+        }
+        let frame = &frames[0];
+        let sample = ibt.read_sample(9000).unwrap();
+        assert_eq!(
+            frame.vehicle.as_ref().unwrap().speed.unwrap().0,
+            sample["Speed"].as_f32().unwrap()
+        );
+        assert_eq!(
+            frame.extras["iracing"]["SteeringWheelTorque_ST"]
+                .as_array()
+                .map(Vec::len),
+            Some(6)
+        );
+    }
+
+    #[test]
+    fn test_unit_table() {
+        let convert = |unit: &str, value: f32| {
+            let unit = UnitConversion::parse(unit).unwrap();
+            (unit.quantity, unit.apply(value))
+        };
+        let (quantity, kpa) = convert("Pa", 101_325.0);
+        assert_eq!(quantity, Quantity::Pressure);
+        assert!((kpa - 101.325).abs() < 1e-4);
+        assert_eq!(convert("bar", 4.5), (Quantity::Pressure, 450.0));
+        let (quantity, kpa) = convert("psi", 26.0);
+        assert_eq!(quantity, Quantity::Pressure);
+        assert!((kpa - 179.26).abs() < 0.01);
+        assert_eq!(convert(" l ", 60.0), (Quantity::Volume, 60.0));
+        assert_eq!(convert("kg/h", 90.0), (Quantity::MassRate, 90.0));
+        let (quantity, celsius) = convert("F", 212.0);
+        assert_eq!(quantity, Quantity::Temperature);
+        assert!((celsius - 100.0).abs() < 1e-4);
+        assert!((convert("K", 300.0).1 - 26.85).abs() < 1e-4);
+        let (quantity, meters) = convert("mm", 55.0);
+        assert_eq!(quantity, Quantity::Length);
+        assert!((meters - 0.055).abs() < 1e-6);
+        for unit in ["", "%", "rad", "m/s", "irsdk_Flags", "l or kWh"] {
+            assert_eq!(UnitConversion::parse(unit), None, "{unit}");
+        }
+    }
+
+    #[test]
+    fn test_frame_values_converted_by_unit() {
+        let vars = [
+            VarHeader::new("OilPress", VarType::Float, 1, "bar", ""),
+            VarHeader::new("FuelUsePerHour", VarType::Float, 1, "kg/h", ""),
+            VarHeader::new("AirPressure", VarType::Float, 1, "Pa", ""),
+            VarHeader::new("ManifoldPress", VarType::Float, 1, "kPa", ""),
+            VarHeader::new("WaterTemp", VarType::Float, 1, "F", ""),
+            VarHeader::new("FuelLevel", VarType::Float, 1, "", ""),
+            // Not a volume, so left out rather than misread
+            VarHeader::new("WaterLevel", VarType::Float, 1, "%", ""),
+            VarHeader::new("LFpressure", VarType::Float, 1, "psi", ""),
+            VarHeader::new("LFrideHeight", VarType::Float, 1, "mm", ""),
+        ];
+        let values = [4.5, 60.0, 101_325.0, 150.0, 194.0, 40.0, 0.5, 26.0, 55.0];
+        let sample: Sample = vars
+            .iter()
+            .zip(values)
+            .map(|(vh, v)| (vh.name.as_str().into(), VarValue::Float(v)))
+            .collect();
+        let path = std::env::temp_dir().join(format!("ost-units-{}.ibt", std::process::id()));
+        let write = |yaml: &str| {
+            let file = File::create(&path).unwrap();
+            let mut writer =
+                crate::ibt_writer::IbtWriter::new(file, vars.clone(), 60, yaml, Utc::now())
+                    .unwrap();
+            writer.write_sample(&sample).unwrap();
+            writer.finish().unwrap();
+            let ibt = IbtFile::open(&path).unwrap();
+            ibt.read_frame(0).unwrap()
+        };
+
+        let frame = write("DriverInfo:\n DriverCarFuelKgPerLtr: 0.750\n");
+        let engine = frame.engine.unwrap();
+        assert_eq!(engine.oil_pressure, Some(Kilopascals(450.0)));
+        assert_eq!(engine.fuel_use_per_hour, Some(LitersPerHour(80.0)));
+        assert_eq!(engine.manifold_pressure, Some(Bar(1.5)));
+        assert!((engine.water_temp.unwrap().0 - 90.0).abs() < 1e-4);
+        assert_eq!(engine.fuel_level, Some(Liters(40.0)));
+        assert_eq!(engine.water_level, None);
+        let air = frame.weather.unwrap().air_pressure.unwrap();
+        assert!((air.0 - 101.325).abs() < 1e-4);
+        let wheel = frame.wheels.unwrap().front_left;
+        assert!((wheel.tyre_pressure.unwrap().0 - 179.26).abs() < 0.01);
+        assert!((wheel.ride_height.unwrap().0 - 55.0).abs() < 1e-4);
+
+        // Without the fuel's density, fuel by mass can't be given as volume
+        let frame = write("DriverInfo:\n DriverCarIdx: 0\n");
+        assert_eq!(frame.engine.unwrap().fuel_use_per_hour, None);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_live_units_match_ibt() {
+        // iRacing's own units for these
+        let vars = [
+            VarHeader::new("OilPress", VarType::Float, 1, "bar", ""),
+            VarHeader::new("FuelPress", VarType::Float, 1, "bar", ""),
+            VarHeader::new("FuelUsePerHour", VarType::Float, 1, "kg/h", ""),
+            VarHeader::new("dpLFTireColdPress", VarType::Float, 1, "Pa", ""),
+            VarHeader::new("dpFuelFill", VarType::Float, 1, "l", ""),
+            VarHeader::new("LFpressure", VarType::Float, 1, "kPa", ""),
+            VarHeader::new("LFbrakeLinePress", VarType::Float, 1, "bar", ""),
+            VarHeader::new("LFshockDefl", VarType::Float, 1, "m", ""),
+        ];
+        let values = [4.5, 3.0, 60.0, 170_000.0, 20.0, 180.0, 12.0, 0.05];
+        let sample: Sample = vars
+            .iter()
+            .zip(values)
+            .map(|(vh, v)| (vh.name.as_str().into(), VarValue::Float(v)))
+            .collect();
+        let path = std::env::temp_dir().join(format!("ost-live-units-{}.ibt", std::process::id()));
+        let yaml = "DriverInfo:\n DriverCarFuelKgPerLtr: 0.750\n";
+        let file = File::create(&path).unwrap();
+        let mut writer =
+            crate::ibt_writer::IbtWriter::new(file, vars.clone(), 60, yaml, Utc::now()).unwrap();
+        writer.write_sample(&sample).unwrap();
+        writer.finish().unwrap();
+        let frame = IbtFile::open(&path).unwrap().read_frame(0).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        // As the live adapter reads them: each variable's unit string from
+        // its `ValueDescription`, and the density from session info
+        let fuel_density = IbtSessionInfo::from_yaml(yaml).unwrap().fuel_kg_per_liter;
+        let live = |name: &str| {
+            let i = vars.iter().position(|vh| vh.name == name)?;
+            Some((SlotUnit::parse(&vars[i].unit), values[i]))
+        };
+        let get_f32 = |name: &str| live(name).map(|(_, v)| v);
+        let get_in = |name: &str, quantity: Quantity| {
+            let (unit, value) = live(name)?;
+            unit.convert(value, quantity, fuel_density)
+        };
+        fn json(value: &impl serde::Serialize) -> serde_json::Value {
+            serde_json::to_value(value).unwrap()
+        }
+        let engine = crate::iracing::iracing_engine(get_f32, |_| None, get_in);
+        assert_eq!(json(&engine), json(&frame.engine));
+        let services = crate::iracing::iracing_requested_services(get_f32, get_in);
+        let pit = frame.pit.unwrap();
+        assert_eq!(json(&services), json(&pit.requested_services.unwrap()));
+        let wheel = crate::iracing::iracing_wheel(
+            |suffix| get_f32(&format!("LF{suffix}")),
+            |suffix, quantity| get_in(&format!("LF{suffix}"), quantity),
+            true,
+        );
+        assert_eq!(json(&wheel), json(&frame.wheels.unwrap().front_left));
+
+        // Converted, not taken as they are
+        let engine = frame.engine.unwrap();
+        assert_eq!(engine.oil_pressure, Some(Kilopascals(450.0)));
+        assert_eq!(engine.fuel_pressure, Some(Kilopascals(300.0)));
+        assert_eq!(engine.fuel_use_per_hour, Some(LitersPerHour(80.0)));
+        assert!((services.tyre_pressure_fl.unwrap().0 - 170.0).abs() < 1e-3);
+        assert_eq!(wheel.brake_line_pressure, Some(Kilopascals(1200.0)));
+    }
+
+    #[test]
+    fn test_truncated_and_corrupt_files() {
+        let vars = [
+            VarHeader::new("Lap", VarType::Int, 1, "", "Laps started"),
+            VarHeader::new("Speed", VarType::Float, 1, "m/s", "GPS vehicle speed"),
+        ];
+        let mut writer = crate::ibt_writer::IbtWriter::new(
+            std::io::Cursor::new(Vec::new()),
+            vars,
+            60,
+            "WeekendInfo:\n TrackName: test\n",
+            Utc::now(),
+        )
+        .unwrap();
+        for i in 0..10 {
+            let sample = Sample::from([("Speed".into(), VarValue::Float(i as f32))]);
+            writer.write_sample(&sample).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+        let path = std::env::temp_dir().join(format!("ost-corrupt-{}.ibt", std::process::id()));
+        let open = |bytes: &[u8]| {
+            std::fs::write(&path, bytes).unwrap();
+            IbtFile::open(&path)
+        };
+
+        let ibt = open(&bytes).unwrap();
+        assert_eq!(ibt.record_count(), 10);
+        assert_eq!(ibt.recovery(), None);
+
+        // Cut off mid-way through the eighth sample
+        let ibt = open(&bytes[..bytes.len() - 20]).unwrap();
+        assert_eq!(ibt.record_count(), 7);
+        let recovery = ibt.recovery().unwrap();
+        assert_eq!((recovery.expected_records, recovery.records), (10, 7));
+        assert!((recovery.percent - 70.0).abs() < 1e-9);
+        let last = ibt.read_sample(6).unwrap();
+        assert_eq!(last["Speed"].as_f32(), Some(6.0));
+        assert!(ibt.read_sample(7).is_err());
+
+        // Never finished: no count in the header
+        let mut unfinished = bytes.clone();
+        unfinished[140..144].copy_from_slice(&0i32.to_le_bytes());
+        let ibt = open(&unfinished).unwrap();
+        assert_eq!(ibt.record_count(), 10);
+        assert_eq!(ibt.recovery(), None);
+
+        let error = open(&bytes[..100]).err().unwrap();
+        assert_eq!(error.to_string(), "Not an .ibt file: only 100 bytes");
+        // Variable headers past the end
+        let mut corrupt = bytes.clone();
+        corrupt[28..32].copy_from_slice(&(bytes.len() as i32).to_le_bytes());
+        let error = open(&corrupt).err().unwrap();
+        assert!(error.to_string().contains("variable headers"), "{error}");
+        // Speed's offset past the 8-byte sample
+        let mut corrupt = bytes.clone();
+        corrupt[144 + 144 + 4..144 + 144 + 8].copy_from_slice(&6i32.to_le_bytes());
+        let error = open(&corrupt).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Corrupt .ibt file: variable Speed lies outside the 8-byte sample"
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn test_ibt_open_and_header() {
         if !has_fixture() {
@@ -1391,8 +2520,7 @@ SessionInfo:
 
         // Read a frame ~30s in where car is likely on track
         let idx = 1800.min(ibt.record_count() - 1);
-        let sample = ibt.read_sample(idx).expect("Failed to read sample");
-        let frame = ibt.sample_to_frame(&sample);
+        let frame = ibt.read_frame(idx).expect("Failed to read frame");
 
         assert_eq!(frame.meta.game, "iRacing Replay");
 
@@ -1439,8 +2567,7 @@ SessionInfo:
             if idx >= ibt.record_count() {
                 continue;
             }
-            let sample = ibt.read_sample(idx).unwrap();
-            let frame = ibt.sample_to_frame(&sample);
+            let frame = ibt.read_frame(idx).unwrap();
 
             if let Some(ref v) = frame.vehicle {
                 if let Some(speed) = v.speed {
@@ -1463,12 +2590,29 @@ SessionInfo:
         }
     }
 
+    #[test]
+    fn test_ibt_timeline() {
+        if !has_fixture() {
+            return;
+        }
+        let ibt = IbtFile::open(&fixture_path()).expect("Failed to open .ibt file");
+        let timeline = ibt.build_timeline().unwrap();
+        assert_eq!(timeline.frames(), ibt.record_count());
+        assert_eq!(timeline.sample_rate(), 60.0);
+        let duration = timeline.duration_secs();
+        assert!(
+            (duration - ibt.duration_secs()).abs() < 1.0,
+            "Timeline covers {duration}s, header says {}s",
+            ibt.duration_secs()
+        );
+    }
+
     #[test]
     fn test_ibt_lap_index() {
         if !has_fixture() {
             return;
         }
-        let mut ibt = IbtFile::open(&fixture_path()).expect("Failed to open .ibt file");
+        let ibt = IbtFile::open(&fixture_path()).expect("Failed to open .ibt file");
         let laps = ibt.build_lap_index().unwrap();
         assert_eq!(laps.len(), 3);
         // Lap 0 is the out-lap (~70s from SessionTime delta)
@@ -1495,8 +2639,7 @@ SessionInfo:
         let ibt = IbtFile::open(&fixture_path()).expect("Failed to open .ibt file");
 
         // Frame 1800 (~30s in) — car should be on track with stable values
-        let sample = ibt.read_sample(1800).unwrap();
-        let frame = ibt.sample_to_frame(&sample);
+        let frame = ibt.read_frame(1800).unwrap();
 
         // Vehicle data assertions
         let vehicle = frame.vehicle.as_ref().expect("vehicle");
@@ -1593,4 +2736,21 @@ SessionInfo:
             "60 frames at 60Hz should span ~1 second, got {elapsed:.3}s"
         );
     }
+
+    #[test]
+    fn test_ibt_timestamps_follow_sim_time() {
+        if !has_fixture() {
+            return;
+        }
+        let ibt = IbtFile::open(&fixture_path()).expect("Failed to open .ibt file");
+        let first = ibt.read_frame(0).unwrap();
+        let sixtieth = ibt.read_frame(60).unwrap();
+
+        let elapsed = (sixtieth.meta.timestamp - first.meta.timestamp).num_milliseconds();
+        assert!(
+            (elapsed - 1000).abs() < 100,
+            "60 samples span ~1s, got {elapsed}ms"
+        );
+        assert!(sixtieth.meta.sim_time.unwrap() > first.meta.sim_time.unwrap());
+    }
 }
@@ -0,0 +1,310 @@
+//! Live tail of .ibt files as iRacing writes them
+//!
+//! With disk telemetry enabled (Alt+L), iRacing appends samples to an .ibt
+//! in its telemetry folder while the car is on track. [`IbtTailAdapter`]
+//! follows the newest file there and streams new samples as frames, for
+//! setups where shared memory isn't reachable, such as a server on another
+//! machine reading the folder over a network share.
+
+use crate::ibt_parser::IbtFile;
+use ost_core::{
+    adapter::{AdapterError, PollHint, TelemetryAdapter, VariableInfo},
+    model::TelemetryFrame,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long a file can go without growing before it is treated as finished.
+/// iRacing buffers its writes, so samples arrive in bursts.
+const STALE_AFTER: Duration = Duration::from_secs(5);
+
+/// The newest .ibt and when it last grew
+#[derive(Clone, Debug)]
+struct Seen {
+    path: PathBuf,
+    len: u64,
+    grew_at: Option<Instant>,
+}
+
+pub struct IbtTailAdapter {
+    dir: PathBuf,
+    seen: Mutex<Option<Seen>>,
+    /// The file being tailed and when a sample was last read from it
+    ibt: Option<(IbtFile, PathBuf, Instant)>,
+    /// Reused for every sample read
+    sample: Vec<u8>,
+    /// Next sample to stream
+    next: usize,
+    active: bool,
+    stale_after: Duration,
+}
+
+impl IbtTailAdapter {
+    /// Follow iRacing's telemetry folder, or `OST_IBT_DIR` if set
+    pub fn new() -> Self {
+        Self::with_dir(default_dir())
+    }
+
+    /// Follow the .ibt files in `dir`
+    pub fn with_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            seen: Mutex::new(None),
+            ibt: None,
+            sample: Vec::new(),
+            next: 0,
+            active: false,
+            stale_after: STALE_AFTER,
+        }
+    }
+
+    /// Tail `path` from its live end
+    fn open(&mut self, path: PathBuf) -> Result<(), AdapterError> {
+        let mut ibt = IbtFile::open(&path)?;
+        self.next = ibt.refresh()?;
+        self.ibt = Some((ibt, path, Instant::now()));
+        Ok(())
+    }
+
+    /// Look at the newest .ibt and return it if it has grown recently
+    fn growing_file(&self) -> Option<PathBuf> {
+        let (path, len) = newest_ibt(&self.dir)?;
+        let mut seen = self.seen.lock().unwrap();
+        let grew_at = match seen.as_ref() {
+            Some(prev) if prev.path == path && len > prev.len => Some(Instant::now()),
+            Some(prev) if prev.path == path => prev.grew_at,
+            // A file is only growing once it has been seen to grow
+            _ => None,
+        };
+        *seen = Some(Seen {
+            path: path.clone(),
+            len,
+            grew_at,
+        });
+        grew_at
+            .filter(|at| at.elapsed() < self.stale_after)
+            .map(|_| path)
+    }
+}
+
+impl Default for IbtTailAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `Documents/iRacing/telemetry` in the user's home folder
+fn default_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("OST_IBT_DIR") {
+        return PathBuf::from(dir);
+    }
+    let home = std::env::var_os("USERPROFILE")
+        .or_else(|| std::env::var_os("HOME"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    home.join("Documents").join("iRacing").join("telemetry")
+}
+
+/// The most recently modified .ibt in `dir` and its size
+fn newest_ibt(dir: &Path) -> Option<(PathBuf, u64)> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = entry.path();
+            if !path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("ibt"))
+            {
+                return None;
+            }
+            let meta = entry.metadata().ok()?;
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((modified, path, meta.len()))
+        })
+        .max_by_key(|(modified, _, _)| *modified)
+        .map(|(_, path, len)| (path, len))
+}
+
+impl TelemetryAdapter for IbtTailAdapter {
+    fn key(&self) -> &str {
+        "ibt-tail"
+    }
+
+    fn name(&self) -> &str {
+        "iRacing (.ibt live tail)"
+    }
+
+    fn detect(&self) -> bool {
+        self.growing_file().is_some()
+    }
+
+    fn start(&mut self) -> Result<(), AdapterError> {
+        let path = self
+            .growing_file()
+            .ok_or_else(|| AdapterError::NotDetected("No .ibt file is being written".into()))?;
+        // Live: only stream samples written from now on
+        self.open(path)?;
+        self.active = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), AdapterError> {
+        self.ibt = None;
+        self.next = 0;
+        self.active = false;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>, AdapterError> {
+        let Some((ibt, path, read_at)) = self.ibt.as_mut() else {
+            return Ok(None);
+        };
+        if self.next >= ibt.record_count() && self.next >= ibt.refresh()? {
+            if read_at.elapsed() < self.stale_after {
+                return Ok(None);
+            }
+            // Finished: follow iRacing to the file it writes next, if any
+            let path = path.clone();
+            return match self.growing_file() {
+                Some(newer) if newer != path => self.open(newer).map(|_| None),
+                _ => Err(AdapterError::ConnectionLost(format!(
+                    "{} stopped growing",
+                    path.display()
+                ))),
+            };
+        }
+        *read_at = Instant::now();
+        ibt.read_raw_sample(self.next, &mut self.sample)?;
+        self.next += 1;
+        let mut frame = ibt.sample_to_frame(&self.sample);
+        // Live data, not a replay
+        frame.meta.game = "iRacing".to_string();
+        Ok(Some(frame))
+    }
+
+    /// Poll at twice the tick rate so bursts of samples are caught up on
+    fn poll_hint(&self) -> PollHint {
+        let tick_rate = self
+            .ibt
+            .as_ref()
+            .map_or(60, |(ibt, ..)| ibt.tick_rate().max(1));
+        PollHint::Interval(Duration::from_secs_f64(0.5 / tick_rate as f64))
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn capabilities(&self) -> &'static [&'static str] {
+        crate::iracing::IRACING_CAPABILITIES
+    }
+
+    fn session_info_raw(&self) -> Option<String> {
+        self.ibt
+            .as_ref()
+            .map(|(ibt, ..)| ibt.session_info_yaml().to_string())
+    }
+
+    fn variables(&self) -> Vec<VariableInfo> {
+        self.ibt.as_ref().map_or_else(Vec::new, |(ibt, ..)| {
+            ibt.var_headers_ref().iter().map(|h| h.info()).collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ibt_writer::IbtWriter;
+
+    #[test]
+    fn test_tails_growing_file() {
+        let fixture = IbtFile::open(
+            &Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt"),
+        )
+        .unwrap();
+        let samples = fixture.read_samples_range(9000, 30).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("ost-ibt-tail-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = std::fs::File::create(dir.join("live.ibt")).unwrap();
+        let mut writer = IbtWriter::new(
+            file,
+            fixture.var_headers_ref().to_vec(),
+            fixture.tick_rate(),
+            fixture.session_info_yaml(),
+            chrono::Utc::now(),
+        )
+        .unwrap();
+        for sample in &samples[..10] {
+            writer.write_sample(sample).unwrap();
+        }
+
+        let mut adapter = IbtTailAdapter::with_dir(&dir);
+        // Not detected until the file is seen to grow
+        assert!(!adapter.detect());
+        writer.write_sample(&samples[10]).unwrap();
+        assert!(adapter.detect());
+
+        adapter.start().unwrap();
+        assert!(adapter.session_info_raw().unwrap().contains("WeekendInfo"));
+        let speed = adapter
+            .variables()
+            .into_iter()
+            .find(|v| v.name == "Speed")
+            .unwrap();
+        assert_eq!(
+            (speed.var_type.as_str(), speed.unit.as_str()),
+            ("float", "m/s")
+        );
+        // Starts at the live end of the file
+        assert!(adapter.read_frame().unwrap().is_none());
+
+        for sample in &samples[11..14] {
+            writer.write_sample(sample).unwrap();
+        }
+        let ticks: Vec<_> = std::iter::from_fn(|| adapter.read_frame().unwrap())
+            .map(|frame| frame.meta.tick)
+            .collect();
+        let expected: Vec<_> = (9011..9014)
+            .map(|i| fixture.read_frame(i).unwrap().meta.tick)
+            .collect();
+        assert_eq!(ticks, expected);
+
+        // Once the file stops growing, the next one iRacing writes is followed
+        adapter.stale_after = Duration::from_millis(100);
+        std::thread::sleep(Duration::from_millis(150));
+        let file = std::fs::File::create(dir.join("next.ibt")).unwrap();
+        let mut next = IbtWriter::new(
+            file,
+            fixture.var_headers_ref().to_vec(),
+            fixture.tick_rate(),
+            fixture.session_info_yaml(),
+            chrono::Utc::now(),
+        )
+        .unwrap();
+        assert!(!adapter.detect());
+        next.write_sample(&samples[20]).unwrap();
+        assert!(adapter.detect());
+        assert!(adapter.read_frame().unwrap().is_none());
+        next.write_sample(&samples[21]).unwrap();
+        let frame = adapter.read_frame().unwrap().unwrap();
+        assert_eq!(frame.meta.tick, fixture.read_frame(9021).unwrap().meta.tick);
+
+        // With nothing newer, a finished file loses the connection
+        std::thread::sleep(Duration::from_millis(150));
+        let error = adapter.read_frame().unwrap_err();
+        assert!(
+            matches!(error, AdapterError::ConnectionLost(_)),
+            "{}",
+            error
+        );
+
+        adapter.stop().unwrap();
+        drop((writer, next));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
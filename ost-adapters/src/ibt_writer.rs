@@ -0,0 +1,810 @@
+//! .ibt file writer
+//!
+//! [`IbtWriter`] produces files in iRacing's binary telemetry format, either
+//! from raw samples (as read by [`IbtFile::read_sample`](crate::ibt_parser::IbtFile::read_sample))
+//! or from [`TelemetryFrame`]s of any game, so recordings can be opened in
+//! tools built around .ibt. Files it writes read back with
+//! [`IbtFile`](crate::ibt_parser::IbtFile).
+
+use crate::ibt_parser::{Sample, VarHeader, VarType, VarValue};
+use crate::iracing::IRACING_AXES;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use ost_core::model::{SessionState, TelemetryFrame, WheelInfo};
+use std::io::{Seek, SeekFrom, Write};
+
+/// `irsdk_header` (112 bytes) followed by the disk sub-header (32 bytes)
+const HEADER_LEN: usize = 144;
+const VAR_HEADER_LEN: usize = 144;
+const IRSDK_VERSION: i32 = 2;
+/// `irsdk_StatusField::irsdk_stConnected`
+const STATUS_CONNECTED: i32 = 1;
+
+/// Streams samples into an .ibt file. Headers are written up front and
+/// patched with the sample count and session times by [`finish`](Self::finish).
+pub struct IbtWriter<W: Write + Seek> {
+    out: W,
+    vars: Vec<VarHeader>,
+    tick_rate: u32,
+    session_info_len: usize,
+    data_offset: usize,
+    buf: Vec<u8>,
+    records: usize,
+    start_date: i64,
+    /// `SessionTime` the start date corresponds to
+    start_time: Option<f64>,
+    end_time: f64,
+    lap_count: i32,
+    /// First frame written with [`write_frame`](Self::write_frame)
+    frame_origin: Option<DateTime<Utc>>,
+}
+
+impl<W: Write + Seek> IbtWriter<W> {
+    /// Start a file holding `vars`, which are packed into each sample in
+    /// order (their offsets are reassigned). Variables can come from another
+    /// file's [`var_headers_ref`](crate::ibt_parser::IbtFile::var_headers_ref)
+    /// or [`VarHeader::new`].
+    pub fn new(
+        mut out: W,
+        vars: impl IntoIterator<Item = VarHeader>,
+        tick_rate: u32,
+        session_info_yaml: &str,
+        start_date: DateTime<Utc>,
+    ) -> Result<Self> {
+        let mut buf_len = 0;
+        let vars: Vec<VarHeader> = vars
+            .into_iter()
+            .map(|mut vh| {
+                vh.count = vh.count.max(1);
+                vh.offset = buf_len as i32;
+                buf_len += vh.byte_range().len();
+                vh
+            })
+            .collect();
+
+        // NUL-terminated, as iRacing writes it
+        let mut session_info = session_info_yaml.as_bytes().to_vec();
+        session_info.push(0);
+        let session_info_offset = HEADER_LEN + vars.len() * VAR_HEADER_LEN;
+
+        out.seek(SeekFrom::Start(0))?;
+        out.write_all(&[0u8; HEADER_LEN])?;
+        for vh in &vars {
+            out.write_all(&var_header_bytes(vh))?;
+        }
+        out.write_all(&session_info)?;
+
+        let mut writer = Self {
+            out,
+            vars,
+            tick_rate,
+            session_info_len: session_info.len(),
+            data_offset: session_info_offset + session_info.len(),
+            buf: vec![0u8; buf_len],
+            records: 0,
+            start_date: start_date.timestamp(),
+            start_time: None,
+            end_time: 0.0,
+            lap_count: 0,
+            frame_origin: None,
+        };
+        writer.write_headers()?;
+        writer
+            .out
+            .seek(SeekFrom::Start(writer.data_offset as u64))?;
+        Ok(writer)
+    }
+
+    /// Start a file for [`write_frame`](Self::write_frame), with the
+    /// iRacing variables OpenSimTelemetry maps to and from the frame model,
+    /// and the track and car of `first` in the session info
+    pub fn for_frames(out: W, tick_rate: u32, first: &TelemetryFrame) -> Result<Self> {
+        let yaml = frame_session_info_yaml(first);
+        let mut writer = Self::new(out, frame_vars(), tick_rate, &yaml, first.meta.timestamp)?;
+        writer.align_clock(first.meta.timestamp, first.meta.sim_time.unwrap_or(0.0));
+        writer.frame_origin = Some(first.meta.timestamp);
+        Ok(writer)
+    }
+
+    /// Line the sim clock up with `date` to the microsecond, so samples at
+    /// `session_time` read back with that timestamp. Otherwise the first
+    /// sample is taken to be at the start date's whole second.
+    pub fn align_clock(&mut self, date: DateTime<Utc>, session_time: f64) {
+        let subsec = date.timestamp_subsec_micros() as f64 / 1e6;
+        self.start_date = date.timestamp();
+        self.start_time = Some(session_time - subsec);
+    }
+
+    /// Append a sample. Variables missing from `sample` are written as zero;
+    /// ones the file doesn't have are ignored.
+    pub fn write_sample(&mut self, sample: &Sample) -> Result<()> {
+        self.buf.fill(0);
+        for vh in &self.vars {
+            if let Some(value) = sample.get(vh.name.as_str()) {
+                encode(&mut self.buf, vh, value);
+            }
+        }
+        if let Some(time) = sample.get("SessionTime").and_then(|v| v.as_f64()) {
+            self.start_time.get_or_insert(time);
+            self.end_time = time;
+        }
+        if let Some(lap) = sample.get("Lap").and_then(|v| v.as_i32()) {
+            self.lap_count = self.lap_count.max(lap);
+        }
+        self.out
+            .write_all(&self.buf)
+            .context("Failed to write .ibt sample")?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Append a frame, converted to iRacing variables and units. Only
+    /// meaningful for writers from [`for_frames`](Self::for_frames).
+    pub fn write_frame(&mut self, frame: &TelemetryFrame) -> Result<()> {
+        let origin = *self.frame_origin.get_or_insert(frame.meta.timestamp);
+        let sim_time = frame.meta.sim_time.unwrap_or_else(|| {
+            (frame.meta.timestamp - origin)
+                .num_microseconds()
+                .unwrap_or(0) as f64
+                / 1e6
+        });
+        self.write_sample(&frame_sample(frame, sim_time))
+    }
+
+    /// Samples written so far
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Fill in the header and return the output
+    pub fn finish(mut self) -> Result<W> {
+        self.write_headers()?;
+        self.out.seek(SeekFrom::End(0))?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn write_headers(&mut self) -> Result<()> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        let ints = |header: &mut Vec<u8>, values: &[i32]| {
+            for v in values {
+                header.extend_from_slice(&v.to_le_bytes());
+            }
+        };
+        ints(
+            &mut header,
+            &[
+                IRSDK_VERSION,
+                STATUS_CONNECTED,
+                self.tick_rate as i32,
+                0, // session_info_update
+                self.session_info_len as i32,
+                (HEADER_LEN + self.vars.len() * VAR_HEADER_LEN) as i32,
+                self.vars.len() as i32,
+                HEADER_LEN as i32,
+                1, // num_buf
+                self.buf.len() as i32,
+                0,
+                0,
+            ],
+        );
+        // One var buf: tick count, offset, padding; then three unused
+        ints(
+            &mut header,
+            &[self.records as i32, self.data_offset as i32, 0, 0],
+        );
+        header.resize(112, 0);
+
+        let start_time = self.start_time.unwrap_or(0.0);
+        header.extend_from_slice(&self.start_date.to_le_bytes());
+        header.extend_from_slice(&start_time.to_le_bytes());
+        header.extend_from_slice(&self.end_time.max(start_time).to_le_bytes());
+        ints(&mut header, &[self.lap_count, self.records as i32]);
+
+        self.out.seek(SeekFrom::Start(0))?;
+        self.out.write_all(&header)?;
+        Ok(())
+    }
+}
+
+pub(crate) fn var_header_bytes(vh: &VarHeader) -> [u8; VAR_HEADER_LEN] {
+    let mut buf = [0u8; VAR_HEADER_LEN];
+    buf[0..4].copy_from_slice(&(vh.var_type as i32).to_le_bytes());
+    buf[4..8].copy_from_slice(&vh.offset.to_le_bytes());
+    buf[8..12].copy_from_slice(&vh.count.to_le_bytes());
+    buf[12] = vh.count_as_time as u8;
+    // Fixed-size NUL-terminated strings
+    for (range, text) in [
+        (16..48, &vh.name),
+        (48..112, &vh.desc),
+        (112..144, &vh.unit),
+    ] {
+        let field = &mut buf[range];
+        let len = text.len().min(field.len() - 1);
+        field[..len].copy_from_slice(&text.as_bytes()[..len]);
+    }
+    buf
+}
+
+/// Write `value` into the sample buffer as `vh`'s type, element by element
+/// for arrays
+fn encode(buf: &mut [u8], vh: &VarHeader, value: &VarValue) {
+    let size = vh.var_type.element_size();
+    let count = vh.count.max(1) as usize;
+    let mut put = |i: usize, v: &VarValue| {
+        let at = vh.offset as usize + i * size;
+        encode_scalar(&mut buf[at..at + size], vh.var_type, v);
+    };
+    match value {
+        VarValue::CharArray(v) => v
+            .iter()
+            .take(count)
+            .enumerate()
+            .for_each(|(i, &x)| put(i, &VarValue::Char(x))),
+        VarValue::IntArray(v) => v
+            .iter()
+            .take(count)
+            .enumerate()
+            .for_each(|(i, &x)| put(i, &VarValue::Int(x))),
+        VarValue::FloatArray(v) => v
+            .iter()
+            .take(count)
+            .enumerate()
+            .for_each(|(i, &x)| put(i, &VarValue::Float(x))),
+        VarValue::DoubleArray(v) => v
+            .iter()
+            .take(count)
+            .enumerate()
+            .for_each(|(i, &x)| put(i, &VarValue::Double(x))),
+        scalar => put(0, scalar),
+    }
+}
+
+fn encode_scalar(out: &mut [u8], var_type: VarType, value: &VarValue) {
+    match var_type {
+        VarType::Char => {
+            out[0] = match value {
+                VarValue::Char(c) => *c,
+                other => other.as_i32().unwrap_or(0) as u8,
+            }
+        }
+        VarType::Bool => out[0] = value.as_bool().unwrap_or(false) as u8,
+        VarType::Int => out.copy_from_slice(&value.as_i32().unwrap_or(0).to_le_bytes()),
+        VarType::BitField => out.copy_from_slice(&value.as_u32().unwrap_or(0).to_le_bytes()),
+        VarType::Float => out.copy_from_slice(&value.as_f32().unwrap_or(0.0).to_le_bytes()),
+        VarType::Double => out.copy_from_slice(&value.as_f64().unwrap_or(0.0).to_le_bytes()),
+    }
+}
+
+// ============================================================================
+// TelemetryFrame -> iRacing variables
+// ============================================================================
+
+/// Wheel variable prefixes, and whether the wheel is on the left
+const WHEELS: [(&str, bool); 4] = [("LF", true), ("RF", false), ("LR", true), ("RR", false)];
+
+/// Variables written by [`IbtWriter::write_frame`]: the ones the .ibt parser
+/// reads into the frame model, with iRacing's names and units
+fn frame_vars() -> Vec<VarHeader> {
+    use VarType::*;
+    let scalars: &[(&str, VarType, &str, &str)] = &[
+        ("SessionTime", Double, "s", "Seconds since session start"),
+        ("SessionTick", Int, "", "Current update number"),
+        ("SessionState", Int, "irsdk_SessionState", "Session state"),
+        (
+            "SessionTimeRemain",
+            Double,
+            "s",
+            "Seconds left till session ends",
+        ),
+        ("SessionTimeOfDay", Float, "s", "Time of day in seconds"),
+        ("Lap", Int, "", "Laps started count"),
+        ("LapCompleted", Int, "", "Laps completed count"),
+        ("LapDist", Float, "m", "Meters traveled from S/F this lap"),
+        ("LapDistPct", Float, "%", "Percentage distance around lap"),
+        (
+            "LapCurrentLapTime",
+            Float,
+            "s",
+            "Estimate of players current lap time",
+        ),
+        ("LapLastLapTime", Float, "s", "Players last lap time"),
+        ("LapBestLapTime", Float, "s", "Players best lap time"),
+        ("LapDeltaToBestLap", Float, "s", "Delta time for best lap"),
+        ("PlayerCarPosition", Int, "", "Players position in race"),
+        (
+            "PlayerCarClassPosition",
+            Int,
+            "",
+            "Players class position in race",
+        ),
+        ("Speed", Float, "m/s", "GPS vehicle speed"),
+        ("RPM", Float, "revs/min", "Engine rpm"),
+        ("Gear", Int, "", "-1=reverse  0=neutral  1..n=current gear"),
+        ("Throttle", Float, "%", "0=off throttle to 1=full throttle"),
+        ("Brake", Float, "%", "0=brake released to 1=max pedal force"),
+        ("Clutch", Float, "%", "0=disengaged to 1=fully engaged"),
+        ("SteeringWheelAngle", Float, "rad", "Steering wheel angle"),
+        (
+            "IsOnTrack",
+            Bool,
+            "",
+            "1=Car on track physics running with player in car",
+        ),
+        ("IsInGarage", Bool, "", "1=Car in garage physics running"),
+        (
+            "OnPitRoad",
+            Bool,
+            "",
+            "Is the player car on pit road between the cones",
+        ),
+        ("WaterTemp", Float, "C", "Engine coolant temp"),
+        ("OilTemp", Float, "C", "Engine oil temperature"),
+        ("OilPress", Float, "kPa", "Engine oil pressure"),
+        ("FuelLevel", Float, "l", "Liters of fuel remaining"),
+        ("FuelLevelPct", Float, "%", "Percent fuel remaining"),
+        ("Voltage", Float, "V", "Engine voltage"),
+        ("VelocityX", Float, "m/s", "X velocity"),
+        ("VelocityY", Float, "m/s", "Y velocity"),
+        ("VelocityZ", Float, "m/s", "Z velocity"),
+        (
+            "LongAccel",
+            Float,
+            "m/s^2",
+            "Longitudinal acceleration (including gravity)",
+        ),
+        (
+            "LatAccel",
+            Float,
+            "m/s^2",
+            "Lateral acceleration (including gravity)",
+        ),
+        (
+            "VertAccel",
+            Float,
+            "m/s^2",
+            "Vertical acceleration (including gravity)",
+        ),
+        ("Roll", Float, "rad", "Roll orientation"),
+        ("Pitch", Float, "rad", "Pitch orientation"),
+        ("Yaw", Float, "rad", "Yaw orientation"),
+        ("RollRate", Float, "rad/s", "Roll rate"),
+        ("PitchRate", Float, "rad/s", "Pitch rate"),
+        ("YawRate", Float, "rad/s", "Yaw rate"),
+        (
+            "YawNorth",
+            Float,
+            "rad",
+            "Yaw orientation relative to north",
+        ),
+        ("Lat", Double, "deg", "Latitude in decimal degrees"),
+        ("Lon", Double, "deg", "Longitude in decimal degrees"),
+        ("Alt", Float, "m", "Altitude in meters"),
+        (
+            "AirTemp",
+            Float,
+            "C",
+            "Temperature of air at start/finish line",
+        ),
+        (
+            "TrackTemp",
+            Float,
+            "C",
+            "Temperature of track at start/finish line",
+        ),
+        (
+            "TrackTempCrew",
+            Float,
+            "C",
+            "Temperature of track measured by crew around track",
+        ),
+    ];
+    let wheel: &[(&str, &str, &str)] = &[
+        ("tempCL", "C", "tire left carcass temperature"),
+        ("tempCM", "C", "tire middle carcass temperature"),
+        ("tempCR", "C", "tire right carcass temperature"),
+        ("pressure", "kPa", "tire pressure"),
+        ("shockDefl", "m", "shock deflection"),
+        ("rideHeight", "m", "ride height"),
+    ];
+
+    let mut vars: Vec<VarHeader> = scalars
+        .iter()
+        .map(|&(name, var_type, unit, desc)| VarHeader::new(name, var_type, 1, unit, desc))
+        .collect();
+    for (prefix, _) in WHEELS {
+        for &(suffix, unit, desc) in wheel {
+            vars.push(VarHeader::new(
+                &format!("{}{}", prefix, suffix),
+                Float,
+                1,
+                unit,
+                &format!("{} {}", prefix, desc),
+            ));
+        }
+    }
+    vars
+}
+
+/// Inverse of `SessionState::from_iracing`
+fn session_state_to_iracing(state: SessionState) -> i32 {
+    match state {
+        SessionState::Invalid => 0,
+        SessionState::GetInCar => 1,
+        SessionState::Warmup => 2,
+        SessionState::ParadeLaps => 3,
+        SessionState::Racing => 4,
+        SessionState::Checkered => 5,
+        SessionState::Cooldown => 6,
+    }
+}
+
+/// Minimal session info YAML with the fields the .ibt parser reads
+fn frame_session_info_yaml(frame: &TelemetryFrame) -> String {
+    let session = frame.session.as_ref();
+    let text = |value: Option<&String>| yaml_string(value.map_or("", |v| v));
+    let track = text(session.and_then(|s| s.track_name.as_ref()));
+    let config = text(session.and_then(|s| s.track_config.as_ref()));
+    let length = session
+        .and_then(|s| s.track_length)
+        .map(|m| format!("{:.2} km", m.0 / 1000.0))
+        .unwrap_or_default();
+    let car = text(frame.vehicle.as_ref().and_then(|v| v.car_name.as_ref()));
+    let driver = text(frame.driver.as_ref().and_then(|d| d.name.as_ref()));
+    format!(
+        "---\nWeekendInfo:\n TrackName: {track}\n TrackDisplayName: {track}\n TrackConfigName: {config}\n TrackLength: {length}\nDriverInfo:\n DriverCarIdx: 0\n Drivers:\n - CarIdx: 0\n   UserName: {driver}\n   CarScreenName: {car}\n\n...\n"
+    )
+}
+
+/// `value` as a double-quoted YAML scalar, so names with `: `, `#`, quotes
+/// or line breaks in them can't break the document
+fn yaml_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The frame as iRacing variables, undoing the parser's conversions
+fn frame_sample(frame: &TelemetryFrame, sim_time: f64) -> Sample {
+    let mut sample = Sample::new();
+    let mut float = |name: &str, value: Option<f32>| {
+        if let Some(v) = value {
+            sample.insert(name.into(), VarValue::Float(v));
+        }
+    };
+
+    if let Some(v) = &frame.vehicle {
+        float("Speed", v.speed.map(|s| s.0));
+        float("RPM", v.rpm.map(|r| r.0));
+        float("Throttle", v.throttle.map(|p| p.0));
+        float("Brake", v.brake.map(|p| p.0));
+        float("Clutch", v.clutch.map(|p| p.0));
+        float(
+            "SteeringWheelAngle",
+            v.steering_angle.map(|d| -d.0.to_radians()),
+        );
+    }
+    if let Some(e) = &frame.engine {
+        float("WaterTemp", e.water_temp.map(|t| t.0));
+        float("OilTemp", e.oil_temp.map(|t| t.0));
+        float("OilPress", e.oil_pressure.map(|p| p.0));
+        float("FuelLevel", e.fuel_level.map(|l| l.0));
+        float("FuelLevelPct", e.fuel_level_pct.map(|p| p.0));
+        float("Voltage", e.voltage.map(|v| v.0));
+    }
+    if let Some(t) = &frame.timing {
+        float("LapDist", t.lap_distance.map(|m| m.0));
+        float("LapDistPct", t.lap_distance_pct.map(|p| p.0));
+        float("LapCurrentLapTime", t.current_lap_time.map(|s| s.0));
+        float("LapLastLapTime", t.last_lap_time.map(|s| s.0));
+        float("LapBestLapTime", t.best_lap_time.map(|s| s.0));
+        float("LapDeltaToBestLap", t.delta_best.map(|s| s.0));
+    }
+    if let Some(s) = &frame.session {
+        float("SessionTimeOfDay", s.session_time_of_day.map(|s| s.0));
+    }
+    if let Some(m) = &frame.motion {
+        let vec3 = |v: Option<[f32; 3]>| v.map(|v| IRACING_AXES.linear_inverse(v));
+        let velocity = vec3(m.velocity.as_ref().map(|v| [v.x.0, v.y.0, v.z.0]));
+        let acceleration = vec3(
+            m.acceleration
+                .as_ref()
+                .map(|a| [a.x.0, a.y.0, a.z.0])
+                // Games without raw acceleration still report g-force
+                .or_else(|| {
+                    m.g_force
+                        .as_ref()
+                        .map(|g| [g.x.0, g.y.0, g.z.0].map(|g| g * 9.81))
+                }),
+        );
+        let angular = |v: [f32; 3]| IRACING_AXES.angular_inverse(v.map(f32::to_radians));
+        let rotation = m.rotation.as_ref().map(|r| angular([r.x.0, r.y.0, r.z.0]));
+        let rates = match (m.pitch_rate, m.yaw_rate, m.roll_rate) {
+            (Some(p), Some(y), Some(r)) => Some(angular([p.0, y.0, r.0])),
+            _ => None,
+        };
+        for (names, values) in [
+            (["VelocityX", "VelocityY", "VelocityZ"], velocity),
+            (["LongAccel", "LatAccel", "VertAccel"], acceleration),
+            (["Roll", "Pitch", "Yaw"], rotation),
+            (["RollRate", "PitchRate", "YawRate"], rates),
+        ] {
+            if let Some(values) = values {
+                for (name, value) in names.into_iter().zip(values) {
+                    float(name, Some(value));
+                }
+            }
+        }
+        float("YawNorth", m.heading.map(|h| -h.0.to_radians()));
+        float("Alt", m.altitude.map(|a| a.0));
+    }
+    if let Some(w) = &frame.weather {
+        float("AirTemp", w.air_temp.map(|t| t.0));
+        float("TrackTemp", w.track_surface_temp.map(|t| t.0));
+        float("TrackTempCrew", w.track_temp.map(|t| t.0));
+    }
+    if let Some(wheels) = &frame.wheels {
+        let all = [
+            &wheels.front_left,
+            &wheels.front_right,
+            &wheels.rear_left,
+            &wheels.rear_right,
+        ];
+        for ((prefix, left), wheel) in WHEELS.into_iter().zip(all) {
+            for (suffix, value) in wheel_values(wheel, left) {
+                float(&format!("{}{}", prefix, suffix), value);
+            }
+        }
+    }
+
+    let mut put = |name: &str, value: Option<VarValue>| {
+        if let Some(value) = value {
+            sample.insert(name.into(), value);
+        }
+    };
+    put("SessionTime", Some(VarValue::Double(sim_time)));
+    put(
+        "SessionTick",
+        frame.meta.tick.map(|t| VarValue::Int(t as i32)),
+    );
+    if let Some(s) = &frame.session {
+        put(
+            "SessionState",
+            s.session_state
+                .map(|state| VarValue::Int(session_state_to_iracing(state))),
+        );
+        put(
+            "SessionTimeRemain",
+            s.session_time_remaining
+                .map(|t| VarValue::Double(t.0 as f64)),
+        );
+    }
+    if let Some(t) = &frame.timing {
+        put("Lap", t.lap_number.map(|l| VarValue::Int(l as i32)));
+        put(
+            "LapCompleted",
+            t.laps_completed.map(|l| VarValue::Int(l as i32)),
+        );
+        put(
+            "PlayerCarPosition",
+            t.race_position.map(|p| VarValue::Int(p as i32)),
+        );
+        put(
+            "PlayerCarClassPosition",
+            t.class_position.map(|p| VarValue::Int(p as i32)),
+        );
+    }
+    if let Some(v) = &frame.vehicle {
+        put("Gear", v.gear.map(|g| VarValue::Int(g as i32)));
+        put("IsOnTrack", v.on_track.map(VarValue::Bool));
+        put("IsInGarage", v.in_garage.map(VarValue::Bool));
+    }
+    put(
+        "OnPitRoad",
+        frame
+            .pit
+            .as_ref()
+            .and_then(|p| p.on_pit_road)
+            .map(VarValue::Bool),
+    );
+    if let Some(m) = &frame.motion {
+        put("Lat", m.latitude.map(VarValue::Double));
+        put("Lon", m.longitude.map(VarValue::Double));
+    }
+    sample
+}
+
+/// Wheel variables by suffix. iRacing's left/right tread temps are the
+/// outer/inner edge on left wheels and the reverse on right wheels.
+fn wheel_values(wheel: &WheelInfo, left: bool) -> [(&'static str, Option<f32>); 6] {
+    let inner = wheel.surface_temp_inner.map(|t| t.0);
+    let outer = wheel.surface_temp_outer.map(|t| t.0);
+    let (cl, cr) = if left { (outer, inner) } else { (inner, outer) };
+    [
+        ("tempCL", cl),
+        ("tempCM", wheel.surface_temp_middle.map(|t| t.0)),
+        ("tempCR", cr),
+        ("pressure", wheel.tyre_pressure.map(|p| p.0)),
+        ("shockDefl", wheel.suspension_travel.map(|mm| mm.0 / 1000.0)),
+        ("rideHeight", wheel.ride_height.map(|mm| mm.0 / 1000.0)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ibt_parser::{IbtFile, IbtSessionInfo};
+    use std::path::{Path, PathBuf};
+
+    fn fixture() -> IbtFile {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt");
+        IbtFile::open(&path).unwrap()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "ost-ibt-writer-{}-{}.ibt",
+            name,
+            std::process::id()
+        ))
+    }
+
+    fn close(a: Option<f32>, b: Option<f32>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => (a - b).abs() <= 1e-3 * a.abs().max(1.0),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn test_rewrite_raw_samples() {
+        let ibt = fixture();
+        let path = temp_path("raw");
+        let file = std::fs::File::create(&path).unwrap();
+        let start = DateTime::from_timestamp(1_771_623_959, 0).unwrap();
+        let mut writer = IbtWriter::new(
+            file,
+            ibt.var_headers_ref().to_vec(),
+            ibt.tick_rate(),
+            ibt.session_info_yaml(),
+            start,
+        )
+        .unwrap();
+        let samples = ibt.read_samples_range(5000, 120).unwrap();
+        for sample in &samples {
+            writer.write_sample(sample).unwrap();
+        }
+        assert_eq!(writer.records(), 120);
+        writer.finish().unwrap();
+
+        let copy = IbtFile::open(&path).unwrap();
+        assert_eq!(copy.record_count(), 120);
+        assert_eq!(copy.tick_rate(), ibt.tick_rate());
+        assert_eq!(copy.var_headers_ref().len(), ibt.var_headers_ref().len());
+        assert_eq!(copy.session_info().car_name, ibt.session_info().car_name);
+        assert!((copy.duration_secs() - 119.0 / 60.0).abs() < 1e-3);
+        for (i, original) in samples.iter().enumerate() {
+            let read = copy.read_sample(i).unwrap();
+            for name in [
+                "Speed",
+                "Lap",
+                "OnPitRoad",
+                "SessionTime",
+                "Lat",
+                "SteeringWheelAngle",
+            ] {
+                assert_eq!(
+                    format!("{:?}", read[name]),
+                    format!("{:?}", original[name]),
+                    "{} at {}",
+                    name,
+                    i
+                );
+            }
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_session_info_names_are_quoted() {
+        let mut frame = TelemetryFrame::new("test");
+        frame.session = Some(ost_core::model::SessionData {
+            track_name: Some("Circuit: \"Old\" # Loop".into()),
+            ..Default::default()
+        });
+        frame.vehicle = Some(ost_core::model::VehicleData {
+            car_name: Some("Car\nTrackName: injected".into()),
+            ..Default::default()
+        });
+        frame.driver = Some(ost_core::model::DriverData {
+            name: Some("A \\ B\u{7}".into()),
+            ..Default::default()
+        });
+
+        let info = IbtSessionInfo::from_yaml(&frame_session_info_yaml(&frame)).unwrap();
+        assert_eq!(info.track_name, "Circuit: \"Old\" # Loop");
+        assert_eq!(info.car_name, "Car\nTrackName: injected");
+        assert_eq!(info.driver_name, "A \\ B\u{7}");
+        assert_eq!(info.track_config_name, "");
+    }
+
+    #[test]
+    fn test_frames_round_trip() {
+        let ibt = fixture();
+        let frames = ibt.read_frames_range(9000, 60).unwrap();
+
+        let path = temp_path("frames");
+        let file = std::fs::File::create(&path).unwrap();
+        let mut writer = IbtWriter::for_frames(file, 60, &frames[0]).unwrap();
+        for frame in &frames {
+            writer.write_frame(frame).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let copy = IbtFile::open(&path).unwrap();
+        assert_eq!(copy.record_count(), frames.len());
+        for (i, original) in frames.iter().enumerate() {
+            let frame = copy.read_frame(i).unwrap();
+            assert_eq!(frame.meta.tick, original.meta.tick);
+            let drift = frame.meta.timestamp - original.meta.timestamp;
+            assert!(drift.num_microseconds().unwrap().abs() <= 1);
+            assert_eq!(
+                frame.session.as_ref().unwrap().track_name,
+                original.session.as_ref().unwrap().track_name
+            );
+
+            let (v, ov) = (frame.vehicle.unwrap(), original.vehicle.clone().unwrap());
+            assert_eq!(v.car_name, ov.car_name);
+            assert_eq!(v.gear, ov.gear);
+            assert!(close(v.speed.map(|s| s.0), ov.speed.map(|s| s.0)));
+            assert!(close(v.throttle.map(|p| p.0), ov.throttle.map(|p| p.0)));
+            assert!(close(
+                v.steering_angle.map(|d| d.0),
+                ov.steering_angle.map(|d| d.0)
+            ));
+
+            let (m, om) = (frame.motion.unwrap(), original.motion.clone().unwrap());
+            assert_eq!(m.latitude, om.latitude);
+            let g = m.g_force.unwrap();
+            let og = om.g_force.unwrap();
+            assert!(close(Some(g.x.0), Some(og.x.0)) && close(Some(g.z.0), Some(og.z.0)));
+            let r = m.rotation.unwrap();
+            let or = om.rotation.unwrap();
+            assert!(close(Some(r.y.0), Some(or.y.0)) && close(Some(r.z.0), Some(or.z.0)));
+            assert!(close(m.yaw_rate.map(|r| r.0), om.yaw_rate.map(|r| r.0)));
+            assert!(close(m.heading.map(|h| h.0), om.heading.map(|h| h.0)));
+
+            let (t, ot) = (frame.timing.unwrap(), original.timing.clone().unwrap());
+            assert_eq!(t.lap_number, ot.lap_number);
+            assert!(close(
+                t.lap_distance_pct.map(|p| p.0),
+                ot.lap_distance_pct.map(|p| p.0)
+            ));
+
+            let (w, ow) = (frame.wheels.unwrap(), original.wheels.clone().unwrap());
+            assert!(close(
+                w.front_left.surface_temp_inner.map(|t| t.0),
+                ow.front_left.surface_temp_inner.map(|t| t.0)
+            ));
+            assert!(close(
+                w.rear_right.ride_height.map(|h| h.0),
+                ow.rear_right.ride_height.map(|h| h.0)
+            ));
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,550 @@
+//! Input device adapter
+//!
+//! A fallback for games with no telemetry of their own (Wreckfest, for
+//! example): reads the wheel and pedals straight from the OS so at least
+//! the inputs in `vehicle` (steering, throttle, brake, clutch, handbrake)
+//! populate, for driver-input overlays. Nothing else about the car is known.
+//!
+//! - Linux: the device's evdev node (`/dev/input/eventN`, or its stable
+//!   `/dev/input/by-id/*-event-joystick` link). Axes are numbered in order of
+//!   their event codes, as the joystick interface and `jstest` number them,
+//!   and scaled by the range the device reports for each.
+//! - Windows: the WinMM joystick API rather than DirectInput itself. WinMM
+//!   reads the same DirectInput game controllers, as six axes (X, Y, Z, R,
+//!   U, V = 0..5) scaled 0..65535, without DirectInput's COM and window
+//!   setup (device 0 by default).
+//!
+//! Only detected while `OST_INPUT_DEVICE` names the device (a path on Linux,
+//! a joystick number on Windows), as most machines have some input device
+//! attached whether or not it's used for driving. Axes are assigned with
+//! `OST_INPUT_AXES` as `steering=0,throttle=-2,brake=-3`, a leading `-` for
+//! an axis that reads highest at rest, as many pedals do. The wheel's lock to
+//! lock rotation comes from `OST_INPUT_STEERING_RANGE` (degrees, 900 by
+//! default), and `OST_INPUT_GAME` names the game in `meta.game`.
+
+use anyhow::{bail, Context, Result};
+use ost_core::adapter::{AdapterError, PollHint, TelemetryAdapter};
+use ost_core::PlatformFeature;
+use ost_core::{model::*, units::*};
+use std::time::Duration;
+
+const NAME: &str = "Input Device";
+
+/// Logitech's layout, the most common wheel: steering, then clutch,
+/// throttle and brake pedals resting at full scale
+const DEFAULT_AXES: &str = "steering=0,clutch=-1,throttle=-2,brake=-3";
+
+const DEFAULT_STEERING_RANGE: f32 = 900.0;
+
+/// Inputs change far faster than the sim reads them; 100 Hz is plenty for
+/// overlays
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// One axis of the device, possibly reversed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxisMap {
+    pub axis: usize,
+    pub inverted: bool,
+}
+
+/// Which device axes drive which inputs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputMapping {
+    pub steering: Option<AxisMap>,
+    pub throttle: Option<AxisMap>,
+    pub brake: Option<AxisMap>,
+    pub clutch: Option<AxisMap>,
+    pub handbrake: Option<AxisMap>,
+    /// Lock to lock, degrees
+    pub steering_range: f32,
+}
+
+impl InputMapping {
+    /// Parse `input=axis` pairs, e.g. `steering=0,throttle=-2`
+    pub fn parse(axes: &str, steering_range: f32) -> Result<Self> {
+        let mut mapping = Self {
+            steering_range,
+            ..Default::default()
+        };
+        for pair in axes.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (input, axis) = pair
+                .split_once('=')
+                .with_context(|| format!("Expected input=axis, got '{}'", pair))?;
+            let axis = axis.trim();
+            let (inverted, number) = match axis.strip_prefix('-') {
+                Some(number) => (true, number),
+                None => (false, axis),
+            };
+            let axis = AxisMap {
+                axis: number
+                    .parse()
+                    .with_context(|| format!("Invalid axis number '{}'", number))?,
+                inverted,
+            };
+            let slot = match input.trim() {
+                "steering" => &mut mapping.steering,
+                "throttle" => &mut mapping.throttle,
+                "brake" => &mut mapping.brake,
+                "clutch" => &mut mapping.clutch,
+                "handbrake" => &mut mapping.handbrake,
+                other => bail!(
+                    "Unknown input '{}': steering, throttle, brake, clutch or handbrake",
+                    other
+                ),
+            };
+            *slot = Some(axis);
+        }
+        Ok(mapping)
+    }
+
+    /// From `OST_INPUT_AXES` and `OST_INPUT_STEERING_RANGE`, or the defaults
+    fn from_env() -> Result<Self> {
+        let range = match std::env::var("OST_INPUT_STEERING_RANGE") {
+            Ok(range) => range
+                .parse()
+                .ok()
+                .filter(|&r: &f32| r > 0.0)
+                .with_context(|| format!("Invalid OST_INPUT_STEERING_RANGE '{}'", range))?,
+            Err(_) => DEFAULT_STEERING_RANGE,
+        };
+        let axes = std::env::var("OST_INPUT_AXES").unwrap_or_else(|_| DEFAULT_AXES.into());
+        Self::parse(&axes, range).context("Invalid OST_INPUT_AXES")
+    }
+
+    /// The inputs from axis positions, each -1.0 to 1.0
+    pub fn frame(&self, game: &str, axes: &[f32]) -> TelemetryFrame {
+        let value = |map: Option<AxisMap>| {
+            let map = map?;
+            let v = *axes.get(map.axis)?;
+            Some(if map.inverted { -v } else { v })
+        };
+        // Pedals travel the whole axis, from -1 at rest
+        let pedal = |map| value(map).map(|v| Percentage::new((v + 1.0) / 2.0));
+
+        let mut frame = TelemetryFrame::new(game);
+        frame.vehicle = Some(VehicleData {
+            steering_angle: value(self.steering).map(|v| Degrees(v * self.steering_range / 2.0)),
+            steering_angle_max: self.steering.map(|_| Degrees(self.steering_range / 2.0)),
+            throttle: pedal(self.throttle),
+            brake: pedal(self.brake),
+            // Pressed is disengaged
+            clutch: pedal(self.clutch),
+            handbrake: pedal(self.handbrake),
+            ..Default::default()
+        });
+        frame
+    }
+}
+
+// =============================================================================
+// Linux evdev events
+// =============================================================================
+
+/// `EV_ABS`, the event type of absolute axes
+#[cfg(any(target_os = "linux", test))]
+const EV_ABS: u16 = 0x03;
+
+/// An absolute axis of an evdev device
+#[cfg(any(target_os = "linux", test))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AbsAxis {
+    /// `ABS_*` event code
+    code: u16,
+    min: i32,
+    max: i32,
+}
+
+#[cfg(any(target_os = "linux", test))]
+impl AbsAxis {
+    /// `value` scaled to -1.0..1.0 over the axis range
+    fn scale(&self, value: i32) -> f32 {
+        if self.max <= self.min {
+            return 0.0;
+        }
+        let t = (value as f64 - self.min as f64) / (self.max as f64 - self.min as f64);
+        (t * 2.0 - 1.0).clamp(-1.0, 1.0) as f32
+    }
+}
+
+/// Apply one event to the axis positions, `axes` being indexed like
+/// `device`. Returns whether it moved an axis.
+#[cfg(any(target_os = "linux", test))]
+fn apply_abs_event(axes: &mut [f32], device: &[AbsAxis], kind: u16, code: u16, value: i32) -> bool {
+    if kind != EV_ABS {
+        return false;
+    }
+    let Some(index) = device.iter().position(|a| a.code == code) else {
+        return false;
+    };
+    axes[index] = device[index].scale(value);
+    true
+}
+
+// =============================================================================
+// Adapter
+// =============================================================================
+
+pub struct InputAdapter {
+    /// `OST_INPUT_DEVICE`, if set
+    device: Option<String>,
+    game: String,
+    /// Read from the environment on start
+    mapping: InputMapping,
+    #[cfg(target_os = "linux")]
+    file: Option<std::fs::File>,
+    /// The device's axes, in the order `axes` holds them
+    #[cfg(target_os = "linux")]
+    device_axes: Vec<AbsAxis>,
+    #[cfg(target_os = "windows")]
+    joystick: Option<u32>,
+    /// Latest position of each axis, -1.0 to 1.0
+    axes: Vec<f32>,
+    active: bool,
+}
+
+impl InputAdapter {
+    pub fn new() -> Self {
+        Self {
+            device: std::env::var("OST_INPUT_DEVICE").ok(),
+            game: std::env::var("OST_INPUT_GAME").unwrap_or_else(|_| NAME.into()),
+            mapping: InputMapping::default(),
+            #[cfg(target_os = "linux")]
+            file: None,
+            #[cfg(target_os = "linux")]
+            device_axes: Vec::new(),
+            #[cfg(target_os = "windows")]
+            joystick: None,
+            axes: Vec::new(),
+            active: false,
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn joystick_id(&self) -> Result<u32> {
+        match &self.device {
+            Some(id) => id
+                .parse()
+                .with_context(|| format!("OST_INPUT_DEVICE '{}' is not a joystick number", id)),
+            None => Ok(0),
+        }
+    }
+
+    /// Apply the pending events
+    #[cfg(target_os = "linux")]
+    fn poll_device(&mut self) -> Result<bool> {
+        let Some(file) = self.file.as_mut() else {
+            return Ok(false);
+        };
+        loop {
+            match evdev::read_event(file) {
+                Ok((kind, code, value)) => {
+                    apply_abs_event(&mut self.axes, &self.device_axes, kind, code, value);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(anyhow::Error::new(e).context("Input device went away")),
+            }
+        }
+        Ok(true)
+    }
+
+    #[cfg(target_os = "windows")]
+    fn poll_device(&mut self) -> Result<bool> {
+        let Some(id) = self.joystick else {
+            return Ok(false);
+        };
+        let raw = windows_impl::read(id).context("Input device went away")?;
+        self.axes = raw
+            .iter()
+            .map(|&v| v as f32 / u16::MAX as f32 * 2.0 - 1.0)
+            .collect();
+        Ok(true)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    fn poll_device(&mut self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+impl Default for InputAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TelemetryAdapter for InputAdapter {
+    fn key(&self) -> &str {
+        "input"
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn detect(&self) -> bool {
+        if self.device.is_none() {
+            return false;
+        }
+        #[cfg(target_os = "linux")]
+        {
+            self.device
+                .as_deref()
+                .is_some_and(|path| std::path::Path::new(path).exists())
+        }
+        #[cfg(target_os = "windows")]
+        {
+            self.joystick_id()
+                .is_ok_and(|id| windows_impl::read(id).is_ok())
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+        {
+            false
+        }
+    }
+
+    fn start(&mut self) -> Result<(), AdapterError> {
+        self.mapping = InputMapping::from_env()?;
+        self.axes.clear();
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            let path = self.device.clone().context("OST_INPUT_DEVICE is not set")?;
+            // Non-blocking, so reads return once the pending events are drained
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(&path)
+                .with_context(|| format!("Failed to open input device {}", path))?;
+            let axes = evdev::abs_axes(&file)
+                .with_context(|| format!("Failed to read the axes of {}", path))?;
+            self.axes = axes
+                .iter()
+                .map(|(axis, value)| axis.scale(*value))
+                .collect();
+            self.device_axes = axes.into_iter().map(|(axis, _)| axis).collect();
+            self.file = Some(file);
+        }
+        #[cfg(target_os = "windows")]
+        {
+            let id = self.joystick_id()?;
+            windows_impl::read(id).with_context(|| format!("No joystick {}", id))?;
+            self.joystick = Some(id);
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+        return Err(AdapterError::Unsupported(
+            "Input device adapter only available on Linux and Windows".into(),
+        ));
+        #[cfg(any(target_os = "linux", target_os = "windows"))]
+        {
+            self.active = true;
+            Ok(())
+        }
+    }
+
+    fn stop(&mut self) -> Result<(), AdapterError> {
+        #[cfg(target_os = "linux")]
+        {
+            self.file = None;
+        }
+        #[cfg(target_os = "windows")]
+        {
+            self.joystick = None;
+        }
+        self.active = false;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>, AdapterError> {
+        if !self.poll_device()? {
+            return Ok(None);
+        }
+        Ok(Some(self.mapping.frame(&self.game, &self.axes)))
+    }
+
+    fn poll_hint(&self) -> PollHint {
+        PollHint::Interval(POLL_INTERVAL)
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn capabilities(&self) -> &'static [&'static str] {
+        &[
+            "vehicle.steering_angle",
+            "vehicle.steering_angle_max",
+            "vehicle.throttle",
+            "vehicle.brake",
+            "vehicle.clutch",
+            "vehicle.handbrake",
+        ]
+    }
+
+    fn platform_requirements(&self) -> &'static [PlatformFeature] {
+        &[PlatformFeature::Joystick]
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod evdev {
+    use super::{AbsAxis, EV_ABS};
+    use anyhow::{bail, Context, Result};
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::fd::AsRawFd;
+
+    const EVIOCGBIT_ABS: libc::Ioctl =
+        libc::_IOR::<[u8; libc::ABS_CNT.div_ceil(8)]>(b'E' as u32, 0x20 + EV_ABS as u32);
+
+    /// `EVIOCGABS(code)`
+    const fn eviocgabs(code: u16) -> libc::Ioctl {
+        libc::_IOR::<libc::input_absinfo>(b'E' as u32, 0x40 + code as u32)
+    }
+
+    /// The device's absolute axes by event code, with their current values
+    pub fn abs_axes(file: &File) -> Result<Vec<(AbsAxis, i32)>> {
+        let fd = file.as_raw_fd();
+        let mut bits = [0u8; libc::ABS_CNT.div_ceil(8)];
+        // SAFETY: EVIOCGBIT writes at most the size encoded in the request,
+        // which is that of `bits`
+        if unsafe { libc::ioctl(fd, EVIOCGBIT_ABS, bits.as_mut_ptr()) } < 0 {
+            return Err(io::Error::last_os_error()).context("Not an evdev device");
+        }
+        let mut axes = Vec::new();
+        for code in 0..libc::ABS_CNT as u16 {
+            if bits[code as usize / 8] & (1 << (code % 8)) == 0 {
+                continue;
+            }
+            // SAFETY: `input_absinfo` is plain integers, valid when zeroed
+            let mut info: libc::input_absinfo = unsafe { std::mem::zeroed() };
+            // SAFETY: EVIOCGABS fills one `input_absinfo`
+            if unsafe { libc::ioctl(fd, eviocgabs(code), &mut info) } < 0 {
+                return Err(io::Error::last_os_error())
+                    .with_context(|| format!("Failed to read axis {}", code));
+            }
+            let axis = AbsAxis {
+                code,
+                min: info.minimum,
+                max: info.maximum,
+            };
+            axes.push((axis, info.value));
+        }
+        if axes.is_empty() {
+            bail!("Device has no axes");
+        }
+        Ok(axes)
+    }
+
+    /// The next `input_event`, as type, code and value
+    pub fn read_event(file: &mut File) -> io::Result<(u16, u16, i32)> {
+        let mut bytes = [0u8; std::mem::size_of::<libc::input_event>()];
+        file.read_exact(&mut bytes)?;
+        // SAFETY: the kernel hands out whole events, and any bytes are a
+        // valid `input_event`
+        let event: libc::input_event = unsafe { std::ptr::read_unaligned(bytes.as_ptr().cast()) };
+        Ok((event.type_, event.code, event.value))
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use anyhow::{bail, Result};
+    use windows::Win32::Media::Multimedia::{joyGetPosEx, JOYINFOEX, JOY_RETURNALL};
+
+    /// The six axes of joystick `id`, each 0..65535
+    pub fn read(id: u32) -> Result<[u32; 6]> {
+        let mut info = JOYINFOEX {
+            dwSize: std::mem::size_of::<JOYINFOEX>() as u32,
+            dwFlags: JOY_RETURNALL,
+            ..Default::default()
+        };
+        // SAFETY: `info` is initialised with its size as the API requires
+        let result = unsafe { joyGetPosEx(id, &mut info) };
+        if result != 0 {
+            bail!("joyGetPosEx failed with {}", result);
+        }
+        Ok([
+            info.dwXpos,
+            info.dwYpos,
+            info.dwZpos,
+            info.dwRpos,
+            info.dwUpos,
+            info.dwVpos,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mapping() {
+        let mapping = InputMapping::parse(DEFAULT_AXES, 900.0).unwrap();
+        assert_eq!(
+            mapping.steering,
+            Some(AxisMap {
+                axis: 0,
+                inverted: false
+            })
+        );
+        assert_eq!(
+            mapping.brake,
+            Some(AxisMap {
+                axis: 3,
+                inverted: true
+            })
+        );
+        assert_eq!(mapping.handbrake, None);
+
+        assert!(InputMapping::parse("gear=4", 900.0).is_err());
+        assert!(InputMapping::parse("throttle", 900.0).is_err());
+        assert!(InputMapping::parse("throttle=x", 900.0).is_err());
+    }
+
+    #[test]
+    fn test_frame_from_axes() {
+        let mapping = InputMapping::parse("steering=0,throttle=-2,brake=3", 900.0).unwrap();
+        let frame = mapping.frame("Wreckfest", &[0.5, 0.0, -1.0, 0.0]);
+        assert_eq!(frame.meta.game, "Wreckfest");
+        let vehicle = frame.vehicle.unwrap();
+        assert_eq!(vehicle.steering_angle, Some(Degrees(225.0)));
+        assert_eq!(vehicle.steering_angle_max, Some(Degrees(450.0)));
+        // Inverted: fully pressed at -1
+        assert_eq!(vehicle.throttle, Some(Percentage(1.0)));
+        assert_eq!(vehicle.brake, Some(Percentage(0.5)));
+        assert_eq!(vehicle.clutch, None);
+
+        // Axes the device doesn't have stay empty
+        let frame = mapping.frame("Wreckfest", &[0.0]);
+        assert_eq!(frame.vehicle.unwrap().throttle, None);
+    }
+
+    #[test]
+    fn test_abs_events() {
+        // A wheel centred on 0, and a pedal reporting 0..255
+        let device = [
+            AbsAxis {
+                code: 0x00,
+                min: -32768,
+                max: 32767,
+            },
+            AbsAxis {
+                code: 0x05,
+                min: 0,
+                max: 255,
+            },
+        ];
+        let mut axes = [0.0; 2];
+        assert!(apply_abs_event(&mut axes, &device, EV_ABS, 0x05, 255));
+        assert_eq!(axes, [0.0, 1.0]);
+        assert!(apply_abs_event(&mut axes, &device, EV_ABS, 0x00, -32768));
+        assert_eq!(axes[0], -1.0);
+        assert!(apply_abs_event(&mut axes, &device, EV_ABS, 0x05, 0));
+        assert_eq!(axes[1], -1.0);
+        // Axes the device didn't report, and other event types, are ignored
+        assert!(!apply_abs_event(&mut axes, &device, EV_ABS, 0x01, 100));
+        assert!(!apply_abs_event(&mut axes, &device, 0x01, 0x00, 1));
+        assert_eq!(axes, [-1.0, -1.0]);
+    }
+}
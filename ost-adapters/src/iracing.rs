@@ -1,33 +1,90 @@
 //! iRacing adapter using the iracing.rs library
 //!
 //! This adapter connects to iRacing via shared memory and reads telemetry data.
-//! It enumerates ALL available telemetry variables each tick via `sample.all()`,
-//! maps known variables to the normalized data model, and dumps everything else
-//! to `extras` with an "iracing/" prefix.
+//! Variable headers are read once per connection, and each tick reads values
+//! straight out of the newest sample buffer: known variables are mapped to the
+//! normalized data model, and everything goes into `extras` under "iracing".
 //!
-//! Only available on Windows.
+//! iRacing is detected by its shared memory header's connected flag rather
+//! than the mapping alone, which outlives the sim while anything holds it
+//! open. If the flag drops mid-session (the sim exited or is restarting) the
+//! adapter releases the mapping and keeps reconnecting for a while before
+//! reporting the game gone, so a quick restart carries on without the
+//! manager having to start it again.
+//!
+//! Only available on Windows, and with the `iracing` feature. The shared
+//! helpers at the bottom are always built, for the `.ibt` parser.
 
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", feature = "iracing"))]
 mod windows_impl {
+    use crate::ibt_parser::{
+        read_var, IbtFile, IbtSessionInfo, Quantity, SlotUnit, VarHeader, VarType, VarValue,
+    };
     use anyhow::Result;
     use chrono::Utc;
     use iracing::session::SessionDetails;
-    use iracing::telemetry::{Connection, Sample as IRacingSample, Value, ValueDescription};
-    use ost_core::{adapter::TelemetryAdapter, model::*, units::*};
+    use iracing::telemetry::Connection;
+    use ost_core::{
+        adapter::{AdapterError, ConnectionState, PollHint, TelemetryAdapter, VariableInfo},
+        control::{CameraSwitch, ChatCommand, ControlCapable, PitCommand, ReplaySpeed},
+        model::*,
+        units::*,
+    };
     use std::collections::HashMap;
-    use std::convert::TryInto;
-    use std::time::Duration;
+    use std::thread::JoinHandle;
+    use std::time::{Duration, Instant};
+
+    /// How often to try reconnecting after the sim went away
+    const RECONNECT_INTERVAL: Duration = Duration::from_millis(500);
+    /// How long to keep trying before reporting iRacing gone
+    const RECONNECT_GRACE: Duration = Duration::from_secs(30);
 
     pub struct IRacingAdapter {
         connection: Option<Connection>,
         blocking: Option<iracing::telemetry::Blocking>,
         active: bool,
+        state: ConnectionState,
+        /// When the sim went away, while waiting for it to come back
+        disconnected_at: Option<Instant>,
+        /// Our own view of the shared memory, for the header fields the
+        /// `iracing` crate doesn't expose
+        memory: Option<SharedMemory>,
         /// Cached session info
         session_details: Option<SessionDetails>,
         /// Whether session info changed on last read
         session_changed: bool,
-        /// Timestamp of last session info refresh (rate-limited to avoid re-parsing YAML every frame)
-        last_session_refresh: Option<std::time::Instant>,
+        /// iRacing's `sessionInfoUpdate` counter for the cached (or
+        /// currently parsing) session info
+        session_version: Option<i32>,
+        /// The variable headers, read once per connection
+        vars: Vec<VarHeader>,
+        /// Where each variable is in `vars`
+        var_index: HashMap<String, usize>,
+        /// Each variable's unit, in `vars` order
+        var_units: Vec<SlotUnit>,
+        /// The newest sample buffer, reused across frames
+        sample: Vec<u8>,
+        /// `DriverCarFuelKgPerLtr` from session info, for fuel given by mass
+        fuel_kg_per_liter: Option<f32>,
+        /// Session info being parsed off the reader thread
+        session_parse: Option<JoinHandle<Option<ParsedSessionInfo>>>,
+        /// Raw session info YAML from the last refresh
+        session_yaml: Option<String>,
+        /// Every variable iRacing publishes, read on connect
+        variables: Vec<VariableInfo>,
+    }
+
+    /// Session info as parsed on a background thread
+    struct ParsedSessionInfo {
+        details: SessionDetails,
+        yaml: Option<String>,
+        fuel_kg_per_liter: Option<f32>,
+    }
+
+    /// The car's fuel density from session info YAML, which the `iracing`
+    /// crate's session details leave out
+    fn fuel_density(yaml: Option<&str>) -> Option<f32> {
+        IbtSessionInfo::from_yaml(yaml?).ok()?.fuel_kg_per_liter
     }
 
     // SAFETY: iRacing's shared memory is thread-safe for reading.
@@ -43,138 +100,149 @@ mod windows_impl {
                 connection: None,
                 blocking: None,
                 active: false,
+                state: ConnectionState::Disconnected,
+                disconnected_at: None,
+                memory: None,
                 session_details: None,
                 session_changed: false,
-                last_session_refresh: None,
+                session_version: None,
+                vars: Vec::new(),
+                var_index: HashMap::new(),
+                var_units: Vec::new(),
+                sample: Vec::new(),
+                fuel_kg_per_liter: None,
+                session_parse: None,
+                session_yaml: None,
+                variables: Vec::new(),
             }
         }
 
-        /// Refresh session info at most once per second (parsing the full YAML
-        /// string on every frame is expensive and unnecessary).
+        /// Open the shared memory and read the initial session info
+        fn connect(&mut self) -> Result<()> {
+            let mut connection = Connection::new()?;
+            let memory = SharedMemory::open()?;
+
+            // Parsed here rather than in the background, so the first frames
+            // already have it
+            self.session_version = Some(super::iracing_session_info_version(memory.header()));
+            let vars = memory.var_headers();
+            self.variables = vars.iter().map(VarHeader::info).collect();
+            self.index_vars(vars);
+            if let Ok(details) = connection.session_info() {
+                self.session_details = Some(details);
+                self.session_yaml = Some(memory.session_yaml());
+                self.fuel_kg_per_liter = fuel_density(self.session_yaml.as_deref());
+                self.session_changed = true;
+            }
+
+            let blocking = connection.blocking()?;
+
+            self.connection = Some(connection);
+            self.blocking = Some(blocking);
+            self.memory = Some(memory);
+            Ok(())
+        }
+
+        /// Release the shared memory after the sim went away, so a restarted
+        /// sim starts from a clean mapping
+        fn disconnect(&mut self) {
+            self.blocking = None;
+            self.connection = None;
+            self.memory = None;
+            self.state = ConnectionState::Disconnected;
+            self.disconnected_at = Some(Instant::now());
+            // A parse still running finishes on its own
+            self.session_parse = None;
+            self.session_version = None;
+        }
+
+        /// Pick up new session info when iRacing bumps its version counter.
+        /// The YAML runs to hundreds of kilobytes in big fields, so it's
+        /// parsed on a background thread rather than stalling frames.
         fn maybe_refresh_session_info(&mut self) {
-            let now = std::time::Instant::now();
-            if let Some(last) = self.last_session_refresh {
-                if now.duration_since(last) < Duration::from_secs(1) {
-                    self.session_changed = false;
-                    return;
+            self.session_changed = false;
+
+            if let Some(parse) = self.session_parse.take_if(|p| p.is_finished()) {
+                if let Ok(Some(parsed)) = parse.join() {
+                    self.session_details = Some(parsed.details);
+                    self.session_yaml = parsed.yaml;
+                    self.fuel_kg_per_liter = parsed.fuel_kg_per_liter;
+                    self.session_changed = true;
                 }
             }
-            self.last_session_refresh = Some(now);
+            if self.session_parse.is_some() {
+                return;
+            }
 
-            if let Some(ref mut conn) = self.connection {
-                match conn.session_info() {
-                    Ok(details) => {
-                        self.session_details = Some(details);
-                        self.session_changed = true;
-                    }
-                    Err(_) => {
-                        self.session_changed = false;
-                    }
-                }
+            let Some(memory) = &self.memory else {
+                return;
+            };
+            let version = super::iracing_session_info_version(memory.header());
+            if self.session_version == Some(version) {
+                return;
             }
+            // Versions bumped mid-parse are caught on the next call after it
+            self.session_version = Some(version);
+            self.session_parse = Some(std::thread::spawn(|| {
+                let details = Connection::new().ok()?.session_info().ok()?;
+                let yaml = read_session_yaml().ok();
+                Some(ParsedSessionInfo {
+                    details,
+                    fuel_kg_per_liter: fuel_density(yaml.as_deref()),
+                    yaml,
+                })
+            }));
         }
 
-        /// Convert iRacing telemetry sample to unified TelemetryFrame.
-        /// Uses `sample.all()` to enumerate every variable, maps known ones
-        /// to normalized fields, and puts the rest into extras.
-        fn convert_sample(&self, sample: &IRacingSample) -> TelemetryFrame {
-            // Get all variables in one pass
-            let all_vars = sample.all();
-            let vars: HashMap<&str, &ValueDescription> =
-                all_vars.iter().map(|v| (v.name.as_str(), v)).collect();
+        /// Index the variable headers read on connect, so frames look each
+        /// variable up without walking the table
+        fn index_vars(&mut self, vars: Vec<VarHeader>) {
+            self.var_index = vars
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (v.name.clone(), i))
+                .collect();
+            self.var_units = vars.iter().map(|v| SlotUnit::parse(&v.unit)).collect();
+            self.vars = vars;
+        }
 
-            // Helper closures for type conversion
-            let get_f32 = |name: &str| -> Option<f32> {
-                vars.get(name).and_then(|v| v.value.clone().try_into().ok())
-            };
-            let get_f64 = |name: &str| -> Option<f64> {
-                vars.get(name).and_then(|v| v.value.clone().try_into().ok())
-            };
-            let get_i32 = |name: &str| -> Option<i32> {
-                vars.get(name).and_then(|v| v.value.clone().try_into().ok())
-            };
-            let get_u32 = |name: &str| -> Option<u32> {
-                vars.get(name).and_then(|v| v.value.clone().try_into().ok())
-            };
-            let get_bool =
-                |name: &str| -> Option<bool> { vars.get(name).map(|v| v.value.clone().into()) };
+        /// Variable `name` of a sample in the model's unit for `quantity`,
+        /// converted from its header's unit as `.ibt` files are
+        fn var_in(&self, sample: &[u8], name: &str, quantity: Quantity) -> Option<f32> {
+            let &i = self.var_index.get(name)?;
+            let value = read_var(sample, &self.vars[i])?.as_f32()?;
+            self.var_units[i].convert(value, quantity, self.fuel_kg_per_liter)
+        }
+
+        /// Variable `name` of a sample, as indexed by [`index_vars`](Self::index_vars)
+        fn var(&self, sample: &[u8], name: &str) -> Option<VarValue> {
+            let &i = self.var_index.get(name)?;
+            read_var(sample, &self.vars[i])
+        }
+
+        /// Convert a sample buffer to unified TelemetryFrame.
+        /// Maps known variables to normalized fields, and puts every
+        /// variable into extras.
+        fn convert_sample(&self, sample: &[u8]) -> TelemetryFrame {
+            let get_f32 = |name: &str| -> Option<f32> { self.var(sample, name)?.as_f32() };
+            let get_f64 = |name: &str| -> Option<f64> { self.var(sample, name)?.as_f64() };
+            let get_i32 = |name: &str| -> Option<i32> { self.var(sample, name)?.as_i32() };
+            let get_u32 = |name: &str| -> Option<u32> { self.var(sample, name)?.as_u32() };
+            let get_bool = |name: &str| -> Option<bool> { self.var(sample, name)?.as_bool() };
+            let get_in = |name: &str, quantity: Quantity| self.var_in(sample, name, quantity);
 
             let tick = get_i32("SessionTick").map(|t| t as u32);
 
             // =================================================================
             // Motion
             // =================================================================
-            let velocity = match (
-                get_f32("VelocityX"),
-                get_f32("VelocityY"),
-                get_f32("VelocityZ"),
-            ) {
-                (Some(vx), Some(vy), Some(vz)) => Some(Vector3::new(
-                    MetersPerSecond(vx),
-                    MetersPerSecond(vy),
-                    MetersPerSecond(vz),
-                )),
-                _ => None,
-            };
-
-            let acceleration = match (
-                get_f32("LatAccel"),
-                get_f32("LongAccel"),
-                get_f32("VertAccel"),
-            ) {
-                (Some(lat), Some(long), Some(vert)) => Some(Vector3::new(
-                    MetersPerSecondSquared(lat),
-                    MetersPerSecondSquared(vert),
-                    MetersPerSecondSquared(long),
-                )),
-                _ => None,
-            };
-
-            let g_force = acceleration.as_ref().map(|a| {
-                Vector3::new(
-                    GForce::from_acceleration(a.x),
-                    GForce::from_acceleration(a.y),
-                    GForce::from_acceleration(a.z),
-                )
-            });
-
-            let rotation = match (get_f32("Pitch"), get_f32("Yaw"), get_f32("Roll")) {
-                (Some(p), Some(y), Some(r)) => Some(Vector3::new(
-                    Degrees::from_radians(p),
-                    Degrees::from_radians(y),
-                    Degrees::from_radians(r),
-                )),
-                _ => None,
-            };
-
-            // YawNorth: yaw relative to geographic north (radians, CCW positive)
-            // Convert to compass heading (degrees, CW from north)
-            let heading = get_f32("YawNorth").map(|yn| {
-                let deg = -yn * (180.0 / std::f32::consts::PI);
-                Degrees(deg.rem_euclid(360.0))
-            });
-
-            let motion = Some(MotionData {
-                position: None,
-                velocity,
-                acceleration,
-                g_force,
-                rotation,
-                pitch_rate: get_f32("PitchRate").map(DegreesPerSecond::from_radians),
-                yaw_rate: get_f32("YawRate").map(DegreesPerSecond::from_radians),
-                roll_rate: get_f32("RollRate").map(DegreesPerSecond::from_radians),
-                angular_acceleration: None,
-                latitude: get_f64("Lat"),
-                longitude: get_f64("Lon"),
-                altitude: get_f32("Alt").map(Meters),
-                heading,
-            });
+            let motion = Some(crate::iracing::iracing_motion(get_f32, get_f64));
 
             // =================================================================
             // Vehicle
             // =================================================================
             let speed = get_f32("Speed").map(MetersPerSecond).or_else(|| {
-                velocity.as_ref().map(|v| {
+                motion.as_ref().and_then(|m| m.velocity.as_ref()).map(|v| {
                     MetersPerSecond((v.x.0.powi(2) + v.y.0.powi(2) + v.z.0.powi(2)).sqrt())
                 })
             });
@@ -200,7 +268,8 @@ mod windows_impl {
                 throttle: get_f32("Throttle").map(Percentage::new),
                 brake: get_f32("Brake").map(Percentage::new),
                 clutch: get_f32("Clutch").map(Percentage::new),
-                steering_angle: get_f32("SteeringWheelAngle").map(Degrees::from_radians),
+                steering_angle: get_f32("SteeringWheelAngle")
+                    .map(crate::iracing::iracing_steering_angle),
                 steering_torque: get_f32("SteeringWheelTorque").map(NewtonMeters),
                 steering_torque_pct: get_f32("SteeringWheelPctTorque").map(Percentage::new),
                 handbrake: get_f32("HandbrakeRaw").map(Percentage::new),
@@ -239,32 +308,19 @@ mod windows_impl {
                 .as_ref()
                 .map(|s| Liters(s.drivers.fuel_capacity));
 
-            let engine_warnings = get_u32("EngineWarnings").map(EngineWarnings::from_iracing_bits);
-
             let engine = Some(EngineData {
-                water_temp: get_f32("WaterTemp").map(Celsius),
-                oil_temp: get_f32("OilTemp").map(Celsius),
-                oil_pressure: get_f32("OilPress").map(Kilopascals),
-                oil_level: get_f32("OilLevel").map(Percentage::new),
-                fuel_level: get_f32("FuelLevel").map(Liters),
-                fuel_level_pct: get_f32("FuelLevelPct").map(Percentage::new),
                 fuel_capacity,
-                fuel_pressure: get_f32("FuelPress").map(Kilopascals),
-                fuel_use_per_hour: get_f32("FuelUsePerHour").map(LitersPerHour),
-                voltage: get_f32("Voltage").map(Volts),
-                manifold_pressure: get_f32("ManifoldPress").map(Bar),
-                water_level: get_f32("WaterLevel").map(Liters),
-                warnings: engine_warnings,
+                ..crate::iracing::iracing_engine(get_f32, get_u32, get_in)
             });
 
             // =================================================================
             // Wheels
             // =================================================================
             let wheels = Some(WheelData {
-                front_left: self.extract_wheel(&vars, "LF", true),
-                front_right: self.extract_wheel(&vars, "RF", false),
-                rear_left: self.extract_wheel(&vars, "LR", true),
-                rear_right: self.extract_wheel(&vars, "RR", false),
+                front_left: self.extract_wheel(sample, "LF", true),
+                front_right: self.extract_wheel(sample, "RF", false),
+                rear_left: self.extract_wheel(sample, "LR", true),
+                rear_right: self.extract_wheel(sample, "RR", false),
             });
 
             // =================================================================
@@ -289,7 +345,7 @@ mod windows_impl {
                 sector_times: None,
                 lap_number: get_i32("Lap").map(|l| l as u32),
                 laps_completed: get_i32("LapCompleted").map(|l| l as u32),
-                lap_distance: get_f32("LapDist").map(Meters),
+                lap_distance: get_in("LapDist", Quantity::Length).map(Meters),
                 lap_distance_pct: get_f32("LapDistPct").map(Percentage::new),
                 race_position: get_i32("PlayerCarPosition").map(|p| p as u32),
                 class_position: get_i32("PlayerCarClassPosition").map(|p| p as u32),
@@ -302,6 +358,8 @@ mod windows_impl {
                 delta_optimal_ok: get_bool("LapDeltaToOptimalLap_OK"),
                 estimated_lap_time,
                 race_laps: get_i32("RaceLaps").map(|l| l as u32),
+                lap_valid: None,
+                corner_name: None,
             });
 
             // =================================================================
@@ -377,16 +435,23 @@ mod windows_impl {
                 track_config,
                 track_length: track_length_m,
                 track_type: track_type_str,
+                track_id: self
+                    .session_details
+                    .as_ref()
+                    .map(|s| format!("iracing:{}", s.weekend.track_name)),
+                sector_starts: None,
+                pit_entry_pct: None,
+                pit_exit_pct: None,
             });
 
             // =================================================================
             // Weather
             // =================================================================
             let weather = Some(WeatherData {
-                air_temp: get_f32("AirTemp").map(Celsius),
-                track_temp: get_f32("TrackTempCrew").map(Celsius),
-                track_surface_temp: get_f32("TrackTemp").map(Celsius),
-                air_pressure: get_f32("AirPressure").map(|v| Kilopascals(v / 1000.0)),
+                air_temp: get_in("AirTemp", Quantity::Temperature).map(Celsius),
+                track_temp: get_in("TrackTempCrew", Quantity::Temperature).map(Celsius),
+                track_surface_temp: get_in("TrackTemp", Quantity::Temperature).map(Celsius),
+                air_pressure: get_in("AirPressure", Quantity::Pressure).map(Kilopascals),
                 air_density: get_f32("AirDensity").map(KilogramsPerCubicMeter),
                 humidity: get_f32("RelativeHumidity").map(|h| Percentage::new(h / 100.0)),
                 wind_speed: get_f32("WindVel").map(MetersPerSecond),
@@ -414,19 +479,8 @@ mod windows_impl {
             // =================================================================
             // Pit
             // =================================================================
-            let requested_services = Some(PitServices {
-                fuel_to_add: get_f32("dpFuelFill").map(Liters),
-                change_tyre_fl: get_f32("dpLFTireChange").map_or(false, |v| v > 0.0),
-                change_tyre_fr: get_f32("dpRFTireChange").map_or(false, |v| v > 0.0),
-                change_tyre_rl: get_f32("dpLRTireChange").map_or(false, |v| v > 0.0),
-                change_tyre_rr: get_f32("dpRRTireChange").map_or(false, |v| v > 0.0),
-                windshield_tearoff: get_f32("dpWindshieldTearoff").map_or(false, |v| v > 0.0),
-                fast_repair: get_f32("dpFastRepair").map_or(false, |v| v > 0.0),
-                tyre_pressure_fl: get_f32("dpLFTireColdPress").map(Kilopascals),
-                tyre_pressure_fr: get_f32("dpRFTireColdPress").map(Kilopascals),
-                tyre_pressure_rl: get_f32("dpLRTireColdPress").map(Kilopascals),
-                tyre_pressure_rr: get_f32("dpRRTireColdPress").map(Kilopascals),
-            });
+            let requested_services =
+                Some(crate::iracing::iracing_requested_services(get_f32, get_in));
 
             let pit_speed_limit = self.session_details.as_ref().and_then(|s| {
                 s.weekend
@@ -486,7 +540,7 @@ mod windows_impl {
             // =================================================================
             // Competitors (from CarIdx arrays)
             // =================================================================
-            let competitors = self.extract_competitors(&all_vars);
+            let competitors = self.extract_competitors(sample);
 
             // =================================================================
             // Driver (from session info)
@@ -513,8 +567,10 @@ mod windows_impl {
             // =================================================================
             let mut iracing_data = serde_json::Map::new();
 
-            for vd in &all_vars {
-                iracing_data.insert(vd.name.clone(), value_to_json(&vd.value));
+            for vh in &self.vars {
+                if let Some(value) = read_var(sample, vh) {
+                    iracing_data.insert(vh.name.clone(), var_to_json(vh, &value));
+                }
             }
 
             // Also add game-specific driver metadata from session info
@@ -563,11 +619,18 @@ mod windows_impl {
                 serde_json::Value::Object(iracing_data),
             );
 
+            let now = Utc::now();
             TelemetryFrame {
                 meta: MetaData {
-                    timestamp: Utc::now(),
+                    timestamp: now,
                     game: "iRacing".to_string(),
                     tick,
+                    sim_time: get_f64("SessionTime"),
+                    invalid_fields: None,
+                    seq: None,
+                    acquired_at: Some(now),
+                    broadcast_at: None,
+                    adapter: None,
                 },
                 motion,
                 vehicle,
@@ -579,8 +642,11 @@ mod windows_impl {
                 pit,
                 electronics,
                 damage: None,
+                energy: crate::iracing::iracing_energy(get_f32),
                 competitors,
                 driver,
+                relative: None,
+                predictions: None,
                 extras,
             }
         }
@@ -588,106 +654,31 @@ mod windows_impl {
         /// Extract per-wheel data.
         /// `prefix` is "LF", "RF", "LR", or "RR".
         /// `is_left_side` determines inner/outer mapping for temperatures.
-        fn extract_wheel(
-            &self,
-            vars: &HashMap<&str, &ValueDescription>,
-            prefix: &str,
-            is_left_side: bool,
-        ) -> WheelInfo {
+        fn extract_wheel(&self, sample: &[u8], prefix: &str, is_left_side: bool) -> WheelInfo {
             let get_f32 = |suffix: &str| -> Option<f32> {
-                let key = format!("{}{}", prefix, suffix);
-                vars.get(key.as_str())
-                    .and_then(|v| v.value.clone().try_into().ok())
+                self.var(sample, &format!("{}{}", prefix, suffix))?.as_f32()
             };
-
-            // Inner/outer mapping: for left wheels, CL=outer edge, CR=inner edge.
-            // For right wheels, CL=inner edge, CR=outer edge.
-            let (surface_temp_inner, surface_temp_outer) = if is_left_side {
-                (
-                    get_f32("tempCR").map(Celsius),
-                    get_f32("tempCL").map(Celsius),
-                )
-            } else {
-                (
-                    get_f32("tempCL").map(Celsius),
-                    get_f32("tempCR").map(Celsius),
-                )
-            };
-
-            let (carcass_temp_inner, carcass_temp_outer) = if is_left_side {
-                (get_f32("tempR").map(Celsius), get_f32("tempL").map(Celsius))
-            } else {
-                (get_f32("tempL").map(Celsius), get_f32("tempR").map(Celsius))
+            let get_in = |suffix: &str, quantity: Quantity| {
+                self.var_in(sample, &format!("{}{}", prefix, suffix), quantity)
             };
-
-            WheelInfo {
-                suspension_travel: get_f32("shockDefl").map(|v| Millimeters(v * 1000.0)),
-                suspension_travel_avg: None, // shockDefl_ST is an array in iRacing, not a scalar
-                shock_velocity: get_f32("shockVel").map(|v| MillimetersPerSecond(v * 1000.0)),
-                shock_velocity_avg: None, // shockVel_ST is an array in iRacing, not a scalar
-                ride_height: get_f32("rideHeight").map(|v| Millimeters(v * 1000.0)),
-                tyre_pressure: get_f32("pressure").map(Kilopascals),
-                tyre_cold_pressure: get_f32("coldPressure").map(Kilopascals),
-                surface_temp_inner,
-                surface_temp_middle: get_f32("tempCM").map(Celsius),
-                surface_temp_outer,
-                carcass_temp_inner,
-                carcass_temp_middle: get_f32("tempM").map(Celsius),
-                carcass_temp_outer,
-                tyre_wear: None, // iRacing only has per-zone wearL/M/R, no overall wear
-                tyre_wear_inner: if is_left_side {
-                    get_f32("wearR").map(Percentage::new)
-                } else {
-                    get_f32("wearL").map(Percentage::new)
-                },
-                tyre_wear_middle: get_f32("wearM").map(Percentage::new),
-                tyre_wear_outer: if is_left_side {
-                    get_f32("wearL").map(Percentage::new)
-                } else {
-                    get_f32("wearR").map(Percentage::new)
-                },
-                wheel_speed: get_f32("speed").map(Rpm::from_radians_per_sec),
-                slip_ratio: None,
-                slip_angle: None,
-                load: None,
-                brake_line_pressure: get_f32("brakeLinePress").map(Kilopascals),
-                brake_temp: None,
-                tyre_compound: None,
-            }
+            crate::iracing::iracing_wheel(get_f32, get_in, is_left_side)
         }
 
         /// Extract competitor data from CarIdx* arrays and merge with session info.
-        fn extract_competitors(
-            &self,
-            all_vars: &[ValueDescription],
-        ) -> Option<Vec<CompetitorData>> {
-            // Find the CarIdx arrays
-            let find_int_vec = |name: &str| -> Option<&Vec<i32>> {
-                all_vars.iter().find(|v| v.name == name).and_then(|v| {
-                    if let Value::IntVec(ref vec) = v.value {
-                        Some(vec)
-                    } else {
-                        None
-                    }
-                })
+        fn extract_competitors(&self, sample: &[u8]) -> Option<Vec<CompetitorData>> {
+            // Read the CarIdx arrays
+            let find_int_vec = |name: &str| match self.var(sample, name)? {
+                VarValue::IntArray(vec) => Some(vec),
+                _ => None,
             };
-            let find_float_vec = |name: &str| -> Option<&Vec<f32>> {
-                all_vars.iter().find(|v| v.name == name).and_then(|v| {
-                    if let Value::FloatVec(ref vec) = v.value {
-                        Some(vec)
-                    } else {
-                        None
-                    }
-                })
+            let find_float_vec = |name: &str| match self.var(sample, name)? {
+                VarValue::FloatArray(vec) => Some(vec),
+                _ => None,
             };
-            let find_bool_vec = |name: &str| -> Option<&Vec<bool>> {
-                all_vars.iter().find(|v| v.name == name).and_then(|v| {
-                    if let Value::BoolVec(ref vec) = v.value {
-                        Some(vec)
-                    } else {
-                        None
-                    }
-                })
+            // Bool arrays are stored a byte per element
+            let find_bool_vec = |name: &str| match self.var(sample, name)? {
+                VarValue::CharArray(vec) => Some(vec.into_iter().map(|b| b != 0).collect()),
+                _ => None,
             };
 
             let laps = find_int_vec("CarIdxLap");
@@ -695,7 +686,7 @@ mod windows_impl {
             let lap_dist_pct = find_float_vec("CarIdxLapDistPct");
             let positions = find_int_vec("CarIdxPosition");
             let class_positions = find_int_vec("CarIdxClassPosition");
-            let on_pit_road = find_bool_vec("CarIdxOnPitRoad");
+            let on_pit_road: Option<Vec<bool>> = find_bool_vec("CarIdxOnPitRoad");
             let track_surfaces = find_int_vec("CarIdxTrackSurface");
             let best_lap_times = find_float_vec("CarIdxBestLapTime");
             let last_lap_times = find_float_vec("CarIdxLastLapTime");
@@ -706,9 +697,10 @@ mod windows_impl {
 
             // Determine number of entries from any available array
             let count = laps
+                .as_ref()
                 .map(|v| v.len())
-                .or_else(|| positions.map(|v| v.len()))
-                .or_else(|| lap_dist_pct.map(|v| v.len()));
+                .or_else(|| positions.as_ref().map(|v| v.len()))
+                .or_else(|| lap_dist_pct.as_ref().map(|v| v.len()));
 
             let count = match count {
                 Some(c) => c,
@@ -722,7 +714,7 @@ mod windows_impl {
 
             for i in 0..count {
                 // Skip invalid entries (lap == -1 means not in session)
-                let lap_val = laps.and_then(|v| v.get(i).copied());
+                let lap_val = laps.as_ref().and_then(|v| v.get(i).copied());
                 if lap_val == Some(-1) {
                     continue;
                 }
@@ -753,6 +745,7 @@ mod windows_impl {
                         });
 
                 let track_surface_val = track_surfaces
+                    .as_ref()
                     .and_then(|v| v.get(i).copied())
                     .map(crate::iracing::iracing_track_surface);
 
@@ -765,33 +758,47 @@ mod windows_impl {
                     car_number: car_number_str,
                     lap: lap_val.map(|l| l as u32),
                     laps_completed: laps_completed
+                        .as_ref()
                         .and_then(|v| v.get(i).copied())
                         .map(|l| l as u32),
                     lap_distance_pct: lap_dist_pct
+                        .as_ref()
                         .and_then(|v| v.get(i).copied())
                         .map(Percentage::new),
-                    position: positions.and_then(|v| v.get(i).copied()).map(|p| p as u32),
+                    position: positions
+                        .as_ref()
+                        .and_then(|v| v.get(i).copied())
+                        .map(|p| p as u32),
                     class_position: class_positions
+                        .as_ref()
                         .and_then(|v| v.get(i).copied())
                         .map(|p| p as u32),
-                    on_pit_road: on_pit_road.and_then(|v| v.get(i).copied()),
+                    on_pit_road: on_pit_road.as_ref().and_then(|v| v.get(i).copied()),
                     track_surface: track_surface_val,
                     best_lap_time: best_lap_times
+                        .as_ref()
                         .and_then(|v| v.get(i).copied())
                         .and_then(|t| if t > 0.0 { Some(Seconds(t)) } else { None }),
                     last_lap_time: last_lap_times
+                        .as_ref()
                         .and_then(|v| v.get(i).copied())
                         .and_then(|t| if t > 0.0 { Some(Seconds(t)) } else { None }),
-                    estimated_time: est_times.and_then(|v| v.get(i).copied()).and_then(|t| {
-                        if t > 0.0 {
-                            Some(Seconds(t))
-                        } else {
-                            None
-                        }
-                    }),
-                    gear: gears.and_then(|v| v.get(i).copied()).map(|g| g as i8),
-                    rpm: rpms.and_then(|v| v.get(i).copied()).map(Rpm),
+                    estimated_time: est_times.as_ref().and_then(|v| v.get(i).copied()).and_then(
+                        |t| {
+                            if t > 0.0 {
+                                Some(Seconds(t))
+                            } else {
+                                None
+                            }
+                        },
+                    ),
+                    gear: gears
+                        .as_ref()
+                        .and_then(|v| v.get(i).copied())
+                        .map(|g| g as i8),
+                    rpm: rpms.as_ref().and_then(|v| v.get(i).copied()).map(Rpm),
                     steering: steers
+                        .as_ref()
                         .and_then(|v| v.get(i).copied())
                         .map(Degrees::from_radians),
                 });
@@ -805,22 +812,14 @@ mod windows_impl {
         }
     }
 
-    /// Convert an iracing Value to serde_json::Value
-    fn value_to_json(value: &Value) -> serde_json::Value {
+    /// A variable's value for extras, as `.ibt` files give it, but with bool
+    /// arrays as bools rather than the bytes they're stored as
+    fn var_to_json(vh: &VarHeader, value: &VarValue) -> serde_json::Value {
         match value {
-            Value::CHAR(c) => serde_json::json!(*c),
-            Value::BOOL(b) => serde_json::json!(*b),
-            Value::INT(i) => serde_json::json!(*i),
-            Value::BITS(u) => serde_json::json!(*u),
-            Value::FLOAT(f) => serde_json::json!((*f * 10000.0).round() / 10000.0),
-            Value::DOUBLE(d) => serde_json::json!((*d * 10000.0).round() / 10000.0),
-            Value::IntVec(v) => serde_json::json!(v),
-            Value::FloatVec(v) => {
-                let rounded: Vec<f32> = v.iter().map(|x| (x * 10000.0).round() / 10000.0).collect();
-                serde_json::json!(rounded)
+            VarValue::CharArray(v) if vh.var_type == VarType::Bool => {
+                serde_json::json!(v.iter().map(|&b| b != 0).collect::<Vec<_>>())
             }
-            Value::BoolVec(v) => serde_json::json!(v),
-            Value::UNKNOWN(_) => serde_json::Value::Null,
+            value => IbtFile::var_value_to_json(value),
         }
     }
 
@@ -833,62 +832,93 @@ mod windows_impl {
             "iRacing"
         }
 
+        /// The sim is connected, or went away recently enough that it may
+        /// be restarting
         fn detect(&self) -> bool {
-            Connection::new().is_ok()
+            sim_connected()
+                || self
+                    .disconnected_at
+                    .is_some_and(|at| at.elapsed() < RECONNECT_GRACE)
         }
 
-        fn start(&mut self) -> Result<()> {
-            let mut connection = Connection::new()?;
-
-            // Read initial session info
-            if let Ok(details) = connection.session_info() {
-                self.session_details = Some(details);
-                self.session_changed = true;
-            }
-
-            let blocking = connection.blocking()?;
-
-            self.connection = Some(connection);
-            self.blocking = Some(blocking);
+        fn start(&mut self) -> Result<(), AdapterError> {
+            self.connect()?;
             self.active = true;
-
+            self.state = ConnectionState::Waiting;
+            self.disconnected_at = None;
             Ok(())
         }
 
-        fn stop(&mut self) -> Result<()> {
+        fn stop(&mut self) -> Result<(), AdapterError> {
             self.blocking = None;
             self.connection = None;
+            self.memory = None;
+            self.state = ConnectionState::Disconnected;
+            self.disconnected_at = None;
+            self.session_parse = None;
+            self.session_version = None;
             self.session_details = None;
+            self.session_yaml = None;
+            self.variables.clear();
             self.active = false;
             self.session_changed = false;
-            self.last_session_refresh = None;
             Ok(())
         }
 
-        fn read_frame(&mut self) -> Result<Option<TelemetryFrame>> {
+        fn read_frame(&mut self) -> Result<Option<TelemetryFrame>, AdapterError> {
             if !self.active {
                 return Ok(None);
             }
 
-            let blocking = match &self.blocking {
-                Some(b) => b,
-                None => return Ok(None),
+            let Some(blocking) = &self.blocking else {
+                // Reconnect, paced by poll_hint() while disconnected
+                if sim_connected() {
+                    self.connect()?;
+                    self.state = ConnectionState::Waiting;
+                    self.disconnected_at = None;
+                }
+                return Ok(None);
             };
 
             // Block up to 32ms waiting for the next frame from iRacing.
             // iRacing publishes at ~60-90Hz (11-16ms intervals), so 32ms gives
             // ample time to catch the next tick without busy-spinning.
             // The manager loop has no fixed sleep of its own — this blocking call
-            // IS the pacing mechanism.
+            // IS the pacing mechanism. The values themselves are read from our
+            // own mapping, through the variables indexed on connect.
             match blocking.sample(Duration::from_millis(32)) {
-                Ok(sample) => {
+                Ok(_) => {
                     // Refresh session info only when iRacing signals it changed
                     self.maybe_refresh_session_info();
 
-                    let frame = self.convert_sample(&sample);
+                    let mut sample = std::mem::take(&mut self.sample);
+                    let read = self
+                        .memory
+                        .as_ref()
+                        .is_some_and(|memory| memory.read_latest(&mut sample));
+                    let frame = read.then(|| self.convert_sample(&sample));
+                    self.sample = sample;
+                    let Some(frame) = frame else {
+                        return Ok(None);
+                    };
+                    self.state = super::iracing_connection_state(&frame);
                     Ok(Some(frame))
                 }
-                Err(_) => Ok(None),
+                Err(_) => {
+                    // Quiet while paused or loading; gone once the flag drops
+                    if !sim_connected() {
+                        self.disconnect();
+                    }
+                    Ok(None)
+                }
+            }
+        }
+
+        fn poll_hint(&self) -> PollHint {
+            if self.blocking.is_some() {
+                PollHint::Blocking
+            } else {
+                PollHint::Interval(RECONNECT_INTERVAL)
             }
         }
 
@@ -896,29 +926,208 @@ mod windows_impl {
             self.active
         }
 
+        fn capabilities(&self) -> &'static [&'static str] {
+            crate::iracing::IRACING_CAPABILITIES
+        }
+
+        fn platform_requirements(&self) -> &'static [ost_core::PlatformFeature] {
+            &[ost_core::PlatformFeature::SharedMemory]
+        }
+
+        fn connection_state(&self) -> ConnectionState {
+            self.state
+        }
+
+        fn variables(&self) -> Vec<VariableInfo> {
+            self.variables.clone()
+        }
+
         fn session_info_changed(&self) -> bool {
             self.session_changed
         }
+
+        fn session_info_raw(&self) -> Option<String> {
+            self.session_yaml.clone()
+        }
+
+        fn control(&mut self) -> Option<&mut dyn ControlCapable> {
+            Some(self)
+        }
+    }
+
+    impl IRacingAdapter {
+        fn send(&self, broadcast: super::Broadcast) -> Result<()> {
+            if !self.active {
+                anyhow::bail!("iRacing is not running");
+            }
+            send_broadcast(broadcast)
+        }
+    }
+
+    impl ControlCapable for IRacingAdapter {
+        fn pit_command(&mut self, command: PitCommand) -> Result<()> {
+            self.send(super::Broadcast::pit_command(command))
+        }
+
+        fn camera_switch(&mut self, switch: CameraSwitch) -> Result<()> {
+            self.send(super::Broadcast::camera_switch(switch))
+        }
+
+        fn replay_play_speed(&mut self, speed: ReplaySpeed) -> Result<()> {
+            self.send(super::Broadcast::replay_play_speed(speed))
+        }
+
+        fn chat_command(&mut self, command: ChatCommand) -> Result<()> {
+            self.send(super::Broadcast::chat_command(command)?)
+        }
+    }
+
+    /// A read-only view of iRacing's shared memory, unmapped on drop
+    struct SharedMemory {
+        handle: windows::Win32::Foundation::HANDLE,
+        view: windows::Win32::System::Memory::MEMORY_MAPPED_VIEW_ADDRESS,
+    }
+
+    impl SharedMemory {
+        fn open() -> Result<Self> {
+            use windows::core::w;
+            use windows::Win32::Foundation::CloseHandle;
+            use windows::Win32::System::Memory::{MapViewOfFile, OpenFileMappingW, FILE_MAP_READ};
+
+            // SAFETY: plain Win32 calls; the handle is closed if mapping fails
+            unsafe {
+                let handle =
+                    OpenFileMappingW(FILE_MAP_READ.0, false, w!("Local\\IRSDKMemMapFileName"))?;
+                let view = MapViewOfFile(handle, FILE_MAP_READ, 0, 0, 0);
+                if view.Value.is_null() {
+                    let _ = CloseHandle(handle);
+                    anyhow::bail!("failed to map iRacing shared memory");
+                }
+                Ok(Self { handle, view })
+            }
+        }
+
+        /// The `irsdk_header`, through its sample buffer list
+        fn header(&self) -> &[u8] {
+            // SAFETY: the header is at the start of the mapping
+            unsafe {
+                std::slice::from_raw_parts(self.view.Value as *const u8, super::IRACING_HEADER_LEN)
+            }
+        }
+
+        /// Every variable header in the variable header table
+        fn var_headers(&self) -> Vec<VarHeader> {
+            let (offset, count) = super::iracing_var_header_range(self.header());
+            // SAFETY: the view is only read within the bounds given by its own header
+            let table = unsafe {
+                std::slice::from_raw_parts(
+                    (self.view.Value as *const u8).add(offset),
+                    count * VarHeader::LEN,
+                )
+            };
+            super::iracing_var_headers(table)
+        }
+
+        /// Copy the newest sample buffer into `buf`. `false` if there is none,
+        /// or iRacing rewrote it while it was being copied.
+        fn read_latest(&self, buf: &mut Vec<u8>) -> bool {
+            let header = self.header();
+            let len = super::iracing_buf_len(header);
+            let Some((slot, newest)) = super::iracing_var_bufs(header)
+                .into_iter()
+                .enumerate()
+                .max_by_key(|(_, vb)| vb.tick_count)
+            else {
+                return false;
+            };
+            // SAFETY: the view is only read within the bounds given by its own header
+            let data = unsafe {
+                std::slice::from_raw_parts(
+                    (self.view.Value as *const u8).add(newest.buf_offset.max(0) as usize),
+                    len,
+                )
+            };
+            buf.clear();
+            buf.extend_from_slice(data);
+            super::iracing_var_bufs(self.header())
+                .get(slot)
+                .is_some_and(|vb| vb.tick_count == newest.tick_count)
+        }
+
+        /// Copy the session info YAML out. The `iracing` crate only hands out
+        /// the parsed form.
+        fn session_yaml(&self) -> String {
+            let (offset, len) = super::iracing_session_info_range(self.header());
+            // SAFETY: the view is only read within the bounds given by its own header
+            let buf = unsafe {
+                std::slice::from_raw_parts((self.view.Value as *const u8).add(offset), len)
+            };
+            super::iracing_session_yaml(buf)
+        }
+    }
+
+    impl Drop for SharedMemory {
+        fn drop(&mut self) {
+            use windows::Win32::Foundation::CloseHandle;
+            use windows::Win32::System::Memory::UnmapViewOfFile;
+
+            // SAFETY: both were opened in `open()` and are released once
+            unsafe {
+                let _ = UnmapViewOfFile(self.view);
+                let _ = CloseHandle(self.handle);
+            }
+        }
+    }
+
+    /// Whether the sim is running a session, per the shared memory header
+    fn sim_connected() -> bool {
+        SharedMemory::open().is_ok_and(|memory| super::iracing_connected(memory.header()))
+    }
+
+    fn read_session_yaml() -> Result<String> {
+        Ok(SharedMemory::open()?.session_yaml())
+    }
+
+    /// Post an `IRSDK_BROADCASTMSG` window message to the sim
+    fn send_broadcast(broadcast: super::Broadcast) -> Result<()> {
+        use windows::core::s;
+        use windows::Win32::Foundation::{LPARAM, WPARAM};
+        use windows::Win32::UI::WindowsAndMessaging::{
+            RegisterWindowMessageA, SendNotifyMessageA, HWND_BROADCAST,
+        };
+
+        // SAFETY: plain Win32 calls with a static, NUL-terminated message name
+        unsafe {
+            let id = RegisterWindowMessageA(s!("IRSDK_BROADCASTMSG"));
+            if id == 0 {
+                anyhow::bail!("failed to register iRacing broadcast message");
+            }
+            let super::Broadcast { msg, var1, var2 } = broadcast;
+            // msg and var1 are packed into wParam, var2 goes in lParam
+            let wparam = (msg as u32 | ((var1 as u32) << 16)) as usize;
+            SendNotifyMessageA(HWND_BROADCAST, id, WPARAM(wparam), LPARAM(var2 as isize))?;
+        }
+        Ok(())
     }
 }
 
 // Re-export for Windows
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", feature = "iracing"))]
 pub use windows_impl::IRacingAdapter;
 
 // Stub implementation for non-Windows platforms
-#[cfg(not(target_os = "windows"))]
+#[cfg(all(not(target_os = "windows"), feature = "iracing"))]
 #[derive(Default)]
 pub struct IRacingAdapter;
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(all(not(target_os = "windows"), feature = "iracing"))]
 impl IRacingAdapter {
     pub fn new() -> Self {
         Self
     }
 }
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(all(not(target_os = "windows"), feature = "iracing"))]
 impl ost_core::adapter::TelemetryAdapter for IRacingAdapter {
     fn key(&self) -> &str {
         "iracing"
@@ -932,28 +1141,562 @@ impl ost_core::adapter::TelemetryAdapter for IRacingAdapter {
         false
     }
 
-    fn start(&mut self) -> anyhow::Result<()> {
-        anyhow::bail!("iRacing adapter only available on Windows")
+    fn start(&mut self) -> ost_core::adapter::Result<()> {
+        Err(ost_core::AdapterError::Unsupported(
+            "iRacing adapter only available on Windows".into(),
+        ))
     }
 
-    fn stop(&mut self) -> anyhow::Result<()> {
+    fn stop(&mut self) -> ost_core::adapter::Result<()> {
         Ok(())
     }
 
-    fn read_frame(&mut self) -> anyhow::Result<Option<ost_core::model::TelemetryFrame>> {
+    fn read_frame(&mut self) -> ost_core::adapter::Result<Option<ost_core::model::TelemetryFrame>> {
         Ok(None)
     }
 
     fn is_active(&self) -> bool {
         false
     }
+
+    fn capabilities(&self) -> &'static [&'static str] {
+        IRACING_CAPABILITIES
+    }
+
+    fn platform_requirements(&self) -> &'static [ost_core::PlatformFeature] {
+        &[ost_core::PlatformFeature::SharedMemory]
+    }
 }
 
 // =============================================================================
 // Shared iRacing helpers (used by both live adapter and ibt_parser)
 // =============================================================================
 
-use ost_core::model::TrackSurface;
+use crate::ibt_parser::{Quantity, VarBuf, VarHeader};
+use ost_core::adapter::ConnectionState;
+use ost_core::control::{
+    CameraFocus, CameraSwitch, ChatCommand, InvalidCommand, PitCommand, PitTire, ReplaySpeed,
+};
+use ost_core::coords::{Axis, CoordinateTransform};
+use ost_core::model::{
+    EnergyData, EngineData, EngineWarnings, MotionData, PitServices, TelemetryFrame, TrackSurface,
+    Vector3, WheelInfo,
+};
+use ost_core::units::{
+    Bar, Celsius, Degrees, DegreesPerSecond, GForce, Kilopascals, Kilowatts, Liters, LitersPerHour,
+    Megajoules, Meters, MetersPerSecond, MetersPerSecondSquared, Millimeters, MillimetersPerSecond,
+    Percentage, Rpm, Volts,
+};
+
+/// iRacing's car-local axes: X forward, Y left, Z up (right-handed)
+pub(crate) const IRACING_AXES: CoordinateTransform =
+    CoordinateTransform::new(Axis::NegY, Axis::Z, Axis::X);
+
+/// What live frames can contain, and `.ibt` frames a subset of, see
+/// [`TelemetryAdapter::capabilities`](ost_core::adapter::TelemetryAdapter::capabilities)
+#[cfg_attr(not(feature = "iracing"), allow(dead_code))]
+pub(crate) const IRACING_CAPABILITIES: &[&str] = &[
+    "motion.velocity",
+    "motion.acceleration",
+    "motion.g_force",
+    "motion.rotation",
+    "motion.pitch_rate",
+    "motion.yaw_rate",
+    "motion.roll_rate",
+    "motion.latitude",
+    "motion.longitude",
+    "motion.altitude",
+    "motion.heading",
+    "vehicle.speed",
+    "vehicle.rpm",
+    "vehicle.max_rpm",
+    "vehicle.idle_rpm",
+    "vehicle.gear",
+    "vehicle.throttle",
+    "vehicle.brake",
+    "vehicle.clutch",
+    "vehicle.steering_angle",
+    "vehicle.steering_torque",
+    "vehicle.steering_torque_pct",
+    "vehicle.handbrake",
+    "vehicle.shift_indicator",
+    "vehicle.steering_angle_max",
+    "vehicle.on_track",
+    "vehicle.in_garage",
+    "vehicle.track_surface",
+    "vehicle.car_name",
+    "vehicle.car_class",
+    "vehicle.setup_name",
+    "engine",
+    "wheels.*.suspension_travel",
+    "wheels.*.shock_velocity",
+    "wheels.*.ride_height",
+    "wheels.*.tyre_pressure",
+    "wheels.*.tyre_cold_pressure",
+    "wheels.*.surface_temp_inner",
+    "wheels.*.surface_temp_middle",
+    "wheels.*.surface_temp_outer",
+    "wheels.*.carcass_temp_inner",
+    "wheels.*.carcass_temp_middle",
+    "wheels.*.carcass_temp_outer",
+    "wheels.*.tyre_wear_inner",
+    "wheels.*.tyre_wear_middle",
+    "wheels.*.tyre_wear_outer",
+    "wheels.*.wheel_speed",
+    "wheels.*.brake_line_pressure",
+    "timing.current_lap_time",
+    "timing.last_lap_time",
+    "timing.best_lap_time",
+    "timing.best_n_lap_time",
+    "timing.best_n_lap_num",
+    "timing.lap_number",
+    "timing.laps_completed",
+    "timing.lap_distance",
+    "timing.lap_distance_pct",
+    "timing.race_position",
+    "timing.class_position",
+    "timing.num_cars",
+    "timing.delta_best",
+    "timing.delta_best_ok",
+    "timing.delta_session_best",
+    "timing.delta_session_best_ok",
+    "timing.delta_optimal",
+    "timing.delta_optimal_ok",
+    "timing.estimated_lap_time",
+    "timing.race_laps",
+    "session.session_type",
+    "session.session_state",
+    "session.session_time",
+    "session.session_time_remaining",
+    "session.session_time_of_day",
+    "session.session_laps",
+    "session.session_laps_remaining",
+    "session.flags",
+    "session.track_name",
+    "session.track_config",
+    "session.track_length",
+    "session.track_type",
+    "session.track_id",
+    "session.sector_starts",
+    "weather",
+    "pit",
+    "electronics.abs",
+    "electronics.abs_active",
+    "electronics.traction_control",
+    "electronics.traction_control_2",
+    "electronics.brake_bias",
+    "electronics.anti_roll_front",
+    "electronics.anti_roll_rear",
+    "electronics.drs_status",
+    "electronics.throttle_shape",
+    "electronics.shift_light_first_rpm",
+    "electronics.shift_light_shift_rpm",
+    "electronics.shift_light_last_rpm",
+    "electronics.shift_light_blink_rpm",
+    "energy.battery_soc",
+    "energy.battery_energy",
+    "energy.deploy_mode",
+    "energy.deploying",
+    "energy.regen_level",
+    "energy.mgu_k_power",
+    "energy.mgu_h_power",
+    "energy.lap_deployed",
+    "energy.lap_deploy_allowance",
+    "energy.lap_deploy_pct",
+    "competitors",
+    "driver",
+    "extras.iracing",
+];
+
+/// Build [`MotionData`] from iRacing telemetry variables, converted to the
+/// canonical frame (see [`ost_core::coords`]).
+pub(crate) fn iracing_motion(
+    get_f32: impl Fn(&str) -> Option<f32>,
+    get_f64: impl Fn(&str) -> Option<f64>,
+) -> MotionData {
+    let vec3 = |x: &str, y: &str, z: &str| Some([get_f32(x)?, get_f32(y)?, get_f32(z)?]);
+
+    let velocity = vec3("VelocityX", "VelocityY", "VelocityZ")
+        .map(|v| IRACING_AXES.vector(v, MetersPerSecond));
+
+    // Long/Lat/Vert are along iRacing's X/Y/Z
+    let acceleration = vec3("LongAccel", "LatAccel", "VertAccel")
+        .map(|v| IRACING_AXES.vector(v, MetersPerSecondSquared));
+
+    let g_force = acceleration.as_ref().map(|a| {
+        Vector3::new(
+            GForce::from_acceleration(a.x),
+            GForce::from_acceleration(a.y),
+            GForce::from_acceleration(a.z),
+        )
+    });
+
+    // Roll/Pitch/Yaw are about iRacing's X/Y/Z
+    let rotation =
+        vec3("Roll", "Pitch", "Yaw").map(|v| IRACING_AXES.angular_vector(v, Degrees::from_radians));
+    let rates = vec3("RollRate", "PitchRate", "YawRate").map(|v| IRACING_AXES.angular(v));
+
+    // YawNorth: yaw relative to geographic north (radians, CCW positive)
+    // Convert to compass heading (degrees, CW from north)
+    let heading = get_f32("YawNorth").map(|yn| {
+        let deg = -yn * (180.0 / std::f32::consts::PI);
+        Degrees(deg.rem_euclid(360.0))
+    });
+
+    MotionData {
+        position: None,
+        velocity,
+        acceleration,
+        g_force,
+        rotation,
+        pitch_rate: rates.map(|[pitch, _, _]| DegreesPerSecond::from_radians(pitch)),
+        yaw_rate: rates.map(|[_, yaw, _]| DegreesPerSecond::from_radians(yaw)),
+        roll_rate: rates.map(|[_, _, roll]| DegreesPerSecond::from_radians(roll)),
+        angular_acceleration: None,
+        latitude: get_f64("Lat"),
+        longitude: get_f64("Lon"),
+        altitude: get_f32("Alt").map(Meters),
+        heading,
+    }
+}
+
+/// Build [`EnergyData`] from iRacing's hybrid variables (joules and watts),
+/// or `None` for cars without a hybrid system, which don't have them
+pub(crate) fn iracing_energy(get_f32: impl Fn(&str) -> Option<f32>) -> Option<EnergyData> {
+    let battery_soc = get_f32("EnergyERSBatteryPct");
+    let battery_energy = get_f32("EnergyERSBattery");
+    if battery_soc.is_none() && battery_energy.is_none() {
+        return None;
+    }
+    let megajoules = |name: &str| get_f32(name).map(|j| Megajoules(j / 1e6));
+    let kilowatts = |name: &str| get_f32(name).map(|w| Kilowatts(w / 1e3));
+    let mgu_k_power = kilowatts("PowerMGU_K");
+
+    Some(EnergyData {
+        battery_soc: battery_soc.map(Percentage),
+        battery_energy: battery_energy.map(|j| Megajoules(j / 1e6)),
+        deploy_mode: get_f32("dcMGUKDeployMode").map(|m| m.round().max(0.0) as u32),
+        deploying: mgu_k_power.map(|p| p.0 > 0.0),
+        regen_level: get_f32("dcMGUKRegenGain"),
+        mgu_k_power,
+        mgu_h_power: kilowatts("PowerMGU_H"),
+        lap_deployed: megajoules("EnergyBatteryToMGU_KLap"),
+        lap_harvested: None,
+        lap_deploy_allowance: megajoules("EnergyBudgetBattToMGU_KLap"),
+        lap_deploy_pct: get_f32("EnergyMGU_KLapDeployPct").map(Percentage),
+        battery_temp: None,
+        mgu_k_temp: None,
+        mgu_h_temp: None,
+    })
+}
+
+/// Build [`EngineData`] from iRacing's engine variables. `get_in` reads a
+/// variable in the model's unit for a quantity, whatever unit its header
+/// gives; `fuel_capacity` comes from session info, so is left to the caller.
+pub(crate) fn iracing_engine(
+    get_f32: impl Fn(&str) -> Option<f32>,
+    get_u32: impl Fn(&str) -> Option<u32>,
+    get_in: impl Fn(&str, Quantity) -> Option<f32>,
+) -> EngineData {
+    EngineData {
+        water_temp: get_in("WaterTemp", Quantity::Temperature).map(Celsius),
+        oil_temp: get_in("OilTemp", Quantity::Temperature).map(Celsius),
+        oil_pressure: get_in("OilPress", Quantity::Pressure).map(Kilopascals),
+        oil_level: get_f32("OilLevel").map(Percentage::new),
+        fuel_level: get_in("FuelLevel", Quantity::Volume).map(Liters),
+        fuel_level_pct: get_f32("FuelLevelPct").map(Percentage::new),
+        fuel_capacity: None,
+        fuel_pressure: get_in("FuelPress", Quantity::Pressure).map(Kilopascals),
+        fuel_use_per_hour: get_in("FuelUsePerHour", Quantity::VolumeRate).map(LitersPerHour),
+        voltage: get_f32("Voltage").map(Volts),
+        manifold_pressure: get_in("ManifoldPress", Quantity::Pressure).map(|kpa| Bar(kpa / 100.0)),
+        water_level: get_in("WaterLevel", Quantity::Volume).map(Liters),
+        warnings: get_u32("EngineWarnings").map(EngineWarnings::from_iracing_bits),
+    }
+}
+
+/// Build the pit services the player has asked for from iRacing's `dp*`
+/// variables, `get_in` as for [`iracing_engine`]
+pub(crate) fn iracing_requested_services(
+    get_f32: impl Fn(&str) -> Option<f32>,
+    get_in: impl Fn(&str, Quantity) -> Option<f32>,
+) -> PitServices {
+    let requested = |name: &str| get_f32(name).is_some_and(|v| v > 0.0);
+    let pressure = |name: &str| get_in(name, Quantity::Pressure).map(Kilopascals);
+    PitServices {
+        fuel_to_add: get_in("dpFuelFill", Quantity::Volume).map(Liters),
+        change_tyre_fl: requested("dpLFTireChange"),
+        change_tyre_fr: requested("dpRFTireChange"),
+        change_tyre_rl: requested("dpLRTireChange"),
+        change_tyre_rr: requested("dpRRTireChange"),
+        windshield_tearoff: requested("dpWindshieldTearoff"),
+        fast_repair: requested("dpFastRepair"),
+        tyre_pressure_fl: pressure("dpLFTireColdPress"),
+        tyre_pressure_fr: pressure("dpRFTireColdPress"),
+        tyre_pressure_rl: pressure("dpLRTireColdPress"),
+        tyre_pressure_rr: pressure("dpRRTireColdPress"),
+    }
+}
+
+/// Build a wheel's [`WheelInfo`] from its variables, named by what follows
+/// the wheel's prefix (`pressure` for `LFpressure`). `get_in` as for
+/// [`iracing_engine`]; `is_left_side` determines inner/outer mapping for
+/// temperatures and wear.
+pub(crate) fn iracing_wheel(
+    get_f32: impl Fn(&str) -> Option<f32>,
+    get_in: impl Fn(&str, Quantity) -> Option<f32>,
+    is_left_side: bool,
+) -> WheelInfo {
+    let temp = |suffix: &str| get_in(suffix, Quantity::Temperature).map(Celsius);
+    let millimeters =
+        |suffix: &str| get_in(suffix, Quantity::Length).map(|m| Millimeters(m * 1000.0));
+    let wear = |suffix: &str| get_f32(suffix).map(Percentage::new);
+
+    // Inner/outer mapping: for left wheels, CL=outer edge, CR=inner edge.
+    // For right wheels, CL=inner edge, CR=outer edge.
+    let (surface_temp_inner, surface_temp_outer) = if is_left_side {
+        (temp("tempCR"), temp("tempCL"))
+    } else {
+        (temp("tempCL"), temp("tempCR"))
+    };
+    let (carcass_temp_inner, carcass_temp_outer) = if is_left_side {
+        (temp("tempR"), temp("tempL"))
+    } else {
+        (temp("tempL"), temp("tempR"))
+    };
+    let (tyre_wear_inner, tyre_wear_outer) = if is_left_side {
+        (wear("wearR"), wear("wearL"))
+    } else {
+        (wear("wearL"), wear("wearR"))
+    };
+
+    WheelInfo {
+        suspension_travel: millimeters("shockDefl"),
+        suspension_travel_avg: None, // shockDefl_ST is an array in iRacing, not a scalar
+        shock_velocity: get_f32("shockVel").map(|v| MillimetersPerSecond(v * 1000.0)),
+        shock_velocity_avg: None, // shockVel_ST is an array in iRacing, not a scalar
+        ride_height: millimeters("rideHeight"),
+        tyre_pressure: get_in("pressure", Quantity::Pressure).map(Kilopascals),
+        tyre_cold_pressure: get_in("coldPressure", Quantity::Pressure).map(Kilopascals),
+        surface_temp_inner,
+        surface_temp_middle: temp("tempCM"),
+        surface_temp_outer,
+        carcass_temp_inner,
+        carcass_temp_middle: temp("tempM"),
+        carcass_temp_outer,
+        tyre_wear: None, // iRacing only has per-zone wearL/M/R, no overall wear
+        tyre_wear_inner,
+        tyre_wear_middle: wear("wearM"),
+        tyre_wear_outer,
+        wheel_speed: get_f32("speed").map(Rpm::from_radians_per_sec),
+        slip_ratio: None,
+        slip_angle: None,
+        load: None,
+        brake_line_pressure: get_in("brakeLinePress", Quantity::Pressure).map(Kilopascals),
+        brake_temp: None,
+        tyre_compound: None,
+        brake_pad_wear: None,
+        brake_temp_estimated: None,
+    }
+}
+
+/// iRacing reports `SteeringWheelAngle` in radians, positive counter-clockwise
+pub(crate) fn iracing_steering_angle(rad: f32) -> Degrees {
+    Degrees::from_radians(-rad)
+}
+
+/// Offset and length of the session info YAML, from the first 24 bytes of an
+/// `irsdk_header` (shared memory or .ibt file)
+#[cfg_attr(not(all(target_os = "windows", feature = "iracing")), allow(dead_code))]
+pub(crate) fn iracing_session_info_range(header: &[u8]) -> (usize, usize) {
+    let field = |at: usize| {
+        i32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+    };
+    (field(20).max(0) as usize, field(16).max(0) as usize)
+}
+
+/// Offset and count of the variable headers, from the first 32 bytes of an
+/// `irsdk_header`
+#[cfg_attr(not(all(target_os = "windows", feature = "iracing")), allow(dead_code))]
+pub(crate) fn iracing_var_header_range(header: &[u8]) -> (usize, usize) {
+    let field = |at: usize| {
+        i32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+    };
+    (field(28).max(0) as usize, field(24).max(0) as usize)
+}
+
+/// A table of `irsdk_varHeader`s, skipping unreadable ones
+#[cfg_attr(not(all(target_os = "windows", feature = "iracing")), allow(dead_code))]
+pub(crate) fn iracing_var_headers(table: &[u8]) -> Vec<VarHeader> {
+    table
+        .chunks_exact(VarHeader::LEN)
+        .filter_map(|chunk| VarHeader::parse(chunk.try_into().ok()?).ok())
+        .collect()
+}
+
+/// Length of an `irsdk_header` through its four `irsdk_varBuf`s
+#[cfg_attr(not(all(target_os = "windows", feature = "iracing")), allow(dead_code))]
+pub(crate) const IRACING_HEADER_LEN: usize = 112;
+
+/// `bufLen`, the size of each sample buffer, from an `irsdk_header`
+#[cfg_attr(not(all(target_os = "windows", feature = "iracing")), allow(dead_code))]
+pub(crate) fn iracing_buf_len(header: &[u8]) -> usize {
+    i32::from_le_bytes([header[36], header[37], header[38], header[39]]).max(0) as usize
+}
+
+/// The `numBuf` sample buffers in use, from an [`IRACING_HEADER_LEN`]-byte
+/// `irsdk_header`. The sim writes them in turn, so the newest has the
+/// highest tick count.
+#[cfg_attr(not(all(target_os = "windows", feature = "iracing")), allow(dead_code))]
+pub(crate) fn iracing_var_bufs(header: &[u8]) -> Vec<VarBuf> {
+    let field = |at: usize| {
+        i32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+    };
+    (0..field(32).clamp(0, 4) as usize)
+        .map(|i| VarBuf {
+            tick_count: field(48 + i * 16),
+            buf_offset: field(52 + i * 16),
+        })
+        .collect()
+}
+
+/// `sessionInfoUpdate` from the first 24 bytes of an `irsdk_header`, which
+/// iRacing increments whenever it rewrites the session info
+#[cfg_attr(not(all(target_os = "windows", feature = "iracing")), allow(dead_code))]
+pub(crate) fn iracing_session_info_version(header: &[u8]) -> i32 {
+    i32::from_le_bytes([header[12], header[13], header[14], header[15]])
+}
+
+/// Whether the `irsdk_stConnected` bit is set in the status field of an
+/// `irsdk_header`. The sim clears it on exit, though the mapping lingers
+/// while other programs have it open.
+#[cfg_attr(not(all(target_os = "windows", feature = "iracing")), allow(dead_code))]
+pub(crate) fn iracing_connected(header: &[u8]) -> bool {
+    header
+        .get(4..8)
+        .is_some_and(|s| i32::from_le_bytes([s[0], s[1], s[2], s[3]]) & 1 != 0)
+}
+
+/// Connected with the player's car on track, otherwise waiting in the
+/// garage, a replay or spectating
+#[cfg_attr(not(all(target_os = "windows", feature = "iracing")), allow(dead_code))]
+pub(crate) fn iracing_connection_state(frame: &TelemetryFrame) -> ConnectionState {
+    if frame.vehicle.as_ref().and_then(|v| v.on_track) == Some(true) {
+        ConnectionState::Connected
+    } else {
+        ConnectionState::Waiting
+    }
+}
+
+/// Decode the session info YAML block, which is NUL-padded to its buffer size
+pub(crate) fn iracing_session_yaml(buf: &[u8]) -> String {
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..end]).into_owned()
+}
+
+/// An iRacing broadcast message: `irsdk_BroadcastMsg` plus its two arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(all(target_os = "windows", feature = "iracing")), allow(dead_code))]
+pub(crate) struct Broadcast {
+    pub msg: u16,
+    pub var1: u16,
+    pub var2: i32,
+}
+
+#[cfg_attr(not(all(target_os = "windows", feature = "iracing")), allow(dead_code))]
+impl Broadcast {
+    // irsdk_BroadcastMsg values from irsdk_defines.h
+    const CAM_SWITCH_POS: u16 = 0;
+    const CAM_SWITCH_NUM: u16 = 1;
+    const REPLAY_SET_PLAY_SPEED: u16 = 3;
+    const CHAT_COMMAND: u16 = 8;
+    const PIT_COMMAND: u16 = 9;
+
+    /// `irsdk_BroadcastPitCommand` with an `irsdk_PitCommandMode`:
+    /// 0 = Clear, 1 = WS, 2 = Fuel, 3-6 = LF/RF/LR/RR, 7 = ClearTires, 8 = FR,
+    /// 9 = ClearWS, 10 = ClearFR, 11 = ClearFuel. Fuel is in whole liters and
+    /// tire pressure in whole kPa; 0 keeps the amount already selected in the
+    /// pit menu.
+    pub fn pit_command(command: PitCommand) -> Self {
+        let whole = |v: Option<f32>| v.map_or(0, |v| v.round().max(0.0) as i32);
+        let (mode, var2) = match command {
+            PitCommand::Clear => (0, 0),
+            PitCommand::Windscreen => (1, 0),
+            PitCommand::Fuel { liters } => (2, whole(liters)),
+            PitCommand::Tire { tire, pressure_kpa } => {
+                let mode = match tire {
+                    PitTire::LeftFront => 3,
+                    PitTire::RightFront => 4,
+                    PitTire::LeftRear => 5,
+                    PitTire::RightRear => 6,
+                };
+                (mode, whole(pressure_kpa))
+            }
+            PitCommand::ClearTires => (7, 0),
+            PitCommand::FastRepair => (8, 0),
+            PitCommand::ClearWindscreen => (9, 0),
+            PitCommand::ClearFastRepair => (10, 0),
+            PitCommand::ClearFuel => (11, 0),
+        };
+        Self {
+            msg: Self::PIT_COMMAND,
+            var1: mode,
+            var2,
+        }
+    }
+
+    /// `irsdk_BroadcastCamSwitchPos`/`CamSwitchNum`. The leader, incident and
+    /// exiting focus modes are the negative `irsdk_csMode` positions -2, -3
+    /// and -1. Group and camera are packed low/high into var2.
+    pub fn camera_switch(switch: CameraSwitch) -> Self {
+        let (msg, var1) = match switch.focus {
+            CameraFocus::Exiting => (Self::CAM_SWITCH_POS, -1i16 as u16),
+            CameraFocus::Leader => (Self::CAM_SWITCH_POS, -2i16 as u16),
+            CameraFocus::Incident => (Self::CAM_SWITCH_POS, -3i16 as u16),
+            CameraFocus::Position(pos) => (Self::CAM_SWITCH_POS, pos),
+            CameraFocus::CarNumber(num) => (Self::CAM_SWITCH_NUM, num),
+        };
+        Self {
+            msg,
+            var1,
+            var2: (switch.group as u32 | ((switch.camera as u32) << 16)) as i32,
+        }
+    }
+
+    /// `irsdk_BroadcastChatComand` with an `irsdk_ChatCommandMode`:
+    /// 0 = Macro (var2 = macro 1-15), 1 = BeginChat, 2 = Reply, 3 = Cancel
+    pub fn chat_command(command: ChatCommand) -> anyhow::Result<Self> {
+        let (mode, var2) = match command {
+            ChatCommand::Macro {
+                number: number @ 1..=15,
+            } => (0, number as i32),
+            ChatCommand::Macro { number } => {
+                return Err(InvalidCommand(format!(
+                    "iRacing chat macros are numbered 1-15, got {}",
+                    number
+                ))
+                .into())
+            }
+            ChatCommand::BeginChat => (1, 0),
+            ChatCommand::Reply => (2, 0),
+            ChatCommand::Cancel => (3, 0),
+        };
+        Ok(Self {
+            msg: Self::CHAT_COMMAND,
+            var1: mode,
+            var2,
+        })
+    }
+
+    /// `irsdk_BroadcastReplaySetPlaySpeed`
+    pub fn replay_play_speed(speed: ReplaySpeed) -> Self {
+        Self {
+            msg: Self::REPLAY_SET_PLAY_SPEED,
+            var1: speed.speed as u16,
+            var2: speed.slow_motion as i32,
+        }
+    }
+}
 
 /// Map iRacing `irsdk_TrkSurf` enum values to our normalised [`TrackSurface`].
 ///
@@ -985,6 +1728,224 @@ pub(crate) fn iracing_track_surface(idx: i32) -> TrackSurface {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_iracing_energy_only_for_hybrids() {
+        assert!(iracing_energy(|_| None).is_none());
+
+        let vars: std::collections::HashMap<&str, f32> = [
+            ("EnergyERSBatteryPct", 0.62),
+            ("EnergyERSBattery", 2_480_000.0),
+            ("PowerMGU_K", -120_000.0),
+            ("dcMGUKDeployMode", 2.0),
+            ("EnergyBudgetBattToMGU_KLap", 4_000_000.0),
+            ("EnergyMGU_KLapDeployPct", 0.25),
+        ]
+        .into_iter()
+        .collect();
+        let energy = iracing_energy(|name| vars.get(name).copied()).unwrap();
+        assert_eq!(energy.battery_soc.unwrap().0, 0.62);
+        assert_eq!(energy.battery_energy.unwrap().0, 2.48);
+        assert_eq!(energy.mgu_k_power.unwrap().0, -120.0);
+        assert_eq!(energy.deploying, Some(false));
+        assert_eq!(energy.deploy_mode, Some(2));
+        assert_eq!(energy.lap_deploy_allowance.unwrap().0, 4.0);
+        assert_eq!(energy.mgu_h_power, None);
+    }
+
+    #[test]
+    fn test_iracing_motion_right_turn_is_canonical() {
+        // Steady right-hander: centripetal acceleration towards -Y (right),
+        // clockwise yaw (negative about Z-up), wheel turned clockwise
+        let vars: std::collections::HashMap<&str, f32> = [
+            ("VelocityX", 40.0),
+            ("VelocityY", 0.0),
+            ("VelocityZ", 0.0),
+            ("LongAccel", 0.0),
+            ("LatAccel", -19.62),
+            ("VertAccel", 9.81),
+            ("RollRate", 0.0),
+            ("PitchRate", 0.0),
+            ("YawRate", -0.5),
+        ]
+        .into_iter()
+        .collect();
+        let motion = iracing_motion(|name| vars.get(name).copied(), |_| None);
+
+        let g = motion.g_force.unwrap();
+        assert!(
+            (g.x.0 - 2.0).abs() < 0.01,
+            "right turn is positive lateral G"
+        );
+        assert!((g.y.0 - 1.0).abs() < 0.01, "+1 g vertical at rest");
+        assert_eq!(motion.velocity.unwrap().z.0, 40.0, "forward is +z");
+        assert!(
+            motion.yaw_rate.unwrap().0 > 0.0,
+            "right turn is positive yaw"
+        );
+        assert!(
+            iracing_steering_angle(-0.3).0 > 0.0,
+            "clockwise is positive"
+        );
+    }
+
+    #[test]
+    fn test_iracing_session_yaml() {
+        let mut header = [0u8; 24];
+        header[16..20].copy_from_slice(&64i32.to_le_bytes());
+        header[20..24].copy_from_slice(&1024i32.to_le_bytes());
+        assert_eq!(iracing_session_info_range(&header), (1024, 64));
+        header[12..16].copy_from_slice(&7i32.to_le_bytes());
+        assert_eq!(iracing_session_info_version(&header), 7);
+
+        assert_eq!(
+            iracing_session_yaml(b"---\nWeekendInfo:\n\0\0\0"),
+            "---\nWeekendInfo:\n"
+        );
+        assert_eq!(iracing_session_yaml(b"no padding"), "no padding");
+    }
+
+    #[test]
+    fn test_iracing_variables() {
+        use crate::ibt_parser::VarType;
+        use crate::ibt_writer::var_header_bytes;
+
+        let mut header = [0u8; 32];
+        header[24..28].copy_from_slice(&2i32.to_le_bytes());
+        header[28..32].copy_from_slice(&144i32.to_le_bytes());
+        assert_eq!(iracing_var_header_range(&header), (144, 2));
+
+        let mut table = var_header_bytes(&VarHeader::new(
+            "CarIdxLapDistPct",
+            VarType::Float,
+            64,
+            "%",
+            "Percentage distance around lap by car index",
+        ))
+        .to_vec();
+        let mut bad = [0u8; VarHeader::LEN];
+        bad[0] = 99;
+        table.extend_from_slice(&bad);
+        let vars = iracing_var_headers(&table);
+        assert_eq!(
+            vars.iter().map(VarHeader::info).collect::<Vec<_>>(),
+            [ost_core::adapter::VariableInfo {
+                name: "CarIdxLapDistPct".to_string(),
+                var_type: "float".to_string(),
+                count: 64,
+                unit: "%".to_string(),
+                description: "Percentage distance around lap by car index".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_iracing_var_bufs() {
+        let mut header = [0u8; IRACING_HEADER_LEN];
+        header[32..36].copy_from_slice(&3i32.to_le_bytes());
+        header[36..40].copy_from_slice(&4096i32.to_le_bytes());
+        for (i, (tick, offset)) in [(10, 8192), (12, 12288), (11, 16384)].iter().enumerate() {
+            header[48 + i * 16..52 + i * 16].copy_from_slice(&i32::to_le_bytes(*tick));
+            header[52 + i * 16..56 + i * 16].copy_from_slice(&i32::to_le_bytes(*offset));
+        }
+        assert_eq!(iracing_buf_len(&header), 4096);
+
+        let bufs = iracing_var_bufs(&header);
+        assert_eq!(bufs.len(), 3);
+        let newest = bufs.iter().max_by_key(|vb| vb.tick_count).unwrap();
+        assert_eq!((newest.tick_count, newest.buf_offset), (12, 12288));
+
+        // numBuf past the four the header has room for
+        header[32..36].copy_from_slice(&9i32.to_le_bytes());
+        assert_eq!(iracing_var_bufs(&header).len(), 4);
+    }
+
+    #[test]
+    fn test_iracing_connection() {
+        let mut header = [0u8; 24];
+        assert!(!iracing_connected(&header));
+        header[4] = 1;
+        assert!(iracing_connected(&header));
+        assert!(!iracing_connected(&header[..6]));
+
+        let mut frame = TelemetryFrame::new("iRacing");
+        assert_eq!(iracing_connection_state(&frame), ConnectionState::Waiting);
+        frame.vehicle = Some(ost_core::model::VehicleData {
+            on_track: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(iracing_connection_state(&frame), ConnectionState::Connected);
+    }
+
+    #[test]
+    fn test_iracing_pit_command_encoding() {
+        let encode = |cmd| {
+            let b = Broadcast::pit_command(cmd);
+            assert_eq!(b.msg, 9);
+            (b.var1, b.var2)
+        };
+        assert_eq!(encode(PitCommand::Clear), (0, 0));
+        assert_eq!(encode(PitCommand::Fuel { liters: Some(42.6) }), (2, 43));
+        assert_eq!(encode(PitCommand::Fuel { liters: None }), (2, 0));
+        assert_eq!(
+            encode(PitCommand::Tire {
+                tire: PitTire::RightRear,
+                pressure_kpa: Some(165.0),
+            }),
+            (6, 165)
+        );
+        assert_eq!(encode(PitCommand::FastRepair), (8, 0));
+        assert_eq!(encode(PitCommand::ClearFuel), (11, 0));
+    }
+
+    #[test]
+    fn test_iracing_camera_and_replay_encoding() {
+        let b = Broadcast::camera_switch(CameraSwitch {
+            focus: CameraFocus::CarNumber(12),
+            group: 3,
+            camera: 1,
+        });
+        assert_eq!(
+            b,
+            Broadcast {
+                msg: 1,
+                var1: 12,
+                var2: 0x0001_0003
+            }
+        );
+
+        let b = Broadcast::camera_switch(CameraSwitch {
+            focus: CameraFocus::Leader,
+            group: 0,
+            camera: 0,
+        });
+        assert_eq!((b.msg, b.var1 as i16), (0, -2));
+
+        let b = Broadcast::replay_play_speed(ReplaySpeed {
+            speed: -4,
+            slow_motion: false,
+        });
+        assert_eq!((b.msg, b.var1 as i16, b.var2), (3, -4, 0));
+    }
+
+    #[test]
+    fn test_iracing_chat_command_encoding() {
+        let b = Broadcast::chat_command(ChatCommand::Macro { number: 15 }).unwrap();
+        assert_eq!(
+            b,
+            Broadcast {
+                msg: 8,
+                var1: 0,
+                var2: 15
+            }
+        );
+        let b = Broadcast::chat_command(ChatCommand::Reply).unwrap();
+        assert_eq!((b.msg, b.var1), (8, 2));
+        assert!(Broadcast::chat_command(ChatCommand::Macro { number: 0 }).is_err());
+        assert!(Broadcast::chat_command(ChatCommand::Macro { number: 16 })
+            .unwrap_err()
+            .is::<InvalidCommand>());
+    }
+
     #[test]
     fn test_iracing_track_surface_mapping() {
         assert_eq!(iracing_track_surface(-1), TrackSurface::NotInWorld);
@@ -0,0 +1,519 @@
+//! KartKraft adapter
+//!
+//! KartKraft sends one FlatBuffers `Frame` per physics tick over UDP to the
+//! address set under Settings → Telemetry (port 5000 by default;
+//! `OST_KARTKRAFT_PORT` changes the port listened on here). The tables used,
+//! from KartKraft's `Frame.fbs`:
+//!
+//! ```text
+//! table Motion { pitch; roll; yaw; accelerationX; accelerationY; accelerationZ; tractionLoss }
+//! table Dashboard { speed; rpm; steer; throttle; brake; gear:int; pos:int;
+//!                   bestLap; currentLap; lastLap; lapCount:int }
+//! table VehicleConfig { rpmLimit; rpmMax; gearMax:int }
+//! table Frame { timestamp; motion:Motion; dash:Dashboard; vehicleConfig:VehicleConfig }
+//! ```
+//!
+//! Karts have no fuel gauge and most have no gearbox, so those fields stay
+//! `None` rather than reading as an empty tank or neutral.
+//!
+//! With `OST_CAPTURE_DIR` set, datagrams are written to a capture that
+//! [`replay`] turns back into frames.
+
+use crate::capture::{Capture, RecordKind, Recording};
+use anyhow::{bail, Context, Result};
+use ost_core::adapter::{AdapterError, TelemetryAdapter};
+use ost_core::coords::{Axis, CoordinateTransform};
+use ost_core::PlatformFeature;
+use ost_core::{model::*, units::*};
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+const GAME: &str = "KartKraft";
+
+const DEFAULT_PORT: u16 = 5000;
+
+const READ_TIMEOUT: Duration = Duration::from_millis(50);
+/// KartKraft only sends while on track, several frames within this
+const DETECT_TIMEOUT: Duration = Duration::from_millis(50);
+/// No frames for this long while running counts as KartKraft having gone
+const STALE_AFTER: Duration = Duration::from_secs(5);
+
+/// Unreal's axes: X forward, Y right, Z up
+const KARTKRAFT_AXES: CoordinateTransform = CoordinateTransform::new(Axis::Y, Axis::Z, Axis::X);
+
+// Field slots, in schema order
+const FRAME_TIMESTAMP: usize = 0;
+const FRAME_MOTION: usize = 1;
+const FRAME_DASH: usize = 2;
+const FRAME_VEHICLE_CONFIG: usize = 3;
+
+const MOTION_PITCH: usize = 0;
+const MOTION_ROLL: usize = 1;
+const MOTION_YAW: usize = 2;
+const MOTION_ACCELERATION_X: usize = 3;
+
+const DASH_SPEED: usize = 0;
+const DASH_RPM: usize = 1;
+const DASH_STEER: usize = 2;
+const DASH_THROTTLE: usize = 3;
+const DASH_BRAKE: usize = 4;
+const DASH_GEAR: usize = 5;
+const DASH_POS: usize = 6;
+const DASH_BEST_LAP: usize = 7;
+const DASH_CURRENT_LAP: usize = 8;
+const DASH_LAST_LAP: usize = 9;
+const DASH_LAP_COUNT: usize = 10;
+
+const CONFIG_RPM_LIMIT: usize = 0;
+const CONFIG_RPM_MAX: usize = 1;
+const CONFIG_GEAR_MAX: usize = 2;
+
+// =============================================================================
+// FlatBuffers
+// =============================================================================
+
+/// A FlatBuffers table, read with bounds checks. Absent scalar fields read as
+/// their schema default (0), as FlatBuffers writers omit them.
+#[derive(Clone, Copy)]
+struct Table<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    vtable: usize,
+    vtable_len: usize,
+}
+
+fn read<const N: usize>(buf: &[u8], pos: usize) -> Result<[u8; N]> {
+    buf.get(pos..pos + N)
+        .and_then(|b| b.try_into().ok())
+        .context("FlatBuffer offset out of bounds")
+}
+
+impl<'a> Table<'a> {
+    /// The buffer's root table
+    fn root(buf: &'a [u8]) -> Result<Self> {
+        Self::at(buf, u32::from_le_bytes(read(buf, 0)?) as usize)
+    }
+
+    fn at(buf: &'a [u8], pos: usize) -> Result<Self> {
+        let vtable = pos as i64 - i32::from_le_bytes(read(buf, pos)?) as i64;
+        let Ok(vtable) = usize::try_from(vtable) else {
+            bail!("FlatBuffer vtable out of bounds");
+        };
+        let vtable_len = u16::from_le_bytes(read(buf, vtable)?) as usize;
+        Ok(Self {
+            buf,
+            pos,
+            vtable,
+            vtable_len,
+        })
+    }
+
+    /// Where field `slot` is stored, if present
+    fn field(&self, slot: usize) -> Result<Option<usize>> {
+        let entry = 4 + slot * 2;
+        if entry + 2 > self.vtable_len {
+            return Ok(None);
+        }
+        let offset = u16::from_le_bytes(read(self.buf, self.vtable + entry)?) as usize;
+        Ok((offset != 0).then_some(self.pos + offset))
+    }
+
+    fn f32(&self, slot: usize) -> Result<f32> {
+        Ok(match self.field(slot)? {
+            Some(pos) => f32::from_le_bytes(read(self.buf, pos)?),
+            None => 0.0,
+        })
+    }
+
+    fn i32(&self, slot: usize) -> Result<i32> {
+        Ok(match self.field(slot)? {
+            Some(pos) => i32::from_le_bytes(read(self.buf, pos)?),
+            None => 0,
+        })
+    }
+
+    fn table(&self, slot: usize) -> Result<Option<Table<'a>>> {
+        let Some(pos) = self.field(slot)? else {
+            return Ok(None);
+        };
+        let target = pos + u32::from_le_bytes(read(self.buf, pos)?) as usize;
+        Table::at(self.buf, target).map(Some)
+    }
+}
+
+/// Lap times are 0 until set
+fn lap_time(seconds: f32) -> Option<Seconds> {
+    (seconds > 0.0).then_some(Seconds(seconds))
+}
+
+/// A frame from one datagram
+pub(crate) fn parse_frame(packet: &[u8]) -> Result<TelemetryFrame> {
+    let root = Table::root(packet)?;
+    let mut frame = TelemetryFrame::new(GAME);
+    frame.meta.sim_time = Some(root.f32(FRAME_TIMESTAMP)? as f64);
+
+    let config = root.table(FRAME_VEHICLE_CONFIG)?;
+    // Direct-drive karts have no gearbox: no gear rather than neutral
+    let gear_max = match config {
+        Some(c) => c.i32(CONFIG_GEAR_MAX)?,
+        None => 0,
+    };
+    let max_rpm = match config {
+        Some(c) => Some(c.f32(CONFIG_RPM_LIMIT)?)
+            .filter(|r| *r > 0.0)
+            .or(Some(c.f32(CONFIG_RPM_MAX)?).filter(|r| *r > 0.0)),
+        None => None,
+    };
+
+    if let Some(dash) = root.table(FRAME_DASH)? {
+        let gear = dash.i32(DASH_GEAR)?;
+        frame.vehicle = Some(VehicleData {
+            speed: Some(MetersPerSecond(dash.f32(DASH_SPEED)?)),
+            rpm: Some(Rpm(dash.f32(DASH_RPM)?)),
+            max_rpm: max_rpm.map(Rpm),
+            gear: (gear_max > 0).then_some(gear.clamp(-1, i8::MAX as i32) as i8),
+            max_gears: (gear_max > 0).then_some(gear_max.min(u8::MAX as i32) as u8),
+            throttle: Some(Percentage::new(dash.f32(DASH_THROTTLE)?)),
+            brake: Some(Percentage::new(dash.f32(DASH_BRAKE)?)),
+            steering_angle: Some(Degrees(dash.f32(DASH_STEER)?)),
+            ..Default::default()
+        });
+
+        let position = dash.i32(DASH_POS)?;
+        let laps = dash.i32(DASH_LAP_COUNT)?.max(0) as u32;
+        frame.timing = Some(TimingData {
+            current_lap_time: Some(Seconds(dash.f32(DASH_CURRENT_LAP)?.max(0.0))),
+            last_lap_time: lap_time(dash.f32(DASH_LAST_LAP)?),
+            best_lap_time: lap_time(dash.f32(DASH_BEST_LAP)?),
+            lap_number: Some(laps + 1),
+            laps_completed: Some(laps),
+            race_position: (position > 0).then_some(position as u32),
+            ..Default::default()
+        });
+    }
+
+    if let Some(motion) = root.table(FRAME_MOTION)? {
+        let acceleration: [f32; 3] =
+            std::array::from_fn(|i| motion.f32(MOTION_ACCELERATION_X + i).unwrap_or(0.0));
+        let mut g_force = KARTKRAFT_AXES.vector(acceleration, GForce);
+        // Reported without gravity
+        g_force.y.0 += 1.0;
+        frame.motion = Some(MotionData {
+            g_force: Some(g_force),
+            // Unreal's pitch is positive nose up
+            rotation: Some(Vector3::new(
+                Degrees(-motion.f32(MOTION_PITCH)?),
+                Degrees(motion.f32(MOTION_YAW)?),
+                Degrees(motion.f32(MOTION_ROLL)?),
+            )),
+            ..Default::default()
+        });
+    }
+
+    Ok(frame)
+}
+
+/// The frames the adapter would have produced from a captured session
+pub fn replay(capture: &Capture) -> Result<Vec<TelemetryFrame>> {
+    capture.packets().map(parse_frame).collect()
+}
+
+// =============================================================================
+// Adapter
+// =============================================================================
+
+pub struct KartKraftAdapter {
+    addr: SocketAddr,
+    socket: Option<UdpSocket>,
+    last_packet: Option<Instant>,
+    capture: Recording,
+    active: bool,
+}
+
+impl KartKraftAdapter {
+    /// Listen on `OST_KARTKRAFT_PORT`, or KartKraft's default port
+    pub fn new() -> Self {
+        let port = std::env::var("OST_KARTKRAFT_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(DEFAULT_PORT);
+        Self::with_address(SocketAddr::from(([0, 0, 0, 0], port)))
+    }
+
+    /// Listen for frames on `addr`
+    pub fn with_address(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            socket: None,
+            last_packet: None,
+            capture: Recording::new("kartkraft"),
+            active: false,
+        }
+    }
+}
+
+impl Default for KartKraftAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TelemetryAdapter for KartKraftAdapter {
+    fn key(&self) -> &str {
+        "kartkraft"
+    }
+
+    fn name(&self) -> &str {
+        GAME
+    }
+
+    /// Listen briefly for a frame. While running the port is taken, so
+    /// recent frames count instead.
+    fn detect(&self) -> bool {
+        if self.socket.is_some() {
+            return self
+                .last_packet
+                .is_some_and(|at| at.elapsed() < STALE_AFTER);
+        }
+        let probe = || -> Result<bool> {
+            let socket = UdpSocket::bind(self.addr)?;
+            socket.set_read_timeout(Some(DETECT_TIMEOUT))?;
+            let mut buf = [0u8; 1024];
+            let len = socket.recv(&mut buf)?;
+            Ok(parse_frame(&buf[..len]).is_ok())
+        };
+        probe().unwrap_or(false)
+    }
+
+    fn start(&mut self) -> Result<(), AdapterError> {
+        let socket = UdpSocket::bind(self.addr)
+            .with_context(|| format!("Failed to listen for KartKraft on {}", self.addr))?;
+        socket.set_read_timeout(Some(READ_TIMEOUT))?;
+        self.socket = Some(socket);
+        // Detection just saw a frame
+        self.last_packet = Some(Instant::now());
+        self.capture.start()?;
+        self.active = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), AdapterError> {
+        self.socket = None;
+        self.last_packet = None;
+        self.active = false;
+        self.capture.finish()?;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>, AdapterError> {
+        let Some(socket) = &self.socket else {
+            return Ok(None);
+        };
+        let mut buf = [0u8; 1024];
+        let len = match socket.recv(&mut buf) {
+            Ok(len) => len,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(None)
+            }
+            Err(e) => return Err(e.into()),
+        };
+        self.last_packet = Some(Instant::now());
+        self.capture.record(RecordKind::Packet, &buf[..len])?;
+        parse_frame(&buf[..len])
+            .map(Some)
+            .map_err(|e| AdapterError::Parse(format!("{:#}", e)))
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn capabilities(&self) -> &'static [&'static str] {
+        &[
+            "vehicle.speed",
+            "vehicle.rpm",
+            "vehicle.max_rpm",
+            "vehicle.gear",
+            "vehicle.max_gears",
+            "vehicle.throttle",
+            "vehicle.brake",
+            "vehicle.steering_angle",
+            "timing.current_lap_time",
+            "timing.last_lap_time",
+            "timing.best_lap_time",
+            "timing.lap_number",
+            "timing.laps_completed",
+            "timing.race_position",
+            "motion.g_force",
+            "motion.rotation",
+        ]
+    }
+
+    fn platform_requirements(&self) -> &'static [PlatformFeature] {
+        &[PlatformFeature::Udp]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::CaptureWriter;
+    use flatbuffers::{FlatBufferBuilder, WIPOffset};
+
+    struct Dash {
+        speed: f32,
+        rpm: f32,
+        gear: i32,
+        pos: i32,
+        last_lap: f32,
+        lap_count: i32,
+    }
+
+    /// A `Frame` as KartKraft's generated code would write it
+    fn frame_bytes(dash: Option<Dash>, gear_max: Option<i32>) -> Vec<u8> {
+        let mut fbb = FlatBufferBuilder::new();
+        let dash = dash.map(|d| {
+            let start = fbb.start_table();
+            fbb.push_slot::<f32>(4 + DASH_SPEED as u16 * 2, d.speed, 0.0);
+            fbb.push_slot::<f32>(4 + DASH_RPM as u16 * 2, d.rpm, 0.0);
+            fbb.push_slot::<f32>(4 + DASH_THROTTLE as u16 * 2, 1.0, 0.0);
+            fbb.push_slot::<i32>(4 + DASH_GEAR as u16 * 2, d.gear, 0);
+            fbb.push_slot::<i32>(4 + DASH_POS as u16 * 2, d.pos, 0);
+            fbb.push_slot::<f32>(4 + DASH_LAST_LAP as u16 * 2, d.last_lap, 0.0);
+            fbb.push_slot::<i32>(4 + DASH_LAP_COUNT as u16 * 2, d.lap_count, 0);
+            fbb.end_table(start)
+        });
+        let motion = {
+            let start = fbb.start_table();
+            fbb.push_slot::<f32>(4 + MOTION_PITCH as u16 * 2, 2.0, 0.0);
+            // 1.5 g to the right
+            fbb.push_slot::<f32>(4 + (MOTION_ACCELERATION_X as u16 + 1) * 2, 1.5, 0.0);
+            fbb.end_table(start)
+        };
+        let config = gear_max.map(|gear_max| {
+            let start = fbb.start_table();
+            fbb.push_slot::<f32>(4 + CONFIG_RPM_LIMIT as u16 * 2, 16000.0, 0.0);
+            fbb.push_slot::<i32>(4 + CONFIG_GEAR_MAX as u16 * 2, gear_max, 0);
+            fbb.end_table(start)
+        });
+        let start = fbb.start_table();
+        fbb.push_slot::<f32>(4 + FRAME_TIMESTAMP as u16 * 2, 12.5, 0.0);
+        fbb.push_slot_always::<WIPOffset<_>>(4 + FRAME_MOTION as u16 * 2, motion);
+        if let Some(dash) = dash {
+            fbb.push_slot_always::<WIPOffset<_>>(4 + FRAME_DASH as u16 * 2, dash);
+        }
+        if let Some(config) = config {
+            fbb.push_slot_always::<WIPOffset<_>>(4 + FRAME_VEHICLE_CONFIG as u16 * 2, config);
+        }
+        let root = fbb.end_table(start);
+        fbb.finish(root, Some("KKFB"));
+        fbb.finished_data().to_vec()
+    }
+
+    #[test]
+    fn test_kart_without_gearbox_or_fuel() {
+        let bytes = frame_bytes(
+            Some(Dash {
+                speed: 25.0,
+                rpm: 14000.0,
+                gear: 0,
+                pos: 3,
+                last_lap: 48.25,
+                lap_count: 6,
+            }),
+            Some(0),
+        );
+        let frame = parse_frame(&bytes).unwrap();
+        assert_eq!(frame.meta.game, "KartKraft");
+        assert_eq!(frame.meta.sim_time, Some(12.5));
+
+        let vehicle = frame.vehicle.as_ref().unwrap();
+        assert_eq!(vehicle.speed, Some(MetersPerSecond(25.0)));
+        assert_eq!(vehicle.max_rpm, Some(Rpm(16000.0)));
+        assert_eq!(vehicle.throttle, Some(Percentage(1.0)));
+        // No gearbox: unknown, not neutral
+        assert_eq!(vehicle.gear, None);
+        assert_eq!(vehicle.max_gears, None);
+        // No fuel: no engine section at all
+        assert!(frame.engine.is_none());
+
+        let timing = frame.timing.as_ref().unwrap();
+        assert_eq!(timing.race_position, Some(3));
+        assert_eq!(timing.last_lap_time, Some(Seconds(48.25)));
+        assert_eq!(timing.best_lap_time, None);
+        assert_eq!(timing.lap_number, Some(7));
+
+        let motion = frame.motion.as_ref().unwrap();
+        // Unreal's Y is right: a right turn is positive lateral G
+        assert_eq!(motion.g_force.unwrap().x, GForce(1.5));
+        assert_eq!(motion.g_force.unwrap().y, GForce(1.0), "+1 g vertical");
+        assert_eq!(motion.rotation.unwrap().x, Degrees(-2.0));
+
+        // The frame serializes without any of the missing sections
+        let json = serde_json::to_value(&frame).unwrap();
+        assert!(json.get("engine").is_none_or(|e| e.is_null()));
+    }
+
+    #[test]
+    fn test_shifter_kart_and_missing_tables() {
+        let bytes = frame_bytes(
+            Some(Dash {
+                speed: 30.0,
+                rpm: 12000.0,
+                gear: 4,
+                pos: 0,
+                last_lap: 0.0,
+                lap_count: 0,
+            }),
+            Some(6),
+        );
+        let frame = parse_frame(&bytes).unwrap();
+        let vehicle = frame.vehicle.as_ref().unwrap();
+        assert_eq!(vehicle.gear, Some(4));
+        assert_eq!(vehicle.max_gears, Some(6));
+        assert_eq!(frame.timing.as_ref().unwrap().race_position, None);
+
+        // Only motion: no vehicle or timing, rather than zeros
+        let frame = parse_frame(&frame_bytes(None, None)).unwrap();
+        assert!(frame.vehicle.is_none());
+        assert!(frame.timing.is_none());
+        assert!(frame.motion.is_some());
+
+        assert!(parse_frame(&bytes[..bytes.len() / 2]).is_err());
+        assert!(parse_frame(&[0xff; 8]).is_err());
+    }
+
+    #[test]
+    fn test_capture_replay() {
+        let mut writer = CaptureWriter::new(Vec::new(), "kartkraft").unwrap();
+        writer
+            .record(RecordKind::Packet, &frame_bytes(None, Some(6)))
+            .unwrap();
+        let capture = Capture::read(writer.finish().unwrap().as_slice()).unwrap();
+        let frames = replay(&capture).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].meta.sim_time, Some(12.5));
+    }
+
+    #[test]
+    fn test_adapter_receives_frames() {
+        let mut adapter = KartKraftAdapter::with_address("127.0.0.1:0".parse().unwrap());
+        adapter.start().unwrap();
+        let addr = adapter.socket.as_ref().unwrap().local_addr().unwrap();
+        // Running adapters report recent frames instead of probing the port
+        assert!(adapter.detect());
+
+        let game = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let bytes = frame_bytes(None, None);
+        game.send_to(&bytes, addr).unwrap();
+        let frame = adapter.read_frame().unwrap().unwrap();
+        assert_eq!(frame.meta.game, "KartKraft");
+        adapter.stop().unwrap();
+        assert!(!adapter.is_active());
+    }
+}
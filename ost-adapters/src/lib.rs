@@ -1,8 +1,44 @@
 //! Game-specific telemetry adapters for OpenSimTelemetry
+//!
+//! Each game's adapter is behind a Cargo feature of the same name (`iracing`,
+//! which also brings `ibt-tail`, `acc`, `ac`, `kartkraft`, `scs`, `input` and
+//! `demo`), all on by default, so slim builds can leave out what they don't
+//! need. The `.ibt` parser and writer are always built.
 
+#[cfg(feature = "ac")]
+pub mod ac;
+#[cfg(feature = "acc")]
+pub mod acc;
+pub mod capture;
+#[cfg(feature = "demo")]
 pub mod demo;
 pub mod ibt_parser;
+#[cfg(feature = "iracing")]
+pub mod ibt_tail;
+pub mod ibt_writer;
+#[cfg(feature = "input")]
+pub mod input;
 pub mod iracing;
+#[cfg(feature = "kartkraft")]
+pub mod kartkraft;
+pub mod scripted;
+#[cfg(feature = "scs")]
+pub mod scs;
 
+#[cfg(feature = "ac")]
+pub use ac::AcAdapter;
+#[cfg(feature = "acc")]
+pub use acc::AccAdapter;
+#[cfg(feature = "demo")]
 pub use demo::DemoAdapter;
+#[cfg(feature = "iracing")]
+pub use ibt_tail::IbtTailAdapter;
+#[cfg(feature = "input")]
+pub use input::InputAdapter;
+#[cfg(feature = "iracing")]
 pub use iracing::IRacingAdapter;
+#[cfg(feature = "kartkraft")]
+pub use kartkraft::KartKraftAdapter;
+pub use scripted::ScriptedAdapter;
+#[cfg(feature = "scs")]
+pub use scs::ScsAdapter;
@@ -0,0 +1,319 @@
+//! Scripted adapter for integration tests
+//!
+//! Plays back a programmed sequence of detection results and reads (frames,
+//! empty reads, errors, the game exiting) so the manager and API can be
+//! tested against adapter lifecycle edge cases without timing-dependent
+//! synthetic data. A [`ScriptHandle`] taken before the adapter is boxed and
+//! registered queues more steps and reports how it was driven.
+
+use ost_core::{
+    adapter::{AdapterError, ErrorKind, PollHint, TelemetryAdapter},
+    model::TelemetryFrame,
+    PlatformFeature,
+};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// One `read_frame()` outcome
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Return this frame
+    Frame(Box<TelemetryFrame>),
+    /// Return no data
+    Idle,
+    /// Fail the read with an error of this kind
+    Error(ErrorKind, String),
+    /// Return no data and report the game as gone from now on
+    Disconnect,
+}
+
+#[derive(Debug)]
+struct Script {
+    /// Upcoming `detect()` results; the last one repeats
+    detect: VecDeque<bool>,
+    /// Upcoming `start()` failures, consumed one per call
+    start_errors: VecDeque<(ErrorKind, String)>,
+    steps: VecDeque<Step>,
+    /// Return the last frame again once the steps run out
+    repeat_last: bool,
+    last_frame: Option<TelemetryFrame>,
+    starts: u32,
+    stops: u32,
+    reads: u32,
+}
+
+/// Adapter that follows a script instead of a game
+pub struct ScriptedAdapter {
+    key: String,
+    name: String,
+    script: Arc<Mutex<Script>>,
+    poll_interval: Duration,
+    requirements: &'static [PlatformFeature],
+    active: bool,
+}
+
+impl ScriptedAdapter {
+    /// An adapter that is always detected and has nothing to read yet
+    pub fn new(key: impl Into<String>) -> Self {
+        let key = key.into();
+        Self {
+            name: format!("Scripted ({})", key),
+            key,
+            script: Arc::new(Mutex::new(Script {
+                detect: VecDeque::from([true]),
+                start_errors: VecDeque::new(),
+                steps: VecDeque::new(),
+                repeat_last: false,
+                last_frame: None,
+                starts: 0,
+                stops: 0,
+                reads: 0,
+            })),
+            poll_interval: Duration::from_millis(5),
+            requirements: &[],
+            active: false,
+        }
+    }
+
+    /// Results for successive `detect()` calls; the last one repeats
+    pub fn detect_sequence(self, results: impl IntoIterator<Item = bool>) -> Self {
+        let results: VecDeque<bool> = results.into_iter().collect();
+        if !results.is_empty() {
+            self.lock().detect = results;
+        }
+        self
+    }
+
+    /// Fail the next `start()` call
+    pub fn fail_start(self, message: impl Into<String>) -> Self {
+        self.fail_start_as(ErrorKind::Other, message)
+    }
+
+    /// Fail the next `start()` call with an error of this kind
+    pub fn fail_start_as(self, kind: ErrorKind, message: impl Into<String>) -> Self {
+        self.lock().start_errors.push_back((kind, message.into()));
+        self
+    }
+
+    pub fn step(self, step: Step) -> Self {
+        self.lock().steps.push_back(step);
+        self
+    }
+
+    pub fn frame(self, frame: TelemetryFrame) -> Self {
+        self.step(Step::Frame(Box::new(frame)))
+    }
+
+    pub fn frames(self, frames: impl IntoIterator<Item = TelemetryFrame>) -> Self {
+        frames.into_iter().fold(self, Self::frame)
+    }
+
+    pub fn error(self, message: impl Into<String>) -> Self {
+        self.error_as(ErrorKind::Other, message)
+    }
+
+    pub fn error_as(self, kind: ErrorKind, message: impl Into<String>) -> Self {
+        self.step(Step::Error(kind, message.into()))
+    }
+
+    pub fn disconnect(self) -> Self {
+        self.step(Step::Disconnect)
+    }
+
+    /// Keep returning the last frame once the steps run out, like a game
+    /// sitting in the pits
+    pub fn repeat_last(self) -> Self {
+        self.lock().repeat_last = true;
+        self
+    }
+
+    /// How long the manager waits between reads (default 5ms)
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Report needing these from the platform (default nothing)
+    pub fn requiring(mut self, requirements: &'static [PlatformFeature]) -> Self {
+        self.requirements = requirements;
+        self
+    }
+
+    /// A handle to the script that outlives boxing the adapter
+    pub fn handle(&self) -> ScriptHandle {
+        ScriptHandle {
+            script: self.script.clone(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Script> {
+        self.script.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl TelemetryAdapter for ScriptedAdapter {
+    fn key(&self) -> &str {
+        &self.key
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn detect(&self) -> bool {
+        let mut script = self.lock();
+        if script.detect.len() > 1 {
+            script.detect.pop_front().unwrap_or(false)
+        } else {
+            script.detect.front().copied().unwrap_or(false)
+        }
+    }
+
+    fn start(&mut self) -> Result<(), AdapterError> {
+        let mut script = self.lock();
+        script.starts += 1;
+        if let Some((kind, message)) = script.start_errors.pop_front() {
+            return Err(AdapterError::new(kind, message));
+        }
+        drop(script);
+        self.active = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), AdapterError> {
+        self.lock().stops += 1;
+        self.active = false;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>, AdapterError> {
+        let mut script = self.lock();
+        script.reads += 1;
+        match script.steps.pop_front() {
+            Some(Step::Frame(frame)) => {
+                script.last_frame = Some(*frame.clone());
+                Ok(Some(*frame))
+            }
+            Some(Step::Idle) => Ok(None),
+            Some(Step::Error(kind, message)) => Err(AdapterError::new(kind, message)),
+            Some(Step::Disconnect) => {
+                script.detect = VecDeque::from([false]);
+                Ok(None)
+            }
+            None if script.repeat_last => Ok(script.last_frame.clone()),
+            None => Ok(None),
+        }
+    }
+
+    fn poll_hint(&self) -> PollHint {
+        PollHint::Interval(self.poll_interval)
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn platform_requirements(&self) -> &'static [PlatformFeature] {
+        self.requirements
+    }
+}
+
+/// Shared view of a [`ScriptedAdapter`]'s script
+#[derive(Clone)]
+pub struct ScriptHandle {
+    script: Arc<Mutex<Script>>,
+}
+
+impl ScriptHandle {
+    /// Queue another read outcome
+    pub fn push(&self, step: Step) {
+        self.lock().steps.push_back(step);
+    }
+
+    /// Replace the upcoming `detect()` results
+    pub fn set_detected(&self, detected: bool) {
+        self.lock().detect = VecDeque::from([detected]);
+    }
+
+    /// Steps not read yet
+    pub fn remaining(&self) -> usize {
+        self.lock().steps.len()
+    }
+
+    pub fn starts(&self) -> u32 {
+        self.lock().starts
+    }
+
+    pub fn stops(&self) -> u32 {
+        self.lock().stops
+    }
+
+    pub fn reads(&self) -> u32 {
+        self.lock().reads
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Script> {
+        self.script.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(tick: u32) -> TelemetryFrame {
+        let mut frame = TelemetryFrame::new("Scripted");
+        frame.meta.tick = Some(tick);
+        frame
+    }
+
+    #[test]
+    fn test_plays_script_in_order() {
+        let mut adapter = ScriptedAdapter::new("scripted")
+            .detect_sequence([false, true])
+            .fail_start("not ready")
+            .frame(frame(1))
+            .step(Step::Idle)
+            .error("boom")
+            .error_as(ErrorKind::ConnectionLost, "gone")
+            .frame(frame(2))
+            .disconnect();
+        let handle = adapter.handle();
+
+        assert!(!adapter.detect());
+        assert!(adapter.detect());
+        assert!(adapter.detect());
+        assert!(adapter.start().is_err());
+        assert!(!adapter.is_active());
+        adapter.start().unwrap();
+        assert!(adapter.is_active());
+
+        assert_eq!(adapter.read_frame().unwrap().unwrap().meta.tick, Some(1));
+        assert!(adapter.read_frame().unwrap().is_none());
+        assert_eq!(adapter.read_frame().unwrap_err().to_string(), "boom");
+        let error = adapter.read_frame().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ConnectionLost);
+        assert_eq!(adapter.read_frame().unwrap().unwrap().meta.tick, Some(2));
+        assert!(adapter.read_frame().unwrap().is_none());
+        assert!(!adapter.detect());
+        // Nothing left to play
+        assert!(adapter.read_frame().unwrap().is_none());
+
+        handle.push(Step::Frame(Box::new(frame(3))));
+        handle.set_detected(true);
+        assert!(adapter.detect());
+        assert_eq!(adapter.read_frame().unwrap().unwrap().meta.tick, Some(3));
+        adapter.stop().unwrap();
+        assert_eq!((handle.starts(), handle.stops(), handle.reads()), (2, 1, 8));
+    }
+
+    #[test]
+    fn test_repeat_last() {
+        let mut adapter = ScriptedAdapter::new("scripted")
+            .frame(frame(7))
+            .repeat_last();
+        adapter.read_frame().unwrap();
+        assert_eq!(adapter.read_frame().unwrap().unwrap().meta.tick, Some(7));
+    }
+}
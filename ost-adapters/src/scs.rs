@@ -0,0 +1,524 @@
+//! Euro Truck Simulator 2 / American Truck Simulator adapter
+//!
+//! SCS games expose telemetry to plugins through their SDK rather than
+//! publishing it themselves. The widely used `scs-sdk-plugin` (copy
+//! `scs-telemetry.dll` into the game's `bin/win_x64/plugins` folder) copies
+//! the SDK's channels into the `Local\SCSTelemetry` shared memory page on
+//! Windows, which this adapter reads. Offsets follow the plugin's
+//! `scsTelemetryMap_t` (revision 12), whose fields are grouped by type into
+//! zones at fixed offsets: header at 0, `u32` at 40, `i32` at 500, `f32` at
+//! 700, `bool` at 1500, `f64` placement at 2200 and strings at 2300.
+//!
+//! Trucks have no laps or sessions, so `timing` and `session` stay empty.
+//! Navigation, the job and the truck's other wear go into `extras.scs`.
+//!
+//! With `OST_CAPTURE_DIR` set, each page read is written to a capture that
+//! [`replay`] turns back into frames.
+
+use crate::capture::{Capture, RecordKind, Recording};
+use anyhow::{bail, Result};
+use ost_core::adapter::{AdapterError, PollHint, TelemetryAdapter};
+use ost_core::PlatformFeature;
+use ost_core::{model::*, units::*};
+use serde_json::json;
+use std::time::Duration;
+
+const NAME: &str = "Euro Truck Simulator 2 / American Truck Simulator";
+
+/// Bytes read from the page: up to the last string mapped
+pub(crate) const PAGE_LEN: usize = STRINGS + 14 * STRING_LEN;
+
+/// Start of the string zone, `char[64]` each
+const STRINGS: usize = 2300;
+const STRING_LEN: usize = 64;
+
+const PSI_TO_KPA: f32 = 6.894_757;
+
+/// How often to poll the page; the plugin updates it every game frame
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Reads the page by byte offset
+struct Page<'a>(&'a [u8]);
+
+impl<'a> Page<'a> {
+    fn new(bytes: &'a [u8]) -> Result<Self> {
+        if bytes.len() < PAGE_LEN {
+            bail!(
+                "SCS telemetry page is {} bytes, expected {}",
+                bytes.len(),
+                PAGE_LEN
+            );
+        }
+        Ok(Self(bytes))
+    }
+
+    fn bool(&self, offset: usize) -> bool {
+        self.0[offset] != 0
+    }
+
+    fn u32(&self, offset: usize) -> u32 {
+        u32::from_le_bytes(self.0[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn i32(&self, offset: usize) -> i32 {
+        i32::from_le_bytes(self.0[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn u64(&self, offset: usize) -> u64 {
+        u64::from_le_bytes(self.0[offset..offset + 8].try_into().unwrap())
+    }
+
+    fn f32(&self, offset: usize) -> f32 {
+        f32::from_le_bytes(self.0[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn f64(&self, offset: usize) -> f64 {
+        f64::from_le_bytes(self.0[offset..offset + 8].try_into().unwrap())
+    }
+
+    /// The `n`th string of the string zone, `None` when empty
+    fn string(&self, n: usize) -> Option<String> {
+        let offset = STRINGS + n * STRING_LEN;
+        let bytes = &self.0[offset..offset + STRING_LEN];
+        let len = bytes.iter().position(|&b| b == 0).unwrap_or(STRING_LEN);
+        let s = String::from_utf8_lossy(&bytes[..len]).trim().to_string();
+        (!s.is_empty()).then_some(s)
+    }
+}
+
+/// The game the plugin is loaded in
+fn game(id: u32) -> &'static str {
+    match id {
+        1 => "Euro Truck Simulator 2",
+        2 => "American Truck Simulator",
+        _ => "SCS",
+    }
+}
+
+/// `Some` unless the SDK reports 0 for "none"
+fn positive(value: f32) -> Option<f32> {
+    (value > 0.0).then_some(value)
+}
+
+/// A frame from the telemetry page, or `None` while the game isn't driving
+/// (menus, or the plugin not yet initialised)
+pub(crate) fn page_frame(bytes: &[u8]) -> Result<Option<TelemetryFrame>> {
+    let p = Page::new(bytes)?;
+    if !p.bool(0) {
+        return Ok(None);
+    }
+
+    let mut frame = TelemetryFrame::new(game(p.u32(52)));
+    // Simulated time, µs
+    frame.meta.sim_time = Some(p.u64(16) as f64 / 1_000_000.0);
+
+    let car_name = match (p.string(1), p.string(3)) {
+        (Some(brand), Some(name)) => Some(format!("{} {}", brand, name)),
+        (brand, name) => name.or(brand),
+    };
+    let max_rpm = p.f32(740);
+    frame.vehicle = Some(VehicleData {
+        // Negative while reversing
+        speed: Some(MetersPerSecond(p.f32(948).abs())),
+        rpm: Some(Rpm(p.f32(952))),
+        max_rpm: positive(max_rpm).map(Rpm),
+        gear: Some(p.i32(508).clamp(i8::MIN as i32, i8::MAX as i32) as i8),
+        max_gears: Some(p.u32(68).min(u8::MAX as u32) as u8),
+        // What the truck does, after the game's automatic shifting and
+        // cruise control
+        throttle: Some(Percentage::new(p.f32(976))),
+        brake: Some(Percentage::new(p.f32(980))),
+        clutch: Some(Percentage::new(p.f32(984))),
+        handbrake: Some(Percentage(if p.bool(1566) { 1.0 } else { 0.0 })),
+        car_name,
+        ..Default::default()
+    });
+
+    let fuel = p.f32(1000);
+    let capacity = p.f32(704);
+    frame.engine = Some(EngineData {
+        fuel_level: Some(Liters(fuel)),
+        fuel_level_pct: positive(capacity).map(|c| Percentage::new(fuel / c)),
+        fuel_capacity: positive(capacity).map(Liters),
+        oil_pressure: Some(Kilopascals(p.f32(1016) * PSI_TO_KPA)),
+        oil_temp: Some(Celsius(p.f32(1020))),
+        water_temp: Some(Celsius(p.f32(1024))),
+        voltage: Some(Volts(p.f32(1028))),
+        warnings: Some(EngineWarnings {
+            oil_pressure_low: p.bool(1572),
+            water_temp_high: p.bool(1573),
+            engine_stalled: !p.bool(1576),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    frame.damage = Some(DamageData {
+        engine: Some(Percentage::new(p.f32(1036))),
+        transmission: Some(Percentage::new(p.f32(1040))),
+        ..Default::default()
+    });
+
+    // World position, X east and Z south; heading as a fraction of a turn
+    // anticlockwise from north
+    let heading = p.f64(2224) as f32;
+    frame.motion = Some(MotionData {
+        position: Some(Vector3::new(
+            Meters(p.f64(2200) as f32),
+            Meters(p.f64(2208) as f32),
+            Meters(p.f64(2216) as f32),
+        )),
+        heading: Some(Degrees(((1.0 - heading) * 360.0).rem_euclid(360.0))),
+        ..Default::default()
+    });
+
+    let speed_limit = p.f32(1068);
+    let cruise_control = p.f32(988);
+    let scs = json!({
+        "navigation_distance": p.f32(1060),
+        "navigation_eta": p.f32(1064),
+        "speed_limit": positive(speed_limit),
+        "cruise_control_speed": positive(cruise_control),
+        "fuel_range": p.f32(1008),
+        "fuel_consumption": p.f32(1004),
+        "odometer": p.f32(1056),
+        "air_pressure": p.f32(992) * PSI_TO_KPA,
+        "fuel_warning": p.bool(1570),
+        "wear_cabin": p.f32(1044),
+        "wear_chassis": p.f32(1048),
+        "wear_wheels": p.f32(1052),
+        "game_time": p.u32(64),
+        "cargo": p.string(5),
+        "cargo_mass": p.f32(748),
+        "cargo_damage": p.f32(1468),
+        "destination_city": p.string(7),
+        "destination_company": p.string(9),
+        "source_city": p.string(11),
+        "source_company": p.string(13),
+    });
+    frame.extras.insert("scs".to_string(), scs);
+
+    Ok(Some(frame))
+}
+
+/// The frames the adapter would have produced from a captured session
+pub fn replay(capture: &Capture) -> Result<Vec<TelemetryFrame>> {
+    let mut frames = Vec::new();
+    for record in &capture.records {
+        if record.kind == RecordKind::Snapshot {
+            frames.extend(page_frame(&record.data)?);
+        }
+    }
+    Ok(frames)
+}
+
+// =============================================================================
+// Adapter
+// =============================================================================
+
+pub struct ScsAdapter {
+    #[cfg(target_os = "windows")]
+    memory: Option<windows_impl::SharedMemory>,
+    capture: Recording,
+    active: bool,
+}
+
+impl ScsAdapter {
+    pub fn new() -> Self {
+        Self {
+            #[cfg(target_os = "windows")]
+            memory: None,
+            capture: Recording::new("scs"),
+            active: false,
+        }
+    }
+}
+
+impl Default for ScsAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TelemetryAdapter for ScsAdapter {
+    fn key(&self) -> &str {
+        "scs"
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn detect(&self) -> bool {
+        #[cfg(target_os = "windows")]
+        {
+            windows_impl::detect()
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            false
+        }
+    }
+
+    fn start(&mut self) -> Result<(), AdapterError> {
+        #[cfg(not(target_os = "windows"))]
+        return Err(AdapterError::Unsupported(
+            "SCS telemetry adapter only available on Windows".into(),
+        ));
+        #[cfg(target_os = "windows")]
+        {
+            self.memory = Some(windows_impl::SharedMemory::open()?);
+            self.capture.start()?;
+            self.active = true;
+            Ok(())
+        }
+    }
+
+    fn stop(&mut self) -> Result<(), AdapterError> {
+        #[cfg(target_os = "windows")]
+        {
+            self.memory = None;
+        }
+        self.active = false;
+        self.capture.finish()?;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>, AdapterError> {
+        #[cfg(target_os = "windows")]
+        {
+            let Some(page) = self.memory.as_mut().and_then(|m| m.read_if_changed()) else {
+                return Ok(None);
+            };
+            self.capture.record(RecordKind::Snapshot, &page)?;
+            page_frame(&page).map_err(|e| AdapterError::Parse(format!("{:#}", e)))
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            Ok(None)
+        }
+    }
+
+    fn poll_hint(&self) -> PollHint {
+        PollHint::Interval(POLL_INTERVAL)
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn capabilities(&self) -> &'static [&'static str] {
+        &[
+            "vehicle.speed",
+            "vehicle.rpm",
+            "vehicle.max_rpm",
+            "vehicle.gear",
+            "vehicle.max_gears",
+            "vehicle.throttle",
+            "vehicle.brake",
+            "vehicle.clutch",
+            "vehicle.handbrake",
+            "vehicle.car_name",
+            "engine",
+            "damage.engine",
+            "damage.transmission",
+            "motion.position",
+            "motion.heading",
+            "extras.scs",
+        ]
+    }
+
+    fn platform_requirements(&self) -> &'static [PlatformFeature] {
+        &[PlatformFeature::SharedMemory]
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use super::PAGE_LEN;
+    use anyhow::{bail, Result};
+    use windows::core::w;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::System::Memory::{
+        MapViewOfFile, OpenFileMappingW, UnmapViewOfFile, FILE_MAP_READ, MEMORY_MAPPED_VIEW_ADDRESS,
+    };
+
+    /// Whether the plugin's page exists and the SDK is active. The page is
+    /// created inside the game process, so it only exists while a game with
+    /// the plugin runs.
+    pub fn detect() -> bool {
+        SharedMemory::open().is_ok_and(|m| m.bytes()[0] != 0)
+    }
+
+    pub struct SharedMemory {
+        handle: HANDLE,
+        view: MEMORY_MAPPED_VIEW_ADDRESS,
+        /// Game timestamp of the last page read
+        last_time: Option<u64>,
+    }
+
+    // SAFETY: the view is only read, and the handle is only closed on drop
+    unsafe impl Send for SharedMemory {}
+
+    impl SharedMemory {
+        pub fn open() -> Result<Self> {
+            // SAFETY: the handle is closed if mapping fails, and on drop
+            unsafe {
+                let handle = OpenFileMappingW(FILE_MAP_READ.0, false, w!("Local\\SCSTelemetry"))?;
+                let view = MapViewOfFile(handle, FILE_MAP_READ, 0, 0, PAGE_LEN);
+                if view.Value.is_null() {
+                    let _ = CloseHandle(handle);
+                    bail!("Failed to map SCS telemetry shared memory");
+                }
+                Ok(Self {
+                    handle,
+                    view,
+                    last_time: None,
+                })
+            }
+        }
+
+        fn bytes(&self) -> &[u8] {
+            // SAFETY: the view is at least PAGE_LEN bytes, as mapped in `open`
+            unsafe { std::slice::from_raw_parts(self.view.Value as *const u8, PAGE_LEN) }
+        }
+
+        /// A copy of the page, unless the game's timestamp hasn't moved
+        /// (paused, or not yet updated since the last read)
+        pub fn read_if_changed(&mut self) -> Option<Vec<u8>> {
+            let page = self.bytes().to_vec();
+            let time = u64::from_le_bytes(page[8..16].try_into().unwrap());
+            if self.last_time == Some(time) {
+                return None;
+            }
+            self.last_time = Some(time);
+            Some(page)
+        }
+    }
+
+    impl Drop for SharedMemory {
+        fn drop(&mut self) {
+            // SAFETY: both were created in `open` and are released once
+            unsafe {
+                let _ = UnmapViewOfFile(self.view);
+                let _ = CloseHandle(self.handle);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::CaptureWriter;
+
+    /// Writes values into a zeroed page by offset
+    struct PageWriter(Vec<u8>);
+
+    impl PageWriter {
+        fn new() -> Self {
+            Self(vec![0; PAGE_LEN])
+        }
+
+        fn bytes(mut self, offset: usize, v: &[u8]) -> Self {
+            self.0[offset..offset + v.len()].copy_from_slice(v);
+            self
+        }
+
+        fn u32(self, offset: usize, v: u32) -> Self {
+            self.bytes(offset, &v.to_le_bytes())
+        }
+
+        fn f32(self, offset: usize, v: f32) -> Self {
+            self.bytes(offset, &v.to_le_bytes())
+        }
+
+        fn f64(self, offset: usize, v: f64) -> Self {
+            self.bytes(offset, &v.to_le_bytes())
+        }
+
+        fn string(self, n: usize, s: &str) -> Self {
+            self.bytes(STRINGS + n * STRING_LEN, s.as_bytes())
+        }
+    }
+
+    fn page() -> Vec<u8> {
+        PageWriter::new()
+            .bytes(0, &[1])
+            .bytes(16, &90_000_000u64.to_le_bytes())
+            .u32(52, 1)
+            .u32(68, 12)
+            .bytes(508, &(-1i32).to_le_bytes())
+            .f32(704, 400.0)
+            .f32(740, 2500.0)
+            .f32(948, -3.0)
+            .f32(952, 1100.0)
+            .f32(976, 0.4)
+            .f32(1000, 100.0)
+            .f32(1016, 50.0)
+            .f32(1036, 0.02)
+            .f32(1060, 12_000.0)
+            .f32(1064, 540.0)
+            .f32(1068, 22.22)
+            .bytes(1576, &[1])
+            .f64(2200, -1234.5)
+            .f64(2224, 0.25)
+            .string(1, "Scania")
+            .string(3, "S")
+            .string(5, "Apples")
+            .string(7, "Berlin")
+            .0
+    }
+
+    #[test]
+    fn test_page_frame() {
+        let frame = page_frame(&page()).unwrap().unwrap();
+        assert_eq!(frame.meta.game, "Euro Truck Simulator 2");
+        assert_eq!(frame.meta.sim_time, Some(90.0));
+
+        let vehicle = frame.vehicle.as_ref().unwrap();
+        assert_eq!(vehicle.speed, Some(MetersPerSecond(3.0)));
+        assert_eq!(vehicle.gear, Some(-1));
+        assert_eq!(vehicle.max_gears, Some(12));
+        assert_eq!(vehicle.car_name.as_deref(), Some("Scania S"));
+
+        let engine = frame.engine.as_ref().unwrap();
+        assert_eq!(engine.fuel_level_pct, Some(Percentage(0.25)));
+        assert!((engine.oil_pressure.unwrap().0 - 344.738).abs() < 0.01);
+        assert!(!engine.warnings.unwrap().engine_stalled);
+        assert_eq!(
+            frame.damage.as_ref().unwrap().engine,
+            Some(Percentage(0.02))
+        );
+
+        let motion = frame.motion.as_ref().unwrap();
+        assert_eq!(motion.position.unwrap().x, Meters(-1234.5));
+        // A quarter turn anticlockwise from north is west
+        assert_eq!(motion.heading, Some(Degrees(270.0)));
+
+        let scs = &frame.extras["scs"];
+        assert_eq!(scs["navigation_eta"], 540.0);
+        assert_eq!(scs["destination_city"], "Berlin");
+        assert_eq!(scs["source_city"], serde_json::Value::Null);
+        assert_eq!(scs["cruise_control_speed"], serde_json::Value::Null);
+
+        // Not driving
+        let mut idle = page();
+        idle[0] = 0;
+        assert!(page_frame(&idle).unwrap().is_none());
+        assert!(page_frame(&idle[..100]).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_replay() {
+        let mut writer = CaptureWriter::new(Vec::new(), "scs").unwrap();
+        writer.record(RecordKind::Snapshot, &page()).unwrap();
+        let capture = Capture::read(writer.finish().unwrap().as_slice()).unwrap();
+        let frames = replay(&capture).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(
+            frames[0].extras["scs"]["cargo"],
+            serde_json::Value::from("Apples")
+        );
+    }
+}
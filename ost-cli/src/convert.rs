@@ -0,0 +1,623 @@
+//! Offline .ibt conversion
+//!
+//! Reads samples through `IbtFile`, converts them to `TelemetryFrame`s and
+//! writes them out as NDJSON, CSV or Parquet, optionally restricted by metric
+//! mask and lap range.
+
+use anyhow::{anyhow, bail, Context, Result};
+use ost_adapters::ibt_parser::{IbtFile, LapInfo};
+use ost_core::model::MetricMask;
+use parquet::basic::{Compression, LogicalType, Repetition, Type as PhysicalType, ZstdLevel};
+use parquet::column::writer::ColumnWriter;
+use parquet::data_type::ByteArray;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use std::collections::HashMap;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Number of samples read from disk per batch
+const BATCH_SIZE: usize = 1000;
+
+/// Frames per Parquet row group
+const ROW_GROUP_SIZE: usize = 10_000;
+
+/// Output file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One JSON-serialized `TelemetryFrame` per line
+    Ndjson,
+    /// Flattened dotted-path columns, one row per frame
+    Csv,
+    /// The CSV columns, typed and zstd-compressed
+    Parquet,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            "csv" => Ok(OutputFormat::Csv),
+            "parquet" => Ok(OutputFormat::Parquet),
+            other => bail!(
+                "Unknown output format '{}' (expected ndjson, csv or parquet)",
+                other
+            ),
+        }
+    }
+}
+
+/// Inclusive range of lap numbers, e.g. `3` or `3-5`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LapRange {
+    pub first: i32,
+    pub last: i32,
+}
+
+impl FromStr for LapRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = |v: &str| -> Result<i32> {
+            v.trim()
+                .parse::<i32>()
+                .map_err(|_| anyhow!("Invalid lap number '{}'", v.trim()))
+        };
+        let (first, last) = match s.split_once('-') {
+            Some((a, b)) => (parse(a)?, parse(b)?),
+            None => {
+                let n = parse(s)?;
+                (n, n)
+            }
+        };
+        if first > last {
+            bail!("Invalid lap range '{}': start is after end", s);
+        }
+        Ok(LapRange { first, last })
+    }
+}
+
+impl LapRange {
+    /// Resolve this lap range to a half-open sample range `[start, end)` using
+    /// the file's lap index. Returns `None` if no lap in the index falls in range.
+    pub fn sample_range(&self, laps: &[LapInfo], record_count: usize) -> Option<(usize, usize)> {
+        let first_idx = laps
+            .iter()
+            .position(|l| l.lap_number >= self.first && l.lap_number <= self.last)?;
+        let start = laps[first_idx].start_frame;
+        let end = laps[first_idx..]
+            .iter()
+            .find(|l| l.lap_number > self.last)
+            .map(|l| l.start_frame)
+            .unwrap_or(record_count);
+        Some((start, end))
+    }
+}
+
+/// Options controlling a single conversion
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    pub format: OutputFormat,
+    pub metric_mask: Option<MetricMask>,
+    pub laps: Option<LapRange>,
+}
+
+/// Convert `ibt` into `out`, returning the number of frames written.
+pub fn convert<W: Write + Send>(ibt: &mut IbtFile, opts: &ConvertOptions, out: W) -> Result<usize> {
+    let total = ibt.record_count();
+    let range = match opts.laps {
+        Some(range) => {
+            let laps = ibt.build_lap_index()?;
+            range.sample_range(&laps, total).ok_or_else(|| {
+                anyhow!(
+                    "No laps in range {}-{} (file has {} laps)",
+                    range.first,
+                    range.last,
+                    laps.len()
+                )
+            })?
+        }
+        None => (0, total),
+    };
+
+    let mask = opts.metric_mask.as_ref();
+    // Columns come from a first pass over the frames, so fields that only
+    // show up partway through (e.g. once the car leaves the pits) get one too
+    let mut writer: Box<dyn FrameWriter> = match opts.format {
+        OutputFormat::Ndjson => Box::new(NdjsonWriter { out }),
+        OutputFormat::Csv => Box::new(CsvWriter::new(out, scan_columns(ibt, mask, range)?)?),
+        OutputFormat::Parquet => {
+            Box::new(ParquetWriter::new(out, scan_columns(ibt, mask, range)?)?)
+        }
+    };
+    let written = for_each_frame(ibt, mask, range, |frame| writer.write_frame(frame))?;
+    writer.finish()?;
+
+    Ok(written)
+}
+
+/// Call `f` with each frame of the sample range `[start, end)`, masked,
+/// returning how many there were
+fn for_each_frame(
+    ibt: &mut IbtFile,
+    mask: Option<&MetricMask>,
+    (start, end): (usize, usize),
+    mut f: impl FnMut(&serde_json::Value) -> Result<()>,
+) -> Result<usize> {
+    let mut count = 0;
+    let mut pos = start;
+    while pos < end {
+        let batch = BATCH_SIZE.min(end - pos);
+        for frame in ibt.read_frames_range(pos, batch)? {
+            f(&frame.to_json_value_filtered(mask)?)?;
+            count += 1;
+        }
+        pos += batch;
+    }
+    Ok(count)
+}
+
+/// Every column of the frames in `range`, in the order first seen
+fn scan_columns(
+    ibt: &mut IbtFile,
+    mask: Option<&MetricMask>,
+    range: (usize, usize),
+) -> Result<Vec<Column>> {
+    let mut columns = Columns::default();
+    for_each_frame(ibt, mask, range, |frame| {
+        columns.add(frame);
+        Ok(())
+    })?;
+    Ok(columns.columns)
+}
+
+/// What a column holds: the narrowest type fitting every value in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Boolean,
+    Int64,
+    Double,
+    /// Strings, arrays, or values of more than one type, as CSV cells
+    Text,
+}
+
+impl ColumnKind {
+    fn of(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Bool(_) => ColumnKind::Boolean,
+            serde_json::Value::Number(n) if n.is_i64() => ColumnKind::Int64,
+            serde_json::Value::Number(_) => ColumnKind::Double,
+            _ => ColumnKind::Text,
+        }
+    }
+
+    /// The kind holding values of both
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (ColumnKind::Int64, ColumnKind::Double) | (ColumnKind::Double, ColumnKind::Int64) => {
+                ColumnKind::Double
+            }
+            _ => ColumnKind::Text,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Column {
+    name: String,
+    kind: ColumnKind,
+}
+
+/// Columns of flattened frames, in the order first seen
+#[derive(Default)]
+struct Columns {
+    columns: Vec<Column>,
+    index: HashMap<String, usize>,
+}
+
+impl Columns {
+    fn add(&mut self, frame: &serde_json::Value) {
+        let mut flat = Vec::new();
+        flatten_json("", frame, &mut flat);
+        for (name, value) in flat {
+            let kind = ColumnKind::of(value);
+            match self.index.get(&name) {
+                Some(&i) => self.columns[i].kind = self.columns[i].kind.merge(kind),
+                None => {
+                    self.index.insert(name.clone(), self.columns.len());
+                    self.columns.push(Column { name, kind });
+                }
+            }
+        }
+    }
+}
+
+trait FrameWriter {
+    fn write_frame(&mut self, frame: &serde_json::Value) -> Result<()>;
+    fn finish(&mut self) -> Result<()>;
+}
+
+struct NdjsonWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> FrameWriter for NdjsonWriter<W> {
+    fn write_frame(&mut self, frame: &serde_json::Value) -> Result<()> {
+        serde_json::to_writer(&mut self.out, frame)?;
+        self.out.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// CSV writer with a column for every field in the input; fields a frame
+/// lacks are left empty.
+struct CsvWriter<W: Write> {
+    out: W,
+    columns: Vec<Column>,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Start the file with the header for `columns`
+    fn new(mut out: W, columns: Vec<Column>) -> Result<Self> {
+        let header: Vec<String> = columns.iter().map(|c| csv_escape(&c.name)).collect();
+        writeln!(out, "{}", header.join(","))?;
+        Ok(Self { out, columns })
+    }
+}
+
+impl<W: Write> FrameWriter for CsvWriter<W> {
+    fn write_frame(&mut self, frame: &serde_json::Value) -> Result<()> {
+        let mut flat = Vec::new();
+        flatten_json("", frame, &mut flat);
+
+        let values: HashMap<String, &serde_json::Value> = flat.into_iter().collect();
+        let row: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| {
+                values
+                    .get(&c.name)
+                    .map(|v| csv_escape(&cell(v)))
+                    .unwrap_or_default()
+            })
+            .collect();
+        writeln!(self.out, "{}", row.join(","))?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Parquet writer with an optional column for every field in the input,
+/// typed by the values found in it
+struct ParquetWriter<W: Write + Send> {
+    writer: SerializedFileWriter<W>,
+    columns: Vec<Column>,
+    /// The row group being filled, by column; `None` where a frame lacks the
+    /// field
+    cells: Vec<Vec<Option<serde_json::Value>>>,
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    fn new(out: W, columns: Vec<Column>) -> Result<Self> {
+        let fields = columns
+            .iter()
+            .map(|c| {
+                let (physical, logical) = match c.kind {
+                    ColumnKind::Boolean => (PhysicalType::BOOLEAN, None),
+                    ColumnKind::Int64 => (PhysicalType::INT64, None),
+                    ColumnKind::Double => (PhysicalType::DOUBLE, None),
+                    ColumnKind::Text => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+                };
+                Type::primitive_type_builder(&c.name, physical)
+                    .with_repetition(Repetition::OPTIONAL)
+                    .with_logical_type(logical)
+                    .build()
+                    .map(Arc::new)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let schema = Type::group_type_builder("frame")
+            .with_fields(fields)
+            .build()?;
+        let props = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .build();
+        let writer = SerializedFileWriter::new(out, Arc::new(schema), Arc::new(props))?;
+        Ok(Self {
+            writer,
+            cells: vec![Vec::new(); columns.len()],
+            columns,
+        })
+    }
+
+    /// Write the buffered frames out as a row group
+    fn flush_row_group(&mut self) -> Result<()> {
+        if self.cells.first().is_none_or(Vec::is_empty) {
+            return Ok(());
+        }
+        let mut group = self.writer.next_row_group()?;
+        for cells in &mut self.cells {
+            let mut column = group
+                .next_column()?
+                .context("Parquet schema has fewer columns than the frames")?;
+            let levels: Vec<i16> = cells.iter().map(|c| i16::from(c.is_some())).collect();
+            let values = cells.iter().flatten();
+            match column.untyped() {
+                ColumnWriter::BoolColumnWriter(w) => {
+                    let values: Vec<bool> = values.filter_map(|v| v.as_bool()).collect();
+                    w.write_batch(&values, Some(&levels), None)?
+                }
+                ColumnWriter::Int64ColumnWriter(w) => {
+                    let values: Vec<i64> = values.filter_map(|v| v.as_i64()).collect();
+                    w.write_batch(&values, Some(&levels), None)?
+                }
+                ColumnWriter::DoubleColumnWriter(w) => {
+                    let values: Vec<f64> = values.filter_map(|v| v.as_f64()).collect();
+                    w.write_batch(&values, Some(&levels), None)?
+                }
+                ColumnWriter::ByteArrayColumnWriter(w) => {
+                    let values: Vec<ByteArray> =
+                        values.map(|v| cell(v).into_bytes().into()).collect();
+                    w.write_batch(&values, Some(&levels), None)?
+                }
+                _ => bail!("Unexpected Parquet column type"),
+            };
+            column.close()?;
+            cells.clear();
+        }
+        group.close()?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> FrameWriter for ParquetWriter<W> {
+    fn write_frame(&mut self, frame: &serde_json::Value) -> Result<()> {
+        let mut flat = Vec::new();
+        flatten_json("", frame, &mut flat);
+
+        let mut values: HashMap<String, &serde_json::Value> = flat.into_iter().collect();
+        for (column, cells) in self.columns.iter().zip(&mut self.cells) {
+            cells.push(values.remove(&column.name).cloned());
+        }
+        if self
+            .cells
+            .first()
+            .is_some_and(|c| c.len() >= ROW_GROUP_SIZE)
+        {
+            self.flush_row_group()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.flush_row_group()?;
+        self.writer.finish()?;
+        self.writer.inner_mut().flush()?;
+        Ok(())
+    }
+}
+
+/// Flatten nested JSON objects into dotted-path `(column, value)` pairs.
+/// Arrays are kept whole as a single value; nulls are skipped.
+fn flatten_json<'a>(
+    prefix: &str,
+    value: &'a serde_json::Value,
+    out: &mut Vec<(String, &'a serde_json::Value)>,
+) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                let key = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                flatten_json(&key, v, out);
+            }
+        }
+        serde_json::Value::Null => {}
+        other => out.push((prefix.to_string(), other)),
+    }
+}
+
+/// A flattened value as a CSV cell: strings as they are, anything else as
+/// JSON text
+fn cell(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lap(lap_number: i32, start_frame: usize) -> LapInfo {
+        LapInfo {
+            lap_number,
+            start_frame,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(
+            "NDJSON".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Ndjson
+        );
+        assert_eq!("csv".parse::<OutputFormat>().unwrap(), OutputFormat::Csv);
+        assert_eq!(
+            "parquet".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Parquet
+        );
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_parse_lap_range() {
+        assert_eq!(
+            "3".parse::<LapRange>().unwrap(),
+            LapRange { first: 3, last: 3 }
+        );
+        assert_eq!(
+            "2-5".parse::<LapRange>().unwrap(),
+            LapRange { first: 2, last: 5 }
+        );
+        assert!("5-2".parse::<LapRange>().is_err());
+        assert!("abc".parse::<LapRange>().is_err());
+    }
+
+    #[test]
+    fn test_lap_range_to_samples() {
+        let laps = vec![lap(0, 0), lap(1, 100), lap(2, 250), lap(3, 400)];
+
+        let range = LapRange { first: 1, last: 2 };
+        assert_eq!(range.sample_range(&laps, 500), Some((100, 400)));
+
+        // Last lap runs to end of file
+        let range = LapRange { first: 3, last: 3 };
+        assert_eq!(range.sample_range(&laps, 500), Some((400, 500)));
+
+        let range = LapRange { first: 7, last: 9 };
+        assert_eq!(range.sample_range(&laps, 500), None);
+    }
+
+    #[test]
+    fn test_flatten_json() {
+        let value = serde_json::json!({
+            "meta": {"game": "iRacing"},
+            "vehicle": {"speed": 42.5, "gear": null, "car_name": "a,b"},
+            "competitors": [{"car_index": 1}]
+        });
+        let mut flat = Vec::new();
+        flatten_json("", &value, &mut flat);
+        let map: HashMap<_, _> = flat.into_iter().collect();
+
+        assert_eq!(cell(map["meta.game"]), "iRacing");
+        assert_eq!(cell(map["vehicle.speed"]), "42.5");
+        assert!(!map.contains_key("vehicle.gear"));
+        assert_eq!(cell(map["competitors"]), "[{\"car_index\":1}]");
+        assert_eq!(csv_escape(&cell(map["vehicle.car_name"])), "\"a,b\"");
+    }
+
+    #[test]
+    fn test_columns_from_every_frame() {
+        let mut columns = Columns::default();
+        columns.add(&serde_json::json!({"a": 1, "b": true}));
+        // Fields first seen in a later frame get a column too
+        columns.add(&serde_json::json!({"a": 2.5, "b": "x", "c": {"d": 3}}));
+        let columns: Vec<_> = columns
+            .columns
+            .into_iter()
+            .map(|c| (c.name, c.kind))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("a".to_string(), ColumnKind::Double),
+                ("b".to_string(), ColumnKind::Text),
+                ("c.d".to_string(), ColumnKind::Int64),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csv_writer_fills_missing_columns() {
+        let mut buf = Vec::new();
+        {
+            let frames = [
+                serde_json::json!({"a": 1}),
+                serde_json::json!({"a": 2, "b": {"c": "x"}}),
+            ];
+            let mut columns = Columns::default();
+            frames.iter().for_each(|f| columns.add(f));
+            let mut writer = CsvWriter::new(&mut buf, columns.columns).unwrap();
+            for frame in &frames {
+                writer.write_frame(frame).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+        let text = String::from_utf8(buf).unwrap();
+        assert_eq!(text, "a,b.c\n1,\n2,x\n");
+    }
+
+    #[test]
+    fn test_parquet_writer() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::record::Field;
+
+        let frames = [
+            serde_json::json!({"meta": {"game": "iRacing"}, "vehicle": {"gear": 0}}),
+            serde_json::json!({"meta": {"game": "iRacing"}, "vehicle": {"gear": 1, "speed": 12.5}}),
+        ];
+        let mut columns = Columns::default();
+        frames.iter().for_each(|f| columns.add(f));
+        let path = std::env::temp_dir().join(format!("ost-cli-{}.parquet", std::process::id()));
+        {
+            let file = std::fs::File::create(&path).unwrap();
+            let mut writer = ParquetWriter::new(file, columns.columns).unwrap();
+            for frame in &frames {
+                writer.write_frame(frame).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let rows: Vec<Vec<(String, Field)>> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                row.unwrap()
+                    .get_column_iter()
+                    .map(|(n, f)| (n.clone(), f.clone()))
+                    .collect()
+            })
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[0],
+            [
+                ("meta.game".to_string(), Field::Str("iRacing".to_string())),
+                ("vehicle.gear".to_string(), Field::Long(0)),
+                ("vehicle.speed".to_string(), Field::Null),
+            ]
+        );
+        assert_eq!(rows[1][1].1, Field::Long(1));
+        assert_eq!(rows[1][2].1, Field::Double(12.5));
+    }
+}
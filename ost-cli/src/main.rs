@@ -0,0 +1,159 @@
+//! OpenSimTelemetry CLI
+//!
+//! Offline conversion of iRacing .ibt files without running the server.
+
+mod convert;
+
+use anyhow::{bail, Context, Result};
+use convert::{ConvertOptions, LapRange, OutputFormat};
+use ost_adapters::ibt_parser::IbtFile;
+use ost_core::model::MetricMask;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "\
+Usage:
+  ost-cli convert <input.ibt> [options]
+  ost-cli laps <input.ibt>
+
+Convert options:
+  -o, --output <path>        Output file ('-' for stdout). Defaults to the input
+                             path with the format's extension. A .zst/.zstd
+                             suffix compresses the output with zstd, a .gz
+                             suffix with gzip (not for parquet, which
+                             compresses its columns itself).
+  -f, --format <fmt>         ndjson (default), csv or parquet
+  -m, --metric-mask <list>   Comma-separated sections/fields, e.g. vehicle,timing
+  -l, --laps <N|A-B>         Only export the given lap or inclusive lap range
+";
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("convert") => run_convert(&args[1..]),
+        Some("laps") => run_laps(&args[1..]),
+        Some("-h") | Some("--help") | Some("help") => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => bail!("Unknown command '{}'\n\n{}", other, USAGE),
+        None => bail!("No command given\n\n{}", USAGE),
+    }
+}
+
+fn run_convert(args: &[String]) -> Result<()> {
+    let mut input: Option<PathBuf> = None;
+    let mut output: Option<String> = None;
+    let mut format = OutputFormat::Ndjson;
+    let mut metric_mask: Option<MetricMask> = None;
+    let mut laps: Option<LapRange> = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .with_context(|| format!("Missing value for {}", name))
+        };
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(value(arg)?),
+            "-f" | "--format" => format = value(arg)?.parse()?,
+            "-m" | "--metric-mask" => metric_mask = Some(MetricMask::parse(&value(arg)?)),
+            "-l" | "--laps" => laps = Some(value(arg)?.parse()?),
+            s if s.starts_with('-') => bail!("Unknown option '{}'\n\n{}", s, USAGE),
+            s => {
+                if input.is_some() {
+                    bail!("Unexpected argument '{}'", s);
+                }
+                input = Some(PathBuf::from(s));
+            }
+        }
+    }
+
+    let input = input.with_context(|| format!("No input file given\n\n{}", USAGE))?;
+    let mut ibt =
+        IbtFile::open(&input).with_context(|| format!("Failed to open {}", input.display()))?;
+
+    let opts = ConvertOptions {
+        format,
+        metric_mask,
+        laps,
+    };
+
+    let output = output.unwrap_or_else(|| {
+        input
+            .with_extension(format.extension())
+            .to_string_lossy()
+            .into_owned()
+    });
+
+    let written = if output == "-" {
+        convert::convert(&mut ibt, &opts, BufWriter::new(std::io::stdout()))?
+    } else {
+        let path = Path::new(&output);
+        let extension = path.extension().and_then(|e| e.to_str());
+        if format == OutputFormat::Parquet && matches!(extension, Some("zst" | "zstd" | "gz")) {
+            bail!("Parquet output is compressed already; drop the .zst/.gz suffix");
+        }
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        let out = BufWriter::new(file);
+        match extension {
+            Some("zst") | Some("zstd") => {
+                let mut encoder = zstd::Encoder::new(out, 3)?;
+                let n = convert::convert(&mut ibt, &opts, &mut encoder)?;
+                encoder.finish()?.flush()?;
+                n
+            }
+            Some("gz") => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(out, flate2::Compression::default());
+                let n = convert::convert(&mut ibt, &opts, &mut encoder)?;
+                encoder.finish()?.flush()?;
+                n
+            }
+            _ => convert::convert(&mut ibt, &opts, out)?,
+        }
+    };
+
+    if output != "-" {
+        eprintln!("Wrote {} frames to {}", written, output);
+    }
+    Ok(())
+}
+
+fn run_laps(args: &[String]) -> Result<()> {
+    let [input] = args else {
+        bail!("Expected exactly one input file\n\n{}", USAGE);
+    };
+    let path = Path::new(input);
+    let ibt = IbtFile::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+    let session = ibt.session_info().clone();
+    println!(
+        "{} / {} — {} samples @ {} Hz ({:.1}s)",
+        session.track_display_name,
+        session.car_screen_name,
+        ibt.record_count(),
+        ibt.tick_rate(),
+        ibt.duration_secs()
+    );
+    for lap in ibt.build_lap_index()? {
+        let time = lap
+            .lap_time_secs
+            .map(|t| format!("{:.3}s", t))
+            .unwrap_or_else(|| "-".to_string());
+        let kind = match (lap.out_lap, lap.in_lap) {
+            (true, true) => "out/in",
+            (true, false) => "out",
+            (false, true) => "in",
+            (false, false) if lap.valid => "valid",
+            (false, false) => "invalid",
+        };
+        println!(
+            "lap {:>3}  samples {:>8}-{:<8}  {:>9}  {}",
+            lap.lap_number, lap.start_frame, lap.end_frame, time, kind
+        );
+    }
+    Ok(())
+}
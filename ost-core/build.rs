@@ -0,0 +1,358 @@
+//! Build script that generates the channel registry (`channels::CHANNELS`)
+//! from the struct definitions and doc comments in `src/model.rs`, so the
+//! registry can't fall out of step with the model, along with accessors for
+//! the numeric channels outside lists and for dropping float values.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Symbols for the unit newtypes in `src/units.rs`. A unit missing here
+/// fails the build, so new units get a symbol when they're added.
+const UNIT_SYMBOLS: &[(&str, &str)] = &[
+    ("Meters", "m"),
+    ("Millimeters", "mm"),
+    ("MetersPerSecond", "m/s"),
+    ("MillimetersPerSecond", "mm/s"),
+    ("MetersPerSecondSquared", "m/s²"),
+    ("Degrees", "°"),
+    ("DegreesPerSecond", "°/s"),
+    ("DegreesPerSecondSquared", "°/s²"),
+    ("Rpm", "rpm"),
+    ("Kilograms", "kg"),
+    ("Newtons", "N"),
+    ("Celsius", "°C"),
+    ("Pascals", "Pa"),
+    ("Kilopascals", "kPa"),
+    ("Percentage", "0-1"),
+    ("Seconds", "s"),
+    ("GForce", "G"),
+    ("Liters", "L"),
+    ("LitersPerHour", "L/h"),
+    ("Volts", "V"),
+    ("Bar", "bar"),
+    ("NewtonMeters", "N·m"),
+    ("KilogramsPerCubicMeter", "kg/m³"),
+    ("Megajoules", "MJ"),
+    ("Kilowatts", "kW"),
+];
+
+struct Field {
+    name: String,
+    ty: String,
+    doc: String,
+}
+
+enum Item {
+    Struct(Vec<Field>),
+    Enum(Vec<String>),
+}
+
+struct Channel {
+    path: String,
+    kind: String,
+    unit: Option<&'static str>,
+    doc: String,
+    values: Vec<String>,
+    /// Expressions reading the value as an `f64` and assigning `value` to
+    /// it, for numeric channels outside lists
+    access: Option<(String, String)>,
+    /// Statement dropping the value, for floats
+    clear: Option<String>,
+}
+
+/// A field on the way from the frame to a value, whether it's an `Option`
+/// and whether it's a list of structs
+#[derive(Clone)]
+struct Step {
+    field: String,
+    optional: bool,
+    list: bool,
+}
+
+fn main() {
+    println!("cargo::rerun-if-changed=src/model.rs");
+
+    let source = fs::read_to_string("src/model.rs").unwrap();
+    let items = parse(&source);
+    let mut channels = Vec::new();
+    walk(&items, "TelemetryFrame", "", "", &[], &mut channels);
+
+    let mut out = String::from("pub static CHANNELS: &[Channel] = &[\n");
+    for (index, c) in channels.iter().enumerate() {
+        let values: Vec<String> = c.values.iter().map(|v| format!("{:?}", v)).collect();
+        let description = Some(&c.doc).filter(|d| !d.is_empty());
+        writeln!(
+            out,
+            "    Channel {{ index: {}, path: {:?}, kind: {:?}, unit: {:?}, description: {:?}, values: &[{}] }},",
+            index,
+            c.path,
+            c.kind,
+            c.unit,
+            description,
+            values.join(", ")
+        )
+        .unwrap();
+    }
+    out.push_str("];\n\n");
+
+    out.push_str("fn number(frame: &TelemetryFrame, index: usize) -> Option<f64> {\n");
+    out.push_str("    Some(match index {\n");
+    for (index, c) in channels.iter().enumerate() {
+        if let Some((get, _)) = &c.access {
+            writeln!(out, "        {} => {},", index, get).unwrap();
+        }
+    }
+    out.push_str("        _ => return None,\n    })\n}\n\n");
+
+    out.push_str(
+        "fn set_number(frame: &mut TelemetryFrame, index: usize, value: f64) -> Option<()> {\n",
+    );
+    out.push_str("    match index {\n");
+    for (index, c) in channels.iter().enumerate() {
+        if let Some((_, set)) = &c.access {
+            writeln!(out, "        {} => {},", index, set).unwrap();
+        }
+    }
+    out.push_str("        _ => return None,\n    }\n    Some(())\n}\n\n");
+
+    out.push_str(
+        "fn clear_number(frame: &mut TelemetryFrame, index: usize, element: usize) -> Option<()> {\n",
+    );
+    out.push_str("    match index {\n");
+    for (index, c) in channels.iter().enumerate() {
+        if let Some(clear) = &c.clear {
+            writeln!(out, "        {} => {},", index, clear).unwrap();
+        }
+    }
+    out.push_str("        _ => return None,\n    }\n    Some(())\n}\n");
+
+    let dest = Path::new(&std::env::var("OUT_DIR").unwrap()).join("channels.rs");
+    fs::write(dest, out).unwrap();
+}
+
+/// Collect the structs (with their fields) and enums (with their variants)
+/// defined at the top level of the model source
+fn parse(source: &str) -> HashMap<String, Item> {
+    let mut items = HashMap::new();
+    let mut current: Option<(String, Item)> = None;
+    let mut doc: Vec<String> = Vec::new();
+    let mut skip_next = false;
+
+    for line in source.lines() {
+        if let Some((_, item)) = current.as_mut() {
+            let trimmed = line.trim();
+            if line.starts_with('}') {
+                let (name, item) = current.take().unwrap();
+                items.entry(name).or_insert(item);
+                continue;
+            }
+            match item {
+                Item::Struct(fields) => {
+                    if let Some(text) = trimmed.strip_prefix("///") {
+                        doc.push(text.trim().to_string());
+                    } else if trimmed.starts_with("#[") {
+                        skip_next |= trimmed.contains("flatten");
+                    } else if let Some((field, ty)) = trimmed
+                        .strip_prefix("pub ")
+                        .and_then(|rest| rest.strip_suffix(','))
+                        .and_then(|rest| rest.split_once(": "))
+                    {
+                        if !skip_next {
+                            fields.push(Field {
+                                name: field.to_string(),
+                                ty: ty.to_string(),
+                                doc: doc.join(" "),
+                            });
+                        }
+                        skip_next = false;
+                        doc.clear();
+                    } else {
+                        doc.clear();
+                    }
+                }
+                Item::Enum(variants) => {
+                    let variant = trimmed.trim_end_matches(',');
+                    if variant.starts_with(|c: char| c.is_ascii_uppercase())
+                        && variant.chars().all(|c| c.is_ascii_alphanumeric())
+                    {
+                        variants.push(variant.to_string());
+                    }
+                }
+            }
+        } else if let Some(rest) = line.strip_prefix("pub struct ") {
+            if let Some(name) = rest.strip_suffix(" {") {
+                let name = name.split('<').next().unwrap().to_string();
+                current = Some((name, Item::Struct(Vec::new())));
+                doc.clear();
+            }
+        } else if let Some(name) = line
+            .strip_prefix("pub enum ")
+            .and_then(|r| r.strip_suffix(" {"))
+        {
+            current = Some((name.to_string(), Item::Enum(Vec::new())));
+        }
+    }
+    items
+}
+
+/// Emit a channel for every leaf value under a field of type `ty` at `path`,
+/// reached through `steps`. Lists get a `*` segment and no accessors.
+/// `Vector3` components share the field's description.
+fn walk(
+    items: &HashMap<String, Item>,
+    ty: &str,
+    path: &str,
+    doc: &str,
+    steps: &[Step],
+    out: &mut Vec<Channel>,
+) {
+    let ty = unwrap_generic(ty, "Option").unwrap_or(ty);
+
+    if let Some(inner) = unwrap_generic(ty, "Vec") {
+        if let Some(Item::Struct(_)) = items.get(inner) {
+            let mut steps = steps.to_vec();
+            if let Some(last) = steps.last_mut() {
+                last.list = true;
+            }
+            return walk(items, inner, &format!("{}.*", path), doc, &steps, out);
+        }
+        let mut channel = leaf(items, inner, path, doc, steps);
+        channel.kind.push_str("[]");
+        channel.access = None;
+        out.push(channel);
+        return;
+    }
+    if let Some(inner) = unwrap_generic(ty, "Vector3") {
+        for axis in ["x", "y", "z"] {
+            let steps = step(steps, axis, false);
+            let path = format!("{}.{}", path, axis);
+            out.push(leaf(items, inner, &path, doc, &steps));
+        }
+        return;
+    }
+    match items.get(ty) {
+        Some(Item::Struct(fields)) => {
+            for field in fields {
+                let path = if path.is_empty() {
+                    field.name.clone()
+                } else {
+                    format!("{}.{}", path, field.name)
+                };
+                let optional = unwrap_generic(&field.ty, "Option").is_some();
+                let steps = step(steps, &field.name, optional);
+                walk(items, &field.ty, &path, &field.doc, &steps, out);
+            }
+        }
+        _ => out.push(leaf(items, ty, path, doc, steps)),
+    }
+}
+
+fn step(steps: &[Step], field: &str, optional: bool) -> Vec<Step> {
+    let mut steps = steps.to_vec();
+    steps.push(Step {
+        field: field.to_string(),
+        optional,
+        list: false,
+    });
+    steps
+}
+
+/// Statement setting the innermost `Option` around a value to `None`: the
+/// whole vector for `Vector3` components and the whole list for lists of
+/// numbers. Lists of structs are indexed by `element`.
+fn clear(steps: &[Step]) -> Option<String> {
+    let last = steps.iter().rposition(|s| s.optional)?;
+    let mut place = String::from("frame");
+    for step in &steps[..last] {
+        place.push('.');
+        place.push_str(&step.field);
+        if step.optional {
+            place.push_str(".as_mut()?");
+        }
+        if step.list {
+            place.push_str(".get_mut(element)?");
+        }
+    }
+    Some(format!("{}.{} = None", place, steps[last].field))
+}
+
+/// Read and assign expressions for a numeric value of type `ty`, a unit
+/// newtype unless it's a primitive
+fn accessors(ty: &str, steps: &[Step]) -> (String, String) {
+    let place = |unwrap: &str| {
+        let mut place = String::from("frame");
+        for step in steps {
+            place.push('.');
+            place.push_str(&step.field);
+            if step.optional {
+                place.push_str(unwrap);
+            }
+        }
+        place
+    };
+    let (get, set) = (place(".as_ref()?"), place(".as_mut()?"));
+    // After `as_ref()?` the value is behind a reference
+    let deref = if steps.last().is_some_and(|s| s.optional) {
+        "*"
+    } else {
+        ""
+    };
+    match ty {
+        "f64" => (
+            format!("{}{}", deref, get),
+            format!("{}{} = value", deref, set),
+        ),
+        "f32" => (
+            format!("{}{} as f64", deref, get),
+            format!("{}{} = value as f32", deref, set),
+        ),
+        "u8" | "i8" | "u32" | "i32" | "u64" | "i64" => (
+            format!("{}{} as f64", deref, get),
+            format!("{}{} = value.round() as {}", deref, set, ty),
+        ),
+        _ => (
+            format!("{}.0 as f64", get),
+            format!("{}.0 = value as f32", set),
+        ),
+    }
+}
+
+fn leaf(items: &HashMap<String, Item>, ty: &str, path: &str, doc: &str, steps: &[Step]) -> Channel {
+    let (kind, unit, values) = match ty {
+        "bool" => ("bool", None, Vec::new()),
+        "u8" | "i8" | "u32" | "i32" | "u64" | "i64" => ("integer", None, Vec::new()),
+        "f32" | "f64" => ("number", None, Vec::new()),
+        "String" => ("string", None, Vec::new()),
+        "DateTime<Utc>" => ("timestamp", None, Vec::new()),
+        _ => match items.get(ty) {
+            Some(Item::Enum(variants)) => ("enum", None, variants.clone()),
+            _ => {
+                let symbol = UNIT_SYMBOLS
+                    .iter()
+                    .find(|(name, _)| *name == ty)
+                    .unwrap_or_else(|| panic!("No unit symbol for `{}` at {}", ty, path))
+                    .1;
+                ("number", Some(symbol), Vec::new())
+            }
+        },
+    };
+    Channel {
+        path: path.to_string(),
+        kind: kind.to_string(),
+        unit,
+        // Intra-doc links read as plain code outside rustdoc
+        doc: doc.replace("[`", "`").replace("`]", "`"),
+        access: Some(steps)
+            .filter(|s| (kind == "number" || kind == "integer") && !s.iter().any(|s| s.list))
+            .map(|steps| accessors(ty, steps)),
+        clear: Some(steps).filter(|_| kind == "number").and_then(clear),
+        values,
+    }
+}
+
+/// `Some("T")` for `Outer<T>`
+fn unwrap_generic<'a>(ty: &'a str, outer: &str) -> Option<&'a str> {
+    ty.strip_prefix(outer)?.strip_prefix('<')?.strip_suffix('>')
+}
@@ -1,7 +1,200 @@
 //! Telemetry adapter trait definition
 
-use crate::model::TelemetryFrame;
-use anyhow::Result;
+use crate::channels;
+use crate::control::{ControlCapable, Unsupported};
+use crate::model::{MetricMask, TelemetryFrame};
+use crate::platform::{self, PlatformFeature};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Why an adapter call failed, so the manager can tell a sim that went away
+/// (stop and wait to detect it again) from a bad read (carry on) and from an
+/// adapter that can never work here (disable it)
+#[derive(Debug, thiserror::Error)]
+pub enum AdapterError {
+    /// The sim isn't running or its data source doesn't exist
+    #[error("{0}")]
+    NotDetected(String),
+    /// The sim's data source went away or stopped answering
+    #[error("{0}")]
+    ConnectionLost(String),
+    /// Data from the sim couldn't be decoded
+    #[error("{0}")]
+    Parse(String),
+    /// Not available on this platform or build, or not implemented by the
+    /// adapter
+    #[error("{0}")]
+    Unsupported(String),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+/// [`AdapterError`] categories, as reported by the API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    NotDetected,
+    ConnectionLost,
+    Parse,
+    Unsupported,
+    Other,
+}
+
+impl AdapterError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match kind {
+            ErrorKind::NotDetected => Self::NotDetected(message),
+            ErrorKind::ConnectionLost => Self::ConnectionLost(message),
+            ErrorKind::Parse => Self::Parse(message),
+            ErrorKind::Unsupported => Self::Unsupported(message),
+            ErrorKind::Other => Self::Other(anyhow::Error::msg(message)),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::NotDetected(_) => ErrorKind::NotDetected,
+            Self::ConnectionLost(_) => ErrorKind::ConnectionLost,
+            Self::Parse(_) => ErrorKind::Parse,
+            Self::Unsupported(_) => ErrorKind::Unsupported,
+            Self::Other(_) => ErrorKind::Other,
+        }
+    }
+
+    /// Whether the sim is gone, so reading should stop until it's detected
+    /// again
+    pub fn is_disconnect(&self) -> bool {
+        matches!(self, Self::NotDetected(_) | Self::ConnectionLost(_))
+    }
+
+    /// Whether retrying can't help, so the adapter should be disabled
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, Self::Unsupported(_))
+    }
+}
+
+/// Keeps an `AdapterError` passed through `anyhow` as it was
+impl From<anyhow::Error> for AdapterError {
+    fn from(error: anyhow::Error) -> Self {
+        error.downcast().unwrap_or_else(Self::Other)
+    }
+}
+
+/// Sorts I/O errors from the sim's socket, file or shared memory by kind
+impl From<std::io::Error> for AdapterError {
+    fn from(error: std::io::Error) -> Self {
+        use std::io::ErrorKind as Io;
+        let message = error.to_string();
+        match error.kind() {
+            Io::NotFound | Io::ConnectionRefused | Io::AddrNotAvailable => {
+                Self::NotDetected(message)
+            }
+            Io::InvalidData | Io::UnexpectedEof => Self::Parse(message),
+            Io::Unsupported => Self::Unsupported(message),
+            _ => Self::ConnectionLost(message),
+        }
+    }
+}
+
+impl From<Unsupported> for AdapterError {
+    fn from(error: Unsupported) -> Self {
+        Self::Unsupported(error.to_string())
+    }
+}
+
+/// Result of an adapter call
+pub type Result<T, E = AdapterError> = std::result::Result<T, E>;
+
+/// How the manager should schedule `read_frame()` calls for an adapter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollHint {
+    /// `read_frame()` waits for data itself (e.g. a shared-memory event or a
+    /// socket read timeout), so it can be called again immediately.
+    Blocking,
+    /// `read_frame()` returns immediately; call it at most once per interval.
+    Interval(Duration),
+}
+
+/// How far an adapter has got connecting to its sim
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    /// Not reading from the sim, or its data source went away
+    #[default]
+    Disconnected,
+    /// The sim is running but nobody is driving (menus, garage, spectating)
+    Waiting,
+    /// Live telemetry from the player's car
+    Connected,
+}
+
+/// A raw variable the sim publishes, as listed by
+/// [`TelemetryAdapter::variables`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariableInfo {
+    pub name: String,
+    /// The sim's type name, e.g. `float` or `bitfield`
+    #[serde(rename = "type")]
+    pub var_type: String,
+    /// Number of elements, 1 for scalars
+    pub count: usize,
+    pub unit: String,
+    pub description: String,
+}
+
+/// What an adapter's frames can contain, resolved from the metric mask
+/// patterns of [`TelemetryAdapter::capabilities`]. `meta` is always present
+/// and left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    /// Top-level sections with at least one channel, e.g. `vehicle`
+    pub sections: Vec<&'static str>,
+    /// Channel paths, as in [`channels::all`]
+    pub channels: Vec<&'static str>,
+    /// Namespaces the adapter puts in `extras`, e.g. `iracing`
+    pub extras: Vec<&'static str>,
+}
+
+impl Capabilities {
+    /// Resolve an adapter's patterns, `None` if it declares none
+    pub fn from_patterns(patterns: &[&'static str]) -> Option<Self> {
+        if patterns.is_empty() {
+            return None;
+        }
+        let mask = MetricMask::parse(&patterns.join(","));
+        let channels: Vec<&'static str> = channels::all()
+            .iter()
+            .map(|c| c.path)
+            .filter(|path| !path.starts_with("meta.") && mask.includes(path))
+            .collect();
+        let mut sections: Vec<&'static str> = channels
+            .iter()
+            .filter_map(|path| path.split('.').next())
+            .collect();
+        // Channels are in model order, so each section's are together
+        sections.dedup();
+        let extras = patterns
+            .iter()
+            .filter_map(|p| p.strip_prefix("extras."))
+            .collect();
+        Some(Self {
+            sections,
+            channels,
+            extras,
+        })
+    }
+
+    /// Channels with a value in `frame` that weren't declared, e.g. to check
+    /// an adapter's patterns against what it produces
+    pub fn undeclared(&self, frame: &TelemetryFrame) -> Vec<&'static str> {
+        channels::present(frame)
+            .into_iter()
+            .map(|c| c.path)
+            .filter(|path| !path.starts_with("meta.") && !self.channels.contains(path))
+            .collect()
+    }
+}
 
 /// Trait for game-specific telemetry adapters
 ///
@@ -36,14 +229,38 @@ pub trait TelemetryAdapter: Send + Sync {
     /// Returns:
     /// - `Ok(Some(frame))` if a new frame is available
     /// - `Ok(None)` if no new data (non-blocking)
-    /// - `Err(_)` if an error occurred
+    /// - `Err(_)` if an error occurred; the manager stops reading on
+    ///   [`AdapterError::is_disconnect`] errors and carries on after others
     ///
-    /// This should be non-blocking or have a short timeout.
+    /// This should be non-blocking or have a short timeout. See [`PollHint`]
+    /// for how the manager paces calls.
     fn read_frame(&mut self) -> Result<Option<TelemetryFrame>>;
 
+    /// How the manager should pace calls to `read_frame()`.
+    ///
+    /// Adapters that block waiting for data (shared memory events, UDP sockets
+    /// with a read timeout) keep the default. Adapters that produce data on
+    /// demand should return an interval so the manager sleeps between reads
+    /// instead of the adapter sleeping on the caller's thread.
+    fn poll_hint(&self) -> PollHint {
+        PollHint::Blocking
+    }
+
     /// Get whether the adapter is currently active
     fn is_active(&self) -> bool;
 
+    /// Where the adapter is in connecting to its sim.
+    ///
+    /// Adapters that can't tell a running sim from a car on track keep the
+    /// default, which follows [`is_active`](Self::is_active).
+    fn connection_state(&self) -> ConnectionState {
+        if self.is_active() {
+            ConnectionState::Connected
+        } else {
+            ConnectionState::Disconnected
+        }
+    }
+
     /// Whether session info has changed since last check.
     ///
     /// Adapters that track session info versions (like iRacing) can override this
@@ -51,4 +268,118 @@ pub trait TelemetryAdapter: Send + Sync {
     fn session_info_changed(&self) -> bool {
         false
     }
+
+    /// The latest complete session info as published by the sim (e.g.
+    /// iRacing's session YAML), for clients that parse it themselves.
+    fn session_info_raw(&self) -> Option<String> {
+        None
+    }
+
+    /// The raw variables the sim publishes, including those only forwarded
+    /// as `extras`, for clients discovering what's available. Empty until
+    /// connected, and for adapters without such a list.
+    fn variables(&self) -> Vec<VariableInfo> {
+        Vec::new()
+    }
+
+    /// What the adapter's frames can contain, as metric mask patterns (e.g.
+    /// `vehicle`, `motion.g_force`, `extras.iracing`), for UIs to hide
+    /// widgets for channels the game never provides. Channels the server
+    /// derives (e.g. `predictions`) aren't listed. Empty for adapters that
+    /// can't tell, such as those forwarding frames from elsewhere.
+    fn capabilities(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// What the adapter needs from the OS to read its sim. Adapters that can
+    /// run anywhere keep the default.
+    fn platform_requirements(&self) -> &'static [PlatformFeature] {
+        &[]
+    }
+
+    /// `Ok` if the adapter can run on this platform, otherwise why not. The
+    /// manager doesn't try to detect or start unsupported adapters.
+    fn platform_support(&self) -> Result<()> {
+        platform::check(self.platform_requirements())
+    }
+
+    /// Sim control commands (pit service, camera, replay), if supported.
+    ///
+    /// Adapters that can drive their sim implement [`ControlCapable`] and
+    /// return `Some(self)`.
+    fn control(&mut self) -> Option<&mut dyn ControlCapable> {
+        None
+    }
+
+    /// Named scenarios the adapter can simulate, for adapters that generate
+    /// telemetry rather than read it from a sim (e.g. the demo's
+    /// `"rain_onset"`)
+    fn scenarios(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Switch to one of [`scenarios`](Self::scenarios)
+    fn set_scenario(&mut self, _name: &str) -> Result<()> {
+        Err(Unsupported::new(self.name(), "scenarios").into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_kinds() {
+        let io = |kind| AdapterError::from(std::io::Error::from(kind));
+        assert_eq!(
+            io(std::io::ErrorKind::NotFound).kind(),
+            ErrorKind::NotDetected
+        );
+        assert!(io(std::io::ErrorKind::ConnectionReset).is_disconnect());
+        assert_eq!(io(std::io::ErrorKind::InvalidData).kind(), ErrorKind::Parse);
+
+        // Through anyhow and back, with context, the kind survives
+        let error =
+            anyhow::Error::from(AdapterError::Parse("bad packet".into())).context("reading");
+        assert_eq!(AdapterError::from(error).kind(), ErrorKind::Parse);
+        let error = AdapterError::from(anyhow::anyhow!("something else"));
+        assert_eq!(error.kind(), ErrorKind::Other);
+        assert!(!error.is_disconnect() && !error.is_fatal());
+
+        let error = AdapterError::from(Unsupported::new("Demo", "pit commands"));
+        assert!(error.is_fatal());
+        assert_eq!(error.to_string(), "Demo does not support pit commands");
+    }
+
+    #[test]
+    fn test_capabilities_from_patterns() {
+        assert_eq!(Capabilities::from_patterns(&[]), None);
+
+        let caps = Capabilities::from_patterns(&["vehicle.speed", "motion.g_force", "extras.demo"])
+            .unwrap();
+        assert_eq!(caps.sections, ["motion", "vehicle"]);
+        assert_eq!(
+            caps.channels,
+            [
+                "motion.g_force.x",
+                "motion.g_force.y",
+                "motion.g_force.z",
+                "vehicle.speed"
+            ]
+        );
+        assert_eq!(caps.extras, ["demo"]);
+
+        let caps = Capabilities::from_patterns(&["competitors"]).unwrap();
+        assert!(caps.channels.contains(&"competitors.*.position"));
+        assert!(!caps.channels.contains(&"meta.game"));
+
+        let mut frame = TelemetryFrame::new("Test");
+        frame.vehicle = Some(crate::model::VehicleData {
+            speed: Some(crate::units::MetersPerSecond(10.0)),
+            rpm: Some(crate::units::Rpm(3000.0)),
+            ..Default::default()
+        });
+        let caps = Capabilities::from_patterns(&["vehicle.speed"]).unwrap();
+        assert_eq!(caps.undeclared(&frame), ["vehicle.rpm"]);
+    }
 }
@@ -0,0 +1,188 @@
+//! Brake temperature estimation
+//!
+//! Few sims publish brake temperatures; iRacing, for one, only reports line
+//! pressure. [`BrakeTempEstimator`] fills in `wheels.*.brake_temp` from a
+//! simple thermal model of each disc, heated by braking in proportion to
+//! speed and cooled towards the air temperature faster the quicker the car
+//! goes, and sets `brake_temp_estimated` so consumers can tell the result
+//! from a measured value. The absolute numbers are ballpark figures for a GT
+//! car; the shape (which corner is hottest, how hard a stop was) is what it
+//! is good for.
+
+use crate::model::TelemetryFrame;
+use crate::units::Celsius;
+
+/// Temperature rise per second at full braking, per m/s of speed, for a disc
+/// taking all of the braking
+const HEATING: f32 = 7.5;
+/// Cooling rate towards ambient when stationary, per second
+const COOLING_STILL: f32 = 0.01;
+/// Extra cooling rate per m/s of speed, per second
+const COOLING_PER_MPS: f32 = 0.001;
+/// Used without `weather.air_temp`
+const DEFAULT_AMBIENT: f32 = 25.0;
+/// Front share of braking without `electronics.brake_bias` or line pressures
+const DEFAULT_FRONT_BIAS: f32 = 0.6;
+const MAX_TEMP: f32 = 1_200.0;
+/// Longer gaps between frames (pauses, dropouts) aren't integrated over
+const MAX_STEP_SECS: f64 = 1.0;
+
+/// Per-disc temperatures carried across frames
+#[derive(Debug, Default)]
+pub struct BrakeTempEstimator {
+    /// FL, FR, RL, RR
+    temps: Option<[f32; 4]>,
+    last_time: Option<f64>,
+}
+
+impl BrakeTempEstimator {
+    /// Fill in estimated brake temperatures unless the adapter measured them.
+    /// Frames without wheels, speed or brake input are left alone.
+    pub fn apply(&mut self, frame: &mut TelemetryFrame) {
+        let Some(wheels) = frame.wheels.as_ref() else {
+            return;
+        };
+        if wheels.all_wheels().iter().any(|w| w.brake_temp.is_some()) {
+            // Measured: nothing to estimate, and start afresh if that stops
+            self.temps = None;
+            self.last_time = None;
+            return;
+        }
+        let vehicle = frame.vehicle.as_ref();
+        let (Some(speed), Some(brake)) = (
+            vehicle.and_then(|v| v.speed).map(|s| s.0.abs()),
+            vehicle.and_then(|v| v.brake).map(|b| b.0.clamp(0.0, 1.0)),
+        ) else {
+            return;
+        };
+        let ambient = frame
+            .weather
+            .as_ref()
+            .and_then(|w| w.air_temp)
+            .map_or(DEFAULT_AMBIENT, |t| t.0);
+        let time = frame
+            .meta
+            .sim_time
+            .unwrap_or_else(|| frame.meta.timestamp.timestamp_micros() as f64 / 1e6);
+
+        // Share of the braking each disc takes
+        let pressures = wheels
+            .all_wheels()
+            .map(|w| w.brake_line_pressure.map_or(0.0, |p| p.0.max(0.0)));
+        let total: f32 = pressures.iter().sum();
+        let shares = if total > 0.0 {
+            pressures.map(|p| p / total)
+        } else {
+            let front = frame
+                .electronics
+                .as_ref()
+                .and_then(|e| e.brake_bias)
+                .map_or(DEFAULT_FRONT_BIAS, |b| b.0);
+            [
+                front / 2.0,
+                front / 2.0,
+                (1.0 - front) / 2.0,
+                (1.0 - front) / 2.0,
+            ]
+        };
+
+        let temps = self.temps.get_or_insert([ambient; 4]);
+        let dt = self.last_time.map(|last| time - last);
+        self.last_time = Some(time);
+        if let Some(dt) = dt.filter(|dt| *dt > 0.0 && *dt <= MAX_STEP_SECS) {
+            let dt = dt as f32;
+            let cooling = COOLING_STILL + COOLING_PER_MPS * speed;
+            for (temp, share) in temps.iter_mut().zip(shares) {
+                let heat = HEATING * brake * share * speed;
+                // Exact decay towards ambient, so long steps can't overshoot
+                let decayed = ambient + (*temp - ambient) * (-cooling * dt).exp();
+                *temp = (decayed + heat * dt).min(MAX_TEMP);
+            }
+        }
+
+        let temps = *temps;
+        if let Some(wheels) = frame.wheels.as_mut() {
+            for (wheel, temp) in wheels.all_wheels_mut().into_iter().zip(temps) {
+                wheel.brake_temp = Some(Celsius(temp));
+                wheel.brake_temp_estimated = Some(true);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(time: f64, speed: f32, brake: f32, brake_temp: Option<f32>) -> TelemetryFrame {
+        let wheel = serde_json::json!({ "brake_temp": brake_temp });
+        serde_json::from_value(serde_json::json!({
+            "meta": {"timestamp": "2026-01-01T00:00:00Z", "game": "test", "sim_time": time},
+            "vehicle": {"speed": speed, "brake": brake},
+            "weather": {"air_temp": 20.0},
+            "wheels": {
+                "front_left": wheel,
+                "front_right": wheel,
+                "rear_left": wheel,
+                "rear_right": wheel
+            }
+        }))
+        .unwrap()
+    }
+
+    fn temps(frame: &TelemetryFrame) -> [f32; 4] {
+        frame
+            .wheels
+            .as_ref()
+            .unwrap()
+            .all_wheels()
+            .map(|w| w.brake_temp.unwrap().0)
+    }
+
+    #[test]
+    fn test_heats_under_braking_and_cools_after() {
+        let mut estimator = BrakeTempEstimator::default();
+        let mut time = 0.0;
+        let mut step = |speed, brake| {
+            let mut f = frame(time, speed, brake, None);
+            estimator.apply(&mut f);
+            time += 1.0 / 60.0;
+            f
+        };
+
+        let start = step(70.0, 0.0);
+        assert_eq!(temps(&start), [20.0; 4]);
+        assert_eq!(
+            start
+                .wheels
+                .as_ref()
+                .unwrap()
+                .front_left
+                .brake_temp_estimated,
+            Some(true)
+        );
+
+        // A three second stop from 70 m/s
+        let hot = (0..180)
+            .map(|i| step(70.0 - i as f32 * 0.3, 1.0))
+            .last()
+            .unwrap();
+        let [fl, fr, rl, rr] = temps(&hot);
+        assert!(fl > 200.0 && fl < 600.0, "front {}", fl);
+        assert_eq!(fl, fr);
+        assert!(rl < fl && rl == rr);
+
+        // Cooling down a straight
+        let cool = (0..600).map(|_| step(60.0, 0.0)).last().unwrap();
+        assert!(temps(&cool)[0] < fl && temps(&cool)[0] > 20.0);
+    }
+
+    #[test]
+    fn test_leaves_measured_temps_alone() {
+        let mut estimator = BrakeTempEstimator::default();
+        let mut f = frame(0.0, 50.0, 1.0, Some(450.0));
+        estimator.apply(&mut f);
+        assert_eq!(temps(&f), [450.0; 4]);
+        assert_eq!(f.wheels.unwrap().front_left.brake_temp_estimated, None);
+    }
+}
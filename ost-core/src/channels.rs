@@ -0,0 +1,206 @@
+//! Registry of the normalized telemetry channels
+//!
+//! One [`Channel`] per leaf value of [`TelemetryFrame`], for dashboards to
+//! build channel pickers from. The registry is generated at build time from
+//! the field definitions and doc comments in `model.rs` (see `build.rs`), so
+//! every field added to the model shows up here without further work.
+//!
+//! Paths use metric mask syntax: wheels are addressed by name
+//! (`wheels.front_left.tyre_pressure`) and list elements by `*`
+//! (`competitors.*.position`).
+
+use crate::model::TelemetryFrame;
+use serde::Serialize;
+
+/// A normalized value in the frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Channel {
+    /// Position in [`all`], for the generated accessors
+    #[serde(skip)]
+    index: usize,
+    /// Dotted path in the frame, e.g. `vehicle.speed`
+    pub path: &'static str,
+    /// `number`, `integer`, `bool`, `string`, `enum` or `timestamp`, with a
+    /// `[]` suffix for lists of them
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// Unit in the data model (before any `units` display profile), e.g.
+    /// `m/s`. `0-1` for fractions.
+    pub unit: Option<&'static str>,
+    pub description: Option<&'static str>,
+    /// Possible values of an `enum`
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub values: &'static [&'static str],
+}
+
+include!(concat!(env!("OUT_DIR"), "/channels.rs"));
+
+impl Channel {
+    /// Whether the channel holds one number per frame, which [`value`] and
+    /// [`set_value`] work with
+    ///
+    /// [`value`]: Channel::value
+    /// [`set_value`]: Channel::set_value
+    pub fn is_numeric(&self) -> bool {
+        matches!(self.kind, "number" | "integer") && !self.path.contains('*')
+    }
+
+    /// The channel's value in `frame`, in data model units
+    pub fn value(&self, frame: &TelemetryFrame) -> Option<f64> {
+        number(frame, self.index)
+    }
+
+    /// Replace the channel's value in `frame`, rounding for integers. Only
+    /// values the frame already has are replaced; returns whether it had one.
+    pub fn set_value(&self, frame: &mut TelemetryFrame, value: f64) -> bool {
+        set_number(frame, self.index, value).is_some()
+    }
+
+    /// Drop the channel's value from `frame`, along with the vector or list of
+    /// numbers it's part of. `element` picks the item of a `*` channel.
+    pub(crate) fn clear_value(&self, frame: &mut TelemetryFrame, element: usize) -> bool {
+        clear_number(frame, self.index, element).is_some()
+    }
+}
+
+/// Every channel, in model order
+pub fn all() -> &'static [Channel] {
+    CHANNELS
+}
+
+/// The channel at `path`, matched case-insensitively
+pub fn find(path: &str) -> Option<&'static Channel> {
+    CHANNELS.iter().find(|c| c.path.eq_ignore_ascii_case(path))
+}
+
+/// Channels whose path or description contains `query`, ignoring case
+pub fn search(query: &str) -> impl Iterator<Item = &'static Channel> {
+    let query = query.to_lowercase();
+    CHANNELS.iter().filter(move |c| {
+        c.path.contains(&query)
+            || c.description
+                .is_some_and(|d| d.to_lowercase().contains(&query))
+    })
+}
+
+/// Channels with a value in `frame`. A `*` channel counts when any list
+/// element has the value.
+pub fn present(frame: &TelemetryFrame) -> Vec<&'static Channel> {
+    let Ok(value) = serde_json::to_value(frame) else {
+        return Vec::new();
+    };
+    CHANNELS
+        .iter()
+        .filter(|c| has_value(&value, c.path.split('.')))
+        .collect()
+}
+
+fn has_value<'a>(
+    value: &serde_json::Value,
+    mut path: impl Iterator<Item = &'a str> + Clone,
+) -> bool {
+    match path.next() {
+        None => !value.is_null(),
+        Some("*") => value
+            .as_array()
+            .is_some_and(|items| items.iter().any(|item| has_value(item, path.clone()))),
+        Some(key) => value.get(key).is_some_and(|v| has_value(v, path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CompetitorData, VehicleData, WheelData};
+    use crate::units::{Kilopascals, MetersPerSecond};
+
+    #[test]
+    fn test_registry_follows_model() {
+        let speed = find("vehicle.speed").unwrap();
+        assert_eq!(speed.kind, "number");
+        assert_eq!(speed.unit, Some("m/s"));
+        assert!(speed.description.is_some());
+
+        assert_eq!(find("motion.g_force.x").unwrap().unit, Some("G"));
+        assert_eq!(
+            find("wheels.rear_left.tyre_pressure").unwrap().unit,
+            Some("kPa")
+        );
+        assert_eq!(find("competitors.*.car_index").unwrap().kind, "integer");
+        assert_eq!(find("relative.standings").unwrap().kind, "integer[]");
+        assert_eq!(find("meta.timestamp").unwrap().kind, "timestamp");
+        let surface = find("vehicle.track_surface").unwrap();
+        assert_eq!(surface.kind, "enum");
+        assert!(surface.values.contains(&"Asphalt"));
+        // Sections and the flattened extras aren't channels themselves
+        assert!(find("vehicle").is_none());
+        assert!(find("extras").is_none());
+        assert!(all()
+            .iter()
+            .all(|c| !c.description.unwrap_or("").contains("[`")));
+
+        assert!(speed.is_numeric());
+        assert!(!find("competitors.*.position").unwrap().is_numeric());
+        assert!(!surface.is_numeric());
+
+        let found: Vec<_> = search("Tyre_Pressure").map(|c| c.path).collect();
+        assert!(found.contains(&"wheels.front_left.tyre_pressure"));
+        assert!(found.iter().all(|p| p.contains("tyre_pressure")));
+    }
+
+    #[test]
+    fn test_present() {
+        let mut frame = TelemetryFrame::new("test");
+        frame.vehicle = Some(VehicleData {
+            speed: Some(MetersPerSecond(20.0)),
+            ..Default::default()
+        });
+        let mut car = CompetitorData::new(3);
+        car.position = Some(2);
+        frame.competitors = Some(vec![CompetitorData::new(1), car]);
+
+        let paths: Vec<_> = present(&frame).iter().map(|c| c.path).collect();
+        assert!(paths.contains(&"meta.game"));
+        assert!(paths.contains(&"vehicle.speed"));
+        assert!(paths.contains(&"competitors.*.position"));
+        assert!(!paths.contains(&"vehicle.rpm"));
+        assert!(!paths.contains(&"competitors.*.driver_name"));
+    }
+
+    #[test]
+    fn test_value() {
+        let mut frame = TelemetryFrame::new("test");
+        let speed = find("vehicle.speed").unwrap();
+        assert_eq!(speed.value(&frame), None);
+        assert!(!speed.set_value(&mut frame, 10.0));
+
+        frame.vehicle = Some(VehicleData {
+            speed: Some(MetersPerSecond(20.0)),
+            gear: Some(3),
+            ..Default::default()
+        });
+        assert_eq!(speed.value(&frame), Some(20.0));
+        assert!(speed.set_value(&mut frame, 12.5));
+        assert_eq!(
+            frame.vehicle.as_ref().unwrap().speed,
+            Some(MetersPerSecond(12.5))
+        );
+
+        let gear = find("vehicle.gear").unwrap();
+        assert!(gear.set_value(&mut frame, 3.6));
+        assert_eq!(gear.value(&frame), Some(4.0));
+        assert_eq!(find("vehicle.car_name").unwrap().value(&frame), None);
+
+        let mut wheels = WheelData::default();
+        wheels.rear_right.tyre_pressure = Some(Kilopascals(170.0));
+        frame.wheels = Some(wheels);
+        let pressure = find("wheels.rear_right.tyre_pressure").unwrap();
+        assert_eq!(pressure.value(&frame), Some(170.0));
+        assert_eq!(
+            find("wheels.rear_left.tyre_pressure")
+                .unwrap()
+                .value(&frame),
+            None
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Columnar frames for analytics
+//!
+//! Walking nested `Option`s for every value of every frame is slow and awkward
+//! for number crunching. [`FrameColumns`] turns a run of frames into one `f32`
+//! column per numeric channel, so lap comparisons and spectra can work on
+//! plain arrays, or hand them to SIMD code or a GPU as they are. Values a frame
+//! doesn't have are `NaN`.
+//!
+//! Channels are always in [`channels::all`] order, whatever order they were
+//! selected in. That order follows the model this crate was built with, so
+//! stored columns should be identified by path rather than position.
+
+use crate::channels::{self, Channel};
+use crate::model::TelemetryFrame;
+
+/// Numeric channel values of a run of frames, a column per channel
+#[derive(Debug, Clone)]
+pub struct FrameColumns {
+    channels: Vec<&'static Channel>,
+    /// One per channel, each with a value per frame
+    columns: Vec<Vec<f32>>,
+    len: usize,
+}
+
+impl Default for FrameColumns {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameColumns {
+    /// Empty columns for every numeric channel
+    pub fn new() -> Self {
+        Self::with_channels(channels::all())
+    }
+
+    /// Empty columns for the numeric ones of `selected`
+    pub fn with_channels<'a>(selected: impl IntoIterator<Item = &'a Channel>) -> Self {
+        let selected: Vec<&Channel> = selected.into_iter().collect();
+        let channels: Vec<&'static Channel> = channels::all()
+            .iter()
+            .filter(|c| c.is_numeric() && selected.contains(c))
+            .collect();
+        Self {
+            columns: vec![Vec::new(); channels.len()],
+            channels,
+            len: 0,
+        }
+    }
+
+    /// Every numeric channel of `frames`
+    pub fn from_frames<'a>(frames: impl IntoIterator<Item = &'a TelemetryFrame>) -> Self {
+        let mut columns = Self::new();
+        for frame in frames {
+            columns.push(frame);
+        }
+        columns
+    }
+
+    /// Append a frame's values
+    pub fn push(&mut self, frame: &TelemetryFrame) {
+        for (channel, column) in self.channels.iter().zip(&mut self.columns) {
+            column.push(channel.value(frame).map_or(f32::NAN, |v| v as f32));
+        }
+        self.len += 1;
+    }
+
+    /// Number of frames
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The channels, in column order
+    pub fn channels(&self) -> &[&'static Channel] {
+        &self.channels
+    }
+
+    /// The values of the channel at `path` (matched case-insensitively), one
+    /// per frame
+    pub fn column(&self, path: &str) -> Option<&[f32]> {
+        self.channels
+            .iter()
+            .position(|c| c.path.eq_ignore_ascii_case(path))
+            .map(|i| self.columns[i].as_slice())
+    }
+
+    /// Every column with its channel
+    pub fn columns(&self) -> impl Iterator<Item = (&'static Channel, &[f32])> {
+        self.channels
+            .iter()
+            .copied()
+            .zip(self.columns.iter().map(Vec::as_slice))
+    }
+
+    /// All values row by row: each frame's values in column order, frame
+    /// after frame
+    pub fn to_rows(&self) -> Vec<f32> {
+        let mut rows = Vec::with_capacity(self.len * self.columns.len());
+        for i in 0..self.len {
+            rows.extend(self.columns.iter().map(|column| column[i]));
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::VehicleData;
+    use crate::units::{MetersPerSecond, Rpm};
+
+    fn frame(speed: Option<f32>, rpm: f32) -> TelemetryFrame {
+        let mut frame = TelemetryFrame::new("test");
+        frame.vehicle = Some(VehicleData {
+            speed: speed.map(MetersPerSecond),
+            rpm: Some(Rpm(rpm)),
+            ..Default::default()
+        });
+        frame
+    }
+
+    #[test]
+    fn test_columns() {
+        let frames = [frame(Some(10.0), 5000.0), frame(None, 6000.0)];
+        let all = FrameColumns::from_frames(&frames);
+        assert_eq!(all.len(), 2);
+        assert!(all.channels().iter().all(|c| c.is_numeric()));
+        assert!(all.column("competitors.*.position").is_none());
+        let speed = all.column("Vehicle.Speed").unwrap();
+        assert_eq!(speed[0], 10.0);
+        assert!(speed[1].is_nan());
+
+        // Registry order, whatever the selection order
+        let mut some = FrameColumns::with_channels([
+            channels::find("vehicle.rpm").unwrap(),
+            channels::find("vehicle.speed").unwrap(),
+            channels::find("vehicle.car_name").unwrap(),
+        ]);
+        for frame in &frames {
+            some.push(frame);
+        }
+        let paths: Vec<_> = some.channels().iter().map(|c| c.path).collect();
+        assert_eq!(paths, ["vehicle.speed", "vehicle.rpm"]);
+        assert_eq!(some.columns().nth(1).unwrap().1, [5000.0, 6000.0]);
+
+        let rows = some.to_rows();
+        assert_eq!(rows.len(), 4);
+        assert_eq!((rows[0], rows[1], rows[3]), (10.0, 5000.0, 6000.0));
+        assert!(rows[2].is_nan());
+    }
+}
@@ -0,0 +1,190 @@
+//! Sim control commands
+//!
+//! Some sims accept commands as well as publishing telemetry: pit service
+//! requests, camera switching, replay playback, chat macros. Adapters that
+//! support them implement [`ControlCapable`] and hand it out through
+//! [`TelemetryAdapter::control`]. Every command has a default that returns
+//! [`Unsupported`], so an adapter only overrides what its sim can do.
+
+use crate::adapter::TelemetryAdapter;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Returned (wrapped in `anyhow::Error`) when an adapter doesn't implement a
+/// control command, so callers can tell it apart from a failed command.
+#[derive(Debug, thiserror::Error)]
+#[error("{adapter} does not support {feature}")]
+pub struct Unsupported {
+    pub adapter: String,
+    pub feature: &'static str,
+}
+
+impl Unsupported {
+    pub fn new(adapter: &str, feature: &'static str) -> Self {
+        Self {
+            adapter: adapter.to_string(),
+            feature,
+        }
+    }
+}
+
+/// Returned (wrapped in `anyhow::Error`) when a command is outside what the
+/// sim accepts, e.g. a chat macro number it doesn't have, so callers can
+/// tell a bad request from a sim that isn't there to take it.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct InvalidCommand(pub String);
+
+/// Commands an adapter can send to its sim
+pub trait ControlCapable: TelemetryAdapter {
+    /// Change the pit stop the driver will receive
+    fn pit_command(&mut self, _command: PitCommand) -> Result<()> {
+        Err(Unsupported::new(self.name(), "pit commands").into())
+    }
+
+    /// Point the sim's camera at a car
+    fn camera_switch(&mut self, _switch: CameraSwitch) -> Result<()> {
+        Err(Unsupported::new(self.name(), "camera control").into())
+    }
+
+    /// Set the sim's replay playback speed
+    fn replay_play_speed(&mut self, _speed: ReplaySpeed) -> Result<()> {
+        Err(Unsupported::new(self.name(), "replay control").into())
+    }
+
+    /// Run a chat macro or open/close the chat box
+    fn chat_command(&mut self, _command: ChatCommand) -> Result<()> {
+        Err(Unsupported::new(self.name(), "chat commands").into())
+    }
+}
+
+/// A tire as addressed by pit service requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PitTire {
+    LeftFront,
+    RightFront,
+    LeftRear,
+    RightRear,
+}
+
+/// A change to the pit stop the driver will receive, as selected in the
+/// sim's pit service menu.
+///
+/// Serialized with a `command` tag, e.g. `{"command": "fuel", "liters": 40}`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum PitCommand {
+    /// Clear all pit service requests
+    Clear,
+    /// Clean the windscreen
+    Windscreen,
+    /// Add fuel. `liters` sets the amount to add; `None` keeps the amount
+    /// already selected.
+    Fuel {
+        #[serde(default)]
+        liters: Option<f32>,
+    },
+    /// Change a tire. `pressure_kpa` sets its cold pressure; `None` keeps the
+    /// pressure already selected.
+    Tire {
+        tire: PitTire,
+        #[serde(default)]
+        pressure_kpa: Option<f32>,
+    },
+    /// Request a fast repair
+    FastRepair,
+    /// Don't change any tires
+    ClearTires,
+    /// Don't clean the windscreen
+    ClearWindscreen,
+    /// Don't use a fast repair
+    ClearFastRepair,
+    /// Don't add fuel
+    ClearFuel,
+}
+
+/// Which car the camera should show, e.g. `"leader"` or `{"car_number": 12}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CameraFocus {
+    /// The race leader
+    Leader,
+    /// The most recent incident
+    Incident,
+    /// The car leaving the current shot
+    Exiting,
+    /// The car in this race position (1 = leader)
+    Position(u16),
+    /// The car with this race number
+    CarNumber(u16),
+}
+
+/// A camera change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CameraSwitch {
+    pub focus: CameraFocus,
+    /// Camera group number as listed by the sim; 0 keeps the current group
+    #[serde(default)]
+    pub group: u16,
+    /// Camera within the group; 0 keeps the current camera
+    #[serde(default)]
+    pub camera: u16,
+}
+
+/// Replay playback speed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplaySpeed {
+    /// Speed multiplier: 0 pauses, 1 is normal speed, negative rewinds
+    pub speed: i16,
+    /// Play at 1/`speed` instead of `speed` times normal
+    #[serde(default)]
+    pub slow_motion: bool,
+}
+
+/// A chat action. Sims only let external tools trigger the driver's own chat
+/// macros, not send arbitrary text.
+///
+/// Serialized with a `command` tag, e.g. `{"command": "macro", "number": 3}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ChatCommand {
+    /// Send the chat macro with this number (1-based, as in the sim's options)
+    Macro { number: u8 },
+    /// Open the chat box
+    BeginChat,
+    /// Open the chat box replying to the last private message
+    Reply,
+    /// Close the chat box
+    Cancel,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control_command_json() {
+        let cmd: PitCommand =
+            serde_json::from_str(r#"{"command": "tire", "tire": "left_front"}"#).unwrap();
+        assert_eq!(
+            cmd,
+            PitCommand::Tire {
+                tire: PitTire::LeftFront,
+                pressure_kpa: None,
+            }
+        );
+
+        let switch: CameraSwitch =
+            serde_json::from_str(r#"{"focus": {"car_number": 12}, "group": 3}"#).unwrap();
+        assert_eq!(switch.focus, CameraFocus::CarNumber(12));
+        assert_eq!(switch.camera, 0);
+
+        let switch: CameraSwitch = serde_json::from_str(r#"{"focus": "leader"}"#).unwrap();
+        assert_eq!(switch.focus, CameraFocus::Leader);
+
+        let chat: ChatCommand =
+            serde_json::from_str(r#"{"command": "macro", "number": 3}"#).unwrap();
+        assert_eq!(chat, ChatCommand::Macro { number: 3 });
+    }
+}
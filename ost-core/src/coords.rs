@@ -0,0 +1,213 @@
+//! Canonical coordinate frame
+//!
+//! Every car-local vector in a [`TelemetryFrame`](crate::model::TelemetryFrame)
+//! (`motion.velocity`, `motion.acceleration`, `motion.g_force`) uses the same
+//! axes regardless of the sim it came from:
+//!
+//! | Axis | Direction |
+//! |---|---|
+//! | `x` | right (lateral) |
+//! | `y` | up (vertical) |
+//! | `z` | forward (longitudinal) |
+//!
+//! This is a left-handed frame, as in Unity and DirectX. So:
+//!
+//! - positive `g_force.x` means the car is turning right
+//! - positive `g_force.z` means accelerating, negative means braking
+//! - `g_force.y` is what an accelerometer reads: +1 g at rest
+//!
+//! Angular quantities (`motion.rotation`, `motion.angular_acceleration` and
+//! the `pitch_rate`/`yaw_rate`/`roll_rate` fields) are pitch, yaw and roll
+//! about `x`, `y` and `z`, positive by the left-hand rule:
+//!
+//! - pitch: positive = nose down
+//! - yaw: positive = turning right (clockwise seen from above)
+//! - roll: positive = right side up (leaning left, as in a right-hand corner)
+//!
+//! `vehicle.steering_angle` is positive when the wheel is turned clockwise.
+//!
+//! World-space `motion.position` keeps `y` up; the orientation of `x` and `z`
+//! within the track is up to the sim.
+//!
+//! Adapters whose sim uses other axes must convert with a
+//! [`CoordinateTransform`] rather than swapping components by hand.
+
+use crate::model::Vector3;
+
+/// A source axis, possibly reversed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+    NegX,
+    NegY,
+    NegZ,
+}
+
+impl Axis {
+    const fn index(self) -> usize {
+        match self {
+            Axis::X | Axis::NegX => 0,
+            Axis::Y | Axis::NegY => 1,
+            Axis::Z | Axis::NegZ => 2,
+        }
+    }
+
+    const fn sign(self) -> f32 {
+        match self {
+            Axis::X | Axis::Y | Axis::Z => 1.0,
+            Axis::NegX | Axis::NegY | Axis::NegZ => -1.0,
+        }
+    }
+
+    fn pick(self, v: [f32; 3]) -> f32 {
+        self.sign() * v[self.index()]
+    }
+}
+
+/// Maps car-local vectors from a sim's axes into the canonical frame.
+///
+/// Built by naming the source axis that points right, up and forward. For
+/// example iRacing uses X forward, Y left, Z up:
+///
+/// ```
+/// use ost_core::coords::{Axis, CoordinateTransform};
+///
+/// let iracing = CoordinateTransform::new(Axis::NegY, Axis::Z, Axis::X);
+/// // 10 m/s² towards the car's right (negative Y in iRacing)
+/// assert_eq!(iracing.linear([0.0, -10.0, 0.0]), [10.0, 0.0, 0.0]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoordinateTransform {
+    right: Axis,
+    up: Axis,
+    forward: Axis,
+}
+
+impl CoordinateTransform {
+    /// The canonical frame itself (no conversion)
+    pub const CANONICAL: Self = Self::new(Axis::X, Axis::Y, Axis::Z);
+
+    /// Describe a sim's axes. Panics if two directions use the same source axis.
+    pub const fn new(right: Axis, up: Axis, forward: Axis) -> Self {
+        assert!(
+            right.index() != up.index()
+                && up.index() != forward.index()
+                && right.index() != forward.index(),
+            "each direction must use a different source axis"
+        );
+        Self { right, up, forward }
+    }
+
+    /// Convert a vector such as velocity or acceleration, given as the
+    /// source's `[x, y, z]`. Returns canonical `[x, y, z]`.
+    pub fn linear(&self, v: [f32; 3]) -> [f32; 3] {
+        [self.right.pick(v), self.up.pick(v), self.forward.pick(v)]
+    }
+
+    /// Convert an angular quantity (rotation, angular velocity) given about
+    /// the source's `[x, y, z]` with the hand rule matching the source's axes.
+    /// Returns canonical `[pitch, yaw, roll]`.
+    pub fn angular(&self, v: [f32; 3]) -> [f32; 3] {
+        let [pitch, yaw, roll] = self.linear(v);
+        let h = self.handedness();
+        [pitch * h, yaw * h, roll * h]
+    }
+
+    /// Inverse of [`linear`](Self::linear): canonical `[x, y, z]` back to the
+    /// source's axes, for writing data in a sim's own format
+    pub fn linear_inverse(&self, v: [f32; 3]) -> [f32; 3] {
+        let mut out = [0.0; 3];
+        for (axis, value) in [self.right, self.up, self.forward].into_iter().zip(v) {
+            out[axis.index()] = axis.sign() * value;
+        }
+        out
+    }
+
+    /// Inverse of [`angular`](Self::angular): canonical `[pitch, yaw, roll]`
+    /// back to angles about the source's `[x, y, z]`
+    pub fn angular_inverse(&self, v: [f32; 3]) -> [f32; 3] {
+        let h = self.handedness();
+        self.linear_inverse(v.map(|c| c * h))
+    }
+
+    /// [`linear`](Self::linear), wrapped in a unit type, e.g.
+    /// `transform.vector(v, MetersPerSecond)`
+    pub fn vector<T>(&self, v: [f32; 3], unit: impl Fn(f32) -> T) -> Vector3<T> {
+        let [x, y, z] = self.linear(v);
+        Vector3::new(unit(x), unit(y), unit(z))
+    }
+
+    /// [`angular`](Self::angular), wrapped in a unit type
+    pub fn angular_vector<T>(&self, v: [f32; 3], unit: impl Fn(f32) -> T) -> Vector3<T> {
+        let [x, y, z] = self.angular(v);
+        Vector3::new(unit(x), unit(y), unit(z))
+    }
+
+    /// +1 if the source frame has the same handedness as the canonical one,
+    /// -1 if it is mirrored. Angular quantities change sign across a mirror.
+    fn handedness(&self) -> f32 {
+        let even = matches!(
+            (self.right.index(), self.up.index(), self.forward.index()),
+            (0, 1, 2) | (1, 2, 0) | (2, 0, 1)
+        );
+        let parity = if even { 1.0 } else { -1.0 };
+        parity * self.right.sign() * self.up.sign() * self.forward.sign()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// X forward, Y left, Z up (right-handed)
+    const X_FORWARD_Y_LEFT: CoordinateTransform =
+        CoordinateTransform::new(Axis::NegY, Axis::Z, Axis::X);
+
+    #[test]
+    fn test_canonical_is_identity() {
+        let v = [1.0, 2.0, 3.0];
+        assert_eq!(CoordinateTransform::CANONICAL.linear(v), v);
+        assert_eq!(CoordinateTransform::CANONICAL.angular(v), v);
+    }
+
+    #[test]
+    fn test_linear_remaps_axes() {
+        // Accelerating forward while turning left at rest-level gravity
+        let [x, y, z] = X_FORWARD_Y_LEFT.linear([3.0, 5.0, 9.81]);
+        assert_eq!(x, -5.0, "left is negative x");
+        assert_eq!(y, 9.81);
+        assert_eq!(z, 3.0);
+    }
+
+    #[test]
+    fn test_angular_flips_across_handedness() {
+        // Right-handed yaw about Z-up: positive is counter-clockwise (left turn)
+        let [_, yaw, _] = X_FORWARD_Y_LEFT.angular([0.0, 0.0, 0.5]);
+        assert_eq!(yaw, -0.5, "left turn is negative yaw");
+
+        // Right-handed pitch about Y-left: positive is nose down
+        let [pitch, _, _] = X_FORWARD_Y_LEFT.angular([0.0, 0.2, 0.0]);
+        assert_eq!(pitch, 0.2);
+
+        // Right-handed roll about X-forward: positive is right side down
+        let [_, _, roll] = X_FORWARD_Y_LEFT.angular([0.1, 0.0, 0.0]);
+        assert_eq!(roll, -0.1);
+    }
+
+    #[test]
+    fn test_inverse_round_trips() {
+        let v = [1.0, -2.0, 3.0];
+        for transform in [X_FORWARD_Y_LEFT, CoordinateTransform::CANONICAL] {
+            assert_eq!(transform.linear_inverse(transform.linear(v)), v);
+            assert_eq!(transform.angular_inverse(transform.angular(v)), v);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "different source axis")]
+    fn test_rejects_repeated_axis() {
+        CoordinateTransform::new(Axis::X, Axis::NegX, Axis::Z);
+    }
+}
@@ -0,0 +1,154 @@
+//! UDP discovery beacon
+//!
+//! Servers broadcast a small JSON [`Beacon`] to [`DISCOVERY_PORT`] every few
+//! seconds, for networks where mDNS is blocked. Clients find them with
+//! [`discover`]:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! for server in ost_core::discovery::discover(Duration::from_secs(3))? {
+//!     println!("{} at {}", server.beacon.name, server.url());
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// UDP port beacons are broadcast to
+pub const DISCOVERY_PORT: u16 = 9199;
+
+/// Value of [`Beacon::service`], to tell beacons apart from other traffic
+pub const BEACON_SERVICE: &str = "opensimtelemetry";
+
+/// Announcement broadcast by a server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Beacon {
+    /// Always [`BEACON_SERVICE`]
+    pub service: String,
+    /// Human-readable server name
+    pub name: String,
+    /// Server version
+    pub version: String,
+    /// HTTP(S) port of the API
+    pub port: u16,
+    /// `http` or `https`
+    pub scheme: String,
+}
+
+impl Beacon {
+    pub fn new(name: impl Into<String>, version: impl Into<String>, port: u16, tls: bool) -> Self {
+        Self {
+            service: BEACON_SERVICE.to_string(),
+            name: name.into(),
+            version: version.into(),
+            port,
+            scheme: if tls { "https" } else { "http" }.to_string(),
+        }
+    }
+
+    /// Datagram payload
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("beacon serializes")
+    }
+
+    /// Parse a datagram, `None` if it isn't a beacon
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice::<Self>(bytes)
+            .ok()
+            .filter(|b| b.service == BEACON_SERVICE)
+    }
+}
+
+/// A server found by [`discover`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredServer {
+    /// Address the beacon came from, with the API port
+    pub addr: SocketAddr,
+    pub beacon: Beacon,
+}
+
+impl DiscoveredServer {
+    /// Base URL of the server, e.g. `http://192.168.1.20:9100`
+    pub fn url(&self) -> String {
+        format!("{}://{}", self.beacon.scheme, self.addr)
+    }
+}
+
+/// Listen on [`DISCOVERY_PORT`] for `timeout` and return each server heard
+/// from. Servers beacon every 2 seconds, so wait at least that long.
+pub fn discover(timeout: Duration) -> io::Result<Vec<DiscoveredServer>> {
+    let socket = UdpSocket::bind(("0.0.0.0", DISCOVERY_PORT))?;
+    discover_on(&socket, timeout)
+}
+
+/// [`discover`] on an already bound socket
+pub fn discover_on(socket: &UdpSocket, timeout: Duration) -> io::Result<Vec<DiscoveredServer>> {
+    let deadline = Instant::now() + timeout;
+    let mut servers: Vec<DiscoveredServer> = Vec::new();
+    let mut buf = [0u8; 1500];
+
+    while let Some(remaining) = deadline
+        .checked_duration_since(Instant::now())
+        .filter(|d| !d.is_zero())
+    {
+        socket.set_read_timeout(Some(remaining))?;
+        let (len, from) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                break
+            }
+            Err(e) => return Err(e),
+        };
+        let Some(beacon) = Beacon::parse(&buf[..len]) else {
+            continue;
+        };
+        let addr = SocketAddr::new(from.ip(), beacon.port);
+        match servers.iter_mut().find(|s| s.addr == addr) {
+            // Keep the latest, in case the server was renamed
+            Some(server) => server.beacon = beacon,
+            None => servers.push(DiscoveredServer { addr, beacon }),
+        }
+    }
+    Ok(servers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_collects_each_server_once() {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let target = listener.local_addr().unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let beacon = Beacon::new("Rig", "0.1.0", 9100, false);
+        sender.send_to(&beacon.to_bytes(), target).unwrap();
+        sender.send_to(b"not a beacon", target).unwrap();
+        sender.send_to(&beacon.to_bytes(), target).unwrap();
+        let tls = Beacon::new("Rig", "0.1.0", 9443, true);
+        sender.send_to(&tls.to_bytes(), target).unwrap();
+
+        let servers = discover_on(&listener, Duration::from_millis(200)).unwrap();
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].url(), "http://127.0.0.1:9100");
+        assert_eq!(servers[1].url(), "https://127.0.0.1:9443");
+        assert_eq!(servers[0].beacon.name, "Rig");
+    }
+
+    #[test]
+    fn test_parse_rejects_other_services() {
+        let mut beacon = Beacon::new("Rig", "0.1.0", 9100, false);
+        beacon.service = "other".to_string();
+        assert_eq!(Beacon::parse(&beacon.to_bytes()), None);
+    }
+}
@@ -0,0 +1,115 @@
+//! Lap validity
+//!
+//! [`LapValidity`] follows the player's laps from frame to frame and flags in
+//! `timing.lap_valid` whether the current lap still counts, for sims that
+//! don't report it themselves. A lap is valid if it was started by crossing
+//! the line on track and the car has not since left the racing surface,
+//! entered pit road or gone back to the garage. Out-laps, in-laps and the
+//! lap in progress when telemetry starts are therefore never valid.
+
+use crate::model::{TelemetryFrame, TrackSurface};
+
+/// Tracks the validity of the current lap across frames
+#[derive(Debug, Default)]
+pub struct LapValidity {
+    lap: Option<u32>,
+    invalid: bool,
+}
+
+impl LapValidity {
+    /// Fill in `timing.lap_valid` unless the adapter already did
+    pub fn apply(&mut self, frame: &mut TelemetryFrame) {
+        let valid = self.update(frame);
+        if let Some(timing) = frame.timing.as_mut() {
+            timing.lap_valid = timing.lap_valid.or(valid);
+        }
+    }
+
+    /// Whether the lap `frame` is on is valid so far. `None` without a lap
+    /// number.
+    pub fn update(&mut self, frame: &TelemetryFrame) -> Option<bool> {
+        let lap = frame.timing.as_ref()?.lap_number?;
+        let disqualified = disqualified(frame);
+        match self.lap {
+            // Crossed the line: a new lap, valid if it started on track
+            Some(prev) if lap == prev + 1 => self.invalid = disqualified,
+            Some(prev) if lap == prev => self.invalid |= disqualified,
+            // First frame, a reset or a dropout: the start of this lap was missed
+            _ => self.invalid = true,
+        }
+        self.lap = Some(lap);
+        Some(!self.invalid)
+    }
+}
+
+/// Whether the car is somewhere that voids the lap
+fn disqualified(frame: &TelemetryFrame) -> bool {
+    let vehicle = frame.vehicle.as_ref();
+    frame.pit.as_ref().and_then(|p| p.on_pit_road) == Some(true)
+        || vehicle.and_then(|v| v.in_garage) == Some(true)
+        || vehicle.and_then(|v| v.on_track) == Some(false)
+        || vehicle
+            .and_then(|v| v.track_surface)
+            .is_some_and(|surface| {
+                matches!(
+                    surface,
+                    TrackSurface::NotInWorld
+                        | TrackSurface::Grass
+                        | TrackSurface::Dirt
+                        | TrackSurface::Sand
+                        | TrackSurface::Gravel
+                        | TrackSurface::Grasscrete
+                        | TrackSurface::Astroturf
+                )
+            })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(lap: u32, on_pit_road: bool, surface: &str) -> TelemetryFrame {
+        serde_json::from_value(serde_json::json!({
+            "meta": {"timestamp": "2026-01-01T00:00:00Z", "game": "test", "tick": 1},
+            "timing": {"lap_number": lap},
+            "pit": {"on_pit_road": on_pit_road},
+            "vehicle": {"track_surface": surface}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_out_lap_and_off_track_laps_are_invalid() {
+        let mut validity = LapValidity::default();
+        let laps = [
+            // Leaving the pits, crossing the line on pit road
+            (frame(1, true, "Asphalt"), false),
+            (frame(2, true, "Asphalt"), false),
+            (frame(2, false, "Asphalt"), false),
+            // First flying lap
+            (frame(3, false, "Asphalt"), true),
+            (frame(3, false, "Rumble"), true),
+            // Off into the gravel, invalid for the rest of the lap
+            (frame(4, false, "Asphalt"), true),
+            (frame(4, false, "Gravel"), false),
+            (frame(4, false, "Asphalt"), false),
+            (frame(5, false, "Asphalt"), true),
+            // Telemetry dropout to lap 0 and back
+            (frame(0, false, "Asphalt"), false),
+            (frame(5, false, "Asphalt"), false),
+        ];
+        for (i, (mut frame, valid)) in laps.into_iter().enumerate() {
+            validity.apply(&mut frame);
+            assert_eq!(frame.timing.unwrap().lap_valid, Some(valid), "frame {}", i);
+        }
+    }
+
+    #[test]
+    fn test_keeps_validity_reported_by_adapter() {
+        let mut validity = LapValidity::default();
+        let mut frame = frame(1, false, "Asphalt");
+        frame.timing.as_mut().unwrap().lap_valid = Some(true);
+        validity.apply(&mut frame);
+        assert_eq!(frame.timing.unwrap().lap_valid, Some(true));
+    }
+}
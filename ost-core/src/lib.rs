@@ -4,8 +4,25 @@
 //! telemetry access across multiple racing simulators.
 
 pub mod adapter;
+pub mod brakes;
+pub mod channels;
+pub mod columns;
+pub mod control;
+pub mod coords;
+pub mod discovery;
+pub mod laps;
+pub mod merge;
 pub mod model;
+pub mod platform;
+pub mod relative;
+pub mod strategy;
+pub mod tracks;
 pub mod units;
+pub mod validate;
 
-pub use adapter::TelemetryAdapter;
+pub use adapter::{
+    AdapterError, ConnectionState, ErrorKind, PollHint, TelemetryAdapter, VariableInfo,
+};
+pub use control::ControlCapable;
 pub use model::{MetricMask, TelemetryFrame};
+pub use platform::PlatformFeature;
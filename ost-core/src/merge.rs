@@ -0,0 +1,418 @@
+//! Frame merging and diffing
+//!
+//! Some sims deliver telemetry as several packet types, each filling in part
+//! of the picture (F1's UDP motion, telemetry and status packets, for
+//! example). Adapters for them decode each packet into a partial
+//! [`TelemetryFrame`] and [`merge`](TelemetryFrame::merge) it into the frame
+//! they are accumulating. [`diff`](TelemetryFrame::diff) is the other
+//! direction: the JSON merge patch that turns one frame into the next, as
+//! sent by delta streaming.
+
+use crate::model::*;
+
+/// Field-by-field merge of a partial value into an accumulated one
+pub trait Merge {
+    /// Overwrite every field that is set in `other`, leaving the rest as is
+    fn merge(&mut self, other: &Self);
+}
+
+/// A set field replaces the current value; an unset one leaves it alone
+fn merge_field<T: Clone>(current: &mut Option<T>, other: &Option<T>) {
+    if let Some(value) = other {
+        *current = Some(value.clone());
+    }
+}
+
+/// Like [`merge_field`], but merges into a section that is already present
+fn merge_section<T: Merge + Clone>(current: &mut Option<T>, other: &Option<T>) {
+    match (current.as_mut(), other) {
+        (Some(current), Some(other)) => current.merge(other),
+        (None, Some(other)) => *current = Some(other.clone()),
+        (_, None) => {}
+    }
+}
+
+/// Implement [`Merge`] for a struct of optional fields. The destructuring
+/// pattern makes this fail to compile when a field is added to the struct
+/// but not listed here.
+macro_rules! impl_merge {
+    ($ty:ident { $($field:ident),* $(,)? }) => {
+        impl Merge for $ty {
+            fn merge(&mut self, other: &Self) {
+                let $ty { $($field),* } = other;
+                $(merge_field(&mut self.$field, $field);)*
+            }
+        }
+    };
+}
+
+impl_merge!(MotionData {
+    position,
+    velocity,
+    acceleration,
+    g_force,
+    rotation,
+    pitch_rate,
+    yaw_rate,
+    roll_rate,
+    angular_acceleration,
+    latitude,
+    longitude,
+    altitude,
+    heading,
+});
+impl_merge!(VehicleData {
+    speed,
+    rpm,
+    max_rpm,
+    idle_rpm,
+    gear,
+    max_gears,
+    throttle,
+    brake,
+    clutch,
+    steering_angle,
+    steering_torque,
+    steering_torque_pct,
+    handbrake,
+    shift_indicator,
+    steering_angle_max,
+    on_track,
+    in_garage,
+    track_surface,
+    car_name,
+    car_class,
+    setup_name,
+});
+impl_merge!(EngineData {
+    water_temp,
+    oil_temp,
+    oil_pressure,
+    oil_level,
+    fuel_level,
+    fuel_level_pct,
+    fuel_capacity,
+    fuel_pressure,
+    fuel_use_per_hour,
+    voltage,
+    manifold_pressure,
+    water_level,
+    warnings,
+});
+impl_merge!(WheelInfo {
+    suspension_travel,
+    suspension_travel_avg,
+    shock_velocity,
+    shock_velocity_avg,
+    ride_height,
+    tyre_pressure,
+    tyre_cold_pressure,
+    surface_temp_inner,
+    surface_temp_middle,
+    surface_temp_outer,
+    carcass_temp_inner,
+    carcass_temp_middle,
+    carcass_temp_outer,
+    tyre_wear,
+    tyre_wear_inner,
+    tyre_wear_middle,
+    tyre_wear_outer,
+    wheel_speed,
+    slip_ratio,
+    slip_angle,
+    load,
+    brake_line_pressure,
+    brake_temp,
+    tyre_compound,
+    brake_pad_wear,
+    brake_temp_estimated,
+});
+impl_merge!(TimingData {
+    current_lap_time,
+    last_lap_time,
+    best_lap_time,
+    best_n_lap_time,
+    best_n_lap_num,
+    sector_times,
+    lap_number,
+    laps_completed,
+    lap_distance,
+    lap_distance_pct,
+    race_position,
+    class_position,
+    num_cars,
+    delta_best,
+    delta_best_ok,
+    delta_session_best,
+    delta_session_best_ok,
+    delta_optimal,
+    delta_optimal_ok,
+    estimated_lap_time,
+    race_laps,
+    lap_valid,
+    corner_name,
+});
+impl_merge!(SessionData {
+    session_type,
+    session_state,
+    session_time,
+    session_time_remaining,
+    session_time_of_day,
+    session_laps,
+    session_laps_remaining,
+    flags,
+    track_name,
+    track_config,
+    track_length,
+    track_type,
+    track_id,
+    sector_starts,
+    pit_entry_pct,
+    pit_exit_pct,
+});
+impl_merge!(WeatherData {
+    air_temp,
+    track_temp,
+    track_surface_temp,
+    air_pressure,
+    air_density,
+    humidity,
+    wind_speed,
+    wind_direction,
+    fog_level,
+    precipitation,
+    track_wetness,
+    skies,
+    declared_wet,
+});
+impl_merge!(PitData {
+    on_pit_road,
+    pit_active,
+    pit_service_status,
+    repair_time_left,
+    optional_repair_time_left,
+    fast_repair_available,
+    fast_repair_used,
+    pit_speed_limit,
+    requested_services,
+});
+impl_merge!(ElectronicsData {
+    abs,
+    abs_active,
+    traction_control,
+    traction_control_2,
+    brake_bias,
+    anti_roll_front,
+    anti_roll_rear,
+    drs_status,
+    push_to_pass_status,
+    push_to_pass_count,
+    throttle_shape,
+    shift_light_first_rpm,
+    shift_light_shift_rpm,
+    shift_light_last_rpm,
+    shift_light_blink_rpm,
+});
+impl_merge!(DamageData {
+    front,
+    rear,
+    left,
+    right,
+    engine,
+    transmission,
+});
+impl_merge!(EnergyData {
+    battery_soc,
+    battery_energy,
+    deploy_mode,
+    deploying,
+    regen_level,
+    mgu_k_power,
+    mgu_h_power,
+    lap_deployed,
+    lap_harvested,
+    lap_deploy_allowance,
+    lap_deploy_pct,
+    battery_temp,
+    mgu_k_temp,
+    mgu_h_temp,
+});
+impl_merge!(PredictionData {
+    fuel_per_lap,
+    fuel_laps_remaining,
+    tyre_wear_per_lap,
+    tyre_cliff_lap,
+});
+impl_merge!(DriverData {
+    name,
+    car_index,
+    car_number,
+    team_name,
+    estimated_lap_time,
+});
+
+impl Merge for WheelData {
+    fn merge(&mut self, other: &Self) {
+        for (wheel, other) in self.all_wheels_mut().into_iter().zip(other.all_wheels()) {
+            wheel.merge(other);
+        }
+    }
+}
+
+impl Merge for TelemetryFrame {
+    fn merge(&mut self, other: &Self) {
+        let TelemetryFrame {
+            meta,
+            motion,
+            vehicle,
+            engine,
+            wheels,
+            timing,
+            session,
+            weather,
+            pit,
+            electronics,
+            damage,
+            energy,
+            competitors,
+            driver,
+            relative,
+            predictions,
+            extras,
+        } = other;
+
+        // The newest packet dates the frame
+        self.meta.timestamp = meta.timestamp;
+        self.meta.game.clone_from(&meta.game);
+        merge_field(&mut self.meta.tick, &meta.tick);
+        merge_field(&mut self.meta.sim_time, &meta.sim_time);
+        merge_field(&mut self.meta.invalid_fields, &meta.invalid_fields);
+        merge_field(&mut self.meta.seq, &meta.seq);
+
+        merge_section(&mut self.motion, motion);
+        merge_section(&mut self.vehicle, vehicle);
+        merge_section(&mut self.engine, engine);
+        merge_section(&mut self.wheels, wheels);
+        merge_section(&mut self.timing, timing);
+        merge_section(&mut self.session, session);
+        merge_section(&mut self.weather, weather);
+        merge_section(&mut self.pit, pit);
+        merge_section(&mut self.electronics, electronics);
+        merge_section(&mut self.damage, damage);
+        merge_section(&mut self.energy, energy);
+        merge_section(&mut self.driver, driver);
+        merge_section(&mut self.predictions, predictions);
+        // Lists describe the whole field, so they're replaced rather than merged
+        merge_field(&mut self.competitors, competitors);
+        merge_field(&mut self.relative, relative);
+        self.extras
+            .extend(extras.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+}
+
+impl TelemetryFrame {
+    /// Merge a partial frame into this one: every field set in `partial`
+    /// overwrites the value here, and fields it leaves unset keep theirs.
+    /// `meta.timestamp` and `meta.game` always come from `partial`.
+    /// Competitors and relative data are replaced as a whole, and extras are
+    /// added to.
+    pub fn merge(&mut self, partial: &TelemetryFrame) {
+        Merge::merge(self, partial);
+    }
+
+    /// The JSON merge patch (RFC 7386) from this frame to `other`, as
+    /// produced by [`compute_merge_patch`]: only changed fields, `null` for
+    /// removed ones, and `meta` in full. [`apply_merge_patch`] applies it to
+    /// this frame's JSON to get `other`'s.
+    pub fn diff(&self, other: &TelemetryFrame) -> serde_json::Result<serde_json::Value> {
+        Ok(compute_merge_patch(
+            &serde_json::to_value(self)?,
+            &serde_json::to_value(other)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::*;
+
+    fn frame(value: serde_json::Value) -> TelemetryFrame {
+        let mut value = value;
+        value["meta"] = serde_json::json!({"timestamp": "2026-01-01T00:00:00Z", "game": "test"});
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_merge_partial_packets() {
+        let mut acc = frame(serde_json::json!({
+            "vehicle": {"speed": 50.0, "gear": 4},
+            "wheels": {
+                "front_left": {"tyre_pressure": 170.0},
+                "front_right": {},
+                "rear_left": {},
+                "rear_right": {}
+            },
+            "competitors": [{"car_index": 1}, {"car_index": 2}]
+        }));
+        let mut partial = frame(serde_json::json!({
+            "vehicle": {"rpm": 7000.0, "gear": 5},
+            "engine": {"water_temp": 90.0},
+            "wheels": {
+                "front_left": {"surface_temp_middle": 85.0},
+                "front_right": {},
+                "rear_left": {},
+                "rear_right": {}
+            },
+            "competitors": [{"car_index": 3}],
+            "f1/ers_mode": 2
+        }));
+        partial.meta.tick = Some(10);
+
+        acc.merge(&partial);
+        let vehicle = acc.vehicle.as_ref().unwrap();
+        assert_eq!(vehicle.speed, Some(MetersPerSecond(50.0)));
+        assert_eq!(vehicle.rpm, Some(Rpm(7000.0)));
+        assert_eq!(vehicle.gear, Some(5));
+        assert_eq!(acc.engine.as_ref().unwrap().water_temp, Some(Celsius(90.0)));
+        let fl = &acc.wheels.as_ref().unwrap().front_left;
+        assert_eq!(fl.tyre_pressure, Some(Kilopascals(170.0)));
+        assert_eq!(fl.surface_temp_middle, Some(Celsius(85.0)));
+        assert_eq!(acc.competitors.as_ref().unwrap().len(), 1);
+        assert_eq!(acc.extras["f1/ers_mode"], 2);
+        assert_eq!(acc.meta.tick, Some(10));
+
+        // Unset fields leave the accumulated frame alone
+        let before = serde_json::to_value(&acc).unwrap();
+        acc.merge(&frame(serde_json::json!({})));
+        assert_eq!(serde_json::to_value(&acc).unwrap(), before);
+        assert_eq!(acc.meta.tick, Some(10));
+    }
+
+    #[test]
+    fn test_diff_round_trips_through_merge_patch() {
+        let prev = frame(serde_json::json!({
+            "vehicle": {"speed": 50.0, "gear": 4},
+            "engine": {"water_temp": 90.0}
+        }));
+        let curr = frame(serde_json::json!({
+            "vehicle": {"speed": 52.0, "gear": 4},
+            "timing": {"lap_number": 3}
+        }));
+
+        let patch = prev.diff(&curr).unwrap();
+        assert_eq!(patch["vehicle"], serde_json::json!({"speed": 52.0}));
+        assert_eq!(patch["timing"]["lap_number"], 3);
+        assert_eq!(patch.get("engine"), Some(&serde_json::Value::Null));
+
+        let mut rebuilt = serde_json::to_value(&prev).unwrap();
+        let mut patch = patch;
+        patch.as_object_mut().unwrap().remove("_delta");
+        apply_merge_patch(&mut rebuilt, &patch);
+        let rebuilt: TelemetryFrame = serde_json::from_value(rebuilt).unwrap();
+        assert_eq!(
+            serde_json::to_value(&rebuilt).unwrap(),
+            serde_json::to_value(&curr).unwrap()
+        );
+    }
+}
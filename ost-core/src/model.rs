@@ -30,6 +30,53 @@ pub struct MetaData {
 
     /// Sample tick/frame number from the sim
     pub tick: Option<u32>,
+
+    /// Sim clock in seconds since the session started. Unlike `timestamp`,
+    /// this follows the sim (pauses, replays) rather than the wall clock.
+    pub sim_time: Option<f64>,
+
+    /// Paths of values flagged by [`TelemetryFrame::validate`], if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invalid_fields: Option<Vec<String>>,
+
+    /// Sequence number the server gives each frame it broadcasts, one higher
+    /// than the last regardless of adapter or sim tick. A gap means frames
+    /// were lost on the way to the client.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+
+    /// Wall-clock time the adapter read this frame from the sim. Unlike
+    /// `timestamp`, this never follows a replayed or fixed clock, so the time
+    /// a frame is broadcast, sent or received minus this is its latency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acquired_at: Option<DateTime<Utc>>,
+
+    /// When the server broadcast this frame to its streams and sinks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broadcast_at: Option<DateTime<Utc>>,
+
+    /// Key of the server adapter that produced this frame (e.g. `iracing`),
+    /// for looking up its capabilities in `/api/adapters`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adapter: Option<String>,
+}
+
+impl MetaData {
+    /// Metadata for a frame captured now, with no tick or sim time
+    pub fn new(game: impl Into<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            timestamp: now,
+            game: game.into(),
+            tick: None,
+            sim_time: None,
+            invalid_fields: None,
+            seq: None,
+            acquired_at: Some(now),
+            broadcast_at: None,
+            adapter: None,
+        }
+    }
 }
 
 // =============================================================================
@@ -53,8 +100,14 @@ pub struct TelemetryFrame {
     pub pit: Option<PitData>,
     pub electronics: Option<ElectronicsData>,
     pub damage: Option<DamageData>,
+    pub energy: Option<EnergyData>,
     pub competitors: Option<Vec<CompetitorData>>,
     pub driver: Option<DriverData>,
+    /// Derived from `competitors`, see [`TelemetryFrame::compute_relative`]
+    pub relative: Option<RelativeData>,
+    /// Derived from fuel and tyre wear over past laps, see
+    /// [`StrategyEstimator`](crate::strategy::StrategyEstimator)
+    pub predictions: Option<PredictionData>,
 
     /// Game-specific telemetry data that doesn't fit the normalized model.
     /// Keyed by lowercase game namespace (e.g., "iracing"), value is a JSON object
@@ -63,6 +116,31 @@ pub struct TelemetryFrame {
     pub extras: HashMap<String, serde_json::Value>,
 }
 
+impl TelemetryFrame {
+    /// An empty frame captured now, for adapters and tests to fill in
+    pub fn new(game: impl Into<String>) -> Self {
+        Self {
+            meta: MetaData::new(game),
+            motion: None,
+            vehicle: None,
+            engine: None,
+            wheels: None,
+            timing: None,
+            session: None,
+            weather: None,
+            pit: None,
+            electronics: None,
+            damage: None,
+            energy: None,
+            competitors: None,
+            driver: None,
+            relative: None,
+            predictions: None,
+            extras: HashMap::new(),
+        }
+    }
+}
+
 // =============================================================================
 // 3D Vector
 // =============================================================================
@@ -85,10 +163,13 @@ impl<T> Vector3<T> {
 // MotionData
 // =============================================================================
 
-/// Physics/motion state of the player's car
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Physics/motion state of the player's car.
+///
+/// Car-local vectors and angles use the canonical frame documented in
+/// [`crate::coords`]: x right, y up, z forward.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MotionData {
-    /// Position in world space (meters)
+    /// Position in world space (meters, y up)
     pub position: Option<Vector3<Meters>>,
 
     /// Linear velocity in car-local space (m/s)
@@ -97,16 +178,18 @@ pub struct MotionData {
     /// Linear acceleration in car-local space (m/s²)
     pub acceleration: Option<Vector3<MetersPerSecondSquared>>,
 
-    /// G-forces experienced (derived from acceleration)
+    /// G-forces experienced (derived from acceleration). Positive x = turning
+    /// right, positive z = accelerating, y ≈ +1 at rest.
     pub g_force: Option<Vector3<GForce>>,
 
-    /// Rotation (pitch, yaw, roll) in degrees
+    /// Rotation (pitch, yaw, roll) in degrees: positive pitch = nose down,
+    /// positive roll = right side up
     pub rotation: Option<Vector3<Degrees>>,
 
     /// Pitch rate (deg/s) — rotation around lateral axis
     pub pitch_rate: Option<DegreesPerSecond>,
 
-    /// Yaw rate (deg/s) — rotation around vertical axis
+    /// Yaw rate (deg/s) — rotation around vertical axis, positive = turning right
     pub yaw_rate: Option<DegreesPerSecond>,
 
     /// Roll rate (deg/s) — rotation around longitudinal axis
@@ -133,7 +216,7 @@ pub struct MotionData {
 // =============================================================================
 
 /// Driver inputs and basic vehicle state
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct VehicleData {
     /// Speed magnitude (m/s)
     pub speed: Option<MetersPerSecond>,
@@ -162,7 +245,7 @@ pub struct VehicleData {
     /// Clutch input (0.0 = engaged, 1.0 = disengaged)
     pub clutch: Option<Percentage>,
 
-    /// Steering wheel angle in degrees
+    /// Steering wheel angle in degrees (positive = clockwise, turning right)
     pub steering_angle: Option<Degrees>,
 
     /// Steering wheel torque
@@ -227,7 +310,7 @@ pub enum TrackSurface {
 // =============================================================================
 
 /// Engine and drivetrain diagnostics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EngineData {
     /// Coolant/water temperature
     pub water_temp: Option<Celsius>,
@@ -274,7 +357,7 @@ pub struct EngineData {
 // =============================================================================
 
 /// Decoded engine warning/status flags
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct EngineWarnings {
     pub water_temp_high: bool,
     pub fuel_pressure_low: bool,
@@ -303,7 +386,7 @@ impl EngineWarnings {
 // =============================================================================
 
 /// Per-wheel telemetry data (Front-Left, Front-Right, Rear-Left, Rear-Right)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WheelData {
     pub front_left: WheelInfo,
     pub front_right: WheelInfo,
@@ -312,6 +395,20 @@ pub struct WheelData {
 }
 
 impl WheelData {
+    pub fn new(
+        front_left: WheelInfo,
+        front_right: WheelInfo,
+        rear_left: WheelInfo,
+        rear_right: WheelInfo,
+    ) -> Self {
+        Self {
+            front_left,
+            front_right,
+            rear_left,
+            rear_right,
+        }
+    }
+
     pub fn all_wheels(&self) -> [&WheelInfo; 4] {
         [
             &self.front_left,
@@ -336,7 +433,7 @@ impl WheelData {
 /// Temperature naming convention: "inner" = toward car center, "outer" = away from car center.
 /// Adapters handle the mapping from game-specific naming (e.g. iRacing CL/CR) to this
 /// car-relative convention.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WheelInfo {
     // --- Suspension ---
     /// Suspension/shock deflection (mm)
@@ -416,42 +513,18 @@ pub struct WheelInfo {
     // --- Compound ---
     /// Tyre compound name or index
     pub tyre_compound: Option<String>,
+
+    /// Brake pad wear (0.0 = new, 1.0 = worn out)
+    pub brake_pad_wear: Option<Percentage>,
+
+    /// Whether `brake_temp` was estimated rather than reported by the sim
+    /// (see [`BrakeTempEstimator`](crate::brakes::BrakeTempEstimator))
+    pub brake_temp_estimated: Option<bool>,
 }
 
 impl WheelInfo {
     pub fn new() -> Self {
-        Self {
-            suspension_travel: None,
-            suspension_travel_avg: None,
-            shock_velocity: None,
-            shock_velocity_avg: None,
-            ride_height: None,
-            tyre_pressure: None,
-            tyre_cold_pressure: None,
-            surface_temp_inner: None,
-            surface_temp_middle: None,
-            surface_temp_outer: None,
-            carcass_temp_inner: None,
-            carcass_temp_middle: None,
-            carcass_temp_outer: None,
-            tyre_wear: None,
-            tyre_wear_inner: None,
-            tyre_wear_middle: None,
-            tyre_wear_outer: None,
-            wheel_speed: None,
-            slip_ratio: None,
-            slip_angle: None,
-            load: None,
-            brake_line_pressure: None,
-            brake_temp: None,
-            tyre_compound: None,
-        }
-    }
-}
-
-impl Default for WheelInfo {
-    fn default() -> Self {
-        Self::new()
+        Self::default()
     }
 }
 
@@ -460,7 +533,7 @@ impl Default for WheelInfo {
 // =============================================================================
 
 /// Lap timing, position, and delta information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TimingData {
     /// Current lap time in seconds
     pub current_lap_time: Option<Seconds>,
@@ -524,6 +597,14 @@ pub struct TimingData {
 
     /// Total race laps completed by leader
     pub race_laps: Option<u32>,
+
+    /// Whether the current lap still counts: it started as a flying lap and
+    /// has stayed on the track and off pit road. Filled in by
+    /// [`crate::laps::LapValidity`] when the adapter doesn't report it.
+    pub lap_valid: Option<bool>,
+
+    /// Name of the corner the car is in, from the track database
+    pub corner_name: Option<String>,
 }
 
 // =============================================================================
@@ -531,7 +612,7 @@ pub struct TimingData {
 // =============================================================================
 
 /// Session state, identity, and metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SessionData {
     /// Session type (practice, qualifying, race, etc.)
     pub session_type: Option<SessionType>,
@@ -568,6 +649,19 @@ pub struct SessionData {
 
     /// Track type (Road, Oval, Dirt, etc.)
     pub track_type: Option<String>,
+
+    /// The sim's own track and layout identifier, as `<sim>:<track>`; the
+    /// key into [`crate::tracks::TrackDatabase`]
+    pub track_id: Option<String>,
+
+    /// Where each sector starts, as lap distance (0.0 to 1.0)
+    pub sector_starts: Option<Vec<Percentage>>,
+
+    /// Lap distance where the pit lane leaves the track (0.0 to 1.0)
+    pub pit_entry_pct: Option<Percentage>,
+
+    /// Lap distance where the pit lane rejoins the track (0.0 to 1.0)
+    pub pit_exit_pct: Option<Percentage>,
 }
 
 // =============================================================================
@@ -703,7 +797,7 @@ impl FlagState {
 // =============================================================================
 
 /// Environmental/weather conditions
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WeatherData {
     /// Air temperature
     pub air_temp: Option<Celsius>,
@@ -761,7 +855,7 @@ pub enum TrackWetness {
 // =============================================================================
 
 /// Pit road state and service information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PitData {
     /// Whether the player's car is on pit road
     pub on_pit_road: Option<bool>,
@@ -792,7 +886,7 @@ pub struct PitData {
 }
 
 /// Detailed pit service request state
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PitServices {
     /// Fuel to add (liters)
     pub fuel_to_add: Option<Liters>,
@@ -833,7 +927,7 @@ pub struct PitServices {
 // =============================================================================
 
 /// Driver aids and electronic systems
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ElectronicsData {
     /// ABS setting level
     pub abs: Option<f32>,
@@ -886,7 +980,7 @@ pub struct ElectronicsData {
 // =============================================================================
 
 /// Vehicle damage information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DamageData {
     /// Front damage (0.0 to 1.0)
     pub front: Option<Percentage>,
@@ -907,6 +1001,56 @@ pub struct DamageData {
     pub transmission: Option<Percentage>,
 }
 
+// =============================================================================
+// EnergyData
+// =============================================================================
+
+/// Hybrid/ERS state: battery, MGU-K/MGU-H and per-lap deployment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnergyData {
+    /// Battery state of charge (0.0 to 1.0)
+    pub battery_soc: Option<Percentage>,
+
+    /// Energy stored in the battery
+    pub battery_energy: Option<Megajoules>,
+
+    /// Deployment mode setting (game-specific numbering)
+    pub deploy_mode: Option<u32>,
+
+    /// Whether electrical power is being deployed right now
+    pub deploying: Option<bool>,
+
+    /// Regen/harvest setting (game-specific scale)
+    pub regen_level: Option<f32>,
+
+    /// MGU-K power, positive when deploying and negative when harvesting
+    pub mgu_k_power: Option<Kilowatts>,
+
+    /// MGU-H power
+    pub mgu_h_power: Option<Kilowatts>,
+
+    /// Energy deployed from the battery this lap
+    pub lap_deployed: Option<Megajoules>,
+
+    /// Energy harvested into the battery this lap
+    pub lap_harvested: Option<Megajoules>,
+
+    /// Energy that may be deployed per lap
+    pub lap_deploy_allowance: Option<Megajoules>,
+
+    /// Share of this lap's allowance already deployed (0.0 to 1.0)
+    pub lap_deploy_pct: Option<Percentage>,
+
+    /// Battery temperature
+    pub battery_temp: Option<Celsius>,
+
+    /// MGU-K temperature
+    pub mgu_k_temp: Option<Celsius>,
+
+    /// MGU-H temperature
+    pub mgu_h_temp: Option<Celsius>,
+}
+
 // =============================================================================
 // CompetitorData
 // =============================================================================
@@ -952,7 +1096,8 @@ pub struct CompetitorData {
     /// Last lap time
     pub last_lap_time: Option<Seconds>,
 
-    /// Estimated time around track
+    /// Estimated time for this car to reach its current position from the
+    /// start/finish line (iRacing `CarIdxEstTime`)
     pub estimated_time: Option<Seconds>,
 
     /// Current gear
@@ -965,12 +1110,39 @@ pub struct CompetitorData {
     pub steering: Option<Degrees>,
 }
 
+impl CompetitorData {
+    /// A competitor with nothing known beyond its car index
+    pub fn new(car_index: u32) -> Self {
+        Self {
+            car_index,
+            driver_name: None,
+            car_name: None,
+            car_class: None,
+            team_name: None,
+            car_number: None,
+            lap: None,
+            laps_completed: None,
+            lap_distance_pct: None,
+            position: None,
+            class_position: None,
+            on_pit_road: None,
+            track_surface: None,
+            best_lap_time: None,
+            last_lap_time: None,
+            estimated_time: None,
+            gear: None,
+            rpm: None,
+            steering: None,
+        }
+    }
+}
+
 // =============================================================================
 // DriverData
 // =============================================================================
 
 /// Player driver metadata (mostly from session info, relatively static)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct DriverData {
     pub name: Option<String>,
     pub car_index: Option<u32>,
@@ -979,101 +1151,325 @@ pub struct DriverData {
     pub estimated_lap_time: Option<Seconds>,
 }
 
+// =============================================================================
+// RelativeData
+// =============================================================================
+
+/// Gaps to the cars around the player and the overall running order, so
+/// relative and radar overlays don't each reimplement the math.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RelativeData {
+    /// Nearest car ahead on track, on any lap
+    pub ahead: Option<RelativeCar>,
+
+    /// Nearest car behind on track, on any lap
+    pub behind: Option<RelativeCar>,
+
+    /// Car indices in running order, leader first. Includes the player when
+    /// `driver.car_index` is known.
+    pub standings: Vec<u32>,
+}
+
+/// A car near the player on track
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RelativeCar {
+    pub car_index: u32,
+
+    /// Time gap on track (always positive), from estimated times or lap
+    /// distance and the player's reference lap time
+    pub gap: Option<Seconds>,
+
+    /// Whole laps this car is ahead of (positive) or behind (negative) the
+    /// player in the race
+    pub lap_difference: Option<i32>,
+}
+
+impl RelativeCar {
+    pub fn new(car_index: u32) -> Self {
+        Self {
+            car_index,
+            gap: None,
+            lap_difference: None,
+        }
+    }
+}
+
+// =============================================================================
+// PredictionData
+// =============================================================================
+
+/// Strategy predictions from the fuel use and tyre wear of past laps
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct PredictionData {
+    /// Average fuel used per lap
+    pub fuel_per_lap: Option<Liters>,
+
+    /// Laps the fuel in the tank lasts at that rate
+    pub fuel_laps_remaining: Option<f32>,
+
+    /// Average wear per lap of the most worn tyre (0.0 to 1.0)
+    pub tyre_wear_per_lap: Option<Percentage>,
+
+    /// Lap on which the most worn tyre reaches the cliff at that rate
+    pub tyre_cliff_lap: Option<u32>,
+}
+
 // =============================================================================
 // Metric Masking for Selective Output
 // =============================================================================
 
 /// Specifies which metrics to include in serialized output.
 ///
-/// Supports both section-level filtering (`vehicle`, `timing`) and
-/// dotted sub-field filtering (`vehicle.speed`, `timing.best_lap_time`).
+/// A comma-separated list of dotted paths, matched case-insensitively:
+/// - `vehicle` selects a whole section, `vehicle.speed` a single field
+/// - `*` matches any one path segment, e.g. `wheels.*.tyre_pressure`
+/// - a trailing `*` matches by prefix, e.g. `vehicle.steering*`
+/// - a leading `!` excludes a path, e.g. `vehicle,!vehicle.steering_torque`.
+///   A mask made only of exclusions selects everything else (`!wheels`).
+/// - `extras` addresses every game-specific namespace, and
+///   `extras.iracing/SessionTick` or `extras.iracing/Session*` address variables
+///   within one (equivalent to `iracing.SessionTick`).
 #[derive(Debug, Clone, Default)]
 pub struct MetricMask {
-    metrics: HashSet<String>,
+    include: Vec<Vec<String>>,
+    exclude: Vec<Vec<String>>,
     include_all: bool,
 }
 
+/// How much of the value at a path a [`MetricMask`] selects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskSelection {
+    None,
+    /// Some descendants are selected and others are not
+    Partial,
+    All,
+}
+
+/// Top-level keys of the standard model. Any other top-level key is a
+/// game-specific extras namespace.
+const STANDARD_SECTIONS: &[&str] = &[
+    "meta",
+    "motion",
+    "vehicle",
+    "engine",
+    "wheels",
+    "timing",
+    "session",
+    "weather",
+    "pit",
+    "electronics",
+    "damage",
+    "energy",
+    "competitors",
+    "driver",
+    "relative",
+    "predictions",
+];
+
+const EXTRAS_PREFIX: &str = "extras";
+
+fn split_path(path: &str) -> Vec<String> {
+    let mut segments: Vec<String> = path.split('.').map(|s| s.trim().to_string()).collect();
+    // `extras.iracing/SessionTick` -> ["extras", "iracing", "SessionTick"]
+    if segments.len() > 1 && segments[0] == EXTRAS_PREFIX {
+        if let Some((ns, var)) = segments[1].split_once('/') {
+            let (ns, var) = (ns.to_string(), var.to_string());
+            segments.splice(1..2, [ns, var]);
+        }
+    }
+    segments
+}
+
+/// Match one pattern segment against a key: `*` matches anything, a trailing
+/// `*` matches by prefix, anything else must be equal. Patterns are already
+/// lowercase.
+fn segment_matches(pattern: &str, key: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => key
+            .get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix)),
+        None => pattern.eq_ignore_ascii_case(key),
+    }
+}
+
+/// Check the leading segments of `pattern` and `path` against each other,
+/// up to the length of the shorter one.
+fn segments_match<S: AsRef<str>>(pattern: &[String], path: &[S]) -> bool {
+    pattern
+        .iter()
+        .zip(path)
+        .all(|(p, s)| segment_matches(p, s.as_ref()))
+}
+
+/// How a mask pattern relates to a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Relation {
+    /// The pattern matches the path or one of its ancestors
+    Covers,
+    /// The pattern matches something below the path
+    Below,
+    Unrelated,
+}
+
+fn relation<S: AsRef<str>>(pattern: &[String], path: &[S]) -> Relation {
+    // `extras.*` patterns apply to paths rooted at any non-standard key
+    let pattern = match pattern.split_first() {
+        Some((first, rest)) if first == EXTRAS_PREFIX => {
+            let is_extras = path.first().is_some_and(|key| {
+                !STANDARD_SECTIONS
+                    .iter()
+                    .any(|s| s.eq_ignore_ascii_case(key.as_ref()))
+            });
+            if !is_extras {
+                return Relation::Unrelated;
+            }
+            rest
+        }
+        _ => pattern,
+    };
+    if !segments_match(pattern, path) {
+        Relation::Unrelated
+    } else if pattern.len() <= path.len() {
+        Relation::Covers
+    } else {
+        Relation::Below
+    }
+}
+
 impl MetricMask {
     /// Create a mask that includes all metrics
     pub fn all() -> Self {
         Self {
-            metrics: HashSet::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
             include_all: true,
         }
     }
 
     /// Create a mask from a comma-separated list of metric names
     pub fn parse(metrics: &str) -> Self {
-        let metrics: HashSet<String> = metrics
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for entry in metrics
             .split(',')
             .map(|s| s.trim().to_lowercase())
             .filter(|s| !s.is_empty())
-            .collect();
+        {
+            match entry.strip_prefix('!').map(str::trim) {
+                Some("") => {}
+                Some(excluded) => exclude.push(split_path(excluded)),
+                None => include.push(split_path(&entry)),
+            }
+        }
 
         Self {
-            metrics,
+            include,
+            exclude,
             include_all: false,
         }
     }
 
-    /// Check if a metric should be included.
-    ///
-    /// Returns true if:
-    /// - All metrics are included (no mask)
-    /// - The exact metric name matches (e.g. "vehicle")
-    /// - A parent section matches (e.g. "vehicle" includes "vehicle.speed")
-    /// - The specific dotted path matches (e.g. "vehicle.speed")
-    pub fn includes(&self, metric: &str) -> bool {
-        if self.include_all {
-            return true;
+    /// How much of the value at `path` (one entry per dotted segment, e.g.
+    /// `["wheels", "front_left"]`) this mask selects. Serializers use this to
+    /// skip unselected fields and to copy fully selected ones without
+    /// checking every leaf.
+    pub fn select<S: AsRef<str>>(&self, path: &[S]) -> MaskSelection {
+        let any = |patterns: &[Vec<String>], rel: Relation| {
+            patterns.iter().any(|p| relation(p, path) == rel)
+        };
+
+        // The path itself or one of its ancestors is excluded
+        if any(&self.exclude, Relation::Covers) {
+            return MaskSelection::None;
         }
 
-        let metric_lower = metric.to_lowercase();
+        let selected = self.include_all
+            || (self.include.is_empty() && !self.exclude.is_empty())
+            || any(&self.include, Relation::Covers);
 
-        // Exact match
-        if self.metrics.contains(&metric_lower) {
-            return true;
-        }
-
-        // Check if any requested metric is a parent section of this metric
-        // e.g. if mask has "vehicle" and metric is "vehicle.speed"
-        if let Some(dot_pos) = metric_lower.find('.') {
-            let section = &metric_lower[..dot_pos];
-            if self.metrics.contains(section) {
-                return true;
+        if selected {
+            if any(&self.exclude, Relation::Below) {
+                MaskSelection::Partial
+            } else {
+                MaskSelection::All
             }
+        } else if any(&self.include, Relation::Below) {
+            MaskSelection::Partial
+        } else {
+            MaskSelection::None
         }
+    }
+
+    /// Check if a metric should be included, in whole or in part.
+    ///
+    /// Returns true if:
+    /// - All metrics are included (no mask)
+    /// - The metric or a parent section matches (e.g. "vehicle" includes "vehicle.speed")
+    /// - A requested path lies below it (e.g. "vehicle.speed" includes "vehicle")
+    ///
+    /// and the metric or a parent section isn't excluded.
+    pub fn includes(&self, metric: &str) -> bool {
+        self.select(&split_path(metric)) != MaskSelection::None
+    }
 
-        // Check if any requested metric is a child of this section
-        // e.g. if mask has "vehicle.speed" and metric is "vehicle" (the section)
-        for f in &self.metrics {
-            if f.starts_with(&metric_lower) && f.as_bytes().get(metric_lower.len()) == Some(&b'.') {
-                return true;
+    /// Prune a serialized value found under the top-level key `key` down to the
+    /// paths this mask selects. Array elements are addressed by index, so
+    /// `competitors.*.position` keeps the position of every competitor.
+    ///
+    /// Returns `None` if nothing under `key` is selected.
+    pub fn filter_value(&self, key: &str, value: serde_json::Value) -> Option<serde_json::Value> {
+        let mut path = vec![key.to_string()];
+        self.prune(&mut path, value)
+    }
+
+    fn prune(&self, path: &mut Vec<String>, value: serde_json::Value) -> Option<serde_json::Value> {
+        use serde_json::Value;
+
+        match self.select(path) {
+            MaskSelection::None => None,
+            MaskSelection::All => Some(value),
+            MaskSelection::Partial => {
+                let mut prune_child = |segment: String, child: Value| {
+                    path.push(segment);
+                    let pruned = self.prune(path, child);
+                    path.pop();
+                    pruned
+                };
+                match value {
+                    Value::Object(map) => Some(Value::Object(
+                        map.into_iter()
+                            .filter_map(|(k, v)| prune_child(k.clone(), v).map(|v| (k, v)))
+                            .collect(),
+                    )),
+                    Value::Array(items) => Some(Value::Array(
+                        items
+                            .into_iter()
+                            .enumerate()
+                            .filter_map(|(i, v)| prune_child(i.to_string(), v))
+                            .collect(),
+                    )),
+                    // A requested path reaches below a leaf value
+                    _ => None,
+                }
             }
         }
-
-        false
     }
 
-    /// Return the set of child keys requested under a section.
+    /// Return the set of child paths requested under a section.
     ///
     /// For example, if the mask contains `extras.iracing/Foo` and `extras.iracing/Bar`,
-    /// calling `child_keys("extras")` returns `Some({"iracing/Foo", "iracing/Bar"})`.
+    /// calling `child_keys("extras")` returns `Some(["iracing.foo", "iracing.bar"])`.
     /// Returns `None` if the bare section name is in the mask (meaning include all).
-    pub fn child_keys(&self, section: &str) -> Option<Vec<&str>> {
-        if self.include_all {
-            return None;
-        }
-        let section_lower = section.to_lowercase();
-        // If the bare section is requested, include everything
-        if self.metrics.contains(&section_lower) {
+    pub fn child_keys(&self, section: &str) -> Option<Vec<String>> {
+        let section = split_path(section);
+        if self.include_all || self.include.contains(&section) {
             return None;
         }
-        let prefix = format!("{}.", section_lower);
-        let keys: Vec<&str> = self
-            .metrics
+        let keys: Vec<String> = self
+            .include
             .iter()
-            .filter_map(|f| f.strip_prefix(&prefix))
+            .filter(|p| p.len() > section.len() && segments_match(p, &section))
+            .map(|p| p[section.len()..].join("."))
             .collect();
         if keys.is_empty() {
             None
@@ -1148,6 +1544,10 @@ impl MetricMaskBuilder {
         self.with_metric("damage")
     }
 
+    pub fn energy(self) -> Self {
+        self.with_metric("energy")
+    }
+
     pub fn competitors(self) -> Self {
         self.with_metric("competitors")
     }
@@ -1156,11 +1556,13 @@ impl MetricMaskBuilder {
         self.with_metric("driver")
     }
 
+    pub fn predictions(self) -> Self {
+        self.with_metric("predictions")
+    }
+
     pub fn build(self) -> MetricMask {
-        MetricMask {
-            metrics: self.metrics,
-            include_all: false,
-        }
+        let metrics: Vec<String> = self.metrics.into_iter().collect();
+        MetricMask::parse(&metrics.join(","))
     }
 }
 
@@ -1186,87 +1588,133 @@ impl TelemetryFrame {
     pub fn to_json_value_filtered(
         &self,
         mask: Option<&MetricMask>,
+    ) -> serde_json::Result<serde_json::Value> {
+        self.to_json_value_in(mask, UnitProfile::METRIC)
+    }
+
+    /// Like `to_json_value_filtered`, with values in the display units of
+    /// `units` (see [`Units`]), for JSON shown to people
+    pub fn to_json_value_in(
+        &self,
+        mask: Option<&MetricMask>,
+        units: UnitProfile,
     ) -> serde_json::Result<serde_json::Value> {
         if mask.is_none() || mask.map(|m| m.is_all()).unwrap_or(true) {
-            return serde_json::to_value(self);
+            return serde_json::to_value(Units(self, units));
         }
 
         let mask = mask.unwrap();
         let mut map = serde_json::Map::new();
 
         // Always include meta
-        map.insert("meta".to_string(), serde_json::to_value(&self.meta)?);
+        map.insert(
+            "meta".to_string(),
+            serde_json::to_value(Units(&self.meta, units))?,
+        );
 
-        // Conditionally include domain sections
-        if mask.includes("motion") {
-            if let Some(ref v) = self.motion {
-                map.insert("motion".to_string(), serde_json::to_value(v)?);
-            }
-        }
-        if mask.includes("vehicle") {
-            if let Some(ref v) = self.vehicle {
-                map.insert("vehicle".to_string(), serde_json::to_value(v)?);
-            }
-        }
-        if mask.includes("engine") {
-            if let Some(ref v) = self.engine {
-                map.insert("engine".to_string(), serde_json::to_value(v)?);
-            }
-        }
-        if mask.includes("wheels") {
-            if let Some(ref v) = self.wheels {
-                map.insert("wheels".to_string(), serde_json::to_value(v)?);
-            }
-        }
-        if mask.includes("timing") {
-            if let Some(ref v) = self.timing {
-                map.insert("timing".to_string(), serde_json::to_value(v)?);
-            }
-        }
-        if mask.includes("session") {
-            if let Some(ref v) = self.session {
-                map.insert("session".to_string(), serde_json::to_value(v)?);
-            }
-        }
-        if mask.includes("weather") {
-            if let Some(ref v) = self.weather {
-                map.insert("weather".to_string(), serde_json::to_value(v)?);
-            }
-        }
-        if mask.includes("pit") {
-            if let Some(ref v) = self.pit {
-                map.insert("pit".to_string(), serde_json::to_value(v)?);
-            }
-        }
-        if mask.includes("electronics") {
-            if let Some(ref v) = self.electronics {
-                map.insert("electronics".to_string(), serde_json::to_value(v)?);
-            }
-        }
-        if mask.includes("damage") {
-            if let Some(ref v) = self.damage {
-                map.insert("damage".to_string(), serde_json::to_value(v)?);
-            }
+        // Domain sections, pruned to the requested fields
+        insert_filtered(&mut map, mask, units, "motion", self.motion.as_ref())?;
+        insert_filtered(&mut map, mask, units, "vehicle", self.vehicle.as_ref())?;
+        insert_filtered(&mut map, mask, units, "engine", self.engine.as_ref())?;
+        insert_filtered(&mut map, mask, units, "wheels", self.wheels.as_ref())?;
+        insert_filtered(&mut map, mask, units, "timing", self.timing.as_ref())?;
+        insert_filtered(&mut map, mask, units, "session", self.session.as_ref())?;
+        insert_filtered(&mut map, mask, units, "weather", self.weather.as_ref())?;
+        insert_filtered(&mut map, mask, units, "pit", self.pit.as_ref())?;
+        insert_filtered(
+            &mut map,
+            mask,
+            units,
+            "electronics",
+            self.electronics.as_ref(),
+        )?;
+        insert_filtered(&mut map, mask, units, "damage", self.damage.as_ref())?;
+        insert_filtered(&mut map, mask, units, "energy", self.energy.as_ref())?;
+        insert_filtered(
+            &mut map,
+            mask,
+            units,
+            "competitors",
+            self.competitors.as_ref(),
+        )?;
+        insert_filtered(&mut map, mask, units, "driver", self.driver.as_ref())?;
+        insert_filtered(
+            &mut map,
+            mask,
+            units,
+            "predictions",
+            self.predictions.as_ref(),
+        )?;
+
+        // Game-specific namespaces (flattened into top level)
+        for (ns, data) in &self.extras {
+            insert_filtered(&mut map, mask, units, ns, Some(data))?;
         }
-        if mask.includes("competitors") {
-            if let Some(ref v) = self.competitors {
-                map.insert("competitors".to_string(), serde_json::to_value(v)?);
-            }
+
+        Ok(serde_json::Value::Object(map))
+    }
+
+    /// Serialize this frame to compact MessagePack bytes respecting the given metric mask.
+    ///
+    /// The binary counterpart to `to_json_filtered`: structs are encoded as maps
+    /// keyed by field name, so the output decodes to the same shape as the JSON.
+    pub fn to_bytes_filtered(
+        &self,
+        mask: Option<&MetricMask>,
+    ) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        if mask.is_none() || mask.map(|m| m.is_all()).unwrap_or(true) {
+            return rmp_serde::to_vec_named(self);
         }
-        if mask.includes("driver") {
-            if let Some(ref v) = self.driver {
-                map.insert("driver".to_string(), serde_json::to_value(v)?);
+        let value = self
+            .to_json_value_filtered(mask)
+            .map_err(|e| rmp_serde::encode::Error::Syntax(e.to_string()))?;
+        rmp_serde::to_vec_named(&value)
+    }
+
+    /// Serialize this frame to MessagePack as `format=msgpack` streams always
+    /// have: without a mask, sections as positional arrays of their fields in
+    /// declaration order; with one, the filtered frame as maps. Kept for
+    /// existing clients; prefer the self-describing `to_bytes_filtered`.
+    pub fn to_bytes_positional(
+        &self,
+        mask: Option<&MetricMask>,
+    ) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        match mask {
+            Some(mask) => {
+                let value = self
+                    .to_json_value_filtered(Some(mask))
+                    .map_err(|e| rmp_serde::encode::Error::Syntax(e.to_string()))?;
+                rmp_serde::to_vec(&value)
             }
+            None => rmp_serde::to_vec(self),
         }
-        // Game-specific namespaces (flattened into top level)
-        for (ns, data) in &self.extras {
-            if mask.includes(ns) {
-                map.insert(ns.clone(), data.clone());
+    }
+
+    /// Decode a frame produced by `to_bytes_filtered` with no mask.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+}
+
+/// Serialize `section` into `map` under `key` if the mask selects any of it,
+/// pruned to the selected fields.
+fn insert_filtered<T: Serialize>(
+    map: &mut serde_json::Map<String, serde_json::Value>,
+    mask: &MetricMask,
+    units: UnitProfile,
+    key: &str,
+    section: Option<&T>,
+) -> serde_json::Result<()> {
+    if let Some(section) = section {
+        if mask.includes(key) {
+            if let Some(value) =
+                mask.filter_value(key, serde_json::to_value(Units(section, units))?)
+            {
+                map.insert(key.to_string(), value);
             }
         }
-
-        Ok(serde_json::Value::Object(map))
     }
+    Ok(())
 }
 
 /// Compute a section-level delta between two JSON frame values.
@@ -1324,117 +1772,137 @@ pub fn compute_section_delta(
     serde_json::Value::Object(delta)
 }
 
+/// Marker value of `_delta` in frames produced by [`compute_merge_patch`].
+pub const MERGE_PATCH_MARKER: &str = "merge-patch";
+
+/// Compute a field-level JSON merge patch (RFC 7386) between two JSON frame values.
+///
+/// Unlike [`compute_section_delta`], nested objects are diffed recursively so
+/// only changed leaves are sent. Arrays are replaced wholesale. Fields that were
+/// removed or became `null` are sent as `null`, so clients treat a null field
+/// and a missing field the same. `meta` is always included in full, along with
+/// a `_delta: "merge-patch"` marker.
+pub fn compute_merge_patch(
+    prev: &serde_json::Value,
+    curr: &serde_json::Value,
+) -> serde_json::Value {
+    let mut patch = match merge_diff(prev, curr) {
+        Some(serde_json::Value::Object(map)) => map,
+        Some(_) => return curr.clone(),
+        None => serde_json::Map::new(),
+    };
+    patch.remove("_delta");
+    if let Some(meta) = curr.get("meta") {
+        patch.insert("meta".to_string(), meta.clone());
+    }
+    patch.insert(
+        "_delta".to_string(),
+        serde_json::Value::String(MERGE_PATCH_MARKER.to_string()),
+    );
+    serde_json::Value::Object(patch)
+}
+
+/// Recursive merge-patch diff. Returns `None` when `prev` and `curr` are equal.
+fn merge_diff(prev: &serde_json::Value, curr: &serde_json::Value) -> Option<serde_json::Value> {
+    if prev == curr {
+        return None;
+    }
+    match (prev.as_object(), curr.as_object()) {
+        (Some(prev_map), Some(curr_map)) => {
+            let mut patch = serde_json::Map::new();
+            for (key, curr_val) in curr_map {
+                match prev_map.get(key) {
+                    Some(prev_val) => {
+                        if let Some(diff) = merge_diff(prev_val, curr_val) {
+                            patch.insert(key.clone(), diff);
+                        }
+                    }
+                    None if !curr_val.is_null() => {
+                        patch.insert(key.clone(), curr_val.clone());
+                    }
+                    None => {}
+                }
+            }
+            for (key, prev_val) in prev_map {
+                if !curr_map.contains_key(key) && !prev_val.is_null() {
+                    patch.insert(key.clone(), serde_json::Value::Null);
+                }
+            }
+            Some(serde_json::Value::Object(patch))
+        }
+        _ => Some(curr.clone()),
+    }
+}
+
+/// Apply a JSON merge patch (RFC 7386) to `target` in place.
+///
+/// Counterpart to [`compute_merge_patch`] for consumers reconstructing full
+/// frames. The `_delta` marker is not stripped; callers should remove it first.
+pub fn apply_merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let Some(patch_map) = patch.as_object() else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(serde_json::Map::new());
+    }
+    let target_map = target.as_object_mut().unwrap();
+    for (key, value) in patch_map {
+        if value.is_null() {
+            target_map.remove(key);
+        } else {
+            apply_merge_patch(
+                target_map
+                    .entry(key.clone())
+                    .or_insert(serde_json::Value::Null),
+                value,
+            );
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     /// Helper to construct a minimal TelemetryFrame for testing
     fn make_test_frame() -> TelemetryFrame {
-        TelemetryFrame {
-            meta: MetaData {
-                timestamp: Utc::now(),
-                game: "TestGame".to_string(),
-                tick: Some(42),
-            },
-            motion: Some(MotionData {
-                position: None,
-                velocity: None,
-                acceleration: None,
-                g_force: Some(Vector3::new(GForce(0.3), GForce(1.0), GForce(-0.5))),
-                rotation: None,
-                pitch_rate: None,
-                yaw_rate: None,
-                roll_rate: None,
-                angular_acceleration: None,
-                latitude: None,
-                longitude: None,
-                altitude: None,
-                heading: None,
-            }),
-            vehicle: Some(VehicleData {
-                speed: Some(MetersPerSecond(30.0)),
-                rpm: Some(Rpm(5000.0)),
-                max_rpm: None,
-                idle_rpm: None,
-                gear: Some(3),
-                max_gears: Some(6),
-                throttle: Some(Percentage::new(0.75)),
-                brake: Some(Percentage::new(0.0)),
-                clutch: Some(Percentage::new(0.0)),
-                handbrake: None,
-                shift_indicator: None,
-                steering_angle_max: None,
-                steering_angle: Some(Degrees(0.1)),
-                steering_torque: None,
-                steering_torque_pct: None,
-                on_track: None,
-                in_garage: None,
-                track_surface: None,
-                car_name: Some("Test Car".to_string()),
-                car_class: None,
-                setup_name: None,
-            }),
-            engine: Some(EngineData {
-                water_temp: Some(Celsius(90.0)),
-                oil_temp: None,
-                oil_pressure: None,
-                oil_level: None,
-                fuel_level: None,
-                fuel_level_pct: None,
-                fuel_capacity: None,
-                fuel_pressure: None,
-                fuel_use_per_hour: None,
-                manifold_pressure: None,
-                water_level: None,
-                voltage: None,
-                warnings: None,
-            }),
-            wheels: None,
-            timing: Some(TimingData {
-                current_lap_time: Some(Seconds(45.2)),
-                last_lap_time: Some(Seconds(87.3)),
-                best_lap_time: Some(Seconds(85.1)),
-                best_n_lap_time: None,
-                best_n_lap_num: None,
-                sector_times: None,
-                lap_number: Some(5),
-                laps_completed: None,
-                lap_distance: None,
-                lap_distance_pct: None,
-                race_position: None,
-                class_position: None,
-                num_cars: None,
-                delta_best: None,
-                delta_best_ok: None,
-                delta_session_best: None,
-                delta_session_best_ok: None,
-                delta_optimal: None,
-                delta_optimal_ok: None,
-                estimated_lap_time: None,
-                race_laps: None,
-            }),
-            session: Some(SessionData {
-                session_type: Some(SessionType::Race),
-                session_state: None,
-                session_time: None,
-                session_time_remaining: Some(Seconds(1200.0)),
-                session_time_of_day: None,
-                session_laps: None,
-                session_laps_remaining: None,
-                flags: None,
-                track_name: Some("Test Track".to_string()),
-                track_config: None,
-                track_length: None,
-                track_type: None,
-            }),
-            weather: None,
-            pit: None,
-            electronics: None,
-            damage: None,
-            competitors: None,
-            driver: None,
-            extras: HashMap::new(),
-        }
+        let mut frame = TelemetryFrame::new("TestGame");
+        frame.meta.tick = Some(42);
+        frame.motion = Some(MotionData {
+            g_force: Some(Vector3::new(GForce(0.3), GForce(1.0), GForce(-0.5))),
+            ..Default::default()
+        });
+        frame.vehicle = Some(VehicleData {
+            speed: Some(MetersPerSecond(30.0)),
+            rpm: Some(Rpm(5000.0)),
+            gear: Some(3),
+            max_gears: Some(6),
+            throttle: Some(Percentage::new(0.75)),
+            brake: Some(Percentage::new(0.0)),
+            clutch: Some(Percentage::new(0.0)),
+            steering_angle: Some(Degrees(0.1)),
+            car_name: Some("Test Car".to_string()),
+            ..Default::default()
+        });
+        frame.engine = Some(EngineData {
+            water_temp: Some(Celsius(90.0)),
+            ..Default::default()
+        });
+        frame.timing = Some(TimingData {
+            current_lap_time: Some(Seconds(45.2)),
+            last_lap_time: Some(Seconds(87.3)),
+            best_lap_time: Some(Seconds(85.1)),
+            lap_number: Some(5),
+            ..Default::default()
+        });
+        frame.session = Some(SessionData {
+            session_type: Some(SessionType::Race),
+            session_time_remaining: Some(Seconds(1200.0)),
+            track_name: Some("Test Track".to_string()),
+            ..Default::default()
+        });
+        frame
     }
 
     #[test]
@@ -1519,6 +1987,116 @@ mod tests {
         assert!(parsed.get("session").is_some());
     }
 
+    #[test]
+    fn test_metric_mask_exclusions() {
+        let mask = MetricMask::parse("!wheels");
+        assert!(mask.includes("vehicle"));
+        assert!(!mask.includes("wheels"));
+        assert!(!mask.includes("wheels.front_left"));
+
+        let mask = MetricMask::parse("vehicle, !vehicle.steering_torque");
+        assert!(mask.includes("vehicle"));
+        assert!(mask.includes("vehicle.speed"));
+        assert!(!mask.includes("vehicle.steering_torque"));
+        assert!(!mask.includes("timing"));
+    }
+
+    #[test]
+    fn test_metric_mask_wildcards() {
+        let mask = MetricMask::parse("wheels.*.tyre_pressure");
+        assert!(mask.includes("wheels"));
+        assert!(mask.includes("wheels.front_left"));
+        assert!(mask.includes("wheels.rear_right.tyre_pressure"));
+        assert!(!mask.includes("wheels.rear_right.tyre_wear"));
+    }
+
+    #[test]
+    fn test_to_json_filtered_prunes_sub_fields() {
+        let frame = make_test_frame();
+        let mask = MetricMask::parse("vehicle.speed,motion.*.x");
+        let value = frame.to_json_value_filtered(Some(&mask)).unwrap();
+
+        let vehicle = value["vehicle"].as_object().unwrap();
+        assert_eq!(vehicle.keys().collect::<Vec<_>>(), ["speed"]);
+        // Only the vector that is present survives, with just its x component
+        let motion = value["motion"].as_object().unwrap();
+        assert_eq!(motion.keys().collect::<Vec<_>>(), ["g_force"]);
+        let g_force = motion["g_force"].as_object().unwrap();
+        assert_eq!(g_force.keys().collect::<Vec<_>>(), ["x"]);
+        assert!(value.get("timing").is_none());
+        assert!(value.get("meta").is_some());
+    }
+
+    #[test]
+    fn test_to_json_filtered_with_exclusions() {
+        let frame = make_test_frame();
+        let mask = MetricMask::parse("!session,!vehicle.rpm");
+        let value = frame.to_json_value_filtered(Some(&mask)).unwrap();
+
+        assert!(value.get("session").is_none());
+        assert!(value.get("timing").is_some());
+        assert!(value["vehicle"].get("speed").is_some());
+        assert!(value["vehicle"].get("rpm").is_none());
+    }
+
+    #[test]
+    fn test_metric_mask_prefix_wildcard() {
+        let mask = MetricMask::parse("vehicle.steering*");
+        assert!(mask.includes("vehicle.steering_angle"));
+        assert!(mask.includes("vehicle.steering_torque_pct"));
+        assert!(!mask.includes("vehicle.speed"));
+    }
+
+    #[test]
+    fn test_metric_mask_extras_paths() {
+        let mask = MetricMask::parse("extras.iracing/SessionTick");
+        assert!(mask.includes("iracing"));
+        assert!(mask.includes("iracing.SessionTick"));
+        assert!(!mask.includes("iracing.SessionTime"));
+        assert!(!mask.includes("vehicle"));
+
+        let mask = MetricMask::parse("extras");
+        assert!(mask.includes("iracing.SessionTick"));
+        assert!(mask.includes("demo"));
+        assert!(!mask.includes("vehicle"));
+        assert!(!mask.includes("meta"));
+
+        let mask = MetricMask::parse("!extras");
+        assert!(mask.includes("vehicle"));
+        assert!(!mask.includes("iracing"));
+
+        assert_eq!(
+            MetricMask::parse("extras.iracing/Foo,extras.iracing/Bar").child_keys("extras"),
+            Some(vec!["iracing.foo".to_string(), "iracing.bar".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_to_json_filtered_extras_by_prefix() {
+        let mut frame = make_test_frame();
+        frame.extras.insert(
+            "iracing".to_string(),
+            serde_json::json!({"SessionTick": 1, "SessionTime": 2.5, "dcBrakeBias": 56.5}),
+        );
+
+        let mask = MetricMask::parse("extras.iracing/Session*");
+        let value = frame.to_json_value_filtered(Some(&mask)).unwrap();
+        assert_eq!(
+            value["iracing"],
+            serde_json::json!({"SessionTick": 1, "SessionTime": 2.5})
+        );
+        assert!(value.get("vehicle").is_none());
+
+        let mask = MetricMask::parse("extras.iracing/*");
+        let value = frame.to_json_value_filtered(Some(&mask)).unwrap();
+        assert_eq!(value["iracing"].as_object().unwrap().len(), 3);
+
+        let mask = MetricMask::parse("vehicle,!extras");
+        let value = frame.to_json_value_filtered(Some(&mask)).unwrap();
+        assert!(value.get("iracing").is_none());
+        assert!(value.get("vehicle").is_some());
+    }
+
     #[test]
     fn test_to_json_filtered_with_all_mask_returns_full_frame() {
         let frame = make_test_frame();
@@ -1715,4 +2293,109 @@ mod tests {
         assert!(map.get("weather").is_some());
         assert!(!map["weather"].is_null());
     }
+
+    /// Strip null fields recursively (merge patches treat null as "absent")
+    fn strip_nulls(value: &mut serde_json::Value) {
+        if let Some(map) = value.as_object_mut() {
+            map.retain(|_, v| !v.is_null());
+            for v in map.values_mut() {
+                strip_nulls(v);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compute_merge_patch_only_changed_leaves() {
+        let frame = make_test_frame();
+        let v1 = serde_json::to_value(&frame).unwrap();
+        let mut frame2 = make_test_frame();
+        frame2.vehicle.as_mut().unwrap().speed = Some(MetersPerSecond(99.0));
+        let v2 = serde_json::to_value(&frame2).unwrap();
+
+        let patch = compute_merge_patch(&v1, &v2);
+        let map = patch.as_object().unwrap();
+        assert_eq!(map["_delta"], MERGE_PATCH_MARKER);
+        assert!(map.get("meta").is_some());
+        // Only the changed leaf is sent, not the whole vehicle section
+        let vehicle = map["vehicle"].as_object().unwrap();
+        assert_eq!(vehicle.len(), 1);
+        assert_eq!(vehicle["speed"], 99.0);
+        assert!(map.get("timing").is_none());
+    }
+
+    #[test]
+    fn test_compute_merge_patch_removed_section_is_null() {
+        let frame = make_test_frame();
+        let v1 = serde_json::to_value(&frame).unwrap();
+        let mut frame2 = make_test_frame();
+        frame2.session = None;
+        let v2 = serde_json::to_value(&frame2).unwrap();
+        let patch = compute_merge_patch(&v1, &v2);
+        assert_eq!(patch["session"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_apply_merge_patch_roundtrip() {
+        let frame = make_test_frame();
+        let v1 = serde_json::to_value(&frame).unwrap();
+        let mut frame2 = make_test_frame();
+        frame2.vehicle.as_mut().unwrap().gear = Some(5);
+        frame2.vehicle.as_mut().unwrap().rpm = None;
+        frame2.session = None;
+        let v2 = serde_json::to_value(&frame2).unwrap();
+
+        let mut patch = compute_merge_patch(&v1, &v2);
+        patch.as_object_mut().unwrap().remove("_delta");
+
+        let mut rebuilt = v1.clone();
+        apply_merge_patch(&mut rebuilt, &patch);
+
+        let mut expected = v2.clone();
+        strip_nulls(&mut rebuilt);
+        strip_nulls(&mut expected);
+        assert_eq!(rebuilt, expected);
+    }
+
+    #[test]
+    fn test_to_bytes_filtered_roundtrip() {
+        let frame = make_test_frame();
+        let bytes = frame.to_bytes_filtered(None).unwrap();
+        let decoded = TelemetryFrame::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&frame).unwrap()
+        );
+
+        // Smaller than the equivalent JSON
+        let json = frame.to_json_filtered(None).unwrap();
+        assert!(bytes.len() < json.len());
+    }
+
+    #[test]
+    fn test_to_bytes_positional() {
+        let frame = make_test_frame();
+        let bytes = frame.to_bytes_positional(None).unwrap();
+        assert_eq!(bytes, rmp_serde::to_vec(&frame).unwrap());
+        // Sections are arrays, not maps keyed by field name
+        let value: serde_json::Value = rmp_serde::from_slice(&bytes).unwrap();
+        assert!(value["meta"].is_array(), "{}", value);
+
+        let mask = MetricMask::parse("vehicle");
+        let bytes = frame.to_bytes_positional(Some(&mask)).unwrap();
+        let value: serde_json::Value = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(value, frame.to_json_value_filtered(Some(&mask)).unwrap());
+    }
+
+    #[test]
+    fn test_to_bytes_filtered_with_mask() {
+        let frame = make_test_frame();
+        let mask = MetricMask::parse("vehicle");
+        let bytes = frame.to_bytes_filtered(Some(&mask)).unwrap();
+        let value: serde_json::Value = rmp_serde::from_slice(&bytes).unwrap();
+
+        assert!(value.get("meta").is_some());
+        assert!(value.get("vehicle").is_some());
+        assert!(value.get("timing").is_none());
+        assert_eq!(value, frame.to_json_value_filtered(Some(&mask)).unwrap());
+    }
 }
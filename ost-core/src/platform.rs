@@ -0,0 +1,93 @@
+//! What the platform provides adapters
+//!
+//! Adapters built for every platform list the OS facilities they need in
+//! [`TelemetryAdapter::platform_requirements`](crate::adapter::TelemetryAdapter::platform_requirements),
+//! and [`check`] says whether this build has them, so a Linux or macOS
+//! server can tell "iRacing isn't running" from "iRacing can't be read
+//! here" and say why.
+
+use crate::adapter::AdapterError;
+use serde::Serialize;
+
+/// An OS facility an adapter reads its sim through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlatformFeature {
+    /// Named shared memory mappings, as iRacing, AC and SCS games publish
+    /// (Windows)
+    SharedMemory,
+    /// Game controllers: evdev devices on Linux, WinMM on Windows
+    Joystick,
+    /// UDP sockets
+    Udp,
+}
+
+impl PlatformFeature {
+    /// Whether this build has it
+    pub const fn is_available(self) -> bool {
+        match self {
+            PlatformFeature::SharedMemory => cfg!(target_os = "windows"),
+            PlatformFeature::Joystick => cfg!(any(target_os = "linux", target_os = "windows")),
+            PlatformFeature::Udp => true,
+        }
+    }
+
+    /// The platforms that have it
+    pub const fn platforms(self) -> &'static str {
+        match self {
+            PlatformFeature::SharedMemory => "Windows",
+            PlatformFeature::Joystick => "Linux and Windows",
+            PlatformFeature::Udp => "all platforms",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            PlatformFeature::SharedMemory => "shared memory",
+            PlatformFeature::Joystick => "game controllers",
+            PlatformFeature::Udp => "UDP",
+        }
+    }
+}
+
+/// The OS this build is for, e.g. `linux`
+pub const OS: &str = std::env::consts::OS;
+
+/// `Ok` if this build has everything in `needs`, otherwise an
+/// [`AdapterError::Unsupported`] naming the first thing it lacks
+pub fn check(needs: &[PlatformFeature]) -> Result<(), AdapterError> {
+    match needs.iter().find(|feature| !feature.is_available()) {
+        None => Ok(()),
+        Some(feature) => Err(AdapterError::Unsupported(format!(
+            "Reads {}, only available on {} (this is {})",
+            feature.description(),
+            feature.platforms(),
+            OS
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        assert!(check(&[]).is_ok());
+        assert!(check(&[PlatformFeature::Udp]).is_ok());
+        let shared_memory = check(&[PlatformFeature::Udp, PlatformFeature::SharedMemory]);
+        if cfg!(target_os = "windows") {
+            assert!(shared_memory.is_ok());
+        } else {
+            let error = shared_memory.unwrap_err();
+            assert!(error.is_fatal());
+            assert_eq!(
+                error.to_string(),
+                format!(
+                    "Reads shared memory, only available on Windows (this is {})",
+                    OS
+                )
+            );
+        }
+    }
+}
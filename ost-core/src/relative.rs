@@ -0,0 +1,253 @@
+//! Relative gaps and running order
+//!
+//! [`TelemetryFrame::compute_relative`] derives [`RelativeData`] from the
+//! `competitors` section, so relative and radar overlays get the gaps to the
+//! cars around the player without each reimplementing the lap wrap-around math.
+
+use crate::model::{CompetitorData, RelativeCar, RelativeData, TelemetryFrame, TrackSurface};
+use crate::units::Seconds;
+use std::cmp::Ordering;
+
+/// A car's place on track, the player included
+struct Placement {
+    car_index: Option<u32>,
+    position: Option<u32>,
+    laps_completed: Option<u32>,
+    pct: Option<f32>,
+    estimated_time: Option<f32>,
+}
+
+impl Placement {
+    fn player(frame: &TelemetryFrame) -> Self {
+        let timing = frame.timing.as_ref();
+        Self {
+            car_index: frame.driver.as_ref().and_then(|d| d.car_index),
+            position: timing.and_then(|t| t.race_position),
+            laps_completed: timing.and_then(|t| t.laps_completed),
+            pct: timing.and_then(|t| t.lap_distance_pct).map(|p| p.0),
+            estimated_time: None,
+        }
+    }
+
+    fn competitor(c: &CompetitorData) -> Self {
+        Self {
+            car_index: Some(c.car_index),
+            position: c.position,
+            laps_completed: c.laps_completed,
+            pct: c.lap_distance_pct.map(|p| p.0),
+            estimated_time: c.estimated_time.map(|t| t.0),
+        }
+    }
+
+    /// Race distance covered, in laps
+    fn progress(&self) -> Option<f32> {
+        Some(self.laps_completed? as f32 + self.pct?)
+    }
+
+    /// Classified position; iRacing reports 0 for unclassified cars
+    fn classified(&self) -> Option<u32> {
+        self.position.filter(|&p| p > 0)
+    }
+}
+
+impl TelemetryFrame {
+    /// Compute gaps to the nearest cars ahead and behind on track, and the
+    /// overall running order, from `competitors`. Returns `None` without
+    /// competitor data.
+    ///
+    /// Gaps use each car's `estimated_time` when both cars have one, and
+    /// otherwise lap distance scaled by the player's reference lap time
+    /// (estimated, then best, then last lap). Cars not in the world are
+    /// ignored for ahead/behind but still ranked in `standings`.
+    pub fn compute_relative(&self) -> Option<RelativeData> {
+        let competitors = self.competitors.as_ref()?;
+        let mut player = Placement::player(self);
+
+        let mut others = Vec::with_capacity(competitors.len());
+        for c in competitors {
+            let placement = Placement::competitor(c);
+            if player.car_index == Some(c.car_index) {
+                // Sims that list the player among competitors have its
+                // estimated time only there
+                player.estimated_time = placement.estimated_time;
+                player.pct = player.pct.or(placement.pct);
+                player.laps_completed = player.laps_completed.or(placement.laps_completed);
+                player.position = player.position.or(placement.position);
+                continue;
+            }
+            others.push((placement, c.track_surface));
+        }
+
+        let lap_time = self.reference_lap_time();
+        let on_track = |(_, surface): &&(Placement, Option<TrackSurface>)| {
+            *surface != Some(TrackSurface::NotInWorld)
+        };
+        let (ahead, behind) = match player.pct {
+            Some(player_pct) => {
+                // Distance ahead of the player, as a fraction of a lap
+                let distance = |p: &Placement| Some((p.pct? - player_pct).rem_euclid(1.0));
+                let ahead = others
+                    .iter()
+                    .filter(on_track)
+                    .filter_map(|(p, _)| Some((p, distance(p)?)))
+                    .filter(|&(_, d)| d > 0.0)
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+                let behind = others
+                    .iter()
+                    .filter(on_track)
+                    .filter_map(|(p, _)| Some((p, distance(p)?)))
+                    .filter(|&(_, d)| d > 0.0)
+                    .max_by(|a, b| a.1.total_cmp(&b.1));
+                (
+                    ahead.map(|(p, d)| relative_car(&player, p, d, lap_time, true)),
+                    behind.map(|(p, d)| relative_car(&player, p, d - 1.0, lap_time, false)),
+                )
+            }
+            None => (None, None),
+        };
+
+        let mut order: Vec<&Placement> = others.iter().map(|(p, _)| p).collect();
+        if player.car_index.is_some() {
+            order.push(&player);
+        }
+        order.sort_by(|a, b| running_order(a, b));
+        let standings = order.iter().filter_map(|p| p.car_index).collect();
+
+        Some(RelativeData {
+            ahead,
+            behind,
+            standings,
+        })
+    }
+
+    /// Player's expected lap time, for turning lap distance into time
+    fn reference_lap_time(&self) -> Option<f32> {
+        let timing = self.timing.as_ref();
+        [
+            timing.and_then(|t| t.estimated_lap_time),
+            self.driver.as_ref().and_then(|d| d.estimated_lap_time),
+            timing.and_then(|t| t.best_lap_time),
+            timing.and_then(|t| t.last_lap_time),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|t| t.0)
+        .find(|&t| t > 0.0)
+    }
+}
+
+/// `signed_distance` is how far `car` is ahead of the player on track, in
+/// laps: positive for the car ahead, negative for the car behind.
+fn relative_car(
+    player: &Placement,
+    car: &Placement,
+    signed_distance: f32,
+    lap_time: Option<f32>,
+    ahead: bool,
+) -> RelativeCar {
+    let gap = lap_time.map(|lap_time| {
+        let (car_time, player_time) = match (car.estimated_time, player.estimated_time) {
+            (Some(c), Some(p)) => (c, p),
+            _ => (
+                car.pct.unwrap_or_default() * lap_time,
+                player.pct.unwrap_or_default() * lap_time,
+            ),
+        };
+        let delta = if ahead {
+            car_time - player_time
+        } else {
+            player_time - car_time
+        };
+        Seconds(delta.rem_euclid(lap_time))
+    });
+    let lap_difference = match (car.progress(), player.progress()) {
+        (Some(c), Some(p)) => Some((c - p - signed_distance).round() as i32),
+        _ => None,
+    };
+
+    RelativeCar {
+        car_index: car.car_index.unwrap_or_default(),
+        gap,
+        lap_difference,
+    }
+}
+
+/// Classified cars by position, then the rest by distance covered
+fn running_order(a: &Placement, b: &Placement) -> Ordering {
+    match (a.classified(), b.classified()) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => {
+            let progress = |p: &Placement| p.progress().unwrap_or(f32::NEG_INFINITY);
+            progress(b).total_cmp(&progress(a))
+        }
+    }
+    .then(a.car_index.cmp(&b.car_index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(competitors: serde_json::Value) -> TelemetryFrame {
+        serde_json::from_value(serde_json::json!({
+            "meta": {"timestamp": "2026-01-01T00:00:00Z", "game": "test", "tick": 1},
+            "timing": {
+                "lap_distance_pct": 0.9,
+                "laps_completed": 3,
+                "race_position": 2,
+                "estimated_lap_time": 100.0
+            },
+            "driver": {"car_index": 0},
+            "competitors": competitors
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_gaps_wrap_around_start_finish() {
+        let frame = frame(serde_json::json!([
+            // Just past the line, a lap up on the player
+            {"car_index": 1, "lap_distance_pct": 0.05, "laps_completed": 5, "position": 1},
+            // Behind on the same lap
+            {"car_index": 2, "lap_distance_pct": 0.8, "laps_completed": 3, "position": 3},
+            {"car_index": 3, "lap_distance_pct": 0.0, "track_surface": "NotInWorld"}
+        ]));
+        let relative = frame.compute_relative().unwrap();
+
+        let ahead = relative.ahead.unwrap();
+        assert_eq!(ahead.car_index, 1);
+        assert!((ahead.gap.unwrap().0 - 15.0).abs() < 1e-3);
+        assert_eq!(ahead.lap_difference, Some(1));
+
+        let behind = relative.behind.unwrap();
+        assert_eq!(behind.car_index, 2);
+        assert!((behind.gap.unwrap().0 - 10.0).abs() < 1e-3);
+        assert_eq!(behind.lap_difference, Some(0));
+
+        assert_eq!(relative.standings, vec![1, 0, 2, 3]);
+    }
+
+    #[test]
+    fn test_prefers_estimated_times() {
+        let mut frame = frame(serde_json::json!([
+            {"car_index": 0, "lap_distance_pct": 0.9, "estimated_time": 92.0},
+            {"car_index": 1, "lap_distance_pct": 0.95, "estimated_time": 96.5}
+        ]));
+        frame.timing.as_mut().unwrap().estimated_lap_time = None;
+        frame.timing.as_mut().unwrap().best_lap_time = Some(Seconds(100.0));
+
+        let relative = frame.compute_relative().unwrap();
+        let ahead = relative.ahead.unwrap();
+        assert!((ahead.gap.unwrap().0 - 4.5).abs() < 1e-3);
+        assert_eq!(relative.behind.unwrap().car_index, 1);
+    }
+
+    #[test]
+    fn test_none_without_competitors() {
+        let mut frame = frame(serde_json::json!([]));
+        frame.competitors = None;
+        assert!(frame.compute_relative().is_none());
+    }
+}
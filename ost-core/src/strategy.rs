@@ -0,0 +1,220 @@
+//! Fuel and tyre strategy predictions
+//!
+//! [`StrategyEstimator`] measures the fuel used and tyre wear added over each
+//! lap the player completes, and fills in the `predictions` section from the
+//! average of recent laps: fuel per lap and how many laps the tank lasts,
+//! and the lap on which the most worn tyre reaches the cliff where grip falls
+//! away. The rates are recalculated as each lap completes; the laps of fuel
+//! remaining follow the fuel level every frame.
+//!
+//! Laps with a refuel or a tyre change don't count, nor does the lap in
+//! progress when telemetry starts.
+
+use crate::model::{PredictionData, TelemetryFrame, WheelInfo};
+use crate::units::{Liters, Percentage};
+use std::collections::VecDeque;
+
+/// Laps averaged over
+const RECENT_LAPS: usize = 5;
+
+/// Tyre wear (0.0 to 1.0) past which grip falls away
+const TYRE_CLIFF_WEAR: f32 = 0.7;
+
+/// The lap in progress, as it started
+#[derive(Debug, Clone, Copy)]
+struct LapStart {
+    number: u32,
+    fuel: Option<f32>,
+    wear: Option<f32>,
+    /// False for a lap whose start was missed, or with a refuel or a tyre
+    /// change so far
+    counts: bool,
+}
+
+/// Fuel use and tyre wear of recent laps, carried across frames
+#[derive(Debug, Default)]
+pub struct StrategyEstimator {
+    lap: Option<LapStart>,
+    /// Fuel and wear of the last frame, to spot refuels and tyre changes
+    last_fuel: Option<f32>,
+    last_wear: Option<f32>,
+    fuel_used: VecDeque<f32>,
+    wear_added: VecDeque<f32>,
+    fuel_per_lap: Option<f32>,
+    wear_per_lap: Option<f32>,
+}
+
+impl StrategyEstimator {
+    /// Fill in `predictions` unless the adapter already did
+    pub fn apply(&mut self, frame: &mut TelemetryFrame) {
+        let predictions = self.update(frame);
+        if frame.predictions.is_none() {
+            frame.predictions = predictions;
+        }
+    }
+
+    /// Predictions as of `frame`. `None` without a lap number, or until a
+    /// lap has been measured.
+    pub fn update(&mut self, frame: &TelemetryFrame) -> Option<PredictionData> {
+        let lap = frame.timing.as_ref()?.lap_number?;
+        let fuel = frame
+            .engine
+            .as_ref()
+            .and_then(|e| e.fuel_level)
+            .map(|f| f.0);
+        let wear = frame.wheels.as_ref().and_then(|w| {
+            w.all_wheels()
+                .into_iter()
+                .filter_map(tyre_wear)
+                .reduce(f32::max)
+        });
+
+        let refuelled = matches!((self.last_fuel, fuel), (Some(last), Some(now)) if now > last);
+        let new_tyres = matches!((self.last_wear, wear), (Some(last), Some(now)) if now < last);
+        self.last_fuel = fuel;
+        self.last_wear = wear;
+
+        match self.lap.as_mut() {
+            Some(current) if current.number == lap => {
+                current.counts &= !refuelled && !new_tyres;
+            }
+            Some(current) if lap == current.number + 1 => {
+                // Crossed the line: the lap just completed is measured
+                if current.counts && !refuelled && !new_tyres {
+                    let previous = *current;
+                    self.lap_completed(previous, fuel, wear);
+                }
+                self.lap = Some(LapStart {
+                    number: lap,
+                    fuel,
+                    wear,
+                    counts: true,
+                });
+            }
+            // First frame, a reset or a dropout: the start of this lap was missed
+            _ => {
+                self.lap = Some(LapStart {
+                    number: lap,
+                    fuel,
+                    wear,
+                    counts: false,
+                });
+            }
+        }
+
+        let predictions = PredictionData {
+            fuel_per_lap: self.fuel_per_lap.map(Liters),
+            fuel_laps_remaining: self.fuel_per_lap.zip(fuel).map(|(rate, fuel)| fuel / rate),
+            tyre_wear_per_lap: self.wear_per_lap.map(Percentage),
+            tyre_cliff_lap: self.wear_per_lap.zip(wear).map(|(rate, wear)| {
+                if wear >= TYRE_CLIFF_WEAR {
+                    lap
+                } else {
+                    lap + ((TYRE_CLIFF_WEAR - wear) / rate).floor() as u32
+                }
+            }),
+        };
+        (predictions != PredictionData::default()).then_some(predictions)
+    }
+
+    /// Record the fuel and wear of a completed lap and recalculate the rates
+    fn lap_completed(&mut self, start: LapStart, fuel: Option<f32>, wear: Option<f32>) {
+        if let Some(used) = start.fuel.zip(fuel).map(|(start, end)| start - end) {
+            if used > 0.0 {
+                push_recent(&mut self.fuel_used, used);
+                self.fuel_per_lap = average(&self.fuel_used);
+            }
+        }
+        if let Some(added) = start.wear.zip(wear).map(|(start, end)| end - start) {
+            if added > 0.0 {
+                push_recent(&mut self.wear_added, added);
+                self.wear_per_lap = average(&self.wear_added);
+            }
+        }
+    }
+}
+
+/// Overall wear, or the most worn part of the tread
+fn tyre_wear(wheel: &WheelInfo) -> Option<f32> {
+    wheel.tyre_wear.map(|w| w.0).or_else(|| {
+        [
+            wheel.tyre_wear_inner,
+            wheel.tyre_wear_middle,
+            wheel.tyre_wear_outer,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|w| w.0)
+        .reduce(f32::max)
+    })
+}
+
+fn push_recent(laps: &mut VecDeque<f32>, value: f32) {
+    if laps.len() == RECENT_LAPS {
+        laps.pop_front();
+    }
+    laps.push_back(value);
+}
+
+fn average(laps: &VecDeque<f32>) -> Option<f32> {
+    (!laps.is_empty()).then(|| laps.iter().sum::<f32>() / laps.len() as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{EngineData, TimingData, WheelData};
+
+    fn frame(lap: u32, fuel: f32, wear: f32) -> TelemetryFrame {
+        let mut frame = TelemetryFrame::new("test");
+        frame.timing = Some(TimingData {
+            lap_number: Some(lap),
+            ..Default::default()
+        });
+        frame.engine = Some(EngineData {
+            fuel_level: Some(Liters(fuel)),
+            ..Default::default()
+        });
+        frame.wheels = Some(WheelData {
+            front_left: WheelInfo {
+                tyre_wear: Some(Percentage(wear)),
+                ..Default::default()
+            },
+            rear_right: WheelInfo {
+                tyre_wear_outer: Some(Percentage(wear / 2.0)),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        frame
+    }
+
+    #[test]
+    fn test_predicts_from_completed_laps() {
+        let mut strategy = StrategyEstimator::default();
+        // Joined mid-lap: nothing measured yet
+        assert_eq!(strategy.update(&frame(3, 52.0, 0.18)), None);
+        assert_eq!(strategy.update(&frame(4, 50.0, 0.2)), None);
+
+        let mut lap_end = frame(5, 47.0, 0.25);
+        strategy.apply(&mut lap_end);
+        let predictions = lap_end.predictions.unwrap();
+        assert_eq!(predictions.fuel_per_lap, Some(Liters(3.0)));
+        assert!((predictions.fuel_laps_remaining.unwrap() - 47.0 / 3.0).abs() < 1e-4);
+        assert!((predictions.tyre_wear_per_lap.unwrap().0 - 0.05).abs() < 1e-6);
+        // 0.45 more wear at 0.05 a lap
+        assert_eq!(predictions.tyre_cliff_lap, Some(14));
+
+        // Laps remaining follow the fuel level between lap completions
+        let predictions = strategy.update(&frame(5, 45.5, 0.26)).unwrap();
+        assert!((predictions.fuel_laps_remaining.unwrap() - 45.5 / 3.0).abs() < 1e-4);
+
+        // A lap with a pit stop doesn't count
+        strategy.update(&frame(5, 60.0, 0.02));
+        let predictions = strategy.update(&frame(6, 59.0, 0.03)).unwrap();
+        assert_eq!(predictions.fuel_per_lap, Some(Liters(3.0)));
+
+        let predictions = strategy.update(&frame(7, 57.0, 0.07)).unwrap();
+        assert_eq!(predictions.fuel_per_lap, Some(Liters(2.5)));
+    }
+}
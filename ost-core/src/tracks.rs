@@ -0,0 +1,264 @@
+//! Track database
+//!
+//! Sector boundaries, corner names and pit lane bounds of known tracks, keyed
+//! by the sim's own track identifier as adapters report it in
+//! `session.track_id` (`<sim>:<track>`, e.g. `iracing:bathurst`). A few
+//! tracks are built in; users can add more, or replace built-in ones, from
+//! their own [`Track`] entries. [`TrackDatabase::enrich`] fills in what a
+//! frame's adapter left out from its track's entry.
+//!
+//! All positions are lap distance, 0.0 to 1.0 from the start/finish line.
+
+use crate::model::TelemetryFrame;
+use crate::units::Percentage;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Built-in entries
+const BUILTIN: &str = include_str!("../tracks.json");
+
+/// Sector starts further apart than this from the sim's disagree
+const SECTOR_TOLERANCE: f32 = 0.001;
+
+/// What's known about one track layout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Track {
+    /// As in `session.track_id`, matched case-insensitively
+    pub id: String,
+    /// Where each sector starts, the first at 0
+    #[serde(default)]
+    pub sectors: Vec<f32>,
+    /// Named corners, in lap order
+    #[serde(default)]
+    pub corners: Vec<TrackCorner>,
+    #[serde(default)]
+    pub pit_lane: Option<PitLane>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrackCorner {
+    pub name: String,
+    pub start: f32,
+    /// Before `start` for a corner across the start/finish line
+    pub end: f32,
+}
+
+/// Where the pit lane leaves and rejoins the track
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PitLane {
+    pub entry: f32,
+    pub exit: f32,
+}
+
+impl Track {
+    /// Whether positions are lap distances and sectors start at 0 and
+    /// increase
+    pub fn check(&self) -> Result<()> {
+        let in_lap = |p: f32| (0.0..=1.0).contains(&p);
+        let positions = self
+            .sectors
+            .iter()
+            .copied()
+            .chain(self.corners.iter().flat_map(|c| [c.start, c.end]))
+            .chain(self.pit_lane.iter().flat_map(|p| [p.entry, p.exit]));
+        for p in positions {
+            if !in_lap(p) {
+                bail!("track {}: {} is not a lap distance (0 to 1)", self.id, p);
+            }
+        }
+        if self.sectors.first().is_some_and(|&s| s != 0.0) {
+            bail!("track {}: the first sector must start at 0", self.id);
+        }
+        if self.sectors.windows(2).any(|w| w[0] >= w[1]) {
+            bail!("track {}: sectors must be in lap order", self.id);
+        }
+        Ok(())
+    }
+
+    /// The named corner at lap distance `pct`
+    pub fn corner_at(&self, pct: f32) -> Option<&TrackCorner> {
+        self.corners.iter().find(|c| {
+            if c.start <= c.end {
+                (c.start..=c.end).contains(&pct)
+            } else {
+                pct >= c.start || pct <= c.end
+            }
+        })
+    }
+
+    /// Whether sector starts reported by a sim agree with this entry
+    pub fn sectors_match(&self, starts: &[Percentage]) -> bool {
+        starts.len() == self.sectors.len()
+            && starts
+                .iter()
+                .zip(&self.sectors)
+                .all(|(a, b)| (a.0 - b).abs() <= SECTOR_TOLERANCE)
+    }
+}
+
+/// Tracks by id
+#[derive(Debug, Clone, Default)]
+pub struct TrackDatabase {
+    tracks: Vec<Track>,
+}
+
+impl TrackDatabase {
+    /// The built-in entries
+    pub fn builtin() -> Self {
+        let tracks = serde_json::from_str(BUILTIN).expect("built-in track database is valid");
+        Self { tracks }
+    }
+
+    /// Add `tracks`, replacing entries with the same id
+    pub fn with_overrides(mut self, tracks: impl IntoIterator<Item = Track>) -> Self {
+        for track in tracks {
+            self.tracks
+                .retain(|t| !t.id.eq_ignore_ascii_case(&track.id));
+            self.tracks.push(track);
+        }
+        self
+    }
+
+    pub fn find(&self, id: &str) -> Option<&Track> {
+        self.tracks.iter().find(|t| t.id.eq_ignore_ascii_case(id))
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// Fill in the frame's sector starts, pit lane bounds and corner name
+    /// from its track's entry, keeping anything the adapter reported.
+    /// Returns false if the adapter's own sectors (starts, or more sector
+    /// times than the track has sectors) disagree with the entry.
+    pub fn enrich(&self, frame: &mut TelemetryFrame) -> bool {
+        let Some(session) = frame.session.as_mut() else {
+            return true;
+        };
+        let Some(track) = session.track_id.as_deref().and_then(|id| self.find(id)) else {
+            return true;
+        };
+
+        let mut consistent = true;
+        if !track.sectors.is_empty() {
+            match &session.sector_starts {
+                Some(starts) => consistent &= track.sectors_match(starts),
+                None => {
+                    session.sector_starts =
+                        Some(track.sectors.iter().map(|&s| Percentage(s)).collect())
+                }
+            }
+        }
+        if let Some(pit_lane) = track.pit_lane {
+            session
+                .pit_entry_pct
+                .get_or_insert(Percentage(pit_lane.entry));
+            session
+                .pit_exit_pct
+                .get_or_insert(Percentage(pit_lane.exit));
+        }
+
+        if let Some(timing) = frame.timing.as_mut() {
+            if let Some(times) = &timing.sector_times {
+                consistent &= track.sectors.is_empty() || times.len() <= track.sectors.len();
+            }
+            if timing.corner_name.is_none() {
+                timing.corner_name = timing
+                    .lap_distance_pct
+                    .and_then(|pct| track.corner_at(pct.0))
+                    .map(|c| c.name.clone());
+            }
+        }
+        consistent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{SessionData, TimingData};
+    use crate::units::Seconds;
+
+    fn frame(track_id: &str, pct: f32) -> TelemetryFrame {
+        let mut frame = TelemetryFrame::new("test");
+        frame.session = Some(SessionData {
+            track_id: Some(track_id.to_string()),
+            ..Default::default()
+        });
+        frame.timing = Some(TimingData {
+            lap_distance_pct: Some(Percentage(pct)),
+            ..Default::default()
+        });
+        frame
+    }
+
+    #[test]
+    fn test_builtin() {
+        let tracks = TrackDatabase::builtin();
+        assert!(!tracks.tracks().is_empty());
+        for track in tracks.tracks() {
+            track.check().unwrap();
+        }
+        assert!(tracks.find("IRACING:bathurst").is_some());
+    }
+
+    #[test]
+    fn test_enrich() {
+        let tracks = TrackDatabase::builtin();
+        let mut hairpin = frame("demo:demo_circuit", 0.85);
+        assert!(tracks.enrich(&mut hairpin));
+        let session = hairpin.session.unwrap();
+        assert_eq!(session.sector_starts.unwrap().len(), 3);
+        assert!(session.pit_entry_pct.is_some());
+        assert_eq!(
+            hairpin.timing.unwrap().corner_name.as_deref(),
+            Some("The Hairpin")
+        );
+
+        let mut straight = frame("demo:demo_circuit", 0.05);
+        tracks.enrich(&mut straight);
+        assert_eq!(straight.timing.unwrap().corner_name, None);
+
+        // The sim's own sectors are kept, but flagged when they disagree
+        let mut reported = frame("demo:demo_circuit", 0.5);
+        reported.session.as_mut().unwrap().sector_starts = Some(vec![Percentage(0.0)]);
+        assert!(!tracks.enrich(&mut reported));
+        assert_eq!(reported.session.unwrap().sector_starts.unwrap().len(), 1);
+        let mut too_many = frame("demo:demo_circuit", 0.5);
+        too_many.timing.as_mut().unwrap().sector_times = Some(vec![Seconds(20.0); 4]);
+        assert!(!tracks.enrich(&mut too_many));
+
+        let mut unknown = frame("demo:elsewhere", 0.85);
+        assert!(tracks.enrich(&mut unknown));
+        assert!(unknown.session.unwrap().sector_starts.is_none());
+    }
+
+    #[test]
+    fn test_overrides() {
+        let custom = Track {
+            id: "Demo:Demo_Circuit".into(),
+            sectors: vec![0.0, 0.5],
+            corners: vec![TrackCorner {
+                name: "Last and First".into(),
+                start: 0.95,
+                end: 0.05,
+            }],
+            pit_lane: None,
+        };
+        custom.check().unwrap();
+        let tracks = TrackDatabase::builtin().with_overrides([custom]);
+        let track = tracks.find("demo:demo_circuit").unwrap();
+        assert_eq!(track.sectors, [0.0, 0.5]);
+        assert_eq!(track.corner_at(0.99).unwrap().name, "Last and First");
+        assert!(track.corner_at(0.5).is_none());
+
+        let backwards = Track {
+            sectors: vec![0.0, 0.6, 0.3],
+            ..track.clone()
+        };
+        assert!(backwards.check().is_err());
+    }
+}
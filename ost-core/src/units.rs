@@ -4,14 +4,661 @@
 //! type safety and prevent unit confusion.
 //!
 //! All unit types serialize with 4 decimal places to reduce JSON payload size.
+//!
+//! Values are stored and serialized in the units named by their type. For
+//! JSON shown to people, [`Units`] serializes a value with a [`UnitProfile`]'s
+//! display units (mph, °F, psi, ...) instead.
 
+use anyhow::{anyhow, bail};
+use serde::ser::{self, Serializer};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 /// Round f32 to 4 decimal places for compact JSON serialization
 fn round4<S: serde::Serializer>(val: &f32, s: S) -> Result<S::Ok, S::Error> {
     s.serialize_f32((*val * 10000.0).round() / 10000.0)
 }
 
+/// Speed display unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedUnit {
+    MetersPerSecond,
+    KilometersPerHour,
+    MilesPerHour,
+}
+
+impl SpeedUnit {
+    pub fn from_meters_per_second(self, v: f32) -> f32 {
+        match self {
+            SpeedUnit::MetersPerSecond => v,
+            SpeedUnit::KilometersPerHour => v * 3.6,
+            SpeedUnit::MilesPerHour => v * 2.236_936,
+        }
+    }
+}
+
+/// Temperature display unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    pub fn from_celsius(self, v: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => v,
+            TemperatureUnit::Fahrenheit => v * 1.8 + 32.0,
+        }
+    }
+}
+
+/// Pressure display unit. `Native` keeps each field in the unit of its type
+/// (kPa for tyre pressures, bar for manifold pressure).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressureUnit {
+    Native,
+    Kilopascals,
+    Bar,
+    Psi,
+}
+
+impl PressureUnit {
+    /// Convert `v`, expressed in units of `pascals_per_unit` pascals.
+    pub fn from_pascals(self, v: f32, pascals_per_unit: f32) -> f32 {
+        match self {
+            PressureUnit::Native => v,
+            PressureUnit::Kilopascals => v * pascals_per_unit / 1_000.0,
+            PressureUnit::Bar => v * pascals_per_unit / 100_000.0,
+            PressureUnit::Psi => v * pascals_per_unit / 6_894.757,
+        }
+    }
+}
+
+/// Distance display unit. `Feet` also turns millimeters into inches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceUnit {
+    Meters,
+    Feet,
+}
+
+impl DistanceUnit {
+    pub fn from_meters(self, v: f32) -> f32 {
+        match self {
+            DistanceUnit::Meters => v,
+            DistanceUnit::Feet => v / 0.3048,
+        }
+    }
+
+    pub fn from_millimeters(self, v: f32) -> f32 {
+        match self {
+            DistanceUnit::Meters => v,
+            DistanceUnit::Feet => v / 25.4,
+        }
+    }
+}
+
+/// Volume display unit (US gallons)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeUnit {
+    Liters,
+    Gallons,
+}
+
+impl VolumeUnit {
+    pub fn from_liters(self, v: f32) -> f32 {
+        match self {
+            VolumeUnit::Liters => v,
+            VolumeUnit::Gallons => v / 3.785_412,
+        }
+    }
+}
+
+/// Display units applied when serializing unit values.
+///
+/// Parsed from `metric`, `imperial`, or either followed by per-quantity
+/// overrides, e.g. `imperial,pressure:kpa` or `metric,speed:kph`:
+///
+/// | Quantity | Units |
+/// |---|---|
+/// | `speed` | `mps`, `kph`, `mph` |
+/// | `temperature` | `c`, `f` |
+/// | `pressure` | `native`, `kpa`, `bar`, `psi` |
+/// | `distance` | `m`, `ft` |
+/// | `volume` | `l`, `gal` |
+///
+/// Converted output is for display only; frames deserialized from it will
+/// not hold the units their types claim.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct UnitProfile {
+    pub speed: SpeedUnit,
+    pub temperature: TemperatureUnit,
+    pub pressure: PressureUnit,
+    pub distance: DistanceUnit,
+    pub volume: VolumeUnit,
+}
+
+impl Default for UnitProfile {
+    fn default() -> Self {
+        Self::METRIC
+    }
+}
+
+impl UnitProfile {
+    /// The units of the data model itself
+    pub const METRIC: Self = Self {
+        speed: SpeedUnit::MetersPerSecond,
+        temperature: TemperatureUnit::Celsius,
+        pressure: PressureUnit::Native,
+        distance: DistanceUnit::Meters,
+        volume: VolumeUnit::Liters,
+    };
+
+    pub const IMPERIAL: Self = Self {
+        speed: SpeedUnit::MilesPerHour,
+        temperature: TemperatureUnit::Fahrenheit,
+        pressure: PressureUnit::Psi,
+        distance: DistanceUnit::Feet,
+        volume: VolumeUnit::Gallons,
+    };
+
+    /// Whether serializing with this profile changes nothing
+    pub fn is_metric(&self) -> bool {
+        *self == Self::METRIC
+    }
+
+    /// `value` of the unit newtype named `unit` (`"Celsius"`) in this
+    /// profile's display unit, `None` for units it leaves alone
+    fn convert(&self, unit: &str, value: f32) -> Option<f32> {
+        Some(match unit {
+            "Meters" => self.distance.from_meters(value),
+            "Millimeters" | "MillimetersPerSecond" => self.distance.from_millimeters(value),
+            "MetersPerSecond" => self.speed.from_meters_per_second(value),
+            "Celsius" => self.temperature.from_celsius(value),
+            "Pascals" => self.pressure.from_pascals(value, 1.0),
+            "Kilopascals" => self.pressure.from_pascals(value, 1_000.0),
+            "Bar" => self.pressure.from_pascals(value, 100_000.0),
+            "Liters" | "LitersPerHour" => self.volume.from_liters(value),
+            _ => return None,
+        })
+    }
+
+    fn set(&mut self, quantity: &str, unit: &str) -> anyhow::Result<()> {
+        match (quantity, unit) {
+            ("speed", "mps" | "m/s") => self.speed = SpeedUnit::MetersPerSecond,
+            ("speed", "kph" | "kmh" | "km/h") => self.speed = SpeedUnit::KilometersPerHour,
+            ("speed", "mph") => self.speed = SpeedUnit::MilesPerHour,
+            ("temperature", "c") => self.temperature = TemperatureUnit::Celsius,
+            ("temperature", "f") => self.temperature = TemperatureUnit::Fahrenheit,
+            ("pressure", "native") => self.pressure = PressureUnit::Native,
+            ("pressure", "kpa") => self.pressure = PressureUnit::Kilopascals,
+            ("pressure", "bar") => self.pressure = PressureUnit::Bar,
+            ("pressure", "psi") => self.pressure = PressureUnit::Psi,
+            ("distance", "m") => self.distance = DistanceUnit::Meters,
+            ("distance", "ft") => self.distance = DistanceUnit::Feet,
+            ("volume", "l") => self.volume = VolumeUnit::Liters,
+            ("volume", "gal") => self.volume = VolumeUnit::Gallons,
+            ("speed" | "temperature" | "pressure" | "distance" | "volume", _) => {
+                bail!("Unknown {} unit '{}'", quantity, unit)
+            }
+            _ => bail!("Unknown quantity '{}'", quantity),
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for UnitProfile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut profile = Self::METRIC;
+        for (i, entry) in s.split(',').map(str::trim).enumerate() {
+            let entry = entry.to_lowercase();
+            match (entry.as_str(), entry.split_once(':')) {
+                ("metric", _) if i == 0 => profile = Self::METRIC,
+                ("imperial", _) if i == 0 => profile = Self::IMPERIAL,
+                (_, Some((quantity, unit))) => profile.set(quantity.trim(), unit.trim())?,
+                _ => {
+                    return Err(anyhow!(
+                    "Invalid unit profile entry '{}' (expected metric, imperial or quantity:unit)",
+                    entry
+                ))
+                }
+            }
+        }
+        Ok(profile)
+    }
+}
+
+impl fmt::Display for UnitProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Whichever base needs fewer overrides
+        let overrides = |base: &UnitProfile| {
+            let mut out = Vec::new();
+            if self.speed != base.speed {
+                out.push(match self.speed {
+                    SpeedUnit::MetersPerSecond => "speed:mps",
+                    SpeedUnit::KilometersPerHour => "speed:kph",
+                    SpeedUnit::MilesPerHour => "speed:mph",
+                });
+            }
+            if self.temperature != base.temperature {
+                out.push(match self.temperature {
+                    TemperatureUnit::Celsius => "temperature:c",
+                    TemperatureUnit::Fahrenheit => "temperature:f",
+                });
+            }
+            if self.pressure != base.pressure {
+                out.push(match self.pressure {
+                    PressureUnit::Native => "pressure:native",
+                    PressureUnit::Kilopascals => "pressure:kpa",
+                    PressureUnit::Bar => "pressure:bar",
+                    PressureUnit::Psi => "pressure:psi",
+                });
+            }
+            if self.distance != base.distance {
+                out.push(match self.distance {
+                    DistanceUnit::Meters => "distance:m",
+                    DistanceUnit::Feet => "distance:ft",
+                });
+            }
+            if self.volume != base.volume {
+                out.push(match self.volume {
+                    VolumeUnit::Liters => "volume:l",
+                    VolumeUnit::Gallons => "volume:gal",
+                });
+            }
+            out
+        };
+
+        let metric = overrides(&Self::METRIC);
+        let imperial = overrides(&Self::IMPERIAL);
+        let (base, rest) = if imperial.len() < metric.len() {
+            ("imperial", imperial)
+        } else {
+            ("metric", metric)
+        };
+        write!(f, "{}", base)?;
+        for entry in rest {
+            write!(f, ",{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for UnitProfile {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> anyhow::Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<UnitProfile> for String {
+    fn from(profile: UnitProfile) -> Self {
+        profile.to_string()
+    }
+}
+
+// =============================================================================
+// Display units
+// =============================================================================
+
+/// `value` serialized with the display units of a profile in place of the
+/// model's, e.g. `serde_json::to_string(&Units(&overlay, profile))`. For JSON
+/// shown to people only: frames read back from it don't hold the units their
+/// types claim, so binary encodings always carry the model's.
+pub struct Units<'a, T: ?Sized>(pub &'a T, pub UnitProfile);
+
+impl<T: Serialize + ?Sized> Serialize for Units<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.1.is_metric() {
+            return self.0.serialize(serializer);
+        }
+        self.0.serialize(Converting {
+            inner: serializer,
+            profile: self.1,
+        })
+    }
+}
+
+/// A value of a unit newtype, converted and rounded
+struct Converted(f32);
+
+impl Serialize for Converted {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        round4(&self.0, serializer)
+    }
+}
+
+/// Passes everything through to `inner`, converting the values of unit
+/// newtypes on the way
+struct Converting<S> {
+    inner: S,
+    profile: UnitProfile,
+}
+
+/// A sequence, map or struct being serialized by [`Converting`], whose
+/// elements are converted too
+struct Compound<C> {
+    inner: C,
+    profile: UnitProfile,
+}
+
+impl<C> Compound<C> {
+    fn new(inner: C, profile: UnitProfile) -> Self {
+        Self { inner, profile }
+    }
+}
+
+impl<S: Serializer> Serializer for Converting<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Compound<S::SerializeSeq>;
+    type SerializeTuple = Compound<S::SerializeTuple>;
+    type SerializeTupleStruct = Compound<S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Compound<S::SerializeTupleVariant>;
+    type SerializeMap = Compound<S::SerializeMap>;
+    type SerializeStruct = Compound<S::SerializeStruct>;
+    type SerializeStructVariant = Compound<S::SerializeStructVariant>;
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        // Unit newtypes hold an f32, taken back out of its serialization
+        let converted = serde_json::to_value(value)
+            .ok()
+            .and_then(|v| v.as_f64())
+            .and_then(|v| self.profile.convert(name, v as f32));
+        match converted {
+            Some(v) => self.inner.serialize_newtype_struct(name, &Converted(v)),
+            None => self
+                .inner
+                .serialize_newtype_struct(name, &Units(value, self.profile)),
+        }
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_some(&Units(value, self.profile))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner
+            .serialize_newtype_variant(name, index, variant, &Units(value, self.profile))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        Ok(Compound::new(self.inner.serialize_seq(len)?, self.profile))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        Ok(Compound::new(
+            self.inner.serialize_tuple(len)?,
+            self.profile,
+        ))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        let inner = self.inner.serialize_tuple_struct(name, len)?;
+        Ok(Compound::new(inner, self.profile))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        let inner = self
+            .inner
+            .serialize_tuple_variant(name, index, variant, len)?;
+        Ok(Compound::new(inner, self.profile))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        Ok(Compound::new(self.inner.serialize_map(len)?, self.profile))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        Ok(Compound::new(
+            self.inner.serialize_struct(name, len)?,
+            self.profile,
+        ))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        let inner = self
+            .inner
+            .serialize_struct_variant(name, index, variant, len)?;
+        Ok(Compound::new(inner, self.profile))
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_bool(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i8(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i16(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i32(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i64(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i128(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u8(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u16(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u32(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u64(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u128(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_f32(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_f64(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_char(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_str(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_bytes(v)
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_none()
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit_variant(name, index, variant)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+impl<C: ser::SerializeSeq> ser::SerializeSeq for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_element(&Units(value, self.profile))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeTuple> ser::SerializeTuple for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_element(&Units(value, self.profile))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeTupleStruct> ser::SerializeTupleStruct for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_field(&Units(value, self.profile))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeTupleVariant> ser::SerializeTupleVariant for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_field(&Units(value, self.profile))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeMap> ser::SerializeMap for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), C::Error> {
+        self.inner.serialize_key(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_value(&Units(value, self.profile))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeStruct> ser::SerializeStruct for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        self.inner.serialize_field(key, &Units(value, self.profile))
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeStructVariant> ser::SerializeStructVariant for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        self.inner.serialize_field(key, &Units(value, self.profile))
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
 /// Meters
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Meters(#[serde(serialize_with = "round4")] pub f32);
@@ -146,3 +793,90 @@ pub struct NewtonMeters(#[serde(serialize_with = "round4")] pub f32);
 /// Kilograms per cubic meter (density)
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct KilogramsPerCubicMeter(#[serde(serialize_with = "round4")] pub f32);
+
+/// Megajoules (energy, e.g. hybrid deployment)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Megajoules(#[serde(serialize_with = "round4")] pub f32);
+
+/// Kilowatts (power)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Kilowatts(#[serde(serialize_with = "round4")] pub f32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_profile_parse() {
+        assert_eq!(
+            "metric".parse::<UnitProfile>().unwrap(),
+            UnitProfile::METRIC
+        );
+        assert_eq!(
+            "Imperial".parse::<UnitProfile>().unwrap(),
+            UnitProfile::IMPERIAL
+        );
+
+        let custom: UnitProfile = "imperial, pressure:kpa".parse().unwrap();
+        assert_eq!(custom.speed, SpeedUnit::MilesPerHour);
+        assert_eq!(custom.pressure, PressureUnit::Kilopascals);
+
+        let custom: UnitProfile = "speed:kph".parse().unwrap();
+        assert_eq!(custom.speed, SpeedUnit::KilometersPerHour);
+        assert_eq!(custom.temperature, TemperatureUnit::Celsius);
+
+        assert!("furlongs".parse::<UnitProfile>().is_err());
+        assert!("speed:knots".parse::<UnitProfile>().is_err());
+        assert!("metric,imperial".parse::<UnitProfile>().is_err());
+    }
+
+    #[test]
+    fn test_unit_profile_display_roundtrip() {
+        for s in [
+            "metric",
+            "imperial",
+            "metric,speed:kph",
+            "imperial,pressure:kpa",
+        ] {
+            let profile: UnitProfile = s.parse().unwrap();
+            assert_eq!(profile.to_string(), s);
+        }
+    }
+
+    fn json<T: Serialize + ?Sized>(value: &T) -> String {
+        serde_json::to_string(value).unwrap()
+    }
+
+    #[test]
+    fn test_units() {
+        fn imperial<T: Serialize>(value: &T) -> String {
+            json(&Units(value, UnitProfile::IMPERIAL))
+        }
+        let speed = MetersPerSecond(10.0);
+
+        assert_eq!(json(&speed), "10.0");
+        assert_eq!(imperial(&speed), "22.3694");
+        assert_eq!(imperial(&Celsius(100.0)), "212.0");
+        assert_eq!(imperial(&Kilopascals(200.0)), "29.0076");
+        assert_eq!(imperial(&Bar(1.0)), "14.5038");
+        assert_eq!(imperial(&Liters(3.785412)), "1.0");
+        assert_eq!(imperial(&Rpm(5000.0)), "5000.0");
+
+        // Through options, lists, maps and structs
+        #[derive(Serialize)]
+        struct Reading {
+            temps: Vec<Option<Celsius>>,
+            by_name: std::collections::BTreeMap<&'static str, Meters>,
+        }
+        let reading = Reading {
+            temps: vec![Some(Celsius(0.0)), None],
+            by_name: [("lap", Meters(10.0))].into(),
+        };
+        assert_eq!(
+            imperial(&reading),
+            r#"{"temps":[32.0,null],"by_name":{"lap":32.8084}}"#
+        );
+        // Metric is the model's own
+        assert_eq!(json(&Units(&reading, UnitProfile::METRIC)), json(&reading));
+    }
+}
@@ -0,0 +1,562 @@
+//! Sanity checks for telemetry frames
+//!
+//! Shared memory can hand adapters torn or garbage data. [`TelemetryFrame::validate`]
+//! flags values that cannot be physically right (NaN/Inf anywhere, negative
+//! speeds, 400 °C coolant) so they are caught before reaching sinks.
+//!
+//! Only the standard sections are checked; `extras` hold JSON values, which
+//! cannot be non-finite and have no known ranges.
+
+use crate::channels;
+use crate::model::TelemetryFrame;
+use serde::ser::{self, Impossible, Serialize};
+use std::fmt;
+
+/// Plausible ranges for known fields, in model units. `*` matches any one
+/// segment and a trailing `*` matches by prefix.
+const BOUNDS: &[(&str, f64, f64)] = &[
+    ("vehicle.speed", 0.0, 200.0),
+    ("vehicle.rpm", 0.0, 30_000.0),
+    ("vehicle.throttle", 0.0, 1.0),
+    ("vehicle.brake", 0.0, 1.0),
+    ("vehicle.clutch", 0.0, 1.0),
+    ("engine.water_temp", -50.0, 200.0),
+    ("engine.oil_temp", -50.0, 250.0),
+    ("engine.oil_pressure", 0.0, 2_000.0),
+    ("engine.fuel_level", 0.0, 500.0),
+    ("wheels.*.tyre_pressure", 0.0, 1_000.0),
+    ("wheels.*.surface_temp*", -50.0, 400.0),
+    ("wheels.*.carcass_temp*", -50.0, 400.0),
+    ("wheels.*.brake_temp", -50.0, 1_500.0),
+    ("wheels.*.brake_pad_wear", 0.0, 1.0),
+    ("motion.g_force.*", -50.0, 50.0),
+    ("weather.air_temp", -60.0, 70.0),
+    ("weather.track_temp", -60.0, 100.0),
+    ("energy.battery_soc", 0.0, 1.0),
+    ("energy.lap_deploy_pct", 0.0, 1.0),
+];
+
+/// What [`TelemetryFrame::validate`] does with invalid values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationMode {
+    /// Don't check frames
+    Off,
+    /// List invalid fields in `meta.invalid_fields`, leaving values untouched
+    #[default]
+    Annotate,
+    /// Annotate, and also clamp out-of-range values to their bounds and drop
+    /// non-finite ones
+    Clamp,
+}
+
+/// Why a value was flagged
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IssueKind {
+    /// NaN or infinite
+    NonFinite,
+    /// Outside the plausible range for the field
+    OutOfRange { min: f64, max: f64 },
+}
+
+/// A single invalid value
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    /// Dotted path, as used by metric masks (e.g. `wheels.front_left.brake_temp`)
+    pub path: String,
+    pub value: f64,
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            IssueKind::NonFinite => write!(f, "{} is {}", self.path, self.value),
+            IssueKind::OutOfRange { min, max } => write!(
+                f,
+                "{} = {} is outside {}..={}",
+                self.path, self.value, min, max
+            ),
+        }
+    }
+}
+
+impl TelemetryFrame {
+    /// Check the frame for impossible values. Returns the issues found; with
+    /// [`ValidationMode::Clamp`] they have also been repaired.
+    pub fn validate(&mut self, mode: ValidationMode) -> Vec<ValidationIssue> {
+        if mode == ValidationMode::Off {
+            return Vec::new();
+        }
+
+        let mut issues = Vec::new();
+        check_section(&self.motion, "motion", &mut issues);
+        check_section(&self.vehicle, "vehicle", &mut issues);
+        check_section(&self.engine, "engine", &mut issues);
+        check_section(&self.wheels, "wheels", &mut issues);
+        check_section(&self.timing, "timing", &mut issues);
+        check_section(&self.session, "session", &mut issues);
+        check_section(&self.weather, "weather", &mut issues);
+        check_section(&self.pit, "pit", &mut issues);
+        check_section(&self.electronics, "electronics", &mut issues);
+        check_section(&self.damage, "damage", &mut issues);
+        check_section(&self.energy, "energy", &mut issues);
+        check_section(&self.competitors, "competitors", &mut issues);
+        check_section(&self.driver, "driver", &mut issues);
+        check_section(&self.relative, "relative", &mut issues);
+        check_section(&self.predictions, "predictions", &mut issues);
+
+        if mode == ValidationMode::Clamp {
+            for issue in &issues {
+                self.repair(issue);
+            }
+        }
+
+        self.meta.invalid_fields = if issues.is_empty() {
+            None
+        } else {
+            Some(issues.iter().map(|i| i.path.clone()).collect())
+        };
+        issues
+    }
+
+    /// Clamp an out-of-range value to its bounds, or drop a non-finite one
+    fn repair(&mut self, issue: &ValidationIssue) {
+        // List indexes become `*`, and lists of numbers are addressed whole
+        let mut element = 0;
+        let segments: Vec<&str> = issue
+            .path
+            .split('.')
+            .map(|s| match s.parse() {
+                Ok(index) => {
+                    element = index;
+                    "*"
+                }
+                Err(_) => s,
+            })
+            .collect();
+        let path = segments.join(".");
+        let Some(channel) =
+            channels::find(&path).or_else(|| channels::find(path.strip_suffix(".*")?))
+        else {
+            return;
+        };
+        match issue.kind {
+            IssueKind::OutOfRange { min, max } => {
+                channel.set_value(self, issue.value.clamp(min, max));
+            }
+            IssueKind::NonFinite => {
+                channel.clear_value(self, element);
+            }
+        }
+    }
+}
+
+fn check_section<T: Serialize>(
+    section: &Option<T>,
+    name: &'static str,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    let Some(value) = section.as_ref() else {
+        return;
+    };
+
+    let mut walker = Walker {
+        path: vec![Segment::Key(name)],
+        issues: Vec::new(),
+    };
+    // The walker itself never fails
+    let _ = value.serialize(&mut walker);
+    issues.extend(walker.issues);
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Segment {
+    Key(&'static str),
+    Index(usize),
+}
+
+impl Segment {
+    fn matches(self, pattern: &str) -> bool {
+        match self {
+            Segment::Key(key) => match pattern.strip_suffix('*') {
+                Some(prefix) => key.starts_with(prefix),
+                None => key == pattern,
+            },
+            Segment::Index(i) => pattern == "*" || pattern.parse() == Ok(i),
+        }
+    }
+}
+
+/// Serializer that visits every float in a value, tracking its path
+struct Walker {
+    path: Vec<Segment>,
+    issues: Vec<ValidationIssue>,
+}
+
+impl Walker {
+    fn path_string(&self) -> String {
+        let parts: Vec<String> = self
+            .path
+            .iter()
+            .map(|s| match s {
+                Segment::Key(k) => k.to_string(),
+                Segment::Index(i) => i.to_string(),
+            })
+            .collect();
+        parts.join(".")
+    }
+
+    fn bounds(&self) -> Option<(f64, f64)> {
+        BOUNDS.iter().find_map(|(pattern, min, max)| {
+            let mut segments = pattern.split('.');
+            let matched = self.path.len() == pattern.split('.').count()
+                && self
+                    .path
+                    .iter()
+                    .all(|s| segments.next().is_some_and(|p| s.matches(p)));
+            matched.then_some((*min, *max))
+        })
+    }
+
+    fn check(&mut self, value: f64) {
+        let kind = if !value.is_finite() {
+            IssueKind::NonFinite
+        } else {
+            match self.bounds() {
+                Some((min, max)) if value < min || value > max => {
+                    IssueKind::OutOfRange { min, max }
+                }
+                _ => return,
+            }
+        };
+        self.issues.push(ValidationIssue {
+            path: self.path_string(),
+            value,
+            kind,
+        });
+    }
+}
+
+/// The walker never fails; this only satisfies serde's trait bounds.
+#[derive(Debug)]
+struct Never;
+
+impl fmt::Display for Never {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unreachable")
+    }
+}
+
+impl std::error::Error for Never {}
+
+impl ser::Error for Never {
+    fn custom<T: fmt::Display>(_msg: T) -> Self {
+        Never
+    }
+}
+
+type Done = Result<(), Never>;
+
+impl ser::Serializer for &mut Walker {
+    type Ok = ();
+    type Error = Never;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Impossible<(), Never>;
+    type SerializeTupleVariant = Impossible<(), Never>;
+    type SerializeMap = Impossible<(), Never>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), Never>;
+
+    fn serialize_f32(self, v: f32) -> Done {
+        self.check(v as f64);
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Done {
+        self.check(v);
+        Ok(())
+    }
+
+    fn serialize_bool(self, _: bool) -> Done {
+        Ok(())
+    }
+
+    fn serialize_i8(self, _: i8) -> Done {
+        Ok(())
+    }
+
+    fn serialize_i16(self, _: i16) -> Done {
+        Ok(())
+    }
+
+    fn serialize_i32(self, _: i32) -> Done {
+        Ok(())
+    }
+
+    fn serialize_i64(self, _: i64) -> Done {
+        Ok(())
+    }
+
+    fn serialize_u8(self, _: u8) -> Done {
+        Ok(())
+    }
+
+    fn serialize_u16(self, _: u16) -> Done {
+        Ok(())
+    }
+
+    fn serialize_u32(self, _: u32) -> Done {
+        Ok(())
+    }
+
+    fn serialize_u64(self, _: u64) -> Done {
+        Ok(())
+    }
+
+    fn serialize_char(self, _: char) -> Done {
+        Ok(())
+    }
+
+    fn serialize_str(self, _: &str) -> Done {
+        Ok(())
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Done {
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Done {
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Done {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Done {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Done {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> Done {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _: &'static str, value: &T) -> Done {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Done {
+        Ok(())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self, Never> {
+        self.path.push(Segment::Index(0));
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self, Never> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Never> {
+        Err(Never)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Never> {
+        Err(Never)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Never> {
+        // No maps in the standard sections
+        Err(Never)
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, Never> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Never> {
+        Err(Never)
+    }
+}
+
+impl ser::SerializeStruct for &mut Walker {
+    type Ok = ();
+    type Error = Never;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Done {
+        self.path.push(Segment::Key(key));
+        // Keep walking siblings even if one field can't be visited
+        let _ = value.serialize(&mut **self);
+        self.path.pop();
+        Ok(())
+    }
+
+    fn end(self) -> Done {
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for &mut Walker {
+    type Ok = ();
+    type Error = Never;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Done {
+        let _ = value.serialize(&mut **self);
+        if let Some(Segment::Index(i)) = self.path.last_mut() {
+            *i += 1;
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Done {
+        self.path.pop();
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for &mut Walker {
+    type Ok = ();
+    type Error = Never;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Done {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Done {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CompetitorData, TimingData, Vector3};
+    use crate::units::*;
+
+    fn frame() -> TelemetryFrame {
+        serde_json::from_value(serde_json::json!({
+            "meta": {"timestamp": "2026-01-01T00:00:00Z", "game": "test", "tick": 1},
+            "vehicle": {"speed": 42.0, "rpm": 6000.0, "throttle": 0.5},
+            "engine": {"water_temp": 90.0, "oil_temp": 110.0},
+            "motion": {"velocity": {"x": 0.0, "y": 0.0, "z": 42.0}},
+            "competitors": [{"car_index": 1, "lap_distance_pct": 0.5}]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_valid_frame_has_no_issues() {
+        let mut frame = frame();
+        assert!(frame.validate(ValidationMode::Clamp).is_empty());
+        assert!(frame.meta.invalid_fields.is_none());
+    }
+
+    #[test]
+    fn test_annotate_flags_without_changing() {
+        let mut frame = frame();
+        frame.vehicle.as_mut().unwrap().speed = Some(MetersPerSecond(-5.0));
+        frame.engine.as_mut().unwrap().water_temp = Some(Celsius(400.0));
+
+        let issues = frame.validate(ValidationMode::Annotate);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].path, "vehicle.speed");
+        assert_eq!(issues[1].path, "engine.water_temp");
+        assert!(matches!(issues[1].kind, IssueKind::OutOfRange { .. }));
+        assert_eq!(
+            frame.meta.invalid_fields,
+            Some(vec![
+                "vehicle.speed".to_string(),
+                "engine.water_temp".to_string()
+            ])
+        );
+        assert_eq!(frame.engine.unwrap().water_temp, Some(Celsius(400.0)));
+    }
+
+    #[test]
+    fn test_clamp_repairs_values() {
+        let mut frame = frame();
+        frame.vehicle.as_mut().unwrap().speed = Some(MetersPerSecond(-5.0));
+        frame.vehicle.as_mut().unwrap().rpm = Some(Rpm(f32::NAN));
+        frame.motion.as_mut().unwrap().velocity = Some(Vector3::new(
+            MetersPerSecond(0.0),
+            MetersPerSecond(f32::INFINITY),
+            MetersPerSecond(42.0),
+        ));
+
+        let issues = frame.validate(ValidationMode::Clamp);
+        assert_eq!(issues.len(), 3);
+        let vehicle = frame.vehicle.unwrap();
+        assert_eq!(vehicle.speed, Some(MetersPerSecond(0.0)));
+        assert_eq!(vehicle.rpm, None);
+        assert_eq!(vehicle.throttle, Some(Percentage(0.5)), "others untouched");
+        assert!(frame.motion.unwrap().velocity.is_none());
+    }
+
+    #[test]
+    fn test_walks_arrays() {
+        let mut frame = frame();
+        frame.competitors.as_mut().unwrap()[0].lap_distance_pct = Some(Percentage(f32::NAN));
+
+        let issues = frame.validate(ValidationMode::Annotate);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "competitors.0.lap_distance_pct");
+        assert_eq!(issues[0].kind, IssueKind::NonFinite);
+    }
+
+    #[test]
+    fn test_clamp_repairs_lists() {
+        let mut frame = frame();
+        let mut second = CompetitorData::new(2);
+        second.lap_distance_pct = Some(Percentage(0.25));
+        second.best_lap_time = Some(Seconds(f32::INFINITY));
+        frame.competitors.as_mut().unwrap().push(second);
+        frame.timing = Some(TimingData {
+            sector_times: Some(vec![Seconds(30.0), Seconds(f32::NAN)]),
+            ..Default::default()
+        });
+
+        let issues = frame.validate(ValidationMode::Clamp);
+        assert_eq!(issues.len(), 2);
+        let competitors = frame.competitors.unwrap();
+        assert_eq!(competitors[1].best_lap_time, None);
+        assert_eq!(competitors[1].lap_distance_pct, Some(Percentage(0.25)));
+        assert_eq!(competitors[0].lap_distance_pct, Some(Percentage(0.5)));
+        assert!(frame.timing.unwrap().sector_times.is_none());
+    }
+
+    #[test]
+    fn test_off_skips_checks() {
+        let mut frame = frame();
+        frame.vehicle.as_mut().unwrap().speed = Some(MetersPerSecond(f32::NAN));
+        assert!(frame.validate(ValidationMode::Off).is_empty());
+    }
+}
@@ -0,0 +1,17 @@
+//! Build script that generates the `proto` message types from
+//! `proto/telemetry.proto` with prost-build, along with its descriptor set
+//! for the field number test.
+
+fn main() -> std::io::Result<()> {
+    // The vendored protoc, so building doesn't need one installed
+    if std::env::var_os("PROTOC").is_none() {
+        let protoc = protoc_bin_vendored::protoc_bin_path().map_err(std::io::Error::other)?;
+        std::env::set_var("PROTOC", protoc);
+    }
+    let well_known = protoc_bin_vendored::include_path().map_err(std::io::Error::other)?;
+    let out_dir = std::path::PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+    println!("cargo:rerun-if-changed=proto/telemetry.proto");
+    prost_build::Config::new()
+        .file_descriptor_set_path(out_dir.join("telemetry.bin"))
+        .compile_protos(&["proto/telemetry.proto"], &["proto".into(), well_known])
+}
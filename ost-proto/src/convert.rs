@@ -0,0 +1,566 @@
+//! Conversions between `ost_core::model` and the generated [`proto`] messages
+//!
+//! Each model type implements [`Convert`] to and from the prost type of its
+//! field. The [`message!`] table lists the fields of every message once; both
+//! directions build struct literals from it, so a field added to the model or
+//! the schema but not the other fails to compile.
+
+use crate::{proto, Error};
+use chrono::{DateTime, Utc};
+use ost_core::model::*;
+use ost_core::units::*;
+use prost_types::value::Kind;
+use std::collections::{BTreeMap, HashMap};
+
+/// The part of a value selected by the metric mask
+#[derive(Debug, Clone)]
+pub(crate) enum Scope<'a> {
+    All,
+    /// Only some descendants of `path` are selected
+    Partial {
+        mask: &'a MetricMask,
+        path: Vec<String>,
+    },
+}
+
+impl<'a> Scope<'a> {
+    /// Scope of a whole frame
+    pub fn root(mask: Option<&'a MetricMask>) -> Self {
+        match mask {
+            Some(mask) if !mask.is_all() => Scope::Partial {
+                mask,
+                path: Vec::new(),
+            },
+            _ => Scope::All,
+        }
+    }
+
+    /// Scope of the child `segment`, or `None` if the mask leaves it out.
+    pub fn child(&self, segment: &str) -> Option<Self> {
+        match self {
+            Scope::All => Some(Scope::All),
+            Scope::Partial { mask, path } => {
+                let mut path = path.clone();
+                path.push(segment.to_string());
+                match mask.select(&path) {
+                    MaskSelection::None => None,
+                    MaskSelection::All => Some(Scope::All),
+                    MaskSelection::Partial => Some(Scope::Partial { mask, path }),
+                }
+            }
+        }
+    }
+
+    /// A scalar is only written when fully selected; a partial scope on a
+    /// leaf means the mask asked for something below it.
+    fn is_leaf_selected(&self) -> bool {
+        matches!(self, Scope::All)
+    }
+}
+
+/// A model type and the prost type it's encoded as
+pub(crate) trait Convert: Sized {
+    type Proto;
+
+    /// The value restricted to `scope`, or `None` if nothing in it is selected
+    fn to_proto(&self, scope: &Scope) -> Option<Self::Proto>;
+
+    /// The value from its field, `None` when the field is absent
+    fn from_proto(proto: Option<Self::Proto>) -> Result<Self, Error>;
+}
+
+/// How a prost field holds a value: scalars and repeated fields hold their
+/// default when absent, optional scalars and messages an `Option`.
+pub(crate) trait Slot<P> {
+    fn put(value: Option<P>) -> Self;
+    fn take(self) -> Option<P>;
+}
+
+impl<P> Slot<P> for Option<P> {
+    fn put(value: Option<P>) -> Self {
+        value
+    }
+
+    fn take(self) -> Option<P> {
+        self
+    }
+}
+
+impl<P> Slot<Vec<P>> for Vec<P> {
+    fn put(value: Option<Vec<P>>) -> Self {
+        value.unwrap_or_default()
+    }
+
+    /// Empty repeated fields decode as `None`, as proto3 can't tell them apart
+    fn take(self) -> Option<Vec<P>> {
+        (!self.is_empty()).then_some(self)
+    }
+}
+
+macro_rules! scalar_slot {
+    ($($ty:ty),*) => {$(
+        impl Slot<$ty> for $ty {
+            fn put(value: Option<$ty>) -> Self {
+                value.unwrap_or_default()
+            }
+
+            fn take(self) -> Option<$ty> {
+                Some(self)
+            }
+        }
+    )*};
+}
+
+scalar_slot!(bool, f32, u32, String);
+
+// === Scalars ===
+
+/// Types encoded as themselves
+macro_rules! scalar {
+    ($($ty:ty),*) => {$(
+        impl Convert for $ty {
+            type Proto = $ty;
+
+            fn to_proto(&self, scope: &Scope) -> Option<$ty> {
+                scope.is_leaf_selected().then(|| self.clone())
+            }
+
+            fn from_proto(proto: Option<$ty>) -> Result<Self, Error> {
+                Ok(proto.unwrap_or_default())
+            }
+        }
+    )*};
+}
+
+scalar!(bool, f32, f64, u32, u64, i32, String);
+
+/// Integers narrower than any protobuf type, widened on the wire
+macro_rules! narrow {
+    ($($ty:ty => $proto:ty),*) => {$(
+        impl Convert for $ty {
+            type Proto = $proto;
+
+            fn to_proto(&self, scope: &Scope) -> Option<$proto> {
+                scope.is_leaf_selected().then(|| (*self).into())
+            }
+
+            fn from_proto(proto: Option<$proto>) -> Result<Self, Error> {
+                let proto = proto.unwrap_or_default();
+                <$ty>::try_from(proto).map_err(|_| {
+                    Error::new(format!("{} out of range for {}", proto, stringify!($ty)))
+                })
+            }
+        }
+    )*};
+}
+
+narrow!(u8 => u32, i8 => i32);
+
+/// Unit newtypes, encoded as their value in the model's units
+macro_rules! unit {
+    ($($ty:ident),* $(,)?) => {$(
+        impl Convert for $ty {
+            type Proto = f32;
+
+            fn to_proto(&self, scope: &Scope) -> Option<f32> {
+                scope.is_leaf_selected().then_some(self.0)
+            }
+
+            fn from_proto(proto: Option<f32>) -> Result<Self, Error> {
+                Ok($ty(proto.unwrap_or_default()))
+            }
+        }
+    )*};
+}
+
+unit!(
+    Meters,
+    Millimeters,
+    MetersPerSecond,
+    MillimetersPerSecond,
+    MetersPerSecondSquared,
+    Degrees,
+    DegreesPerSecond,
+    DegreesPerSecondSquared,
+    Rpm,
+    Newtons,
+    Celsius,
+    Kilopascals,
+    Percentage,
+    Seconds,
+    GForce,
+    Liters,
+    LitersPerHour,
+    Volts,
+    Bar,
+    NewtonMeters,
+    KilogramsPerCubicMeter,
+    Megajoules,
+    Kilowatts,
+);
+
+impl Convert for DateTime<Utc> {
+    type Proto = String;
+
+    fn to_proto(&self, scope: &Scope) -> Option<String> {
+        scope.is_leaf_selected().then(|| self.to_rfc3339())
+    }
+
+    fn from_proto(proto: Option<String>) -> Result<Self, Error> {
+        let proto = proto.unwrap_or_default();
+        DateTime::parse_from_rfc3339(&proto)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| Error::new(format!("bad timestamp {:?}: {}", proto, e)))
+    }
+}
+
+// === Containers ===
+
+impl<T: Convert> Convert for Option<T> {
+    type Proto = T::Proto;
+
+    fn to_proto(&self, scope: &Scope) -> Option<T::Proto> {
+        self.as_ref()?.to_proto(scope)
+    }
+
+    fn from_proto(proto: Option<T::Proto>) -> Result<Self, Error> {
+        proto.map(|p| T::from_proto(Some(p))).transpose()
+    }
+}
+
+/// Elements are addressed by index in the metric mask, as in
+/// `MetricMask::filter_value`.
+impl<T: Convert> Convert for Vec<T> {
+    type Proto = Vec<T::Proto>;
+
+    fn to_proto(&self, scope: &Scope) -> Option<Vec<T::Proto>> {
+        Some(
+            self.iter()
+                .enumerate()
+                .filter_map(|(i, v)| v.to_proto(&scope.child(&i.to_string())?))
+                .collect(),
+        )
+    }
+
+    fn from_proto(proto: Option<Vec<T::Proto>>) -> Result<Self, Error> {
+        proto
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| T::from_proto(Some(p)))
+            .collect()
+    }
+}
+
+impl<T: Convert<Proto = f32>> Convert for Vector3<T> {
+    type Proto = proto::Vector3;
+
+    fn to_proto(&self, scope: &Scope) -> Option<proto::Vector3> {
+        let axis = |name: &str, v: &T| scope.child(name).and_then(|s| v.to_proto(&s));
+        Some(proto::Vector3 {
+            x: Slot::put(axis("x", &self.x)),
+            y: Slot::put(axis("y", &self.y)),
+            z: Slot::put(axis("z", &self.z)),
+        })
+    }
+
+    fn from_proto(proto: Option<proto::Vector3>) -> Result<Self, Error> {
+        let proto = proto.unwrap_or_default();
+        Ok(Vector3::new(
+            T::from_proto(Some(proto.x))?,
+            T::from_proto(Some(proto.y))?,
+            T::from_proto(Some(proto.z))?,
+        ))
+    }
+}
+
+// === Enums ===
+
+/// Model enums, encoded as the generated enum's value of the same name
+macro_rules! enumeration {
+    ($($ty:ident { $($variant:ident),* $(,)? })*) => {$(
+        impl Convert for $ty {
+            type Proto = i32;
+
+            fn to_proto(&self, scope: &Scope) -> Option<i32> {
+                let value = match self {
+                    $($ty::$variant => proto::$ty::$variant,)*
+                };
+                scope.is_leaf_selected().then_some(value as i32)
+            }
+
+            fn from_proto(proto: Option<i32>) -> Result<Self, Error> {
+                let proto = proto.unwrap_or_default();
+                match proto::$ty::try_from(proto) {
+                    $(Ok(proto::$ty::$variant) => Ok($ty::$variant),)*
+                    Err(_) => Err(Error::new(format!(
+                        "unknown {} value {}",
+                        stringify!($ty),
+                        proto
+                    ))),
+                }
+            }
+        }
+    )*};
+}
+
+enumeration! {
+    TrackSurface {
+        NotInWorld, Undefined, Asphalt, Concrete, RacingDirt, Paint, Rumble,
+        Grass, Dirt, Sand, Gravel, Grasscrete, Astroturf, Unknown,
+    }
+    SessionType { Practice, Qualifying, Race, Hotlap, TimeTrial, Drift, Warmup, Other }
+    SessionState { Invalid, GetInCar, Warmup, ParadeLaps, Racing, Checkered, Cooldown }
+    TrackWetness { Dry, SlightlyWet, Wet, VeryWet, Flooded, Unknown }
+}
+
+// === Messages ===
+
+/// Model structs and the generated message with the same fields
+macro_rules! message {
+    ($($ty:ident { $($field:ident),* $(,)? })*) => {$(
+        impl Convert for $ty {
+            type Proto = proto::$ty;
+
+            fn to_proto(&self, scope: &Scope) -> Option<proto::$ty> {
+                Some(proto::$ty {
+                    $($field: Slot::put(
+                        scope
+                            .child(stringify!($field))
+                            .and_then(|s| self.$field.to_proto(&s)),
+                    ),)*
+                })
+            }
+
+            fn from_proto(proto: Option<proto::$ty>) -> Result<Self, Error> {
+                let proto = proto.unwrap_or_default();
+                Ok($ty {
+                    $($field: Convert::from_proto(Slot::take(proto.$field))?,)*
+                })
+            }
+        }
+
+        impl From<&$ty> for proto::$ty {
+            fn from(value: &$ty) -> Self {
+                value.to_proto(&Scope::All).expect("messages are always encoded")
+            }
+        }
+
+        impl TryFrom<proto::$ty> for $ty {
+            type Error = Error;
+
+            fn try_from(message: proto::$ty) -> Result<Self, Error> {
+                Convert::from_proto(Some(message))
+            }
+        }
+    )*};
+}
+
+message! {
+    MetaData {
+        timestamp, game, tick, sim_time, invalid_fields, seq, acquired_at,
+        broadcast_at, adapter,
+    }
+    MotionData {
+        position, velocity, acceleration, g_force, rotation, pitch_rate,
+        yaw_rate, roll_rate, angular_acceleration, latitude, longitude,
+        altitude, heading,
+    }
+    VehicleData {
+        speed, rpm, max_rpm, idle_rpm, gear, max_gears, throttle, brake,
+        clutch, steering_angle, steering_torque, steering_torque_pct,
+        handbrake, shift_indicator, steering_angle_max, on_track, in_garage,
+        track_surface, car_name, car_class, setup_name,
+    }
+    EngineData {
+        water_temp, oil_temp, oil_pressure, oil_level, fuel_level,
+        fuel_level_pct, fuel_capacity, fuel_pressure, fuel_use_per_hour,
+        voltage, manifold_pressure, water_level, warnings,
+    }
+    EngineWarnings {
+        water_temp_high, fuel_pressure_low, oil_pressure_low, engine_stalled,
+        pit_speed_limiter, rev_limiter,
+    }
+    WheelData { front_left, front_right, rear_left, rear_right }
+    WheelInfo {
+        suspension_travel, suspension_travel_avg, shock_velocity,
+        shock_velocity_avg, ride_height, tyre_pressure, tyre_cold_pressure,
+        surface_temp_inner, surface_temp_middle, surface_temp_outer,
+        carcass_temp_inner, carcass_temp_middle, carcass_temp_outer,
+        tyre_wear, tyre_wear_inner, tyre_wear_middle, tyre_wear_outer,
+        wheel_speed, slip_ratio, slip_angle, load, brake_line_pressure,
+        brake_temp, tyre_compound, brake_pad_wear, brake_temp_estimated,
+    }
+    TimingData {
+        current_lap_time, last_lap_time, best_lap_time, best_n_lap_time,
+        best_n_lap_num, sector_times, lap_number, laps_completed, lap_distance,
+        lap_distance_pct, race_position, class_position, num_cars, delta_best,
+        delta_best_ok, delta_session_best, delta_session_best_ok,
+        delta_optimal, delta_optimal_ok, estimated_lap_time, race_laps,
+        lap_valid, corner_name,
+    }
+    SessionData {
+        session_type, session_state, session_time, session_time_remaining,
+        session_time_of_day, session_laps, session_laps_remaining, flags,
+        track_name, track_config, track_length, track_type, track_id,
+        sector_starts, pit_entry_pct, pit_exit_pct,
+    }
+    FlagState {
+        green, yellow, yellow_waving, caution, caution_waving, red, blue,
+        white, checkered, black, disqualified, debris, crossed,
+        one_lap_to_green, green_held, ten_to_go, five_to_go, can_service,
+        furled, repair, start_hidden, start_ready, start_set, start_go,
+    }
+    WeatherData {
+        air_temp, track_temp, track_surface_temp, air_pressure, air_density,
+        humidity, wind_speed, wind_direction, fog_level, precipitation,
+        track_wetness, skies, declared_wet,
+    }
+    PitData {
+        on_pit_road, pit_active, pit_service_status, repair_time_left,
+        optional_repair_time_left, fast_repair_available, fast_repair_used,
+        pit_speed_limit, requested_services,
+    }
+    PitServices {
+        fuel_to_add, change_tyre_fl, change_tyre_fr, change_tyre_rl,
+        change_tyre_rr, windshield_tearoff, fast_repair, tyre_pressure_fl,
+        tyre_pressure_fr, tyre_pressure_rl, tyre_pressure_rr,
+    }
+    ElectronicsData {
+        abs, abs_active, traction_control, traction_control_2, brake_bias,
+        anti_roll_front, anti_roll_rear, drs_status, push_to_pass_status,
+        push_to_pass_count, throttle_shape, shift_light_first_rpm,
+        shift_light_shift_rpm, shift_light_last_rpm, shift_light_blink_rpm,
+    }
+    DamageData { front, rear, left, right, engine, transmission }
+    EnergyData {
+        battery_soc, battery_energy, deploy_mode, deploying, regen_level,
+        mgu_k_power, mgu_h_power, lap_deployed, lap_harvested,
+        lap_deploy_allowance, lap_deploy_pct, battery_temp, mgu_k_temp,
+        mgu_h_temp,
+    }
+    CompetitorData {
+        car_index, driver_name, car_name, car_class, team_name, car_number,
+        lap, laps_completed, lap_distance_pct, position, class_position,
+        on_pit_road, track_surface, best_lap_time, last_lap_time,
+        estimated_time, gear, rpm, steering,
+    }
+    DriverData { name, car_index, car_number, team_name, estimated_lap_time }
+    RelativeData { ahead, behind, standings }
+    RelativeCar { car_index, gap, lap_difference }
+    PredictionData {
+        fuel_per_lap, fuel_laps_remaining, tyre_wear_per_lap, tyre_cliff_lap,
+    }
+}
+
+// === TelemetryFrame ===
+
+impl Convert for TelemetryFrame {
+    type Proto = proto::TelemetryFrame;
+
+    fn to_proto(&self, scope: &Scope) -> Option<proto::TelemetryFrame> {
+        let section = |name: &str| scope.child(name);
+        let extras = self
+            .extras
+            .iter()
+            .filter_map(|(ns, value)| {
+                let value = match scope {
+                    Scope::All => value.clone(),
+                    Scope::Partial { mask, .. } => mask.filter_value(ns, value.clone())?,
+                };
+                Some((ns.clone(), json_to_value(value)))
+            })
+            .collect();
+
+        Some(proto::TelemetryFrame {
+            // meta is always included, as in `to_json_filtered`
+            meta: self.meta.to_proto(&Scope::All),
+            motion: section("motion").and_then(|s| self.motion.to_proto(&s)),
+            vehicle: section("vehicle").and_then(|s| self.vehicle.to_proto(&s)),
+            engine: section("engine").and_then(|s| self.engine.to_proto(&s)),
+            wheels: section("wheels").and_then(|s| self.wheels.to_proto(&s)),
+            timing: section("timing").and_then(|s| self.timing.to_proto(&s)),
+            session: section("session").and_then(|s| self.session.to_proto(&s)),
+            weather: section("weather").and_then(|s| self.weather.to_proto(&s)),
+            pit: section("pit").and_then(|s| self.pit.to_proto(&s)),
+            electronics: section("electronics").and_then(|s| self.electronics.to_proto(&s)),
+            damage: section("damage").and_then(|s| self.damage.to_proto(&s)),
+            competitors: Slot::put(
+                section("competitors").and_then(|s| self.competitors.to_proto(&s)),
+            ),
+            driver: section("driver").and_then(|s| self.driver.to_proto(&s)),
+            relative: section("relative").and_then(|s| self.relative.to_proto(&s)),
+            energy: section("energy").and_then(|s| self.energy.to_proto(&s)),
+            predictions: section("predictions").and_then(|s| self.predictions.to_proto(&s)),
+            extras,
+        })
+    }
+
+    fn from_proto(proto: Option<proto::TelemetryFrame>) -> Result<Self, Error> {
+        let proto = proto.unwrap_or_default();
+        Ok(TelemetryFrame {
+            meta: Convert::from_proto(proto.meta)?,
+            motion: Convert::from_proto(proto.motion)?,
+            vehicle: Convert::from_proto(proto.vehicle)?,
+            engine: Convert::from_proto(proto.engine)?,
+            wheels: Convert::from_proto(proto.wheels)?,
+            timing: Convert::from_proto(proto.timing)?,
+            session: Convert::from_proto(proto.session)?,
+            weather: Convert::from_proto(proto.weather)?,
+            pit: Convert::from_proto(proto.pit)?,
+            electronics: Convert::from_proto(proto.electronics)?,
+            damage: Convert::from_proto(proto.damage)?,
+            competitors: Convert::from_proto(Slot::take(proto.competitors))?,
+            driver: Convert::from_proto(proto.driver)?,
+            relative: Convert::from_proto(proto.relative)?,
+            energy: Convert::from_proto(proto.energy)?,
+            predictions: Convert::from_proto(proto.predictions)?,
+            extras: proto
+                .extras
+                .into_iter()
+                .map(|(ns, value)| (ns, value_to_json(value)))
+                .collect::<HashMap<_, _>>(),
+        })
+    }
+}
+
+// === Extras ===
+
+/// A JSON value as a `google.protobuf.Value`. Numbers become doubles, so
+/// integers beyond 2^53 lose precision.
+fn json_to_value(json: serde_json::Value) -> prost_types::Value {
+    let kind = match json {
+        serde_json::Value::Null => Kind::NullValue(0),
+        serde_json::Value::Bool(b) => Kind::BoolValue(b),
+        serde_json::Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or_default()),
+        serde_json::Value::String(s) => Kind::StringValue(s),
+        serde_json::Value::Array(items) => Kind::ListValue(prost_types::ListValue {
+            values: items.into_iter().map(json_to_value).collect(),
+        }),
+        serde_json::Value::Object(fields) => Kind::StructValue(prost_types::Struct {
+            fields: fields
+                .into_iter()
+                .map(|(k, v)| (k, json_to_value(v)))
+                .collect::<BTreeMap<_, _>>(),
+        }),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+/// The JSON for a `google.protobuf.Value`. Whole numbers come back as
+/// integers, as the sims report them.
+fn value_to_json(value: prost_types::Value) -> serde_json::Value {
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => serde_json::Value::Null,
+        Some(Kind::BoolValue(b)) => b.into(),
+        Some(Kind::NumberValue(n)) if n.fract() == 0.0 && n.abs() < 9.0e15 => (n as i64).into(),
+        Some(Kind::NumberValue(n)) => n.into(),
+        Some(Kind::StringValue(s)) => s.into(),
+        Some(Kind::ListValue(list)) => list.values.into_iter().map(value_to_json).collect(),
+        Some(Kind::StructValue(fields)) => fields
+            .fields
+            .into_iter()
+            .map(|(k, v)| (k, value_to_json(v)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+    }
+}
@@ -0,0 +1,274 @@
+//! Protocol Buffers encoding for OpenSimTelemetry frames
+//!
+//! `proto/telemetry.proto` mirrors `ost_core::model`, so consumers in other
+//! languages can generate strongly-typed bindings from it. [`proto`] holds the
+//! same messages generated by prost, with conversions from and to the model:
+//! `proto::TelemetryFrame::from(&frame)` and
+//! `TelemetryFrame::try_from(message)`.
+
+mod convert;
+
+use convert::{Convert, Scope};
+use ost_core::model::{MetricMask, TelemetryFrame};
+use prost::Message;
+
+/// Message types generated from `proto/telemetry.proto`
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/opensimtelemetry.v1.rs"));
+}
+
+/// The `.proto` schema source, e.g. for serving to clients that generate code.
+pub const SCHEMA: &str = include_str!("../proto/telemetry.proto");
+
+/// Protobuf encoding or decoding error
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct Error(String);
+
+impl Error {
+    pub(crate) fn new(msg: impl Into<String>) -> Self {
+        Self(msg.into())
+    }
+}
+
+impl From<prost::DecodeError> for Error {
+    fn from(e: prost::DecodeError) -> Self {
+        Self(e.to_string())
+    }
+}
+
+/// Encode a frame as an `opensimtelemetry.v1.TelemetryFrame` message,
+/// keeping only the fields selected by the metric mask.
+pub fn encode(frame: &TelemetryFrame, mask: Option<&MetricMask>) -> Vec<u8> {
+    proto::TelemetryFrame::masked(frame, mask).encode_to_vec()
+}
+
+/// Decode an `opensimtelemetry.v1.TelemetryFrame` message.
+pub fn decode(bytes: &[u8]) -> Result<TelemetryFrame, Error> {
+    TelemetryFrame::try_from(proto::TelemetryFrame::decode(bytes)?)
+}
+
+impl proto::TelemetryFrame {
+    /// The message for `frame` with only the fields selected by the mask
+    pub fn masked(frame: &TelemetryFrame, mask: Option<&MetricMask>) -> Self {
+        frame
+            .to_proto(&Scope::root(mask))
+            .expect("frames are always encoded")
+    }
+}
+
+impl From<&TelemetryFrame> for proto::TelemetryFrame {
+    fn from(frame: &TelemetryFrame) -> Self {
+        Self::masked(frame, None)
+    }
+}
+
+impl TryFrom<proto::TelemetryFrame> for TelemetryFrame {
+    type Error = Error;
+
+    fn try_from(message: proto::TelemetryFrame) -> Result<Self, Error> {
+        Convert::from_proto(Some(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ost_core::adapter::TelemetryAdapter;
+    use ost_core::model::*;
+    use ost_core::units::*;
+    use std::collections::BTreeMap;
+
+    fn demo_frame() -> TelemetryFrame {
+        let mut adapter = ost_adapters::DemoAdapter::new();
+        adapter.start().unwrap();
+        adapter.read_frame().unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip_demo_frame() {
+        let mut frame = demo_frame();
+        frame.vehicle.as_mut().unwrap().gear = Some(-1);
+        frame.extras.insert(
+            "iracing".to_string(),
+            serde_json::json!({"PlayerCarIdx": 3, "DCLapStatus": [1, 2], "Name": "x", "On": true}),
+        );
+
+        let bytes = encode(&frame, None);
+        let decoded = decode(&bytes).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&frame).unwrap()
+        );
+        assert!(bytes.len() < frame.to_json_filtered(None).unwrap().len());
+    }
+
+    #[test]
+    fn test_typed_messages() {
+        let mut frame = TelemetryFrame::new("iRacing");
+        frame.vehicle = Some(VehicleData {
+            speed: Some(MetersPerSecond(42.5)),
+            gear: Some(3),
+            track_surface: Some(TrackSurface::Asphalt),
+            ..Default::default()
+        });
+        frame.extras.insert(
+            "iracing".to_string(),
+            serde_json::json!({"PlayerCarIdx": 3}),
+        );
+
+        let message = proto::TelemetryFrame::from(&frame);
+        let vehicle = message.vehicle.as_ref().unwrap();
+        assert_eq!(vehicle.speed, Some(42.5));
+        assert_eq!(vehicle.gear, Some(3));
+        assert_eq!(vehicle.track_surface(), proto::TrackSurface::Asphalt);
+        assert!(message.motion.is_none());
+        let extras = &message.extras["iracing"];
+        let Some(prost_types::value::Kind::StructValue(fields)) = &extras.kind else {
+            panic!("extras namespace is not a struct: {:?}", extras);
+        };
+        assert_eq!(
+            fields.fields["PlayerCarIdx"].kind,
+            Some(prost_types::value::Kind::NumberValue(3.0))
+        );
+
+        // Values the model can't hold are rejected rather than truncated
+        let mut message = message;
+        message.vehicle.as_mut().unwrap().max_gears = Some(300);
+        assert!(TelemetryFrame::try_from(message.clone()).is_err());
+        message.vehicle.as_mut().unwrap().max_gears = None;
+        message.vehicle.as_mut().unwrap().track_surface = Some(99);
+        assert!(TelemetryFrame::try_from(message).is_err());
+    }
+
+    #[test]
+    fn test_encode_with_mask() {
+        let frame = demo_frame();
+        let mask = MetricMask::parse("vehicle");
+        let decoded = decode(&encode(&frame, Some(&mask))).unwrap();
+
+        assert_eq!(decoded.meta.game, frame.meta.game);
+        assert!(decoded.vehicle.is_some());
+        assert!(decoded.motion.is_none());
+        assert!(decoded.timing.is_none());
+    }
+
+    #[test]
+    fn test_encode_prunes_sub_fields() {
+        let mut frame = demo_frame();
+        frame
+            .extras
+            .insert("iracing".to_string(), serde_json::json!({"A": 1, "B": 2}));
+        let mask = MetricMask::parse("vehicle.speed,wheels.*.tyre_pressure,iracing.a");
+        let bytes = encode(&frame, Some(&mask));
+        let decoded = decode(&bytes).unwrap();
+
+        let vehicle = decoded.vehicle.unwrap();
+        assert!(vehicle.speed.is_some());
+        assert!(vehicle.rpm.is_none());
+        let wheels = decoded.wheels.unwrap();
+        for wheel in wheels.all_wheels() {
+            assert!(wheel.tyre_pressure.is_some());
+            assert!(wheel.tyre_wear.is_none());
+        }
+        assert_eq!(decoded.extras["iracing"], serde_json::json!({"A": 1}));
+        assert!(bytes.len() < encode(&frame, Some(&MetricMask::parse("vehicle,wheels"))).len());
+    }
+
+    #[test]
+    fn test_missing_fields_decode_as_defaults() {
+        // meta with only the game set; tick and all sections absent
+        let message = proto::TelemetryFrame {
+            meta: Some(proto::MetaData {
+                timestamp: "2026-01-01T00:00:00Z".to_string(),
+                game: "Test".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut bytes = message.encode_to_vec();
+        // Unknown field numbers are skipped: field 99, length-delimited
+        bytes.extend_from_slice(&[0x9a, 0x06, 6]);
+        bytes.extend_from_slice(b"future");
+
+        let frame = decode(&bytes).unwrap();
+        assert_eq!(frame.meta.game, "Test");
+        assert_eq!(frame.meta.tick, None);
+        assert!(frame.vehicle.is_none());
+        assert!(frame.extras.is_empty());
+    }
+
+    // === Schema stability ===
+
+    /// Field and enum value numbers of the schema as prost compiled it
+    fn compiled_numbers() -> BTreeMap<String, i32> {
+        let bytes = include_bytes!(concat!(env!("OUT_DIR"), "/telemetry.bin"));
+        let set = prost_types::FileDescriptorSet::decode(&bytes[..]).unwrap();
+        let mut numbers = BTreeMap::new();
+        for file in set
+            .file
+            .iter()
+            .filter(|f| f.package() == "opensimtelemetry.v1")
+        {
+            for message in &file.message_type {
+                for field in &message.field {
+                    numbers.insert(
+                        format!("{}.{}", message.name(), field.name()),
+                        field.number(),
+                    );
+                }
+            }
+            for e in &file.enum_type {
+                for value in &e.value {
+                    numbers.insert(format!("{}.{}", e.name(), value.name()), value.number());
+                }
+            }
+        }
+        numbers
+    }
+
+    #[test]
+    fn test_field_numbers_stable() {
+        // Every number ever given out, from `proto/telemetry.numbers`
+        let locked: BTreeMap<String, i32> = include_str!("../proto/telemetry.numbers")
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (name, number) = line.split_once(" = ").unwrap();
+                (name.to_string(), number.parse().unwrap())
+            })
+            .collect();
+        let compiled = compiled_numbers();
+
+        for (name, number) in &compiled {
+            match locked.get(name) {
+                Some(locked) => assert_eq!(
+                    number, locked,
+                    "{} changed number; existing fields keep theirs",
+                    name
+                ),
+                None => panic!(
+                    "{} = {} is new: add it to proto/telemetry.numbers",
+                    name, number
+                ),
+            }
+        }
+        // A new field may not take the number of one that was removed
+        for (name, number) in &locked {
+            if compiled.contains_key(name) {
+                continue;
+            }
+            let message = name.split('.').next().unwrap();
+            let reused = compiled
+                .iter()
+                .find(|(n, num)| n.split('.').next() == Some(message) && *num == number);
+            assert!(
+                reused.is_none(),
+                "{:?} reuses the number of removed {}",
+                reused,
+                name
+            );
+        }
+    }
+}
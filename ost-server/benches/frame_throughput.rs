@@ -1,8 +1,11 @@
-//! Benchmarks for telemetry frame serialization and compression
+//! Benchmarks for telemetry frame serialization, compression and fan-out
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use ost_adapters::DemoAdapter;
+use ost_core::units::UnitProfile;
 use ost_core::TelemetryAdapter;
+use ost_server::shared_frames::{EncodedFrame, EncodingKey, SharedFrames};
+use std::sync::Arc;
 
 /// Generate a realistic telemetry frame from the demo adapter
 fn make_demo_frame() -> ost_core::model::TelemetryFrame {
@@ -32,6 +35,18 @@ fn bench_frame_json_filtered(c: &mut Criterion) {
     });
 }
 
+fn bench_frame_json_field_filtered(c: &mut Criterion) {
+    let frame = make_demo_frame();
+    let mask =
+        ost_core::model::MetricMask::parse("vehicle.speed,vehicle.rpm,wheels.*.tyre_pressure");
+    c.bench_function("frame_to_json_field_filtered", |b| {
+        b.iter(|| {
+            let json = black_box(&frame).to_json_filtered(Some(&mask)).unwrap();
+            black_box(json);
+        })
+    });
+}
+
 fn bench_frame_msgpack_serialize(c: &mut Criterion) {
     let frame = make_demo_frame();
     c.bench_function("frame_to_msgpack", |b| {
@@ -58,6 +73,50 @@ fn bench_frames_zstd_compress(c: &mut Criterion) {
     });
 }
 
+fn bench_broadcast_fan_out(c: &mut Criterion) {
+    let mut frame = make_demo_frame();
+    let spec: Arc<str> = Arc::from("vehicle,timing");
+    let mask = ost_core::model::MetricMask::parse(&spec);
+    let mut group = c.benchmark_group("broadcast_fan_out");
+    for subscribers in [1, 8, 32] {
+        // As AppState's telemetry channel, each subscriber a stream client
+        let (tx, _) = tokio::sync::broadcast::channel(100);
+        let mut clients: Vec<_> = (0..subscribers).map(|_| tx.subscribe()).collect();
+        let shared = SharedFrames::default();
+        let mut seq = 0;
+        group.throughput(Throughput::Elements(subscribers as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(subscribers),
+            &subscribers,
+            |b, _| {
+                b.iter(|| {
+                    seq += 1;
+                    frame.meta.seq = Some(seq);
+                    tx.send(black_box(frame.clone())).unwrap();
+                    // Every client gets its own copy; the first serializes it
+                    // for the others with the same mask
+                    for client in &mut clients {
+                        let frame = client.try_recv().unwrap();
+                        let key = EncodingKey {
+                            seq,
+                            mask: Some(spec.clone()),
+                            units: UnitProfile::METRIC,
+                            custom_metrics: 0,
+                        };
+                        let encoded = shared.get_or_encode(key, || {
+                            let value = frame.to_json_value_filtered(Some(&mask)).ok()?;
+                            let json = serde_json::to_string(&value).ok()?.into();
+                            Some(EncodedFrame { value, json })
+                        });
+                        black_box(encoded.unwrap().json.clone());
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
 fn bench_ibt_parse_frame(c: &mut Criterion) {
     let fixture_path =
         std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures/race.ibt");
@@ -70,8 +129,7 @@ fn bench_ibt_parse_frame(c: &mut Criterion) {
         b.iter_with_setup(
             || ost_adapters::ibt_parser::IbtFile::open(&fixture_path).unwrap(),
             |ibt| {
-                let sample = ibt.read_sample(0).unwrap();
-                let tf = ibt.sample_to_frame(&sample);
+                let tf = ibt.read_frame(0).unwrap();
                 black_box(tf);
             },
         )
@@ -82,8 +140,10 @@ criterion_group!(
     benches,
     bench_frame_json_serialize,
     bench_frame_json_filtered,
+    bench_frame_json_field_filtered,
     bench_frame_msgpack_serialize,
     bench_frames_zstd_compress,
+    bench_broadcast_fan_out,
     bench_ibt_parse_frame,
 );
 criterion_main!(benches);
@@ -1,23 +1,51 @@
 //! REST API and SSE routes
 
-use crate::replay::ReplayState;
-use crate::state::{Annotation, AppState, SinkConfig};
+use crate::archive::ArchiveEntry;
+use crate::competitors::StandingsTracker;
+use crate::config::AuthConfig;
+use crate::ghost::{Ghost, GhostSettings};
+use crate::ingest::{self, IngestBody};
+use crate::latency::Stage;
+use crate::metrics::labelled_counter;
+use crate::overlay::Overlay;
+use crate::registry;
+use crate::relay;
+use crate::replay::{FrameRangeKey, ReplayFrames, ReplayState};
+use crate::shared_frames::{EncodedFrame, EncodingKey, SharedFrames};
+use crate::state::{
+    AdapterConfig, AdapterSlot, AdapterStats, Annotation, AppState, CustomMetrics, SinkConfig,
+};
+use crate::subscribers::{ClientRequest, LagPolicy};
 use crate::web_ui;
 use axum::{
-    extract::{DefaultBodyLimit, Multipart, Query, State},
+    body::Bytes,
+    extract::{
+        ws::{self, WebSocketUpgrade},
+        DefaultBodyLimit, Multipart, Query, State,
+    },
     http::{header, StatusCode},
     middleware::Next,
     response::{
         sse::{Event, KeepAlive, Sse},
-        IntoResponse,
+        IntoResponse, Response,
     },
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use futures::stream::{self, Stream, StreamExt as FuturesStreamExt};
-use ost_core::model::{compute_section_delta, MetricMask, TelemetryFrame};
+use ost_core::adapter::Capabilities;
+use ost_core::channels::{self, Channel};
+use ost_core::columns::FrameColumns;
+use ost_core::control::{
+    CameraSwitch, ChatCommand, ControlCapable, InvalidCommand, PitCommand, ReplaySpeed, Unsupported,
+};
+use ost_core::model::{compute_merge_patch, compute_section_delta, MetricMask, TelemetryFrame};
+use ost_core::units::{UnitProfile, Units};
+use ost_core::VariableInfo;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::convert::Infallible;
+use std::path::Path;
 use std::time::Duration;
 use tokio_stream::wrappers::BroadcastStream;
 use tokio_util::sync::CancellationToken;
@@ -172,55 +200,103 @@ fn round_json_floats(val: &mut serde_json::Value) {
 /// How often to send full frames during delta mode (for sync recovery).
 const DELTA_FULL_FRAME_INTERVAL: u64 = 60;
 
-/// Serialize a frame to JSON with optional delta encoding and custom metrics merge.
-/// Returns the JSON string and the full Value to store as previous state.
-fn serialize_frame_json(
-    frame: &TelemetryFrame,
-    mask: Option<&MetricMask>,
-    use_delta: bool,
-    last_json: &std::sync::Mutex<Option<serde_json::Value>>,
-    frame_count: u64,
-    custom_metrics: Option<&crate::state::CustomMetrics>,
-) -> Option<String> {
-    let mut curr_value = frame.to_json_value_filtered(mask).ok()?;
-    round_json_floats(&mut curr_value);
+/// Delta encoding applied to JSON frames on SSE streams.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DeltaMode {
+    /// Every frame is sent in full
+    Full,
+    /// Changed top-level sections only (`_delta: true`)
+    Sections,
+    /// Field-level JSON merge patch (`_delta: "merge-patch"`)
+    MergePatch,
+}
 
-    // Merge custom metrics if any
-    if let Some(cm) = custom_metrics {
-        if !cm.is_empty() {
-            let tick = frame.meta.tick;
-            cm.merge_into(&mut curr_value, tick);
+impl DeltaMode {
+    /// Resolve from `?mode=full|sections|delta`, falling back to the legacy
+    /// `?delta=true|false` flag (section deltas by default).
+    fn from_query(query: &StreamQuery) -> Self {
+        match query.mode.as_deref() {
+            Some("delta") => DeltaMode::MergePatch,
+            Some("sections") => DeltaMode::Sections,
+            Some("full") => DeltaMode::Full,
+            _ if query.delta.unwrap_or(true) => DeltaMode::Sections,
+            _ => DeltaMode::Full,
         }
     }
+}
 
-    let send_full = !use_delta || frame_count.is_multiple_of(DELTA_FULL_FRAME_INTERVAL);
+/// `frame` as a stream client with `mask` (given as `mask_spec`) and
+/// `units` is sent it, custom metrics merged. Frames that were broadcast are
+/// encoded once for all the clients alike, see [`SharedFrames`].
+fn encode_frame(
+    shared: &SharedFrames,
+    frame: &TelemetryFrame,
+    mask_spec: Option<&std::sync::Arc<str>>,
+    mask: Option<&MetricMask>,
+    units: UnitProfile,
+    custom_metrics: &CustomMetrics,
+) -> Option<std::sync::Arc<EncodedFrame>> {
+    let encode = || {
+        let mut value = frame.to_json_value_in(mask, units).ok()?;
+        round_json_floats(&mut value);
+        if !custom_metrics.is_empty() {
+            custom_metrics.merge_into(&mut value, frame.meta.tick);
+        }
+        let json = serde_json::to_string(&value).ok()?.into();
+        Some(EncodedFrame { value, json })
+    };
+    match frame.meta.seq {
+        Some(seq) => shared.get_or_encode(
+            EncodingKey {
+                seq,
+                mask: mask_spec.cloned(),
+                units,
+                custom_metrics: custom_metrics.generation,
+            },
+            encode,
+        ),
+        None => encode().map(std::sync::Arc::new),
+    }
+}
 
-    let json = if send_full {
-        serde_json::to_string(&curr_value).ok()?
-    } else {
-        let prev = last_json.lock().unwrap();
-        match prev.as_ref() {
-            Some(prev_val) => {
-                let delta = compute_section_delta(prev_val, &curr_value);
-                serde_json::to_string(&delta).ok()?
-            }
-            None => serde_json::to_string(&curr_value).ok()?,
+/// JSON to send a stream client for `encoded`, delta encoded against the
+/// last frame it was sent (`last_sent`) unless a full frame is due. Full
+/// frames are the shared JSON itself.
+fn serialize_frame_json(
+    encoded: &std::sync::Arc<EncodedFrame>,
+    delta_mode: DeltaMode,
+    last_sent: &std::sync::Mutex<Option<std::sync::Arc<EncodedFrame>>>,
+    frame_count: u64,
+) -> Option<std::sync::Arc<str>> {
+    let send_full =
+        delta_mode == DeltaMode::Full || frame_count.is_multiple_of(DELTA_FULL_FRAME_INTERVAL);
+
+    let mut last = last_sent.lock().unwrap();
+    let json = match last.as_ref() {
+        Some(prev) if !send_full => {
+            let delta = if delta_mode == DeltaMode::MergePatch {
+                compute_merge_patch(&prev.value, &encoded.value)
+            } else {
+                compute_section_delta(&prev.value, &encoded.value)
+            };
+            serde_json::to_string(&delta).ok()?.into()
         }
+        _ => encoded.json.clone(),
     };
 
-    *last_json.lock().unwrap() = Some(curr_value);
+    *last = Some(encoded.clone());
     Some(json)
 }
 
-/// Check if a Basic auth header matches the token (password field).
-fn check_basic_auth(auth_header: &str, token: &str) -> bool {
+/// Check if a Basic auth header carries an accepted token (password field).
+fn check_basic_auth(auth_header: &str, auth: &AuthConfig) -> bool {
     if let Some(encoded) = auth_header.strip_prefix("Basic ") {
         use base64::Engine;
         if let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(encoded) {
             if let Ok(credentials) = std::str::from_utf8(&decoded) {
                 // Format is "username:password" — we only check the password
                 if let Some(password) = credentials.split_once(':').map(|(_, p)| p) {
-                    return password == token;
+                    return auth.accepts(password);
                 }
             }
         }
@@ -228,47 +304,55 @@ fn check_basic_auth(auth_header: &str, token: &str) -> bool {
     false
 }
 
-/// Auth middleware: checks token on all routes when auth is configured.
-/// Supports Bearer token, Basic auth, and ?token= query parameter.
-/// The UI page (/) triggers a browser Basic auth prompt on 401.
+/// Auth middleware: checks the token on the routes covered by the auth scope
+/// (state-changing requests, or all of them). Supports Bearer token, Basic
+/// auth, and ?token= query parameter. The UI page (/) triggers a browser
+/// Basic auth prompt on 401.
 async fn auth_middleware(
     State(state): State<AppState>,
     req: axum::http::Request<axum::body::Body>,
     next: Next,
 ) -> Result<axum::response::Response, axum::response::Response> {
-    let token = match &state.auth_token {
-        Some(t) => t,
-        None => return Ok(next.run(req).await),
-    };
+    let config = &state.auth;
+    if !config.requires_token(req.method()) || presents_token(req.headers(), req.uri(), config) {
+        return Ok(next.run(req).await);
+    }
+
+    // Return 401 with WWW-Authenticate header to trigger browser Basic auth prompt
+    Err((
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Basic realm=\"OpenSimTelemetry\"")],
+    )
+        .into_response())
+}
 
+/// Whether a request carries an accepted token, as a Bearer token, Basic
+/// auth password or `?token=` query parameter
+fn presents_token(
+    headers: &axum::http::HeaderMap,
+    uri: &axum::http::Uri,
+    config: &AuthConfig,
+) -> bool {
     // Check Authorization header (Bearer or Basic)
-    if let Some(auth) = req.headers().get(header::AUTHORIZATION) {
+    if let Some(auth) = headers.get(header::AUTHORIZATION) {
         if let Ok(val) = auth.to_str() {
-            if val.strip_prefix("Bearer ").is_some_and(|t| t == token)
-                || check_basic_auth(val, token)
+            if val
+                .strip_prefix("Bearer ")
+                .is_some_and(|t| config.accepts(t))
+                || check_basic_auth(val, config)
             {
-                return Ok(next.run(req).await);
+                return true;
             }
         }
     }
 
     // Check ?token= query param
-    if let Some(query) = req.uri().query() {
-        for pair in query.split('&') {
-            if let Some(val) = pair.strip_prefix("token=") {
-                if val == token {
-                    return Ok(next.run(req).await);
-                }
-            }
-        }
-    }
-
-    // Return 401 with WWW-Authenticate header to trigger browser Basic auth prompt
-    Err((
-        StatusCode::UNAUTHORIZED,
-        [(header::WWW_AUTHENTICATE, "Basic realm=\"OpenSimTelemetry\"")],
-    )
-        .into_response())
+    uri.query().is_some_and(|query| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.strip_prefix("token="))
+            .any(|val| config.accepts(val))
+    })
 }
 
 /// Create the main application router
@@ -286,11 +370,36 @@ pub fn create_router(state: AppState) -> Router {
 
     router = router
         .route("/api/docs", get(api_docs))
+        .route("/metrics", get(prometheus_metrics))
+        .route("/api/clients", get(list_clients))
+        .route("/api/clients/:id", delete(disconnect_client))
         .route("/api/adapters", get(list_adapters))
+        .route("/api/adapters/features", get(adapter_features))
+        .route("/api/adapters/register", post(register_adapter))
+        .route("/api/adapters/:name", delete(unregister_adapter))
         .route("/api/adapters/:name/toggle", post(toggle_adapter))
+        .route("/api/adapters/:name/activate", post(activate_adapter))
+        .route(
+            "/api/adapters/:name/config",
+            get(get_adapter_config).post(set_adapter_config),
+        )
+        .route("/api/adapters/:name/pit", post(adapter_pit_command))
+        .route("/api/adapters/:name/camera", post(adapter_camera_switch))
+        .route(
+            "/api/adapters/:name/replay/speed",
+            post(adapter_replay_speed),
+        )
+        .route("/api/adapters/:name/chat", post(adapter_chat_command))
+        .route("/api/adapters/:name/scenario", post(adapter_scenario))
+        .route("/api/adapters/:name/variables", get(adapter_variables))
+        .route("/api/channels", get(list_channels))
         .route("/api/stream", get(unified_stream))
         .route("/api/telemetry/stream", get(telemetry_stream))
+        .route("/api/telemetry/ws", get(telemetry_ws))
         .route("/api/status/stream", get(status_stream))
+        .route("/api/competitors/stream", get(competitors_stream))
+        .route("/api/session/stream", get(session_stream))
+        .route("/api/session/raw", get(session_raw))
         .route("/api/metrics", get(get_metrics).post(submit_metrics))
         .route(
             "/api/metrics/custom",
@@ -305,22 +414,39 @@ pub fn create_router(state: AppState) -> Router {
             get(list_annotations).post(create_annotation),
         )
         .route("/api/annotations/:id", delete(delete_annotation))
+        .route("/overlay", get(overlay_snapshot))
+        .route("/overlay/stream", get(overlay_stream))
         .route("/api/sinks", get(list_sinks).post(create_sink))
         .route("/api/sinks/stream", get(sinks_stream))
         .route("/api/sinks/:id", delete(delete_sink))
+        .route("/api/relay", get(relay_uplink))
+        .route("/api/ingest", post(ingest_samples))
         // Replay endpoints
         .route(
             "/api/replay/upload",
-            post(replay_upload).layer(DefaultBodyLimit::max(1024 * 1024 * 1024)),
+            // Limited as it streams in, see `ReplayStore::max_upload_bytes`
+            post(replay_upload).layer(DefaultBodyLimit::disable()),
         )
         .route("/api/replay/info", get(replay_info))
+        .route("/api/replay/storage", get(replay_storage))
         .route("/api/replay/frames", get(replay_frames))
         .route("/api/replay/trackmap", get(replay_trackmap))
+        .route("/api/replay/overview", get(replay_overview))
+        .route("/api/replay/suspension", get(replay_suspension))
+        .route("/api/analytics/corners", get(analytics_corners))
+        .route("/api/analytics/pace", get(analytics_pace))
         .route("/api/replay/control", post(replay_control))
+        .route("/api/replay/extract", post(replay_extract))
+        .route(
+            "/api/replay/ghost",
+            post(replay_ghost_set).delete(replay_ghost_clear),
+        )
+        .route("/api/replay/ghost/stream", get(replay_ghost_stream))
         .route("/api/replay", delete(replay_delete))
         // History buffer config & aggregation
         .route("/api/history/config", post(history_config))
         .route("/api/history/aggregate", get(history_aggregate))
+        .route("/api/history/frames", get(history_frames))
         // Conversion endpoints
         .route(
             "/api/convert/ibt",
@@ -339,6 +465,11 @@ pub fn create_router(state: AppState) -> Router {
             "/api/persistence/files/:name",
             delete(persistence_delete_file),
         )
+        // Session archive
+        .route("/api/archive", get(archive_list))
+        .route("/api/archive/:file", delete(archive_delete))
+        .route("/api/archive/:file/tags", put(archive_set_tags))
+        .route("/api/archive/:file/load", post(archive_load))
         // Session endpoints (serve mode)
         .route(
             "/api/sessions/upload",
@@ -367,6 +498,43 @@ struct AdapterInfo {
     detected: bool,
     active: bool,
     enabled: bool,
+    stats: AdapterStats,
+    config: AdapterConfig,
+    /// Scenarios accepted by `POST /api/adapters/:name/scenario`
+    scenarios: &'static [&'static str],
+    /// What the adapter's frames can contain, `null` if it doesn't say
+    capabilities: Option<Capabilities>,
+    /// Why the adapter can't run on this platform, `null` if it can
+    unsupported: Option<String>,
+}
+
+impl AdapterInfo {
+    /// Build status for a registered adapter. The active adapter is reported
+    /// without locking it, since its reader task may be blocked in `read_frame()`.
+    fn from_slot(
+        slot: &AdapterSlot,
+        active_name: Option<&str>,
+        disabled: &HashSet<String>,
+    ) -> Self {
+        let (detected, active) = if active_name == Some(slot.key()) {
+            (true, true)
+        } else {
+            let adapter = slot.lock();
+            (adapter.detect(), adapter.is_active())
+        };
+        Self {
+            key: slot.key().to_string(),
+            name: slot.name().to_string(),
+            detected,
+            active,
+            enabled: !disabled.contains(slot.key()),
+            stats: slot.stats(),
+            config: slot.config(),
+            scenarios: slot.scenarios(),
+            capabilities: slot.capabilities().cloned(),
+            unsupported: slot.unsupported().map(str::to_string),
+        }
+    }
 }
 
 async fn list_adapters(State(state): State<AppState>) -> Json<Vec<AdapterInfo>> {
@@ -376,17 +544,7 @@ async fn list_adapters(State(state): State<AppState>) -> Json<Vec<AdapterInfo>>
 
     let info: Vec<AdapterInfo> = adapters
         .iter()
-        .map(|adapter| AdapterInfo {
-            key: adapter.key().to_string(),
-            name: adapter.name().to_string(),
-            detected: adapter.detect(),
-            active: adapter.is_active()
-                || active_name
-                    .as_ref()
-                    .map(|n| n == adapter.key())
-                    .unwrap_or(false),
-            enabled: !disabled.contains(adapter.key()),
-        })
+        .map(|slot| AdapterInfo::from_slot(slot, active_name.as_deref(), &disabled))
         .collect();
 
     Json(info)
@@ -397,45 +555,61 @@ async fn toggle_adapter(
     axum::extract::Path(key): axum::extract::Path<String>,
 ) -> Result<Json<AdapterInfo>, (StatusCode, String)> {
     let result = {
-        let mut adapters = state.adapters.write().await;
+        let adapters = state.adapters.read().await;
         let mut active_adapter = state.active_adapter.write().await;
         let mut disabled = state.disabled_adapters.write().await;
 
-        let adapter = adapters.iter_mut().find(|a| a.key() == key).ok_or((
+        let slot = adapters.iter().find(|a| a.key() == key).ok_or((
             StatusCode::NOT_FOUND,
             format!("Adapter '{}' not found", key),
         ))?;
 
-        let is_enabled = !disabled.contains(adapter.key());
+        let is_enabled = !disabled.contains(slot.key());
 
         if is_enabled {
             // Disable: stop if active, add to disabled set
-            let is_active = adapter.is_active()
-                || active_adapter
-                    .as_ref()
-                    .map(|n| n == adapter.key())
-                    .unwrap_or(false);
-            if is_active {
-                let _ = adapter.stop();
+            let is_active = active_adapter.as_deref() == Some(slot.key());
+            let detected = if is_active {
+                // The reader task stops the adapter once it sees the cancellation
+                if let Some(cancel) = state.reader_cancel.write().await.take() {
+                    cancel.cancel();
+                }
                 *active_adapter = None;
-            }
+                true
+            } else {
+                let mut adapter = slot.lock();
+                if adapter.is_active() {
+                    let _ = adapter.stop();
+                }
+                adapter.detect()
+            };
             disabled.insert(key.clone());
             Ok(Json(AdapterInfo {
-                key: adapter.key().to_string(),
-                name: adapter.name().to_string(),
-                detected: adapter.detect(),
+                key: slot.key().to_string(),
+                name: slot.name().to_string(),
+                detected,
                 active: false,
                 enabled: false,
+                stats: slot.stats(),
+                config: slot.config(),
+                scenarios: slot.scenarios(),
+                capabilities: slot.capabilities().cloned(),
+                unsupported: slot.unsupported().map(str::to_string),
             }))
         } else {
             // Enable: remove from disabled set, let detection loop handle starting
             disabled.remove(&key);
             Ok(Json(AdapterInfo {
-                key: adapter.key().to_string(),
-                name: adapter.name().to_string(),
-                detected: adapter.detect(),
+                key: slot.key().to_string(),
+                name: slot.name().to_string(),
+                detected: slot.lock().detect(),
                 active: false,
                 enabled: true,
+                stats: slot.stats(),
+                config: slot.config(),
+                scenarios: slot.scenarios(),
+                capabilities: slot.capabilities().cloned(),
+                unsupported: slot.unsupported().map(str::to_string),
             }))
         }
     };
@@ -444,202 +618,651 @@ async fn toggle_adapter(
     result
 }
 
-/// Build the current adapter status JSON and broadcast it to all status SSE subscribers.
-/// Called after any adapter state change (toggle, auto-detect start/stop).
-pub async fn broadcast_adapter_status(state: &AppState) {
-    let adapters = state.adapters.read().await;
-    let active_name = state.active_adapter.read().await;
-    let disabled = state.disabled_adapters.read().await;
-
-    let info: Vec<AdapterInfo> = adapters
-        .iter()
-        .map(|adapter| AdapterInfo {
-            key: adapter.key().to_string(),
-            name: adapter.name().to_string(),
-            detected: adapter.detect(),
-            active: adapter.is_active()
-                || active_name
-                    .as_ref()
-                    .map(|n| n == adapter.key())
-                    .unwrap_or(false),
-            enabled: !disabled.contains(adapter.key()),
-        })
-        .collect();
-
-    if let Ok(json) = serde_json::to_string(&info) {
-        let _ = state.status_tx.send(json);
-    }
-}
-
-/// GET /api/metrics — returns the latest telemetry frame as JSON.
-/// Accepts optional `metric_mask` query param to filter top-level sections.
-#[derive(Deserialize)]
-struct MetricsQuery {
-    metric_mask: Option<String>,
-}
-
-async fn get_metrics(
-    State(state): State<AppState>,
-    Query(query): Query<MetricsQuery>,
-) -> impl IntoResponse {
-    let history = state.history.read().await;
-    match history.latest_frame() {
-        Some(frame) => {
-            let mask = query.metric_mask.as_deref().map(MetricMask::parse);
-            let mut val = frame
-                .to_json_value_filtered(mask.as_ref())
-                .unwrap_or(serde_json::Value::Null);
-            // Merge custom metrics
-            let cm = state.custom_metrics.read().unwrap();
-            if !cm.is_empty() {
-                cm.merge_into(&mut val, frame.meta.tick);
-            }
-            let json = serde_json::to_string(&val).unwrap_or_else(|_| "{}".to_string());
-            (
-                StatusCode::OK,
-                [(header::CONTENT_TYPE, "application/json")],
-                json,
-            )
-        }
-        None => (
-            StatusCode::OK,
-            [(header::CONTENT_TYPE, "application/json")],
-            "null".to_string(),
-        ),
-    }
-}
-
-// ===================== Custom Metrics API =====================
-
-#[derive(Deserialize)]
-struct SubmitMetricsRequest {
-    /// Namespace for the metrics (e.g., "analysis", "custom")
-    namespace: String,
-    /// Key-value pairs of metric data
-    metrics: serde_json::Value,
-    /// Optional tick number — if set, metrics apply only to that frame
-    tick: Option<u32>,
-}
-
-/// POST /api/metrics — submit custom metrics
-async fn submit_metrics(
+/// Prefer an adapter over any other detected one: it moves to the front of
+/// the priority list and, once detected, replaces the active adapter on the
+/// next detection cycle. 409 while it's disabled.
+async fn activate_adapter(
     State(state): State<AppState>,
-    Json(req): Json<SubmitMetricsRequest>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    if req.namespace.is_empty() {
+    axum::extract::Path(key): axum::extract::Path<String>,
+) -> Result<Json<AdapterInfo>, (StatusCode, String)> {
+    let slot = {
+        let adapters = state.adapters.read().await;
+        adapters.iter().find(|a| a.key() == key).cloned().ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Adapter '{}' not found", key),
+        ))?
+    };
+    if let Some(reason) = slot.unsupported() {
         return Err((
-            StatusCode::BAD_REQUEST,
-            "namespace must not be empty".to_string(),
+            StatusCode::CONFLICT,
+            format!("Adapter '{}' is unsupported here: {}", key, reason),
         ));
     }
-    if !req.metrics.is_object() {
+    if state.disabled_adapters.read().await.contains(&key) {
         return Err((
-            StatusCode::BAD_REQUEST,
-            "metrics must be a JSON object".to_string(),
+            StatusCode::CONFLICT,
+            format!("Adapter '{}' is disabled", key),
         ));
     }
+    {
+        let mut priority = state.adapter_priority.write().await;
+        priority.retain(|k| *k != key);
+        priority.insert(0, key.clone());
+    }
 
-    let mut cm = state.custom_metrics.write().unwrap();
-    match req.tick {
-        Some(tick) => {
-            cm.by_tick
-                .entry(tick)
-                .or_default()
-                .insert(req.namespace.clone(), req.metrics);
-        }
-        None => {
-            cm.sticky.insert(req.namespace.clone(), req.metrics);
+    let active = state.active_adapter.read().await.clone();
+    if let Some(active) = active.filter(|active| *active != key) {
+        if slot.lock().detect() && state.deactivate_adapter(&active).await {
+            tracing::info!("Activating {}, stopping {}", key, active);
         }
     }
+    broadcast_adapter_status(&state).await;
 
-    Ok(Json(serde_json::json!({
-        "ok": true,
-        "namespace": req.namespace,
-        "tick": req.tick,
-    })))
+    let active = state.active_adapter.read().await;
+    let disabled = state.disabled_adapters.read().await;
+    Ok(Json(AdapterInfo::from_slot(
+        &slot,
+        active.as_deref(),
+        &disabled,
+    )))
 }
 
-/// GET /api/metrics/custom — list all custom metrics
-async fn list_custom_metrics(State(state): State<AppState>) -> Json<serde_json::Value> {
-    let cm = state.custom_metrics.read().unwrap();
-    Json(serde_json::json!({
-        "sticky": cm.sticky,
-        "by_tick": cm.by_tick,
-    }))
+/// GET /api/adapters/features — the adapter features this build has
+async fn adapter_features() -> Json<&'static [registry::AdapterFeature]> {
+    Json(registry::FEATURES)
 }
 
-/// DELETE /api/metrics/custom — clear all custom metrics
-async fn clear_custom_metrics(State(state): State<AppState>) -> StatusCode {
-    let mut cm = state.custom_metrics.write().unwrap();
-    cm.sticky.clear();
-    cm.by_tick.clear();
-    StatusCode::NO_CONTENT
+#[derive(Deserialize)]
+struct RegisterAdapterRequest {
+    key: String,
 }
 
-/// DELETE /api/metrics/custom/:namespace — clear custom metrics for a specific namespace
-async fn clear_custom_metrics_ns(
+/// POST /api/adapters/register — register a built-in adapter, remembering it
+/// across restarts
+async fn register_adapter(
     State(state): State<AppState>,
-    axum::extract::Path(namespace): axum::extract::Path<String>,
-) -> StatusCode {
-    let mut cm = state.custom_metrics.write().unwrap();
-    cm.sticky.remove(&namespace);
-    cm.by_tick.retain(|_, v| {
-        v.remove(&namespace);
-        !v.is_empty()
-    });
-    StatusCode::NO_CONTENT
-}
+    Json(request): Json<RegisterAdapterRequest>,
+) -> Result<(StatusCode, Json<AdapterInfo>), (StatusCode, String)> {
+    let key = request.key;
+    if state.adapters.read().await.iter().any(|a| a.key() == key) {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Adapter '{}' is already registered", key),
+        ));
+    }
+    let adapter = registry::builtin(&key, &state)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("No built-in adapter '{}' is available", key),
+        ))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))?;
+    state
+        .adapter_registry
+        .set_registered(&key, true)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))?;
+    let slot = registry::insert(&state, adapter).await;
+    tracing::info!("Registered adapter {}", key);
+    broadcast_adapter_status(&state).await;
 
-// ===================== Annotations API =====================
+    let active = state.active_adapter.read().await;
+    let disabled = state.disabled_adapters.read().await;
+    let info = AdapterInfo::from_slot(&slot, active.as_deref(), &disabled);
+    Ok((StatusCode::CREATED, Json(info)))
+}
 
-/// POST /api/annotations — create a new annotation
-async fn create_annotation(
+/// DELETE /api/adapters/:name — stop and unregister an adapter until it is
+/// registered again
+async fn unregister_adapter(
     State(state): State<AppState>,
-    Json(mut ann): Json<Annotation>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    if ann.title.is_empty() {
+    axum::extract::Path(key): axum::extract::Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if !state.adapters.read().await.iter().any(|a| a.key() == key) {
         return Err((
-            StatusCode::BAD_REQUEST,
-            "title must not be empty".to_string(),
+            StatusCode::NOT_FOUND,
+            format!("Adapter '{}' not found", key),
         ));
     }
-    if ann.id.is_empty() {
-        ann.id = format!(
-            "ann-{}",
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_millis()
-        );
-    }
-    let id = ann.id.clone();
-
-    let mut annotations = state.annotations.write().unwrap();
-    annotations.push(ann);
-    broadcast_annotations(&state, &annotations);
-    Ok(Json(serde_json::json!({ "ok": true, "id": id })))
+    state
+        .adapter_registry
+        .set_registered(&key, false)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))?;
+    registry::remove(&state, &key).await;
+    tracing::info!("Unregistered adapter {}", key);
+    broadcast_adapter_status(&state).await;
+    Ok(StatusCode::NO_CONTENT)
 }
 
-/// GET /api/annotations — list all annotations
-async fn list_annotations(State(state): State<AppState>) -> Json<Vec<Annotation>> {
-    let annotations = state.annotations.read().unwrap();
-    Json(annotations.clone())
-}
+/// Maximum configurable adapter publish rate (Hz)
+const MAX_ADAPTER_RATE_HZ: f64 = 1000.0;
 
-/// DELETE /api/annotations/:id — delete an annotation by ID
-async fn delete_annotation(
+async fn get_adapter_config(
     State(state): State<AppState>,
-    axum::extract::Path(id): axum::extract::Path<String>,
-) -> StatusCode {
-    let mut annotations = state.annotations.write().unwrap();
-    let before = annotations.len();
-    annotations.retain(|a| a.id != id);
-    if annotations.len() < before {
-        broadcast_annotations(&state, &annotations);
-        StatusCode::NO_CONTENT
-    } else {
-        StatusCode::NOT_FOUND
-    }
+    axum::extract::Path(key): axum::extract::Path<String>,
+) -> Result<Json<AdapterConfig>, (StatusCode, String)> {
+    let adapters = state.adapters.read().await;
+    let slot = adapters.iter().find(|a| a.key() == key).ok_or((
+        StatusCode::NOT_FOUND,
+        format!("Adapter '{}' not found", key),
+    ))?;
+    Ok(Json(slot.config()))
+}
+
+async fn set_adapter_config(
+    State(state): State<AppState>,
+    axum::extract::Path(key): axum::extract::Path<String>,
+    Json(config): Json<AdapterConfig>,
+) -> Result<Json<AdapterConfig>, (StatusCode, String)> {
+    if let Some(hz) = config.max_rate_hz {
+        if !(hz > 0.0 && hz <= MAX_ADAPTER_RATE_HZ) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("max_rate_hz must be between 0 and {}", MAX_ADAPTER_RATE_HZ),
+            ));
+        }
+    }
+    config
+        .restart
+        .check()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    {
+        let adapters = state.adapters.read().await;
+        let slot = adapters.iter().find(|a| a.key() == key).ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Adapter '{}' not found", key),
+        ))?;
+        slot.set_config(config.clone());
+    }
+    broadcast_adapter_status(&state).await;
+    Ok(Json(config))
+}
+
+/// Run a control command on an adapter. Adapters without sim control (or
+/// without this command) answer 501; failed commands, e.g. because the sim
+/// isn't running, answer 409.
+async fn adapter_control(
+    state: &AppState,
+    key: &str,
+    feature: &'static str,
+    command: impl FnOnce(&mut dyn ControlCapable) -> anyhow::Result<()> + Send + 'static,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let slot = {
+        let adapters = state.adapters.read().await;
+        adapters.iter().find(|a| a.key() == key).cloned().ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Adapter '{}' not found", key),
+        ))?
+    };
+    // The reader task holds the adapter lock for each read, so wait off the runtime
+    tokio::task::spawn_blocking(move || {
+        let mut adapter = slot.lock();
+        let name = adapter.name().to_string();
+        match adapter.control() {
+            Some(control) => command(control),
+            None => Err(Unsupported::new(&name, feature).into()),
+        }
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| {
+        let status = if e.is::<Unsupported>() {
+            StatusCode::NOT_IMPLEMENTED
+        } else if e.is::<InvalidCommand>() {
+            StatusCode::UNPROCESSABLE_ENTITY
+        } else {
+            StatusCode::CONFLICT
+        };
+        (status, e.to_string())
+    })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Send a pit service request to the sim through an adapter
+async fn adapter_pit_command(
+    State(state): State<AppState>,
+    axum::extract::Path(key): axum::extract::Path<String>,
+    Json(command): Json<PitCommand>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    adapter_control(&state, &key, "pit commands", move |c| {
+        c.pit_command(command)
+    })
+    .await
+}
+
+/// Point the sim's camera at a car
+async fn adapter_camera_switch(
+    State(state): State<AppState>,
+    axum::extract::Path(key): axum::extract::Path<String>,
+    Json(switch): Json<CameraSwitch>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    adapter_control(&state, &key, "camera control", move |c| {
+        c.camera_switch(switch)
+    })
+    .await
+}
+
+/// Set the sim's replay playback speed
+async fn adapter_replay_speed(
+    State(state): State<AppState>,
+    axum::extract::Path(key): axum::extract::Path<String>,
+    Json(speed): Json<ReplaySpeed>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    adapter_control(&state, &key, "replay control", move |c| {
+        c.replay_play_speed(speed)
+    })
+    .await
+}
+
+/// Trigger a chat macro or open/close the sim's chat box
+async fn adapter_chat_command(
+    State(state): State<AppState>,
+    axum::extract::Path(key): axum::extract::Path<String>,
+    Json(command): Json<ChatCommand>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    adapter_control(&state, &key, "chat commands", move |c| {
+        c.chat_command(command)
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct ScenarioRequest {
+    scenario: String,
+}
+
+/// Switch a simulating adapter (the demo) to another scenario. Adapters
+/// without scenarios answer 501, unknown scenario names 400.
+async fn adapter_scenario(
+    State(state): State<AppState>,
+    axum::extract::Path(key): axum::extract::Path<String>,
+    Json(request): Json<ScenarioRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let slot = {
+        let adapters = state.adapters.read().await;
+        adapters.iter().find(|a| a.key() == key).cloned().ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Adapter '{}' not found", key),
+        ))?
+    };
+    if slot.scenarios().is_empty() {
+        return Err((
+            StatusCode::NOT_IMPLEMENTED,
+            Unsupported::new(slot.name(), "scenarios").to_string(),
+        ));
+    }
+    if !slot.scenarios().contains(&request.scenario.as_str()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Unknown scenario '{}'; expected one of {}",
+                request.scenario,
+                slot.scenarios().join(", ")
+            ),
+        ));
+    }
+    // The reader task holds the adapter lock for each read, so wait off the runtime
+    tokio::task::spawn_blocking(move || slot.lock().set_scenario(&request.scenario))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::CONFLICT, e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List the raw variables a sim publishes (name, type, unit, description),
+/// from the live connection or, for iRacing, a replayed .ibt. 404 when
+/// there are none to list, e.g. because the sim isn't running.
+async fn adapter_variables(
+    State(state): State<AppState>,
+    axum::extract::Path(key): axum::extract::Path<String>,
+) -> Result<Json<Vec<VariableInfo>>, (StatusCode, String)> {
+    if key == "iracing" {
+        if let Some(variables) = state
+            .replay
+            .read()
+            .await
+            .as_ref()
+            .and_then(|rs| rs.variables())
+        {
+            return Ok(Json(variables));
+        }
+    }
+    let slot = {
+        let adapters = state.adapters.read().await;
+        adapters.iter().find(|a| a.key() == key).cloned().ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Adapter '{}' not found", key),
+        ))?
+    };
+    // The reader task holds the adapter lock for each read, so wait off the runtime
+    let variables = tokio::task::spawn_blocking(move || slot.lock().variables())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if variables.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("No variables available from '{}'", key),
+        ));
+    }
+    Ok(Json(variables))
+}
+
+#[derive(Deserialize)]
+struct ChannelsQuery {
+    /// Only channels whose path or description contains this
+    q: Option<String>,
+    /// Only channels this game's adapter can provide
+    game: Option<String>,
+}
+
+/// A channel with the games whose adapters can provide it
+#[derive(Serialize)]
+struct ChannelInfo {
+    #[serde(flatten)]
+    channel: Channel,
+    games: Vec<String>,
+}
+
+/// GET /api/channels — every normalized channel with its unit, description
+/// and the games whose adapters declare it in their capabilities
+async fn list_channels(
+    State(state): State<AppState>,
+    Query(query): Query<ChannelsQuery>,
+) -> Json<Vec<ChannelInfo>> {
+    let adapters = state.adapters.read().await;
+    let games = |path: &str| {
+        let mut games: Vec<String> = Vec::new();
+        for slot in adapters.iter() {
+            let provides = slot
+                .capabilities()
+                .is_some_and(|caps| caps.channels.contains(&path));
+            if provides && !games.iter().any(|g| g == slot.name()) {
+                games.push(slot.name().to_string());
+            }
+        }
+        games
+    };
+    let matching: Vec<&Channel> = match &query.q {
+        Some(q) => channels::search(q).collect(),
+        None => channels::all().iter().collect(),
+    };
+    Json(
+        matching
+            .into_iter()
+            .map(|channel| ChannelInfo {
+                channel: *channel,
+                games: games(channel.path),
+            })
+            .filter(|info| {
+                query
+                    .game
+                    .as_ref()
+                    .is_none_or(|game| info.games.iter().any(|g| g.eq_ignore_ascii_case(game)))
+            })
+            .collect(),
+    )
+}
+
+/// Build the current adapter status JSON and broadcast it to all status SSE subscribers.
+/// Called after any adapter state change (toggle, auto-detect start/stop).
+pub async fn broadcast_adapter_status(state: &AppState) {
+    let adapters = state.adapters.read().await;
+    let active_name = state.active_adapter.read().await;
+    let disabled = state.disabled_adapters.read().await;
+
+    let info: Vec<AdapterInfo> = adapters
+        .iter()
+        .map(|slot| AdapterInfo::from_slot(slot, active_name.as_deref(), &disabled))
+        .collect();
+
+    if let Ok(json) = serde_json::to_string(&info) {
+        let _ = state.status_tx.send(json);
+    }
+    if let Ok(json) = serde_json::to_string(&state.subscribers.snapshot()) {
+        let _ = state.subscribers_tx.send(json);
+    }
+}
+
+/// GET /metrics — server counters in the Prometheus text format
+async fn prometheus_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let mut body = state.subscribers.prometheus();
+    body.push_str(&state.latency.prometheus());
+    body.push_str(&format!(
+        "# HELP ost_frames_broadcast_total Frames broadcast to subscribers\n\
+         # TYPE ost_frames_broadcast_total counter\n\
+         ost_frames_broadcast_total {}\n",
+        state.frame_seq.load(std::sync::atomic::Ordering::Relaxed)
+    ));
+    body.push_str(&validation_prometheus(&state.adapters.read().await));
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+/// Validation counters of each adapter, in the Prometheus text format
+fn validation_prometheus(adapters: &[AdapterSlot]) -> String {
+    let stats: Vec<(&str, AdapterStats)> = adapters.iter().map(|s| (s.key(), s.stats())).collect();
+    let adapter = |(key, _): &(&str, AdapterStats)| vec![("adapter", key.to_string())];
+    let mut out = String::new();
+    labelled_counter(
+        &mut out,
+        "ost_validation_invalid_frames_total",
+        "Frames with at least one value flagged by validation",
+        &stats,
+        adapter,
+        |(_, s)| s.invalid_frames,
+    );
+    labelled_counter(
+        &mut out,
+        "ost_validation_invalid_values_total",
+        "Values flagged by validation",
+        &stats,
+        adapter,
+        |(_, s)| s.invalid_values,
+    );
+    out
+}
+
+/// Connected stream clients, with `behind` the frames broadcast since the
+/// last one each was sent
+async fn list_clients(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let head = state.frame_seq.load(std::sync::atomic::Ordering::Relaxed);
+    let clients: Vec<serde_json::Value> = state
+        .subscribers
+        .snapshot()
+        .into_iter()
+        .filter_map(|stats| {
+            let behind = stats.last_seq.map(|seq| head.saturating_sub(seq));
+            let mut value = serde_json::to_value(stats).ok()?;
+            value["behind"] = serde_json::json!(behind);
+            Some(value)
+        })
+        .collect();
+    Json(serde_json::json!(clients))
+}
+
+/// End a stream client's connection, e.g. one overloading the server
+async fn disconnect_client(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+) -> StatusCode {
+    if state.subscribers.disconnect(id) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// GET /api/metrics — returns the latest telemetry frame as JSON.
+/// Accepts optional `metric_mask` query param to filter top-level sections.
+#[derive(Deserialize)]
+struct MetricsQuery {
+    metric_mask: Option<String>,
+    units: Option<String>,
+}
+
+async fn get_metrics(
+    State(state): State<AppState>,
+    Query(query): Query<MetricsQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let units = parse_units(query.units.as_deref())?;
+    let history = state.history.read().await;
+    match history.latest_frame() {
+        Some(frame) => {
+            let mask = query.metric_mask.as_deref().map(MetricMask::parse);
+            let mut val = frame
+                .to_json_value_in(mask.as_ref(), units)
+                .unwrap_or(serde_json::Value::Null);
+            // Merge custom metrics
+            let cm = state.custom_metrics.read().unwrap();
+            if !cm.is_empty() {
+                cm.merge_into(&mut val, frame.meta.tick);
+            }
+            let json = serde_json::to_string(&val).unwrap_or_else(|_| "{}".to_string());
+            Ok((
+                StatusCode::OK,
+                [(header::CONTENT_TYPE, "application/json")],
+                json,
+            ))
+        }
+        None => Ok((
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/json")],
+            "null".to_string(),
+        )),
+    }
+}
+
+// ===================== Custom Metrics API =====================
+
+#[derive(Deserialize)]
+struct SubmitMetricsRequest {
+    /// Namespace for the metrics (e.g., "analysis", "custom")
+    namespace: String,
+    /// Key-value pairs of metric data
+    metrics: serde_json::Value,
+    /// Optional tick number — if set, metrics apply only to that frame
+    tick: Option<u32>,
+}
+
+/// POST /api/metrics — submit custom metrics
+async fn submit_metrics(
+    State(state): State<AppState>,
+    Json(req): Json<SubmitMetricsRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    if req.namespace.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "namespace must not be empty".to_string(),
+        ));
+    }
+    if !req.metrics.is_object() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "metrics must be a JSON object".to_string(),
+        ));
+    }
+
+    let mut cm = state.custom_metrics.write().unwrap();
+    cm.generation += 1;
+    match req.tick {
+        Some(tick) => {
+            cm.by_tick
+                .entry(tick)
+                .or_default()
+                .insert(req.namespace.clone(), req.metrics);
+        }
+        None => {
+            cm.sticky.insert(req.namespace.clone(), req.metrics);
+        }
+    }
+
+    Ok(Json(serde_json::json!({
+        "ok": true,
+        "namespace": req.namespace,
+        "tick": req.tick,
+    })))
+}
+
+/// GET /api/metrics/custom — list all custom metrics
+async fn list_custom_metrics(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let cm = state.custom_metrics.read().unwrap();
+    Json(serde_json::json!({
+        "sticky": cm.sticky,
+        "by_tick": cm.by_tick,
+    }))
+}
+
+/// DELETE /api/metrics/custom — clear all custom metrics
+async fn clear_custom_metrics(State(state): State<AppState>) -> StatusCode {
+    let mut cm = state.custom_metrics.write().unwrap();
+    cm.generation += 1;
+    cm.sticky.clear();
+    cm.by_tick.clear();
+    StatusCode::NO_CONTENT
+}
+
+/// DELETE /api/metrics/custom/:namespace — clear custom metrics for a specific namespace
+async fn clear_custom_metrics_ns(
+    State(state): State<AppState>,
+    axum::extract::Path(namespace): axum::extract::Path<String>,
+) -> StatusCode {
+    let mut cm = state.custom_metrics.write().unwrap();
+    cm.generation += 1;
+    cm.sticky.remove(&namespace);
+    cm.by_tick.retain(|_, v| {
+        v.remove(&namespace);
+        !v.is_empty()
+    });
+    StatusCode::NO_CONTENT
+}
+
+// ===================== Annotations API =====================
+
+/// POST /api/annotations — create a new annotation
+async fn create_annotation(
+    State(state): State<AppState>,
+    Json(mut ann): Json<Annotation>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    if ann.title.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "title must not be empty".to_string(),
+        ));
+    }
+    if ann.id.is_empty() {
+        ann.id = format!(
+            "ann-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis()
+        );
+    }
+    let id = ann.id.clone();
+
+    let mut annotations = state.annotations.write().unwrap();
+    annotations.push(ann);
+    broadcast_annotations(&state, &annotations);
+    Ok(Json(serde_json::json!({ "ok": true, "id": id })))
+}
+
+/// GET /api/annotations — list all annotations
+async fn list_annotations(State(state): State<AppState>) -> Json<Vec<Annotation>> {
+    let annotations = state.annotations.read().unwrap();
+    Json(annotations.clone())
+}
+
+/// DELETE /api/annotations/:id — delete an annotation by ID
+async fn delete_annotation(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> StatusCode {
+    let mut annotations = state.annotations.write().unwrap();
+    let before = annotations.len();
+    annotations.retain(|a| a.id != id);
+    if annotations.len() < before {
+        broadcast_annotations(&state, &annotations);
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
 }
 
 /// Broadcast annotations to SSE subscribers.
@@ -658,12 +1281,15 @@ async fn broadcast_sinks(state: &AppState) {
 }
 
 /// Unified SSE endpoint that multiplexes telemetry, status, and sinks events
-/// over a single connection. Uses named events: "frame", "status", "sinks".
+/// over a single connection. Uses named events: "frame", "status", "sinks",
+/// "annotations", "spotter", "session_event" and "connection".
 /// This avoids consuming multiple HTTP/1.1 connection slots (browsers limit to 6).
 async fn unified_stream(
     State(state): State<AppState>,
     Query(query): Query<StreamQuery>,
-) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    headers: axum::http::HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, String)> {
+    let units = parse_units(query.units.as_deref())?;
     // Build initial status
     let initial_status_json = {
         let adapters = state.adapters.read().await;
@@ -671,17 +1297,7 @@ async fn unified_stream(
         let disabled = state.disabled_adapters.read().await;
         let info: Vec<AdapterInfo> = adapters
             .iter()
-            .map(|adapter| AdapterInfo {
-                key: adapter.key().to_string(),
-                name: adapter.name().to_string(),
-                detected: adapter.detect(),
-                active: adapter.is_active()
-                    || active_name
-                        .as_ref()
-                        .map(|n| n == adapter.key())
-                        .unwrap_or(false),
-                enabled: !disabled.contains(adapter.key()),
-            })
+            .map(|slot| AdapterInfo::from_slot(slot, active_name.as_deref(), &disabled))
             .collect();
         serde_json::to_string(&info).unwrap_or_default()
     };
@@ -703,6 +1319,10 @@ async fn unified_stream(
     let status_rx = state.status_tx.subscribe();
     let sinks_rx = state.sinks_tx.subscribe();
     let annotations_rx = state.annotations_tx.subscribe();
+    let spotter_rx = state.spotter_tx.subscribe();
+    let events_rx = state.events_tx.subscribe();
+    let connection_rx = state.connection_tx.subscribe();
+    let subscribers_rx = state.subscribers_tx.subscribe();
 
     // Initial events
     let initial = stream::iter(vec![
@@ -714,66 +1334,89 @@ async fn unified_stream(
     ]);
 
     // Telemetry frames (with optional metric mask filtering and rate limiting)
-    let metric_mask = query.metric_mask.map(|f| MetricMask::parse(&f));
+    let mask_spec = query.mask_spec().map(std::sync::Arc::<str>::from);
+    let metric_mask = mask_spec.as_deref().map(MetricMask::parse);
     let min_interval = rate_to_interval(query.rate);
-    let use_msgpack = query
-        .format
-        .as_deref()
-        .is_some_and(|f| f.eq_ignore_ascii_case("msgpack"));
-    let use_delta = !use_msgpack && query.delta.unwrap_or(true);
+    let msgpack = query.msgpack();
+    check_binary_units(msgpack.is_some(), units)?;
+    let delta_mode = if msgpack.is_some() {
+        DeltaMode::Full
+    } else {
+        DeltaMode::from_query(&query)
+    };
     // Adaptive throttling state: tracks lag and dynamically adjusts skip rate
     let throttle_state =
         std::sync::Arc::new(std::sync::Mutex::new(AdaptiveThrottle::new(min_interval)));
     let last_emit = std::sync::Arc::new(std::sync::Mutex::new(tokio::time::Instant::now()));
-    let last_sent_json: std::sync::Arc<std::sync::Mutex<Option<serde_json::Value>>> =
+    let last_sent_json: std::sync::Arc<std::sync::Mutex<Option<std::sync::Arc<EncodedFrame>>>> =
         std::sync::Arc::new(std::sync::Mutex::new(None));
+    let shared_frames = state.shared_frames.clone();
     let delta_frame_count = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
     let custom_metrics = state.custom_metrics.clone();
-    let telemetry = BroadcastStream::new(telemetry_rx).filter_map(move |result| {
+    let latency = state.latency.clone();
+    let subscriber = std::sync::Arc::new(state.subscribers.register(
+        "/api/stream",
+        query.on_lag.unwrap_or_default(),
+        query.client_request(&headers),
+    ));
+    let disconnected = subscriber.disconnected();
+    let resumed = resume_frames(&state, last_event_seq(&headers), min_interval).await;
+    let resumed_to = resumed.last().and_then(|f| f.meta.seq);
+    let telemetry = with_resumed(resumed, telemetry_rx).filter_map(move |(result, resumed)| {
         let mask = metric_mask.clone();
+        let mask_spec = mask_spec.clone();
+        let shared = shared_frames.clone();
         let last = last_emit.clone();
         let throttle = throttle_state.clone();
         let last_json = last_sent_json.clone();
         let frame_counter = delta_frame_count.clone();
         let cm = custom_metrics.clone();
+        let subscriber = subscriber.clone();
+        let latency = latency.clone();
         async move {
             match result {
                 Ok(frame) => {
-                    let mut ts = throttle.lock().unwrap();
-                    ts.on_frame_received();
-                    let effective_interval = ts.effective_interval();
-                    drop(ts);
-
-                    if let Some(interval) = effective_interval {
-                        let mut guard = last.lock().unwrap();
-                        if guard.elapsed() < interval {
-                            return None;
+                    if resumed {
+                        // Already thinned out to the rate
+                    } else if is_resumed(&frame, resumed_to) {
+                        return None;
+                    } else {
+                        let mut ts = throttle.lock().unwrap();
+                        ts.on_frame_received();
+                        let effective_interval = ts.effective_interval();
+                        drop(ts);
+
+                        if let Some(interval) = effective_interval {
+                            let mut guard = last.lock().unwrap();
+                            if guard.elapsed() < interval {
+                                return None;
+                            }
+                            *guard = tokio::time::Instant::now();
                         }
-                        *guard = tokio::time::Instant::now();
+                        latency.record(Stage::Stream, &frame);
                     }
-                    if use_msgpack {
-                        serialize_frame_msgpack(&frame, mask.as_ref())
+                    subscriber.record_sent(frame.meta.seq);
+                    if let Some(layout) = msgpack {
+                        serialize_frame_msgpack(&frame, mask.as_ref(), layout)
                     } else {
                         let count =
                             frame_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                        let cm_guard = cm.read().unwrap();
-                        let cm_ref = if cm_guard.is_empty() {
-                            None
-                        } else {
-                            Some(&*cm_guard)
-                        };
-                        let json = serialize_frame_json(
+                        let encoded = encode_frame(
+                            &shared,
                             &frame,
+                            mask_spec.as_ref(),
                             mask.as_ref(),
-                            use_delta,
-                            &last_json,
-                            count,
-                            cm_ref,
+                            units,
+                            &cm.read().unwrap(),
                         )?;
-                        Some(Ok(Event::default().event("frame").data(json)))
+                        let json = serialize_frame_json(&encoded, delta_mode, &last_json, count)?;
+                        Some(Ok(frame_event(&frame).event("frame").data(json)))
                     }
                 }
                 Err(tokio_stream::wrappers::errors::BroadcastStreamRecvError::Lagged(n)) => {
+                    if subscriber.record_lag(n) {
+                        return None;
+                    }
                     let mut ts = throttle.lock().unwrap();
                     let effective_fps = ts.on_lag(n);
                     tracing::debug!(
@@ -815,16 +1458,58 @@ async fn unified_stream(
         }
     });
 
+    // Spotter call changes
+    let spotter = BroadcastStream::new(spotter_rx).filter_map(|result| async move {
+        match result {
+            Ok(json) => Some(Ok(Event::default().event("spotter").data(json))),
+            Err(_) => None,
+        }
+    });
+
+    // Lap records, incidents and session results
+    let session_events = BroadcastStream::new(events_rx).filter_map(|result| async move {
+        let event = result.ok()?;
+        let json = serde_json::to_string(&event).ok()?;
+        Some(Ok(Event::default().event("session_event").data(json)))
+    });
+
+    // Adapters connecting to and losing their sims
+    let connection = BroadcastStream::new(connection_rx).filter_map(|result| async move {
+        let event = result.ok()?;
+        let json = serde_json::to_string(&event).ok()?;
+        Some(Ok(Event::default().event("connection").data(json)))
+    });
+
+    // Stream client delivery stats, sent along with adapter status
+    let subscribers = BroadcastStream::new(subscribers_rx).filter_map(|result| async move {
+        match result {
+            Ok(json) => Some(Ok(Event::default().event("subscribers").data(json))),
+            Err(_) => None,
+        }
+    });
+
     // Merge all streams using select (round-robin polling)
     let merged = futures::stream::select(
         futures::stream::select(
-            futures::stream::select(initial.chain(telemetry), status),
-            sinks,
+            futures::stream::select(
+                futures::stream::select(
+                    futures::stream::select(initial.chain(telemetry), status),
+                    sinks,
+                ),
+                annotations,
+            ),
+            spotter,
         ),
-        annotations,
-    );
+        futures::stream::select(
+            futures::stream::select(session_events, connection),
+            subscribers,
+        ),
+    )
+    // A lagging client with the disconnect policy, or one disconnected from
+    // `/api/clients`, loses the whole connection
+    .take_until(disconnected);
 
-    Sse::new(merged).keep_alive(KeepAlive::default())
+    Ok(Sse::new(merged).keep_alive(KeepAlive::default()))
 }
 
 /// SSE endpoint that pushes sink config updates in real-time.
@@ -849,6 +1534,64 @@ async fn sinks_stream(
     Sse::new(initial_event.chain(updates)).keep_alive(KeepAlive::default())
 }
 
+/// SSE endpoint that pushes the active session's metadata (see
+/// [`SessionInfo`](crate::state::SessionInfo)).
+/// Sends the current metadata immediately on connect (`null` when no adapter
+/// is active), then again only when it changes.
+async fn session_stream(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    // Subscribe before reading so a change in between isn't missed
+    let rx = state.session_info_tx.subscribe();
+    let initial_json = {
+        let current = state.session_info.read().unwrap_or_else(|e| e.into_inner());
+        serde_json::to_string(&*current).unwrap_or_default()
+    };
+
+    let updates = BroadcastStream::new(rx).filter_map(|result| async move {
+        match result {
+            Ok(json) => Some(Ok(Event::default().data(json))),
+            Err(_) => None,
+        }
+    });
+
+    let initial_event = stream::once(async move { Ok(Event::default().data(initial_json)) });
+
+    Sse::new(initial_event.chain(updates)).keep_alive(KeepAlive::default())
+}
+
+/// Latest complete session info blob (iRacing's session YAML), verbatim.
+/// Comes from the replayed .ibt file while a replay is active, otherwise from
+/// the active adapter. Refused with 403 under the `redact` transform.
+async fn session_raw(State(state): State<AppState>) -> Result<String, (StatusCode, String)> {
+    if state.redacting() {
+        // The session info lists every driver by name
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Session info is withheld while personal details are redacted".to_string(),
+        ));
+    }
+    if let Some(raw) = state
+        .replay
+        .read()
+        .await
+        .as_ref()
+        .and_then(|rs| rs.session_info_raw())
+    {
+        return Ok(raw.to_string());
+    }
+    state
+        .session_info
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|info| info.raw.clone())
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "No session info available".to_string(),
+        ))
+}
+
 /// SSE endpoint that pushes adapter status updates in real-time.
 /// Sends the current state immediately on connect, then on every change.
 async fn status_stream(
@@ -861,17 +1604,7 @@ async fn status_stream(
 
     let initial: Vec<AdapterInfo> = adapters
         .iter()
-        .map(|adapter| AdapterInfo {
-            key: adapter.key().to_string(),
-            name: adapter.name().to_string(),
-            detected: adapter.detect(),
-            active: adapter.is_active()
-                || active_name
-                    .as_ref()
-                    .map(|n| n == adapter.key())
-                    .unwrap_or(false),
-            enabled: !disabled.contains(adapter.key()),
-        })
+        .map(|slot| AdapterInfo::from_slot(slot, active_name.as_deref(), &disabled))
         .collect();
     drop(adapters);
     drop(active_name);
@@ -886,11 +1619,23 @@ async fn status_stream(
             Err(_) => None,
         }
     });
+    // Named events, so clients that only read adapter status ignore them
+    let subscribers =
+        BroadcastStream::new(state.subscribers_tx.subscribe()).filter_map(|result| async move {
+            match result {
+                Ok(json) => Some(Ok(Event::default().event("subscribers").data(json))),
+                Err(_) => None,
+            }
+        });
 
     // Prepend the initial state event
     let initial_event = stream::once(async move { Ok(Event::default().data(initial_json)) });
 
-    Sse::new(initial_event.chain(updates)).keep_alive(KeepAlive::default())
+    Sse::new(futures::stream::select(
+        initial_event.chain(updates),
+        subscribers,
+    ))
+    .keep_alive(KeepAlive::default())
 }
 
 // === Telemetry Stream Endpoint ===
@@ -900,106 +1645,506 @@ struct StreamQuery {
     metric_mask: Option<String>,
     /// Frames per second (0.0–60.0). Defaults to 60.
     rate: Option<f64>,
-    /// Wire format: "json" (default) or "msgpack" (base64-encoded MessagePack)
+    /// Wire format: "json" (default), "msgpack" or "msgpack_named"
+    /// (base64-encoded MessagePack); "json" or "binary" on the WebSocket
     format: Option<String>,
     /// Enable delta encoding — only send changed sections (default: true).
     /// Set to false for full frames every time.
     delta: Option<bool>,
+    /// Delta mode: "full", "sections" (default) or "delta" (field-level JSON
+    /// merge patches). Takes precedence over `delta`.
+    mode: Option<String>,
+    /// Display units, e.g. "imperial" or "metric,speed:kph" (default: metric)
+    units: Option<String>,
+    /// Set to false to leave `competitors` out of frames, e.g. when they are
+    /// read from `/api/competitors/stream` instead
+    competitors: Option<bool>,
+    /// What to do when the client falls behind: "drop_oldest" (default) or
+    /// "disconnect"
+    on_lag: Option<LagPolicy>,
+}
+
+impl StreamQuery {
+    /// What the client asked for, as shown in `/api/clients`
+    fn client_request(&self, headers: &axum::http::HeaderMap) -> ClientRequest {
+        ClientRequest {
+            metric_mask: self.metric_mask.clone(),
+            rate: self.rate,
+            format: self.format.clone(),
+            user_agent: headers
+                .get(header::USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+        }
+    }
+
+    /// The MessagePack layout asked for by `format`, `None` for JSON
+    fn msgpack(&self) -> Option<MsgpackLayout> {
+        match self.format.as_deref() {
+            Some(f) if f.eq_ignore_ascii_case("msgpack") => Some(MsgpackLayout::Positional),
+            Some(f) if f.eq_ignore_ascii_case("msgpack_named") => Some(MsgpackLayout::Named),
+            _ => None,
+        }
+    }
+
+    /// The requested metric mask, plus `!competitors` when `competitors=false`
+    fn mask_spec(&self) -> Option<String> {
+        match (self.metric_mask.as_deref(), self.competitors) {
+            (mask, Some(false)) => Some(format!("{},!competitors", mask.unwrap_or_default())),
+            (mask, _) => mask.map(str::to_string),
+        }
+    }
+}
+
+/// Parse the `units` query param, rejecting unknown units with 400.
+fn parse_units(units: Option<&str>) -> Result<UnitProfile, (StatusCode, String)> {
+    units
+        .map(str::parse)
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e: anyhow::Error| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// Reject display units for binary frames with 400: those always carry the
+/// model's units, so clients can decode them into its types
+fn check_binary_units(binary: bool, units: UnitProfile) -> Result<(), (StatusCode, String)> {
+    if binary && !units.is_metric() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "units only apply to JSON frames; MessagePack is always metric".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Most frames replayed to a reconnecting client, 30 s at 60 Hz
+const RESUME_MAX_FRAMES: usize = 1800;
+
+/// An SSE event for `frame`, with its `meta.seq` as the event ID so a
+/// reconnecting EventSource sends it back as `Last-Event-ID`
+fn frame_event(frame: &TelemetryFrame) -> Event {
+    match frame.meta.seq {
+        Some(seq) => Event::default().id(seq.to_string()),
+        None => Event::default(),
+    }
+}
+
+/// The `meta.seq` of the last frame a reconnecting client received
+fn last_event_seq(headers: &axum::http::HeaderMap) -> Option<u64> {
+    headers
+        .get("last-event-id")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Frames broadcast after `seq` that are still in the history buffer, at
+/// most the newest [`RESUME_MAX_FRAMES`], thinned out to `min_interval`
+async fn resume_frames(
+    state: &AppState,
+    seq: Option<u64>,
+    min_interval: Option<Duration>,
+) -> Vec<TelemetryFrame> {
+    let Some(seq) = seq else {
+        return Vec::new();
+    };
+    let history = state.history.read().await;
+    let frames = history.get_frames_since_seq(seq, usize::MAX);
+    let skip = frames.len().saturating_sub(RESUME_MAX_FRAMES);
+    let mut last: Option<chrono::DateTime<chrono::Utc>> = None;
+    frames
+        .into_iter()
+        .skip(skip)
+        .filter(|(_, frame)| {
+            let at = frame.meta.timestamp;
+            let due = match (min_interval, last) {
+                (Some(min), Some(last)) => (at - last).to_std().is_ok_and(|d| d >= min),
+                _ => true,
+            };
+            if due {
+                last = Some(at);
+            }
+            due
+        })
+        .map(|(_, frame)| frame.clone())
+        .collect()
+}
+
+/// `resumed` frames, then the live stream, each flagged with whether it's
+/// resumed. Subscribe before reading the history, so no frame falls between.
+fn with_resumed(
+    resumed: Vec<TelemetryFrame>,
+    rx: tokio::sync::broadcast::Receiver<TelemetryFrame>,
+) -> impl Stream<
+    Item = (
+        Result<TelemetryFrame, tokio_stream::wrappers::errors::BroadcastStreamRecvError>,
+        bool,
+    ),
+> {
+    stream::iter(resumed.into_iter().map(|frame| (Ok(frame), true)))
+        .chain(BroadcastStream::new(rx).map(|result| (result, false)))
+}
+
+/// Whether a live frame was already sent when resuming up to `resumed_to`
+fn is_resumed(frame: &TelemetryFrame, resumed_to: Option<u64>) -> bool {
+    matches!((frame.meta.seq, resumed_to), (Some(seq), Some(to)) if seq <= to)
 }
 
 async fn telemetry_stream(
     State(state): State<AppState>,
     Query(query): Query<StreamQuery>,
-) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    headers: axum::http::HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, String)> {
+    let units = parse_units(query.units.as_deref())?;
     let rx = state.subscribe();
-    let metric_mask = query.metric_mask.map(|f| MetricMask::parse(&f));
+    let mask_spec = query.mask_spec().map(std::sync::Arc::<str>::from);
+    let metric_mask = mask_spec.as_deref().map(MetricMask::parse);
     let min_interval = rate_to_interval(query.rate);
-    let use_msgpack = query
-        .format
-        .as_deref()
-        .is_some_and(|f| f.eq_ignore_ascii_case("msgpack"));
-    let use_delta = !use_msgpack && query.delta.unwrap_or(true);
+    let msgpack = query.msgpack();
+    check_binary_units(msgpack.is_some(), units)?;
+    let delta_mode = if msgpack.is_some() {
+        DeltaMode::Full
+    } else {
+        DeltaMode::from_query(&query)
+    };
 
     let throttle_state =
         std::sync::Arc::new(std::sync::Mutex::new(AdaptiveThrottle::new(min_interval)));
     let last_emit = std::sync::Arc::new(std::sync::Mutex::new(tokio::time::Instant::now()));
-    let last_sent_json: std::sync::Arc<std::sync::Mutex<Option<serde_json::Value>>> =
+    let last_sent_json: std::sync::Arc<std::sync::Mutex<Option<std::sync::Arc<EncodedFrame>>>> =
         std::sync::Arc::new(std::sync::Mutex::new(None));
+    let shared_frames = state.shared_frames.clone();
     let delta_frame_count = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
     let custom_metrics = state.custom_metrics.clone();
-    let stream = BroadcastStream::new(rx).filter_map(move |result| {
-        let mask = metric_mask.clone();
-        let last = last_emit.clone();
-        let throttle = throttle_state.clone();
-        let last_json = last_sent_json.clone();
-        let frame_counter = delta_frame_count.clone();
-        let cm = custom_metrics.clone();
-        async move {
-            match result {
-                Ok(frame) => {
-                    let mut ts = throttle.lock().unwrap();
-                    ts.on_frame_received();
-                    let effective_interval = ts.effective_interval();
-                    drop(ts);
-
-                    if let Some(interval) = effective_interval {
-                        let mut guard = last.lock().unwrap();
-                        if guard.elapsed() < interval {
+    let latency = state.latency.clone();
+    let subscriber = std::sync::Arc::new(state.subscribers.register(
+        "/api/telemetry/stream",
+        query.on_lag.unwrap_or_default(),
+        query.client_request(&headers),
+    ));
+    let disconnected = subscriber.disconnected();
+    let resumed = resume_frames(&state, last_event_seq(&headers), min_interval).await;
+    let resumed_to = resumed.last().and_then(|f| f.meta.seq);
+    let stream = with_resumed(resumed, rx)
+        .filter_map(move |(result, resumed)| {
+            let mask = metric_mask.clone();
+            let mask_spec = mask_spec.clone();
+            let shared = shared_frames.clone();
+            let last = last_emit.clone();
+            let throttle = throttle_state.clone();
+            let last_json = last_sent_json.clone();
+            let frame_counter = delta_frame_count.clone();
+            let cm = custom_metrics.clone();
+            let subscriber = subscriber.clone();
+            let latency = latency.clone();
+            async move {
+                match result {
+                    Ok(frame) => {
+                        if resumed {
+                            // Already thinned out to the rate
+                        } else if is_resumed(&frame, resumed_to) {
                             return None;
+                        } else {
+                            let mut ts = throttle.lock().unwrap();
+                            ts.on_frame_received();
+                            let effective_interval = ts.effective_interval();
+                            drop(ts);
+
+                            if let Some(interval) = effective_interval {
+                                let mut guard = last.lock().unwrap();
+                                if guard.elapsed() < interval {
+                                    return None;
+                                }
+                                *guard = tokio::time::Instant::now();
+                            }
+                            latency.record(Stage::Stream, &frame);
                         }
-                        *guard = tokio::time::Instant::now();
-                    }
-                    if use_msgpack {
-                        serialize_frame_msgpack(&frame, mask.as_ref())
-                    } else {
-                        let count =
-                            frame_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                        let cm_guard = cm.read().unwrap();
-                        let cm_ref = if cm_guard.is_empty() {
-                            None
+                        subscriber.record_sent(frame.meta.seq);
+                        if let Some(layout) = msgpack {
+                            serialize_frame_msgpack(&frame, mask.as_ref(), layout)
                         } else {
-                            Some(&*cm_guard)
-                        };
-                        let json = serialize_frame_json(
-                            &frame,
-                            mask.as_ref(),
-                            use_delta,
-                            &last_json,
-                            count,
-                            cm_ref,
-                        )?;
-                        Some(Ok(Event::default().data(json)))
+                            let count =
+                                frame_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                            let encoded = encode_frame(
+                                &shared,
+                                &frame,
+                                mask_spec.as_ref(),
+                                mask.as_ref(),
+                                units,
+                                &cm.read().unwrap(),
+                            )?;
+                            let json =
+                                serialize_frame_json(&encoded, delta_mode, &last_json, count)?;
+                            Some(Ok(frame_event(&frame).data(json)))
+                        }
+                    }
+                    Err(tokio_stream::wrappers::errors::BroadcastStreamRecvError::Lagged(n)) => {
+                        if subscriber.record_lag(n) {
+                            return None;
+                        }
+                        let mut ts = throttle.lock().unwrap();
+                        ts.on_lag(n);
+                        *last_json.lock().unwrap() = None;
+                        None
+                    }
+                }
+            }
+        })
+        .take_until(disconnected);
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// WebSocket counterpart to `/api/telemetry/stream` for low-latency clients:
+/// each frame as one message, with no SSE framing or base64. `format=binary`
+/// sends binary MessagePack messages (`TelemetryFrame::to_bytes_filtered`),
+/// otherwise frames are JSON text. Takes `metric_mask`, `rate`, `units`,
+/// `competitors` and `on_lag` as the SSE stream does; frames are always full.
+async fn telemetry_ws(
+    State(state): State<AppState>,
+    Query(query): Query<StreamQuery>,
+    headers: axum::http::HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, (StatusCode, String)> {
+    let units = parse_units(query.units.as_deref())?;
+    let binary = match query.format.as_deref() {
+        None => false,
+        Some(f) if f.eq_ignore_ascii_case("json") => false,
+        Some(f) if f.eq_ignore_ascii_case("binary") => true,
+        Some(f) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Unknown format '{}', expected json or binary", f),
+            ))
+        }
+    };
+    check_binary_units(binary, units)?;
+    let mut rx = state.subscribe();
+    let mask_spec = query.mask_spec().map(std::sync::Arc::<str>::from);
+    let mask = mask_spec.as_deref().map(MetricMask::parse);
+    let min_interval = rate_to_interval(query.rate);
+    let subscriber = state.subscribers.register(
+        "/api/telemetry/ws",
+        query.on_lag.unwrap_or_default(),
+        query.client_request(&headers),
+    );
+
+    Ok(upgrade.on_upgrade(move |mut socket| async move {
+        let disconnected = subscriber.disconnected();
+        tokio::pin!(disconnected);
+        let mut last_sent: Option<tokio::time::Instant> = None;
+        loop {
+            let frame = tokio::select! {
+                _ = &mut disconnected => break,
+                received = rx.recv() => match received {
+                    Ok(frame) => frame,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        if subscriber.record_lag(n) {
+                            break;
+                        }
+                        continue;
                     }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                },
+                // Clients only ever close the socket
+                message = socket.recv() => match message {
+                    Some(Ok(ws::Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                },
+            };
+            if let (Some(interval), Some(last)) = (min_interval, last_sent) {
+                if last.elapsed() < interval {
+                    continue;
+                }
+            }
+            last_sent = Some(tokio::time::Instant::now());
+            state.latency.record(Stage::Stream, &frame);
+
+            let message = if binary {
+                frame
+                    .to_bytes_filtered(mask.as_ref())
+                    .ok()
+                    .map(ws::Message::Binary)
+            } else {
+                encode_frame(
+                    &state.shared_frames,
+                    &frame,
+                    mask_spec.as_ref(),
+                    mask.as_ref(),
+                    units,
+                    &state.custom_metrics.read().unwrap(),
+                )
+                .map(|encoded| ws::Message::Text(encoded.json.to_string()))
+            };
+            let Some(message) = message else {
+                continue;
+            };
+            subscriber.record_sent(frame.meta.seq);
+            if socket.send(message).await.is_err() {
+                break;
+            }
+        }
+    }))
+}
+
+#[derive(Deserialize)]
+struct CompetitorsQuery {
+    /// Full competitor list updates per second (default 4)
+    rate: Option<f64>,
+    /// Display units, as for `/api/stream`
+    units: Option<String>,
+}
+
+/// SSE endpoint for competitor data, split out of the frame stream.
+///
+/// Sends `competitors` events with the full competitor list at `rate`, and
+/// `standings` events listing each
+/// [`PositionChange`](crate::competitors::PositionChange) as soon as any
+/// car's position changes.
+async fn competitors_stream(
+    State(state): State<AppState>,
+    Query(query): Query<CompetitorsQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, String)> {
+    let units = parse_units(query.units.as_deref())?;
+    let interval = Duration::from_secs_f64(1.0 / query.rate.unwrap_or(4.0).clamp(0.01, 60.0));
+    let mut tracker = StandingsTracker::default();
+    let mut last_list: Option<tokio::time::Instant> = None;
+
+    let stream = BroadcastStream::new(state.subscribe())
+        .map(move |result| {
+            let mut events = Vec::new();
+            let Some(competitors) = result.ok().and_then(|frame| frame.competitors) else {
+                return events;
+            };
+            // The first list doubles as the initial standings
+            let changes = tracker.update(&competitors);
+            if last_list.is_some() && !changes.is_empty() {
+                if let Ok(json) = serde_json::to_string(&changes) {
+                    events.push(Event::default().event("standings").data(json));
                 }
-                Err(tokio_stream::wrappers::errors::BroadcastStreamRecvError::Lagged(n)) => {
-                    let mut ts = throttle.lock().unwrap();
-                    ts.on_lag(n);
-                    *last_json.lock().unwrap() = None;
-                    None
+            }
+            if last_list.is_none_or(|t| t.elapsed() >= interval) {
+                last_list = Some(tokio::time::Instant::now());
+                if let Ok(mut value) = serde_json::to_value(Units(&competitors, units)) {
+                    round_json_floats(&mut value);
+                    events.push(
+                        Event::default()
+                            .event("competitors")
+                            .data(value.to_string()),
+                    );
                 }
             }
-        }
-    });
+            events
+        })
+        .flat_map(|events| stream::iter(events.into_iter().map(Ok)));
 
-    Sse::new(stream).keep_alive(KeepAlive::default())
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// How `msgpack` stream frames lay out structs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MsgpackLayout {
+    /// `format=msgpack`: positional arrays in unmasked frames, as the stream
+    /// has always sent them
+    Positional,
+    /// `format=msgpack_named`: maps keyed by field name, as sinks and relays
+    /// send them
+    Named,
 }
 
 /// Serialize a frame to base64-encoded MessagePack for SSE transport.
 fn serialize_frame_msgpack(
     frame: &TelemetryFrame,
     mask: Option<&MetricMask>,
+    layout: MsgpackLayout,
 ) -> Option<Result<Event, Infallible>> {
-    // If there's a metric mask, filter through JSON first then serialize the filtered value
-    let bytes = if let Some(mask) = mask {
-        let json_str = frame.to_json_filtered(Some(mask)).ok()?;
-        let val: serde_json::Value = serde_json::from_str(&json_str).ok()?;
-        rmp_serde::to_vec(&val).ok()?
-    } else {
-        rmp_serde::to_vec(frame).ok()?
-    };
+    let bytes = match layout {
+        MsgpackLayout::Positional => frame.to_bytes_positional(mask),
+        MsgpackLayout::Named => frame.to_bytes_filtered(mask),
+    }
+    .ok()?;
     use base64::Engine;
     let encoded = base64::engine::general_purpose::STANDARD.encode(&bytes);
-    Some(Ok(Event::default().event("msgpack").data(encoded)))
+    Some(Ok(frame_event(frame).event("msgpack").data(encoded)))
+}
+
+// === Overlay Endpoints ===
+
+#[derive(Deserialize)]
+struct OverlayQuery {
+    /// Updates per second for `/overlay/stream` (default 2, at most 10)
+    rate: Option<f64>,
+    /// Display units, as for `/api/stream`
+    units: Option<String>,
+}
+
+fn overlay_json(overlay: &Overlay, units: UnitProfile) -> String {
+    serde_json::to_string(&Units(overlay, units)).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// The overlay summary of the latest frame, for overlays that poll
+async fn overlay_snapshot(
+    State(state): State<AppState>,
+    Query(query): Query<OverlayQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let units = parse_units(query.units.as_deref())?;
+    let overlay = state
+        .history
+        .read()
+        .await
+        .latest_frame()
+        .map_or_else(Overlay::empty, Overlay::from_frame);
+    Ok((
+        [(header::CONTENT_TYPE, "application/json")],
+        overlay_json(&overlay, units),
+    ))
+}
+
+/// SSE endpoint sending the overlay summary as `overlay` events at `rate`,
+/// skipping updates that change nothing
+async fn overlay_stream(
+    State(state): State<AppState>,
+    Query(query): Query<OverlayQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, String)> {
+    let units = parse_units(query.units.as_deref())?;
+    let interval = Duration::from_secs_f64(1.0 / query.rate.unwrap_or(2.0).clamp(0.01, 10.0));
+    // Subscribe before reading so a frame in between isn't missed
+    let rx = state.subscribe();
+    let initial = state
+        .history
+        .read()
+        .await
+        .latest_frame()
+        .map_or_else(Overlay::empty, Overlay::from_frame);
+    let initial_event = Event::default()
+        .event("overlay")
+        .data(overlay_json(&initial, units));
+
+    let mut last_sent = Some(initial);
+    let mut last_emit = tokio::time::Instant::now();
+    let updates = BroadcastStream::new(rx).filter_map(move |result| {
+        let event = result.ok().and_then(|frame| {
+            if last_emit.elapsed() < interval {
+                return None;
+            }
+            let overlay = Overlay::from_frame(&frame);
+            if last_sent.as_ref() == Some(&overlay) {
+                return None;
+            }
+            last_emit = tokio::time::Instant::now();
+            let json = overlay_json(&overlay, units);
+            last_sent = Some(overlay);
+            Some(Ok(Event::default().event("overlay").data(json)))
+        });
+        async move { event }
+    });
+
+    Ok(
+        Sse::new(stream::once(async move { Ok(initial_event) }).chain(updates))
+            .keep_alive(KeepAlive::default()),
+    )
 }
 
 // === Sink Management Endpoints ===
@@ -1015,10 +2160,19 @@ struct CreateSinkRequest {
     config: SinkConfig,
 }
 
+/// POST /api/sinks — add a sink. The sink is built once first, so a bad
+/// address, port or LED layout is a 400 rather than a warning in the log.
 async fn create_sink(
     State(state): State<AppState>,
     Json(request): Json<CreateSinkRequest>,
-) -> impl IntoResponse {
+) -> Result<(StatusCode, Json<SinkConfig>), (StatusCode, String)> {
+    let check = request.config.clone();
+    // Opening a serial port blocks
+    tokio::task::spawn_blocking(move || crate::sinks::create_sink(&check).map(drop))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("{:#}", e)))?;
+
     let config = {
         let mut sinks = state.sinks.write().await;
 
@@ -1033,7 +2187,7 @@ async fn create_sink(
     };
     broadcast_sinks(&state).await;
 
-    (StatusCode::CREATED, Json(config))
+    Ok((StatusCode::CREATED, Json(config)))
 }
 
 async fn delete_sink(
@@ -1052,9 +2206,64 @@ async fn delete_sink(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Accept a relay uplink from another server's `relay` sink. Uplinks feed
+/// frames in, so they need a token whenever tokens are configured, whatever
+/// the auth scope.
+async fn relay_uplink(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    uri: axum::http::Uri,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    if !state.auth.tokens.is_empty() && !presents_token(&headers, &uri, &state.auth) {
+        return (StatusCode::UNAUTHORIZED, "Relay uplinks need a token").into_response();
+    }
+    let hub = state.relay.clone();
+    upgrade.on_upgrade(move |socket| relay::receive(hub, socket))
+}
+
+/// POST /api/ingest — phone sensor samples for the `ingest` adapter. Like
+/// relay uplinks they feed frames in, so they need a token whenever tokens
+/// are configured.
+async fn ingest_samples(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    uri: axum::http::Uri,
+    Json(body): Json<IngestBody>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    if !state.auth.tokens.is_empty() && !presents_token(&headers, &uri, &state.auth) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Ingesting samples needs a token".to_string(),
+        ));
+    }
+    let samples = body.into_samples();
+    if samples.is_empty() || samples.len() > ingest::MAX_BATCH {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Send between 1 and {} samples", ingest::MAX_BATCH),
+        ));
+    }
+    if let Some(i) = samples.iter().position(|s| !s.has_readings()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Sample {} has no accel, gyro or gps reading", i),
+        ));
+    }
+    if !state.ingest.push(&samples) {
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            "The ingest queue is full; retry shortly".to_string(),
+        ));
+    }
+    Ok(Json(
+        serde_json::json!({ "ok": true, "accepted": samples.len() }),
+    ))
+}
+
 // === Replay Endpoints ===
 
-/// Handle .ibt file upload, create replay state, and start playback
+/// Handle .ibt or recording upload, create replay state, and start playback
 async fn replay_upload(
     State(state): State<AppState>,
     mut multipart: Multipart,
@@ -1071,41 +2280,58 @@ async fn replay_upload(
         .ok_or((StatusCode::BAD_REQUEST, "No file provided".to_string()))?;
 
     let file_name = field.file_name().unwrap_or("upload.ibt").to_string();
+    let recording = crate::compression::is_recording(&file_name.to_lowercase());
 
-    if !file_name.to_lowercase().ends_with(".ibt") {
+    if !file_name.to_lowercase().ends_with(".ibt") && !recording {
         return Err((
             StatusCode::BAD_REQUEST,
-            "Only .ibt files are supported".to_string(),
+            "Only .ibt files and .ost.ndjson recordings are supported".to_string(),
         ));
     }
 
-    let data = field.bytes().await.map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            format!("Failed to read file data: {}", e),
-        )
-    })?;
+    let store = state.replay_store.clone();
+    let temp_path = store
+        .upload_path(&file_name)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))?;
+    let size = write_upload(field, &temp_path, store.max_upload_bytes())
+        .await
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp_path);
+        })?;
 
-    tracing::info!("Received .ibt file: {} ({} bytes)", file_name, data.len());
+    tracing::info!("Received replay file: {} ({} bytes)", file_name, size);
+
+    let loaded = state
+        .replay
+        .read()
+        .await
+        .as_ref()
+        .and_then(|rs| rs.temp_path().map(Path::to_path_buf));
 
     // Move blocking file I/O off the async runtime to avoid starving
     // SSE keep-alive events and other async tasks
-    let replay_state = tokio::task::spawn_blocking(move || {
-        let temp_dir = std::env::temp_dir().join("ost-replay");
-        std::fs::create_dir_all(&temp_dir).map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to create temp dir: {}", e),
-            )
-        })?;
+    let mut replay_state = tokio::task::spawn_blocking(move || {
+        // Make room, keeping the replay still loaded
+        let keep: Vec<&Path> = [Some(temp_path.as_path()), loaded.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if let Err(e) = store.sweep(&keep) {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err((StatusCode::INSUFFICIENT_STORAGE, format!("{:#}", e)));
+        }
 
-        let temp_path = temp_dir.join(&file_name);
-        std::fs::write(&temp_path, &data).map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to write temp file: {}", e),
-            )
-        })?;
+        if recording {
+            // Recordings are read into memory, so the upload isn't kept
+            let result = ReplayState::from_ndjson(&temp_path);
+            let _ = std::fs::remove_file(&temp_path);
+            return result.map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Failed to parse recording: {}", e),
+                )
+            });
+        }
 
         ReplayState::from_file(&temp_path).map_err(|e| {
             let _ = std::fs::remove_file(&temp_path);
@@ -1123,8 +2349,10 @@ async fn replay_upload(
         )
     })??;
 
+    start_replay_indexing(&state, &replay_state);
     let info = replay_state.info();
 
+    replay_state.set_redacted(state.redacting());
     {
         let mut replay = state.replay.write().await;
         *replay = Some(replay_state);
@@ -1133,63 +2361,426 @@ async fn replay_upload(
     start_playback_task(state.clone()).await;
 
     Ok(Json(serde_json::json!({
-        "status": "ok",
-        "info": info
+        "status": "ok",
+        "info": info
+    })))
+}
+
+/// Space taken by uploaded replays, and the limits on it
+async fn replay_storage(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let store = state.replay_store.clone();
+    let usage = tokio::task::spawn_blocking(move || store.usage())
+        .await
+        .unwrap_or_default();
+    Json(serde_json::json!({
+        "usage": usage,
+        "limits": state.replay_store.config(),
+    }))
+}
+
+/// Stream an upload to `path`, failing with 413 past `limit` bytes
+async fn write_upload(
+    mut field: axum::extract::multipart::Field<'_>,
+    path: &Path,
+    limit: u64,
+) -> Result<u64, (StatusCode, String)> {
+    use tokio::io::AsyncWriteExt;
+
+    let write_error = |e: std::io::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to write temp file: {}", e),
+        )
+    };
+    let mut file = tokio::fs::File::create(path).await.map_err(write_error)?;
+    let mut size = 0u64;
+    while let Some(chunk) = field.chunk().await.map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Failed to read file data: {}", e),
+        )
+    })? {
+        size += chunk.len() as u64;
+        if size > limit {
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("Uploads are limited to {} MB", limit / 1024 / 1024),
+            ));
+        }
+        file.write_all(&chunk).await.map_err(write_error)?;
+    }
+    file.flush().await.map_err(write_error)?;
+    Ok(size)
+}
+
+/// Index a newly loaded replay in the background, sending each update to
+/// status stream subscribers as `{"source": "replay", "replay_index": ...}`
+fn start_replay_indexing(state: &AppState, replay: &ReplayState) {
+    let status_tx = state.status_tx.clone();
+    replay.start_indexing(move |index| {
+        let _ = status_tx.send(
+            serde_json::json!({
+                "source": "replay",
+                "replay_index": index,
+            })
+            .to_string(),
+        );
+    });
+}
+
+async fn replay_info(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let replay = state.replay.read().await;
+    if let Some(rs) = &*replay {
+        let mut info = serde_json::to_value(rs.info()).unwrap();
+        let obj = info.as_object_mut().unwrap();
+        obj.insert("mode".into(), "replay".into());
+        if let Some(rss) = get_process_rss_bytes() {
+            obj.insert(
+                "process_memory_mb".into(),
+                serde_json::json!(rss as f64 / 1_048_576.0),
+            );
+        }
+        Ok(Json(info))
+    } else {
+        drop(replay);
+        let history = state.history.read().await;
+        Ok(Json(serde_json::json!({
+            "mode": "history",
+            "total_frames": history.frame_count(),
+            "tick_rate": history.tick_rate(),
+            "duration_secs": history.duration_secs(),
+            "current_frame": history.frame_count().saturating_sub(1),
+            "playing": false,
+            "playback_speed": 1.0,
+            "track_name": history.track_name(),
+            "car_name": history.car_name(),
+            "file_size": 0,
+            "laps": history.laps(),
+            "replay_id": "",
+            "paused": history.is_paused(),
+            "estimated_memory_mb": history.estimated_memory_mb(),
+            "process_memory_mb": get_process_rss_bytes().map(|b| b as f64 / 1_048_576.0),
+            "max_duration_secs": history.max_duration_secs(),
+        })))
+    }
+}
+
+/// Return the pre-computed track outline for the current replay.
+/// The outline is an array of [lat, lng] pairs extracted from on-track GPS data.
+async fn replay_trackmap(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let replay = state.replay.read().await;
+    if let Some(rs) = &*replay {
+        Ok(Json(serde_json::json!({
+            "outline": rs.track_outline(),
+        })))
+    } else {
+        Err((StatusCode::NOT_FOUND, "No active replay".into()))
+    }
+}
+
+async fn replay_overview(
+    State(state): State<AppState>,
+) -> Result<Json<crate::overview::Overview>, (StatusCode, String)> {
+    let replay = state.replay.read().await;
+    match &*replay {
+        Some(rs) => Ok(Json(rs.overview().clone())),
+        None => Err((StatusCode::NOT_FOUND, "No active replay".into())),
+    }
+}
+
+/// Longest run of frames `/api/replay/suspension` analyses at once, an hour
+/// at 60 Hz
+const SUSPENSION_MAX_FRAMES: usize = 216_000;
+
+#[derive(Deserialize)]
+struct SuspensionQuery {
+    /// Analyse this lap, by number
+    lap: Option<i32>,
+    /// Or these frames, inclusive; the whole replay by default
+    start: Option<usize>,
+    end: Option<usize>,
+    /// Histogram bin width, mm/s
+    bin_width: Option<f32>,
+}
+
+/// Shock velocity histograms and spectra of each corner over a lap or range
+/// of frames of the current replay
+async fn replay_suspension(
+    State(state): State<AppState>,
+    Query(params): Query<SuspensionQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let bin_width = params
+        .bin_width
+        .unwrap_or(crate::suspension::DEFAULT_BIN_WIDTH);
+    if !(1.0..=crate::suspension::HISTOGRAM_RANGE).contains(&bin_width) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "bin_width must be between 1 and {} mm/s",
+                crate::suspension::HISTOGRAM_RANGE
+            ),
+        ));
+    }
+
+    let (frames, sample_rate_hz, start, end) = {
+        let replay = state.replay.read().await;
+        let rs = replay
+            .as_ref()
+            .ok_or((StatusCode::NOT_FOUND, "No active replay".to_string()))?;
+        let last = rs.total_frames().saturating_sub(1);
+        let (start, end) = match params.lap {
+            Some(number) => replay_lap(rs, number)?,
+            None => (
+                params.start.unwrap_or(0),
+                params.end.unwrap_or(last).min(last),
+            ),
+        };
+        (rs.frames(), rs.tick_rate() as f64, start, end)
+    };
+    if start > end {
+        return Err((StatusCode::BAD_REQUEST, "start is after end".into()));
+    }
+    if end - start + 1 > SUSPENSION_MAX_FRAMES {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("At most {} frames at once", SUSPENSION_MAX_FRAMES),
+        ));
+    }
+
+    // Decoding an hour of frames takes a while, so keep it off the runtime
+    let corners = tokio::task::spawn_blocking(move || {
+        let mut columns = crate::suspension::columns();
+        read_replay_columns(&frames, start, end, &mut columns)?;
+        Ok(crate::suspension::analyze(
+            &columns,
+            sample_rate_hz,
+            bin_width,
+        ))
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))??;
+    Ok(Json(serde_json::json!({
+        "start_frame": start,
+        "end_frame": end,
+        "sample_rate_hz": sample_rate_hz,
+        "corners": corners,
+    })))
+}
+
+#[derive(Deserialize)]
+struct CornersQuery {
+    /// Lap to find the corners on; the fastest valid lap by default
+    lap: Option<i32>,
+    /// Another lap to take the same corners from
+    compare: Option<i32>,
+}
+
+/// Corners of a reference lap of the current replay, with entry, minimum
+/// and exit speed and gear, optionally measured on a second lap too
+async fn analytics_corners(
+    State(state): State<AppState>,
+    Query(params): Query<CornersQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let (frames, sample_rate_hz, lap, compare) = {
+        let replay = state.replay.read().await;
+        let rs = replay
+            .as_ref()
+            .ok_or((StatusCode::NOT_FOUND, "No active replay".to_string()))?;
+        let lap = match params.lap {
+            Some(number) => number,
+            None => rs
+                .laps()
+                .iter()
+                .filter(|lap| lap.valid)
+                .filter_map(|lap| Some((lap.lap_number, lap.lap_time_secs?)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(number, _)| number)
+                .ok_or((
+                    StatusCode::NOT_FOUND,
+                    "No complete valid lap; choose one with ?lap=".to_string(),
+                ))?,
+        };
+        let compare = match params.compare {
+            Some(number) => Some((number, replay_lap(rs, number)?)),
+            None => None,
+        };
+        (
+            rs.frames(),
+            rs.tick_rate() as f64,
+            (lap, replay_lap(rs, lap)?),
+            compare,
+        )
+    };
+
+    // Reading whole laps of frames takes a while, so keep it off the runtime
+    tokio::task::spawn_blocking(move || {
+        let lap_columns = |(start, end): (usize, usize)| {
+            let mut columns = crate::corners::columns();
+            read_replay_columns(&frames, start, end, &mut columns)?;
+            Ok::<_, (StatusCode, String)>(columns)
+        };
+        let corners = crate::corners::detect(&lap_columns(lap.1)?, sample_rate_hz);
+        let mut body = serde_json::json!({
+            "lap": lap.0,
+            "corners": corners,
+        });
+        if let Some((number, range)) = compare {
+            let columns = lap_columns(range)?;
+            let metrics: Vec<_> = corners
+                .iter()
+                .map(|corner| crate::corners::measure(&columns, corner))
+                .collect();
+            body["compare"] = serde_json::json!({
+                "lap": number,
+                "corners": metrics,
+            });
+        }
+        Ok(Json(body))
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+}
+
+#[derive(Deserialize)]
+struct PaceQuery {
+    /// `live` or `replay`; the replay when one is loaded by default
+    source: Option<String>,
+    /// Valid laps consistency and pace are measured over
+    window: Option<usize>,
+}
+
+/// Lap time consistency, theoretical best and pace against the leader, of
+/// the live session or the current replay
+async fn analytics_pace(
+    State(state): State<AppState>,
+    Query(params): Query<PaceQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let window = params.window.unwrap_or(crate::pace::DEFAULT_WINDOW);
+    if window < 2 {
+        return Err((StatusCode::BAD_REQUEST, "window must be at least 2".into()));
+    }
+    let replay = state.replay.read().await;
+    let source = match params.source.as_deref() {
+        Some("live") => "live",
+        Some("replay") => "replay",
+        None if replay.is_some() => "replay",
+        None => "live",
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Unknown source '{}': live or replay", other),
+            ))
+        }
+    };
+
+    let pace = if source == "live" {
+        let events = state.events.lock().unwrap_or_else(|e| e.into_inner());
+        events.pace(window)
+    } else {
+        let rs = replay
+            .as_ref()
+            .ok_or((StatusCode::NOT_FOUND, "No active replay".to_string()))?;
+        replay_pace_log(&state, rs)?.stats(window)
+    };
+    Ok(Json(serde_json::json!({
+        "source": source,
+        "window": window,
+        "pace": pace,
     })))
 }
 
-async fn replay_info(
-    State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let replay = state.replay.read().await;
-    if let Some(rs) = &*replay {
-        let mut info = serde_json::to_value(rs.info()).unwrap();
-        let obj = info.as_object_mut().unwrap();
-        obj.insert("mode".into(), "replay".into());
-        if let Some(rss) = get_process_rss_bytes() {
-            obj.insert(
-                "process_memory_mb".into(),
-                serde_json::json!(rss as f64 / 1_048_576.0),
-            );
+/// The replay's timed laps, with sectors timed from their lap distance
+fn replay_pace_log(
+    state: &AppState,
+    rs: &ReplayState,
+) -> Result<crate::pace::PaceLog, (StatusCode, String)> {
+    let internal = |e: anyhow::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    let sector_starts: Vec<f32> = rs
+        .get_frames_range(0, 1)
+        .map_err(internal)?
+        .into_iter()
+        .next()
+        .and_then(|(_, mut frame)| {
+            state.tracks.enrich(&mut frame);
+            frame.session?.sector_starts
+        })
+        .map(|starts| starts.iter().map(|s| s.0).collect())
+        .unwrap_or_default();
+
+    let rate = rs.tick_rate().max(1) as f64;
+    let frames = rs.frames();
+    let mut log = crate::pace::PaceLog::default();
+    for lap in rs.laps() {
+        let Some(lap_time) = lap.lap_time_secs else {
+            continue;
+        };
+        let mut columns = FrameColumns::with_channels(
+            [
+                "timing.lap_distance_pct",
+                "vehicle.throttle",
+                "vehicle.brake",
+            ]
+            .into_iter()
+            .filter_map(channels::find),
+        );
+        read_replay_columns(&frames, lap.start_frame, lap.end_frame, &mut columns)?;
+        let sectors = columns.column("timing.lap_distance_pct").and_then(|pct| {
+            let mut timer = crate::pace::SectorTimer::new(&sector_starts, 0.0);
+            for (i, &p) in pct.iter().enumerate().filter(|(_, p)| !p.is_nan()) {
+                timer.add(i as f64 / rate, p);
+            }
+            timer.finish(lap_time as f32)
+        });
+        let mut inputs = crate::replay_index::InputCounter::default();
+        if let (Some(throttle), Some(brake)) = (
+            columns.column("vehicle.throttle"),
+            columns.column("vehicle.brake"),
+        ) {
+            for (&throttle, &brake) in throttle.iter().zip(brake) {
+                if throttle.is_finite() && brake.is_finite() {
+                    inputs.add(throttle, brake);
+                }
+            }
         }
-        Ok(Json(info))
-    } else {
-        drop(replay);
-        let history = state.history.read().await;
-        Ok(Json(serde_json::json!({
-            "mode": "history",
-            "total_frames": history.frame_count(),
-            "tick_rate": history.tick_rate(),
-            "duration_secs": history.duration_secs(),
-            "current_frame": history.frame_count().saturating_sub(1),
-            "playing": false,
-            "playback_speed": 1.0,
-            "track_name": history.track_name(),
-            "car_name": history.car_name(),
-            "file_size": 0,
-            "laps": history.laps(),
-            "replay_id": "",
-            "paused": history.is_paused(),
-            "estimated_memory_mb": history.estimated_memory_mb(),
-            "process_memory_mb": get_process_rss_bytes().map(|b| b as f64 / 1_048_576.0),
-            "max_duration_secs": history.max_duration_secs(),
-        })))
+        log.add_lap(lap_time as f32, lap.valid, sectors, inputs.take());
     }
+    Ok(log)
 }
 
-/// Return the pre-computed track outline for the current replay.
-/// The outline is an array of [lat, lng] pairs extracted from on-track GPS data.
-async fn replay_trackmap(
-    State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let replay = state.replay.read().await;
-    if let Some(rs) = &*replay {
-        Ok(Json(serde_json::json!({
-            "outline": rs.track_outline(),
-        })))
-    } else {
-        Err((StatusCode::NOT_FOUND, "No active replay".into()))
+/// First and last frame of lap `number` of the replay
+fn replay_lap(rs: &ReplayState, number: i32) -> Result<(usize, usize), (StatusCode, String)> {
+    rs.laps()
+        .iter()
+        .find(|lap| lap.lap_number == number)
+        .map(|lap| (lap.start_frame, lap.end_frame))
+        .ok_or((StatusCode::NOT_FOUND, format!("No lap {}", number)))
+}
+
+/// Append frames `start..=end` of the replay to `columns`
+fn read_replay_columns(
+    frames: &ReplayFrames,
+    start: usize,
+    end: usize,
+    columns: &mut FrameColumns,
+) -> Result<(), (StatusCode, String)> {
+    let mut next = start;
+    while next <= end {
+        let batch = frames
+            .get_frames_range(next, end + 1 - next)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        if batch.is_empty() {
+            break;
+        }
+        next += batch.len();
+        for (_, frame) in &batch {
+            columns.push(frame);
+        }
     }
+    Ok(())
 }
 
 #[derive(Deserialize)]
@@ -1204,23 +2795,43 @@ struct ReplayFramesQuery {
 async fn replay_frames(
     State(state): State<AppState>,
     Query(params): Query<ReplayFramesQuery>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<Response, (StatusCode, String)> {
     let replay = state.replay.read().await;
     if let Some(rs) = replay.as_ref() {
-        // Serve from replay file
-        let frames = rs
-            .get_frames_range(params.start, params.count)
-            .map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Failed to read frames: {}", e),
-                )
-            })?;
-
-        let metric_mask = params.metric_mask.map(|f| MetricMask::parse(&f));
+        // Serve from replay file, or the serialized response to an earlier
+        // identical request (scrubbing asks for the same ranges repeatedly)
         let cm = state.custom_metrics.read().unwrap();
-        let cm_ref = if cm.is_empty() { None } else { Some(&*cm) };
-        let json_frames = serialize_frames(frames.into_iter(), &metric_mask, cm_ref);
+        let key = FrameRangeKey {
+            start: params.start,
+            count: params.count,
+            metric_mask: params.metric_mask.clone(),
+            custom_metrics: cm.generation,
+        };
+        let body = match rs.cached_frames(&key) {
+            Some(body) => body,
+            None => {
+                let frames = rs
+                    .get_frames_range(params.start, params.count)
+                    .map_err(|e| {
+                        (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            format!("Failed to read frames: {}", e),
+                        )
+                    })?;
+
+                let metric_mask = params.metric_mask.map(|f| MetricMask::parse(&f));
+                let cm_ref = if cm.is_empty() { None } else { Some(&*cm) };
+                let json_frames = serialize_frames(frames.into_iter(), &metric_mask, cm_ref);
+                let body = Bytes::from(serde_json::to_vec(&json_frames).map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to serialize frames: {}", e),
+                    )
+                })?);
+                rs.cache_frames(key, body.clone());
+                body
+            }
+        };
 
         // When a replay_id is in the URL, the response is content-addressed and immutable
         let cache_header = if params.rid.is_some() {
@@ -1230,9 +2841,13 @@ async fn replay_frames(
         };
 
         Ok((
-            [(header::CACHE_CONTROL, cache_header)],
-            Json(serde_json::json!(json_frames)),
-        ))
+            [
+                (header::CONTENT_TYPE, "application/json"),
+                (header::CACHE_CONTROL, cache_header),
+            ],
+            body,
+        )
+            .into_response())
     } else {
         // Serve from history buffer
         drop(replay);
@@ -1251,7 +2866,8 @@ async fn replay_frames(
         Ok((
             [(header::CACHE_CONTROL, "no-cache")],
             Json(serde_json::json!(json_frames)),
-        ))
+        )
+            .into_response())
     }
 }
 
@@ -1325,6 +2941,17 @@ async fn replay_control(
                     serde_json::json!({"status": "speed_set", "speed": rs.playback_speed()}),
                 ))
             }
+            "session" => {
+                // No value plays the whole replay again
+                let session = request.value.map(|v| v as i32);
+                rs.select_session(session)
+                    .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+                Ok(Json(serde_json::json!({
+                    "status": "session_set",
+                    "session": session,
+                    "frame": rs.current_frame(),
+                })))
+            }
             _ => Err((
                 StatusCode::BAD_REQUEST,
                 format!("Unknown action: {}", request.action),
@@ -1368,6 +2995,230 @@ async fn replay_delete(State(state): State<AppState>) -> Result<StatusCode, (Sta
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ExtractFormat {
+    #[default]
+    Ibt,
+    Ndjson,
+}
+
+#[derive(Deserialize)]
+struct ExtractRequest {
+    /// A lap to extract, or else an inclusive range of frames
+    lap: Option<i32>,
+    start: Option<usize>,
+    end: Option<usize>,
+    #[serde(default)]
+    format: ExtractFormat,
+}
+
+/// Most frames extracted at once, an hour at 60 Hz
+const EXTRACT_MAX_FRAMES: usize = 216_000;
+
+/// Download a lap or range of frames of the replay as a new .ibt or NDJSON
+/// recording, e.g. to share a single flying lap
+async fn replay_extract(
+    State(state): State<AppState>,
+    Json(request): Json<ExtractRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let replay = state.replay.read().await;
+    let rs = replay
+        .as_ref()
+        .ok_or((StatusCode::NOT_FOUND, "No active replay".to_string()))?;
+    let (start, end, segment) = match (request.lap, request.start, request.end) {
+        (Some(lap), None, None) => {
+            let (start, end) = replay_lap(rs, lap)?;
+            (start, end, format!("lap{}", lap))
+        }
+        (None, Some(start), Some(end)) if start <= end && end < rs.total_frames() => {
+            (start, end, format!("frames{}-{}", start, end))
+        }
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Give a lap, or start and end frames within the replay".to_string(),
+            ))
+        }
+    };
+    if end + 1 - start > EXTRACT_MAX_FRAMES {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("At most {} frames at once", EXTRACT_MAX_FRAMES),
+        ));
+    }
+
+    let (body, content_type, extension) = match request.format {
+        ExtractFormat::Ibt => (
+            rs.extract_ibt(start, end, std::io::Cursor::new(Vec::new()))
+                .map(|out| out.into_inner()),
+            "application/octet-stream",
+            "ibt",
+        ),
+        ExtractFormat::Ndjson => (
+            rs.extract_ndjson(start, end, Vec::new()),
+            "application/x-ndjson",
+            "ost.ndjson",
+        ),
+    };
+    let body = body.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to extract frames: {}", e),
+        )
+    })?;
+
+    // Header-safe names, as track and car names can be anything
+    let clean = |name: &str| {
+        let name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        if name.is_empty() {
+            "unknown".to_string()
+        } else {
+            name
+        }
+    };
+    let filename = format!(
+        "{}_{}_{}.{}",
+        clean(rs.track_name()),
+        clean(rs.car_name()),
+        segment,
+        extension
+    );
+
+    let mut headers = axum::http::HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+    headers.insert(
+        header::CONTENT_DISPOSITION,
+        format!("attachment; filename=\"{}\"", filename)
+            .parse()
+            .unwrap(),
+    );
+    Ok((headers, body))
+}
+
+/// Set the reference lap paired with the replay position on
+/// `/api/replay/ghost/stream`
+async fn replay_ghost_set(
+    State(state): State<AppState>,
+    Json(settings): Json<GhostSettings>,
+) -> Result<Json<GhostSettings>, (StatusCode, String)> {
+    let mut replay = state.replay.write().await;
+    let rs = replay
+        .as_mut()
+        .ok_or((StatusCode::NOT_FOUND, "No active replay".to_string()))?;
+    replay_lap(rs, settings.lap)?;
+    let ghost =
+        Ghost::new(rs, settings).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    rs.set_ghost(Some(ghost));
+    Ok(Json(settings))
+}
+
+async fn replay_ghost_clear(
+    State(state): State<AppState>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let mut replay = state.replay.write().await;
+    let rs = replay
+        .as_mut()
+        .ok_or((StatusCode::NOT_FOUND, "No active replay".to_string()))?;
+    rs.set_ghost(None);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct GhostStreamQuery {
+    metric_mask: Option<String>,
+    /// Most events per second
+    rate: Option<f64>,
+}
+
+/// SSE stream of the replay position paired with the matching frame of the
+/// ghost lap, sent whenever the position changes, while playing or scrubbing
+async fn replay_ghost_stream(
+    State(state): State<AppState>,
+    Query(query): Query<GhostStreamQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let interval = Duration::from_secs_f64(1.0 / query.rate.unwrap_or(30.0).clamp(1.0, 60.0));
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let metric_mask = query.metric_mask.map(|m| MetricMask::parse(&m));
+    let updates = stream::unfold(
+        (state, ticker, metric_mask, None),
+        |(state, mut ticker, metric_mask, mut last)| async move {
+            loop {
+                ticker.tick().await;
+                let data = {
+                    let replay = state.replay.read().await;
+                    let Some(rs) = replay.as_ref() else {
+                        continue;
+                    };
+                    let Some(ghost) = rs.ghost() else {
+                        continue;
+                    };
+                    let position = Some((rs.current_frame(), ghost.settings()));
+                    if last == position {
+                        continue;
+                    }
+                    last = position;
+                    ghost_json(&state, rs, ghost, &metric_mask)
+                };
+                if let Some(data) = data {
+                    let event = Event::default().event("ghost").data(data);
+                    return Some((Ok(event), (state, ticker, metric_mask, last)));
+                }
+            }
+        },
+    );
+    Sse::new(updates).keep_alive(KeepAlive::default())
+}
+
+/// The `ghost` event for the replay position, `None` off the replay's laps
+fn ghost_json(
+    state: &AppState,
+    rs: &ReplayState,
+    ghost: &Ghost,
+    metric_mask: &Option<MetricMask>,
+) -> Option<String> {
+    let index = rs.current_frame();
+    let frame = rs.get_frame(index).ok()?;
+    let distance = frame
+        .timing
+        .as_ref()
+        .and_then(|t| t.lap_distance_pct)
+        .map(|pct| pct.0);
+    let pair = ghost.pair(rs.laps(), index, distance)?;
+    let reference = rs.get_frame(pair.reference).ok()?;
+
+    let cm = state.custom_metrics.read().unwrap();
+    let cm_ref = if cm.is_empty() { None } else { Some(&*cm) };
+    let mut frames = serialize_frames(
+        [(index, frame), (pair.reference, reference)].into_iter(),
+        metric_mask,
+        cm_ref,
+    );
+    let reference = frames.pop()?;
+    let player = frames.pop()?;
+    let settings = ghost.settings();
+    Some(
+        serde_json::json!({
+            "lap": settings.lap,
+            "align": settings.align,
+            "delta": pair.delta,
+            "player": player,
+            "reference": reference,
+        })
+        .to_string(),
+    )
+}
+
 // === History Config ===
 
 #[derive(Deserialize)]
@@ -1385,6 +3236,33 @@ async fn history_config(
     Json(serde_json::json!({"status": "ok", "max_duration_secs": clamped}))
 }
 
+#[derive(Deserialize)]
+struct HistoryFramesQuery {
+    /// Return frames with a higher `meta.seq` than this
+    since_seq: u64,
+    count: Option<usize>,
+    metric_mask: Option<String>,
+}
+
+/// Frames from the history buffer broadcast after `since_seq`, so clients
+/// that notice a gap in `meta.seq` can backfill it
+async fn history_frames(
+    State(state): State<AppState>,
+    Query(params): Query<HistoryFramesQuery>,
+) -> Json<serde_json::Value> {
+    let history = state.history.read().await;
+    let frames = history.get_frames_since_seq(params.since_seq, params.count.unwrap_or(usize::MAX));
+
+    let metric_mask = params.metric_mask.map(|f| MetricMask::parse(&f));
+    let cm = state.custom_metrics.read().unwrap();
+    let cm_ref = if cm.is_empty() { None } else { Some(&*cm) };
+    Json(serde_json::json!(serialize_frames(
+        frames.into_iter().map(|(i, f)| (i, f.clone())),
+        &metric_mask,
+        cm_ref,
+    )))
+}
+
 // === History Aggregation ===
 
 #[derive(Deserialize)]
@@ -1500,6 +3378,10 @@ async fn start_playback_task(state: AppState) {
         // First tick completes immediately
         interval.tick().await;
         let mut last_send = tokio::time::Instant::now();
+        // Replay time the wall clock has reached, and the frame it expects to
+        // send next; any other frame means the replay was seeked
+        let mut clock = 0.0;
+        let mut expected = None;
 
         loop {
             tokio::select! {
@@ -1532,41 +3414,49 @@ async fn start_playback_task(state: AppState) {
                 last_send = tokio::time::Instant::now();
             }
 
-            // Calculate how many frames are due based on elapsed wall time
             let now = tokio::time::Instant::now();
             let elapsed = (now - last_send).as_secs_f64();
-            let frames_due = (elapsed * tick_rate as f64 * playback_speed)
-                .round()
-                .max(1.0) as usize;
             last_send = now;
 
             let frame = {
                 let mut rs = replay.write().await;
                 match rs.as_mut() {
                     Some(rs) => {
-                        // Skip frames if behind schedule
-                        if frames_due > 1 {
-                            let target = rs.current_frame() + frames_due - 1;
-                            rs.seek(target);
-                        }
-                        let idx = rs.current_frame();
-                        match rs.get_frame(idx) {
-                            Ok(frame) => {
-                                rs.advance();
-                                Some(frame)
-                            }
-                            Err(e) => {
-                                tracing::error!("Failed to read frame {}: {}", idx, e);
-                                rs.advance();
-                                None
-                            }
+                        // The frame due by the clock, skipping frames if behind
+                        // schedule and holding the last one through gaps
+                        let current = rs.current_frame();
+                        clock = if expected == Some(current) {
+                            clock + elapsed * playback_speed
+                        } else {
+                            rs.timeline().time_of(current)
+                        };
+                        let due = rs.timeline().frame_at(clock);
+                        if due < current {
+                            None
+                        } else {
+                            rs.seek(due);
+                            let idx = rs.current_frame();
+                            let frame = match rs.get_frame(idx) {
+                                Ok(frame) => Some(frame),
+                                Err(e) => {
+                                    tracing::error!("Failed to read frame {}: {}", idx, e);
+                                    None
+                                }
+                            };
+                            rs.advance();
+                            expected = Some(rs.current_frame());
+                            frame
                         }
                     }
                     None => break,
                 }
             };
 
-            if let Some(frame) = frame {
+            if let Some(mut frame) = frame {
+                // Recorded long ago; only live frames have a latency
+                frame.meta.acquired_at = None;
+                state.tracks.enrich(&mut frame);
+                state.assign_seq(&mut frame);
                 let _ = tx.send(frame);
             }
         }
@@ -1584,6 +3474,10 @@ async fn persistence_get_config(State(state): State<AppState>) -> Json<serde_jso
         "enabled": config.enabled,
         "frequency_hz": config.frequency_hz,
         "auto_save": config.auto_save,
+        "auto_record": config.auto_record,
+        "laps_only": config.laps_only,
+        "compression": config.compression,
+        "rotation": config.rotation,
         "retention": config.retention,
         "directory": dir.to_string_lossy(),
     }))
@@ -1594,6 +3488,11 @@ struct PersistenceConfigRequest {
     enabled: Option<bool>,
     frequency_hz: Option<u32>,
     auto_save: Option<bool>,
+    auto_record: Option<bool>,
+    laps_only: Option<bool>,
+    compression: Option<crate::compression::Compression>,
+    max_size_mb: Option<Option<u64>>,
+    max_duration_mins: Option<Option<u32>>,
     max_sessions: Option<Option<usize>>,
     max_age_days: Option<Option<u32>>,
 }
@@ -1612,6 +3511,21 @@ async fn persistence_set_config(
     if let Some(auto_save) = req.auto_save {
         config.auto_save = auto_save;
     }
+    if let Some(auto_record) = req.auto_record {
+        config.auto_record = auto_record;
+    }
+    if let Some(laps_only) = req.laps_only {
+        config.laps_only = laps_only;
+    }
+    if let Some(compression) = req.compression {
+        config.compression = compression;
+    }
+    if let Some(max_size_mb) = req.max_size_mb {
+        config.rotation.max_size_mb = max_size_mb;
+    }
+    if let Some(max_duration_mins) = req.max_duration_mins {
+        config.rotation.max_duration_mins = max_duration_mins;
+    }
     if let Some(max_sessions) = req.max_sessions {
         config.retention.max_sessions = max_sessions;
     }
@@ -1622,8 +3536,10 @@ async fn persistence_set_config(
     // Run cleanup after config change
     let retention = config.retention.clone();
     drop(config);
+    let archive = state.archive.clone();
     tokio::task::spawn_blocking(move || {
         crate::persistence::cleanup_old_sessions(&retention);
+        crate::archive::prune(&archive);
     });
 
     let config = state.persistence_config.read().await;
@@ -1632,6 +3548,10 @@ async fn persistence_set_config(
         "enabled": config.enabled,
         "frequency_hz": config.frequency_hz,
         "auto_save": config.auto_save,
+        "auto_record": config.auto_record,
+        "laps_only": config.laps_only,
+        "compression": config.compression,
+        "rotation": config.rotation,
         "retention": config.retention,
     }))
 }
@@ -1747,9 +3667,7 @@ async fn convert_ibt(mut multipart: Multipart) -> Result<impl IntoResponse, (Sta
             let mut encoder = zstd::Encoder::new(sync_write, 3)?;
             for start in (0..total).step_by(batch_size) {
                 let count = batch_size.min(total - start);
-                let samples = ibt.read_samples_range(start, count)?;
-                for sample in &samples {
-                    let frame = ibt.sample_to_frame(sample);
+                for frame in ibt.read_frames_range(start, count)? {
                     let json = serde_json::to_string(&frame)?;
                     writeln!(encoder, "{}", json)?;
                 }
@@ -1850,7 +3768,7 @@ async fn persistence_list_files() -> Json<Vec<serde_json::Value>> {
                 .unwrap_or_default()
                 .to_string_lossy()
                 .to_string();
-            if !name.ends_with(".ost.ndjson.zstd") {
+            if !crate::compression::is_recording(&name) {
                 continue;
             }
             let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
@@ -1889,6 +3807,20 @@ struct LoadFileRequest {
 async fn persistence_load_file(
     State(state): State<AppState>,
     Json(req): Json<LoadFileRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    // Validate filename to prevent path traversal
+    if req.filename.contains('/') || req.filename.contains('\\') || req.filename.contains("..") {
+        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
+    }
+
+    let dir = crate::persistence::telemetry_dir();
+    load_recording(&state, dir.join(&req.filename)).await
+}
+
+/// Open a recorded NDJSON file as the active replay
+async fn load_recording(
+    state: &AppState,
+    path: std::path::PathBuf,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
     {
         let replay = state.replay.read().await;
@@ -1900,19 +3832,12 @@ async fn persistence_load_file(
         }
     }
 
-    // Validate filename to prevent path traversal
-    if req.filename.contains('/') || req.filename.contains('\\') || req.filename.contains("..") {
-        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
-    }
-
-    let dir = crate::persistence::telemetry_dir();
-    let path = dir.join(&req.filename);
     if !path.exists() {
         return Err((StatusCode::NOT_FOUND, "File not found".to_string()));
     }
 
-    let replay_state = tokio::task::spawn_blocking(move || {
-        ReplayState::from_ndjson_zstd(&path).map_err(|e| {
+    let mut replay_state = tokio::task::spawn_blocking(move || {
+        ReplayState::from_ndjson(&path).map_err(|e| {
             (
                 StatusCode::BAD_REQUEST,
                 format!("Failed to load file: {}", e),
@@ -1929,6 +3854,7 @@ async fn persistence_load_file(
 
     let info = replay_state.info();
 
+    replay_state.set_redacted(state.redacting());
     {
         let mut replay = state.replay.write().await;
         *replay = Some(replay_state);
@@ -1943,36 +3869,151 @@ async fn persistence_load_file(
 }
 
 async fn persistence_delete_file(
+    State(state): State<AppState>,
     axum::extract::Path(name): axum::extract::Path<String>,
 ) -> Result<StatusCode, (StatusCode, String)> {
     // Validate filename to prevent path traversal
     if name.contains('/') || name.contains('\\') || name.contains("..") {
         return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
     }
-    if !name.ends_with(".ost.ndjson.zstd") {
+    if !crate::compression::is_recording(&name) {
         return Err((
             StatusCode::BAD_REQUEST,
-            "Only .ost.ndjson.zstd files can be deleted".to_string(),
+            "Only .ost.ndjson recordings can be deleted".to_string(),
         ));
     }
 
-    let dir = crate::persistence::telemetry_dir();
-    let path = dir.join(&name);
+    // The archive lives in the telemetry directory
+    let mut archive = state.archive.write().unwrap();
+    let path = archive.dir().join(&name);
     if !path.exists() {
         return Err((StatusCode::NOT_FOUND, "File not found".to_string()));
     }
 
-    std::fs::remove_file(&path).map_err(|e| {
+    // Drops the archive entry along with the file, so the session is gone
+    // from /api/archive by the time this returns
+    let failed = |e: std::io::Error| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             format!("Failed to delete file: {}", e),
         )
-    })?;
+    };
+    if !archive.remove(&name).map_err(failed)? {
+        std::fs::remove_file(&path).map_err(failed)?;
+    }
 
     tracing::info!("Deleted telemetry file: {}", name);
     Ok(StatusCode::NO_CONTENT)
 }
 
+// === Session Archive Endpoints ===
+
+#[derive(Deserialize)]
+struct ArchiveQuery {
+    game: Option<String>,
+    track: Option<String>,
+    car: Option<String>,
+    tag: Option<String>,
+}
+
+/// GET /api/archive — archived sessions, newest first, optionally filtered
+async fn archive_list(
+    State(state): State<AppState>,
+    Query(query): Query<ArchiveQuery>,
+) -> Json<Vec<ArchiveEntry>> {
+    let archive = state.archive.read().unwrap();
+    let matches = |filter: &Option<String>, value: &str| {
+        filter
+            .as_deref()
+            .is_none_or(|f| f.eq_ignore_ascii_case(value))
+    };
+    Json(
+        archive
+            .entries()
+            .iter()
+            .filter(|e| matches(&query.game, &e.game))
+            .filter(|e| matches(&query.track, &e.track))
+            .filter(|e| matches(&query.car, &e.car))
+            .filter(|e| {
+                query
+                    .tag
+                    .as_deref()
+                    .is_none_or(|tag| e.tags.iter().any(|t| t == tag))
+            })
+            .cloned()
+            .collect(),
+    )
+}
+
+#[derive(Deserialize)]
+struct ArchiveTagsRequest {
+    tags: Vec<String>,
+}
+
+/// PUT /api/archive/:file/tags — replace a session's tags
+async fn archive_set_tags(
+    State(state): State<AppState>,
+    axum::extract::Path(file): axum::extract::Path<String>,
+    Json(req): Json<ArchiveTagsRequest>,
+) -> Result<Json<ArchiveEntry>, (StatusCode, String)> {
+    let mut tags: Vec<String> = req
+        .tags
+        .into_iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    // Keep the first of each, in the order given
+    let mut seen = std::collections::HashSet::new();
+    tags.retain(|t| seen.insert(t.clone()));
+    state
+        .archive
+        .write()
+        .unwrap()
+        .set_tags(&file, tags)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to save archive index: {}", e),
+            )
+        })?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Session not found".to_string()))
+}
+
+/// DELETE /api/archive/:file — delete a session's recording and entry
+async fn archive_delete(
+    State(state): State<AppState>,
+    axum::extract::Path(file): axum::extract::Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let removed = state.archive.write().unwrap().remove(&file).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to delete session: {}", e),
+        )
+    })?;
+    if !removed {
+        return Err((StatusCode::NOT_FOUND, "Session not found".to_string()));
+    }
+    tracing::info!("Deleted archived session: {}", file);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/archive/:file/load — open an archived session as the replay
+async fn archive_load(
+    State(state): State<AppState>,
+    axum::extract::Path(file): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    // Only indexed files, so the name can't point outside the archive
+    let path = {
+        let archive = state.archive.read().unwrap();
+        archive
+            .get(&file)
+            .map(|e| archive.dir().join(&e.file))
+            .ok_or((StatusCode::NOT_FOUND, "Session not found".to_string()))?
+    };
+    load_recording(&state, path).await
+}
+
 // === Session Endpoints (serve mode) ===
 
 /// Check admin credentials for serve mode.
@@ -2157,6 +4198,7 @@ async fn load_session(
         )
     })??;
 
+    start_replay_indexing(&state, &replay_state);
     let info = replay_state.info();
 
     {
@@ -2454,6 +4496,7 @@ async fn serve_session_page(
                 format!("Failed to parse session file: {}", e),
             )
         })?;
+    start_replay_indexing(&state, &replay_state);
 
     {
         let mut replay = state.replay.write().await;
@@ -0,0 +1,345 @@
+//! Session archive index
+//!
+//! Every recording in the telemetry directory gets an entry in `index.json`
+//! (game, track, car, date, best lap, tags), so past sessions can be listed,
+//! tagged and reopened as replays without decompressing each file.
+
+use crate::compression;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use ost_core::model::TelemetryFrame;
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tracing::{info, warn};
+
+/// Name of the index file in the telemetry directory
+pub const INDEX_FILE: &str = "index.json";
+
+/// One archived session
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// File name in the telemetry directory, also the entry's id
+    pub file: String,
+    pub game: String,
+    pub track: String,
+    pub car: String,
+    pub started_at: DateTime<Utc>,
+    /// Timestamp of the last frame; `None` while still recording
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Best lap time in seconds
+    pub best_lap: Option<f32>,
+    pub frame_count: u64,
+    pub size_bytes: u64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl ArchiveEntry {
+    /// Start an entry from the first frame of a recording
+    pub fn from_frame(file: &str, frame: &TelemetryFrame) -> Self {
+        let session = frame.session.as_ref();
+        let mut entry = Self {
+            file: file.to_string(),
+            game: frame.meta.game.clone(),
+            track: session
+                .and_then(|s| s.track_name.clone())
+                .unwrap_or_default(),
+            car: frame
+                .vehicle
+                .as_ref()
+                .and_then(|v| v.car_name.clone())
+                .unwrap_or_default(),
+            started_at: frame.meta.timestamp,
+            ended_at: None,
+            best_lap: None,
+            frame_count: 0,
+            size_bytes: 0,
+            tags: Vec::new(),
+        };
+        entry.record(frame);
+        entry
+    }
+
+    /// Fold a recorded frame into the entry
+    pub fn record(&mut self, frame: &TelemetryFrame) {
+        self.frame_count += 1;
+        let best = frame
+            .timing
+            .as_ref()
+            .and_then(|t| t.best_lap_time)
+            .map(|t| t.0)
+            .filter(|&t| t > 0.0);
+        if let Some(best) = best {
+            self.best_lap = Some(self.best_lap.map_or(best, |b| b.min(best)));
+        }
+    }
+
+    /// Mark the recording as complete
+    pub fn finish(&mut self, last_frame_at: DateTime<Utc>, size_bytes: u64) {
+        self.ended_at = Some(last_frame_at);
+        self.size_bytes = size_bytes;
+    }
+}
+
+/// The index of recordings in one directory
+#[derive(Debug)]
+pub struct Archive {
+    dir: PathBuf,
+    entries: Vec<ArchiveEntry>,
+}
+
+impl Archive {
+    /// Load the index in `dir`, starting empty if there is none yet
+    pub fn open(dir: PathBuf) -> Self {
+        let entries = match std::fs::read_to_string(dir.join(INDEX_FILE)) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Archive: ignoring unreadable {}: {}", INDEX_FILE, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self { dir, entries }
+    }
+
+    /// Directory holding the recordings and the index
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// All entries, newest first
+    pub fn entries(&self) -> &[ArchiveEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, file: &str) -> Option<&ArchiveEntry> {
+        self.entries.iter().find(|e| e.file == file)
+    }
+
+    /// Add or replace the entry for `entry.file`, keeping any tags
+    pub fn upsert(&mut self, mut entry: ArchiveEntry) -> std::io::Result<()> {
+        match self.entries.iter_mut().find(|e| e.file == entry.file) {
+            Some(existing) => {
+                if entry.tags.is_empty() {
+                    entry.tags = std::mem::take(&mut existing.tags);
+                }
+                *existing = entry;
+            }
+            None => self.entries.push(entry),
+        }
+        self.entries
+            .sort_by(|a, b| b.started_at.cmp(&a.started_at).then(b.file.cmp(&a.file)));
+        self.save()
+    }
+
+    /// Replace an entry's tags. Returns the updated entry, `None` if unknown.
+    pub fn set_tags(
+        &mut self,
+        file: &str,
+        tags: Vec<String>,
+    ) -> std::io::Result<Option<ArchiveEntry>> {
+        let Some(entry) = self.entries.iter_mut().find(|e| e.file == file) else {
+            return Ok(None);
+        };
+        entry.tags = tags;
+        let entry = entry.clone();
+        self.save()?;
+        Ok(Some(entry))
+    }
+
+    /// Delete a recording and its entry. Returns false if it wasn't indexed.
+    pub fn remove(&mut self, file: &str) -> std::io::Result<bool> {
+        let Some(index) = self.entries.iter().position(|e| e.file == file) else {
+            return Ok(false);
+        };
+        match std::fs::remove_file(self.dir.join(file)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        self.entries.remove(index);
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Recordings in the directory that have no entry yet
+    pub fn unindexed_files(&self) -> Vec<PathBuf> {
+        let Ok(dir) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        dir.flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                (compression::is_recording(&name) && self.get(&name).is_none())
+                    .then(|| entry.path())
+            })
+            .collect()
+    }
+
+    /// Build an entry by reading a whole recording
+    pub fn index_file(path: &Path) -> Result<ArchiveEntry> {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let size_bytes = std::fs::metadata(path)?.len();
+        let reader = compression::open(path)?;
+
+        let mut entry: Option<ArchiveEntry> = None;
+        let mut last_frame_at = None;
+        for line in reader.lines() {
+            // A recording cut short by a crash ends in a truncated frame
+            let Ok(line) = line else { break };
+            let Ok(frame) = serde_json::from_str::<TelemetryFrame>(&line) else {
+                continue;
+            };
+            last_frame_at = Some(frame.meta.timestamp);
+            match entry.as_mut() {
+                Some(entry) => entry.record(&frame),
+                None => entry = Some(ArchiveEntry::from_frame(&name, &frame)),
+            }
+        }
+
+        let mut entry = entry.ok_or_else(|| anyhow::anyhow!("no frames in {}", name))?;
+        if let Some(at) = last_frame_at {
+            entry.finish(at, size_bytes);
+        }
+        Ok(entry)
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string_pretty(&self.entries)?;
+        // Write then rename so a crash never leaves a half-written index
+        let tmp = self.dir.join(format!("{}.tmp", INDEX_FILE));
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, self.dir.join(INDEX_FILE))
+    }
+}
+
+/// Drop entries whose recording was deleted outside the archive (e.g. by
+/// retention cleanup). Blocking; checks the files without holding the lock.
+pub fn prune(archive: &RwLock<Archive>) {
+    let (dir, files): (PathBuf, Vec<String>) = {
+        let archive = archive.read().unwrap_or_else(|e| e.into_inner());
+        let files = archive.entries.iter().map(|e| e.file.clone()).collect();
+        (archive.dir.clone(), files)
+    };
+    let missing: Vec<String> = files
+        .into_iter()
+        .filter(|file| !dir.join(file).exists())
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+    let mut archive = archive.write().unwrap_or_else(|e| e.into_inner());
+    archive.entries.retain(|e| !missing.contains(&e.file));
+    info!("Archive: dropped {} deleted recordings", missing.len());
+    if let Err(e) = archive.save() {
+        warn!("Archive: failed to save index: {}", e);
+    }
+}
+
+/// Index recordings that aren't in the archive yet, e.g. ones made before the
+/// index existed. Blocking; reads each file in full.
+pub fn index_unindexed(archive: &RwLock<Archive>) {
+    let files = archive
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .unindexed_files();
+    for path in files {
+        match Archive::index_file(&path) {
+            Ok(entry) => {
+                info!("Archive: indexed {}", entry.file);
+                let mut archive = archive.write().unwrap_or_else(|e| e.into_inner());
+                if let Err(e) = archive.upsert(entry) {
+                    warn!("Archive: failed to save index: {}", e);
+                }
+            }
+            Err(e) => warn!("Archive: skipping {}: {}", path.display(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn frame(secs: i64, best_lap: Option<f32>) -> TelemetryFrame {
+        serde_json::from_value(serde_json::json!({
+            "meta": {
+                "timestamp": DateTime::from_timestamp(1_767_225_600 + secs, 0).unwrap(),
+                "game": "iRacing",
+                "tick": secs
+            },
+            "session": {"track_name": "Spa"},
+            "vehicle": {"car_name": "MX-5"},
+            "timing": {"best_lap_time": best_lap}
+        }))
+        .unwrap()
+    }
+
+    fn frame_entry(file: &str) -> ArchiveEntry {
+        ArchiveEntry::from_frame(file, &frame(0, None))
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ost-archive-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_index_existing_recording_and_persist_tags() {
+        let dir = temp_dir("index");
+        let path = dir.join("2026-01-01_00-00-00_Spa_MX-5.ost.ndjson.zstd");
+        let mut encoder = zstd::Encoder::new(std::fs::File::create(&path).unwrap(), 3).unwrap();
+        for (secs, best) in [(0, None), (90, Some(131.2)), (180, Some(130.4))] {
+            writeln!(
+                encoder,
+                "{}",
+                serde_json::to_string(&frame(secs, best)).unwrap()
+            )
+            .unwrap();
+        }
+        encoder.finish().unwrap();
+
+        let archive = RwLock::new(Archive::open(dir.clone()));
+        index_unindexed(&archive);
+        let mut archive = archive.into_inner().unwrap();
+        let entry = &archive.entries()[0];
+        assert_eq!(entry.track, "Spa");
+        assert_eq!(entry.car, "MX-5");
+        assert_eq!(entry.frame_count, 3);
+        assert_eq!(entry.best_lap, Some(130.4));
+        assert!(entry.ended_at.is_some());
+        assert!(archive.unindexed_files().is_empty());
+
+        let file = entry.file.clone();
+        archive.set_tags(&file, vec!["race".to_string()]).unwrap();
+        let reopened = Archive::open(dir.clone());
+        assert_eq!(reopened.get(&file).unwrap().tags, vec!["race"]);
+
+        // Re-indexing keeps tags; deleting removes the file too
+        let mut reopened = reopened;
+        reopened
+            .upsert(Archive::index_file(&path).unwrap())
+            .unwrap();
+        assert_eq!(reopened.get(&file).unwrap().tags, vec!["race"]);
+        assert!(reopened.remove(&file).unwrap());
+        assert!(!path.exists());
+        assert!(Archive::open(dir.clone()).entries().is_empty());
+
+        // Recordings deleted behind the archive's back are pruned
+        reopened.upsert(frame_entry(&file)).unwrap();
+        let archive = RwLock::new(reopened);
+        prune(&archive);
+        assert!(archive.read().unwrap().entries().is_empty());
+        assert!(Archive::open(dir.clone()).entries().is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
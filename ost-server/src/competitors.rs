@@ -0,0 +1,112 @@
+//! Competitor standings tracking
+//!
+//! Backs `/api/competitors/stream`, which sends the full competitor list at a
+//! low rate and, in between, only the cars whose position changed.
+
+use ost_core::model::CompetitorData;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A car's change of overall position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PositionChange {
+    pub car_index: u32,
+    /// Previous position, `None` if the car wasn't classified before
+    pub from: Option<u32>,
+    /// New position, `None` if the car is no longer classified
+    pub to: Option<u32>,
+}
+
+/// Remembers the last overall position of each car so only changes are reported
+#[derive(Debug, Default)]
+pub struct StandingsTracker {
+    positions: HashMap<u32, u32>,
+}
+
+impl StandingsTracker {
+    /// Record the competitors' current positions and return the cars whose
+    /// position changed, ordered by new position (cars that dropped out last).
+    /// Position 0 is treated as unclassified, as iRacing reports it.
+    pub fn update(&mut self, competitors: &[CompetitorData]) -> Vec<PositionChange> {
+        let current: HashMap<u32, u32> = competitors
+            .iter()
+            .filter_map(|c| Some((c.car_index, c.position.filter(|&p| p > 0)?)))
+            .collect();
+
+        let mut changes: Vec<PositionChange> = current
+            .iter()
+            .filter(|(car, pos)| self.positions.get(car) != Some(pos))
+            .map(|(&car_index, &to)| PositionChange {
+                car_index,
+                from: self.positions.get(&car_index).copied(),
+                to: Some(to),
+            })
+            .collect();
+        changes.extend(
+            self.positions
+                .iter()
+                .filter(|(car, _)| !current.contains_key(car))
+                .map(|(&car_index, &from)| PositionChange {
+                    car_index,
+                    from: Some(from),
+                    to: None,
+                }),
+        );
+        changes.sort_by_key(|c| (c.to.unwrap_or(u32::MAX), c.car_index));
+
+        self.positions = current;
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn car(car_index: u32, position: u32) -> CompetitorData {
+        serde_json::from_value(serde_json::json!({
+            "car_index": car_index,
+            "position": position,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_standings_tracker_reports_only_changes() {
+        let mut tracker = StandingsTracker::default();
+        let initial = tracker.update(&[car(0, 1), car(1, 2), car(2, 3)]);
+        assert_eq!(initial.len(), 3);
+        assert!(tracker
+            .update(&[car(0, 1), car(1, 2), car(2, 3)])
+            .is_empty());
+
+        // Car 2 overtakes car 1
+        let changes = tracker.update(&[car(0, 1), car(1, 3), car(2, 2)]);
+        assert_eq!(
+            changes,
+            vec![
+                PositionChange {
+                    car_index: 2,
+                    from: Some(3),
+                    to: Some(2),
+                },
+                PositionChange {
+                    car_index: 1,
+                    from: Some(2),
+                    to: Some(3),
+                },
+            ]
+        );
+
+        // Car 0 disconnects (position 0)
+        let changes = tracker.update(&[car(0, 0), car(1, 3), car(2, 2)]);
+        assert_eq!(
+            changes,
+            vec![PositionChange {
+                car_index: 0,
+                from: Some(1),
+                to: None,
+            }]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Recording file compression
+//!
+//! Recordings are NDJSON, written uncompressed, gzip'd or (by default)
+//! zstd-compressed as a stream, one file at a time. The extension records
+//! which, but readers go by the file's magic bytes so renamed or uploaded
+//! files load too.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Extensions of recordings, one per [`Compression`]
+pub const RECORDING_EXTENSIONS: [&str; 3] = [".ost.ndjson.zstd", ".ost.ndjson.gz", ".ost.ndjson"];
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// How a recording is compressed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,
+    Gzip,
+    #[default]
+    Zstd,
+}
+
+impl Compression {
+    /// File extension for recordings, including the `.ost.ndjson` part
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Zstd => RECORDING_EXTENSIONS[0],
+            Self::Gzip => RECORDING_EXTENSIONS[1],
+            Self::None => RECORDING_EXTENSIONS[2],
+        }
+    }
+
+    /// Identify compression from the first bytes of a file
+    pub fn detect(header: &[u8]) -> Self {
+        if header.starts_with(&ZSTD_MAGIC) {
+            Self::Zstd
+        } else if header.starts_with(&GZIP_MAGIC) {
+            Self::Gzip
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// Whether `name` looks like a recording, compressed or not
+pub fn is_recording(name: &str) -> bool {
+    RECORDING_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+}
+
+/// Streaming writer for one recording
+pub enum Encoder {
+    None(BufWriter<File>),
+    Gzip(flate2::write::GzEncoder<File>),
+    Zstd(zstd::Encoder<'static, File>),
+}
+
+impl Encoder {
+    pub fn new(file: File, compression: Compression) -> io::Result<Self> {
+        Ok(match compression {
+            Compression::None => Self::None(BufWriter::new(file)),
+            Compression::Gzip => Self::Gzip(flate2::write::GzEncoder::new(
+                file,
+                flate2::Compression::default(),
+            )),
+            // Level 3 = good balance
+            Compression::Zstd => Self::Zstd(zstd::Encoder::new(file, 3)?),
+        })
+    }
+
+    /// The underlying file, holding whatever has been flushed so far
+    pub fn file(&self) -> &File {
+        match self {
+            Self::None(w) => w.get_ref(),
+            Self::Gzip(w) => w.get_ref(),
+            Self::Zstd(w) => w.get_ref(),
+        }
+    }
+
+    /// Write the end of the compressed stream
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            Self::None(mut w) => w.flush(),
+            Self::Gzip(w) => w.finish().map(drop),
+            Self::Zstd(w) => w.finish().map(drop),
+        }
+    }
+}
+
+impl Write for Encoder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::None(w) => w.write(buf),
+            Self::Gzip(w) => w.write(buf),
+            Self::Zstd(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::None(w) => w.flush(),
+            Self::Gzip(w) => w.flush(),
+            Self::Zstd(w) => w.flush(),
+        }
+    }
+}
+
+/// Open a recording for reading line by line, decompressing as needed
+pub fn open(path: &Path) -> io::Result<Box<dyn BufRead + Send>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(match Compression::detect(reader.fill_buf()?) {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_each_compression() {
+        let dir = std::env::temp_dir().join(format!("ost-compression-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for compression in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            let path = dir.join(format!("test{}", compression.extension()));
+            assert!(is_recording(&path.to_string_lossy()));
+            let mut encoder = Encoder::new(File::create(&path).unwrap(), compression).unwrap();
+            writeln!(encoder, "{{\"a\":1}}").unwrap();
+            writeln!(encoder, "{{\"a\":2}}").unwrap();
+            encoder.finish().unwrap();
+
+            let header = std::fs::read(&path).unwrap();
+            assert_eq!(Compression::detect(&header), compression);
+            let lines: Vec<String> = open(&path).unwrap().lines().map(Result::unwrap).collect();
+            assert_eq!(lines, ["{\"a\":1}", "{\"a\":2}"], "{:?}", compression);
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,397 @@
+//! Server config file
+//!
+//! Read at startup from `--config <path>`, `OST_CONFIG`, or `config.toml` in
+//! the OpenSimTelemetry data directory if it exists. Everything is optional:
+//!
+//! ```toml
+//! [auth]
+//! tokens = ["engineer-token", "overlay-token"]
+//! # "mutations" (default) only guards routes that change state, such as
+//! # sinks, replay control and adapter toggles; "all" also guards streams
+//! scope = "mutations"
+//!
+//! # Serve HTTPS instead of HTTP. Relative paths are resolved against the
+//! # directory of the config file.
+//! [tls]
+//! cert = "cert.pem"
+//! key = "key.pem"
+//!
+//! [discovery]
+//! # Advertise as _ost._tcp.local (default true)
+//! mdns = true
+//! # Broadcast a UDP beacon to port 9199 every 2 seconds (default true)
+//! beacon = true
+//!
+//! [recording]
+//! # Record whenever a session is in progress, without pressing record
+//! auto_record = true
+//! # Keep only complete, valid laps (no out-laps, off-track laps or garage time)
+//! laps_only = false
+//! # "zstd" (default), "gzip" or "none"
+//! compression = "zstd"
+//! # Continue long sessions in a new file (both optional)
+//! max_size_mb = 500
+//! max_duration_mins = 60
+//!
+//! # Post lap records, incidents and session results to Discord
+//! [discord]
+//! webhook_url = "https://discord.com/api/webhooks/..."
+//! # "lap_record", "incident" and/or "session_result" (default: all)
+//! events = ["lap_record", "session_result"]
+//!
+//! # When several games are detected at once, start the first of these, then
+//! # the rest in their built-in order
+//! [adapters]
+//! priority = ["acc", "iracing"]
+//!
+//! # Follow another server's telemetry as the "remote" adapter (see `remote`)
+//! [remote]
+//! url = "http://rig-1.local:9100"
+//! token = "rig-token"
+//!
+//! # Limits on uploaded replays (see `replay_store`)
+//! [replay_storage]
+//! max_upload_mb = 1024
+//! quota_mb = 4096
+//!
+//! # Log a real car from an OBD-II dongle and/or GPS (see `hardware`)
+//! [hardware]
+//! obd = { port = "/dev/rfcomm0" }
+//! gps = { port = "/dev/ttyACM0", baud = 9600 }
+//!
+//! # Add or replace track database entries (see `ost_core::tracks`)
+//! [[tracks]]
+//! id = "iracing:spielberg gp"
+//! sectors = [0.0, 0.35, 0.7]
+//! corners = [{ name = "Niki Lauda Kurve", start = 0.05, end = 0.09 }]
+//! pit_lane = { entry = 0.96, exit = 0.08 }
+//!
+//! # Transforms applied to every live frame, in order (see `transforms`)
+//! [[transforms]]
+//! type = "drop"
+//! sections = ["competitors"]
+//! [[transforms]]
+//! type = "ema"
+//! channels = ["vehicle.steering_torque"]
+//! alpha = 0.3
+//! ```
+
+use crate::compression::Compression;
+use crate::discord::DiscordConfig;
+use crate::hardware::HardwareConfig;
+use crate::persistence::RotationConfig;
+use crate::remote::RemoteConfig;
+use crate::replay_store::ReplayStorageConfig;
+use crate::transforms::TransformConfig;
+use anyhow::{Context, Result};
+use axum::http::Method;
+use ost_core::tracks::Track;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Contents of the config file
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub discovery: DiscoveryConfig,
+    #[serde(default)]
+    pub recording: Option<RecordingConfig>,
+    #[serde(default)]
+    pub discord: Option<DiscordConfig>,
+    #[serde(default)]
+    pub adapters: AdaptersConfig,
+    #[serde(default)]
+    pub remote: Option<RemoteConfig>,
+    #[serde(default)]
+    pub hardware: Option<HardwareConfig>,
+    #[serde(default)]
+    pub replay_storage: ReplayStorageConfig,
+    #[serde(default)]
+    pub tracks: Vec<Track>,
+    #[serde(default)]
+    pub transforms: Vec<TransformConfig>,
+}
+
+impl ServerConfig {
+    /// Parse a config file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading config {}", path.display()))?;
+        let mut config: Self =
+            toml::from_str(&text).with_context(|| format!("parsing config {}", path.display()))?;
+        for transform in &config.transforms {
+            transform
+                .check()
+                .with_context(|| format!("in config {}", path.display()))?;
+        }
+        for track in &config.tracks {
+            track
+                .check()
+                .with_context(|| format!("in config {}", path.display()))?;
+        }
+        if let Some(remote) = &config.remote {
+            remote
+                .check()
+                .with_context(|| format!("in config {}", path.display()))?;
+        }
+        if let Some(hardware) = &config.hardware {
+            hardware
+                .check()
+                .with_context(|| format!("in config {}", path.display()))?;
+        }
+        config
+            .replay_storage
+            .check()
+            .with_context(|| format!("in config {}", path.display()))?;
+        if let (Some(tls), Some(dir)) = (config.tls.as_mut(), path.parent()) {
+            tls.cert = dir.join(&tls.cert);
+            tls.key = dir.join(&tls.key);
+        }
+        Ok(config)
+    }
+
+    /// `config.toml` in the OpenSimTelemetry data directory
+    pub fn default_path() -> PathBuf {
+        data_dir().join("config.toml")
+    }
+}
+
+/// The OpenSimTelemetry data directory
+pub fn data_dir() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        let base = dirs::document_dir()
+            .unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")));
+        base.join("OpenSimTelemetry")
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let base = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        base.join(".opensimtelemetry")
+    }
+}
+
+/// Certificate chain and private key for HTTPS, both PEM encoded
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// How the server makes itself known on the LAN
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiscoveryConfig {
+    /// Advertise over mDNS/Bonjour
+    #[serde(default = "default_true")]
+    pub mdns: bool,
+    /// Broadcast a UDP discovery beacon, for networks that block mDNS
+    #[serde(default = "default_true")]
+    pub beacon: bool,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            mdns: true,
+            beacon: true,
+        }
+    }
+}
+
+/// Which adapter starts when several games are detected at once
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdaptersConfig {
+    /// Adapter keys tried first, in order
+    #[serde(default)]
+    pub priority: Vec<String>,
+}
+
+/// Startup recording settings, applied to the persistence config
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecordingConfig {
+    #[serde(default)]
+    pub auto_record: bool,
+    #[serde(default)]
+    pub laps_only: bool,
+    #[serde(default)]
+    pub compression: Compression,
+    pub max_size_mb: Option<u64>,
+    pub max_duration_mins: Option<u32>,
+}
+
+impl RecordingConfig {
+    pub fn rotation(&self) -> RotationConfig {
+        RotationConfig {
+            max_size_mb: self.max_size_mb,
+            max_duration_mins: self.max_duration_mins,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Which requests need an API token
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthScope {
+    /// Only requests that change state (anything but GET/HEAD/OPTIONS)
+    #[default]
+    Mutations,
+    /// Every request, streams and the web UI included
+    All,
+}
+
+/// API token authentication. Disabled while `tokens` is empty.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub tokens: Vec<String>,
+    #[serde(default)]
+    pub scope: AuthScope,
+}
+
+impl AuthConfig {
+    /// The `OST_AUTH_TOKEN` env var, which guards every route
+    pub fn from_env() -> Self {
+        Self {
+            tokens: std::env::var("OST_AUTH_TOKEN")
+                .ok()
+                .filter(|s| !s.is_empty())
+                .into_iter()
+                .collect(),
+            scope: AuthScope::All,
+        }
+    }
+
+    /// Whether a request with this method must present a token
+    pub fn requires_token(&self, method: &Method) -> bool {
+        if self.tokens.is_empty() {
+            return false;
+        }
+        match self.scope {
+            AuthScope::All => true,
+            AuthScope::Mutations => {
+                !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+            }
+        }
+    }
+
+    /// Whether `token` is one of the configured tokens
+    pub fn accepts(&self, token: &str) -> bool {
+        self.tokens.iter().any(|t| t == token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_auth_config() {
+        let config: ServerConfig = toml::from_str(
+            r#"
+            [auth]
+            tokens = ["a", "b"]
+            "#,
+        )
+        .unwrap();
+        assert!(config.auth.accepts("b"));
+        assert!(!config.auth.accepts("c"));
+        assert!(config.auth.requires_token(&Method::POST));
+        assert!(config.auth.requires_token(&Method::DELETE));
+        assert!(!config.auth.requires_token(&Method::GET));
+
+        let config: ServerConfig =
+            toml::from_str("[auth]\ntokens = [\"a\"]\nscope = \"all\"").unwrap();
+        assert!(config.auth.requires_token(&Method::GET));
+
+        // No tokens, no auth
+        let config: ServerConfig = toml::from_str("").unwrap();
+        assert!(!config.auth.requires_token(&Method::POST));
+        assert!(toml::from_str::<ServerConfig>("[auth]\ntoken = \"a\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_recording_config() {
+        let config: ServerConfig =
+            toml::from_str("[recording]\nauto_record = true\nmax_duration_mins = 60").unwrap();
+        let recording = config.recording.unwrap();
+        assert!(recording.auto_record);
+        assert_eq!(recording.rotation().max_duration_mins, Some(60));
+        assert_eq!(recording.rotation().max_size_mb, None);
+    }
+
+    #[test]
+    fn test_parse_adapters_config() {
+        let config: ServerConfig = toml::from_str("").unwrap();
+        assert!(config.adapters.priority.is_empty());
+        let config: ServerConfig =
+            toml::from_str("[adapters]\npriority = [\"acc\", \"iracing\"]").unwrap();
+        assert_eq!(config.adapters.priority, ["acc", "iracing"]);
+    }
+
+    #[test]
+    fn test_parse_replay_storage_config() {
+        let config: ServerConfig = toml::from_str("").unwrap();
+        assert_eq!(config.replay_storage.quota_mb, 4096);
+        let config: ServerConfig =
+            toml::from_str("[replay_storage]\nquota_mb = 500\nmax_upload_mb = 200").unwrap();
+        assert_eq!(config.replay_storage.max_files, 8);
+        assert!(config.replay_storage.check().is_ok());
+        let config: ServerConfig = toml::from_str("[replay_storage]\nquota_mb = 500").unwrap();
+        assert!(config.replay_storage.check().is_err());
+    }
+
+    #[test]
+    fn test_parse_tracks_config() {
+        let config: ServerConfig = toml::from_str(
+            r#"
+            [[tracks]]
+            id = "ac:magione"
+            sectors = [0.0, 0.5]
+            corners = [{ name = "Curva 1", start = 0.1, end = 0.15 }]
+            pit_lane = { entry = 0.95, exit = 0.05 }
+            "#,
+        )
+        .unwrap();
+        let track = &config.tracks[0];
+        assert_eq!(track.corner_at(0.12).unwrap().name, "Curva 1");
+        assert_eq!(track.pit_lane.unwrap().exit, 0.05);
+        assert!(toml::from_str::<ServerConfig>(
+            "[[tracks]]
+id = \"x\"
+turns = []"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_tls_paths_relative_to_config_file() {
+        let dir = std::env::temp_dir().join(format!("ost-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        std::fs::write(
+            &path,
+            "[tls]\ncert = \"cert.pem\"\nkey = \"/etc/ost/key.pem\"\n",
+        )
+        .unwrap();
+
+        let tls = ServerConfig::load(&path).unwrap().tls.unwrap();
+        assert_eq!(tls.cert, dir.join("cert.pem"));
+        assert_eq!(tls.key, PathBuf::from("/etc/ost/key.pem"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
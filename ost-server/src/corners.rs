@@ -0,0 +1,293 @@
+//! Corner detection
+//!
+//! [`detect`] finds the corners of a reference lap from sustained lateral
+//! acceleration: `motion.g_force.x`, or yaw rate times speed (the path's
+//! curvature) for sims that don't report it. Corners are placed by lap
+//! distance, so [`measure`] can take the same corner on any other lap and
+//! the two can be compared corner by corner instead of only by delta.
+
+use ost_core::channels;
+use ost_core::columns::FrameColumns;
+use serde::Serialize;
+
+/// Channels [`detect`] and [`measure`] read
+const CHANNELS: [&str; 5] = [
+    "motion.g_force.x",
+    "motion.yaw_rate",
+    "vehicle.speed",
+    "vehicle.gear",
+    "timing.lap_distance_pct",
+];
+
+/// Lateral acceleration that counts as cornering, in G
+const CORNERING_G: f32 = 0.4;
+
+/// Lateral acceleration is averaged over this long first, to ride out
+/// kerbs and corrections
+const SMOOTHING_SECS: f64 = 0.5;
+
+/// Cornering the same way after a shorter break than this is the same corner
+const MERGE_SECS: f64 = 0.5;
+
+/// Shorter cornering than this is a kink, not a corner
+const MIN_CORNER_SECS: f64 = 1.0;
+
+const GRAVITY: f32 = 9.80665;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+/// A corner of the reference lap, with its metrics on that lap
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Corner {
+    /// From 1, in lap order
+    pub number: usize,
+    pub direction: Direction,
+    /// Lap distance (0–1) where cornering starts
+    pub start_pct: f32,
+    /// Lap distance (0–1) of the lowest speed
+    pub apex_pct: f32,
+    /// Lap distance (0–1) where cornering ends
+    pub end_pct: f32,
+    #[serde(flatten)]
+    pub metrics: CornerMetrics,
+}
+
+/// How a lap took a corner
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct CornerMetrics {
+    /// m/s at the start of the corner
+    pub entry_speed: f32,
+    /// Lowest m/s through the corner
+    pub min_speed: f32,
+    /// m/s at the end of the corner
+    pub exit_speed: f32,
+    /// Gear at the lowest speed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gear: Option<i8>,
+}
+
+/// Empty columns of the channels [`detect`] and [`measure`] read
+pub fn columns() -> FrameColumns {
+    FrameColumns::with_channels(CHANNELS.iter().filter_map(|path| channels::find(path)))
+}
+
+/// The corners of the lap in `columns`, sampled at `sample_rate_hz`
+pub fn detect(columns: &FrameColumns, sample_rate_hz: f64) -> Vec<Corner> {
+    let (Some(pct), Some(speed)) = (
+        columns.column("timing.lap_distance_pct"),
+        columns.column("vehicle.speed"),
+    ) else {
+        return Vec::new();
+    };
+    let frames = |secs: f64| ((secs * sample_rate_hz).round() as usize).max(1);
+    let lateral = smooth(&lateral_g(columns), frames(SMOOTHING_SECS));
+
+    // Spans of frames cornering one way, with short breaks bridged
+    let mut spans: Vec<(usize, usize, Direction)> = Vec::new();
+    for (i, &g) in lateral.iter().enumerate() {
+        if g.is_nan() || g.abs() < CORNERING_G {
+            continue;
+        }
+        let direction = if g > 0.0 {
+            Direction::Right
+        } else {
+            Direction::Left
+        };
+        match spans.last_mut() {
+            Some((_, end, d)) if *d == direction && i - *end <= frames(MERGE_SECS) => *end = i,
+            _ => spans.push((i, i, direction)),
+        }
+    }
+
+    spans
+        .into_iter()
+        .filter(|(start, end, _)| end - start + 1 >= frames(MIN_CORNER_SECS))
+        .filter_map(|(start, end, direction)| {
+            let (start_pct, end_pct) = (pct[start], pct[end]);
+            let apex = (start..=end)
+                .filter(|&i| !speed[i].is_nan())
+                .min_by(|&a, &b| speed[a].total_cmp(&speed[b]))?;
+            let corner = Corner {
+                number: 0,
+                direction,
+                start_pct,
+                apex_pct: pct[apex],
+                end_pct,
+                metrics: measure_frames(columns, start..=end)?,
+            };
+            (!start_pct.is_nan() && !end_pct.is_nan() && !corner.apex_pct.is_nan())
+                .then_some(corner)
+        })
+        .enumerate()
+        .map(|(i, corner)| Corner {
+            number: i + 1,
+            ..corner
+        })
+        .collect()
+}
+
+/// How the lap in `columns` took `corner`, from its frames between the
+/// corner's start and end by lap distance. `None` if the lap has none there.
+pub fn measure(columns: &FrameColumns, corner: &Corner) -> Option<CornerMetrics> {
+    let pct = columns.column("timing.lap_distance_pct")?;
+    // A corner may span the start/finish line
+    let within = |p: f32| {
+        if corner.start_pct <= corner.end_pct {
+            (corner.start_pct..=corner.end_pct).contains(&p)
+        } else {
+            p >= corner.start_pct || p <= corner.end_pct
+        }
+    };
+    let first = pct.iter().position(|&p| within(p))?;
+    let last = pct.iter().rposition(|&p| within(p))?;
+    measure_frames(columns, first..=last)
+}
+
+fn measure_frames(
+    columns: &FrameColumns,
+    frames: std::ops::RangeInclusive<usize>,
+) -> Option<CornerMetrics> {
+    let speed = columns.column("vehicle.speed")?;
+    let known: Vec<usize> = frames.filter(|&i| !speed[i].is_nan()).collect();
+    let apex = *known
+        .iter()
+        .min_by(|&&a, &&b| speed[a].total_cmp(&speed[b]))?;
+    let gear = columns
+        .column("vehicle.gear")
+        .map(|gear| gear[apex])
+        .filter(|g| !g.is_nan())
+        .map(|g| g as i8);
+    Some(CornerMetrics {
+        entry_speed: speed[*known.first()?],
+        min_speed: speed[apex],
+        exit_speed: speed[*known.last()?],
+        gear,
+    })
+}
+
+/// Lateral G of each frame, positive turning right; `NaN` where unknown
+fn lateral_g(columns: &FrameColumns) -> Vec<f32> {
+    let g_force = columns.column("motion.g_force.x");
+    let yaw_rate = columns.column("motion.yaw_rate");
+    let speed = columns.column("vehicle.speed");
+    (0..columns.len())
+        .map(|i| {
+            let measured = g_force.map_or(f32::NAN, |g| g[i]);
+            if !measured.is_nan() {
+                return measured;
+            }
+            match (yaw_rate, speed) {
+                (Some(yaw_rate), Some(speed)) => yaw_rate[i].to_radians() * speed[i] / GRAVITY,
+                _ => f32::NAN,
+            }
+        })
+        .collect()
+}
+
+/// Centred moving average over `window` frames, skipping `NaN`s
+fn smooth(values: &[f32], window: usize) -> Vec<f32> {
+    let half = window / 2;
+    (0..values.len())
+        .map(|i| {
+            let around = &values[i.saturating_sub(half)..(i + half + 1).min(values.len())];
+            let (sum, n) = around
+                .iter()
+                .filter(|v| !v.is_nan())
+                .fold((0.0, 0), |(sum, n), v| (sum + v, n + 1));
+            if n > 0 {
+                sum / n as f32
+            } else {
+                f32::NAN
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ost_core::model::{MotionData, TelemetryFrame, TimingData, VehicleData};
+    use ost_core::units::{DegreesPerSecond, MetersPerSecond, Percentage};
+
+    /// A lap at 10 Hz: a right-hander on 20–30% (with a brief wobble), a
+    /// kink at 50%, and a left-hander on 70–80%, taken in second gear at
+    /// 20 m/s. Only yaw rate, no lateral G.
+    fn lap() -> FrameColumns {
+        let mut columns = columns();
+        for i in 0..200 {
+            let pct = i as f32 / 200.0;
+            let yaw_rate = match i {
+                40..=59 if i != 50 => 60.0,
+                100..=102 => 60.0,
+                140..=159 => -60.0,
+                _ => 0.0,
+            };
+            let corner = (40..60).contains(&i) || (140..160).contains(&i);
+            let mut frame = TelemetryFrame::new("test");
+            frame.motion = Some(MotionData {
+                yaw_rate: Some(DegreesPerSecond(yaw_rate)),
+                ..Default::default()
+            });
+            frame.vehicle = Some(VehicleData {
+                speed: Some(MetersPerSecond(if corner { 20.0 } else { 40.0 })),
+                gear: Some(if corner { 2 } else { 4 }),
+                ..Default::default()
+            });
+            frame.timing = Some(TimingData {
+                lap_distance_pct: Some(Percentage(pct)),
+                ..Default::default()
+            });
+            columns.push(&frame);
+        }
+        columns
+    }
+
+    #[test]
+    fn test_detect() {
+        let corners = detect(&lap(), 10.0);
+        let found: Vec<_> = corners
+            .iter()
+            .map(|c| (c.number, c.direction, c.start_pct, c.end_pct))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (1, Direction::Right, 0.19, 0.305),
+                (2, Direction::Left, 0.69, 0.805),
+            ]
+        );
+        let right = &corners[0];
+        assert!((0.2..=0.3).contains(&right.apex_pct));
+        assert_eq!(right.metrics.min_speed, 20.0);
+        assert_eq!(right.metrics.gear, Some(2));
+        // Smoothing stretches the corner a little on to the straights
+        assert_eq!(right.metrics.entry_speed, 40.0);
+    }
+
+    #[test]
+    fn test_measure_other_lap() {
+        let reference = detect(&lap(), 10.0);
+        let mut other = columns();
+        for i in 0..100 {
+            let mut frame = TelemetryFrame::new("test");
+            frame.vehicle = Some(VehicleData {
+                speed: Some(MetersPerSecond(50.0 - i as f32 / 10.0)),
+                ..Default::default()
+            });
+            frame.timing = Some(TimingData {
+                lap_distance_pct: Some(Percentage(i as f32 / 100.0)),
+                ..Default::default()
+            });
+            other.push(&frame);
+        }
+        let metrics = measure(&other, &reference[1]).unwrap();
+        assert_eq!(metrics.gear, None);
+        assert!(metrics.entry_speed > metrics.exit_speed);
+        assert_eq!(metrics.min_speed, metrics.exit_speed);
+    }
+}
@@ -0,0 +1,213 @@
+//! Compact binary packets for microcontroller dashboards
+//!
+//! A fixed 40-byte little-endian layout an Arduino or ESP32 can `memcpy` into
+//! a packed struct, sent by sinks with `"format": "dash"`. Values the frame
+//! doesn't have are 0. Units are fixed, so `units` and `metric_mask` don't
+//! apply.
+//!
+//! | Offset | Type  | Field                                             |
+//! |--------|-------|---------------------------------------------------|
+//! | 0      | u8[2] | magic `"OD"`                                      |
+//! | 2      | u8    | layout version (1)                                |
+//! | 3      | i8    | gear (-1 reverse, 0 neutral)                      |
+//! | 4      | u16   | sequence number (low 16 bits of `meta.seq`)       |
+//! | 6      | u16   | RPM                                               |
+//! | 8      | u16   | max RPM                                           |
+//! | 10     | u16   | speed, 0.1 km/h                                   |
+//! | 12     | u8    | throttle, 0-255                                   |
+//! | 13     | u8    | brake, 0-255                                      |
+//! | 14     | u8    | shift light, 0-255                                |
+//! | 15     | u8    | race position                                     |
+//! | 16     | u16   | flag bits, see [`flag_bits`]                      |
+//! | 18     | u16   | lap number                                        |
+//! | 20     | u32   | current lap time, ms                              |
+//! | 24     | u32   | last lap time, ms                                 |
+//! | 28     | u32   | best lap time, ms                                 |
+//! | 32     | i16   | delta to best lap, ms (clamped to ±32767)         |
+//! | 34     | u16   | fuel, 0.1 L                                       |
+//! | 36     | u8    | fuel, percent of capacity                         |
+//! | 37     | u8    | water temperature, °C                             |
+//! | 38     | u16   | CRC-16/CCITT-FALSE of bytes 0-37                  |
+
+use ost_core::model::TelemetryFrame;
+
+/// Size of a packet in bytes
+pub const PACKET_LEN: usize = 40;
+
+/// Leading bytes of every packet, for finding packet boundaries in a stream
+pub const MAGIC: [u8; 2] = *b"OD";
+
+/// Bumped whenever the layout changes
+pub const VERSION: u8 = 1;
+
+/// Bits of the flags field
+pub mod flag_bits {
+    pub const GREEN: u16 = 1 << 0;
+    pub const YELLOW: u16 = 1 << 1;
+    pub const BLUE: u16 = 1 << 2;
+    pub const WHITE: u16 = 1 << 3;
+    pub const CHECKERED: u16 = 1 << 4;
+    pub const BLACK: u16 = 1 << 5;
+    pub const RED: u16 = 1 << 6;
+    pub const PIT_LIMITER: u16 = 1 << 7;
+    pub const ON_PIT_ROAD: u16 = 1 << 8;
+    pub const REV_LIMITER: u16 = 1 << 9;
+    pub const ABS_ACTIVE: u16 = 1 << 10;
+}
+
+/// The frame as a dashboard packet
+pub fn encode(frame: &TelemetryFrame) -> [u8; PACKET_LEN] {
+    let mut packet = [0u8; PACKET_LEN];
+    packet[0..2].copy_from_slice(&MAGIC);
+    packet[2] = VERSION;
+    let seq = frame.meta.seq.unwrap_or(0) as u16;
+    packet[4..6].copy_from_slice(&seq.to_le_bytes());
+
+    let mut flags = 0u16;
+    let mut set = |bit: u16, on: bool| {
+        if on {
+            flags |= bit;
+        }
+    };
+
+    if let Some(v) = &frame.vehicle {
+        packet[3] = v.gear.unwrap_or(0) as u8;
+        put_u16(&mut packet, 6, v.rpm.map(|r| r.0));
+        put_u16(&mut packet, 8, v.max_rpm.map(|r| r.0));
+        put_u16(&mut packet, 10, v.speed.map(|s| s.0 * 36.0));
+        packet[12] = byte(v.throttle.map(|p| p.0 * 255.0));
+        packet[13] = byte(v.brake.map(|p| p.0 * 255.0));
+        packet[14] = byte(v.shift_indicator.map(|p| p.0 * 255.0));
+    }
+    if let Some(t) = &frame.timing {
+        packet[15] = byte(t.race_position.map(|p| p as f32));
+        put_u16(&mut packet, 18, t.lap_number.map(|l| l as f32));
+        put_u32(&mut packet, 20, t.current_lap_time.map(|s| s.0));
+        put_u32(&mut packet, 24, t.last_lap_time.map(|s| s.0));
+        put_u32(&mut packet, 28, t.best_lap_time.map(|s| s.0));
+        let delta = t.delta_best.map_or(0.0, |s| (s.0 * 1000.0).round());
+        let delta = delta.clamp(i16::MIN as f32 + 1.0, i16::MAX as f32) as i16;
+        packet[32..34].copy_from_slice(&delta.to_le_bytes());
+    }
+    if let Some(e) = &frame.engine {
+        put_u16(&mut packet, 34, e.fuel_level.map(|l| l.0 * 10.0));
+        packet[36] = byte(e.fuel_level_pct.map(|p| p.0 * 100.0));
+        packet[37] = byte(e.water_temp.map(|t| t.0));
+        if let Some(w) = &e.warnings {
+            set(flag_bits::PIT_LIMITER, w.pit_speed_limiter);
+            set(flag_bits::REV_LIMITER, w.rev_limiter);
+        }
+    }
+    if let Some(f) = frame.session.as_ref().and_then(|s| s.flags.as_ref()) {
+        set(flag_bits::GREEN, f.green);
+        set(flag_bits::YELLOW, f.yellow || f.caution);
+        set(flag_bits::BLUE, f.blue);
+        set(flag_bits::WHITE, f.white);
+        set(flag_bits::CHECKERED, f.checkered);
+        set(flag_bits::BLACK, f.black);
+        set(flag_bits::RED, f.red);
+    }
+    if let Some(p) = &frame.pit {
+        set(flag_bits::ON_PIT_ROAD, p.on_pit_road.unwrap_or(false));
+    }
+    if let Some(e) = &frame.electronics {
+        set(flag_bits::ABS_ACTIVE, e.abs_active.unwrap_or(false));
+    }
+    packet[16..18].copy_from_slice(&flags.to_le_bytes());
+
+    let crc = crc16(&packet[..PACKET_LEN - 2]);
+    packet[PACKET_LEN - 2..].copy_from_slice(&crc.to_le_bytes());
+    packet
+}
+
+/// CRC-16/CCITT-FALSE (polynomial 0x1021, initial value 0xFFFF), the
+/// variant most embedded CRC libraries default to
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for &b in data {
+        crc ^= (b as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Saturating conversions: out-of-range values pin to the ends of the range
+fn byte(value: Option<f32>) -> u8 {
+    value.map_or(0, |v| v.round() as u8)
+}
+
+fn put_u16(packet: &mut [u8], offset: usize, value: Option<f32>) {
+    let value = value.map_or(0, |v| v.round() as u16);
+    packet[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Seconds as milliseconds
+fn put_u32(packet: &mut [u8], offset: usize, seconds: Option<f32>) {
+    let value = seconds.map_or(0, |s| (s as f64 * 1000.0).round() as u32);
+    packet[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ost_core::model::{FlagState, SessionData, TimingData, VehicleData};
+    use ost_core::units::{MetersPerSecond, Percentage, Rpm, Seconds};
+
+    #[test]
+    fn test_crc16_check_value() {
+        // The standard check input for CRC catalogues
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+    }
+
+    #[test]
+    fn test_encode_layout() {
+        let mut frame = TelemetryFrame::new("Demo");
+        frame.meta.seq = Some(0x1_0005);
+        frame.vehicle = Some(VehicleData {
+            gear: Some(-1),
+            rpm: Some(Rpm(7250.4)),
+            speed: Some(MetersPerSecond(50.0)),
+            throttle: Some(Percentage(1.5)),
+            ..Default::default()
+        });
+        frame.timing = Some(TimingData {
+            last_lap_time: Some(Seconds(85.123)),
+            delta_best: Some(Seconds(-40.0)),
+            ..Default::default()
+        });
+        frame.session = Some(SessionData {
+            flags: Some(FlagState {
+                yellow: true,
+                checkered: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+
+        let packet = encode(&frame);
+        assert_eq!(&packet[0..3], b"OD\x01");
+        assert_eq!(packet[3] as i8, -1);
+        assert_eq!(u16::from_le_bytes([packet[4], packet[5]]), 5);
+        assert_eq!(u16::from_le_bytes([packet[6], packet[7]]), 7250);
+        assert_eq!(u16::from_le_bytes([packet[10], packet[11]]), 1800);
+        // Saturated rather than wrapped
+        assert_eq!(packet[12], 255);
+        assert_eq!(
+            u16::from_le_bytes([packet[16], packet[17]]),
+            flag_bits::YELLOW | flag_bits::CHECKERED
+        );
+        assert_eq!(
+            u32::from_le_bytes(packet[24..28].try_into().unwrap()),
+            85123
+        );
+        assert_eq!(i16::from_le_bytes([packet[32], packet[33]]), -32767);
+        let crc = u16::from_le_bytes([packet[38], packet[39]]);
+        assert_eq!(crc, crc16(&packet[..38]));
+    }
+}
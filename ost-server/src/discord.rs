@@ -0,0 +1,306 @@
+//! Discord webhook notifications
+//!
+//! Posts session events (new best laps, incidents, session results) to a
+//! Discord channel through a webhook, configured in the `[discord]` section
+//! of the config file:
+//!
+//! ```toml
+//! [discord]
+//! webhook_url = "https://discord.com/api/webhooks/..."
+//! # Which events to post (default: all of them)
+//! events = ["lap_record", "session_result"]
+//! # Name to post as (default: the webhook's own)
+//! username = "Race Engineer"
+//! ```
+
+use crate::events::{EventDetail, EventType, IncidentKind, SessionEvent};
+use crate::webhook;
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+/// Longest rate-limit wait honoured before a message is dropped
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// Embed colors
+const COLOR_RECORD: u32 = 0x9b59b6;
+const COLOR_INCIDENT: u32 = 0xe67e22;
+const COLOR_PIT: u32 = 0x3498db;
+const COLOR_RESULT: u32 = 0x2ecc71;
+
+/// `[discord]` config file section
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiscordConfig {
+    pub webhook_url: String,
+    #[serde(default = "all_events")]
+    pub events: Vec<EventType>,
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+fn all_events() -> Vec<EventType> {
+    EventType::ALL.to_vec()
+}
+
+/// Post the events the config asks for until the channel closes
+pub async fn run(config: DiscordConfig, mut rx: broadcast::Receiver<SessionEvent>) {
+    loop {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!("Discord: skipped {} session events (lagged)", n);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if !config.events.contains(&event.detail.event_type()) {
+            continue;
+        }
+        if let Err(e) = post(&config, &event).await {
+            warn!("Discord: {:#}", e);
+        }
+    }
+}
+
+/// Post one event, waiting out a rate limit once
+pub async fn post(config: &DiscordConfig, event: &SessionEvent) -> Result<()> {
+    let body = message(event, config.username.as_deref());
+    let mut response = webhook::post_json(&config.webhook_url, &body).await?;
+    if response.status == 429 {
+        let wait = response
+            .retry_after
+            .unwrap_or(Duration::from_secs(1))
+            .min(MAX_RETRY_AFTER);
+        tokio::time::sleep(wait).await;
+        response = webhook::post_json(&config.webhook_url, &body).await?;
+    }
+    if !response.is_success() {
+        bail!("Webhook responded with HTTP {}", response.status);
+    }
+    Ok(())
+}
+
+/// The webhook payload for an event: one embed
+pub fn message(event: &SessionEvent, username: Option<&str>) -> Value {
+    let (title, color, fields) = match &event.detail {
+        EventDetail::LapRecord {
+            lap,
+            time,
+            previous,
+        } => {
+            let mut fields = vec![];
+            if let Some(lap) = lap {
+                fields.push(field("Lap", lap.to_string()));
+            }
+            if let Some(previous) = previous {
+                fields.push(field(
+                    "Improvement",
+                    format!(
+                        "-{:.3}s (was {})",
+                        previous.0 - time.0,
+                        lap_time(previous.0)
+                    ),
+                ));
+            }
+            (
+                format!("New best lap: {}", lap_time(time.0)),
+                COLOR_RECORD,
+                fields,
+            )
+        }
+        EventDetail::Incident { kind, lap, damage } => {
+            let title = match kind {
+                IncidentKind::OffTrack => "Off track",
+                IncidentKind::Damage => "Damage taken",
+            };
+            let mut fields = vec![];
+            if let Some(lap) = lap {
+                fields.push(field("Lap", lap.to_string()));
+            }
+            if let Some(damage) = damage {
+                fields.push(field("Damage", format!("+{:.0}%", damage * 100.0)));
+            }
+            (title.to_string(), COLOR_INCIDENT, fields)
+        }
+        EventDetail::PitEntry { lap } => {
+            let fields = lap.map(|lap| field("Lap", lap.to_string()));
+            (
+                "Pit entry".to_string(),
+                COLOR_PIT,
+                fields.into_iter().collect(),
+            )
+        }
+        EventDetail::PitExit {
+            lap,
+            pit_lane_time,
+            stationary_time,
+        } => {
+            let mut fields = vec![];
+            if let Some(lap) = lap {
+                fields.push(field("Lap", lap.to_string()));
+            }
+            fields.push(field("Stationary", format!("{:.1}s", stationary_time.0)));
+            (
+                format!("Pit stop: {:.1}s in the pit lane", pit_lane_time.0),
+                COLOR_PIT,
+                fields,
+            )
+        }
+        EventDetail::SessionResult {
+            session_type,
+            position,
+            class_position,
+            num_cars,
+            laps_completed,
+            best_lap_time,
+            pace,
+        } => {
+            let session = session_type.map_or("Session".to_string(), |t| format!("{:?}", t));
+            let title = match (position, num_cars) {
+                (Some(p), Some(n)) => format!("{} finished: P{} of {}", session, p, n),
+                (Some(p), None) => format!("{} finished: P{}", session, p),
+                _ => format!("{} finished", session),
+            };
+            let mut fields = vec![];
+            if let Some(p) = class_position.filter(|c| Some(*c) != *position) {
+                fields.push(field("Class position", format!("P{}", p)));
+            }
+            if let Some(laps) = laps_completed {
+                fields.push(field("Laps", laps.to_string()));
+            }
+            if let Some(best) = best_lap_time {
+                fields.push(field("Best lap", lap_time(best.0)));
+            }
+            if let Some(best) = pace.theoretical_best {
+                fields.push(field("Theoretical best", lap_time(best.0)));
+            }
+            if let Some(consistency) = pace.consistency {
+                fields.push(field(
+                    "Consistency",
+                    format!(
+                        "±{:.3}s over {} laps",
+                        consistency.stddev.0, consistency.laps
+                    ),
+                ));
+            }
+            (title, COLOR_RESULT, fields)
+        }
+    };
+
+    let description = [
+        event.driver.as_deref(),
+        event.car.as_deref(),
+        event.track.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" · ");
+
+    let mut body = json!({
+        "embeds": [{
+            "title": title,
+            "description": description,
+            "color": color,
+            "timestamp": event.timestamp.to_rfc3339(),
+            "fields": fields,
+            "footer": {"text": event.game},
+        }],
+    });
+    if let Some(username) = username {
+        body["username"] = json!(username);
+    }
+    body
+}
+
+fn field(name: &str, value: String) -> Value {
+    json!({"name": name, "value": value, "inline": true})
+}
+
+/// Seconds as `m:ss.mmm`
+fn lap_time(seconds: f32) -> String {
+    let millis = (seconds.max(0.0) as f64 * 1000.0).round() as u64;
+    format!(
+        "{}:{:02}.{:03}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ost_core::units::Seconds;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn event(detail: EventDetail) -> SessionEvent {
+        SessionEvent {
+            timestamp: "2026-01-01T12:00:00Z".parse().unwrap(),
+            game: "iRacing".to_string(),
+            driver: Some("Sam".to_string()),
+            car: None,
+            track: Some("Spa".to_string()),
+            detail,
+        }
+    }
+
+    #[test]
+    fn test_message() {
+        let record = event(EventDetail::LapRecord {
+            lap: Some(7),
+            time: Seconds(138.25),
+            previous: Some(Seconds(139.0)),
+        });
+        let body = message(&record, Some("Engineer"));
+        assert_eq!(body["username"], "Engineer");
+        let embed = &body["embeds"][0];
+        assert_eq!(embed["title"], "New best lap: 2:18.250");
+        assert_eq!(embed["description"], "Sam · Spa");
+        assert_eq!(embed["fields"][1]["value"], "-0.750s (was 2:19.000)");
+
+        let config: DiscordConfig =
+            toml::from_str("webhook_url = \"https://discord.com/api/webhooks/1/x\"").unwrap();
+        assert_eq!(config.events, EventType::ALL);
+    }
+
+    #[tokio::test]
+    async fn test_post() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = DiscordConfig {
+            webhook_url: format!("http://{}/api/webhooks/1/x", listener.local_addr().unwrap()),
+            events: vec![EventType::SessionResult],
+            username: None,
+        };
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"}]}") {
+                let mut chunk = [0u8; 4096];
+                let n = socket.read(&mut chunk).await.unwrap();
+                request.extend_from_slice(&chunk[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let result = event(EventDetail::SessionResult {
+            session_type: Some(ost_core::model::SessionType::Race),
+            position: Some(3),
+            class_position: None,
+            num_cars: Some(20),
+            laps_completed: Some(12),
+            best_lap_time: None,
+            pace: Default::default(),
+        });
+        post(&config, &result).await.unwrap();
+        assert!(server.await.unwrap().contains("Race finished: P3 of 20"));
+    }
+}
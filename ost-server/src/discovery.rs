@@ -0,0 +1,150 @@
+//! LAN discovery
+//!
+//! Advertises the server over mDNS/DNS-SD as [`MDNS_SERVICE_TYPE`], so
+//! companion apps (phone dashboards, tablets) can find it without the user
+//! typing in an IP address. Where mDNS is blocked, [`run_beacon`] broadcasts
+//! an [`ost_core::discovery::Beacon`] instead.
+
+use anyhow::{Context, Result};
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use ost_core::discovery::{Beacon, DISCOVERY_PORT};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tracing::warn;
+
+/// How often the UDP beacon is broadcast
+pub const BEACON_INTERVAL: Duration = Duration::from_secs(2);
+
+/// DNS-SD service type the server registers under
+pub const MDNS_SERVICE_TYPE: &str = "_ost._tcp.local.";
+
+/// What is advertised about this server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    /// Human-readable server name, e.g. "OpenSimTelemetry on RIG-PC"
+    pub name: String,
+    pub port: u16,
+    /// Whether the server speaks HTTPS
+    pub tls: bool,
+}
+
+impl Announcement {
+    /// Announcement for this machine, named after its host name
+    pub fn new(port: u16, tls: bool) -> Self {
+        let name = match host_name() {
+            Some(host) => format!("OpenSimTelemetry on {}", host),
+            None => "OpenSimTelemetry".to_string(),
+        };
+        Self { name, port, tls }
+    }
+
+    fn scheme(&self) -> &'static str {
+        if self.tls {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// UDP beacon for this server
+    pub fn beacon(&self) -> Beacon {
+        Beacon::new(&self.name, env!("CARGO_PKG_VERSION"), self.port, self.tls)
+    }
+
+    /// TXT record entries
+    fn properties(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("version", env!("CARGO_PKG_VERSION").to_string()),
+            ("scheme", self.scheme().to_string()),
+            ("path", "/api".to_string()),
+        ]
+    }
+}
+
+/// Register the server with mDNS. Advertising stops when the returned daemon
+/// is shut down or dropped at exit.
+pub fn advertise_mdns(announcement: &Announcement) -> Result<ServiceDaemon> {
+    let daemon = ServiceDaemon::new().context("starting mDNS daemon")?;
+    let host = format!(
+        "{}.local.",
+        host_name().unwrap_or_else(|| "ost".to_string())
+    );
+    let properties = announcement.properties();
+    let service = ServiceInfo::new(
+        MDNS_SERVICE_TYPE,
+        &announcement.name,
+        &host,
+        (),
+        announcement.port,
+        &properties[..],
+    )
+    .context("building mDNS service info")?
+    .enable_addr_auto();
+    daemon
+        .register(service)
+        .context("registering mDNS service")?;
+    Ok(daemon)
+}
+
+/// Broadcast the beacon to [`DISCOVERY_PORT`] every [`BEACON_INTERVAL`]
+/// until the task is dropped.
+pub async fn run_beacon(announcement: Announcement) -> Result<()> {
+    let socket = tokio::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .await
+        .context("binding beacon socket")?;
+    socket.set_broadcast(true)?;
+    let target = SocketAddr::from((Ipv4Addr::BROADCAST, DISCOVERY_PORT));
+    let payload = announcement.beacon().to_bytes();
+
+    let mut interval = tokio::time::interval(BEACON_INTERVAL);
+    let mut failing = false;
+    loop {
+        interval.tick().await;
+        // Machines without a broadcast route fail every send; only log once
+        match socket.send_to(&payload, target).await {
+            Ok(_) => failing = false,
+            Err(e) if !failing => {
+                warn!("Discovery beacon send failed: {}", e);
+                failing = true;
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// This machine's name, reduced to characters valid in a DNS label
+fn host_name() -> Option<String> {
+    let raw = std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())?;
+    let label: String = raw
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let label = label.trim_matches('-').to_string();
+    (!label.is_empty()).then_some(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_announcement_properties() {
+        let announcement = Announcement {
+            name: "OpenSimTelemetry on RIG".to_string(),
+            port: 9100,
+            tls: true,
+        };
+        let properties = announcement.properties();
+        assert!(properties.contains(&("scheme", "https".to_string())));
+        assert!(properties.contains(&("version", env!("CARGO_PKG_VERSION").to_string())));
+
+        let beacon = Beacon::parse(&announcement.beacon().to_bytes()).unwrap();
+        assert_eq!(beacon.name, "OpenSimTelemetry on RIG");
+        assert_eq!(beacon.port, 9100);
+        assert_eq!(beacon.scheme, "https");
+    }
+}
@@ -0,0 +1,394 @@
+//! Session events from live frames
+//!
+//! [`EventDetector`] compares each frame with what it has seen so far in the
+//! session and reports the moments worth telling someone about: a new best
+//! lap, an incident (leaving the track or picking up damage), pit lane entry
+//! and exit with the stop's timing, and the result when the session ends. They are published as the `session_event` event on
+//! `/api/stream` and drive notifiers such as the Discord webhook.
+//!
+//! The first frame after the adapter starts only sets the baseline, so
+//! connecting mid-session doesn't replay the session's best lap or damage.
+
+use crate::pace::{PaceStats, PaceTracker, DEFAULT_WINDOW};
+use crate::pits::{PitSample, PitTracker, PitTransition};
+use chrono::{DateTime, Utc};
+use ost_core::model::{DamageData, SessionState, SessionType, TelemetryFrame, TrackSurface};
+use ost_core::units::Seconds;
+use serde::{Deserialize, Serialize};
+
+/// Damage increase (summed over the car, 0-1 per area) that counts as an
+/// incident, so rounding noise isn't reported
+const DAMAGE_THRESHOLD: f32 = 0.01;
+
+/// Kinds of session event, for choosing which ones a notifier sends
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    LapRecord,
+    Incident,
+    PitStop,
+    SessionResult,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 4] = [
+        EventType::LapRecord,
+        EventType::Incident,
+        EventType::PitStop,
+        EventType::SessionResult,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentKind {
+    /// Left the racing surface
+    OffTrack,
+    /// Picked up damage
+    Damage,
+}
+
+/// What happened
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventDetail {
+    /// A lap faster than any before it this session
+    LapRecord {
+        lap: Option<u32>,
+        time: Seconds,
+        /// The best lap it beat, if there was one
+        previous: Option<Seconds>,
+    },
+    Incident {
+        kind: IncidentKind,
+        lap: Option<u32>,
+        /// Damage added, summed over the car's areas (for `damage`)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        damage: Option<f32>,
+    },
+    /// Into the pit lane
+    PitEntry { lap: Option<u32> },
+    /// Out of the pit lane, with how long the stop took
+    PitExit {
+        lap: Option<u32>,
+        /// From pit entry to exit
+        pit_lane_time: Seconds,
+        /// Stood still in the pit lane
+        stationary_time: Seconds,
+    },
+    /// The player's result when the chequered flag fell
+    SessionResult {
+        session_type: Option<SessionType>,
+        position: Option<u32>,
+        class_position: Option<u32>,
+        num_cars: Option<u32>,
+        laps_completed: Option<u32>,
+        best_lap_time: Option<Seconds>,
+        /// Consistency and pace over the session
+        pace: PaceStats,
+    },
+}
+
+impl EventDetail {
+    pub fn event_type(&self) -> EventType {
+        match self {
+            EventDetail::LapRecord { .. } => EventType::LapRecord,
+            EventDetail::Incident { .. } => EventType::Incident,
+            EventDetail::PitEntry { .. } | EventDetail::PitExit { .. } => EventType::PitStop,
+            EventDetail::SessionResult { .. } => EventType::SessionResult,
+        }
+    }
+}
+
+/// A session event with the context it happened in
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SessionEvent {
+    pub timestamp: DateTime<Utc>,
+    pub game: String,
+    pub driver: Option<String>,
+    pub car: Option<String>,
+    pub track: Option<String>,
+    #[serde(flatten)]
+    pub detail: EventDetail,
+}
+
+/// What the detector remembers between frames
+#[derive(Debug, Default)]
+pub struct EventDetector {
+    /// False until the first frame of a session has set the baseline
+    started: bool,
+    session_type: Option<SessionType>,
+    best_lap: Option<f32>,
+    off_track: bool,
+    damage: f32,
+    pits: PitTracker,
+    /// Whether the car's time in the pit lane has been seen from its entry
+    pit_entry_seen: bool,
+    pace: PaceTracker,
+    finished: bool,
+}
+
+impl EventDetector {
+    /// Events `frame` brings, in the order they happened
+    pub fn update(&mut self, frame: &TelemetryFrame) -> Vec<SessionEvent> {
+        let timing = frame.timing.as_ref();
+        let session = frame.session.as_ref();
+        let session_type = session.and_then(|s| s.session_type);
+        if self.started && session_type.is_some() && session_type != self.session_type {
+            // A new session starts from scratch, but not mid-session. The
+            // car may still be in the pit lane, though.
+            *self = Self {
+                started: true,
+                pits: self.pits,
+                ..Self::default()
+            };
+        }
+        self.session_type = session_type;
+
+        let best_lap = timing
+            .and_then(|t| t.best_lap_time)
+            .map(|s| s.0)
+            .filter(|t| *t > 0.0);
+        let off_track = off_track(frame);
+        let damage = frame.damage.as_ref().map_or(0.0, total_damage);
+        let finished = session.is_some_and(|s| {
+            s.session_state == Some(SessionState::Checkered) || s.flags.is_some_and(|f| f.checkered)
+        });
+
+        self.pace.update(frame);
+        let time = frame.meta.timestamp.timestamp_micros() as f64 / 1e6;
+        let pit = self.pits.update(&PitSample::from_frame(frame, time));
+
+        let mut details = Vec::new();
+        if self.started {
+            let lap = timing.and_then(|t| t.lap_number);
+            if let Some(best) = best_lap {
+                if self.best_lap.is_none_or(|prev| best < prev) {
+                    details.push(EventDetail::LapRecord {
+                        // The best lap was the one just completed
+                        lap: timing.and_then(|t| t.laps_completed),
+                        time: Seconds(best),
+                        previous: self.best_lap.map(Seconds),
+                    });
+                }
+            }
+            if off_track && !self.off_track {
+                details.push(EventDetail::Incident {
+                    kind: IncidentKind::OffTrack,
+                    lap,
+                    damage: None,
+                });
+            }
+            if damage - self.damage >= DAMAGE_THRESHOLD {
+                details.push(EventDetail::Incident {
+                    kind: IncidentKind::Damage,
+                    lap,
+                    damage: Some(damage - self.damage),
+                });
+            }
+            match pit {
+                Some(PitTransition::Entered) => {
+                    self.pit_entry_seen = true;
+                    details.push(EventDetail::PitEntry { lap });
+                }
+                // Only stops timed from their entry
+                Some(PitTransition::Exited(stop)) if self.pit_entry_seen => {
+                    self.pit_entry_seen = false;
+                    details.push(EventDetail::PitExit {
+                        lap,
+                        pit_lane_time: Seconds(stop.pit_lane_secs),
+                        stationary_time: Seconds(stop.stationary_secs),
+                    });
+                }
+                _ => {}
+            }
+            if finished && !self.finished {
+                details.push(EventDetail::SessionResult {
+                    session_type,
+                    position: timing.and_then(|t| t.race_position),
+                    class_position: timing.and_then(|t| t.class_position),
+                    num_cars: timing.and_then(|t| t.num_cars),
+                    laps_completed: timing.and_then(|t| t.laps_completed),
+                    best_lap_time: best_lap.map(Seconds),
+                    pace: self.pace.stats(DEFAULT_WINDOW),
+                });
+            }
+        }
+
+        self.started = true;
+        if best_lap.is_some() {
+            self.best_lap = best_lap;
+        }
+        self.off_track = off_track;
+        // Repairs lower the baseline, so new damage after a pit stop counts
+        if damage - self.damage >= DAMAGE_THRESHOLD || damage < self.damage {
+            self.damage = damage;
+        }
+        self.finished |= finished;
+
+        details
+            .into_iter()
+            .map(|detail| SessionEvent {
+                timestamp: frame.meta.timestamp,
+                game: frame.meta.game.clone(),
+                driver: frame.driver.as_ref().and_then(|d| d.name.clone()),
+                car: frame.vehicle.as_ref().and_then(|v| v.car_name.clone()),
+                track: session.and_then(|s| s.track_name.clone()),
+                detail,
+            })
+            .collect()
+    }
+
+    /// Consistency and pace over the session so far, measured over the last
+    /// `window` valid laps
+    pub fn pace(&self, window: usize) -> PaceStats {
+        self.pace.stats(window)
+    }
+
+    /// Forget the session, e.g. when the adapter stops
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+fn off_track(frame: &TelemetryFrame) -> bool {
+    frame
+        .vehicle
+        .as_ref()
+        .and_then(|v| v.track_surface)
+        .is_some_and(|surface| {
+            matches!(
+                surface,
+                TrackSurface::Grass
+                    | TrackSurface::Dirt
+                    | TrackSurface::Sand
+                    | TrackSurface::Gravel
+                    | TrackSurface::Grasscrete
+                    | TrackSurface::Astroturf
+            )
+        })
+}
+
+fn total_damage(damage: &DamageData) -> f32 {
+    [
+        damage.front,
+        damage.rear,
+        damage.left,
+        damage.right,
+        damage.engine,
+        damage.transmission,
+    ]
+    .into_iter()
+    .flatten()
+    .map(|p| p.0)
+    .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(value: serde_json::Value) -> TelemetryFrame {
+        let mut json = serde_json::json!({
+            "meta": {"timestamp": "2026-01-01T00:00:00Z", "game": "test"},
+            "session": {"session_type": "Race", "track_name": "Spa"},
+        });
+        for (key, v) in value.as_object().unwrap() {
+            json[key] = v.clone();
+        }
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn types(events: &[SessionEvent]) -> Vec<EventType> {
+        events.iter().map(|e| e.detail.event_type()).collect()
+    }
+
+    #[test]
+    fn test_detects_session_events() {
+        let mut detector = EventDetector::default();
+        // Baseline: an existing best lap and damage aren't reported
+        let baseline = frame(serde_json::json!({
+            "timing": {"best_lap_time": 92.0, "lap_number": 4},
+            "damage": {"front": 0.2},
+        }));
+        assert!(detector.update(&baseline).is_empty());
+
+        let events = detector.update(&frame(serde_json::json!({
+            "timing": {"best_lap_time": 91.5, "lap_number": 5, "laps_completed": 4},
+            "damage": {"front": 0.2},
+            "vehicle": {"track_surface": "Grass"},
+        })));
+        assert_eq!(types(&events), [EventType::LapRecord, EventType::Incident]);
+        assert_eq!(events[0].track.as_deref(), Some("Spa"));
+        assert_eq!(
+            events[0].detail,
+            EventDetail::LapRecord {
+                lap: Some(4),
+                time: Seconds(91.5),
+                previous: Some(Seconds(92.0)),
+            }
+        );
+
+        // Still off track: nothing new. More damage: an incident.
+        let events = detector.update(&frame(serde_json::json!({
+            "timing": {"best_lap_time": 91.5, "lap_number": 5},
+            "damage": {"front": 0.25, "engine": 0.05},
+            "vehicle": {"track_surface": "Grass"},
+        })));
+        assert_eq!(types(&events), [EventType::Incident]);
+        let value = serde_json::to_value(&events[0]).unwrap();
+        assert_eq!(value["type"], "incident");
+        assert_eq!(value["kind"], "damage");
+
+        let finish = frame(serde_json::json!({
+            "timing": {"best_lap_time": 91.5, "race_position": 3, "num_cars": 20},
+            "session": {"session_type": "Race", "session_state": "Checkered"},
+        }));
+        let events = detector.update(&finish);
+        assert_eq!(types(&events), [EventType::SessionResult]);
+        assert!(detector.update(&finish).is_empty());
+
+        // The next session starts over, without reporting its first best
+        // lap as beating the last session's
+        let events = detector.update(&frame(serde_json::json!({
+            "timing": {"best_lap_time": 95.0},
+            "session": {"session_type": "Practice"},
+        })));
+        assert_eq!(types(&events), [EventType::LapRecord]);
+        assert!(matches!(
+            events[0].detail,
+            EventDetail::LapRecord { previous: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_times_pit_stops() {
+        let at = |secs: u32, on_pit_road: bool, speed: f32| {
+            frame(serde_json::json!({
+                "meta": {"timestamp": format!("2026-01-01T00:00:{:02}Z", secs), "game": "test"},
+                "timing": {"lap_number": 7},
+                "pit": {"on_pit_road": on_pit_road},
+                "vehicle": {"speed": speed},
+            }))
+        };
+        let mut detector = EventDetector::default();
+        // Already in the pit lane when the adapter starts: that stop isn't timed
+        assert!(detector.update(&at(0, true, 0.0)).is_empty());
+        assert!(detector.update(&at(1, false, 20.0)).is_empty());
+
+        let events = detector.update(&at(10, true, 20.0));
+        assert_eq!(events[0].detail, EventDetail::PitEntry { lap: Some(7) });
+        assert!(detector.update(&at(20, true, 0.0)).is_empty());
+        assert!(detector.update(&at(45, true, 5.0)).is_empty());
+        let events = detector.update(&at(55, false, 20.0));
+        assert_eq!(types(&events), [EventType::PitStop]);
+        assert_eq!(
+            events[0].detail,
+            EventDetail::PitExit {
+                lap: Some(7),
+                pit_lane_time: Seconds(45.0),
+                stationary_time: Seconds(25.0),
+            }
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! A/B lap ghost for replays
+//!
+//! Pairs the replay's position with the matching frame of a reference lap,
+//! so a ghost of that lap can be drawn alongside while playing or scrubbing.
+//! Frames are matched by lap distance, putting both cars at the same point
+//! on track with the time between them as the delta, or by time into the
+//! lap, showing how far apart they are after the same time.
+
+use crate::replay::ReplayState;
+use anyhow::{anyhow, Result};
+use ost_adapters::ibt_parser::LapInfo;
+use serde::{Deserialize, Serialize};
+
+/// How replay frames are matched to the reference lap
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Align {
+    /// Same `timing.lap_distance_pct`
+    #[default]
+    Distance,
+    /// Same time since the start of the lap
+    Time,
+}
+
+/// Which lap a replay is compared against, as reported in the replay info
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GhostSettings {
+    pub lap: i32,
+    #[serde(default)]
+    pub align: Align,
+}
+
+/// A replay frame's match on the reference lap
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GhostPair {
+    /// Replay frame of the reference lap
+    pub reference: usize,
+    /// Seconds the replay is behind the reference at the same distance,
+    /// negative when ahead. Only for [`Align::Distance`].
+    pub delta: Option<f64>,
+}
+
+/// Frames at the start of a lap can still report the end of the previous
+/// one; within this many seconds of the line, distances past half way are
+/// taken as the start.
+const WRAP_SECS: f64 = 1.0;
+
+/// A reference lap of the replay to pair frames with
+pub struct Ghost {
+    settings: GhostSettings,
+    start: usize,
+    tick_rate: u32,
+    /// The reference lap's distance at each of its frames, never decreasing
+    distances: Vec<f32>,
+}
+
+impl Ghost {
+    /// Reference lap `settings.lap` of the replay
+    pub fn new(rs: &ReplayState, settings: GhostSettings) -> Result<Self> {
+        let lap = rs
+            .laps()
+            .iter()
+            .find(|lap| lap.lap_number == settings.lap)
+            .ok_or_else(|| anyhow!("No lap {}", settings.lap))?;
+        let mut distances = Vec::with_capacity(lap.end_frame + 1 - lap.start_frame);
+        while lap.start_frame + distances.len() <= lap.end_frame {
+            let next = lap.start_frame + distances.len();
+            let frames = rs.get_frames_range(next, lap.end_frame + 1 - next)?;
+            if frames.is_empty() {
+                break;
+            }
+            distances.extend(frames.iter().map(|(_, frame)| {
+                frame
+                    .timing
+                    .as_ref()
+                    .and_then(|t| t.lap_distance_pct)
+                    .map(|pct| pct.0)
+            }));
+        }
+        Ok(Self::from_distances(
+            settings,
+            lap,
+            rs.tick_rate(),
+            distances,
+        ))
+    }
+
+    fn from_distances(
+        settings: GhostSettings,
+        lap: &LapInfo,
+        tick_rate: u32,
+        distances: Vec<Option<f32>>,
+    ) -> Self {
+        let mut furthest = 0.0f32;
+        let distances = distances
+            .into_iter()
+            .enumerate()
+            .map(|(i, distance)| {
+                let distance = unwrap_distance(distance.unwrap_or(furthest), i, tick_rate);
+                furthest = furthest.max(distance);
+                furthest
+            })
+            .collect();
+        Self {
+            settings,
+            start: lap.start_frame,
+            tick_rate: tick_rate.max(1),
+            distances,
+        }
+    }
+
+    pub fn settings(&self) -> GhostSettings {
+        self.settings
+    }
+
+    /// Match replay frame `index`, at `distance` round its lap, to the
+    /// reference lap. `None` outside the replay's laps. Past the end of the
+    /// reference lap, the ghost waits on the line.
+    pub fn pair(&self, laps: &[LapInfo], index: usize, distance: Option<f32>) -> Option<GhostPair> {
+        let last = self.distances.len().checked_sub(1)?;
+        let lap = laps
+            .iter()
+            .find(|lap| lap.start_frame <= index && index <= lap.end_frame)?;
+        let elapsed = index - lap.start_frame;
+        let (offset, delta) = match self.settings.align {
+            Align::Time => (elapsed.min(last), None),
+            Align::Distance => {
+                let distance = unwrap_distance(distance?, elapsed, self.tick_rate);
+                let offset = self.distances.partition_point(|d| *d < distance).min(last);
+                let delta = (elapsed as f64 - offset as f64) / self.tick_rate as f64;
+                (offset, Some(delta))
+            }
+        };
+        Some(GhostPair {
+            reference: self.start + offset,
+            delta,
+        })
+    }
+}
+
+/// `distance` of the frame `elapsed` frames into a lap, with the end of the
+/// previous lap taken as the start
+fn unwrap_distance(distance: f32, elapsed: usize, tick_rate: u32) -> f32 {
+    if distance > 0.5 && (elapsed as f64) < WRAP_SECS * tick_rate as f64 {
+        0.0
+    } else {
+        distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lap(lap_number: i32, start_frame: usize, end_frame: usize) -> LapInfo {
+        LapInfo {
+            lap_number,
+            start_frame,
+            end_frame,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pair_by_distance_and_time() {
+        let laps = [lap(2, 100, 199), lap(3, 200, 319)];
+        // 10 Hz; the reference lap covers 1% a frame, after a frame still
+        // showing the previous lap's end
+        let distances = (0..100)
+            .map(|i| Some(if i == 0 { 0.999 } else { i as f32 / 100.0 }))
+            .collect();
+        let settings = GhostSettings {
+            lap: 2,
+            align: Align::Distance,
+        };
+        let ghost = Ghost::from_distances(settings, &laps[0], 10, distances);
+
+        // Lap 3 is slower: half way after 60 frames, 10 behind the reference
+        let pair = ghost.pair(&laps, 260, Some(0.5)).unwrap();
+        assert_eq!(pair.reference, 150);
+        assert!((pair.delta.unwrap() - 1.0).abs() < 1e-9);
+        // The line, wrapped or not
+        assert_eq!(ghost.pair(&laps, 200, Some(0.99)).unwrap().reference, 100);
+        // Past the reference's last frame it waits at the end
+        assert_eq!(ghost.pair(&laps, 318, Some(0.999)).unwrap().reference, 199);
+        // Before the first lap, or with no distance, nothing to pair with
+        assert_eq!(ghost.pair(&laps, 50, Some(0.5)), None);
+        assert_eq!(ghost.pair(&laps, 260, None), None);
+
+        let ghost = Ghost {
+            settings: GhostSettings {
+                align: Align::Time,
+                ..settings
+            },
+            ..ghost
+        };
+        let pair = ghost.pair(&laps, 260, None).unwrap();
+        assert_eq!((pair.reference, pair.delta), (160, None));
+        assert_eq!(ghost.pair(&laps, 318, None).unwrap().reference, 199);
+    }
+}
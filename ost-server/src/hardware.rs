@@ -0,0 +1,762 @@
+//! Real car sensors: OBD-II and GPS
+//!
+//! For track days. With a `[hardware]` section in the config file:
+//!
+//! ```toml
+//! [hardware]
+//! # An ELM327 OBD-II dongle; Bluetooth ones pair as a serial port
+//! # (rfcomm on Linux, an outgoing COM port on Windows). Baud 38400 by default.
+//! obd = { port = "/dev/rfcomm0" }
+//! # An NMEA GPS receiver. Baud 9600 by default.
+//! gps = { port = "/dev/ttyACM0", baud = 9600 }
+//! ```
+//!
+//! the [`HardwareAdapter`] polls the car's ECU for RPM, speed, throttle
+//! position, coolant and oil temperature and fuel level, and takes position,
+//! altitude and heading from the GPS; either works without the other. Each
+//! port is read on a thread of its own, and frames are built at 10 Hz from
+//! the latest values. Speed comes from the ECU when it reports one,
+//! otherwise from the GPS. A device that goes silent stops its thread with
+//! an error, and the adapter restarts once it's back.
+
+use crate::serial;
+use anyhow::{bail, Context, Result};
+use ost_core::adapter::{AdapterError, PollHint, TelemetryAdapter};
+use ost_core::{model::*, units::*};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+const GAME: &str = "Real Car";
+
+const OBD_BAUD: u32 = 38400;
+const GPS_BAUD: u32 = 9600;
+
+const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Readings older than this are left out of frames
+const STALE_AFTER: Duration = Duration::from_secs(3);
+
+// Mode 01 PIDs, polled in this order
+const PID_COOLANT_TEMP: u8 = 0x05;
+const PID_RPM: u8 = 0x0C;
+const PID_SPEED: u8 = 0x0D;
+const PID_THROTTLE: u8 = 0x11;
+const PID_FUEL_LEVEL: u8 = 0x2F;
+const PID_OIL_TEMP: u8 = 0x5C;
+const PIDS: [u8; 6] = [
+    PID_RPM,
+    PID_SPEED,
+    PID_THROTTLE,
+    PID_COOLANT_TEMP,
+    PID_FUEL_LEVEL,
+    PID_OIL_TEMP,
+];
+
+/// ELM327 setup: reset, echo off, linefeeds off, automatic protocol
+const ELM_INIT: [&str; 4] = ["ATZ", "ATE0", "ATL0", "ATSP0"];
+
+/// Longest ELM327 reply accepted before its prompt
+const MAX_REPLY: usize = 1024;
+
+/// How long the dongle gets to answer a command; `ATZ` resets it, which
+/// takes a second or so
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long the GPS may go without sending anything. Receivers send every
+/// second, even without a fix.
+const GPS_SILENCE: Duration = Duration::from_secs(5);
+
+const KNOTS_TO_MPS: f32 = 0.514_444;
+
+/// A serial device
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SerialDevice {
+    pub port: String,
+    /// The device's usual rate when unset
+    #[serde(default)]
+    pub baud: Option<u32>,
+}
+
+/// `[hardware]` config file section
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HardwareConfig {
+    #[serde(default)]
+    pub obd: Option<SerialDevice>,
+    #[serde(default)]
+    pub gps: Option<SerialDevice>,
+}
+
+impl HardwareConfig {
+    pub fn check(&self) -> Result<()> {
+        if self.obd.is_none() && self.gps.is_none() {
+            bail!("[hardware] needs an obd or gps port");
+        }
+        Ok(())
+    }
+}
+
+/// Where the GPS last put the car
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct GpsFix {
+    latitude: f64,
+    longitude: f64,
+    /// Meters above sea level
+    altitude: Option<f32>,
+    /// m/s
+    speed: Option<f32>,
+    /// Degrees clockwise from true north
+    heading: Option<f32>,
+}
+
+/// Latest values from the reader threads
+#[derive(Debug, Default)]
+struct Readings {
+    /// Each PID's value, with when it was read
+    obd: HashMap<u8, (f32, Instant)>,
+    gps: Option<(GpsFix, Instant)>,
+    /// Why a reader thread stopped
+    error: Option<anyhow::Error>,
+}
+
+impl Readings {
+    /// A frame from the fresh readings, `None` without any
+    fn frame(&self, now: Instant) -> Option<TelemetryFrame> {
+        let fresh = |at: &Instant| now.duration_since(*at) < STALE_AFTER;
+        let obd = |pid: u8| {
+            self.obd
+                .get(&pid)
+                .filter(|(_, at)| fresh(at))
+                .map(|(v, _)| *v)
+        };
+        let gps = self
+            .gps
+            .as_ref()
+            .filter(|(_, at)| fresh(at))
+            .map(|(fix, _)| fix);
+        if gps.is_none() && PIDS.iter().all(|&pid| obd(pid).is_none()) {
+            return None;
+        }
+
+        let mut frame = TelemetryFrame::new(GAME);
+        frame.vehicle = Some(VehicleData {
+            speed: obd(PID_SPEED)
+                .map(|kmh| kmh / 3.6)
+                .or(gps.and_then(|g| g.speed))
+                .map(MetersPerSecond),
+            rpm: obd(PID_RPM).map(Rpm),
+            throttle: obd(PID_THROTTLE).map(Percentage::new),
+            ..Default::default()
+        });
+        let engine = EngineData {
+            water_temp: obd(PID_COOLANT_TEMP).map(Celsius),
+            oil_temp: obd(PID_OIL_TEMP).map(Celsius),
+            fuel_level_pct: obd(PID_FUEL_LEVEL).map(Percentage::new),
+            ..Default::default()
+        };
+        if engine.water_temp.is_some()
+            || engine.oil_temp.is_some()
+            || engine.fuel_level_pct.is_some()
+        {
+            frame.engine = Some(engine);
+        }
+        if let Some(fix) = gps {
+            frame.motion = Some(MotionData {
+                latitude: Some(fix.latitude),
+                longitude: Some(fix.longitude),
+                altitude: fix.altitude.map(Meters),
+                heading: fix.heading.map(Degrees),
+                ..Default::default()
+            });
+        }
+        Some(frame)
+    }
+}
+
+// =============================================================================
+// OBD-II
+// =============================================================================
+
+/// The value of mode 01 `pid` from an ELM327 reply such as `41 0C 1A F8`:
+/// RPM, km/h, °C, or 0.0 to 1.0 for throttle and fuel level. `None` for
+/// `NO DATA` and other replies without it.
+fn obd_value(pid: u8, reply: &str) -> Option<f32> {
+    let header = format!("41{:02X}", pid);
+    let data = reply.lines().find_map(|line| {
+        let hex: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+        let data = hex.strip_prefix(&header).filter(|d| d.is_ascii())?;
+        (0..data.len() / 2)
+            .map(|i| u8::from_str_radix(&data[i * 2..i * 2 + 2], 16).ok())
+            .collect::<Option<Vec<u8>>>()
+    })?;
+    let a = *data.first()? as f32;
+    match pid {
+        PID_RPM => Some((a * 256.0 + *data.get(1)? as f32) / 4.0),
+        PID_SPEED => Some(a),
+        PID_THROTTLE | PID_FUEL_LEVEL => Some(a / 255.0),
+        PID_COOLANT_TEMP | PID_OIL_TEMP => Some(a - 40.0),
+        _ => None,
+    }
+}
+
+/// An ELM327 dongle on a serial port
+struct Elm327<'a, P> {
+    port: P,
+    /// Gives up waiting for a reply when set
+    stop: &'a AtomicBool,
+    timeout: Duration,
+}
+
+impl<P: Read + Write> Elm327<'_, P> {
+    /// Send `command` and read its reply, up to the `>` prompt
+    fn command(&mut self, command: &str) -> Result<String> {
+        self.port.write_all(format!("{}\r", command).as_bytes())?;
+        let deadline = Instant::now() + self.timeout;
+        let mut reply = Vec::new();
+        let mut byte = [0u8];
+        loop {
+            // Reads time out empty, see `serial::READ_TIMEOUT`
+            if self.port.read(&mut byte)? == 0 {
+                if self.stop.load(Ordering::Relaxed) {
+                    bail!("Stopped");
+                }
+                if Instant::now() >= deadline {
+                    bail!("OBD-II dongle stopped answering");
+                }
+                continue;
+            }
+            match byte[0] {
+                b'>' => break,
+                b'\r' => reply.push(b'\n'),
+                b => reply.push(b),
+            }
+            if reply.len() > MAX_REPLY {
+                bail!("OBD-II dongle sent no prompt");
+            }
+        }
+        Ok(String::from_utf8_lossy(&reply).trim().to_string())
+    }
+
+    fn init(&mut self) -> Result<()> {
+        for command in ELM_INIT {
+            self.command(command)?;
+        }
+        Ok(())
+    }
+
+    /// Request each of `pids` once, recording the values. Returns the PIDs
+    /// the car answered.
+    fn poll(&mut self, pids: &[u8], readings: &Mutex<Readings>) -> Result<Vec<u8>> {
+        let mut answered = Vec::with_capacity(pids.len());
+        for &pid in pids {
+            let reply = self.command(&format!("01{:02X}", pid))?;
+            match obd_value(pid, &reply) {
+                Some(value) => {
+                    let mut readings = readings.lock().unwrap_or_else(|e| e.into_inner());
+                    readings.obd.insert(pid, (value, Instant::now()));
+                    answered.push(pid);
+                }
+                None => debug!("OBD-II PID {:02X} not answered: {}", pid, reply),
+            }
+        }
+        Ok(answered)
+    }
+}
+
+fn read_obd(device: &SerialDevice, readings: &Mutex<Readings>, stop: &AtomicBool) -> Result<()> {
+    let port = serial::open(&device.port, device.baud.unwrap_or(OBD_BAUD))?;
+    let mut elm = Elm327 {
+        port,
+        stop,
+        timeout: REPLY_TIMEOUT,
+    };
+    elm.init()?;
+    // Stop asking for what the car doesn't answer, so the rest update faster
+    let mut pids = PIDS.to_vec();
+    while !stop.load(Ordering::Relaxed) {
+        pids = elm.poll(&pids, readings)?;
+        if pids.is_empty() {
+            bail!("The car answered none of the OBD-II requests; is the ignition on?");
+        }
+    }
+    Ok(())
+}
+
+// =============================================================================
+// GPS
+// =============================================================================
+
+/// The fields of an NMEA sentence with a valid checksum, talker and type
+/// first (`GPRMC`)
+fn nmea_fields(line: &str) -> Option<Vec<&str>> {
+    let (body, checksum) = line.trim().strip_prefix('$')?.split_once('*')?;
+    let expected = u8::from_str_radix(checksum.get(..2)?, 16).ok()?;
+    (body.bytes().fold(0, |sum, b| sum ^ b) == expected).then(|| body.split(',').collect())
+}
+
+/// Degrees from NMEA's `dddmm.mmmm` and hemisphere
+fn nmea_coordinate(value: &str, hemisphere: &str) -> Option<f64> {
+    let value: f64 = value.parse().ok()?;
+    let degrees = (value / 100.0).trunc();
+    let degrees = degrees + (value - degrees * 100.0) / 60.0;
+    match hemisphere {
+        "N" | "E" => Some(degrees),
+        "S" | "W" => Some(-degrees),
+        _ => None,
+    }
+}
+
+/// Update `fix` from an RMC or GGA sentence. Returns whether it holds a
+/// position afterwards; a sentence reporting no fix clears it.
+fn apply_nmea(line: &str, fix: &mut Option<GpsFix>) -> bool {
+    let Some(fields) = nmea_fields(line) else {
+        return false;
+    };
+    let field = |i: usize| fields.get(i).copied().unwrap_or("");
+    let kind = field(0).get(2..).unwrap_or("");
+    let position = |lat: usize| {
+        Some((
+            nmea_coordinate(field(lat), field(lat + 1))?,
+            nmea_coordinate(field(lat + 2), field(lat + 3))?,
+        ))
+    };
+    match kind {
+        // Time, status, lat, N/S, lon, E/W, knots, course
+        "RMC" => match position(3).filter(|_| field(2) == "A") {
+            Some((latitude, longitude)) => {
+                let current = fix.get_or_insert(GpsFix::default());
+                current.latitude = latitude;
+                current.longitude = longitude;
+                current.speed = field(7).parse::<f32>().ok().map(|k| k * KNOTS_TO_MPS);
+                current.heading = field(8).parse().ok();
+            }
+            None => *fix = None,
+        },
+        // Time, lat, N/S, lon, E/W, quality, satellites, HDOP, altitude
+        "GGA" => match position(2).filter(|_| !matches!(field(6), "" | "0")) {
+            Some((latitude, longitude)) => {
+                let current = fix.get_or_insert(GpsFix::default());
+                current.latitude = latitude;
+                current.longitude = longitude;
+                current.altitude = field(9).parse().ok();
+            }
+            None => *fix = None,
+        },
+        _ => return false,
+    }
+    fix.is_some()
+}
+
+fn read_gps(device: &SerialDevice, readings: &Mutex<Readings>, stop: &AtomicBool) -> Result<()> {
+    let port = serial::open(&device.port, device.baud.unwrap_or(GPS_BAUD))?;
+    read_nmea(port, readings, stop, GPS_SILENCE)
+}
+
+/// Apply NMEA sentences from `port` until stopped, or an error if it sends
+/// nothing for `silence`
+fn read_nmea(
+    port: impl Read,
+    readings: &Mutex<Readings>,
+    stop: &AtomicBool,
+    silence: Duration,
+) -> Result<()> {
+    let mut port = BufReader::new(port);
+    let mut fix = None;
+    let mut line = Vec::new();
+    let mut last_data = Instant::now();
+    // Receivers send a burst of sentences every update
+    while !stop.load(Ordering::Relaxed) {
+        // Reads time out empty, see `serial::READ_TIMEOUT`
+        let read = port
+            .read_until(b'\n', &mut line)
+            .context("GPS read failed")?;
+        if read == 0 {
+            if last_data.elapsed() >= silence {
+                bail!("GPS stopped sending");
+            }
+            continue;
+        }
+        last_data = Instant::now();
+        // The rest of the line comes with the next read
+        if line.last() != Some(&b'\n') {
+            continue;
+        }
+        let updated = apply_nmea(&String::from_utf8_lossy(&line), &mut fix);
+        line.clear();
+        let mut readings = readings.lock().unwrap_or_else(|e| e.into_inner());
+        match fix {
+            Some(fix) if updated => readings.gps = Some((fix, Instant::now())),
+            None => readings.gps = None,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// =============================================================================
+// Adapter
+// =============================================================================
+
+/// Adapter for a real car's OBD-II port and a GPS receiver
+pub struct HardwareAdapter {
+    config: HardwareConfig,
+    readings: Arc<Mutex<Readings>>,
+    /// Tells the reader threads of the current run to finish
+    stop: Arc<AtomicBool>,
+    /// Reader threads of the current run, each holding its port open
+    threads: Vec<JoinHandle<()>>,
+    active: bool,
+}
+
+impl HardwareAdapter {
+    pub fn new(config: HardwareConfig) -> Self {
+        Self {
+            config,
+            readings: Arc::default(),
+            stop: Arc::new(AtomicBool::new(true)),
+            threads: Vec::new(),
+            active: false,
+        }
+    }
+
+    /// Stop the reader threads and wait for them to close their ports, which
+    /// takes at most a `serial::READ_TIMEOUT`
+    fn stop_threads(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+
+    /// Run `read` for `device` on a thread until told to stop, keeping its
+    /// error for `read_frame` to return
+    fn spawn(
+        &mut self,
+        device: SerialDevice,
+        read: fn(&SerialDevice, &Mutex<Readings>, &AtomicBool) -> Result<()>,
+    ) -> Result<()> {
+        let (readings, stop) = (self.readings.clone(), self.stop.clone());
+        let thread = std::thread::Builder::new()
+            .name(format!("hardware {}", device.port))
+            .spawn(move || {
+                if let Err(e) = read(&device, &readings, &stop) {
+                    if !stop.load(Ordering::Relaxed) {
+                        let error = e.context(format!("Reading {}", device.port));
+                        readings.lock().unwrap_or_else(|e| e.into_inner()).error = Some(error);
+                    }
+                }
+            })?;
+        self.threads.push(thread);
+        Ok(())
+    }
+}
+
+impl Drop for HardwareAdapter {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl TelemetryAdapter for HardwareAdapter {
+    fn key(&self) -> &str {
+        "hardware"
+    }
+
+    fn name(&self) -> &str {
+        "OBD-II / GPS"
+    }
+
+    /// Whether the configured devices are plugged in (paired, for Bluetooth)
+    fn detect(&self) -> bool {
+        #[cfg(unix)]
+        {
+            let mut devices = self.config.obd.iter().chain(&self.config.gps);
+            devices.all(|d| std::path::Path::new(&d.port).exists())
+        }
+        // COM ports can't be looked for without opening them
+        #[cfg(not(unix))]
+        {
+            true
+        }
+    }
+
+    fn start(&mut self) -> Result<(), AdapterError> {
+        // Threads of an earlier run still have the ports open
+        self.stop_threads();
+        self.readings = Arc::default();
+        self.stop = Arc::new(AtomicBool::new(false));
+        if let Some(obd) = self.config.obd.clone() {
+            self.spawn(obd, read_obd)?;
+        }
+        if let Some(gps) = self.config.gps.clone() {
+            self.spawn(gps, read_gps)?;
+        }
+        self.active = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), AdapterError> {
+        self.stop_threads();
+        self.active = false;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>, AdapterError> {
+        let mut readings = self.readings.lock().unwrap_or_else(|e| e.into_inner());
+        // A reader thread stopped, so start again once the device is back
+        if let Some(error) = readings.error.take() {
+            return Err(AdapterError::ConnectionLost(format!("{:#}", error)));
+        }
+        Ok(readings.frame(Instant::now()))
+    }
+
+    fn poll_hint(&self) -> PollHint {
+        PollHint::Interval(FRAME_INTERVAL)
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn capabilities(&self) -> &'static [&'static str] {
+        &[
+            "vehicle.speed",
+            "vehicle.rpm",
+            "vehicle.throttle",
+            "engine.water_temp",
+            "engine.oil_temp",
+            "engine.fuel_level_pct",
+            "motion.latitude",
+            "motion.longitude",
+            "motion.altitude",
+            "motion.heading",
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn test_obd_values() {
+        assert_eq!(obd_value(PID_RPM, "41 0C 1A F8"), Some(1726.0));
+        assert_eq!(obd_value(PID_SPEED, "SEARCHING...\n410D64"), Some(100.0));
+        assert_eq!(obd_value(PID_COOLANT_TEMP, "41 05 7B"), Some(83.0));
+        assert_eq!(obd_value(PID_THROTTLE, "41 11 FF"), Some(1.0));
+        assert_eq!(obd_value(PID_OIL_TEMP, "NO DATA"), None);
+        assert_eq!(obd_value(PID_RPM, "41 0C 1A"), None);
+        assert_eq!(obd_value(PID_SPEED, "41 0C 1A F8"), None);
+    }
+
+    /// A dongle answering each command from a script
+    struct FakeElm {
+        answers: HashMap<&'static str, &'static str>,
+        pending: VecDeque<u8>,
+    }
+
+    impl Read for FakeElm {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.pending.len());
+            for (b, p) in buf.iter_mut().zip(self.pending.drain(..n)) {
+                *b = p;
+            }
+            Ok(n)
+        }
+    }
+
+    impl Write for FakeElm {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let command = String::from_utf8_lossy(buf).trim().to_string();
+            let answer = self.answers.get(command.as_str()).unwrap_or(&"?");
+            self.pending.extend(format!("{}\r\r>", answer).into_bytes());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_elm327_polling() {
+        let answers = HashMap::from([
+            ("ATZ", "ELM327 v1.5"),
+            ("ATE0", "OK"),
+            ("ATL0", "OK"),
+            ("ATSP0", "OK"),
+            ("010C", "41 0C 0F A0"),
+            ("010D", "41 0D 50"),
+            ("0111", "41 11 33"),
+            ("0105", "41 05 5A"),
+            ("012F", "NO DATA"),
+            ("015C", "NO DATA"),
+        ]);
+        let stop = AtomicBool::new(false);
+        let mut elm = Elm327 {
+            port: FakeElm {
+                answers,
+                pending: VecDeque::new(),
+            },
+            stop: &stop,
+            timeout: REPLY_TIMEOUT,
+        };
+        elm.init().unwrap();
+        let readings = Mutex::new(Readings::default());
+        let answered = elm.poll(&PIDS, &readings).unwrap();
+        assert_eq!(
+            answered,
+            [PID_RPM, PID_SPEED, PID_THROTTLE, PID_COOLANT_TEMP]
+        );
+
+        let frame = readings.lock().unwrap().frame(Instant::now()).unwrap();
+        let vehicle = frame.vehicle.unwrap();
+        assert_eq!(vehicle.rpm, Some(Rpm(1000.0)));
+        assert!((vehicle.speed.unwrap().0 - 80.0 / 3.6).abs() < 1e-4);
+        assert_eq!(vehicle.throttle, Some(Percentage(0.2)));
+        let engine = frame.engine.unwrap();
+        assert_eq!(engine.water_temp, Some(Celsius(50.0)));
+        assert_eq!(engine.fuel_level_pct, None);
+        assert!(frame.motion.is_none());
+    }
+
+    /// A device that never sends anything, its reads timing out empty
+    struct Silent;
+
+    impl Read for Silent {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Ok(0)
+        }
+    }
+
+    impl Write for Silent {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_silent_devices() {
+        let stop = AtomicBool::new(false);
+        let mut elm = Elm327 {
+            port: Silent,
+            stop: &stop,
+            timeout: Duration::from_millis(50),
+        };
+        let error = elm.command("010C").unwrap_err();
+        assert_eq!(error.to_string(), "OBD-II dongle stopped answering");
+
+        let readings = Mutex::new(Readings::default());
+        let error = read_nmea(Silent, &readings, &stop, Duration::from_millis(50)).unwrap_err();
+        assert_eq!(error.to_string(), "GPS stopped sending");
+
+        // Stopping doesn't wait out the timeout
+        stop.store(true, Ordering::Relaxed);
+        elm.timeout = Duration::from_secs(3600);
+        assert!(elm.command("010C").is_err());
+        read_nmea(Silent, &readings, &stop, Duration::from_secs(3600)).unwrap();
+    }
+
+    /// A port returning one chunk per read, then timing out
+    struct Chunks(VecDeque<&'static [u8]>);
+
+    impl Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let chunk = self.0.pop_front().unwrap_or_default();
+            buf[..chunk.len()].copy_from_slice(chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    #[test]
+    fn test_nmea_split_reads() {
+        // A read timing out halfway through a sentence
+        let port = Chunks(VecDeque::from([
+            &b"$GPGGA,123520,4807.038,S,01131.000,"[..],
+            b"",
+            b"W,1,08,0.9,545.4,M,46.9,M,,*42\r\n",
+        ]));
+        let readings = Mutex::new(Readings::default());
+        let stop = AtomicBool::new(false);
+        assert!(read_nmea(port, &readings, &stop, Duration::from_millis(50)).is_err());
+        let gps = readings.lock().unwrap().gps.unwrap().0;
+        assert_eq!(gps.altitude, Some(545.4));
+    }
+
+    #[test]
+    fn test_nmea() {
+        let mut fix = None;
+        assert!(!apply_nmea("$GPGSV,3,1,11,03,03,111,00*4A", &mut fix));
+        // Bad checksum
+        assert!(!apply_nmea(
+            "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6B",
+            &mut fix
+        ));
+        assert!(apply_nmea(
+            "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A",
+            &mut fix
+        ));
+        let rmc = fix.unwrap();
+        assert!((rmc.latitude - 48.1173).abs() < 1e-6);
+        assert!((rmc.longitude - 11.516_667).abs() < 1e-6);
+        assert!((rmc.speed.unwrap() - 11.523_546).abs() < 1e-4);
+        assert_eq!(rmc.heading, Some(84.4));
+
+        assert!(apply_nmea(
+            "$GPGGA,123520,4807.038,S,01131.000,W,1,08,0.9,545.4,M,46.9,M,,*42",
+            &mut fix
+        ));
+        let gga = fix.unwrap();
+        assert!(gga.latitude < 0.0 && gga.longitude < 0.0);
+        assert_eq!(gga.altitude, Some(545.4));
+        // Kept from the RMC sentence
+        assert_eq!(gga.heading, Some(84.4));
+
+        // Lost the fix
+        assert!(!apply_nmea("$GPRMC,123521,V,,,,,,,230394,,*38", &mut fix));
+        assert!(fix.is_none());
+    }
+
+    #[test]
+    fn test_stale_readings() {
+        let start = Instant::now();
+        let readings = Readings {
+            gps: Some((
+                GpsFix {
+                    latitude: -33.4,
+                    longitude: 149.5,
+                    speed: Some(30.0),
+                    ..Default::default()
+                },
+                start,
+            )),
+            ..Default::default()
+        };
+        let frame = readings.frame(start).unwrap();
+        assert_eq!(frame.meta.game, "Real Car");
+        assert_eq!(frame.vehicle.unwrap().speed, Some(MetersPerSecond(30.0)));
+        assert_eq!(frame.motion.unwrap().latitude, Some(-33.4));
+        assert!(readings.frame(start + STALE_AFTER).is_none());
+    }
+
+    #[test]
+    fn test_config() {
+        let config: HardwareConfig = toml::from_str("gps = { port = \"/dev/ttyACM0\" }").unwrap();
+        config.check().unwrap();
+        assert_eq!(config.gps.unwrap().baud, None);
+        let empty: HardwareConfig = toml::from_str("").unwrap();
+        assert!(empty.check().is_err());
+    }
+}
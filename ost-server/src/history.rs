@@ -129,6 +129,15 @@ impl HistoryBuffer {
             .collect()
     }
 
+    /// Frames broadcast after sequence number `seq` (see `meta.seq`), oldest
+    /// first, for clients backfilling a gap. Capped like `get_frames_range`.
+    pub fn get_frames_since_seq(&self, seq: u64, count: usize) -> Vec<(usize, &TelemetryFrame)> {
+        let start = self
+            .frames
+            .partition_point(|f| f.meta.seq.is_none_or(|s| s <= seq));
+        self.get_frames_range(start, count)
+    }
+
     /// Number of frames currently in the buffer
     pub fn frame_count(&self) -> usize {
         self.frames.len()
@@ -229,52 +238,17 @@ impl HistoryBuffer {
 mod tests {
     use super::*;
     use chrono::Utc;
-    use ost_core::model::{MetaData, SessionData, TimingData};
+    use ost_core::model::{SessionData, TimingData, VehicleData};
     use ost_core::units::Seconds;
 
     fn make_frame(lap: Option<u32>, last_lap_time: Option<f64>) -> TelemetryFrame {
-        TelemetryFrame {
-            meta: MetaData {
-                timestamp: Utc::now(),
-                game: "test".to_string(),
-                tick: None,
-            },
-            motion: None,
-            vehicle: None,
-            engine: None,
-            wheels: None,
-            timing: Some(TimingData {
-                current_lap_time: None,
-                last_lap_time: last_lap_time.map(|v| Seconds(v as f32)),
-                best_lap_time: None,
-                best_n_lap_time: None,
-                best_n_lap_num: None,
-                sector_times: None,
-                lap_number: lap,
-                laps_completed: None,
-                lap_distance: None,
-                lap_distance_pct: None,
-                race_position: None,
-                class_position: None,
-                num_cars: None,
-                delta_best: None,
-                delta_best_ok: None,
-                delta_session_best: None,
-                delta_session_best_ok: None,
-                delta_optimal: None,
-                delta_optimal_ok: None,
-                estimated_lap_time: None,
-                race_laps: None,
-            }),
-            session: None,
-            weather: None,
-            pit: None,
-            electronics: None,
-            damage: None,
-            competitors: None,
-            driver: None,
-            extras: Default::default(),
-        }
+        let mut frame = TelemetryFrame::new("test");
+        frame.timing = Some(TimingData {
+            last_lap_time: last_lap_time.map(|v| Seconds(v as f32)),
+            lap_number: lap,
+            ..Default::default()
+        });
+        frame
     }
 
     #[test]
@@ -434,6 +408,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_frames_since_seq() {
+        let mut buf = HistoryBuffer::new(10);
+        for seq in 1..=10 {
+            let mut frame = make_frame(Some(1), None);
+            frame.meta.seq = Some(seq);
+            buf.push(frame);
+        }
+        let frames = buf.get_frames_since_seq(7, 100);
+        let seqs: Vec<_> = frames.iter().map(|(_, f)| f.meta.seq.unwrap()).collect();
+        assert_eq!(seqs, [8, 9, 10]);
+        assert_eq!(frames[0].0, 7);
+        assert_eq!(buf.get_frames_since_seq(7, 2).len(), 2);
+        assert!(buf.get_frames_since_seq(10, 100).is_empty());
+        // Evicted frames can't be backfilled; the client gets what's left
+        assert_eq!(buf.get_frames_since_seq(0, 100).len(), 10);
+    }
+
     #[test]
     fn test_get_frames_since_empty() {
         let buf = HistoryBuffer::new(10);
@@ -446,41 +438,12 @@ mod tests {
         let mut buf = HistoryBuffer::new(10);
         let mut frame = make_frame(Some(1), None);
         frame.session = Some(SessionData {
-            session_type: None,
-            session_state: None,
-            session_time: None,
-            session_time_remaining: None,
-            session_time_of_day: None,
-            session_laps: None,
-            session_laps_remaining: None,
-            flags: None,
             track_name: Some("Spa".to_string()),
-            track_config: None,
-            track_length: None,
-            track_type: None,
+            ..Default::default()
         });
-        frame.vehicle = Some(ost_core::model::VehicleData {
-            speed: None,
-            rpm: None,
-            max_rpm: None,
-            idle_rpm: None,
-            gear: None,
-            max_gears: None,
-            throttle: None,
-            brake: None,
-            clutch: None,
-            steering_angle: None,
-            steering_torque: None,
-            steering_torque_pct: None,
-            handbrake: None,
-            shift_indicator: None,
-            steering_angle_max: None,
-            on_track: None,
-            in_garage: None,
-            track_surface: None,
+        frame.vehicle = Some(VehicleData {
             car_name: Some("McLaren".to_string()),
-            car_class: None,
-            setup_name: None,
+            ..Default::default()
         });
         buf.push(frame);
         assert_eq!(buf.track_name(), "Spa");
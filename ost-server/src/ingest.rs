@@ -0,0 +1,324 @@
+//! Phone sensor ingestion
+//!
+//! For karting and track days without dedicated hardware: a phone app posts
+//! its accelerometer, gyroscope and GPS readings to `POST /api/ingest`, and
+//! the [`IngestAdapter`] turns them into frames. It is detected while samples
+//! are arriving and started like any sim adapter, so streams, recording and
+//! laps work on them.
+//!
+//! Vectors are in the car's axes as described in [`ost_core::coords`] (x
+//! right, y up, z forward), so the phone app accounts for how the phone is
+//! mounted. `accel` is what the accelerometer reads, gravity included.
+
+use ost_core::adapter::{Result, TelemetryAdapter};
+use ost_core::{model::*, units::*};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Most samples taken in one request
+pub const MAX_BATCH: usize = 1000;
+
+/// Frames kept for the adapter; room for two full batches, so one can arrive
+/// while the previous is being read
+const QUEUE_LEN: usize = 2 * MAX_BATCH;
+
+/// How long `read_frame()` waits for a sample
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Detected until no sample has arrived for this long
+const STALE_AFTER: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SensorVector {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GpsReading {
+    /// Degrees, WGS84
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Meters above sea level
+    #[serde(default)]
+    pub altitude: Option<f32>,
+    /// m/s
+    #[serde(default)]
+    pub speed: Option<f32>,
+    /// Degrees clockwise from true north
+    #[serde(default)]
+    pub heading: Option<f32>,
+}
+
+/// One reading from the phone
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SensorSample {
+    /// When it was taken, ms since the Unix epoch; arrival time if unset
+    #[serde(default)]
+    pub timestamp: Option<i64>,
+    /// Shown as the frame's game, "Phone Sensors" if unset
+    #[serde(default)]
+    pub source: Option<String>,
+    /// m/s², gravity included
+    #[serde(default)]
+    pub accel: Option<SensorVector>,
+    /// Pitch, yaw and roll rates about x, y and z, rad/s
+    #[serde(default)]
+    pub gyro: Option<SensorVector>,
+    #[serde(default)]
+    pub gps: Option<GpsReading>,
+}
+
+/// A `POST /api/ingest` body: one sample, or a batch, oldest first
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum IngestBody {
+    // Tried first: a sample's fields can also be read from an array, so `[]`
+    // would otherwise be one blank sample
+    Batch(Vec<SensorSample>),
+    One(SensorSample),
+}
+
+impl IngestBody {
+    pub fn into_samples(self) -> Vec<SensorSample> {
+        match self {
+            IngestBody::One(sample) => vec![sample],
+            IngestBody::Batch(samples) => samples,
+        }
+    }
+}
+
+impl SensorSample {
+    /// Whether it has any reading at all
+    pub fn has_readings(&self) -> bool {
+        self.accel.is_some() || self.gyro.is_some() || self.gps.is_some()
+    }
+
+    pub fn to_frame(&self) -> TelemetryFrame {
+        let mut frame = TelemetryFrame::new(self.source.as_deref().unwrap_or("Phone Sensors"));
+        if let Some(at) = self
+            .timestamp
+            .and_then(chrono::DateTime::from_timestamp_millis)
+        {
+            frame.meta.timestamp = at;
+        }
+
+        let mut motion = MotionData::default();
+        if let Some(a) = self.accel {
+            let [x, y, z] = [a.x, a.y, a.z].map(MetersPerSecondSquared);
+            motion.acceleration = Some(Vector3::new(x, y, z));
+            motion.g_force = Some(Vector3::new(
+                GForce::from_acceleration(x),
+                GForce::from_acceleration(y),
+                GForce::from_acceleration(z),
+            ));
+        }
+        if let Some(gyro) = self.gyro {
+            motion.pitch_rate = Some(DegreesPerSecond::from_radians(gyro.x));
+            motion.yaw_rate = Some(DegreesPerSecond::from_radians(gyro.y));
+            motion.roll_rate = Some(DegreesPerSecond::from_radians(gyro.z));
+        }
+        if let Some(gps) = self.gps {
+            motion.latitude = Some(gps.latitude);
+            motion.longitude = Some(gps.longitude);
+            motion.altitude = gps.altitude.map(Meters);
+            motion.heading = gps.heading.map(Degrees);
+            frame.vehicle = Some(VehicleData {
+                speed: gps.speed.map(MetersPerSecond),
+                ..Default::default()
+            });
+        }
+        frame.motion = Some(motion);
+        frame
+    }
+}
+
+/// Frames from `/api/ingest`, shared by the handler and the
+/// [`IngestAdapter`]. Cloning is cheap and shares the same queue.
+#[derive(Clone, Default)]
+pub struct IngestHub {
+    inner: Arc<(Mutex<Inbox>, Condvar)>,
+}
+
+#[derive(Default)]
+struct Inbox {
+    frames: VecDeque<TelemetryFrame>,
+    last_received: Option<Instant>,
+}
+
+impl IngestHub {
+    /// Queue a phone's samples for the adapter, all or none: `false` when the
+    /// queue has no room for them
+    pub fn push(&self, samples: &[SensorSample]) -> bool {
+        let mut inbox = self.lock();
+        inbox.last_received = Some(Instant::now());
+        if inbox.frames.len() + samples.len() > QUEUE_LEN {
+            return false;
+        }
+        inbox
+            .frames
+            .extend(samples.iter().map(SensorSample::to_frame));
+        self.inner.1.notify_one();
+        true
+    }
+
+    /// Whether samples have arrived lately
+    pub fn is_receiving(&self) -> bool {
+        self.lock()
+            .last_received
+            .is_some_and(|at| at.elapsed() < STALE_AFTER)
+    }
+
+    /// The next frame, waiting up to `timeout` for one
+    pub fn next_frame(&self, timeout: Duration) -> Option<TelemetryFrame> {
+        let (lock, ready) = &*self.inner;
+        let inbox = lock.lock().unwrap_or_else(|e| e.into_inner());
+        let (mut inbox, _) = ready
+            .wait_timeout_while(inbox, timeout, |i| i.frames.is_empty())
+            .unwrap_or_else(|e| e.into_inner());
+        inbox.frames.pop_front()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inbox> {
+        self.inner.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Adapter for phone sensor samples, detected while they are arriving
+pub struct IngestAdapter {
+    hub: IngestHub,
+    active: bool,
+}
+
+impl IngestAdapter {
+    pub fn new(hub: IngestHub) -> Self {
+        Self { hub, active: false }
+    }
+}
+
+impl TelemetryAdapter for IngestAdapter {
+    fn key(&self) -> &str {
+        "ingest"
+    }
+
+    fn name(&self) -> &str {
+        "Phone Sensors"
+    }
+
+    fn detect(&self) -> bool {
+        self.hub.is_receiving()
+    }
+
+    fn start(&mut self) -> Result<()> {
+        // Samples queued before starting are stale by now
+        self.hub.lock().frames.clear();
+        self.active = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.active = false;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>> {
+        if !self.active {
+            return Ok(None);
+        }
+        Ok(self.hub.next_frame(READ_TIMEOUT))
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn capabilities(&self) -> &'static [&'static str] {
+        &[
+            "motion.acceleration",
+            "motion.g_force",
+            "motion.pitch_rate",
+            "motion.yaw_rate",
+            "motion.roll_rate",
+            "motion.latitude",
+            "motion.longitude",
+            "motion.altitude",
+            "motion.heading",
+            "vehicle.speed",
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_to_frame() {
+        let body: IngestBody = serde_json::from_str(
+            r#"[{"timestamp": 1767225600250, "accel": {"x": 4.905, "y": 9.81, "z": 0.0},
+                 "gyro": {"x": 0.0, "y": 0.5, "z": 0.0}},
+                {"source": "Karting", "gps": {"latitude": 51.5, "longitude": -0.1, "speed": 12.5}}]"#,
+        )
+        .unwrap();
+        let samples = body.into_samples();
+        assert_eq!(samples.len(), 2);
+
+        let frame = samples[0].to_frame();
+        assert_eq!(frame.meta.game, "Phone Sensors");
+        assert_eq!(frame.meta.timestamp.timestamp_millis(), 1_767_225_600_250);
+        let motion = frame.motion.unwrap();
+        let g = motion.g_force.unwrap();
+        assert!((g.x.0 - 0.5).abs() < 1e-6 && (g.y.0 - 1.0).abs() < 1e-6);
+        assert!((motion.yaw_rate.unwrap().0 - 28.6479).abs() < 1e-3);
+        assert!(frame.vehicle.is_none());
+
+        let frame = samples[1].to_frame();
+        assert_eq!(frame.meta.game, "Karting");
+        assert_eq!(frame.vehicle.unwrap().speed, Some(MetersPerSecond(12.5)));
+        assert_eq!(frame.motion.unwrap().longitude, Some(-0.1));
+
+        assert!(serde_json::from_str::<IngestBody>(r#"{"accel": {"x": 1}}"#).is_err());
+
+        // An empty batch is no samples, not one blank sample
+        let empty: IngestBody = serde_json::from_str("[]").unwrap();
+        assert!(empty.into_samples().is_empty());
+        let blank: IngestBody = serde_json::from_str(r#"{"source": "Karting"}"#).unwrap();
+        assert!(!blank.into_samples()[0].has_readings());
+    }
+
+    #[test]
+    fn test_adapter_follows_hub() {
+        let hub = IngestHub::default();
+        let mut adapter = IngestAdapter::new(hub.clone());
+        assert!(!adapter.detect());
+
+        let sample: SensorSample =
+            serde_json::from_str(r#"{"gps": {"latitude": 1.0, "longitude": 2.0}}"#).unwrap();
+        hub.push(std::slice::from_ref(&sample));
+        assert!(adapter.detect());
+        adapter.start().unwrap();
+        assert!(adapter.read_frame().unwrap().is_none());
+
+        assert!(hub.push(&[sample.clone(), sample.clone()]));
+        assert!(adapter.read_frame().unwrap().is_some());
+        assert!(adapter.read_frame().unwrap().is_some());
+        assert!(adapter.read_frame().unwrap().is_none());
+
+        // A batch that doesn't fit is refused whole, keeping what's queued
+        let batch = vec![sample; MAX_BATCH];
+        assert!(hub.push(&batch));
+        assert!(hub.push(&batch[1..]));
+        assert!(!hub.push(&batch[..2]));
+        assert!(hub.push(&batch[..1]));
+        for _ in 0..QUEUE_LEN {
+            assert!(adapter.read_frame().unwrap().is_some());
+        }
+        assert!(adapter.read_frame().unwrap().is_none());
+    }
+}
@@ -0,0 +1,167 @@
+//! End-to-end frame latency
+//!
+//! Adapters stamp each frame with `meta.acquired_at` as they read it from the
+//! sim (the reader stamps frames from adapters that don't), and the server
+//! stamps `meta.broadcast_at` as it hands the frame to its subscribers.
+//! [`Latency`] keeps the most recent delays from acquisition at each
+//! [`Stage`] and serves their p50/p99 in `/metrics`.
+//!
+//! Frames relayed from another server keep its acquisition time, so their
+//! latency includes the network, and any difference between the two
+//! machines' clocks. Replayed frames carry no acquisition time and aren't
+//! measured.
+
+use chrono::Utc;
+use ost_core::model::TelemetryFrame;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Samples kept per stage for the quantiles
+const WINDOW: usize = 1024;
+
+/// Where a frame's latency is measured, each from its acquisition
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// Broadcast to streams and sinks, after the transform chain
+    Broadcast,
+    /// Serialized for a stream client
+    Stream,
+    /// Sent by an output sink
+    Sink,
+}
+
+impl Stage {
+    const ALL: [Stage; 3] = [Stage::Broadcast, Stage::Stream, Stage::Sink];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Stage::Broadcast => "broadcast",
+            Stage::Stream => "stream",
+            Stage::Sink => "sink",
+        }
+    }
+}
+
+/// Recent latency samples of every stage
+#[derive(Debug, Default)]
+pub struct Latency {
+    stages: [Mutex<Samples>; 3],
+}
+
+#[derive(Debug, Default)]
+struct Samples {
+    /// Seconds, newest last
+    recent: VecDeque<f64>,
+    count: u64,
+    sum: f64,
+}
+
+impl Latency {
+    /// Record how long ago `frame` was acquired, if it was
+    pub fn record(&self, stage: Stage, frame: &TelemetryFrame) {
+        let Some(acquired_at) = frame.meta.acquired_at else {
+            return;
+        };
+        let elapsed = (Utc::now() - acquired_at)
+            .to_std()
+            .unwrap_or_default()
+            .as_secs_f64();
+        self.record_secs(stage, elapsed);
+    }
+
+    fn record_secs(&self, stage: Stage, secs: f64) {
+        let mut samples = self.lock(stage);
+        if samples.recent.len() >= WINDOW {
+            samples.recent.pop_front();
+        }
+        samples.recent.push_back(secs);
+        samples.count += 1;
+        samples.sum += secs;
+    }
+
+    /// The `q` quantile (0-1) of the recent samples, in seconds
+    pub fn quantile(&self, stage: Stage, q: f64) -> Option<f64> {
+        let mut sorted: Vec<f64> = self.lock(stage).recent.iter().copied().collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_by(f64::total_cmp);
+        let rank = (q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64).round() as usize;
+        Some(sorted[rank])
+    }
+
+    /// Prometheus text exposition, as a summary per stage
+    pub fn prometheus(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# HELP ost_frame_latency_seconds Time from a frame's acquisition from the sim to each stage\n\
+             # TYPE ost_frame_latency_seconds summary"
+        );
+        for stage in Stage::ALL {
+            let name = stage.as_str();
+            for q in [0.5, 0.99] {
+                if let Some(value) = self.quantile(stage, q) {
+                    let _ = writeln!(
+                        out,
+                        "ost_frame_latency_seconds{{stage=\"{name}\",quantile=\"{q}\"}} {value}"
+                    );
+                }
+            }
+            let samples = self.lock(stage);
+            let _ = writeln!(
+                out,
+                "ost_frame_latency_seconds_sum{{stage=\"{name}\"}} {}\n\
+                 ost_frame_latency_seconds_count{{stage=\"{name}\"}} {}",
+                samples.sum, samples.count
+            );
+        }
+        out
+    }
+
+    fn lock(&self, stage: Stage) -> std::sync::MutexGuard<'_, Samples> {
+        self.stages[stage as usize]
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantiles() {
+        let latency = Latency::default();
+        assert_eq!(latency.quantile(Stage::Broadcast, 0.5), None);
+        for ms in 1..=100 {
+            latency.record_secs(Stage::Broadcast, ms as f64 / 1000.0);
+        }
+        assert_eq!(latency.quantile(Stage::Broadcast, 0.5), Some(0.051));
+        assert_eq!(latency.quantile(Stage::Broadcast, 0.99), Some(0.099));
+        assert_eq!(latency.quantile(Stage::Sink, 0.5), None);
+
+        // Only the most recent samples count
+        for _ in 0..WINDOW {
+            latency.record_secs(Stage::Broadcast, 0.002);
+        }
+        assert_eq!(latency.quantile(Stage::Broadcast, 0.99), Some(0.002));
+
+        let text = latency.prometheus();
+        assert!(text
+            .contains("ost_frame_latency_seconds{stage=\"broadcast\",quantile=\"0.5\"} 0.002\n"));
+        assert!(text.contains("ost_frame_latency_seconds_count{stage=\"broadcast\"} 1124\n"));
+        assert!(text.contains("ost_frame_latency_seconds_count{stage=\"sink\"} 0\n"));
+    }
+
+    #[test]
+    fn test_unacquired_frames_are_not_measured() {
+        let latency = Latency::default();
+        let mut frame = TelemetryFrame::new("test");
+        latency.record(Stage::Stream, &frame);
+        frame.meta.acquired_at = None;
+        latency.record(Stage::Stream, &frame);
+        assert_eq!(latency.lock(Stage::Stream).count, 1);
+    }
+}
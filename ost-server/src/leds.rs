@@ -0,0 +1,311 @@
+//! Shift-light LED patterns
+//!
+//! Turns RPM into the colors of a strip of LEDs for sinks with
+//! `"format": "leds"`. The bar fills from the car's first shift-light RPM to
+//! its last (`electronics.shift_light_*_rpm`), falling back to the game's
+//! `vehicle.shift_indicator` and then to fractions of `vehicle.max_rpm`.
+//! Past the blink RPM the whole strip flashes.
+//!
+//! A packet is the magic `"OL"`, a version byte (1), the LED count, then one
+//! RGB triplet per LED in strip order.
+
+use anyhow::{bail, Result};
+use ost_core::model::TelemetryFrame;
+use serde::{Deserialize, Serialize};
+
+/// Leading bytes of every packet
+pub const MAGIC: [u8; 2] = *b"OL";
+
+/// Bumped whenever the packet layout changes
+pub const VERSION: u8 = 1;
+
+/// Most LEDs a strip may have
+pub const MAX_LEDS: u8 = 64;
+
+/// Fallback thresholds as fractions of max RPM, when the car has no
+/// shift-light RPMs and the game no shift indicator
+const FALLBACK_FIRST: f32 = 0.80;
+const FALLBACK_LAST: f32 = 0.95;
+const FALLBACK_BLINK: f32 = 0.98;
+
+/// How long the strip stays on, then off, while blinking
+const BLINK_PERIOD_MS: i64 = 100;
+
+/// An sRGB color, written as `"#rrggbb"`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rgb(pub [u8; 3]);
+
+impl TryFrom<String> for Rgb {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let hex = s.strip_prefix('#').unwrap_or(&s);
+        match u32::from_str_radix(hex, 16) {
+            Ok(v) if hex.len() == 6 => Ok(Rgb([(v >> 16) as u8, (v >> 8) as u8, v as u8])),
+            _ => Err(format!("Invalid color '{}', expected #rrggbb", s)),
+        }
+    }
+}
+
+impl From<Rgb> for String {
+    fn from(c: Rgb) -> Self {
+        format!("#{:02x}{:02x}{:02x}", c.0[0], c.0[1], c.0[2])
+    }
+}
+
+/// Order in which LEDs light up
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedPattern {
+    /// First LED to last
+    #[default]
+    LeftToRight,
+    /// From both ends towards the middle
+    OutsideIn,
+    /// From the middle towards both ends
+    CenterOut,
+}
+
+/// Shift-light settings of a sink
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LedConfig {
+    /// Number of LEDs on the strip
+    pub count: u8,
+    pub pattern: LedPattern,
+    /// Exponent applied to the fill fraction: below 1 lights LEDs early in
+    /// the range, above 1 holds them back until near the shift point
+    pub curve: f32,
+    /// Colors of equal-sized zones along the fill order, e.g. green, yellow,
+    /// red
+    pub colors: Vec<Rgb>,
+    /// Color of the whole strip when blinking
+    pub blink_color: Rgb,
+}
+
+impl Default for LedConfig {
+    fn default() -> Self {
+        Self {
+            count: 16,
+            pattern: LedPattern::default(),
+            curve: 1.0,
+            colors: vec![Rgb([0, 255, 0]), Rgb([255, 255, 0]), Rgb([255, 0, 0])],
+            blink_color: Rgb([0, 0, 255]),
+        }
+    }
+}
+
+impl LedConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.count == 0 || self.count > MAX_LEDS {
+            bail!("LED count must be between 1 and {}", MAX_LEDS);
+        }
+        if self.colors.is_empty() {
+            bail!("At least one LED color is required");
+        }
+        if !(self.curve.is_finite() && self.curve > 0.0) {
+            bail!("LED curve must be a positive number");
+        }
+        Ok(())
+    }
+}
+
+/// Where a frame sits on the shift-light scale
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShiftLight {
+    /// How full the bar is, 0-1
+    pub fill: f32,
+    pub blink: bool,
+}
+
+/// The frame's shift-light state, if it has an RPM to show
+pub fn shift_light(frame: &TelemetryFrame) -> Option<ShiftLight> {
+    let vehicle = frame.vehicle.as_ref()?;
+    let rpm = vehicle.rpm?.0;
+    let e = frame.electronics.as_ref();
+    let first = e.and_then(|e| e.shift_light_first_rpm).map(|r| r.0);
+    let last = e
+        .and_then(|e| e.shift_light_last_rpm.or(e.shift_light_shift_rpm))
+        .map(|r| r.0);
+    let blink = e.and_then(|e| e.shift_light_blink_rpm).map(|r| r.0);
+
+    if let (Some(first), Some(last)) = (first, last) {
+        if last > first {
+            return Some(ShiftLight {
+                fill: ((rpm - first) / (last - first)).clamp(0.0, 1.0),
+                blink: blink.is_some_and(|b| rpm >= b),
+            });
+        }
+    }
+    if let Some(indicator) = vehicle.shift_indicator {
+        return Some(ShiftLight {
+            fill: indicator.0.clamp(0.0, 1.0),
+            blink: blink.is_some_and(|b| rpm >= b),
+        });
+    }
+    let max = vehicle.max_rpm?.0;
+    let (first, last) = (max * FALLBACK_FIRST, max * FALLBACK_LAST);
+    Some(ShiftLight {
+        fill: ((rpm - first) / (last - first)).clamp(0.0, 1.0),
+        blink: rpm >= blink.unwrap_or(max * FALLBACK_BLINK),
+    })
+}
+
+/// RGB of each LED in strip order
+pub fn colors(frame: &TelemetryFrame, config: &LedConfig) -> Vec<Rgb> {
+    let count = config.count as usize;
+    let off = Rgb([0, 0, 0]);
+    let Some(state) = shift_light(frame) else {
+        return vec![off; count];
+    };
+    if state.blink {
+        let phase = frame.meta.timestamp.timestamp_millis() / BLINK_PERIOD_MS;
+        let color = if phase % 2 == 0 {
+            config.blink_color
+        } else {
+            off
+        };
+        return vec![color; count];
+    }
+
+    // Mirrored patterns light two LEDs per step, so the fill and the zones
+    // follow the step rather than the position on the strip
+    let order = fill_order(count, config.pattern);
+    let steps = order.len();
+    let lit = (state.fill.powf(config.curve) * steps as f32).round() as usize;
+    let mut leds = vec![off; count];
+    for (step, (led, mirror)) in order.into_iter().enumerate().take(lit) {
+        let zone = step * config.colors.len() / steps;
+        leds[led] = config.colors[zone];
+        if let Some(mirror) = mirror {
+            leds[mirror] = config.colors[zone];
+        }
+    }
+    leds
+}
+
+/// The LED(s) each fill step lights: one, or a mirrored pair
+fn fill_order(count: usize, pattern: LedPattern) -> Vec<(usize, Option<usize>)> {
+    let pair = |i: usize| {
+        let mirror = count - 1 - i;
+        (i, (mirror != i).then_some(mirror))
+    };
+    match pattern {
+        LedPattern::LeftToRight => (0..count).map(|i| (i, None)).collect(),
+        LedPattern::OutsideIn => (0..count.div_ceil(2)).map(pair).collect(),
+        LedPattern::CenterOut => (0..count.div_ceil(2)).rev().map(pair).collect(),
+    }
+}
+
+/// The frame as an LED packet
+pub fn encode(frame: &TelemetryFrame, config: &LedConfig) -> Vec<u8> {
+    let leds = colors(frame, config);
+    let mut packet = Vec::with_capacity(4 + leds.len() * 3);
+    packet.extend_from_slice(&MAGIC);
+    packet.push(VERSION);
+    packet.push(leds.len() as u8);
+    for led in leds {
+        packet.extend_from_slice(&led.0);
+    }
+    packet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ost_core::model::{ElectronicsData, VehicleData};
+    use ost_core::units::Rpm;
+
+    fn frame(rpm: f32) -> TelemetryFrame {
+        let mut frame = TelemetryFrame::new("Demo");
+        frame.vehicle = Some(VehicleData {
+            rpm: Some(Rpm(rpm)),
+            max_rpm: Some(Rpm(8000.0)),
+            ..Default::default()
+        });
+        frame.electronics = Some(ElectronicsData {
+            shift_light_first_rpm: Some(Rpm(6000.0)),
+            shift_light_last_rpm: Some(Rpm(7000.0)),
+            shift_light_blink_rpm: Some(Rpm(7500.0)),
+            ..Default::default()
+        });
+        frame
+    }
+
+    fn lit(leds: &[Rgb]) -> String {
+        leds.iter()
+            .map(|c| if c.0 == [0, 0, 0] { '.' } else { '#' })
+            .collect()
+    }
+
+    #[test]
+    fn test_patterns_and_curve() {
+        let config = LedConfig {
+            count: 8,
+            ..Default::default()
+        };
+        assert_eq!(lit(&colors(&frame(5000.0), &config)), "........");
+        assert_eq!(lit(&colors(&frame(6500.0), &config)), "####....");
+        assert_eq!(lit(&colors(&frame(7200.0), &config)), "########");
+
+        let center = LedConfig {
+            pattern: LedPattern::CenterOut,
+            ..config.clone()
+        };
+        assert_eq!(lit(&colors(&frame(6500.0), &center)), "..####..");
+        let outside = LedConfig {
+            pattern: LedPattern::OutsideIn,
+            ..config.clone()
+        };
+        assert_eq!(lit(&colors(&frame(6500.0), &outside)), "##....##");
+
+        // A steeper curve holds the LEDs back
+        let late = LedConfig {
+            curve: 2.0,
+            ..config.clone()
+        };
+        assert_eq!(lit(&colors(&frame(6500.0), &late)), "##......");
+
+        // Zones: green first, red last
+        let full = colors(&frame(7000.0), &config);
+        assert_eq!(full[0], Rgb([0, 255, 0]));
+        assert_eq!(full[7], Rgb([255, 0, 0]));
+    }
+
+    #[test]
+    fn test_blink_and_fallback() {
+        let config = LedConfig::default();
+        let blinking = colors(&frame(7600.0), &config);
+        assert!(blinking.iter().all(|c| *c == blinking[0]));
+
+        // No shift-light RPMs: fractions of max RPM
+        let mut f = frame(7000.0);
+        f.electronics = None;
+        let leds = colors(&f, &config);
+        assert_eq!(lit(&leds), "########........");
+
+        let packet = encode(&f, &config);
+        assert_eq!(&packet[..4], b"OL\x01\x10");
+        assert_eq!(packet.len(), 4 + 16 * 3);
+    }
+
+    #[test]
+    fn test_config() {
+        let config: LedConfig = serde_json::from_value(
+            serde_json::json!({"count": 10, "colors": ["#00FF00", "ff0000"]}),
+        )
+        .unwrap();
+        assert_eq!(config.colors, [Rgb([0, 255, 0]), Rgb([255, 0, 0])]);
+        assert_eq!(config.pattern, LedPattern::LeftToRight);
+        assert!(
+            serde_json::from_value::<LedConfig>(serde_json::json!({"colors": ["red"]})).is_err()
+        );
+        assert!(LedConfig {
+            count: 0,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -3,11 +3,43 @@
 //! Exposes server components for integration testing.
 
 pub mod api;
+pub mod archive;
+pub mod competitors;
+pub mod compression;
+pub mod config;
+pub mod corners;
+pub mod dash;
+pub mod discord;
+pub mod discovery;
+pub mod events;
+pub mod ghost;
+pub mod hardware;
 pub mod history;
+pub mod ingest;
+pub mod latency;
+pub mod leds;
 pub mod manager;
+pub mod metrics;
+pub mod overlay;
+pub mod overview;
+pub mod pace;
 pub mod persistence;
+pub mod pits;
+pub mod registry;
+pub mod relay;
+pub mod remote;
 pub mod replay;
+pub mod replay_index;
+pub mod replay_store;
+pub mod serial;
 pub mod sessions;
+pub mod shared_frames;
+pub mod simhub;
 pub mod sinks;
+pub mod spotter;
 pub mod state;
+pub mod subscribers;
+pub mod suspension;
+pub mod transforms;
 pub mod web_ui;
+pub mod webhook;
@@ -2,8 +2,12 @@
 //!
 //! Main server application with web UI and REST API
 
-use anyhow::Result;
-use ost_server::{api, manager, persistence, sessions, state};
+use anyhow::{Context, Result};
+use ost_core::tracks::TrackDatabase;
+use ost_server::{
+    api, archive, config, discord, discovery, manager, persistence, registry, replay_store,
+    sessions, sinks, state, subscribers,
+};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tracing::info;
@@ -23,6 +27,52 @@ async fn main() -> Result<()> {
     // Create application state
     let mut state = state::AppState::new();
 
+    // Config file: --config <path>, OST_CONFIG, or the default location if present
+    let args: Vec<String> = std::env::args().collect();
+    let config_path = args
+        .iter()
+        .position(|a| a == "--config")
+        .and_then(|i| args.get(i + 1))
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("OST_CONFIG").map(std::path::PathBuf::from))
+        .or_else(|| Some(config::ServerConfig::default_path()).filter(|p| p.exists()));
+    let mut tls = None;
+    let mut discovery_config = config::DiscoveryConfig::default();
+    let mut discord_config = None;
+    if let Some(path) = config_path {
+        let config = config::ServerConfig::load(&path)?;
+        info!("Loaded config from {}", path.display());
+        tls = config.tls;
+        discovery_config = config.discovery;
+        discord_config = config.discord;
+        state.transforms = Arc::new(config.transforms);
+        state.adapter_priority = Arc::new(tokio::sync::RwLock::new(config.adapters.priority));
+        state.remote = config.remote;
+        state.hardware = config.hardware;
+        state.tracks = Arc::new(TrackDatabase::builtin().with_overrides(config.tracks));
+        state.replay_store = Arc::new(replay_store::ReplayStore::new(
+            std::env::temp_dir().join("ost-replay"),
+            config.replay_storage,
+        ));
+        if let Some(recording) = config.recording {
+            let mut persistence = state.persistence_config.write().await;
+            persistence.auto_record = recording.auto_record;
+            persistence.laps_only = recording.laps_only;
+            persistence.compression = recording.compression;
+            persistence.rotation = recording.rotation();
+        }
+        // Tokens from the config file replace OST_AUTH_TOKEN
+        if !config.auth.tokens.is_empty() {
+            state.auth = config.auth;
+        }
+    }
+    if !state.auth.tokens.is_empty() {
+        info!("API token auth enabled ({:?})", state.auth.scope);
+    }
+    state.adapter_registry = Arc::new(registry::AdapterRegistry::load(
+        config::data_dir().join("adapters.json"),
+    ));
+
     if serve_mode {
         info!("Starting OpenSimTelemetry Server in SERVE mode");
 
@@ -79,6 +129,12 @@ async fn main() -> Result<()> {
     // Build the router
     let app = api::create_router(state.clone());
 
+    // Keep the stream client stats on status streams current
+    tokio::spawn(subscribers::run(
+        state.subscribers.clone(),
+        state.subscribers_tx.clone(),
+    ));
+
     if !serve_mode {
         // Start adapter manager in background (not needed in serve mode)
         tokio::spawn(manager::run(state.clone()));
@@ -87,16 +143,94 @@ async fn main() -> Result<()> {
         let persistence_rx = state.subscribe();
         tokio::spawn(persistence::run(
             state.persistence_config.clone(),
+            state.archive.clone(),
             persistence_rx,
         ));
+
+        // Forward telemetry to the configured output sinks
+        tokio::spawn(sinks::run(
+            state.sinks.clone(),
+            state.latency.clone(),
+            state.subscribe(),
+        ));
+
+        // Post session events to Discord
+        if let Some(discord_config) = discord_config {
+            info!("Posting session events to Discord");
+            tokio::spawn(discord::run(discord_config, state.events_tx.subscribe()));
+        }
+
+        // Clear out uploads left behind, then keep them within limits
+        tokio::spawn(replay_store::run(
+            state.replay_store.clone(),
+            state.replay.clone(),
+        ));
+
+        // Drop recordings deleted while the server was down, and index
+        // those made before the archive existed
+        let archive = state.archive.clone();
+        tokio::task::spawn_blocking(move || {
+            archive::prune(&archive);
+            archive::index_unindexed(&archive);
+        });
     }
 
     // Start server
     let addr = SocketAddr::from(([0, 0, 0, 0], 9100));
-    info!("Server listening on http://{}", addr);
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // Advertise on the LAN; the daemon must outlive the server
+    let _mdns = if discovery_config.mdns && !serve_mode {
+        let announcement = discovery::Announcement::new(addr.port(), tls.is_some());
+        match discovery::advertise_mdns(&announcement) {
+            Ok(daemon) => {
+                info!(
+                    "Advertising \"{}\" as {}",
+                    announcement.name,
+                    discovery::MDNS_SERVICE_TYPE
+                );
+                Some(daemon)
+            }
+            Err(e) => {
+                tracing::warn!("mDNS advertisement failed: {:#}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    if discovery_config.beacon && !serve_mode {
+        let announcement = discovery::Announcement::new(addr.port(), tls.is_some());
+        tokio::spawn(async move {
+            if let Err(e) = discovery::run_beacon(announcement).await {
+                tracing::warn!("Discovery beacon failed: {:#}", e);
+            }
+        });
+    }
+    match tls {
+        Some(tls) => {
+            // Only ring is compiled in; ignore the error if already installed
+            let _ = rustls::crypto::ring::default_provider().install_default();
+            let rustls_config =
+                axum_server::tls_rustls::RustlsConfig::from_pem_file(&tls.cert, &tls.key)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "loading TLS cert {} and key {}",
+                            tls.cert.display(),
+                            tls.key.display()
+                        )
+                    })?;
+            info!("Server listening on https://{}", addr);
+            axum_server::bind_rustls(addr, rustls_config)
+                .serve(app.into_make_service())
+                .await?;
+        }
+        None => {
+            info!("Server listening on http://{}", addr);
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            axum::serve(listener, app).await?;
+        }
+    }
 
     Ok(())
 }
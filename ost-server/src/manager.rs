@@ -3,173 +3,782 @@
 //! This module handles:
 //! - Polling adapters for game detection
 //! - Starting/stopping adapters when games are detected/exit
-//! - Reading frames from active adapters
+//! - Running a dedicated reader task for the active adapter
 //! - Broadcasting frames to subscribers
 
 use crate::api::broadcast_adapter_status;
-use crate::state::AppState;
+use crate::latency::Stage;
+use crate::registry;
+use crate::state::{AdapterSlot, AppState, SessionInfo};
+use crate::transforms::TransformChain;
 use anyhow::Result;
-use ost_adapters::{DemoAdapter, IRacingAdapter};
-use std::time::Duration;
-use tokio::time::sleep;
+use ost_core::{ConnectionState, PollHint};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
 const DETECTION_INTERVAL: Duration = Duration::from_secs(1);
-const IDLE_INTERVAL: Duration = Duration::from_millis(100); // Sleep when no active adapter
+const IDLE_INTERVAL: Duration = Duration::from_millis(100); // Reader poll while a replay is active
 
-/// Main manager loop
+/// Main manager entry point: registers the built-in adapters and runs detection
 pub async fn run(state: AppState) {
-    // Register adapters
-    state
-        .register_adapter(Box::new(IRacingAdapter::new()))
-        .await;
-    state.register_adapter(Box::new(DemoAdapter::new())).await;
-    broadcast_adapter_status(&state).await;
+    let manager = Manager::new(state);
+    manager.register_builtin_adapters().await;
+    manager.run().await;
+}
 
-    info!("Adapter manager started");
+/// Adapter lifecycle manager.
+///
+/// Runs detection on a fixed interval. Frames are read by the active adapter's
+/// reader task, so detection timing is independent of frame reads.
+pub struct Manager {
+    state: AppState,
+    detection_interval: Duration,
+}
 
-    loop {
-        // Check for game detection
-        if let Err(e) = detection_cycle(&state).await {
-            error!("Error in detection cycle: {}", e);
+impl Manager {
+    pub fn new(state: AppState) -> Self {
+        Self {
+            state,
+            detection_interval: DETECTION_INTERVAL,
         }
+    }
 
-        // Read frames from active adapter.
-        // When an adapter is active, read_frame() blocks until data is available
-        // (up to the adapter's own timeout), so no additional sleep is needed.
-        // When idle, sleep briefly before the next detection check.
-        match frame_read_cycle(&state).await {
-            Ok(true) => {
-                // Frame was read or adapter is active — loop immediately
-            }
-            Ok(false) => {
-                // No active adapter — sleep before next detection check
-                sleep(IDLE_INTERVAL).await;
-            }
-            Err(e) => {
-                error!("Error reading frames: {}", e);
-                sleep(IDLE_INTERVAL).await;
+    /// Override how often adapters are checked for detection
+    pub fn with_detection_interval(mut self, interval: Duration) -> Self {
+        self.detection_interval = interval;
+        self
+    }
+
+    /// Register the adapters that ship with the server, less any unregistered
+    pub async fn register_builtin_adapters(&self) {
+        registry::register_builtins(&self.state).await;
+        let adapters = self.state.adapters.read().await;
+        for key in self.state.adapter_priority.read().await.iter() {
+            let known = registry::BUILTIN.contains(&key.as_str())
+                || adapters.iter().any(|slot| slot.key() == key);
+            if !known {
+                warn!("Unknown adapter '{}' in the priority list", key);
             }
         }
+        drop(adapters);
+        broadcast_adapter_status(&self.state).await;
     }
-}
 
-/// Check all adapters for game detection
-async fn detection_cycle(state: &AppState) -> Result<()> {
-    static mut LAST_CHECK: Option<std::time::Instant> = None;
+    /// Run detection forever
+    pub async fn run(self) {
+        info!("Adapter manager started");
 
-    // Rate limit detection checks to once per second
-    unsafe {
-        if let Some(last) = LAST_CHECK {
-            if last.elapsed() < DETECTION_INTERVAL {
-                return Ok(());
+        let mut ticker = tokio::time::interval(self.detection_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.detection_cycle().await {
+                error!("Error in detection cycle: {}", e);
             }
         }
-        LAST_CHECK = Some(std::time::Instant::now());
     }
 
-    let mut changed = false;
+    /// Check all adapters for game detection and start a reader for the first
+    /// detected one, in priority order. Loss of detection for the active
+    /// adapter is handled by its reader task.
+    pub async fn detection_cycle(&self) -> Result<()> {
+        let state = &self.state;
 
-    {
-        let mut adapters = state.adapters.write().await;
-        let mut active_adapter = state.active_adapter.write().await;
-
-        // If we have an active adapter, check if it's still detected
-        if let Some(ref active_key) = *active_adapter {
-            if let Some(adapter) = adapters.iter_mut().find(|a| a.key() == active_key) {
-                if !adapter.detect() {
-                    info!(
-                        "Game {} no longer detected, stopping adapter",
-                        adapter.name()
-                    );
-                    if let Err(e) = adapter.stop() {
-                        error!("Error stopping adapter {}: {}", adapter.name(), e);
-                    }
-                    *active_adapter = None;
-                    changed = true;
-                }
-                if changed {
-                    drop(adapters);
-                    drop(active_adapter);
-                    broadcast_adapter_status(state).await;
-                }
-                return Ok(());
-            }
+        let active = state.active_adapter.read().await.clone();
+        if let Some(active) = active {
+            self.preempt(&active).await;
+            return Ok(());
         }
 
-        // No active adapter, look for detected games (skip disabled adapters)
-        let disabled = state.disabled_adapters.read().await;
-        for adapter in adapters.iter_mut() {
-            if disabled.contains(adapter.key()) {
-                continue;
-            }
-            if adapter.detect() && !adapter.is_active() {
-                info!("Game {} detected, starting adapter", adapter.name());
-                match adapter.start() {
-                    Ok(_) => {
-                        *active_adapter = Some(adapter.key().to_string());
-                        info!("Adapter {} started successfully", adapter.name());
-                        changed = true;
-                        break;
-                    }
-                    Err(e) => {
-                        error!("Failed to start adapter {}: {}", adapter.name(), e);
+        let mut started = None;
+        let mut unsupported = Vec::new();
+
+        {
+            let adapters = state.adapters.read().await;
+            let priority = state.adapter_priority.read().await;
+            let mut active_adapter = state.active_adapter.write().await;
+            let disabled = state.disabled_adapters.read().await;
+
+            // Look for detected games (skip disabled adapters and those
+            // backing off after a restart)
+            for slot in by_priority(&adapters, &priority) {
+                if !startable(slot, &disabled) {
+                    continue;
+                }
+                let mut adapter = slot.lock();
+                if adapter.detect() && !adapter.is_active() {
+                    info!("Game {} detected, starting adapter", slot.name());
+                    match adapter.start() {
+                        Ok(_) => {
+                            slot.lock_stats().restart_at = None;
+                            *active_adapter = Some(slot.key().to_string());
+                            info!("Adapter {} started successfully", slot.name());
+                            started = Some(slot.clone());
+                            break;
+                        }
+                        Err(e) => {
+                            error!("Failed to start adapter {}: {}", slot.name(), e);
+                            slot.lock_stats().record_error(&e);
+                            // Retrying can't help, so stop trying until re-enabled
+                            if e.is_fatal() {
+                                unsupported.push(slot.key().to_string());
+                            }
+                        }
                     }
                 }
             }
         }
+
+        let changed = started.is_some() || !unsupported.is_empty();
+        if !unsupported.is_empty() {
+            warn!("Disabling unsupported adapters: {}", unsupported.join(", "));
+            state.disabled_adapters.write().await.extend(unsupported);
+        }
+        if let Some(slot) = started {
+            self.spawn_reader(slot).await;
+        }
+        if changed {
+            broadcast_adapter_status(state).await;
+        }
+
+        Ok(())
+    }
+
+    /// Stop the active adapter when one ahead of it in the priority list is
+    /// detected, so the next cycle starts that one instead
+    async fn preempt(&self, active: &str) {
+        let state = &self.state;
+        let preferred = {
+            let adapters = state.adapters.read().await;
+            let priority = state.adapter_priority.read().await;
+            let disabled = state.disabled_adapters.read().await;
+            let ahead = priority
+                .iter()
+                .position(|key| key == active)
+                .unwrap_or(priority.len());
+            priority[..ahead]
+                .iter()
+                .filter_map(|key| adapters.iter().find(|slot| slot.key() == key))
+                .find(|slot| startable(slot, &disabled) && slot.lock().detect())
+                .map(|slot| slot.name().to_string())
+        };
+        let Some(preferred) = preferred else {
+            return;
+        };
+        if state.deactivate_adapter(active).await {
+            info!("{} detected, stopping {} for it", preferred, active);
+            broadcast_adapter_status(state).await;
+        }
     }
 
-    if changed {
-        broadcast_adapter_status(state).await;
+    /// Spawn the reader task for a freshly started adapter, cancelling any previous one.
+    async fn spawn_reader(&self, slot: AdapterSlot) {
+        let cancel = CancellationToken::new();
+        if let Some(previous) = self
+            .state
+            .reader_cancel
+            .write()
+            .await
+            .replace(cancel.clone())
+        {
+            previous.cancel();
+        }
+        let state = self.state.clone();
+        let detection_interval = self.detection_interval;
+        tokio::task::spawn_blocking(move || reader_loop(state, slot, detection_interval, cancel));
     }
+}
 
-    Ok(())
+/// `adapters` in the order detection tries them: those in `priority` first,
+/// in its order, then the rest as registered
+fn by_priority<'a>(adapters: &'a [AdapterSlot], priority: &[String]) -> Vec<&'a AdapterSlot> {
+    let rank = |slot: &AdapterSlot| {
+        priority
+            .iter()
+            .position(|key| key == slot.key())
+            .unwrap_or(priority.len())
+    };
+    let mut ordered: Vec<_> = adapters.iter().collect();
+    ordered.sort_by_key(|slot| rank(slot));
+    ordered
 }
 
-/// Read frames from the active adapter and broadcast them.
-/// Returns `true` if an adapter is active (even if no frame was available this tick),
-/// `false` if no adapter is active (caller should sleep).
-async fn frame_read_cycle(state: &AppState) -> Result<bool> {
-    // Don't send adapter frames while a replay is active
+/// Whether detection may start `slot`: supported on this platform, enabled
+/// and not backing off after a restart
+fn startable(slot: &AdapterSlot, disabled: &HashSet<String>) -> bool {
+    slot.unsupported().is_none()
+        && !disabled.contains(slot.key())
+        && slot.lock_stats().restart_due()
+}
+
+/// Dedicated reader for the active adapter, running on the blocking thread pool.
+///
+/// Calls `read_frame()` paced by the adapter's [`PollHint`], pushes frames into
+/// the history buffer and broadcast channel, and once per detection interval
+/// re-checks detection and publishes updated [`AdapterStats`] on the status
+/// stream. Stops the adapter and exits when cancelled, when the game is no
+/// longer detected, or when a read reports the sim gone, leaving detection to
+/// start it again. A read error that retrying can't help, as when the
+/// adapter turns out to be unsupported, disables it as a failed start does.
+/// Other read errors are recorded and reading carries on, until so many fail
+/// in a row that the adapter is restarted after a backoff under its
+/// [`RestartPolicy`](crate::state::RestartPolicy).
+fn reader_loop(
+    state: AppState,
+    slot: AdapterSlot,
+    detection_interval: Duration,
+    cancel: CancellationToken,
+) {
+    let runtime = tokio::runtime::Handle::current();
+    let key = slot.key().to_string();
+    let mut last_detect = Instant::now();
+    let mut last_published: Option<Instant> = None;
+    // Session metadata is checked on the first frame, whenever the adapter
+    // flags new session info, and otherwise once per detection interval
+    let mut session_pending = true;
+    let mut transforms = TransformChain::new(&state.transforms);
+    // Track database disagreements are only worth a warning once
+    let mut sectors_warned = false;
     {
-        let replay = state.replay.read().await;
-        if replay.is_some() {
-            return Ok(false);
-        }
+        let mut stats = slot.lock_stats();
+        stats.reset_window();
+        stats.roll_window();
     }
 
-    let active_key = {
-        let active = state.active_adapter.read().await;
-        active.clone()
-    };
+    while !cancel.is_cancelled() {
+        // Don't send adapter frames while a replay is active
+        if state.replay.blocking_read().is_some() {
+            std::thread::sleep(IDLE_INTERVAL);
+            continue;
+        }
 
-    let Some(active_key) = active_key else {
-        return Ok(false);
-    };
+        let config = slot.config();
+        let min_interval = config.min_frame_interval();
 
-    let mut adapters = state.adapters.write().await;
+        let (result, delay, detected, session_raw, connection) = {
+            let mut adapter = slot.lock();
+            let read_started = Instant::now();
+            let result = adapter.read_frame();
+            // Interval adapters are simply polled less often when rate-limited
+            let delay = match adapter.poll_hint() {
+                PollHint::Blocking => Duration::ZERO,
+                PollHint::Interval(interval) => interval
+                    .max(min_interval.unwrap_or_default())
+                    .saturating_sub(read_started.elapsed()),
+            };
+            // Re-check detection once per interval (None when not checked this tick)
+            let detected = if last_detect.elapsed() >= detection_interval {
+                last_detect = Instant::now();
+                Some(adapter.detect())
+            } else {
+                None
+            };
+            session_pending |= adapter.session_info_changed() || detected.is_some();
+            let session_raw = session_pending.then(|| adapter.session_info_raw());
+            let connection = adapter.connection_state();
+            (result, delay, detected, session_raw, connection)
+        };
+
+        if state.update_connection(&slot, connection) {
+            info!("{} connection: {:?}", key, connection);
+            runtime.block_on(broadcast_adapter_status(&state));
+        }
 
-    if let Some(adapter) = adapters.iter_mut().find(|a| a.key() == active_key) {
-        match adapter.read_frame() {
-            Ok(Some(frame)) => {
-                // Store in history buffer for seek-back
+        let (mut lost, mut fatal, mut wedged) = (false, false, false);
+        match result {
+            Ok(Some(mut frame)) => {
+                // Frames the adapter didn't stamp were acquired as they were read
+                frame.meta.acquired_at.get_or_insert_with(chrono::Utc::now);
+                frame.meta.adapter = Some(key.clone());
+                if !state.tracks.enrich(&mut frame) && !sectors_warned {
+                    sectors_warned = true;
+                    let track = frame.session.as_ref().and_then(|s| s.track_id.as_deref());
+                    warn!(
+                        "{} reports different sectors for {} than the track database",
+                        key,
+                        track.unwrap_or_default()
+                    );
+                }
+                transforms.apply(&mut frame);
+                let issues = frame.validate(config.validation);
                 {
-                    let mut history = state.history.write().await;
-                    history.push(frame.clone());
+                    let mut stats = slot.lock_stats();
+                    stats.record_frame(&frame);
+                    stats.record_validation(&issues);
+                }
+                state.update_spotter(Some(&frame));
+                state.update_events(Some(&frame));
+                if let Some(raw) = session_raw {
+                    let raw = raw.filter(|_| !state.redacting());
+                    state.update_session_info(Some(SessionInfo::from_frame(&key, &frame, raw)));
+                    session_pending = false;
+                }
+                // Blocking adapters keep reading at their native rate (so they
+                // don't fall behind the game) and we skip frames to honour the limit
+                let due = match (min_interval, last_published) {
+                    (Some(min), Some(last)) => last.elapsed() >= min,
+                    _ => true,
+                };
+                if due {
+                    last_published = Some(Instant::now());
+                    state.assign_seq(&mut frame);
+                    state.latency.record(Stage::Broadcast, &frame);
+                    // Store in history buffer for seek-back
+                    state.history.blocking_write().push(frame.clone());
+                    // Broadcast to all subscribers
+                    // Ignore error if no receivers (they'll get the next frame)
+                    let _ = state.telemetry_tx.send(frame);
                 }
-                // Broadcast to all subscribers
-                // Ignore error if no receivers (they'll get the next frame)
-                let _ = state.telemetry_tx.send(frame);
             }
             Ok(None) => {
                 // No data available this tick, adapter will provide data on next call
             }
             Err(e) => {
-                warn!("Error reading frame from {}: {}", active_key, e);
+                warn!("Error reading frame from {}: {}", key, e);
+                let mut stats = slot.lock_stats();
+                stats.record_read_error(&e);
+                lost = e.is_disconnect();
+                fatal = e.is_fatal();
+                wedged = config.restart.exceeded(stats.consecutive_errors);
+            }
+        }
+
+        let stopping = match (detected, lost) {
+            (Some(false), _) => {
+                info!("Game {} no longer detected, stopping adapter", key);
+                true
+            }
+            (_, true) => {
+                info!("Lost connection to {}, stopping adapter", key);
+                true
+            }
+            _ if fatal => {
+                // Retrying can't help, so stop trying until re-enabled
+                warn!("Disabling unsupported adapter: {}", key);
+                state.disabled_adapters.blocking_write().insert(key.clone());
+                true
+            }
+            _ if wedged => {
+                let mut stats = slot.lock_stats();
+                let errors = stats.consecutive_errors;
+                let backoff = stats.schedule_restart(&config.restart);
+                warn!(
+                    "{} failed {} reads in a row, restarting in {:?}",
+                    key, errors, backoff
+                );
+                true
+            }
+            (Some(true), false) => {
+                // Periodic health update for the status stream
+                slot.lock_stats().roll_window();
+                runtime.block_on(broadcast_adapter_status(&state));
+                false
+            }
+            (None, false) => false,
+        };
+        if stopping {
+            let mut active = state.active_adapter.blocking_write();
+            if active.as_deref() == Some(key.as_str()) {
+                *active = None;
+            }
+            break;
+        }
+
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+    }
+
+    if let Err(e) = slot.lock().stop() {
+        error!("Error stopping adapter {}: {}", key, e);
+        slot.lock_stats().record_error(&e);
+    }
+    slot.lock_stats().reset_window();
+    state.update_connection(&slot, ConnectionState::Disconnected);
+    state.update_session_info(None);
+    state.update_spotter(None);
+    state.update_events(None);
+    runtime.block_on(broadcast_adapter_status(&state));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ost_adapters::DemoAdapter;
+
+    async fn state_with_demo(enabled: bool) -> AppState {
+        let state = AppState::new();
+        state.register_adapter(Box::new(DemoAdapter::new())).await;
+        if enabled {
+            state.disabled_adapters.write().await.remove("demo");
+        }
+        state
+    }
+
+    /// Cancel the running reader and wait for it to stop `slot`, so dropping
+    /// the runtime doesn't wait on a reader that never exits
+    async fn stop_reader(state: &AppState, slot: &AdapterSlot) {
+        state.reader_cancel.write().await.take().unwrap().cancel();
+        let stopped = tokio::time::timeout(Duration::from_secs(2), async {
+            while slot.lock().is_active() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(stopped.is_ok(), "adapter should stop after cancellation");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_detection_skips_disabled_adapter() {
+        let state = state_with_demo(false).await;
+        let manager = Manager::new(state.clone());
+
+        manager.detection_cycle().await.unwrap();
+
+        assert!(state.active_adapter.read().await.is_none());
+        assert!(state.reader_cancel.read().await.is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_detection_starts_reader_and_cancel_stops_adapter() {
+        let state = state_with_demo(true).await;
+        let mut rx = state.subscribe();
+        let mut connection_rx = state.connection_tx.subscribe();
+        let manager = Manager::new(state.clone());
+
+        manager.detection_cycle().await.unwrap();
+        assert_eq!(state.active_adapter.read().await.as_deref(), Some("demo"));
+
+        // The reader task pushes frames into the broadcast channel
+        let frame = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("reader should produce a frame")
+            .unwrap();
+        assert_eq!(frame.meta.game, "Demo");
+        assert_eq!(frame.meta.adapter.as_deref(), Some("demo"));
+        // Broadcast frames are numbered consecutively
+        let next = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.meta.seq, Some(frame.meta.seq.unwrap() + 1));
+
+        // Session metadata is published with the first frame
+        let session = state.session_info.read().unwrap().clone().unwrap();
+        assert_eq!(session.adapter, "demo");
+        assert!(session.track_name.is_some());
+
+        // A second cycle with an active adapter is a no-op
+        manager.detection_cycle().await.unwrap();
+        assert_eq!(state.active_adapter.read().await.as_deref(), Some("demo"));
+
+        // Cancelling the reader stops the adapter
+        state.reader_cancel.write().await.take().unwrap().cancel();
+        let slot = state.adapters.read().await[0].clone();
+        let stopped = tokio::time::timeout(Duration::from_secs(2), async {
+            while slot.lock().is_active() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
             }
+        })
+        .await;
+        assert!(stopped.is_ok(), "adapter should stop after cancellation");
+
+        // Connecting and stopping are published as connection events
+        let mut transitions = Vec::new();
+        for _ in 0..2 {
+            let event = tokio::time::timeout(Duration::from_secs(2), connection_rx.recv())
+                .await
+                .expect("connection event")
+                .unwrap();
+            assert_eq!(event.adapter, "demo");
+            transitions.push((event.previous, event.state));
+        }
+        assert_eq!(
+            transitions,
+            [
+                (ConnectionState::Disconnected, ConnectionState::Connected),
+                (ConnectionState::Connected, ConnectionState::Disconnected),
+            ]
+        );
+        assert_eq!(slot.stats().connection, ConnectionState::Disconnected);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scripted_lifecycle_failures() {
+        use ost_adapters::scripted::Step;
+        use ost_adapters::ScriptedAdapter;
+        use ost_core::model::TelemetryFrame;
+
+        let adapter = ScriptedAdapter::new("scripted")
+            .fail_start("not ready")
+            .frame(TelemetryFrame::new("Scripted"))
+            .error("read failed")
+            .frame(TelemetryFrame::new("Scripted"));
+        let script = adapter.handle();
+        let state = AppState::new();
+        state.register_adapter(Box::new(adapter)).await;
+        let slot = state.adapters.read().await[0].clone();
+        let mut rx = state.subscribe();
+        let manager =
+            Manager::new(state.clone()).with_detection_interval(Duration::from_millis(20));
+
+        // A failed start is recorded and retried on the next cycle
+        manager.detection_cycle().await.unwrap();
+        assert!(state.active_adapter.read().await.is_none());
+        assert_eq!(slot.stats().last_error.as_deref(), Some("not ready"));
+        manager.detection_cycle().await.unwrap();
+        assert_eq!(
+            state.active_adapter.read().await.as_deref(),
+            Some("scripted")
+        );
+
+        // A read error doesn't stop the reader
+        for _ in 0..2 {
+            tokio::time::timeout(Duration::from_secs(2), rx.recv())
+                .await
+                .expect("reader should keep publishing")
+                .unwrap();
         }
+        assert_eq!(slot.stats().last_error.as_deref(), Some("read failed"));
+        assert_eq!(slot.stats().frames_total, 2);
+
+        // The game going away stops the adapter and frees the active slot
+        script.push(Step::Disconnect);
+        let stopped = tokio::time::timeout(Duration::from_secs(2), async {
+            while state.active_adapter.read().await.is_some() || script.stops() == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(stopped.is_ok(), "adapter should stop once undetected");
+        assert_eq!((script.starts(), script.stops()), (2, 1));
     }
 
-    Ok(true)
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_error_kinds_retry_or_disable() {
+        use ost_adapters::ScriptedAdapter;
+        use ost_core::model::TelemetryFrame;
+        use ost_core::ErrorKind;
+
+        let unsupported = ScriptedAdapter::new("unsupported")
+            .fail_start_as(ErrorKind::Unsupported, "only available on Windows");
+        let unsupported_script = unsupported.handle();
+        let adapter = ScriptedAdapter::new("scripted")
+            .frame(TelemetryFrame::new("Scripted"))
+            .error_as(ErrorKind::ConnectionLost, "sim closed the socket")
+            .frame(TelemetryFrame::new("Scripted"));
+        let script = adapter.handle();
+        let state = AppState::new();
+        state.register_adapter(Box::new(unsupported)).await;
+        state.register_adapter(Box::new(adapter)).await;
+        let slot = state.adapters.read().await[1].clone();
+        let mut rx = state.subscribe();
+        let manager =
+            Manager::new(state.clone()).with_detection_interval(Duration::from_millis(20));
+
+        // An unsupported adapter is disabled rather than retried
+        manager.detection_cycle().await.unwrap();
+        assert!(state.disabled_adapters.read().await.contains("unsupported"));
+        let stats = state.adapters.read().await[0].stats();
+        assert_eq!(stats.last_error_kind, Some(ErrorKind::Unsupported));
+        assert_eq!(
+            state.active_adapter.read().await.as_deref(),
+            Some("scripted")
+        );
+
+        // A lost connection stops the reader, and detection starts it again
+        tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let stopped = tokio::time::timeout(Duration::from_secs(2), async {
+            while state.active_adapter.read().await.is_some() || script.stops() == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(stopped.is_ok(), "adapter should stop once the sim is gone");
+        let stats = slot.stats();
+        assert_eq!(stats.last_error_kind, Some(ErrorKind::ConnectionLost));
+        assert_eq!(stats.errors.get(&ErrorKind::ConnectionLost), Some(&1));
+
+        manager.detection_cycle().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("restarted reader should publish")
+            .unwrap();
+        assert_eq!((script.starts(), unsupported_script.starts()), (2, 1));
+
+        stop_reader(&state, &slot).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_fatal_read_error_disables_adapter() {
+        use ost_adapters::ScriptedAdapter;
+        use ost_core::model::TelemetryFrame;
+        use ost_core::ErrorKind;
+
+        let adapter = ScriptedAdapter::new("scripted")
+            .frame(TelemetryFrame::new("Scripted"))
+            .error_as(ErrorKind::Unsupported, "no such device on this platform");
+        let script = adapter.handle();
+        let state = AppState::new();
+        state.register_adapter(Box::new(adapter)).await;
+        let manager =
+            Manager::new(state.clone()).with_detection_interval(Duration::from_millis(20));
+
+        manager.detection_cycle().await.unwrap();
+        let stopped = tokio::time::timeout(Duration::from_secs(2), async {
+            while state.active_adapter.read().await.is_some() || script.stops() == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(stopped.is_ok(), "adapter should stop on a fatal read error");
+        assert!(state.disabled_adapters.read().await.contains("scripted"));
+
+        // Not started again until re-enabled
+        manager.detection_cycle().await.unwrap();
+        assert_eq!(script.starts(), 1);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn test_unsupported_platform_is_skipped() {
+        use ost_adapters::ScriptedAdapter;
+        use ost_core::PlatformFeature;
+
+        let unsupported = ScriptedAdapter::new("shm").requiring(&[PlatformFeature::SharedMemory]);
+        let script = unsupported.handle();
+        let state = AppState::new();
+        state.register_adapter(Box::new(unsupported)).await;
+        let slot = state.adapters.read().await[0].clone();
+        assert!(slot
+            .unsupported()
+            .unwrap()
+            .contains("only available on Windows"));
+
+        // Detected, but never started
+        let manager = Manager::new(state.clone());
+        manager.detection_cycle().await.unwrap();
+        assert!(state.active_adapter.read().await.is_none());
+        assert_eq!(script.starts(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_priority_picks_and_preempts() {
+        use ost_adapters::ScriptedAdapter;
+
+        let fallback = ScriptedAdapter::new("fallback");
+        let fallback_script = fallback.handle();
+        let preferred = ScriptedAdapter::new("preferred").detect_sequence([false]);
+        let preferred_script = preferred.handle();
+        let state = AppState::new();
+        state.register_adapter(Box::new(fallback)).await;
+        state.register_adapter(Box::new(preferred)).await;
+        let preferred_slot = state.adapters.read().await[1].clone();
+        *state.adapter_priority.write().await = vec!["preferred".into()];
+        let manager =
+            Manager::new(state.clone()).with_detection_interval(Duration::from_millis(20));
+
+        // Only the fallback is running
+        manager.detection_cycle().await.unwrap();
+        assert_eq!(
+            state.active_adapter.read().await.as_deref(),
+            Some("fallback")
+        );
+        manager.detection_cycle().await.unwrap();
+        assert_eq!(
+            state.active_adapter.read().await.as_deref(),
+            Some("fallback")
+        );
+
+        // Once the preferred game shows up, it takes over
+        preferred_script.set_detected(true);
+        manager.detection_cycle().await.unwrap();
+        assert!(state.active_adapter.read().await.is_none());
+        manager.detection_cycle().await.unwrap();
+        assert_eq!(
+            state.active_adapter.read().await.as_deref(),
+            Some("preferred")
+        );
+        let stopped = tokio::time::timeout(Duration::from_secs(2), async {
+            while fallback_script.stops() == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(stopped.is_ok(), "fallback should stop");
+
+        // Both detected: the preferred one stays
+        manager.detection_cycle().await.unwrap();
+        assert_eq!(
+            state.active_adapter.read().await.as_deref(),
+            Some("preferred")
+        );
+        assert_eq!(fallback_script.starts(), 1);
+
+        stop_reader(&state, &preferred_slot).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_repeated_read_errors_restart_with_backoff() {
+        use crate::state::{AdapterConfig, RestartPolicy};
+        use ost_adapters::ScriptedAdapter;
+        use ost_core::model::TelemetryFrame;
+
+        let adapter = ScriptedAdapter::new("scripted")
+            .error("wedged")
+            .error("wedged")
+            .frame(TelemetryFrame::new("Scripted"));
+        let script = adapter.handle();
+        let state = AppState::new();
+        state.register_adapter(Box::new(adapter)).await;
+        let slot = state.adapters.read().await[0].clone();
+        slot.set_config(AdapterConfig {
+            restart: RestartPolicy {
+                max_errors: 2,
+                backoff_secs: 0.3,
+                max_backoff_secs: 1.0,
+            },
+            ..Default::default()
+        });
+        let mut rx = state.subscribe();
+        let manager =
+            Manager::new(state.clone()).with_detection_interval(Duration::from_millis(20));
+
+        manager.detection_cycle().await.unwrap();
+        let stopped = tokio::time::timeout(Duration::from_secs(2), async {
+            while state.active_adapter.read().await.is_some() || script.stops() == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(stopped.is_ok(), "adapter should stop after repeated errors");
+        let stats = slot.stats();
+        assert_eq!((stats.restarts, stats.consecutive_errors), (1, 0));
+        assert!(stats.restart_at.is_some());
+
+        // Not started again until the backoff is over
+        manager.detection_cycle().await.unwrap();
+        assert!(state.active_adapter.read().await.is_none());
+        assert_eq!(script.starts(), 1);
+
+        tokio::time::sleep(Duration::from_millis(350)).await;
+        manager.detection_cycle().await.unwrap();
+        assert_eq!(
+            state.active_adapter.read().await.as_deref(),
+            Some("scripted")
+        );
+        assert!(slot.stats().restart_at.is_none());
+        tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("restarted adapter should publish")
+            .unwrap();
+
+        stop_reader(&state, &slot).await;
+    }
 }
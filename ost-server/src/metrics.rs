@@ -0,0 +1,61 @@
+//! Prometheus text exposition helpers for `/metrics`
+
+use std::fmt::Write;
+
+/// Append a counter with one sample per item, labelled by `labels`
+pub fn labelled_counter<T>(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    items: &[T],
+    labels: impl Fn(&T) -> Vec<(&'static str, String)>,
+    value: impl Fn(&T) -> u64,
+) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} counter");
+    for item in items {
+        let labels: Vec<String> = labels(item)
+            .iter()
+            .map(|(label, v)| format!("{label}=\"{}\"", escape_label(v)))
+            .collect();
+        let _ = writeln!(out, "{name}{{{}}} {}", labels.join(","), value(item));
+    }
+}
+
+/// A label value with `\`, `"` and newlines escaped, as the format requires
+pub fn escape_label(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labelled_counter() {
+        let mut out = String::new();
+        labelled_counter(
+            &mut out,
+            "ost_test_total",
+            "Things counted",
+            &[("a\"b\\c\nd", 3u64)],
+            |(name, _)| vec![("name", name.to_string()), ("kind", "x".into())],
+            |(_, n)| *n,
+        );
+        assert_eq!(
+            out,
+            "# HELP ost_test_total Things counted\n\
+             # TYPE ost_test_total counter\n\
+             ost_test_total{name=\"a\\\"b\\\\c\\nd\",kind=\"x\"} 3\n"
+        );
+    }
+}
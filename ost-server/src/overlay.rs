@@ -0,0 +1,238 @@
+//! Stream overlay data
+//!
+//! A small, flat summary of the player's situation for browser-source
+//! overlays in OBS and similar: lap, position, gaps to the cars either side
+//! and tyre temperatures. Its shape is versioned separately from the frame
+//! model, so overlays keep working as the model grows: fields are only ever
+//! added, every field is always present (`null` when unknown), and anything
+//! else bumps [`SCHEMA_VERSION`].
+
+use ost_core::model::{RelativeCar, TelemetryFrame, WheelInfo};
+use ost_core::units::{Celsius, Seconds};
+use serde::Serialize;
+
+/// Bumped whenever a field is renamed, removed or changes meaning
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Overlay {
+    pub schema: u32,
+    pub game: Option<String>,
+    pub driver: Option<String>,
+    pub car: Option<String>,
+    pub track: Option<String>,
+    pub lap: Lap,
+    pub position: Position,
+    pub gap: Gaps,
+    pub tyres: Tyres,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Lap {
+    /// Lap being driven
+    pub number: Option<u32>,
+    /// Race distance in laps, when the session has one
+    pub total: Option<u32>,
+    /// Time so far on the current lap
+    pub current: Option<Seconds>,
+    pub last: Option<Seconds>,
+    pub best: Option<Seconds>,
+    /// Delta to the personal best, negative when faster
+    pub delta_best: Option<Seconds>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Position {
+    pub overall: Option<u32>,
+    pub class: Option<u32>,
+    pub cars: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Gaps {
+    pub ahead: Option<Gap>,
+    pub behind: Option<Gap>,
+}
+
+/// The nearest car on track in one direction
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Gap {
+    pub driver: Option<String>,
+    pub car_number: Option<String>,
+    /// Time gap on track, always positive
+    pub time: Option<Seconds>,
+    /// Whole laps the car is ahead of (positive) or behind (negative) the
+    /// player
+    pub laps: Option<i32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Tyres {
+    pub front_left: Tyre,
+    pub front_right: Tyre,
+    pub rear_left: Tyre,
+    pub rear_right: Tyre,
+}
+
+/// Surface temperatures across one tyre
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Tyre {
+    /// Mean of the readings below
+    pub temp: Option<Celsius>,
+    pub inner: Option<Celsius>,
+    pub middle: Option<Celsius>,
+    pub outer: Option<Celsius>,
+}
+
+impl Overlay {
+    /// An overlay with nothing known, for when no frame has arrived yet
+    pub fn empty() -> Self {
+        Self {
+            schema: SCHEMA_VERSION,
+            game: None,
+            driver: None,
+            car: None,
+            track: None,
+            lap: Lap::default(),
+            position: Position::default(),
+            gap: Gaps::default(),
+            tyres: Tyres::default(),
+        }
+    }
+
+    pub fn from_frame(frame: &TelemetryFrame) -> Self {
+        let timing = frame.timing.as_ref();
+        let session = frame.session.as_ref();
+        let relative = frame.relative.as_ref();
+        let gap = |car: Option<&RelativeCar>| {
+            car.map(|car| {
+                let competitor = frame
+                    .competitors
+                    .iter()
+                    .flatten()
+                    .find(|c| c.car_index == car.car_index);
+                Gap {
+                    driver: competitor.and_then(|c| c.driver_name.clone()),
+                    car_number: competitor.and_then(|c| c.car_number.clone()),
+                    time: car.gap,
+                    laps: car.lap_difference,
+                }
+            })
+        };
+
+        Self {
+            schema: SCHEMA_VERSION,
+            game: Some(frame.meta.game.clone()),
+            driver: frame.driver.as_ref().and_then(|d| d.name.clone()),
+            car: frame.vehicle.as_ref().and_then(|v| v.car_name.clone()),
+            track: session.and_then(|s| s.track_name.clone()),
+            lap: Lap {
+                number: timing.and_then(|t| t.lap_number),
+                total: timing
+                    .and_then(|t| t.race_laps)
+                    .or(session.and_then(|s| s.session_laps)),
+                current: timing.and_then(|t| t.current_lap_time),
+                last: timing.and_then(|t| t.last_lap_time).filter(|t| t.0 > 0.0),
+                best: timing.and_then(|t| t.best_lap_time).filter(|t| t.0 > 0.0),
+                delta_best: timing
+                    .filter(|t| t.delta_best_ok != Some(false))
+                    .and_then(|t| t.delta_best),
+            },
+            position: Position {
+                overall: timing.and_then(|t| t.race_position).filter(|p| *p > 0),
+                class: timing.and_then(|t| t.class_position).filter(|p| *p > 0),
+                cars: timing.and_then(|t| t.num_cars),
+            },
+            gap: Gaps {
+                ahead: gap(relative.and_then(|r| r.ahead.as_ref())),
+                behind: gap(relative.and_then(|r| r.behind.as_ref())),
+            },
+            tyres: frame
+                .wheels
+                .as_ref()
+                .map(|w| Tyres {
+                    front_left: Tyre::from_wheel(&w.front_left),
+                    front_right: Tyre::from_wheel(&w.front_right),
+                    rear_left: Tyre::from_wheel(&w.rear_left),
+                    rear_right: Tyre::from_wheel(&w.rear_right),
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Tyre {
+    fn from_wheel(wheel: &WheelInfo) -> Self {
+        let readings = [
+            wheel.surface_temp_inner,
+            wheel.surface_temp_middle,
+            wheel.surface_temp_outer,
+        ];
+        let known: Vec<f32> = readings.iter().flatten().map(|t| t.0).collect();
+        Self {
+            temp: (!known.is_empty())
+                .then(|| Celsius(known.iter().sum::<f32>() / known.len() as f32)),
+            inner: wheel.surface_temp_inner,
+            middle: wheel.surface_temp_middle,
+            outer: wheel.surface_temp_outer,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_frame() {
+        let frame: TelemetryFrame = serde_json::from_value(serde_json::json!({
+            "meta": {"timestamp": "2026-01-01T00:00:00Z", "game": "iRacing"},
+            "timing": {
+                "lap_number": 5, "last_lap_time": 92.5, "best_lap_time": 0.0,
+                "race_position": 4, "class_position": 0, "num_cars": 20,
+                "delta_best": 0.3, "delta_best_ok": false,
+            },
+            "session": {"session_laps": 20, "track_name": "Spa"},
+            "wheels": {
+                "front_left": {"surface_temp_inner": 80.0, "surface_temp_outer": 90.0},
+                "front_right": {}, "rear_left": {}, "rear_right": {},
+            },
+            "competitors": [{"car_index": 3, "driver_name": "Alex", "car_number": "12"}],
+            "relative": {
+                "ahead": {"car_index": 3, "gap": 1.25, "lap_difference": 0},
+                "standings": [],
+            },
+        }))
+        .unwrap();
+
+        let overlay = Overlay::from_frame(&frame);
+        assert_eq!(overlay.lap.number, Some(5));
+        assert_eq!(overlay.lap.total, Some(20));
+        assert_eq!(overlay.lap.last, Some(Seconds(92.5)));
+        // Placeholders and invalid deltas read as unknown
+        assert_eq!(overlay.lap.best, None);
+        assert_eq!(overlay.lap.delta_best, None);
+        assert_eq!(overlay.position.class, None);
+        assert_eq!(overlay.tyres.front_left.temp, Some(Celsius(85.0)));
+
+        let ahead = overlay.gap.ahead.as_ref().unwrap();
+        assert_eq!(ahead.driver.as_deref(), Some("Alex"));
+        assert_eq!(ahead.time, Some(Seconds(1.25)));
+
+        // Every field is present, even when unknown
+        let value = serde_json::to_value(&overlay).unwrap();
+        assert_eq!(value["schema"], 1);
+        assert!(value["gap"]["behind"].is_null());
+        assert!(value["tyres"]["rear_right"]
+            .as_object()
+            .unwrap()
+            .contains_key("outer"));
+        assert_eq!(
+            serde_json::to_value(Overlay::empty()).unwrap()["lap"]
+                .as_object()
+                .unwrap()
+                .len(),
+            6
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Replay overview series
+//!
+//! A decimated copy of a few channels over the whole replay, computed when it
+//! loads, so the UI can draw the full-session scrub bar without fetching
+//! every frame.
+
+use ost_core::model::TelemetryFrame;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Channels in the overview, as metric paths
+pub const CHANNELS: [&str; 4] = [
+    "vehicle.speed",
+    "vehicle.throttle",
+    "vehicle.brake",
+    "vehicle.rpm",
+];
+
+/// iRacing variables for [`CHANNELS`], in the same order and units
+pub const IBT_VARS: [&str; 4] = ["Speed", "Throttle", "Brake", "RPM"];
+
+/// Summary of one channel over one bucket of frames
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Bucket {
+    pub min: f32,
+    pub max: f32,
+    pub avg: f32,
+}
+
+/// Per-channel min/max/avg at roughly 1 Hz
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Overview {
+    /// Frames summarised by each bucket; bucket `i` starts at frame
+    /// `i * frames_per_bucket`
+    pub frames_per_bucket: usize,
+    /// Seconds covered by each bucket
+    pub interval_secs: f64,
+    /// One series per channel with any data; `None` where a bucket had none
+    pub channels: BTreeMap<&'static str, Vec<Option<Bucket>>>,
+}
+
+impl Overview {
+    /// Decimate per-frame values, one per entry of [`CHANNELS`], into one
+    /// bucket per second
+    pub fn build(values: impl IntoIterator<Item = [Option<f32>; 4]>, tick_rate: u32) -> Self {
+        let frames_per_bucket = tick_rate.max(1) as usize;
+        let mut series: [Vec<Option<Bucket>>; 4] = Default::default();
+        let mut acc = [Accumulator::default(); 4];
+        let mut in_bucket = 0;
+
+        for frame in values {
+            for (acc, value) in acc.iter_mut().zip(frame) {
+                if let Some(value) = value.filter(|v| v.is_finite()) {
+                    acc.add(value);
+                }
+            }
+            in_bucket += 1;
+            if in_bucket == frames_per_bucket {
+                for (series, acc) in series.iter_mut().zip(&mut acc) {
+                    series.push(acc.take());
+                }
+                in_bucket = 0;
+            }
+        }
+        if in_bucket > 0 {
+            for (series, acc) in series.iter_mut().zip(&mut acc) {
+                series.push(acc.take());
+            }
+        }
+
+        let channels = CHANNELS
+            .into_iter()
+            .zip(series)
+            .filter(|(_, series)| series.iter().any(Option::is_some))
+            .collect();
+        Self {
+            frames_per_bucket,
+            interval_secs: frames_per_bucket as f64 / tick_rate.max(1) as f64,
+            channels,
+        }
+    }
+
+    /// Overview of frames already in memory
+    pub fn from_frames(frames: &[TelemetryFrame], tick_rate: u32) -> Self {
+        let values = frames.iter().map(|f| {
+            let v = f.vehicle.as_ref();
+            [
+                v.and_then(|v| v.speed).map(|s| s.0),
+                v.and_then(|v| v.throttle).map(|t| t.0),
+                v.and_then(|v| v.brake).map(|b| b.0),
+                v.and_then(|v| v.rpm).map(|r| r.0),
+            ]
+        });
+        Self::build(values, tick_rate)
+    }
+}
+
+/// Running min/max/avg of one channel
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Accumulator {
+    min: f32,
+    max: f32,
+    sum: f64,
+    count: u32,
+}
+
+impl Accumulator {
+    pub(crate) fn add(&mut self, value: f32) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.sum += value as f64;
+        self.count += 1;
+    }
+
+    /// The summary so far, `None` if nothing was added; resets for the next
+    pub(crate) fn take(&mut self) -> Option<Bucket> {
+        let acc = std::mem::take(self);
+        (acc.count > 0).then(|| Bucket {
+            min: acc.min,
+            max: acc.max,
+            avg: (acc.sum / acc.count as f64) as f32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_per_second() {
+        // 2.5 seconds at 4 Hz, no RPM
+        let values = (0..10).map(|i| {
+            let brake = (i < 4).then_some(1.0);
+            [Some(i as f32), Some(0.5), brake, None]
+        });
+        let overview = Overview::build(values, 4);
+        assert_eq!(overview.frames_per_bucket, 4);
+        assert_eq!(overview.interval_secs, 1.0);
+        assert!(!overview.channels.contains_key("vehicle.rpm"));
+
+        let speed = &overview.channels["vehicle.speed"];
+        assert_eq!(speed.len(), 3);
+        assert_eq!(
+            speed[1],
+            Some(Bucket {
+                min: 4.0,
+                max: 7.0,
+                avg: 5.5
+            })
+        );
+        assert_eq!(speed[2].unwrap().max, 9.0, "partial last bucket");
+        assert_eq!(overview.channels["vehicle.brake"][1], None);
+    }
+}
@@ -0,0 +1,446 @@
+//! Driver pace and consistency
+//!
+//! [`PaceLog`] collects the player's completed laps and sums them up as
+//! [`PaceStats`]: how consistent the last few valid laps were (standard
+//! deviation of their times), the theoretical best lap from the best time in
+//! each sector, and the pace against the race leader over the same number of
+//! laps. Sectors are timed by [`SectorTimer`] from where the lap distance
+//! crosses `session.sector_starts`, so sims that don't report sector times
+//! get them too. Each lap's pedal use is scored as [`InputStats`], as for
+//! indexed replay laps.
+//!
+//! [`PaceTracker`] feeds a log from live frames; replays are logged lap by
+//! lap from their frames.
+
+use crate::replay_index::{InputCounter, InputStats};
+use ost_core::model::TelemetryFrame;
+use ost_core::units::Seconds;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Laps consistency and pace are measured over when no window is asked for
+pub const DEFAULT_WINDOW: usize = 5;
+
+/// Pace over the laps logged so far
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PaceStats {
+    /// Completed laps, valid or not
+    pub laps: usize,
+    pub valid_laps: usize,
+    pub best_lap: Option<Seconds>,
+    /// Over the last valid laps
+    pub consistency: Option<Consistency>,
+    /// Best time in each sector over the valid laps with every sector timed
+    pub best_sectors: Vec<Seconds>,
+    /// Sum of `best_sectors`
+    pub theoretical_best: Option<Seconds>,
+    /// Average of the player's last laps less the leader's over as many of
+    /// theirs; negative when faster
+    pub pace_vs_leader: Option<Seconds>,
+    /// Pedal use on the last completed lap
+    pub last_lap_inputs: Option<InputStats>,
+    /// Pedal use averaged over the last valid laps scored
+    pub inputs: Option<InputStats>,
+}
+
+/// Spread of recent lap times
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Consistency {
+    /// Laps measured over
+    pub laps: usize,
+    pub mean: Seconds,
+    /// Standard deviation of the lap times
+    pub stddev: Seconds,
+}
+
+#[derive(Clone, Debug)]
+struct LoggedLap {
+    time: f32,
+    valid: bool,
+    sectors: Option<Vec<f32>>,
+    inputs: Option<InputStats>,
+}
+
+/// The player's completed laps, and the lap times of the other cars
+#[derive(Clone, Debug, Default)]
+pub struct PaceLog {
+    laps: Vec<LoggedLap>,
+    /// Lap times of the leader, when known
+    leader_laps: Vec<f32>,
+}
+
+impl PaceLog {
+    pub fn add_lap(
+        &mut self,
+        time: f32,
+        valid: bool,
+        sectors: Option<Vec<f32>>,
+        inputs: Option<InputStats>,
+    ) {
+        self.laps.push(LoggedLap {
+            time,
+            valid,
+            sectors,
+            inputs,
+        });
+    }
+
+    /// Replace the leader's lap times, oldest first
+    pub fn set_leader_laps(&mut self, laps: Vec<f32>) {
+        self.leader_laps = laps;
+    }
+
+    /// Stats with consistency and pace over the last `window` valid laps
+    pub fn stats(&self, window: usize) -> PaceStats {
+        let valid: Vec<&LoggedLap> = self.laps.iter().filter(|lap| lap.valid).collect();
+        let recent: Vec<f32> = valid
+            .iter()
+            .rev()
+            .take(window)
+            .rev()
+            .map(|lap| lap.time)
+            .collect();
+
+        let best_sectors = best_sectors(valid.iter().filter_map(|lap| lap.sectors.as_deref()));
+        let leader: Vec<f32> = self
+            .leader_laps
+            .iter()
+            .rev()
+            .take(recent.len())
+            .copied()
+            .collect();
+        let pace_vs_leader = (!recent.is_empty() && !leader.is_empty())
+            .then(|| mean(&recent) - mean(&leader))
+            .map(Seconds);
+        let scored: Vec<InputStats> = valid
+            .iter()
+            .rev()
+            .filter_map(|lap| lap.inputs)
+            .take(window)
+            .collect();
+        let average =
+            |share: fn(&InputStats) -> f32| mean(&scored.iter().map(share).collect::<Vec<_>>());
+        let inputs = (!scored.is_empty()).then(|| InputStats {
+            overlap: average(|i| i.overlap),
+            coasting: average(|i| i.coasting),
+            full_throttle: average(|i| i.full_throttle),
+        });
+
+        PaceStats {
+            laps: self.laps.len(),
+            valid_laps: valid.len(),
+            best_lap: valid
+                .iter()
+                .map(|lap| lap.time)
+                .reduce(f32::min)
+                .map(Seconds),
+            consistency: consistency(&recent),
+            theoretical_best: (!best_sectors.is_empty())
+                .then(|| Seconds(best_sectors.iter().sum())),
+            best_sectors: best_sectors.into_iter().map(Seconds).collect(),
+            pace_vs_leader,
+            last_lap_inputs: self.laps.last().and_then(|lap| lap.inputs),
+            inputs,
+        }
+    }
+}
+
+/// Mean and standard deviation of at least two lap times
+pub fn consistency(times: &[f32]) -> Option<Consistency> {
+    if times.len() < 2 {
+        return None;
+    }
+    let mean = mean(times);
+    let variance = times.iter().map(|t| (t - mean).powi(2)).sum::<f32>() / times.len() as f32;
+    Some(Consistency {
+        laps: times.len(),
+        mean: Seconds(mean),
+        stddev: Seconds(variance.sqrt()),
+    })
+}
+
+fn mean(times: &[f32]) -> f32 {
+    times.iter().sum::<f32>() / times.len() as f32
+}
+
+/// Best time of each sector, over laps with the most sectors timed
+fn best_sectors<'a>(laps: impl Iterator<Item = &'a [f32]>) -> Vec<f32> {
+    let mut best: Vec<f32> = Vec::new();
+    for sectors in laps {
+        if sectors.len() > best.len() {
+            // The track's sectors changed: only full laps count
+            best = sectors.to_vec();
+        } else if sectors.len() == best.len() {
+            for (best, &time) in best.iter_mut().zip(sectors) {
+                *best = best.min(time);
+            }
+        }
+    }
+    best
+}
+
+/// Times one lap's sectors from its lap distance
+#[derive(Clone, Debug)]
+pub struct SectorTimer {
+    /// Lap distance each sector after the first starts at
+    splits: Vec<f32>,
+    lap_start: f64,
+    /// Seconds into the lap each split was crossed
+    crossed: Vec<f64>,
+    last: Option<(f64, f32)>,
+    /// Whether the lap distance has wrapped past the line yet
+    begun: bool,
+}
+
+impl SectorTimer {
+    /// Start timing a lap begun at `time` (seconds), for sectors starting at
+    /// `sector_starts`
+    pub fn new(sector_starts: &[f32], time: f64) -> Self {
+        Self {
+            splits: sector_starts.iter().copied().filter(|&s| s > 0.0).collect(),
+            lap_start: time,
+            crossed: Vec::new(),
+            last: None,
+            begun: false,
+        }
+    }
+
+    /// The car was at lap distance `pct` at `time`
+    pub fn add(&mut self, time: f64, pct: f32) {
+        // The first samples of a lap may still read just short of the line
+        self.begun |= pct < 0.5;
+        if !self.begun {
+            return;
+        }
+        while let Some(&split) = self.splits.get(self.crossed.len()) {
+            if pct < split {
+                break;
+            }
+            let at = match self.last {
+                Some((last_time, last_pct)) if last_pct < split && pct > last_pct => {
+                    last_time + (time - last_time) * ((split - last_pct) / (pct - last_pct)) as f64
+                }
+                _ => time,
+            };
+            self.crossed.push(at - self.lap_start);
+        }
+        self.last = Some((time, pct));
+    }
+
+    /// Sector times of the lap, given its time. `None` unless every split
+    /// was crossed.
+    pub fn finish(&self, lap_time: f32) -> Option<Vec<f32>> {
+        if self.splits.is_empty() || self.crossed.len() < self.splits.len() {
+            return None;
+        }
+        let mut bounds = vec![0.0];
+        bounds.extend(&self.crossed);
+        bounds.push(lap_time as f64);
+        Some(bounds.windows(2).map(|w| (w[1] - w[0]) as f32).collect())
+    }
+}
+
+/// Builds a [`PaceLog`] from live frames
+#[derive(Debug, Default)]
+pub struct PaceTracker {
+    log: PaceLog,
+    laps_completed: Option<u32>,
+    /// Whether the lap in progress is still valid, as of the last frame
+    lap_valid: bool,
+    timer: Option<SectorTimer>,
+    /// Pedal use on the lap in progress; `None` until a lap has been seen
+    /// from its start
+    inputs: Option<InputCounter>,
+    /// Lap times of every car, and how many laps each had completed
+    cars: HashMap<u32, (u32, Vec<f32>)>,
+}
+
+impl PaceTracker {
+    pub fn update(&mut self, frame: &TelemetryFrame) {
+        let Some(timing) = frame.timing.as_ref() else {
+            return;
+        };
+        let time = frame
+            .meta
+            .sim_time
+            .unwrap_or_else(|| frame.meta.timestamp.timestamp_micros() as f64 / 1e6);
+        let starts = frame
+            .session
+            .as_ref()
+            .and_then(|s| s.sector_starts.as_ref());
+
+        if let Some(completed) = timing.laps_completed {
+            if self
+                .laps_completed
+                .is_some_and(|prev| completed == prev + 1)
+            {
+                if let Some(lap_time) = timing.last_lap_time.map(|t| t.0).filter(|t| *t > 0.0) {
+                    let sectors = self.timer.as_ref().and_then(|t| t.finish(lap_time));
+                    let inputs = self.inputs.as_mut().and_then(InputCounter::take);
+                    self.log.add_lap(lap_time, self.lap_valid, sectors, inputs);
+                }
+            }
+            if self.laps_completed != Some(completed) {
+                self.timer = starts.map(|starts| {
+                    let starts: Vec<f32> = starts.iter().map(|s| s.0).collect();
+                    SectorTimer::new(&starts, time)
+                });
+                // Joined mid-lap, the first lap's pedal use would be partial
+                self.inputs = self.laps_completed.is_some().then(InputCounter::default);
+            }
+            self.laps_completed = Some(completed);
+        }
+        if let (Some(timer), Some(pct)) = (self.timer.as_mut(), timing.lap_distance_pct) {
+            timer.add(time, pct.0);
+        }
+        let vehicle = frame.vehicle.as_ref();
+        let pedals = vehicle.and_then(|v| Some((v.throttle?.0, v.brake?.0)));
+        if let (Some(inputs), Some((throttle, brake))) = (self.inputs.as_mut(), pedals) {
+            inputs.add(throttle, brake);
+        }
+        self.lap_valid = timing.lap_valid.unwrap_or(true);
+
+        let player = frame.driver.as_ref().and_then(|d| d.car_index);
+        let mut leader = None;
+        for car in frame.competitors.iter().flatten() {
+            if car.position == Some(1) {
+                leader = Some(car.car_index);
+            }
+            let Some(completed) = car.laps_completed else {
+                continue;
+            };
+            let (last_completed, times) = self
+                .cars
+                .entry(car.car_index)
+                .or_insert((completed, Vec::new()));
+            if completed == *last_completed + 1 {
+                if let Some(lap_time) = car.last_lap_time.map(|t| t.0).filter(|t| *t > 0.0) {
+                    times.push(lap_time);
+                }
+            }
+            *last_completed = completed;
+        }
+        match leader {
+            // Leading: no one to compare with
+            Some(leader) if Some(leader) == player => self.log.set_leader_laps(Vec::new()),
+            Some(leader) => {
+                let laps = self.cars.get(&leader).map(|(_, times)| times.clone());
+                self.log.set_leader_laps(laps.unwrap_or_default());
+            }
+            None => {}
+        }
+    }
+
+    pub fn stats(&self, window: usize) -> PaceStats {
+        self.log.stats(window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut log = PaceLog::default();
+        let inputs = |overlap: f32| {
+            Some(InputStats {
+                overlap,
+                coasting: 0.1,
+                full_throttle: 0.5,
+            })
+        };
+        log.add_lap(95.0, false, None, inputs(0.5));
+        log.add_lap(90.0, true, Some(vec![30.0, 31.0, 29.0]), inputs(0.1));
+        log.add_lap(91.0, true, Some(vec![29.5, 31.5, 30.0]), None);
+        log.add_lap(92.0, true, None, inputs(0.2));
+        log.add_lap(89.0, true, Some(vec![30.5, 30.0, 28.5]), inputs(0.3));
+        log.set_leader_laps(vec![88.0, 100.0, 88.5, 88.5]);
+
+        let stats = log.stats(3);
+        assert_eq!((stats.laps, stats.valid_laps), (5, 4));
+        assert_eq!(stats.best_lap, Some(Seconds(89.0)));
+        let consistency = stats.consistency.unwrap();
+        assert_eq!(consistency.laps, 3);
+        assert!((consistency.mean.0 - 90.667).abs() < 1e-3);
+        assert!((consistency.stddev.0 - 1.247).abs() < 1e-3);
+        assert_eq!(
+            stats.best_sectors,
+            [Seconds(29.5), Seconds(30.0), Seconds(28.5)]
+        );
+        assert_eq!(stats.theoretical_best, Some(Seconds(88.0)));
+        // 90.67 against the leader's 92.33 over their last three
+        assert!((stats.pace_vs_leader.unwrap().0 + 1.667).abs() < 1e-3);
+        assert_eq!(stats.last_lap_inputs, inputs(0.3));
+        // Over the last three valid laps that were scored
+        let average = stats.inputs.unwrap();
+        assert!((average.overlap - 0.2).abs() < 1e-6);
+        assert_eq!(average.full_throttle, 0.5);
+
+        assert_eq!(PaceLog::default().stats(3), PaceStats::default());
+    }
+
+    #[test]
+    fn test_sector_timer() {
+        let mut timer = SectorTimer::new(&[0.0, 0.3, 0.6], 100.0);
+        // Still reading the end of the last lap, then 1% a second
+        timer.add(100.0, 0.999);
+        for i in 0..=100 {
+            timer.add(100.5 + i as f64, i as f32 / 100.0);
+        }
+        let sectors = timer.finish(101.0).unwrap();
+        assert_eq!(sectors.len(), 3);
+        assert!((sectors[0] - 30.5).abs() < 1e-3);
+        assert!((sectors[1] - 30.0).abs() < 1e-3);
+        assert!((sectors[2] - 40.5).abs() < 1e-3);
+
+        // Missed a split
+        let mut timer = SectorTimer::new(&[0.0, 0.3, 0.6], 0.0);
+        timer.add(1.0, 0.1);
+        assert_eq!(timer.finish(90.0), None);
+    }
+
+    #[test]
+    fn test_tracker_logs_laps_and_leader() {
+        let frame = |secs: f64, completed: u32, pct: f32, leader_completed: u32| {
+            serde_json::from_value::<TelemetryFrame>(serde_json::json!({
+                "meta": {"timestamp": "2026-01-01T00:00:00Z", "game": "test", "sim_time": secs},
+                "vehicle": {"throttle": 1.0 - pct, "brake": pct},
+                "timing": {"laps_completed": completed, "last_lap_time": 60.0 + completed as f32,
+                           "lap_distance_pct": pct, "lap_valid": true},
+                "session": {"sector_starts": [0.0, 0.5]},
+                "driver": {"car_index": 1},
+                "competitors": [
+                    {"car_index": 1, "position": 2, "laps_completed": completed},
+                    {"car_index": 2, "position": 1, "laps_completed": leader_completed,
+                     "last_lap_time": 59.0},
+                ],
+            }))
+            .unwrap()
+        };
+        let mut tracker = PaceTracker::default();
+        // Joined mid-lap: the sim times that lap, but not its sectors
+        tracker.update(&frame(0.0, 1, 0.7, 1));
+        tracker.update(&frame(30.0, 2, 0.0, 2));
+        tracker.update(&frame(60.0, 2, 0.5, 2));
+        tracker.update(&frame(92.0, 3, 0.0, 3));
+
+        let stats = tracker.stats(DEFAULT_WINDOW);
+        assert_eq!(stats.laps, 2);
+        assert_eq!(stats.best_lap, Some(Seconds(62.0)));
+        assert_eq!(stats.best_sectors, [Seconds(30.0), Seconds(33.0)]);
+        // 62.5 on average against the leader's 59
+        assert_eq!(stats.pace_vs_leader, Some(Seconds(3.5)));
+        // Only the lap seen from its start is scored: flat out, then on
+        // both pedals at half distance
+        assert_eq!(
+            stats.last_lap_inputs,
+            Some(InputStats {
+                overlap: 0.5,
+                coasting: 0.0,
+                full_throttle: 0.5,
+            })
+        );
+        assert_eq!(stats.inputs, stats.last_lap_inputs);
+    }
+}
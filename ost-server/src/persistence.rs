@@ -1,12 +1,21 @@
 //! Telemetry persistence — writes NDJSON+ZSTD files to disk
 //!
 //! Subscribes to the telemetry broadcast channel and writes frames
-//! to compressed NDJSON files at a configurable frequency.
+//! to NDJSON files, zstd or gzip compressed, at a configurable frequency.
+//!
+//! With `auto_save` every frame is recorded. With `auto_record` a recording
+//! starts when a session is under way and finishes when it ends (cooldown,
+//! session type change, or the adapter going quiet). Either way, files are
+//! rotated by size or duration if configured, and `laps_only` keeps just the
+//! complete laps flagged valid in `timing.lap_valid`.
 
-use ost_core::model::TelemetryFrame;
+use crate::archive::{Archive, ArchiveEntry};
+use crate::compression::{self, Compression, Encoder};
+use ost_core::model::{SessionState, SessionType, TelemetryFrame};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{broadcast, RwLock};
 use tracing::{error, info, warn};
 
@@ -16,6 +25,17 @@ pub struct PersistenceConfig {
     pub enabled: bool,
     pub frequency_hz: u32,
     pub auto_save: bool,
+    /// Record only while a session is in progress
+    #[serde(default)]
+    pub auto_record: bool,
+    /// Keep only complete, valid laps, dropping out-laps, off-track laps and
+    /// garage time
+    #[serde(default)]
+    pub laps_only: bool,
+    #[serde(default)]
+    pub compression: Compression,
+    #[serde(default)]
+    pub rotation: RotationConfig,
     #[serde(default)]
     pub retention: RetentionConfig,
 }
@@ -26,11 +46,30 @@ impl Default for PersistenceConfig {
             enabled: false,
             frequency_hz: 60,
             auto_save: false,
+            auto_record: false,
+            laps_only: false,
+            compression: Compression::default(),
+            rotation: RotationConfig::default(),
             retention: RetentionConfig::default(),
         }
     }
 }
 
+/// When to continue a long session in a new file
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RotationConfig {
+    /// Start a new file once the current one reaches this size (None or 0 =
+    /// no limit)
+    pub max_size_mb: Option<u64>,
+    /// Start a new file after this many minutes of frames (None or 0 = no
+    /// limit)
+    pub max_duration_mins: Option<u32>,
+}
+
+/// With `auto_record`, finish the recording when no frame arrives for this
+/// long, e.g. because the sim was closed
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Retention policy for automatic cleanup of old session files
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct RetentionConfig {
@@ -71,8 +110,9 @@ fn sanitize_filename(s: &str) -> String {
 }
 
 /// Generate a filename from session info
-/// Format: YYYY-MM-DD_track_car.ost.ndjson.zstd
-fn generate_filename(track: &str, car: &str) -> String {
+/// Format: YYYY-MM-DD_track_car.ost.ndjson.zstd (or the extension for
+/// `compression`)
+fn generate_filename(track: &str, car: &str, compression: Compression) -> String {
     let now = chrono::Local::now();
     let date = now.format("%Y-%m-%d_%H-%M-%S").to_string();
     let track_clean = if track.is_empty() {
@@ -85,26 +125,46 @@ fn generate_filename(track: &str, car: &str) -> String {
     } else {
         &sanitize_filename(car)
     };
-    format!("{}_{track_clean}_{car_clean}.ost.ndjson.zstd", date)
+    format!(
+        "{}_{track_clean}_{car_clean}{}",
+        date,
+        compression.extension()
+    )
 }
 
 /// Active file writer state
 struct ActiveWriter {
-    encoder: zstd::Encoder<'static, std::fs::File>,
+    encoder: Encoder,
     path: PathBuf,
     frame_count: u64,
     track: String,
     car: String,
+    session_type: Option<SessionType>,
+    /// Archive entry, created with the first frame written
+    entry: Option<ArchiveEntry>,
+    last_frame_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl ActiveWriter {
-    fn new(track: &str, car: &str) -> Result<Self, std::io::Error> {
-        let dir = telemetry_dir();
-        std::fs::create_dir_all(&dir)?;
-        let filename = generate_filename(track, car);
-        let path = dir.join(&filename);
+    fn new(
+        dir: &Path,
+        track: &str,
+        car: &str,
+        session_type: Option<SessionType>,
+        compression: Compression,
+    ) -> Result<Self, std::io::Error> {
+        std::fs::create_dir_all(dir)?;
+        let filename = generate_filename(track, car, compression);
+        let mut path = dir.join(&filename);
+        // A rotated file can start within the same second as the last one
+        let stem = filename.trim_end_matches(compression.extension());
+        let mut part = 2;
+        while path.exists() {
+            path = dir.join(format!("{}_{}{}", stem, part, compression.extension()));
+            part += 1;
+        }
         let file = std::fs::File::create(&path)?;
-        let encoder = zstd::Encoder::new(file, 3)?; // Level 3 = good balance
+        let encoder = Encoder::new(file, compression)?;
         info!("Persistence: writing to {}", path.display());
         Ok(Self {
             encoder,
@@ -112,16 +172,31 @@ impl ActiveWriter {
             frame_count: 0,
             track: track.to_string(),
             car: car.to_string(),
+            session_type,
+            entry: None,
+            last_frame_at: None,
         })
     }
 
     fn write_frame(
         &mut self,
         frame: &TelemetryFrame,
+        archive: &std::sync::RwLock<Archive>,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let json = serde_json::to_string(frame)?;
         writeln!(self.encoder, "{}", json)?;
         self.frame_count += 1;
+        self.last_frame_at = Some(frame.meta.timestamp);
+        match self.entry.as_mut() {
+            Some(entry) => entry.record(frame),
+            None => {
+                // List the session in the archive as soon as it starts
+                let name = self.path.file_name().unwrap_or_default().to_string_lossy();
+                let entry = ArchiveEntry::from_frame(&name, frame);
+                archive.write().unwrap().upsert(entry.clone())?;
+                self.entry = Some(entry);
+            }
+        }
         // Flush every 60 frames (~1 second at 60Hz)
         if self.frame_count.is_multiple_of(60) {
             self.encoder.flush()?;
@@ -129,7 +204,26 @@ impl ActiveWriter {
         Ok(())
     }
 
-    fn finish(mut self) -> Result<PathBuf, std::io::Error> {
+    /// Whether the file has outgrown the rotation limits by the time of `frame`.
+    /// A limit of 0 is no limit, rather than a new file for every frame.
+    fn due_for_rotation(&self, rotation: &RotationConfig, frame: &TelemetryFrame) -> bool {
+        let too_big = rotation.max_size_mb.filter(|&mb| mb > 0).is_some_and(|mb| {
+            // Only counts what has been flushed, so may run over by ~1 second
+            let size = self.encoder.file().metadata().map_or(0, |m| m.len());
+            size >= mb.saturating_mul(1024 * 1024)
+        });
+        let max_duration_mins = rotation.max_duration_mins.filter(|&mins| mins > 0);
+        let too_long = match (max_duration_mins, self.entry.as_ref()) {
+            (Some(mins), Some(entry)) => {
+                frame.meta.timestamp - entry.started_at
+                    >= chrono::Duration::minutes(i64::from(mins))
+            }
+            _ => false,
+        };
+        too_big || too_long
+    }
+
+    fn finish(mut self, archive: &std::sync::RwLock<Archive>) -> Result<PathBuf, std::io::Error> {
         self.encoder.flush()?;
         self.encoder.finish()?;
         info!(
@@ -137,38 +231,46 @@ impl ActiveWriter {
             self.path.display(),
             self.frame_count
         );
+        if let (Some(mut entry), Some(at)) = (self.entry, self.last_frame_at) {
+            let size = std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+            entry.finish(at, size);
+            archive.write().unwrap().upsert(entry)?;
+        }
         Ok(self.path)
     }
 }
 
-/// Run the persistence background task
-pub async fn run(
-    config: Arc<RwLock<PersistenceConfig>>,
-    mut rx: broadcast::Receiver<TelemetryFrame>,
-) {
-    let mut writer: Option<ActiveWriter> = None;
-    let mut frame_counter: u64 = 0;
+/// Whether a frame belongs to a session that is under way. Sims that don't
+/// report a session state count as in progress for as long as they send
+/// frames.
+fn session_in_progress(frame: &TelemetryFrame) -> bool {
+    !matches!(
+        frame.session.as_ref().and_then(|s| s.session_state),
+        Some(SessionState::Invalid | SessionState::Cooldown)
+    )
+}
 
-    loop {
-        let frame = match rx.recv().await {
-            Ok(f) => f,
-            Err(broadcast::error::RecvError::Lagged(n)) => {
-                warn!("Persistence: skipped {} frames (lagged)", n);
-                continue;
-            }
-            Err(broadcast::error::RecvError::Closed) => break,
-        };
+/// Decides when recordings start, rotate and finish
+#[derive(Default)]
+struct Recorder {
+    writer: Option<ActiveWriter>,
+    frame_counter: u64,
+    /// With `laps_only`, frames of the current lap while it is still valid
+    lap_frames: Vec<TelemetryFrame>,
+}
 
-        let cfg = config.read().await.clone();
-        if !cfg.auto_save {
-            // If auto-save was just disabled, finish any open file
-            if let Some(w) = writer.take() {
-                if let Err(e) = w.finish() {
-                    error!("Persistence: failed to finish file: {}", e);
-                }
-            }
-            frame_counter = 0;
-            continue;
+impl Recorder {
+    fn handle(
+        &mut self,
+        frame: &TelemetryFrame,
+        cfg: &PersistenceConfig,
+        archive: &std::sync::RwLock<Archive>,
+    ) {
+        let recording = cfg.auto_save || (cfg.auto_record && session_in_progress(frame));
+        if !recording {
+            // Recording was switched off, or the session ended
+            self.finish(archive);
+            return;
         }
 
         // Compute skip interval from frequency and tick rate
@@ -180,10 +282,53 @@ pub async fn run(
             1
         };
 
+        // Write frame at configured frequency
+        self.frame_counter += 1;
+        if !self.frame_counter.is_multiple_of(skip_interval) {
+            return;
+        }
+        if cfg.laps_only {
+            self.buffer_lap(frame, cfg, archive);
+        } else {
+            self.write(frame, cfg, archive);
+        }
+    }
+
+    /// Hold frames back until their lap completes, then write the lap if it
+    /// stayed valid throughout
+    fn buffer_lap(
+        &mut self,
+        frame: &TelemetryFrame,
+        cfg: &PersistenceConfig,
+        archive: &std::sync::RwLock<Archive>,
+    ) {
+        let timing = frame.timing.as_ref();
+        let lap = timing.and_then(|t| t.lap_number);
+        let lap_done = self
+            .lap_frames
+            .last()
+            .is_some_and(|last| last.timing.as_ref().and_then(|t| t.lap_number) != lap);
+        if lap_done {
+            for lap_frame in std::mem::take(&mut self.lap_frames) {
+                self.write(&lap_frame, cfg, archive);
+            }
+        }
+        if timing.and_then(|t| t.lap_valid) == Some(true) {
+            self.lap_frames.push(frame.clone());
+        } else {
+            self.lap_frames.clear();
+        }
+    }
+
+    fn write(
+        &mut self,
+        frame: &TelemetryFrame,
+        cfg: &PersistenceConfig,
+        archive: &std::sync::RwLock<Archive>,
+    ) {
         // Extract session info
-        let track = frame
-            .session
-            .as_ref()
+        let session = frame.session.as_ref();
+        let track = session
             .and_then(|s| s.track_name.as_deref())
             .unwrap_or("")
             .to_string();
@@ -193,50 +338,90 @@ pub async fn run(
             .and_then(|v| v.car_name.as_deref())
             .unwrap_or("")
             .to_string();
+        let session_type = session.and_then(|s| s.session_type);
 
-        // Check if session changed — start a new file
-        let session_changed = writer
-            .as_ref()
-            .map(|w| (!track.is_empty() && w.track != track) || (!car.is_empty() && w.car != car))
-            .unwrap_or(false);
-
-        if session_changed {
-            if let Some(w) = writer.take() {
-                if let Err(e) = w.finish() {
-                    error!("Persistence: failed to finish file: {}", e);
-                }
-            }
-            frame_counter = 0;
+        // Start a new file when the session changes or the current file is
+        // due for rotation
+        let new_file = self.writer.as_ref().is_some_and(|w| {
+            (!track.is_empty() && w.track != track)
+                || (!car.is_empty() && w.car != car)
+                || (session_type.is_some() && w.session_type != session_type)
+                || w.due_for_rotation(&cfg.rotation, frame)
+        });
+        if new_file {
+            self.finish(archive);
         }
 
         // Open writer if needed
-        if writer.is_none() {
-            match ActiveWriter::new(&track, &car) {
-                Ok(w) => writer = Some(w),
+        if self.writer.is_none() {
+            let dir = archive.read().unwrap().dir().to_path_buf();
+            match ActiveWriter::new(&dir, &track, &car, session_type, cfg.compression) {
+                Ok(w) => self.writer = Some(w),
                 Err(e) => {
                     error!("Persistence: failed to create file: {}", e);
-                    continue;
+                    return;
                 }
             }
         }
 
-        // Write frame at configured frequency
-        frame_counter += 1;
-        if frame_counter.is_multiple_of(skip_interval) {
-            if let Some(ref mut w) = writer {
-                if let Err(e) = w.write_frame(&frame) {
-                    error!("Persistence: failed to write frame: {}", e);
-                }
+        if let Some(ref mut w) = self.writer {
+            if let Err(e) = w.write_frame(frame, archive) {
+                error!("Persistence: failed to write frame: {}", e);
             }
         }
     }
 
-    // Clean up
-    if let Some(w) = writer {
-        if let Err(e) = w.finish() {
-            error!("Persistence: failed to finish file on shutdown: {}", e);
+    /// Finish the open file, if any. A lap still in progress is dropped.
+    fn finish(&mut self, archive: &std::sync::RwLock<Archive>) {
+        self.lap_frames.clear();
+        if let Some(w) = self.writer.take() {
+            if let Err(e) = w.finish(archive) {
+                error!("Persistence: failed to finish file: {}", e);
+            }
         }
+        self.frame_counter = 0;
+    }
+}
+
+/// Run the persistence background task. Recordings are written to the
+/// archive's directory and indexed in it.
+pub async fn run(
+    config: Arc<RwLock<PersistenceConfig>>,
+    archive: Arc<std::sync::RwLock<Archive>>,
+    mut rx: broadcast::Receiver<TelemetryFrame>,
+) {
+    let mut recorder = Recorder::default();
+
+    loop {
+        let received = if recorder.writer.is_some() {
+            match tokio::time::timeout(IDLE_TIMEOUT, rx.recv()).await {
+                Ok(received) => received,
+                Err(_) => {
+                    // The adapter went quiet: the session is over
+                    if config.read().await.auto_record {
+                        recorder.finish(&archive);
+                    }
+                    continue;
+                }
+            }
+        } else {
+            rx.recv().await
+        };
+        let frame = match received {
+            Ok(f) => f,
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!("Persistence: skipped {} frames (lagged)", n);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let cfg = config.read().await.clone();
+        recorder.handle(&frame, &cfg, &archive);
     }
+
+    // Clean up
+    recorder.finish(&archive);
 }
 
 /// Run retention policy cleanup: delete old session files based on config.
@@ -253,7 +438,7 @@ pub fn cleanup_old_sessions(config: &RetentionConfig) {
                 .unwrap_or_default()
                 .to_string_lossy()
                 .to_string();
-            if !name.ends_with(".ost.ndjson.zstd") {
+            if !compression::is_recording(&name) {
                 continue;
             }
             let modified = entry
@@ -266,7 +451,7 @@ pub fn cleanup_old_sessions(config: &RetentionConfig) {
     }
 
     // Sort newest first
-    files.sort_by(|a, b| b.1.cmp(&a.1));
+    files.sort_by_key(|f| std::cmp::Reverse(f.1));
 
     // Enforce max_age_days: delete files older than N days
     if let Some(max_days) = config.max_age_days {
@@ -317,7 +502,7 @@ pub fn storage_stats() -> serde_json::Value {
     if let Ok(entries) = std::fs::read_dir(&dir) {
         for entry in entries.flatten() {
             let name = entry.file_name().to_string_lossy().to_string();
-            if name.ends_with(".ost.ndjson.zstd") {
+            if compression::is_recording(&name) {
                 file_count += 1;
                 total_size += entry.metadata().map(|m| m.len()).unwrap_or(0);
             }
@@ -344,3 +529,130 @@ pub fn compress_frames(
     let compressed = encoder.finish()?;
     Ok(compressed)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(secs: i64, session_type: &str, session_state: &str) -> TelemetryFrame {
+        serde_json::from_value(serde_json::json!({
+            "meta": {
+                "timestamp": chrono::DateTime::from_timestamp(1_767_225_600 + secs, 0).unwrap(),
+                "game": "iRacing",
+                "tick": 1
+            },
+            "session": {
+                "track_name": "Spa",
+                "session_type": session_type,
+                "session_state": session_state
+            },
+            "vehicle": {"car_name": "MX-5"}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_auto_record_follows_session_and_rotates() {
+        let dir = std::env::temp_dir().join(format!("ost-recorder-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let archive = std::sync::RwLock::new(Archive::open(dir.clone()));
+        let cfg = PersistenceConfig {
+            auto_record: true,
+            rotation: RotationConfig {
+                max_size_mb: None,
+                max_duration_mins: Some(1),
+            },
+            ..Default::default()
+        };
+        let mut recorder = Recorder::default();
+
+        // Nothing is recorded before the session starts
+        recorder.handle(&frame(0, "Practice", "Invalid"), &cfg, &archive);
+        assert!(recorder.writer.is_none());
+
+        for secs in [10, 40, 75] {
+            recorder.handle(&frame(secs, "Practice", "Racing"), &cfg, &archive);
+        }
+        // A new session type starts a new file, the end of a session closes it
+        recorder.handle(&frame(80, "Qualifying", "Racing"), &cfg, &archive);
+        recorder.handle(&frame(90, "Qualifying", "Cooldown"), &cfg, &archive);
+        assert!(recorder.writer.is_none());
+
+        let archive = archive.into_inner().unwrap();
+        let mut frames: Vec<u64> = archive.entries().iter().map(|e| e.frame_count).collect();
+        frames.reverse();
+        assert_eq!(frames, vec![2, 1, 1], "rotated after a minute");
+        assert!(archive.entries().iter().all(|e| e.ended_at.is_some()));
+        assert!(archive.unindexed_files().is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_zero_rotation_limits_are_off() {
+        let dir = std::env::temp_dir().join(format!("ost-rotation-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let archive = std::sync::RwLock::new(Archive::open(dir.clone()));
+        let mut cfg = PersistenceConfig {
+            auto_record: true,
+            rotation: RotationConfig {
+                max_size_mb: Some(0),
+                max_duration_mins: Some(0),
+            },
+            ..Default::default()
+        };
+        let mut recorder = Recorder::default();
+        for secs in [10, 40, 75] {
+            recorder.handle(&frame(secs, "Practice", "Racing"), &cfg, &archive);
+        }
+        // Large enough to overflow as bytes
+        cfg.rotation.max_size_mb = Some(u64::MAX);
+        recorder.handle(&frame(90, "Practice", "Racing"), &cfg, &archive);
+        recorder.handle(&frame(95, "Practice", "Cooldown"), &cfg, &archive);
+
+        let archive = archive.into_inner().unwrap();
+        let frames: Vec<u64> = archive.entries().iter().map(|e| e.frame_count).collect();
+        assert_eq!(frames, vec![4]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_laps_only_keeps_complete_valid_laps() {
+        let dir = std::env::temp_dir().join(format!("ost-laps-only-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let archive = std::sync::RwLock::new(Archive::open(dir.clone()));
+        let cfg = PersistenceConfig {
+            auto_save: true,
+            laps_only: true,
+            ..Default::default()
+        };
+        let mut recorder = Recorder::default();
+
+        let laps = [
+            // Out-lap, a valid lap, a lap gone off track, then a lap cut short
+            (1, false),
+            (1, false),
+            (2, true),
+            (2, true),
+            (2, true),
+            (3, true),
+            (3, false),
+            (4, true),
+        ];
+        for (secs, (lap, valid)) in laps.into_iter().enumerate() {
+            let mut frame = frame(secs as i64, "Practice", "Racing");
+            frame.timing =
+                serde_json::from_value(serde_json::json!({"lap_number": lap, "lap_valid": valid}))
+                    .unwrap();
+            recorder.handle(&frame, &cfg, &archive);
+        }
+        recorder.finish(&archive);
+
+        let archive = archive.into_inner().unwrap();
+        assert_eq!(archive.entries().len(), 1);
+        assert_eq!(archive.entries()[0].frame_count, 3, "only lap 2 is kept");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
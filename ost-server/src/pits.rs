@@ -0,0 +1,191 @@
+//! Pit stop detection
+//!
+//! [`PitTracker`] follows the car through the pit lane from whatever the sim
+//! offers: `pit.on_pit_road` where it's reported, otherwise the pit speed
+//! limiter, within the track's pit lane bounds when they are known. Each stop
+//! is timed from pit entry to exit, with how long the car stood still in its
+//! box, so sims that don't time stops themselves get the same results.
+
+use ost_core::model::TelemetryFrame;
+use serde::Serialize;
+
+/// Slower than this in the pit lane counts as stationary, m/s
+const STATIONARY_SPEED: f32 = 0.5;
+
+/// What [`PitTracker`] needs from one frame
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PitSample {
+    /// Seconds from any fixed point, increasing
+    pub time: f64,
+    pub on_pit_road: Option<bool>,
+    pub pit_limiter: Option<bool>,
+    /// m/s
+    pub speed: Option<f32>,
+    pub lap_distance_pct: Option<f32>,
+    /// Pit entry and exit as lap distances
+    pub pit_lane: Option<(f32, f32)>,
+}
+
+impl PitSample {
+    pub fn from_frame(frame: &TelemetryFrame, time: f64) -> Self {
+        let session = frame.session.as_ref();
+        let pit_lane = session.and_then(|s| Some((s.pit_entry_pct?.0, s.pit_exit_pct?.0)));
+        Self {
+            time,
+            on_pit_road: frame.pit.as_ref().and_then(|p| p.on_pit_road),
+            pit_limiter: frame
+                .engine
+                .as_ref()
+                .and_then(|e| e.warnings)
+                .map(|w| w.pit_speed_limiter),
+            speed: frame.vehicle.as_ref().and_then(|v| v.speed).map(|s| s.0),
+            lap_distance_pct: frame
+                .timing
+                .as_ref()
+                .and_then(|t| t.lap_distance_pct)
+                .map(|p| p.0),
+            pit_lane,
+        }
+    }
+
+    /// Whether the car is in the pit lane
+    fn in_pit_lane(&self) -> bool {
+        if let Some(on_pit_road) = self.on_pit_road {
+            return on_pit_road;
+        }
+        if self.pit_limiter != Some(true) {
+            return false;
+        }
+        // The limiter alone also goes on for a drive-through or by mistake
+        match (self.pit_lane, self.lap_distance_pct) {
+            (Some((entry, exit)), Some(pct)) if entry <= exit => (entry..=exit).contains(&pct),
+            (Some((entry, exit)), Some(pct)) => pct >= entry || pct <= exit,
+            _ => true,
+        }
+    }
+}
+
+/// Times of one pass through the pit lane
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct PitStop {
+    /// From pit entry to exit, seconds
+    pub pit_lane_secs: f32,
+    /// Stood still in the pit lane, seconds
+    pub stationary_secs: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PitTransition {
+    Entered,
+    Exited(PitStop),
+}
+
+/// Follows the car in and out of the pit lane
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PitTracker {
+    /// When the car entered the pit lane, while it's there
+    entered_at: Option<f64>,
+    stationary: f64,
+    last_time: Option<f64>,
+    /// Whether the car stood still at the last sample
+    stopped: bool,
+}
+
+impl PitTracker {
+    /// Whether the car is in the pit lane
+    pub fn in_pit_lane(&self) -> bool {
+        self.entered_at.is_some()
+    }
+
+    /// Whether `sample` takes the car in or out of the pit lane
+    pub fn update(&mut self, sample: &PitSample) -> Option<PitTransition> {
+        let elapsed = self
+            .last_time
+            .map_or(0.0, |last| (sample.time - last).max(0.0));
+        let was_stopped = self.stopped;
+        self.last_time = Some(sample.time);
+        self.stopped = sample.speed.is_some_and(|s| s < STATIONARY_SPEED);
+
+        match (self.entered_at, sample.in_pit_lane()) {
+            (None, true) => {
+                self.entered_at = Some(sample.time);
+                self.stationary = 0.0;
+                Some(PitTransition::Entered)
+            }
+            (Some(_), true) => {
+                // Stood still since the last sample
+                if was_stopped {
+                    self.stationary += elapsed;
+                }
+                None
+            }
+            (Some(entered_at), false) => {
+                self.entered_at = None;
+                Some(PitTransition::Exited(PitStop {
+                    pit_lane_secs: (sample.time - entered_at) as f32,
+                    stationary_secs: self.stationary as f32,
+                }))
+            }
+            (None, false) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: f64, speed: f32) -> PitSample {
+        PitSample {
+            time,
+            pit_limiter: Some(true),
+            speed: Some(speed),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_times_stop_from_limiter() {
+        let mut tracker = PitTracker::default();
+        assert_eq!(
+            tracker.update(&at(10.0, 22.0)),
+            Some(PitTransition::Entered)
+        );
+        assert!(tracker.in_pit_lane());
+        for (time, speed) in [(15.0, 10.0), (16.0, 0.0), (40.0, 0.0), (41.0, 5.0)] {
+            assert_eq!(tracker.update(&at(time, speed)), None);
+        }
+        let exit = PitSample {
+            pit_limiter: Some(false),
+            ..at(52.0, 22.0)
+        };
+        assert_eq!(
+            tracker.update(&exit),
+            Some(PitTransition::Exited(PitStop {
+                pit_lane_secs: 42.0,
+                stationary_secs: 25.0,
+            }))
+        );
+        assert!(!tracker.in_pit_lane());
+    }
+
+    #[test]
+    fn test_pit_lane_sources() {
+        // The sim's own pit road flag wins over the limiter
+        let on_track = PitSample {
+            on_pit_road: Some(false),
+            ..at(0.0, 20.0)
+        };
+        assert!(!on_track.in_pit_lane());
+
+        // Limiter on the racing line, away from a pit lane across the line
+        let mut sample = PitSample {
+            pit_lane: Some((0.95, 0.05)),
+            lap_distance_pct: Some(0.5),
+            ..at(0.0, 20.0)
+        };
+        assert!(!sample.in_pit_lane());
+        sample.lap_distance_pct = Some(0.02);
+        assert!(sample.in_pit_lane());
+    }
+}
@@ -0,0 +1,295 @@
+//! Built-in adapters and which of them are registered
+//!
+//! Every built-in adapter is registered at startup unless it has been
+//! unregistered with `DELETE /api/adapters/:name`, a choice kept in
+//! `adapters.json` in the data directory so it lasts across restarts.
+//! `POST /api/adapters/register` brings one back without restarting.
+//!
+//! Game adapters are only built in with their Cargo feature, see [`FEATURES`].
+
+use crate::hardware::HardwareAdapter;
+use crate::ingest::IngestAdapter;
+use crate::relay::RelayAdapter;
+use crate::remote::RemoteAdapter;
+use crate::state::{AdapterSlot, AppState};
+use anyhow::{Context, Result};
+use ost_core::adapter::TelemetryAdapter;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Keys of the built-in adapters, in the order detection tries them
+pub const BUILTIN: &[&str] = &[
+    "iracing",
+    // After shared memory, which wins when both are available
+    "ibt-tail",
+    "acc",
+    "ac",
+    "kartkraft",
+    "scs",
+    // After the games: any game with its own telemetry is better than raw
+    // inputs
+    "input",
+    "relay",
+    "ingest",
+    "remote",
+    "hardware",
+    "demo",
+];
+
+/// A Cargo feature that compiles in game adapters
+#[derive(Debug, Serialize)]
+pub struct AdapterFeature {
+    pub name: &'static str,
+    /// Whether this build has it
+    pub enabled: bool,
+    /// Keys of the adapters it brings
+    pub adapters: &'static [&'static str],
+}
+
+/// The adapter features, and which this binary was built with
+pub const FEATURES: &[AdapterFeature] = &[
+    AdapterFeature {
+        name: "iracing",
+        enabled: cfg!(feature = "iracing"),
+        adapters: &["iracing", "ibt-tail"],
+    },
+    AdapterFeature {
+        name: "acc",
+        enabled: cfg!(feature = "acc"),
+        adapters: &["acc"],
+    },
+    AdapterFeature {
+        name: "ac",
+        enabled: cfg!(feature = "ac"),
+        adapters: &["ac"],
+    },
+    AdapterFeature {
+        name: "kartkraft",
+        enabled: cfg!(feature = "kartkraft"),
+        adapters: &["kartkraft"],
+    },
+    AdapterFeature {
+        name: "scs",
+        enabled: cfg!(feature = "scs"),
+        adapters: &["scs"],
+    },
+    AdapterFeature {
+        name: "input",
+        enabled: cfg!(feature = "input"),
+        adapters: &["input"],
+    },
+    AdapterFeature {
+        name: "demo",
+        enabled: cfg!(feature = "demo"),
+        adapters: &["demo"],
+    },
+];
+
+/// Build the built-in adapter `key`. `None` if there's no such adapter, it
+/// wasn't compiled in, or it needs a config file section that isn't there
+/// (`remote`, `hardware`).
+pub fn builtin(key: &str, state: &AppState) -> Option<Result<Box<dyn TelemetryAdapter>>> {
+    let adapter: Box<dyn TelemetryAdapter> = match key {
+        #[cfg(feature = "iracing")]
+        "iracing" => Box::new(ost_adapters::IRacingAdapter::new()),
+        #[cfg(feature = "iracing")]
+        "ibt-tail" => Box::new(ost_adapters::IbtTailAdapter::new()),
+        #[cfg(feature = "acc")]
+        "acc" => Box::new(ost_adapters::AccAdapter::new()),
+        #[cfg(feature = "ac")]
+        "ac" => Box::new(ost_adapters::AcAdapter::new()),
+        #[cfg(feature = "kartkraft")]
+        "kartkraft" => Box::new(ost_adapters::KartKraftAdapter::new()),
+        #[cfg(feature = "scs")]
+        "scs" => Box::new(ost_adapters::ScsAdapter::new()),
+        #[cfg(feature = "input")]
+        "input" => Box::new(ost_adapters::InputAdapter::new()),
+        "relay" => Box::new(RelayAdapter::new(state.relay.clone())),
+        "ingest" => Box::new(IngestAdapter::new(state.ingest.clone())),
+        "remote" => {
+            let remote = state.remote.clone()?;
+            return Some(RemoteAdapter::new(remote).map(|adapter| Box::new(adapter) as Box<_>));
+        }
+        "hardware" => Box::new(HardwareAdapter::new(state.hardware.clone()?)),
+        #[cfg(feature = "demo")]
+        "demo" => Box::new(ost_adapters::DemoAdapter::new()),
+        _ => return None,
+    };
+    Some(Ok(adapter))
+}
+
+/// Register the built-in adapters that haven't been unregistered
+pub async fn register_builtins(state: &AppState) {
+    for key in BUILTIN {
+        if !state.adapter_registry.is_registered(key) {
+            tracing::info!("Adapter {} is unregistered, skipping it", key);
+            continue;
+        }
+        match builtin(key, state) {
+            Some(Ok(adapter)) => state.register_adapter(adapter).await,
+            Some(Err(e)) => tracing::warn!("Adapter {} unavailable: {:#}", key, e),
+            None => {}
+        }
+    }
+}
+
+/// Add `adapter` in its place among the built-ins, or last if it isn't one
+pub async fn insert(state: &AppState, adapter: Box<dyn TelemetryAdapter>) -> AdapterSlot {
+    let rank = |key: &str| {
+        BUILTIN
+            .iter()
+            .position(|k| *k == key)
+            .unwrap_or(BUILTIN.len())
+    };
+    let slot = AdapterSlot::new(adapter);
+    let mut adapters = state.adapters.write().await;
+    let at = adapters
+        .iter()
+        .position(|other| rank(other.key()) > rank(slot.key()))
+        .unwrap_or(adapters.len());
+    adapters.insert(at, slot.clone());
+    slot
+}
+
+/// Remove adapter `key`, stopping it first. Returns it, `None` if it isn't
+/// registered.
+pub async fn remove(state: &AppState, key: &str) -> Option<AdapterSlot> {
+    let was_active = state.deactivate_adapter(key).await;
+    let slot = {
+        let mut adapters = state.adapters.write().await;
+        let at = adapters.iter().position(|slot| slot.key() == key)?;
+        adapters.remove(at)
+    };
+    // The active adapter is stopped by its reader task
+    if !was_active {
+        let mut adapter = slot.lock();
+        if adapter.is_active() {
+            let _ = adapter.stop();
+        }
+    }
+    Some(slot)
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Saved {
+    #[serde(default)]
+    unregistered: BTreeSet<String>,
+}
+
+/// Adapters unregistered through the API, saved to a file if it has one
+#[derive(Default)]
+pub struct AdapterRegistry {
+    path: Option<PathBuf>,
+    unregistered: Mutex<BTreeSet<String>>,
+}
+
+impl AdapterRegistry {
+    /// The registry saved at `path`, empty if there is none yet
+    pub fn load(path: PathBuf) -> Self {
+        let saved = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring {}: {}", path.display(), e);
+                Saved::default()
+            }),
+            Err(_) => Saved::default(),
+        };
+        Self {
+            path: Some(path),
+            unregistered: Mutex::new(saved.unregistered),
+        }
+    }
+
+    pub fn is_registered(&self, key: &str) -> bool {
+        !self.lock().contains(key)
+    }
+
+    /// Keys of the adapters unregistered
+    pub fn unregistered(&self) -> Vec<String> {
+        self.lock().iter().cloned().collect()
+    }
+
+    /// Remember whether `key` is registered, saving the change
+    pub fn set_registered(&self, key: &str, registered: bool) -> Result<()> {
+        let mut unregistered = self.lock();
+        let mut saved = Saved {
+            unregistered: unregistered.clone(),
+        };
+        if registered {
+            saved.unregistered.remove(key);
+        } else {
+            saved.unregistered.insert(key.to_string());
+        }
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)
+                    .with_context(|| format!("creating {}", dir.display()))?;
+            }
+            std::fs::write(path, serde_json::to_vec_pretty(&saved)?)
+                .with_context(|| format!("writing {}", path.display()))?;
+        }
+        *unregistered = saved.unregistered;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeSet<String>> {
+        self.unregistered.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_persists_unregistered() {
+        let path = std::env::temp_dir().join(format!("ost-adapters-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let registry = AdapterRegistry::load(path.clone());
+        assert!(registry.is_registered("acc"));
+        registry.set_registered("acc", false).unwrap();
+        registry.set_registered("scs", false).unwrap();
+        registry.set_registered("scs", true).unwrap();
+
+        let registry = AdapterRegistry::load(path.clone());
+        assert!(!registry.is_registered("acc"));
+        assert!(registry.is_registered("scs"));
+        assert_eq!(registry.unregistered(), ["acc"]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(all(
+        feature = "iracing",
+        feature = "acc",
+        feature = "kartkraft",
+        feature = "demo"
+    ))]
+    #[tokio::test]
+    async fn test_builtins_keep_their_order() {
+        let state = AppState::new();
+        assert!(builtin("nope", &state).is_none());
+        // Not configured
+        assert!(builtin("remote", &state).is_none());
+        assert!(builtin("hardware", &state).is_none());
+
+        for key in ["iracing", "kartkraft", "demo"] {
+            let adapter = builtin(key, &state).unwrap().unwrap();
+            assert_eq!(adapter.key(), key);
+            state.register_adapter(adapter).await;
+        }
+        remove(&state, "kartkraft").await.unwrap();
+        assert!(remove(&state, "kartkraft").await.is_none());
+        insert(&state, builtin("acc", &state).unwrap().unwrap()).await;
+        let keys: Vec<String> = state
+            .adapters
+            .read()
+            .await
+            .iter()
+            .map(|slot| slot.key().to_string())
+            .collect();
+        assert_eq!(keys, ["iracing", "acc", "demo"]);
+    }
+}
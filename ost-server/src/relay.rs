@@ -0,0 +1,426 @@
+//! Team relay: forwarding one server's frames to another
+//!
+//! For endurance teams whose engineer isn't at the rig. A `relay` sink on the
+//! driver's server keeps an outbound WebSocket open to `/api/relay` on the
+//! engineer's server and sends it every frame as MessagePack. The receiving
+//! server hands the frames to its [`RelayAdapter`], which is detected while an
+//! uplink is connected and started like any sim adapter, so streams,
+//! recording and sinks there work on the relayed telemetry.
+//!
+//! Each driver's server relays to the same URL. When several are connected at
+//! once (around a driver swap) the newest uplink is the one relayed.
+
+use crate::sinks::Sink;
+use anyhow::{bail, Context, Result};
+use axum::extract::ws::{self, WebSocket};
+use futures::{SinkExt, StreamExt};
+use ost_core::adapter::{AdapterError, TelemetryAdapter};
+use ost_core::model::{MetricMask, TelemetryFrame};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::header::AUTHORIZATION;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tracing::{debug, info, warn};
+
+/// Frames kept for the relay adapter before the oldest are dropped
+const QUEUE_LEN: usize = 32;
+
+/// Frames waiting to go up the uplink. Frames sent while these are
+/// outstanding are dropped rather than queueing behind a slow connection.
+const UPLINK_QUEUE_LEN: usize = 8;
+
+/// How long `read_frame()` waits for a relayed frame
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Reconnect delays for a lost uplink, doubling from the first to the last
+const RETRY_MIN: Duration = Duration::from_secs(1);
+const RETRY_MAX: Duration = Duration::from_secs(30);
+
+// =============================================================================
+// Receiving end
+// =============================================================================
+
+/// Frames arriving from relay uplinks, shared by the `/api/relay` handler and
+/// the [`RelayAdapter`]. Cloning is cheap and shares the same queue.
+#[derive(Clone, Default)]
+pub struct RelayHub {
+    inner: Arc<(Mutex<Uplinks>, Condvar)>,
+}
+
+#[derive(Default)]
+struct Uplinks {
+    next_id: u64,
+    /// Connected uplinks, oldest first
+    connected: Vec<u64>,
+    frames: VecDeque<TelemetryFrame>,
+}
+
+impl RelayHub {
+    /// Number of connected uplinks
+    pub fn uplinks(&self) -> usize {
+        self.lock().connected.len()
+    }
+
+    /// Register a new uplink, which becomes the one relayed
+    pub fn connect(&self) -> Uplink {
+        let mut uplinks = self.lock();
+        uplinks.next_id += 1;
+        let id = uplinks.next_id;
+        uplinks.connected.push(id);
+        // Don't mix in the previous uplink's frames
+        uplinks.frames.clear();
+        Uplink {
+            hub: self.clone(),
+            id,
+        }
+    }
+
+    /// The next relayed frame, waiting up to `timeout` for one
+    pub(crate) fn next_frame(&self, timeout: Duration) -> Option<TelemetryFrame> {
+        let (lock, ready) = &*self.inner;
+        let uplinks = lock.lock().unwrap_or_else(|e| e.into_inner());
+        let (mut uplinks, _) = ready
+            .wait_timeout_while(uplinks, timeout, |u| u.frames.is_empty())
+            .unwrap_or_else(|e| e.into_inner());
+        uplinks.frames.pop_front()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Uplinks> {
+        self.inner.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A connected uplink. Disconnects from the hub when dropped.
+pub struct Uplink {
+    hub: RelayHub,
+    id: u64,
+}
+
+impl Uplink {
+    /// Queue a frame for the relay adapter, unless a newer uplink has taken over
+    pub fn push(&self, frame: TelemetryFrame) {
+        let mut uplinks = self.hub.lock();
+        if uplinks.connected.last() != Some(&self.id) {
+            return;
+        }
+        if uplinks.frames.len() >= QUEUE_LEN {
+            uplinks.frames.pop_front();
+        }
+        uplinks.frames.push_back(frame);
+        self.hub.inner.1.notify_one();
+    }
+}
+
+impl Drop for Uplink {
+    fn drop(&mut self) {
+        let mut uplinks = self.hub.lock();
+        let current = uplinks.connected.last() == Some(&self.id);
+        uplinks.connected.retain(|id| *id != self.id);
+        if current {
+            uplinks.frames.clear();
+        }
+    }
+}
+
+/// Feed an accepted `/api/relay` WebSocket into the hub until it closes.
+/// Binary messages are MessagePack frames, text messages JSON frames.
+pub async fn receive(hub: RelayHub, mut socket: WebSocket) {
+    let uplink = hub.connect();
+    info!("Relay uplink connected");
+    while let Some(Ok(message)) = socket.recv().await {
+        let frame: Result<TelemetryFrame> = match message {
+            ws::Message::Binary(bytes) => TelemetryFrame::from_bytes(&bytes).map_err(Into::into),
+            ws::Message::Text(text) => serde_json::from_str(&text).map_err(Into::into),
+            ws::Message::Close(_) => break,
+            _ => continue,
+        };
+        match frame {
+            Ok(frame) => uplink.push(frame),
+            Err(e) => debug!("Relay uplink sent an unreadable frame: {}", e),
+        }
+    }
+    info!("Relay uplink disconnected");
+}
+
+/// Adapter for frames relayed from another server. Detected while an uplink
+/// is connected; the frames keep the game of the server they came from.
+pub struct RelayAdapter {
+    hub: RelayHub,
+    active: bool,
+}
+
+impl RelayAdapter {
+    pub fn new(hub: RelayHub) -> Self {
+        Self { hub, active: false }
+    }
+}
+
+impl TelemetryAdapter for RelayAdapter {
+    fn key(&self) -> &str {
+        "relay"
+    }
+
+    fn name(&self) -> &str {
+        "Team Relay"
+    }
+
+    fn detect(&self) -> bool {
+        self.hub.uplinks() > 0
+    }
+
+    fn start(&mut self) -> Result<(), AdapterError> {
+        self.active = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), AdapterError> {
+        self.active = false;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>, AdapterError> {
+        if !self.active {
+            return Ok(None);
+        }
+        Ok(self.hub.next_frame(READ_TIMEOUT))
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+// =============================================================================
+// Sending end
+// =============================================================================
+
+/// Sink relaying frames to another server's `/api/relay`
+///
+/// The connection lives on a thread of its own and is re-established with
+/// backoff when lost. Frames are always MessagePack in data model units; the
+/// sink's `format` and `units` don't apply.
+pub struct RelaySink {
+    tx: mpsc::Sender<Vec<u8>>,
+}
+
+impl RelaySink {
+    /// Relay to a `ws://` or `wss://` URL, presenting `token` as a Bearer token
+    pub fn new(url: &str, token: Option<&str>) -> Result<Self> {
+        client_request(url, token)?;
+        let (tx, rx) = mpsc::channel(UPLINK_QUEUE_LEN);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let (url, token) = (url.to_string(), token.map(str::to_string));
+        std::thread::Builder::new()
+            .name(format!("relay-sink {}", url))
+            .spawn(move || runtime.block_on(uplink(url, token, rx)))?;
+        Ok(Self { tx })
+    }
+}
+
+impl Sink for RelaySink {
+    fn send(&mut self, frame: &TelemetryFrame, mask: Option<&MetricMask>) -> Result<()> {
+        match self.tx.try_send(frame.to_bytes_filtered(mask)?) {
+            Ok(()) | Err(TrySendError::Full(_)) => Ok(()),
+            Err(TrySendError::Closed(_)) => bail!("Relay uplink stopped"),
+        }
+    }
+}
+
+/// The WebSocket handshake request, with a fresh key each time
+fn client_request(url: &str, token: Option<&str>) -> Result<Request> {
+    let mut request = url.into_client_request().context("Invalid relay URL")?;
+    if !matches!(request.uri().scheme_str(), Some("ws" | "wss")) {
+        bail!("Relay URL must be ws:// or wss://");
+    }
+    if let Some(token) = token {
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, format!("Bearer {}", token).parse()?);
+    }
+    Ok(request)
+}
+
+/// Open the WebSocket, verifying `wss://` servers against the platform's
+/// root certificates
+async fn connect(
+    url: &str,
+    token: Option<&str>,
+) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
+    let request = client_request(url, token)?;
+    let (socket, _) = tokio_tungstenite::connect_async_with_config(request, None, true)
+        .await
+        .context("Relay connection failed")?;
+    Ok(socket)
+}
+
+/// Keep an uplink to `url` and send it the sink's frames until the sink is
+/// dropped
+async fn uplink(url: String, token: Option<String>, mut rx: mpsc::Receiver<Vec<u8>>) {
+    let mut retry = RETRY_MIN;
+    loop {
+        match connect(&url, token.as_deref()).await {
+            Ok(mut socket) => {
+                info!("Relay uplink to {} connected", url);
+                retry = RETRY_MIN;
+                // Frames queued while disconnected are stale by now
+                while rx.try_recv().is_ok() {}
+                loop {
+                    tokio::select! {
+                        message = rx.recv() => {
+                            let Some(message) = message else {
+                                let _ = socket.close(None).await;
+                                return;
+                            };
+                            if let Err(e) = socket.send(Message::Binary(message)).await {
+                                warn!("Relay uplink to {} lost: {}", url, e);
+                                break;
+                            }
+                        }
+                        // Reading answers pings and notices the server closing
+                        incoming = socket.next() => match incoming {
+                            Some(Ok(Message::Close(_))) | None => {
+                                info!("Relay uplink to {} closed by the server", url);
+                                break;
+                            }
+                            Some(Ok(_)) => {}
+                            Some(Err(e)) => {
+                                warn!("Relay uplink to {} lost: {}", url, e);
+                                break;
+                            }
+                        },
+                    }
+                }
+            }
+            // Only the first failure of an outage is worth a warning
+            Err(e) if retry == RETRY_MIN => warn!("Relay uplink to {}: {:#}", url, e),
+            Err(e) => debug!("Relay uplink to {}: {:#}", url, e),
+        }
+        if !idle(&mut rx, retry).await {
+            return;
+        }
+        retry = (retry * 2).min(RETRY_MAX);
+    }
+}
+
+/// Wait out `delay`, discarding frames. Returns false once the sink is gone.
+async fn idle(rx: &mut mpsc::Receiver<Vec<u8>>, delay: Duration) -> bool {
+    let deadline = tokio::time::sleep(delay);
+    tokio::pin!(deadline);
+    loop {
+        tokio::select! {
+            _ = &mut deadline => return true,
+            message = rx.recv() => if message.is_none() {
+                return false;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AuthConfig, AuthScope};
+    use crate::state::AppState;
+    use ost_core::model::VehicleData;
+    use ost_core::units::MetersPerSecond;
+
+    /// Wait for the hub to see `count` uplinks
+    async fn uplinks(hub: &RelayHub, count: usize) {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while hub.uplinks() != count {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("uplink count should change");
+    }
+
+    fn read(adapter: &mut RelayAdapter) -> Option<TelemetryFrame> {
+        tokio::task::block_in_place(|| (0..20).find_map(|_| adapter.read_frame().unwrap()))
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_relay() {
+        let mut state = AppState::new();
+        state.auth = AuthConfig {
+            tokens: vec!["secret".to_string()],
+            scope: AuthScope::Mutations,
+        };
+        let hub = state.relay.clone();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/api/relay", listener.local_addr().unwrap());
+        let router = crate::api::create_router(state);
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let mut adapter = RelayAdapter::new(hub.clone());
+        assert!(!adapter.detect());
+
+        // Uplinks always need a token when there are tokens
+        assert!(connect(&url, None).await.is_err());
+        assert!(connect(&url, Some("wrong")).await.is_err());
+        assert!(RelaySink::new("http://example.com/api/relay", None).is_err());
+
+        let mut sink = RelaySink::new(&url, Some("secret")).unwrap();
+        uplinks(&hub, 1).await;
+        assert!(adapter.detect());
+        adapter.start().unwrap();
+
+        let mut frame = TelemetryFrame::new("iRacing");
+        frame.vehicle = Some(VehicleData {
+            speed: Some(MetersPerSecond(42.0)),
+            ..Default::default()
+        });
+        sink.send(&frame, None).unwrap();
+        let relayed = read(&mut adapter).expect("frame should be relayed");
+        assert_eq!(relayed.meta.game, "iRacing");
+        assert_eq!(relayed.vehicle.unwrap().speed, Some(MetersPerSecond(42.0)));
+
+        // A newer uplink (the next driver) takes over
+        let mut next = connect(&url, Some("secret")).await.unwrap();
+        uplinks(&hub, 2).await;
+        sink.send(&frame, None).unwrap();
+        let json = serde_json::to_string(&TelemetryFrame::new("ACC")).unwrap();
+        next.send(Message::Text(json)).await.unwrap();
+        assert_eq!(read(&mut adapter).unwrap().meta.game, "ACC");
+
+        drop(next);
+        uplinks(&hub, 1).await;
+        drop(sink);
+        uplinks(&hub, 0).await;
+        assert!(!adapter.detect());
+    }
+
+    #[tokio::test]
+    async fn test_uplink_reads() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/api/relay", listener.local_addr().unwrap());
+        let sink = RelaySink::new(&url, None).unwrap();
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut server = tokio_tungstenite::accept_async(tcp).await.unwrap();
+
+        // Answered with no frames going up
+        server.send(Message::Ping(b"hi".to_vec())).await.unwrap();
+        let pong = tokio::time::timeout(Duration::from_secs(2), server.next())
+            .await
+            .expect("uplink should answer pings")
+            .unwrap()
+            .unwrap();
+        assert_eq!(pong, Message::Pong(b"hi".to_vec()));
+
+        // A server closing the connection gets a new one
+        server.close(None).await.unwrap();
+        tokio::time::timeout(RETRY_MIN * 3, listener.accept())
+            .await
+            .expect("uplink should reconnect")
+            .unwrap();
+        drop(sink);
+    }
+}
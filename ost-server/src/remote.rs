@@ -0,0 +1,331 @@
+//! Remote source: following another server's telemetry
+//!
+//! For chained setups, such as a server on each rig feeding one in the pit
+//! garage, or a public-facing server in front of a private one. With a
+//! `[remote]` section in the config file:
+//!
+//! ```toml
+//! [remote]
+//! url = "http://rig-1.local:9100"
+//! # Sent as a Bearer token, for servers that guard their streams
+//! token = "rig-token"
+//! ```
+//!
+//! the [`RemoteAdapter`] subscribes to that server's `/api/telemetry/stream`
+//! as MessagePack and re-broadcasts its frames like a local sim's. It is
+//! detected while the other server is streaming, and the frames keep the game
+//! they came from. Unlike a [relay](crate::relay), the connection is made from
+//! this end, so the other server needs nothing configured for it.
+
+use crate::relay::{RelayHub, Uplink};
+use crate::webhook;
+use anyhow::{anyhow, bail, Context, Result};
+use eventsource_stream::Eventsource;
+use futures::StreamExt;
+use ost_core::adapter::{AdapterError, TelemetryAdapter};
+use ost_core::model::TelemetryFrame;
+use reqwest::{StatusCode, Url};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use tokio_util::sync::{CancellationToken, DropGuard};
+use tracing::{debug, info, warn};
+
+/// The stream followed, relative to the configured URL
+const STREAM_PATH: &str = "/api/telemetry/stream";
+const STREAM_QUERY: &str = "format=msgpack_named";
+
+/// How long `read_frame()` waits for a frame
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Give up on connecting and reading the response head after this long
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The other server counts as streaming until it has sent no frame for this
+/// long; its stream stays open, with keep-alives, while it has no sim
+const STALE_AFTER: Duration = Duration::from_secs(3);
+
+/// Drop a connection that has sent nothing, not even a keep-alive (every 15
+/// seconds), for this long
+const IDLE_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Reconnect delays, doubling from the first to the last
+const RETRY_MIN: Duration = Duration::from_secs(1);
+const RETRY_MAX: Duration = Duration::from_secs(30);
+
+/// `[remote]` config file section
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteConfig {
+    /// The other server, e.g. `http://rig-1.local:9100`
+    pub url: String,
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+impl RemoteConfig {
+    /// Reject URLs that can't be followed
+    pub fn check(&self) -> Result<()> {
+        self.stream_url().map(|_| ())
+    }
+
+    /// Where the stream is fetched from
+    fn stream_url(&self) -> Result<Url> {
+        let mut url = Url::parse(&self.url).context("Invalid remote server URL")?;
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!("Remote server URL must be http:// or https://");
+        }
+        if url.host_str().is_none() {
+            bail!("Remote server URL has no host");
+        }
+        // Servers behind a proxy may live under a path
+        let path = format!("{}{}", url.path().trim_end_matches('/'), STREAM_PATH);
+        url.set_path(&path);
+        url.set_query(Some(STREAM_QUERY));
+        Ok(url)
+    }
+}
+
+/// Adapter for another server's frames
+///
+/// The connection lives on a thread of its own, started with the adapter and
+/// re-established with backoff when lost, since detection depends on it.
+/// Frames queue in a [`RelayHub`], which the connection joins while frames
+/// are arriving.
+pub struct RemoteAdapter {
+    hub: RelayHub,
+    active: bool,
+    _stop: DropGuard,
+}
+
+impl RemoteAdapter {
+    pub fn new(config: RemoteConfig) -> Result<Self> {
+        let target = config.stream_url()?;
+        let hub = RelayHub::default();
+        let stop = CancellationToken::new();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let (follow_hub, cancelled) = (hub.clone(), stop.clone());
+        std::thread::Builder::new()
+            .name(format!("remote {}", config.url))
+            .spawn(move || {
+                runtime.block_on(async move {
+                    tokio::select! {
+                        _ = follow(config, target, follow_hub) => {}
+                        _ = cancelled.cancelled() => {}
+                    }
+                })
+            })?;
+        Ok(Self {
+            hub,
+            active: false,
+            _stop: stop.drop_guard(),
+        })
+    }
+}
+
+impl TelemetryAdapter for RemoteAdapter {
+    fn key(&self) -> &str {
+        "remote"
+    }
+
+    fn name(&self) -> &str {
+        "Remote Server"
+    }
+
+    fn detect(&self) -> bool {
+        self.hub.uplinks() > 0
+    }
+
+    fn start(&mut self) -> Result<(), AdapterError> {
+        self.active = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), AdapterError> {
+        self.active = false;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Option<TelemetryFrame>, AdapterError> {
+        if !self.active {
+            return Ok(None);
+        }
+        Ok(self.hub.next_frame(READ_TIMEOUT))
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+/// Keep following the other server's stream, feeding its frames to `hub`
+async fn follow(config: RemoteConfig, target: Url, hub: RelayHub) {
+    let url = &config.url;
+    let mut retry = RETRY_MIN;
+    loop {
+        let opened = tokio::time::timeout(CONNECT_TIMEOUT, open(&target, config.token.as_deref()))
+            .await
+            .map_err(|_| anyhow!("Timed out connecting"))
+            .and_then(|result| result);
+        match opened {
+            Ok(response) => {
+                info!("Following remote server {}", url);
+                retry = RETRY_MIN;
+                match receive(response, &hub).await {
+                    Ok(()) => info!("Remote server {} ended its stream", url),
+                    Err(e) => warn!("Lost remote server {}: {:#}", url, e),
+                }
+            }
+            // Only the first failure of an outage is worth a warning
+            Err(e) if retry == RETRY_MIN => warn!("Remote server {}: {:#}", url, e),
+            Err(e) => debug!("Remote server {}: {:#}", url, e),
+        }
+        tokio::time::sleep(retry).await;
+        retry = (retry * 2).min(RETRY_MAX);
+    }
+}
+
+/// Request the stream and wait for the response head
+async fn open(url: &Url, token: Option<&str>) -> Result<reqwest::Response> {
+    let mut request = webhook::client()?
+        .get(url.clone())
+        .header(reqwest::header::ACCEPT, "text/event-stream");
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .context("Failed to connect to the remote server")?;
+    match response.status() {
+        StatusCode::OK => Ok(response),
+        StatusCode::UNAUTHORIZED => bail!("Remote server needs a valid token"),
+        status => bail!("Remote server answered {}", status.as_u16()),
+    }
+}
+
+/// Read frames off the stream until it ends, joining `hub` while they arrive
+async fn receive(response: reqwest::Response, hub: &RelayHub) -> Result<()> {
+    // Keep-alives count as traffic, though they make no events
+    let last_read = Cell::new(Instant::now());
+    let mut events = response
+        .bytes_stream()
+        .inspect(|_| last_read.set(Instant::now()))
+        .eventsource();
+    let mut uplink: Option<Uplink> = None;
+    let mut last_frame = Instant::now();
+    loop {
+        if last_frame.elapsed() >= STALE_AFTER {
+            uplink = None;
+        }
+        if last_read.get().elapsed() >= IDLE_TIMEOUT {
+            bail!("Nothing received for {} seconds", IDLE_TIMEOUT.as_secs());
+        }
+        let event = match tokio::time::timeout(STALE_AFTER, events.next()).await {
+            Ok(Some(event)) => event?,
+            Ok(None) => return Ok(()),
+            Err(_) => continue,
+        };
+        if event.event != "msgpack" {
+            continue;
+        }
+        use base64::Engine;
+        let frame = base64::engine::general_purpose::STANDARD
+            .decode(event.data.as_bytes())
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| TelemetryFrame::from_bytes(&bytes).map_err(Into::into));
+        match frame {
+            Ok(frame) => {
+                uplink.get_or_insert_with(|| hub.connect()).push(frame);
+                last_frame = Instant::now();
+            }
+            Err(e) => debug!("Remote server sent an unreadable frame: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AuthConfig, AuthScope};
+    use crate::state::AppState;
+    use ost_core::model::VehicleData;
+    use ost_core::units::MetersPerSecond;
+
+    #[test]
+    fn test_target() {
+        let config = |url: &str| RemoteConfig {
+            url: url.to_string(),
+            token: None,
+        };
+        assert_eq!(
+            config("https://example.com/rig1/")
+                .stream_url()
+                .unwrap()
+                .as_str(),
+            "https://example.com/rig1/api/telemetry/stream?format=msgpack_named"
+        );
+        assert_eq!(
+            config("http://10.0.0.2:9100")
+                .stream_url()
+                .unwrap()
+                .as_str(),
+            "http://10.0.0.2:9100/api/telemetry/stream?format=msgpack_named"
+        );
+        assert!(config("ws://10.0.0.2:9100").check().is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_remote() {
+        let mut state = AppState::new();
+        state.auth = AuthConfig {
+            tokens: vec!["secret".to_string()],
+            scope: AuthScope::All,
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let router = crate::api::create_router(state.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let rejected = RemoteConfig {
+            url: url.clone(),
+            token: Some("wrong".to_string()),
+        };
+        let error = open(&rejected.stream_url().unwrap(), rejected.token.as_deref())
+            .await
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("token"));
+
+        let mut adapter = RemoteAdapter::new(RemoteConfig {
+            url,
+            token: Some("secret".to_string()),
+        })
+        .unwrap();
+        assert!(!adapter.detect());
+
+        let mut frame = TelemetryFrame::new("iRacing");
+        frame.vehicle = Some(VehicleData {
+            speed: Some(MetersPerSecond(42.0)),
+            ..Default::default()
+        });
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !adapter.detect() {
+                let _ = state.telemetry_tx.send(frame.clone());
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("remote server should be detected");
+        adapter.start().unwrap();
+
+        let _ = state.telemetry_tx.send(frame.clone());
+        let followed =
+            tokio::task::block_in_place(|| (0..20).find_map(|_| adapter.read_frame().unwrap()))
+                .expect("frame should be followed");
+        assert_eq!(followed.meta.game, "iRacing");
+        assert_eq!(followed.vehicle.unwrap().speed, Some(MetersPerSecond(42.0)));
+    }
+}
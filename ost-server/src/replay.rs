@@ -3,20 +3,80 @@
 //! Manages the state of an active replay session including playback control
 //! (play/pause/seek/speed) and frame-by-frame reading from parsed .ibt files.
 
+use crate::ghost::{Ghost, GhostSettings};
+use crate::overview::{self, Overview};
+use crate::replay_index::{self, IndexStatus, ReplayIndex};
+use crate::transforms::redact;
 use anyhow::Result;
-use ost_adapters::ibt_parser::{IbtFile, LapInfo};
+use axum::body::Bytes;
+use ost_adapters::ibt_parser::{IbtFile, IbtRecovery, IbtSession, LapInfo, TickGap, Timeline};
+use ost_adapters::ibt_writer::IbtWriter;
 use ost_core::model::TelemetryFrame;
+use ost_core::VariableInfo;
 use serde::Serialize;
 use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
 use std::hash::{Hash, Hasher};
+use std::io::{Seek, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Most `/api/replay/frames` responses kept per replay
+const FRAME_CACHE_ENTRIES: usize = 64;
+
+/// Most bytes of `/api/replay/frames` responses kept per replay
+const FRAME_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Frames read at a time when extracting part of a replay
+const EXTRACT_BATCH: usize = 1000;
 
 /// The data source backing a replay session
+#[derive(Clone)]
 enum ReplaySource {
     /// .ibt file with random-access reads
-    Ibt(Box<IbtFile>),
-    /// In-memory frames from NDJSON+ZSTD file
-    Ndjson(Vec<TelemetryFrame>),
+    Ibt(Arc<IbtFile>),
+    /// In-memory frames from an NDJSON recording
+    Ndjson(Arc<Vec<TelemetryFrame>>),
+}
+
+/// A replay's frames, read without holding the replay lock. Cheap to clone,
+/// so long reads can move to a blocking thread.
+#[derive(Clone)]
+pub struct ReplayFrames {
+    source: ReplaySource,
+    total_frames: usize,
+    redacted: bool,
+}
+
+impl ReplayFrames {
+    /// Read a range of frames for batch delivery to the client.
+    pub fn get_frames_range(
+        &self,
+        start: usize,
+        count: usize,
+    ) -> Result<Vec<(usize, TelemetryFrame)>> {
+        let max_count = 7200; // Cap at 2 minutes at 60fps
+        let clamped_start = start.min(self.total_frames.saturating_sub(1));
+        let clamped_count = count
+            .min(max_count)
+            .min(self.total_frames.saturating_sub(clamped_start));
+
+        let mut frames: Vec<_> = match &self.source {
+            ReplaySource::Ibt(ibt) => ibt
+                .read_frames_range(clamped_start, clamped_count)?
+                .into_iter()
+                .enumerate()
+                .map(|(i, frame)| (clamped_start + i, frame))
+                .collect(),
+            ReplaySource::Ndjson(frames) => (clamped_start..clamped_start + clamped_count)
+                .map(|i| (i, frames[i].clone()))
+                .collect(),
+        };
+        if self.redacted {
+            frames.iter_mut().for_each(|(_, frame)| redact(frame));
+        }
+        Ok(frames)
+    }
 }
 
 /// State for an active replay session
@@ -31,11 +91,30 @@ pub struct ReplayState {
     temp_path: Option<PathBuf>,
     track_name: String,
     car_name: String,
-    duration_secs: f64,
+    /// When each frame was taken, for playing back over gaps
+    timeline: Arc<Timeline>,
     laps: Vec<LapInfo>,
+    /// Sessions of the weekend the file covers, for .ibt replays
+    sessions: Vec<IbtSession>,
+    /// Index into `sessions` of the one playback is limited to
+    session: Option<usize>,
+    /// How much of a truncated .ibt could be read
+    recovery: Option<IbtRecovery>,
     replay_id: String,
     /// Pre-computed track outline as [[lat, lng], ...] for the track map widget
     track_outline: Vec<[f64; 2]>,
+    /// Pre-computed 1 Hz channel summary for the scrub bar
+    overview: Overview,
+    /// Serialized frame ranges, reused while scrubbing
+    frame_cache: Mutex<FrameCache>,
+    /// Reused by [`Self::get_frame`] for each .ibt sample it reads
+    sample_buf: Mutex<Vec<u8>>,
+    /// Full-scan index of an .ibt, filled in by [`Self::start_indexing`]
+    index: Option<Arc<RwLock<ReplayIndex>>>,
+    /// Strip personal details from frames, see [`crate::transforms::redact`]
+    redacted: bool,
+    /// Reference lap for `/api/replay/ghost/stream`
+    ghost: Option<Ghost>,
 }
 
 impl ReplayState {
@@ -43,13 +122,33 @@ impl ReplayState {
         let mut ibt = IbtFile::open(path)?;
 
         let total_frames = ibt.record_count();
-        let tick_rate = ibt.tick_rate();
+        let recovery = ibt.recovery();
+        if let Some(r) = recovery {
+            tracing::warn!(
+                "{} is truncated: recovered {} of {} samples ({:.1}%)",
+                path.display(),
+                r.records,
+                r.expected_records,
+                r.percent
+            );
+        }
+        let timeline = ibt.build_timeline()?;
+        // Frames a second, which is less than the sim's tick rate when it
+        // logged every other tick or fewer
+        let tick_rate = timeline.sample_rate().round() as u32;
         let file_size = ibt.file_size();
         let track_name = ibt.session_info().track_display_name.clone();
         let car_name = ibt.session_info().car_name.clone();
-        let duration_secs = ibt.duration_secs();
         let laps = ibt.build_lap_index().unwrap_or_default();
+        let sessions = ibt.build_session_index().unwrap_or_default();
         let track_outline = ibt.build_track_outline().unwrap_or_default();
+        let overview = ibt
+            .read_f32_channels(&overview::IBT_VARS)
+            .map(|series| {
+                let values = (0..total_frames).map(|i| std::array::from_fn(|c| series[c][i]));
+                Overview::build(values, tick_rate)
+            })
+            .unwrap_or_default();
 
         // Compute a stable replay ID from file metadata
         let mut hasher = DefaultHasher::new();
@@ -60,7 +159,7 @@ impl ReplayState {
         let replay_id = format!("{:016x}", hasher.finish());
 
         Ok(ReplayState {
-            source: ReplaySource::Ibt(Box::new(ibt)),
+            source: ReplaySource::Ibt(Arc::new(ibt)),
             current_frame: 0,
             total_frames,
             tick_rate,
@@ -70,19 +169,27 @@ impl ReplayState {
             temp_path: Some(path.to_path_buf()),
             track_name,
             car_name,
-            duration_secs,
+            timeline: Arc::new(timeline),
             laps,
+            sessions,
+            session: None,
+            recovery,
             replay_id,
             track_outline,
+            overview,
+            frame_cache: Mutex::default(),
+            sample_buf: Mutex::default(),
+            index: Some(Arc::default()),
+            redacted: false,
+            ghost: None,
         })
     }
 
-    /// Load an NDJSON+ZSTD telemetry file
-    pub fn from_ndjson_zstd(path: &Path) -> Result<Self> {
-        let file = std::fs::File::open(path)?;
-        let file_size = file.metadata()?.len();
-        let decoder = zstd::Decoder::new(file)?;
-        let reader = std::io::BufReader::new(decoder);
+    /// Load an NDJSON telemetry file: a recording, zstd or gzip compressed
+    /// or plain
+    pub fn from_ndjson(path: &Path) -> Result<Self> {
+        let file_size = std::fs::metadata(path)?.len();
+        let reader = crate::compression::open(path)?;
 
         let mut frames = Vec::new();
         use std::io::BufRead;
@@ -117,7 +224,7 @@ impl ReplayState {
             60
         };
 
-        let duration_secs = total_frames as f64 / tick_rate as f64;
+        let timeline = Timeline::contiguous(tick_rate, total_frames);
 
         let track_name = frames[0]
             .session
@@ -145,11 +252,29 @@ impl ReplayState {
                         lap_number: lap_num as i32,
                         start_frame: i,
                         lap_time_secs: lap_time,
+                        ..Default::default()
                     });
                 }
                 last_lap = Some(lap_num);
             }
         }
+        let ends: Vec<usize> = laps
+            .iter()
+            .skip(1)
+            .map(|lap| lap.start_frame - 1)
+            .chain([total_frames - 1])
+            .collect();
+        let lap_count = laps.len();
+        let on_pit_road =
+            |i: usize| frames[i].pit.as_ref().and_then(|p| p.on_pit_road) == Some(true);
+        for (n, (lap, end)) in laps.iter_mut().zip(ends).enumerate() {
+            lap.end_frame = end;
+            lap.out_lap = on_pit_road(lap.start_frame);
+            lap.in_lap = on_pit_road(end);
+            // Recorded validity as of the lap's last frame; the last lap never finished
+            lap.valid = n + 1 < lap_count
+                && frames[end].timing.as_ref().and_then(|t| t.lap_valid) == Some(true);
+        }
 
         // Build track outline from GPS data
         let mut track_outline = Vec::new();
@@ -176,6 +301,8 @@ impl ReplayState {
             }
         }
 
+        let overview = Overview::from_frames(&frames, tick_rate);
+
         let mut hasher = DefaultHasher::new();
         file_size.hash(&mut hasher);
         total_frames.hash(&mut hasher);
@@ -184,7 +311,7 @@ impl ReplayState {
         let replay_id = format!("{:016x}", hasher.finish());
 
         Ok(ReplayState {
-            source: ReplaySource::Ndjson(frames),
+            source: ReplaySource::Ndjson(Arc::new(frames)),
             current_frame: 0,
             total_frames,
             tick_rate,
@@ -194,24 +321,38 @@ impl ReplayState {
             temp_path: None, // Don't delete on drop — it's the user's saved file
             track_name,
             car_name,
-            duration_secs,
+            timeline: Arc::new(timeline),
             laps,
+            sessions: Vec::new(),
+            session: None,
+            recovery: None,
             replay_id,
             track_outline,
+            overview,
+            frame_cache: Mutex::default(),
+            sample_buf: Mutex::default(),
+            index: None,
+            redacted: false,
+            ghost: None,
         })
     }
 
     pub fn get_frame(&self, index: usize) -> Result<TelemetryFrame> {
-        match &self.source {
+        let mut frame = match &self.source {
             ReplaySource::Ibt(ibt) => {
-                let sample = ibt.read_sample(index)?;
-                Ok(ibt.sample_to_frame(&sample))
+                let mut buf = self.sample_buf.lock().unwrap();
+                ibt.read_raw_sample(index, &mut buf)?;
+                ibt.sample_to_frame(&buf)
             }
             ReplaySource::Ndjson(frames) => frames
                 .get(index)
                 .cloned()
-                .ok_or_else(|| anyhow::anyhow!("Frame index {} out of range", index)),
+                .ok_or_else(|| anyhow::anyhow!("Frame index {} out of range", index))?,
+        };
+        if self.redacted {
+            redact(&mut frame);
         }
+        Ok(frame)
     }
 
     /// Read a range of frames for batch delivery to the client.
@@ -220,40 +361,111 @@ impl ReplayState {
         start: usize,
         count: usize,
     ) -> Result<Vec<(usize, TelemetryFrame)>> {
-        let max_count = 7200; // Cap at 2 minutes at 60fps
-        let clamped_start = start.min(self.total_frames.saturating_sub(1));
-        let clamped_count = count
-            .min(max_count)
-            .min(self.total_frames.saturating_sub(clamped_start));
+        self.frames().get_frames_range(start, count)
+    }
 
-        match &self.source {
-            ReplaySource::Ibt(ibt) => {
-                let samples = ibt.read_samples_range(clamped_start, clamped_count)?;
-                let frames = samples
-                    .iter()
-                    .enumerate()
-                    .map(|(i, sample)| (clamped_start + i, ibt.sample_to_frame(sample)))
-                    .collect();
-                Ok(frames)
+    /// The frames, to read after releasing the replay lock
+    pub fn frames(&self) -> ReplayFrames {
+        ReplayFrames {
+            source: self.source.clone(),
+            total_frames: self.total_frames,
+            redacted: self.redacted,
+        }
+    }
+
+    /// Write frames `start..=end` as an .ibt. .ibt replays keep their raw
+    /// samples, variables and session info unless redacted; anything else is
+    /// converted with [`IbtWriter::for_frames`].
+    pub fn extract_ibt<W: Write + Seek>(&self, start: usize, end: usize, out: W) -> Result<W> {
+        // Ticks keep counting at the sim's rate, whatever rate frames are at
+        let tick_rate = match &self.source {
+            ReplaySource::Ibt(ibt) => ibt.tick_rate(),
+            ReplaySource::Ndjson(_) => self.tick_rate,
+        };
+        if let (ReplaySource::Ibt(ibt), false) = (&self.source, self.redacted) {
+            let first = ibt.read_sample(start)?;
+            let date = ibt.read_frame(start)?.meta.timestamp;
+            let mut writer = IbtWriter::new(
+                out,
+                ibt.var_headers_ref().to_vec(),
+                tick_rate,
+                ibt.session_info_yaml(),
+                date,
+            )?;
+            if let Some(time) = first.get("SessionTime").and_then(|v| v.as_f64()) {
+                writer.align_clock(date, time);
             }
-            ReplaySource::Ndjson(frames) => {
-                let result = (clamped_start..clamped_start + clamped_count)
-                    .map(|i| (i, frames[i].clone()))
-                    .collect();
-                Ok(result)
+            for batch in (start..=end).step_by(EXTRACT_BATCH) {
+                let count = EXTRACT_BATCH.min(end + 1 - batch);
+                for sample in ibt.read_samples_range(batch, count)? {
+                    writer.write_sample(&sample)?;
+                }
+            }
+            return writer.finish();
+        }
+
+        let mut writer: Option<IbtWriter<W>> = None;
+        let mut out = Some(out);
+        self.for_each_frame(start, end, |frame| {
+            let writer = match &mut writer {
+                Some(writer) => writer,
+                None => writer.insert(IbtWriter::for_frames(
+                    out.take().expect("writer is created once"),
+                    tick_rate,
+                    frame,
+                )?),
+            };
+            writer.write_frame(frame)
+        })?;
+        writer
+            .ok_or_else(|| anyhow::anyhow!("No frames in {}..={}", start, end))?
+            .finish()
+    }
+
+    /// Write frames `start..=end` as an NDJSON recording, one frame a line
+    pub fn extract_ndjson<W: Write>(&self, start: usize, end: usize, mut out: W) -> Result<W> {
+        self.for_each_frame(start, end, |frame| {
+            serde_json::to_writer(&mut out, frame)?;
+            out.write_all(b"\n")?;
+            Ok(())
+        })?;
+        out.flush()?;
+        Ok(out)
+    }
+
+    fn for_each_frame(
+        &self,
+        start: usize,
+        end: usize,
+        mut f: impl FnMut(&TelemetryFrame) -> Result<()>,
+    ) -> Result<()> {
+        for batch in (start..=end).step_by(EXTRACT_BATCH) {
+            let count = EXTRACT_BATCH.min(end + 1 - batch);
+            for (_, frame) in self.get_frames_range(batch, count)? {
+                f(&frame)?;
             }
         }
+        Ok(())
     }
 
     pub fn total_frames(&self) -> usize {
         self.total_frames
     }
 
+    pub fn track_name(&self) -> &str {
+        &self.track_name
+    }
+
+    pub fn car_name(&self) -> &str {
+        &self.car_name
+    }
+
     pub fn info(&self) -> ReplayInfo {
         ReplayInfo {
             total_frames: self.total_frames,
             tick_rate: self.tick_rate,
-            duration_secs: self.duration_secs,
+            duration_secs: self.timeline.duration_secs(),
+            gaps: self.timeline.gaps(),
             current_frame: self.current_frame,
             playing: self.playing,
             playback_speed: self.playback_speed,
@@ -261,10 +473,57 @@ impl ReplayState {
             car_name: self.car_name.clone(),
             file_size: self.file_size,
             laps: self.laps.clone(),
+            sessions: self.sessions.clone(),
+            session: self.selected_session().map(|s| s.session_num),
+            recovery: self.recovery,
             replay_id: self.replay_id.clone(),
+            index: self
+                .index
+                .as_ref()
+                .map(|index| index.read().unwrap().clone()),
+            ghost: self.ghost.as_ref().map(Ghost::settings),
         }
     }
 
+    /// Scan the whole .ibt on a blocking thread, filling in
+    /// [`ReplayInfo::index`] as it goes and passing each update to
+    /// `on_update`. Stops early if the replay is closed. No-op for
+    /// recordings.
+    pub fn start_indexing(&self, on_update: impl Fn(&ReplayIndex) + Send + 'static) {
+        let (ReplaySource::Ibt(ibt), Some(index)) = (&self.source, &self.index) else {
+            return;
+        };
+        let ibt = Arc::clone(ibt);
+        let timeline = Arc::clone(&self.timeline);
+        let index = Arc::downgrade(index);
+        tokio::task::spawn_blocking(move || {
+            let publish = |update: &ReplayIndex| {
+                let Some(index) = index.upgrade() else {
+                    return false;
+                };
+                *index.write().unwrap() = update.clone();
+                on_update(update);
+                true
+            };
+            match replay_index::index_ibt(&ibt, &timeline, publish) {
+                Ok(Some(done)) => {
+                    publish(&done);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("Replay indexing failed: {}", e);
+                    let Some(current) = index.upgrade() else {
+                        return;
+                    };
+                    let mut failed = current.read().unwrap().clone();
+                    failed.status = IndexStatus::Failed;
+                    failed.error = Some(e.to_string());
+                    publish(&failed);
+                }
+            }
+        });
+    }
+
     pub fn current_frame(&self) -> usize {
         self.current_frame
     }
@@ -273,6 +532,15 @@ impl ReplayState {
         self.tick_rate
     }
 
+    /// When each frame was taken
+    pub fn timeline(&self) -> &Timeline {
+        &self.timeline
+    }
+
+    pub fn laps(&self) -> &[LapInfo] {
+        &self.laps
+    }
+
     pub fn playback_speed(&self) -> f64 {
         self.playback_speed
     }
@@ -285,12 +553,64 @@ impl ReplayState {
         &self.track_outline
     }
 
+    pub fn overview(&self) -> &Overview {
+        &self.overview
+    }
+
+    /// The reference lap frames are paired with, if one is set
+    pub fn ghost(&self) -> Option<&Ghost> {
+        self.ghost.as_ref()
+    }
+
+    pub fn set_ghost(&mut self, ghost: Option<Ghost>) {
+        self.ghost = ghost;
+    }
+
+    /// A previously serialized `/api/replay/frames` response
+    pub fn cached_frames(&self, key: &FrameRangeKey) -> Option<Bytes> {
+        self.frame_cache.lock().unwrap().get(key)
+    }
+
+    /// Keep a serialized `/api/replay/frames` response for reuse
+    pub fn cache_frames(&self, key: FrameRangeKey, body: Bytes) {
+        self.frame_cache.lock().unwrap().insert(key, body);
+    }
+
+    /// Session info YAML embedded in the replayed .ibt file
+    pub fn session_info_raw(&self) -> Option<&str> {
+        match &self.source {
+            ReplaySource::Ibt(ibt) => Some(ibt.session_info_yaml()),
+            ReplaySource::Ndjson(_) => None,
+        }
+    }
+
+    /// Variables recorded in the replayed .ibt file
+    pub fn variables(&self) -> Option<Vec<VariableInfo>> {
+        match &self.source {
+            ReplaySource::Ibt(ibt) => {
+                Some(ibt.var_headers_ref().iter().map(|h| h.info()).collect())
+            }
+            ReplaySource::Ndjson(_) => None,
+        }
+    }
+
+    /// The uploaded file deleted on drop, if any
+    pub fn temp_path(&self) -> Option<&Path> {
+        self.temp_path.as_deref()
+    }
+
     /// Clear the temp path so the file is NOT deleted on drop.
     /// Used for session files that should persist.
     pub fn set_persistent(&mut self) {
         self.temp_path = None;
     }
 
+    /// Strip personal details from the frames served, for the `redact`
+    /// transform. The session info is withheld separately.
+    pub fn set_redacted(&mut self, redacted: bool) {
+        self.redacted = redacted;
+    }
+
     pub fn play(&mut self) {
         self.playing = true;
     }
@@ -299,8 +619,44 @@ impl ReplayState {
         self.playing = false;
     }
 
+    /// Seek to `frame`, kept within the selected session
     pub fn seek(&mut self, frame: usize) {
-        self.current_frame = frame.min(self.total_frames.saturating_sub(1));
+        let (first, last) = self.frame_bounds();
+        self.current_frame = frame.clamp(first, last);
+    }
+
+    pub fn sessions(&self) -> &[IbtSession] {
+        &self.sessions
+    }
+
+    pub fn selected_session(&self) -> Option<&IbtSession> {
+        self.session.map(|i| &self.sessions[i])
+    }
+
+    /// Limit playback to session `session_num` and seek to its start, or
+    /// play the whole replay again for `None`. Fails if there's no such
+    /// session.
+    pub fn select_session(&mut self, session_num: Option<i32>) -> Result<()> {
+        self.session = match session_num {
+            Some(num) => Some(
+                self.sessions
+                    .iter()
+                    .position(|s| s.session_num == num)
+                    .ok_or_else(|| anyhow::anyhow!("No session {}", num))?,
+            ),
+            None => None,
+        };
+        let (first, _) = self.frame_bounds();
+        self.seek(first);
+        Ok(())
+    }
+
+    /// First and last frame playback may reach
+    fn frame_bounds(&self) -> (usize, usize) {
+        match self.selected_session() {
+            Some(session) => (session.start_frame, session.end_frame),
+            None => (0, self.total_frames.saturating_sub(1)),
+        }
     }
 
     pub fn set_speed(&mut self, speed: f64) {
@@ -312,7 +668,7 @@ impl ReplayState {
             return None;
         }
 
-        if self.current_frame >= self.total_frames.saturating_sub(1) {
+        if self.current_frame >= self.frame_bounds().1 {
             self.playing = false;
             return None;
         }
@@ -332,12 +688,59 @@ impl Drop for ReplayState {
     }
 }
 
+/// Identifies a `/api/replay/frames` response
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameRangeKey {
+    pub start: usize,
+    pub count: usize,
+    pub metric_mask: Option<String>,
+    /// [`crate::state::CustomMetrics::generation`] merged into the frames
+    pub custom_metrics: u64,
+}
+
+/// Least recently used cache of serialized frame ranges
+#[derive(Default)]
+struct FrameCache {
+    /// Most recently used last
+    entries: VecDeque<(FrameRangeKey, Bytes)>,
+    bytes: usize,
+}
+
+impl FrameCache {
+    fn get(&mut self, key: &FrameRangeKey) -> Option<Bytes> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(index)?;
+        let body = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(body)
+    }
+
+    fn insert(&mut self, key: FrameRangeKey, body: Bytes) {
+        if let Some(index) = self.entries.iter().position(|(k, _)| *k == key) {
+            if let Some((_, old)) = self.entries.remove(index) {
+                self.bytes -= old.len();
+            }
+        }
+        self.bytes += body.len();
+        self.entries.push_back((key, body));
+        while self.entries.len() > FRAME_CACHE_ENTRIES || self.bytes > FRAME_CACHE_BYTES {
+            match self.entries.pop_front() {
+                Some((_, old)) => self.bytes -= old.len(),
+                None => break,
+            }
+        }
+    }
+}
+
 /// Serializable replay info for the API
 #[derive(Debug, Clone, Serialize)]
 pub struct ReplayInfo {
     pub total_frames: usize,
     pub tick_rate: u32,
     pub duration_secs: f64,
+    /// Where frames are missing, e.g. logging was paused
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub gaps: Vec<TickGap>,
     pub current_frame: usize,
     pub playing: bool,
     pub playback_speed: f64,
@@ -345,5 +748,61 @@ pub struct ReplayInfo {
     pub car_name: String,
     pub file_size: u64,
     pub laps: Vec<LapInfo>,
+    /// Sessions of a weekend recorded in one .ibt, left out for other
+    /// replays
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sessions: Vec<IbtSession>,
+    /// `session_num` of the session playback is limited to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<i32>,
+    /// Set for an .ibt cut short, which plays what's left
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery: Option<IbtRecovery>,
     pub replay_id: String,
+    /// Background full-scan results for .ibt replays
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<ReplayIndex>,
+    /// Reference lap set with `POST /api/replay/ghost`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ghost: Option<GhostSettings>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(start: usize) -> FrameRangeKey {
+        FrameRangeKey {
+            start,
+            count: 100,
+            metric_mask: None,
+            custom_metrics: 0,
+        }
+    }
+
+    #[test]
+    fn test_frame_cache_evicts_least_recently_used() {
+        let mut cache = FrameCache::default();
+        for start in 0..FRAME_CACHE_ENTRIES {
+            cache.insert(key(start), Bytes::from(vec![0u8; 10]));
+        }
+        // Touch the oldest so the second oldest goes first
+        assert!(cache.get(&key(0)).is_some());
+        cache.insert(key(1000), Bytes::from_static(b"[]"));
+        assert!(cache.get(&key(0)).is_some());
+        assert!(cache.get(&key(1)).is_none());
+        assert_eq!(cache.entries.len(), FRAME_CACHE_ENTRIES);
+        assert_eq!(cache.bytes, (FRAME_CACHE_ENTRIES - 1) * 10 + 2);
+
+        let stale = FrameRangeKey {
+            custom_metrics: 1,
+            ..key(0)
+        };
+        assert!(cache.get(&stale).is_none());
+
+        // One huge response pushes out everything else, then itself
+        cache.insert(key(2000), Bytes::from(vec![0u8; FRAME_CACHE_BYTES + 1]));
+        assert!(cache.entries.is_empty());
+        assert_eq!(cache.bytes, 0);
+    }
 }
@@ -0,0 +1,496 @@
+//! Background indexing of .ibt replays
+//!
+//! Loading an .ibt reads only what playback needs. [`index_ibt`] then scans
+//! every sample off the request path for per-lap channel stats, pedal use
+//! and pit stop times, off-track and pit road events, and which variables carry any data at
+//! all, handing back the partial index after each chunk so clients can show
+//! it as it fills in.
+
+use crate::overview::{self, Accumulator, Bucket};
+use crate::pits::{PitSample, PitStop, PitTracker, PitTransition};
+use anyhow::Result;
+use ost_adapters::ibt_parser::{is_off_track, IbtFile, Timeline, VarHeader};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Samples scanned between progress reports
+const CHUNK: usize = 4096;
+
+/// Positions of speed, throttle and brake in [`overview::CHANNELS`]
+const SPEED: usize = 0;
+const THROTTLE: usize = 1;
+const BRAKE: usize = 2;
+
+/// Pedal travel (0–1) that counts as on the pedal
+const PEDAL_APPLIED: f32 = 0.05;
+
+/// Throttle travel (0–1) that counts as flat out
+const FULL_THROTTLE: f32 = 0.99;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexStatus {
+    #[default]
+    Indexing,
+    Ready,
+    Failed,
+}
+
+/// What a full scan of a replay found, so far
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ReplayIndex {
+    pub status: IndexStatus,
+    /// Fraction of frames scanned, 0–1
+    pub progress: f32,
+    /// Laps completed within the frames scanned; the last lap of the file
+    /// is added once ready
+    pub laps: Vec<LapStats>,
+    pub events: Vec<ReplayEvent>,
+    /// Variables that are non-zero in at least one frame
+    pub channels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Channel stats over one lap
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LapStats {
+    pub lap_number: i32,
+    pub start_frame: usize,
+    /// Last frame of the lap, inclusive
+    pub end_frame: usize,
+    /// Min/max/avg of each [`overview::CHANNELS`] entry with data on the lap
+    pub channels: BTreeMap<&'static str, Bucket>,
+    /// How the pedals were used; `None` without throttle and brake data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<InputStats>,
+    /// A pit stop that ended on this lap
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pit_stop: Option<PitStop>,
+}
+
+/// Shares of a lap's frames by what the driver was doing with the pedals,
+/// for coaching: overlap is trail braking into the throttle (or left-foot
+/// braking), coasting is time lost on neither
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct InputStats {
+    /// On the brake and the throttle at once
+    pub overlap: f32,
+    /// On neither pedal
+    pub coasting: f32,
+    /// Flat out
+    pub full_throttle: f32,
+}
+
+/// Counts frames towards [`InputStats`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InputCounter {
+    frames: u32,
+    overlap: u32,
+    coasting: u32,
+    full_throttle: u32,
+}
+
+impl InputCounter {
+    /// Count a frame's pedal positions, 0-1
+    pub fn add(&mut self, throttle: f32, brake: f32) {
+        let (on_throttle, on_brake) = (throttle >= PEDAL_APPLIED, brake >= PEDAL_APPLIED);
+        self.frames += 1;
+        self.overlap += (on_throttle && on_brake) as u32;
+        self.coasting += (!on_throttle && !on_brake) as u32;
+        self.full_throttle += (throttle >= FULL_THROTTLE) as u32;
+    }
+
+    /// The stats so far, starting over
+    pub fn take(&mut self) -> Option<InputStats> {
+        let counter = std::mem::take(self);
+        let share = |n: u32| n as f32 / counter.frames as f32;
+        (counter.frames > 0).then(|| InputStats {
+            overlap: share(counter.overlap),
+            coasting: share(counter.coasting),
+            full_throttle: share(counter.full_throttle),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// Car off the track surface
+    OffTrack,
+    /// Car on pit road, from entry to exit
+    PitStop,
+}
+
+/// A span of frames worth marking on the timeline
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ReplayEvent {
+    pub kind: EventKind,
+    pub start_frame: usize,
+    /// Last frame of the event, inclusive; `None` while it is still going on
+    pub end_frame: Option<usize>,
+}
+
+/// What the index needs from one frame
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IndexSample {
+    /// Seconds since the first frame
+    pub time: f64,
+    pub lap: Option<i32>,
+    /// One value per [`overview::CHANNELS`] entry
+    pub values: [Option<f32>; 4],
+    pub off_track: bool,
+    pub on_pit_road: bool,
+}
+
+/// Builds a [`ReplayIndex`] one frame at a time
+#[derive(Debug, Default)]
+pub struct Indexer {
+    index: ReplayIndex,
+    frames: usize,
+    /// Lap in progress and its first frame
+    lap: Option<(i32, usize)>,
+    lap_channels: [Accumulator; 4],
+    lap_inputs: InputCounter,
+    lap_pit_stop: Option<PitStop>,
+    pits: PitTracker,
+    /// Positions in `index.events` of the open event of each kind
+    off_track: Option<usize>,
+    pit: Option<usize>,
+}
+
+impl Indexer {
+    pub fn add(&mut self, sample: IndexSample) {
+        let frame = self.frames;
+        self.frames += 1;
+
+        if let Some(lap) = sample.lap {
+            if self.lap.map(|(current, _)| current) != Some(lap) {
+                self.close_lap(frame);
+                self.lap = Some((lap, frame));
+            }
+        }
+        if self.lap.is_some() {
+            for (acc, value) in self.lap_channels.iter_mut().zip(sample.values) {
+                if let Some(value) = value.filter(|v| v.is_finite()) {
+                    acc.add(value);
+                }
+            }
+            let pedal = |i: usize| sample.values[i].filter(|v| v.is_finite());
+            if let (Some(throttle), Some(brake)) = (pedal(THROTTLE), pedal(BRAKE)) {
+                self.lap_inputs.add(throttle, brake);
+            }
+        }
+
+        let pit = self.pits.update(&PitSample {
+            time: sample.time,
+            on_pit_road: Some(sample.on_pit_road),
+            speed: sample.values[SPEED],
+            ..Default::default()
+        });
+        if let Some(PitTransition::Exited(stop)) = pit {
+            self.lap_pit_stop = Some(stop);
+        }
+
+        let events = &mut self.index.events;
+        track_event(
+            events,
+            &mut self.off_track,
+            EventKind::OffTrack,
+            sample.off_track,
+            frame,
+        );
+        track_event(
+            events,
+            &mut self.pit,
+            EventKind::PitStop,
+            sample.on_pit_road,
+            frame,
+        );
+    }
+
+    /// The index so far
+    pub fn index(&self) -> &ReplayIndex {
+        &self.index
+    }
+
+    /// Close the last lap and any events still going on
+    pub fn finish(mut self) -> ReplayIndex {
+        self.close_lap(self.frames);
+        let last = self.frames.saturating_sub(1);
+        for open in [self.off_track, self.pit].into_iter().flatten() {
+            self.index.events[open].end_frame = Some(last);
+        }
+        self.index.status = IndexStatus::Ready;
+        self.index.progress = 1.0;
+        self.index
+    }
+
+    /// End the lap in progress just before `frame`
+    fn close_lap(&mut self, frame: usize) {
+        let Some((lap_number, start_frame)) = self.lap.take() else {
+            return;
+        };
+        let channels = overview::CHANNELS
+            .into_iter()
+            .zip(&mut self.lap_channels)
+            .filter_map(|(name, acc)| Some((name, acc.take()?)))
+            .collect();
+        self.index.laps.push(LapStats {
+            lap_number,
+            start_frame,
+            end_frame: frame.saturating_sub(1),
+            channels,
+            inputs: self.lap_inputs.take(),
+            pit_stop: self.lap_pit_stop.take(),
+        });
+    }
+}
+
+/// Open an event when `active` starts and close it when it stops
+fn track_event(
+    events: &mut Vec<ReplayEvent>,
+    open: &mut Option<usize>,
+    kind: EventKind,
+    active: bool,
+    frame: usize,
+) {
+    match (*open, active) {
+        (None, true) => {
+            *open = Some(events.len());
+            events.push(ReplayEvent {
+                kind,
+                start_frame: frame,
+                end_frame: None,
+            });
+        }
+        (Some(i), false) => {
+            events[i].end_frame = Some(frame - 1);
+            *open = None;
+        }
+        _ => {}
+    }
+}
+
+/// Scan a whole .ibt, timing events by `timeline`, passing the partial index
+/// to `progress` after each chunk of frames. Returns `None` if `progress` asks to stop by returning
+/// false.
+pub fn index_ibt(
+    ibt: &IbtFile,
+    timeline: &Timeline,
+    mut progress: impl FnMut(&ReplayIndex) -> bool,
+) -> Result<Option<ReplayIndex>> {
+    let lap = ibt.var_header("Lap");
+    let surface = ibt.var_header("PlayerTrackSurface");
+    let pit_road = ibt.var_header("OnPitRoad");
+    let channels: Vec<Option<&VarHeader>> = overview::IBT_VARS
+        .iter()
+        .map(|name| ibt.var_header(name))
+        .collect();
+    let headers = ibt.var_headers_ref();
+    let mut populated = vec![false; headers.len()];
+
+    let mut indexer = Indexer::default();
+    let total = ibt.record_count();
+    let sample_len = ibt.sample_len();
+    let mut start = 0;
+    while start < total && sample_len > 0 {
+        let buf = ibt.read_raw_samples(start, CHUNK)?;
+        for sample in buf.chunks_exact(sample_len) {
+            for (populated, vh) in populated.iter_mut().zip(headers) {
+                if !*populated {
+                    *populated = sample
+                        .get(vh.byte_range())
+                        .is_some_and(|bytes| bytes.iter().any(|&b| b != 0));
+                }
+            }
+            let read = |vh: Option<&VarHeader>| vh.and_then(|vh| vh.read_scalar(sample));
+            indexer.add(IndexSample {
+                time: timeline.time_of(indexer.frames),
+                lap: read(lap).and_then(|v| v.as_i32()),
+                values: std::array::from_fn(|i| read(channels[i]).and_then(|v| v.as_f32())),
+                off_track: is_off_track(read(surface).and_then(|v| v.as_i32())),
+                on_pit_road: read(pit_road).and_then(|v| v.as_bool()) == Some(true),
+            });
+        }
+        start += CHUNK;
+
+        indexer.index.channels = headers
+            .iter()
+            .zip(&populated)
+            .filter(|(_, &populated)| populated)
+            .map(|(vh, _)| vh.name.clone())
+            .collect();
+        indexer.index.progress = start.min(total) as f32 / total as f32;
+        if !progress(indexer.index()) {
+            return Ok(None);
+        }
+    }
+    Ok(Some(indexer.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn sample(lap: i32, speed: f32, off_track: bool, on_pit_road: bool) -> IndexSample {
+        IndexSample {
+            time: 0.0,
+            lap: Some(lap),
+            values: [Some(speed), None, None, None],
+            off_track,
+            on_pit_road,
+        }
+    }
+
+    #[test]
+    fn test_laps_and_events() {
+        let mut indexer = Indexer::default();
+        indexer.add(sample(1, 10.0, false, true));
+        indexer.add(sample(1, 20.0, false, false));
+        indexer.add(sample(2, 50.0, true, false));
+        indexer.add(sample(2, 40.0, true, false));
+        indexer.add(sample(2, 30.0, false, false));
+        indexer.add(sample(3, 60.0, false, true));
+        assert_eq!(indexer.index().laps.len(), 2);
+        assert_eq!(indexer.index().events[2].end_frame, None);
+
+        let index = indexer.finish();
+        assert_eq!(index.status, IndexStatus::Ready);
+        let laps: Vec<_> = index
+            .laps
+            .iter()
+            .map(|l| (l.lap_number, l.start_frame, l.end_frame))
+            .collect();
+        assert_eq!(laps, [(1, 0, 1), (2, 2, 4), (3, 5, 5)]);
+        assert_eq!(
+            index.laps[1].channels["vehicle.speed"],
+            Bucket {
+                min: 30.0,
+                max: 50.0,
+                avg: 40.0
+            }
+        );
+        assert!(!index.laps[1].channels.contains_key("vehicle.rpm"));
+        assert_eq!(index.laps[1].inputs, None);
+
+        let event = |kind, start_frame, end_frame| ReplayEvent {
+            kind,
+            start_frame,
+            end_frame: Some(end_frame),
+        };
+        assert_eq!(
+            index.events,
+            [
+                event(EventKind::PitStop, 0, 0),
+                event(EventKind::OffTrack, 2, 3),
+                event(EventKind::PitStop, 5, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_input_stats() {
+        let mut indexer = Indexer::default();
+        for (throttle, brake) in [(1.0, 0.0), (1.0, 0.0), (0.0, 0.8), (0.3, 0.2), (0.0, 0.0)] {
+            indexer.add(IndexSample {
+                lap: Some(1),
+                values: [None, Some(throttle), Some(brake), None],
+                ..Default::default()
+            });
+        }
+        // Frames without both pedals don't count
+        indexer.add(IndexSample {
+            lap: Some(1),
+            values: [None, Some(1.0), None, None],
+            ..Default::default()
+        });
+        let index = indexer.finish();
+        assert_eq!(
+            index.laps[0].inputs,
+            Some(InputStats {
+                overlap: 0.2,
+                coasting: 0.2,
+                full_throttle: 0.4,
+            })
+        );
+    }
+
+    #[test]
+    fn test_pit_stop_times() {
+        let mut indexer = Indexer::default();
+        let stint = [
+            (1, 30.0, false),
+            (1, 20.0, true),
+            (2, 0.0, true),
+            (2, 0.0, true),
+            (2, 15.0, true),
+            (2, 30.0, false),
+        ];
+        for (i, (lap, speed, on_pit_road)) in stint.into_iter().enumerate() {
+            indexer.add(IndexSample {
+                time: i as f64 * 10.0,
+                ..sample(lap, speed, false, on_pit_road)
+            });
+        }
+        let index = indexer.finish();
+        assert_eq!(index.laps[0].pit_stop, None);
+        assert_eq!(
+            index.laps[1].pit_stop,
+            Some(PitStop {
+                pit_lane_secs: 40.0,
+                stationary_secs: 20.0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_index_fixture() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt");
+        let ibt = IbtFile::open(&path).unwrap();
+
+        let mut updates = Vec::new();
+        let timeline = ibt.build_timeline().unwrap();
+        let index = index_ibt(&ibt, &timeline, |index| {
+            updates.push(index.progress);
+            true
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(updates.len(), ibt.record_count().div_ceil(CHUNK));
+        assert!(updates.windows(2).all(|w| w[0] < w[1]));
+
+        // Lap boundaries line up with the replay's lap index
+        let laps = ibt.build_lap_index().unwrap();
+        let starts: Vec<_> = index.laps.iter().map(|l| l.start_frame).collect();
+        let expected: Vec<_> = laps.iter().map(|l| l.start_frame).collect();
+        assert_eq!(starts, expected);
+        assert_eq!(index.laps.last().unwrap().end_frame, ibt.record_count() - 1);
+        let speed = index.laps[2].channels["vehicle.speed"];
+        assert!(speed.min < speed.avg && speed.avg < speed.max);
+        let inputs = index.laps[2].inputs.unwrap();
+        assert!(inputs.full_throttle > 0.0);
+        assert!(inputs.coasting < 1.0);
+
+        assert!(index
+            .events
+            .iter()
+            .any(|e| e.kind == EventKind::OffTrack && e.end_frame.is_some()));
+        // Reset to the pits at the end of the session
+        assert!(index.events.iter().any(|e| e.kind == EventKind::PitStop));
+
+        assert!(index.channels.iter().any(|c| c == "Speed"));
+        assert!(index.channels.len() < ibt.var_headers_ref().len());
+    }
+
+    #[test]
+    fn test_index_stops_when_asked() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt");
+        let ibt = IbtFile::open(&path).unwrap();
+        let timeline = ibt.build_timeline().unwrap();
+        assert!(index_ibt(&ibt, &timeline, |_| false).unwrap().is_none());
+    }
+}
@@ -0,0 +1,285 @@
+//! Disk space for uploaded replays
+//!
+//! Uploads are written to `ost-replay` in the temp dir and read from there
+//! while loaded. A replay deletes its file when dropped, but a crash, a kill
+//! or a failed load leaves files behind, which add up on servers running for
+//! weeks. The store caps the directory: uploads older than `max_age_hours`
+//! are swept on a timer, and each upload makes room for itself by deleting
+//! the oldest files past `max_files` or `quota_mb`.
+//!
+//! ```toml
+//! [replay_storage]
+//! max_upload_mb = 1024
+//! quota_mb = 4096
+//! max_files = 8
+//! max_age_hours = 24
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// Files this new are left alone, as their upload may still be loading
+const GRACE: Duration = Duration::from_secs(60);
+
+/// How often [`run`] sweeps the directory
+const SWEEP_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Limits on uploaded replays, from the config file's `[replay_storage]`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ReplayStorageConfig {
+    /// Largest upload accepted
+    pub max_upload_mb: u64,
+    /// Most space all uploads may take
+    pub quota_mb: u64,
+    /// Most uploads kept
+    pub max_files: usize,
+    /// Uploads older than this are deleted, unless loaded
+    pub max_age_hours: u64,
+}
+
+impl Default for ReplayStorageConfig {
+    fn default() -> Self {
+        Self {
+            max_upload_mb: 1024,
+            quota_mb: 4096,
+            max_files: 8,
+            max_age_hours: 24,
+        }
+    }
+}
+
+impl ReplayStorageConfig {
+    pub fn check(&self) -> Result<()> {
+        if self.max_upload_mb == 0 || self.max_files == 0 {
+            bail!("replay_storage max_upload_mb and max_files must be at least 1");
+        }
+        if self.max_upload_mb > self.quota_mb {
+            bail!("replay_storage max_upload_mb is over quota_mb");
+        }
+        Ok(())
+    }
+}
+
+/// Space taken by uploads
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct StorageUsage {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// The uploaded replays directory and its limits
+pub struct ReplayStore {
+    dir: PathBuf,
+    config: ReplayStorageConfig,
+}
+
+impl Default for ReplayStore {
+    /// `ost-replay` in the temp dir, with the default limits
+    fn default() -> Self {
+        Self::new(
+            std::env::temp_dir().join("ost-replay"),
+            ReplayStorageConfig::default(),
+        )
+    }
+}
+
+impl ReplayStore {
+    pub fn new(dir: PathBuf, config: ReplayStorageConfig) -> Self {
+        Self { dir, config }
+    }
+
+    pub fn config(&self) -> &ReplayStorageConfig {
+        &self.config
+    }
+
+    pub fn max_upload_bytes(&self) -> u64 {
+        self.config.max_upload_mb * 1024 * 1024
+    }
+
+    fn quota_bytes(&self) -> u64 {
+        self.config.quota_mb * 1024 * 1024
+    }
+
+    /// A new path to write an upload named `file_name` to
+    pub fn upload_path(&self, file_name: &str) -> Result<PathBuf> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("creating {}", self.dir.display()))?;
+        // Only the final component, so a name can't point outside the dir
+        let name = Path::new(file_name)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("upload.ibt");
+        Ok(self.dir.join(format!(
+            "{}-{}-{}",
+            chrono::Utc::now().format("%Y%m%d%H%M%S"),
+            NEXT.fetch_add(1, Ordering::Relaxed),
+            name
+        )))
+    }
+
+    pub fn usage(&self) -> StorageUsage {
+        let files = self.files();
+        StorageUsage {
+            files: files.len(),
+            bytes: files.iter().map(|f| f.size).sum(),
+        }
+    }
+
+    /// Delete uploads other than `keep` that are past the age limit, then
+    /// the oldest until the rest are within `max_files` and the quota.
+    /// Fails if `keep` alone is over the quota. Returns the files deleted.
+    pub fn sweep(&self, keep: &[&Path]) -> Result<Vec<PathBuf>> {
+        let now = SystemTime::now();
+        let max_age = Duration::from_secs(self.config.max_age_hours * 3600);
+        let age = |modified: SystemTime| now.duration_since(modified).unwrap_or_default();
+
+        let (kept, mut others): (Vec<_>, Vec<_>) = self
+            .files()
+            .into_iter()
+            .partition(|f| keep.contains(&f.path.as_path()) || age(f.modified) < GRACE);
+        let mut count = kept.len();
+        let mut bytes: u64 = kept.iter().map(|f| f.size).sum();
+        // Newest first, so whatever is over the limits is at the end
+        others.sort_by_key(|f| std::cmp::Reverse(f.modified));
+        let mut deleted = Vec::new();
+        for file in others {
+            if count < self.config.max_files
+                && bytes + file.size <= self.quota_bytes()
+                && age(file.modified) <= max_age
+            {
+                count += 1;
+                bytes += file.size;
+                continue;
+            }
+            match std::fs::remove_file(&file.path) {
+                Ok(()) => deleted.push(file.path),
+                Err(e) => tracing::warn!("Failed to delete {}: {}", file.path.display(), e),
+            }
+        }
+        if !deleted.is_empty() {
+            tracing::info!("Deleted {} old replay uploads", deleted.len());
+        }
+        if bytes > self.quota_bytes() {
+            bail!(
+                "replay uploads take {} MB, over the {} MB quota",
+                bytes / 1024 / 1024,
+                self.config.quota_mb
+            );
+        }
+        Ok(deleted)
+    }
+
+    fn files(&self) -> Vec<StoredFile> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                meta.is_file().then(|| StoredFile {
+                    path: entry.path(),
+                    size: meta.len(),
+                    modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                })
+            })
+            .collect()
+    }
+}
+
+struct StoredFile {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// Sweep the store every so often, keeping the loaded replay's file
+pub async fn run(
+    store: std::sync::Arc<ReplayStore>,
+    replay: std::sync::Arc<tokio::sync::RwLock<Option<crate::replay::ReplayState>>>,
+) {
+    let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        let loaded = replay
+            .read()
+            .await
+            .as_ref()
+            .and_then(|rs| rs.temp_path().map(Path::to_path_buf));
+        let store = store.clone();
+        let swept = tokio::task::spawn_blocking(move || {
+            let keep: Vec<&Path> = loaded.iter().map(PathBuf::as_path).collect();
+            store.sweep(&keep)
+        })
+        .await;
+        if let Ok(Err(e)) = swept {
+            tracing::warn!("Replay storage: {:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_aged(path: &Path, size: usize, age_secs: u64) {
+        std::fs::write(path, vec![0u8; size]).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age_secs);
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sweep_enforces_limits() {
+        let dir = std::env::temp_dir().join(format!("ost-replay-store-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = ReplayStore::new(
+            dir.clone(),
+            ReplayStorageConfig {
+                max_upload_mb: 1,
+                quota_mb: 3,
+                max_files: 3,
+                max_age_hours: 1,
+            },
+        );
+        let mb = 1024 * 1024;
+        let paths: Vec<PathBuf> = (0..5)
+            .map(|i| store.upload_path(&format!("../{}.ibt", i)).unwrap())
+            .collect();
+        assert!(paths.iter().all(|p| p.parent() == Some(dir.as_path())));
+        // Oldest last: 2 hours old, then 10-minute steps, then just uploaded
+        write_aged(&paths[4], mb, 7200);
+        write_aged(&paths[3], mb, 3000);
+        write_aged(&paths[2], mb, 2400);
+        write_aged(&paths[1], mb, 1800);
+        write_aged(&paths[0], mb, 0);
+        assert_eq!(store.usage().files, 5);
+
+        // Past max_age, then over the quota, though the loaded one stays
+        let deleted = store.sweep(&[&paths[3]]).unwrap();
+        assert_eq!(deleted, vec![paths[2].clone(), paths[4].clone()]);
+        assert_eq!(
+            store.usage(),
+            StorageUsage {
+                files: 3,
+                bytes: 3 * mb as u64
+            }
+        );
+
+        // Just uploaded files are never deleted, even when nothing else fits
+        write_aged(&paths[1], 3 * mb, 0);
+        assert!(store.sweep(&[]).is_err());
+        assert!(paths[0].exists() && paths[1].exists() && !paths[3].exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
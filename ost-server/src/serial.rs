@@ -0,0 +1,119 @@
+//! Serial port access for sinks and the hardware adapter
+//!
+//! Opens a serial device for reading and writing in raw 8N1 mode at a given baud rate:
+//! termios on Unix, the comm API on Windows, where bare `COM3`-style names
+//! are accepted too. Reads return 0 bytes after [`READ_TIMEOUT`] without
+//! data, so readers can notice a silent device or being told to stop.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::time::Duration;
+
+/// How long a read waits for the first byte before returning none
+pub const READ_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Open `port` at `baud`
+pub fn open(port: &str, baud: u32) -> Result<File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true).write(true);
+    // Never become the server's controlling terminal
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, libc::O_NOCTTY);
+    let file = options
+        .open(device_path(port))
+        .with_context(|| format!("Failed to open serial port {}", port))?;
+    configure(&file, baud).with_context(|| format!("Failed to configure serial port {}", port))?;
+    Ok(file)
+}
+
+#[cfg(unix)]
+fn device_path(port: &str) -> String {
+    port.to_string()
+}
+
+#[cfg(unix)]
+fn configure(file: &File, baud: u32) -> Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let speed = match baud {
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        460800 => libc::B460800,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        921600 => libc::B921600,
+        _ => anyhow::bail!("Unsupported baud rate {}", baud),
+    };
+
+    let fd = file.as_raw_fd();
+    // SAFETY: `fd` is an open descriptor owned by `file` for the duration of
+    // these calls, and `termios` is fully initialised by tcgetattr before use.
+    unsafe {
+        let mut termios = std::mem::zeroed::<libc::termios>();
+        if libc::tcgetattr(fd, &mut termios) != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        libc::cfmakeraw(&mut termios);
+        termios.c_cflag |= libc::CLOCAL | libc::CREAD;
+        // Return what's there, or nothing once VTIME tenths of a second pass
+        termios.c_cc[libc::VMIN] = 0;
+        termios.c_cc[libc::VTIME] = (READ_TIMEOUT.as_millis() / 100) as libc::cc_t;
+        if libc::cfsetispeed(&mut termios, speed) != 0
+            || libc::cfsetospeed(&mut termios, speed) != 0
+            || libc::tcsetattr(fd, libc::TCSANOW, &termios) != 0
+        {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn device_path(port: &str) -> String {
+    // COM10 and up only open through the device namespace
+    if port.starts_with(r"\\.\") {
+        port.to_string()
+    } else {
+        format!(r"\\.\{}", port)
+    }
+}
+
+#[cfg(windows)]
+fn configure(file: &File, baud: u32) -> Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Devices::Communication::{
+        GetCommState, SetCommState, SetCommTimeouts, COMMTIMEOUTS, DCB, NOPARITY, ONESTOPBIT,
+    };
+    use windows::Win32::Foundation::HANDLE;
+
+    let handle = HANDLE(file.as_raw_handle());
+    let mut dcb = DCB {
+        DCBlength: std::mem::size_of::<DCB>() as u32,
+        ..Default::default()
+    };
+    // SAFETY: `handle` stays valid while `file` is borrowed, and `dcb` is a
+    // properly sized DCB.
+    unsafe {
+        GetCommState(handle, &mut dcb)?;
+        dcb.BaudRate = baud;
+        dcb.ByteSize = 8;
+        dcb.Parity = NOPARITY;
+        dcb.StopBits = ONESTOPBIT;
+        SetCommState(handle, &dcb)?;
+        // Reads return as soon as a byte is there, or with none after
+        // READ_TIMEOUT; writes give up rather than block forever
+        let timeouts = COMMTIMEOUTS {
+            ReadIntervalTimeout: u32::MAX,
+            ReadTotalTimeoutMultiplier: u32::MAX,
+            ReadTotalTimeoutConstant: READ_TIMEOUT.as_millis() as u32,
+            WriteTotalTimeoutMultiplier: 0,
+            WriteTotalTimeoutConstant: 1000,
+        };
+        SetCommTimeouts(handle, &timeouts)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Stream frames serialized once for every client that wants them alike
+//!
+//! `/api/stream` and `/api/telemetry/stream` send each client every frame
+//! as JSON: masked, in its units and with custom metrics merged. Clients
+//! that asked for the same mask and units would each turn a frame into the
+//! same JSON, so the first of them to get to it keeps the result in
+//! [`SharedFrames`] and the others share it. Clients taking full frames send
+//! its `Arc<str>` as it is, and delta clients diff against its value
+//! without a copy of their own.
+
+use ost_core::units::UnitProfile;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Frames kept, counting back from the newest: enough for clients a few
+/// frames behind one another to find them
+const KEEP_FRAMES: u64 = 16;
+
+/// Most encodings kept, however many different masks clients ask for
+const MAX_ENTRIES: usize = 256;
+
+/// What a frame was encoded for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodingKey {
+    /// `meta.seq` of the frame
+    pub seq: u64,
+    /// The metric mask as the client gave it
+    pub mask: Option<Arc<str>>,
+    pub units: UnitProfile,
+    /// [`crate::state::CustomMetrics::generation`] merged into the frame
+    pub custom_metrics: u64,
+}
+
+/// A frame as stream clients are sent it
+#[derive(Debug)]
+pub struct EncodedFrame {
+    /// Masked, in the client's units, rounded and with custom metrics merged
+    pub value: serde_json::Value,
+    /// `value` serialized
+    pub json: Arc<str>,
+}
+
+/// Encodings of the latest frames, shared by the stream clients
+#[derive(Default)]
+pub struct SharedFrames {
+    /// Oldest first
+    entries: Mutex<Vec<(EncodingKey, Arc<EncodedFrame>)>>,
+}
+
+impl SharedFrames {
+    /// The frame encoded for `key`, by `encode` unless a client has already.
+    /// `None` if `encode` fails.
+    pub fn get_or_encode(
+        &self,
+        key: EncodingKey,
+        encode: impl FnOnce() -> Option<EncodedFrame>,
+    ) -> Option<Arc<EncodedFrame>> {
+        if let Some(encoded) = Self::find(&self.lock(), &key) {
+            return Some(encoded);
+        }
+        // Encoded without holding the lock: clients racing to the same frame
+        // may both encode it, but no client waits on another's encoding
+        let encoded = Arc::new(encode()?);
+
+        let mut entries = self.lock();
+        if let Some(existing) = Self::find(&entries, &key) {
+            return Some(existing);
+        }
+        let newest = entries.iter().map(|(k, _)| k.seq).fold(key.seq, u64::max);
+        entries.retain(|(k, _)| k.seq + KEEP_FRAMES > newest);
+        // Not worth keeping for a client resuming from far back
+        if key.seq + KEEP_FRAMES > newest {
+            if entries.len() >= MAX_ENTRIES {
+                entries.remove(0);
+            }
+            entries.push((key, encoded.clone()));
+        }
+        Some(encoded)
+    }
+
+    fn find(
+        entries: &[(EncodingKey, Arc<EncodedFrame>)],
+        key: &EncodingKey,
+    ) -> Option<Arc<EncodedFrame>> {
+        entries
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, encoded)| encoded.clone())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<(EncodingKey, Arc<EncodedFrame>)>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn key(seq: u64, mask: Option<&str>) -> EncodingKey {
+        EncodingKey {
+            seq,
+            mask: mask.map(Arc::from),
+            units: UnitProfile::METRIC,
+            custom_metrics: 0,
+        }
+    }
+
+    #[test]
+    fn test_frames_encoded_once_per_key() {
+        let shared = SharedFrames::default();
+        let encodes = Cell::new(0);
+        let encode = |seq: u64| {
+            encodes.set(encodes.get() + 1);
+            Some(EncodedFrame {
+                value: serde_json::json!({ "seq": seq }),
+                json: format!("{{\"seq\":{}}}", seq).into(),
+            })
+        };
+
+        let first = shared.get_or_encode(key(1, None), || encode(1)).unwrap();
+        let second = shared.get_or_encode(key(1, None), || encode(1)).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(encodes.get(), 1);
+
+        // Another mask, units or custom metrics generation is its own
+        shared.get_or_encode(key(1, Some("vehicle")), || encode(1));
+        let imperial = EncodingKey {
+            units: UnitProfile::IMPERIAL,
+            ..key(1, None)
+        };
+        shared.get_or_encode(imperial, || encode(1));
+        let merged = EncodingKey {
+            custom_metrics: 1,
+            ..key(1, None)
+        };
+        shared.get_or_encode(merged, || encode(1));
+        assert_eq!(encodes.get(), 4);
+
+        // Failures aren't kept
+        assert!(shared.get_or_encode(key(2, None), || None).is_none());
+        shared.get_or_encode(key(2, None), || encode(2));
+        assert_eq!(encodes.get(), 5);
+    }
+
+    #[test]
+    fn test_old_frames_dropped() {
+        let shared = SharedFrames::default();
+        let encode = || {
+            Some(EncodedFrame {
+                value: serde_json::Value::Null,
+                json: "null".into(),
+            })
+        };
+        for seq in 1..=KEEP_FRAMES + 4 {
+            shared.get_or_encode(key(seq, None), encode);
+        }
+        let entries = shared.lock();
+        assert_eq!(entries.len(), KEEP_FRAMES as usize);
+        assert_eq!(entries[0].0.seq, 5);
+        drop(entries);
+
+        // A frame from before those isn't kept
+        shared.get_or_encode(key(2, None), encode);
+        assert_eq!(shared.lock().len(), KEEP_FRAMES as usize);
+    }
+}
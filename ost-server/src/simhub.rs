@@ -0,0 +1,246 @@
+//! SimHub-compatible game data
+//!
+//! Maps a frame onto the property names of SimHub's normalized game data
+//! (`SpeedKmh`, `Rpms`, `Gear`, `TyreTemperatureFrontLeft`, ...) in the
+//! `{"GameRunning", "GameName", "NewData"}` shape SimHub uses for its own JSON,
+//! so SimHub dashboards and plugins reading that shape keep working with
+//! this server as the only component talking to the game.
+//!
+//! SimHub's units are fixed: km/h, °C, psi, liters, G, degrees, 0-100 for
+//! pedals and percentages, and lap times as .NET `TimeSpan` strings
+//! (`"00:01:25.1230000"`). Booleans are 0/1 as in SimHub's own data.
+
+use ost_core::model::{SessionType, TelemetryFrame, WheelInfo};
+use serde_json::{json, Map, Value};
+
+const KPA_PER_PSI: f32 = 6.894_757;
+
+/// The frame as a SimHub game data document
+pub fn game_data(frame: &TelemetryFrame) -> Value {
+    let mut data = Map::new();
+    let mut put = |key: &str, value: Option<Value>| {
+        if let Some(value) = value {
+            data.insert(key.to_string(), value);
+        }
+    };
+
+    if let Some(v) = &frame.vehicle {
+        put("SpeedKmh", v.speed.map(|s| json!(s.0 * 3.6)));
+        put("Rpms", v.rpm.map(|r| json!(r.0)));
+        put("MaxRpm", v.max_rpm.map(|r| json!(r.0)));
+        put("Gear", v.gear.map(|g| json!(gear_name(g))));
+        put("Throttle", v.throttle.map(|p| json!(p.0 * 100.0)));
+        put("Brake", v.brake.map(|p| json!(p.0 * 100.0)));
+        put("Clutch", v.clutch.map(|p| json!(p.0 * 100.0)));
+        put("Handbrake", v.handbrake.map(|p| json!(p.0 * 100.0)));
+        put("CarModel", v.car_name.clone().map(Value::from));
+        put("CarClass", v.car_class.clone().map(Value::from));
+    }
+
+    if let Some(e) = &frame.engine {
+        put("Fuel", e.fuel_level.map(|l| json!(l.0)));
+        put("MaxFuel", e.fuel_capacity.map(|l| json!(l.0)));
+        put("FuelPercent", e.fuel_level_pct.map(|p| json!(p.0 * 100.0)));
+        put("WaterTemperature", e.water_temp.map(|t| json!(t.0)));
+        put("OilTemperature", e.oil_temp.map(|t| json!(t.0)));
+        if let Some(w) = &e.warnings {
+            put("PitLimiterOn", Some(flag(w.pit_speed_limiter)));
+            put("EngineStarted", Some(flag(!w.engine_stalled)));
+            put("EngineIgnitionOn", Some(flag(!w.engine_stalled)));
+        }
+    }
+
+    if let Some(t) = &frame.timing {
+        put("CurrentLapTime", t.current_lap_time.map(|s| timespan(s.0)));
+        put("LastLapTime", t.last_lap_time.map(|s| timespan(s.0)));
+        put("BestLapTime", t.best_lap_time.map(|s| timespan(s.0)));
+        put("CurrentLap", t.lap_number.map(Value::from));
+        put("CompletedLaps", t.laps_completed.map(Value::from));
+        put("Position", t.race_position.map(Value::from));
+        put("OpponentsCount", t.num_cars.map(Value::from));
+        put(
+            "TrackPositionPercent",
+            t.lap_distance_pct.map(|p| json!(p.0)),
+        );
+        put(
+            "DeltaToSessionBest",
+            t.delta_session_best.map(|s| json!(s.0)),
+        );
+        put("DeltaToAllTimeBest", t.delta_best.map(|s| json!(s.0)));
+    }
+
+    if let Some(s) = &frame.session {
+        put("TrackName", s.track_name.clone().map(Value::from));
+        put("TrackConfig", s.track_config.clone().map(Value::from));
+        put("TrackLength", s.track_length.map(|m| json!(m.0)));
+        put(
+            "SessionTypeName",
+            s.session_type.map(|t| json!(session_type_name(t))),
+        );
+        put(
+            "SessionTimeLeft",
+            s.session_time_remaining.map(|t| timespan(t.0)),
+        );
+        put("TotalLaps", s.session_laps.map(Value::from));
+        if let Some(f) = &s.flags {
+            put("Flag_Green", Some(flag(f.green)));
+            put("Flag_Yellow", Some(flag(f.yellow || f.caution)));
+            put("Flag_Blue", Some(flag(f.blue)));
+            put("Flag_White", Some(flag(f.white)));
+            put("Flag_Checkered", Some(flag(f.checkered)));
+            put("Flag_Black", Some(flag(f.black)));
+        }
+    }
+
+    if let Some(w) = &frame.weather {
+        put("AirTemperature", w.air_temp.map(|t| json!(t.0)));
+        put("RoadTemperature", w.track_temp.map(|t| json!(t.0)));
+    }
+
+    if let Some(p) = &frame.pit {
+        put("IsInPitLane", p.on_pit_road.map(flag));
+        put("IsInPit", p.pit_active.map(flag));
+    }
+
+    if let Some(e) = &frame.electronics {
+        put("ABSLevel", e.abs.map(|v| json!(v)));
+        put("ABSActive", e.abs_active.map(flag));
+        put("TCLevel", e.traction_control.map(|v| json!(v)));
+        put("BrakeBias", e.brake_bias.map(|p| json!(p.0 * 100.0)));
+        put("DRSEnabled", e.drs_status.map(|s| flag(s >= 3)));
+    }
+
+    if let Some(m) = &frame.motion {
+        if let Some(g) = &m.g_force {
+            put("AccelerationSway", Some(json!(g.x.0)));
+            put("AccelerationHeave", Some(json!(g.y.0)));
+            put("AccelerationSurge", Some(json!(g.z.0)));
+        }
+        if let Some(r) = &m.rotation {
+            put("OrientationPitch", Some(json!(r.x.0)));
+            put("OrientationYaw", Some(json!(r.y.0)));
+            put("OrientationRoll", Some(json!(r.z.0)));
+        }
+    }
+
+    if let Some(d) = &frame.driver {
+        put("PlayerName", d.name.clone().map(Value::from));
+    }
+
+    if let Some(wheels) = &frame.wheels {
+        for (corner, wheel) in [
+            ("FrontLeft", &wheels.front_left),
+            ("FrontRight", &wheels.front_right),
+            ("RearLeft", &wheels.rear_left),
+            ("RearRight", &wheels.rear_right),
+        ] {
+            wheel_data(&mut put, corner, wheel);
+        }
+    }
+
+    json!({
+        "GameRunning": true,
+        "GameName": frame.meta.game,
+        "NewData": data,
+    })
+}
+
+fn wheel_data(put: &mut impl FnMut(&str, Option<Value>), corner: &str, wheel: &WheelInfo) {
+    put(
+        &format!("TyreTemperature{corner}"),
+        wheel.surface_temp_middle.map(|t| json!(t.0)),
+    );
+    put(
+        &format!("TyreTemperature{corner}Inner"),
+        wheel.surface_temp_inner.map(|t| json!(t.0)),
+    );
+    put(
+        &format!("TyreTemperature{corner}Outer"),
+        wheel.surface_temp_outer.map(|t| json!(t.0)),
+    );
+    put(
+        &format!("TyrePressure{corner}"),
+        wheel.tyre_pressure.map(|p| json!(p.0 / KPA_PER_PSI)),
+    );
+    put(
+        &format!("TyreWear{corner}"),
+        wheel.tyre_wear.map(|p| json!(p.0 * 100.0)),
+    );
+    put(
+        &format!("BrakeTemperature{corner}"),
+        wheel.brake_temp.map(|t| json!(t.0)),
+    );
+}
+
+/// SimHub's gear names: "R", "N", then numbers
+fn gear_name(gear: i8) -> String {
+    match gear {
+        g if g < 0 => "R".to_string(),
+        0 => "N".to_string(),
+        g => g.to_string(),
+    }
+}
+
+fn session_type_name(session_type: SessionType) -> &'static str {
+    match session_type {
+        SessionType::Practice => "Practice",
+        SessionType::Qualifying => "Qualify",
+        SessionType::Race => "Race",
+        SessionType::Hotlap => "Hotlap",
+        SessionType::TimeTrial => "Time Trial",
+        SessionType::Drift => "Drift",
+        SessionType::Warmup => "Warmup",
+        SessionType::Other => "Other",
+    }
+}
+
+fn flag(on: bool) -> Value {
+    json!(on as u8)
+}
+
+/// Seconds as a .NET `TimeSpan` string, e.g. "00:01:25.1230000". Rounded to
+/// the millisecond, as f32 seconds carry noise below that.
+fn timespan(seconds: f32) -> Value {
+    let millis = (seconds.max(0.0) as f64 * 1000.0).round() as u64;
+    let secs = millis / 1000;
+    json!(format!(
+        "{:02}:{:02}:{:02}.{:03}0000",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        millis % 1000
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ost_core::adapter::TelemetryAdapter;
+
+    #[test]
+    fn test_game_data() {
+        let mut adapter = ost_adapters::DemoAdapter::new();
+        adapter.start().unwrap();
+        let frame = adapter.read_frame().unwrap().unwrap();
+        let value = game_data(&frame);
+
+        assert_eq!(value["GameName"], "Demo");
+        let data = &value["NewData"];
+        let speed = frame.vehicle.as_ref().unwrap().speed.unwrap().0;
+        assert!((data["SpeedKmh"].as_f64().unwrap() as f32 - speed * 3.6).abs() < 0.01);
+        assert!(data["Gear"].is_string());
+        assert_eq!(data["TrackName"], "Demo Circuit");
+        assert_eq!(data["Flag_Green"], 1);
+        assert!(data["TyrePressureFrontLeft"].as_f64().unwrap() > 20.0);
+        assert!(data["LastLapTime"].as_str().unwrap().starts_with("00:01:"));
+    }
+
+    #[test]
+    fn test_timespan() {
+        assert_eq!(timespan(85.123), json!("00:01:25.1230000"));
+        assert_eq!(timespan(3725.5), json!("01:02:05.5000000"));
+        assert_eq!(timespan(-1.0), json!("00:00:00.0000000"));
+        assert_eq!(gear_name(-1), "R");
+        assert_eq!(gear_name(0), "N");
+    }
+}
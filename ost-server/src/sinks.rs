@@ -1,42 +1,600 @@
 //! Output sink implementations
 //!
-//! Sinks forward telemetry data to UDP destinations
+//! Sinks forward telemetry data to UDP destinations, serial devices or other
+//! servers (see [`crate::relay`]). [`run`] keeps one sink per entry in the
+//! configured sink list and sends it every frame, at the entry's update rate
+//! and through its metric mask.
 
-#![allow(dead_code)]
-
-use crate::state::SinkConfig;
-use anyhow::Result;
+use crate::latency::{Latency, Stage};
+use crate::leds::LedConfig;
+use crate::relay::RelaySink;
+use crate::state::{SinkConfig, SinkFormat, SinkType};
+use anyhow::{bail, Result};
 use ost_core::model::{MetricMask, TelemetryFrame};
+use ost_core::units::UnitProfile;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, warn};
+
+/// Delays before creating a stopped sink again, doubling from the first to
+/// the last
+const RESTART_MIN: Duration = Duration::from_secs(1);
+const RESTART_MAX: Duration = Duration::from_secs(30);
 
 /// Trait for output sinks
 pub trait Sink: Send {
     fn send(&mut self, frame: &TelemetryFrame, mask: Option<&MetricMask>) -> Result<()>;
+
+    /// Whether the sink has stopped for good, so it needs creating again
+    fn stopped(&self) -> bool {
+        false
+    }
 }
 
 /// UDP sink
 pub struct UdpSink {
     socket: std::net::UdpSocket,
     addr: std::net::SocketAddr,
+    encoder: Encoder,
 }
 
 impl UdpSink {
-    pub fn new(host: String, port: u16) -> Result<Self> {
+    pub fn new(host: String, port: u16, format: SinkFormat, units: UnitProfile) -> Result<Self> {
         let socket = std::net::UdpSocket::bind("0.0.0.0:0")?;
         socket.set_nonblocking(true)?;
         let addr = format!("{}:{}", host, port).parse()?;
-        Ok(Self { socket, addr })
+        Ok(Self {
+            socket,
+            addr,
+            encoder: Encoder::new(format, units),
+        })
+    }
+
+    /// Shift-light settings for the `leds` format
+    pub fn with_leds(mut self, leds: LedConfig) -> Self {
+        self.encoder.leds = leds;
+        self
     }
 }
 
 impl Sink for UdpSink {
     fn send(&mut self, frame: &TelemetryFrame, mask: Option<&MetricMask>) -> Result<()> {
-        let json = frame.to_json_filtered(mask)?;
-        self.socket.send_to(json.as_bytes(), self.addr)?;
+        let bytes = self.encoder.encode(frame, mask)?;
+        self.socket.send_to(&bytes, self.addr)?;
         Ok(())
     }
 }
 
+/// Serial sink
+///
+/// A serial link has no datagrams, so messages are delimited: JSON formats
+/// are written one per line (NDJSON), MessagePack and Protobuf get a u16
+/// little-endian length prefix, and dashboard packets are written as they
+/// are, as are LED packets (both start with a magic and have a fixed length). Writes happen on
+/// a thread of their own; frames arriving while the previous one is still
+/// being written are dropped instead of queueing behind a slow port. A failed
+/// write stops the sink, and [`Sinks`] creates it again.
+pub struct SerialSink {
+    tx: SyncSender<Vec<u8>>,
+    writer: Option<JoinHandle<std::io::Result<()>>>,
+    encoder: Encoder,
+}
+
+impl SerialSink {
+    pub fn new(port: &str, baud: u32, format: SinkFormat, units: UnitProfile) -> Result<Self> {
+        let mut file = crate::serial::open(port, baud)?;
+        let (tx, rx) = mpsc::sync_channel::<Vec<u8>>(1);
+        let writer = std::thread::Builder::new()
+            .name(format!("serial-sink {}", port))
+            .spawn(move || {
+                // Ends when the sink is dropped and the channel closes
+                for message in rx {
+                    file.write_all(&message)?;
+                }
+                Ok(())
+            })?;
+        Ok(Self {
+            tx,
+            writer: Some(writer),
+            encoder: Encoder::new(format, units),
+        })
+    }
+
+    /// Shift-light settings for the `leds` format
+    pub fn with_leds(mut self, leds: LedConfig) -> Self {
+        self.encoder.leds = leds;
+        self
+    }
+}
+
+impl Sink for SerialSink {
+    fn send(&mut self, frame: &TelemetryFrame, mask: Option<&MetricMask>) -> Result<()> {
+        let mut message = self.encoder.encode(frame, mask)?;
+        match self.encoder.format {
+            SinkFormat::Json | SinkFormat::Simhub => message.push(b'\n'),
+            SinkFormat::Msgpack | SinkFormat::Protobuf => {
+                let len = u16::try_from(message.len())?;
+                message.splice(0..0, len.to_le_bytes());
+            }
+            SinkFormat::Dash | SinkFormat::Leds => {}
+        }
+        match self.tx.try_send(message) {
+            Ok(()) | Err(TrySendError::Full(_)) => Ok(()),
+            Err(TrySendError::Disconnected(_)) => match self.writer.take().map(JoinHandle::join) {
+                Some(Ok(Err(e))) => Err(anyhow::Error::from(e).context("Serial port write failed")),
+                _ => bail!("Serial port writer stopped"),
+            },
+        }
+    }
+
+    fn stopped(&self) -> bool {
+        self.writer.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+}
+
+/// Turns frames into the messages of a sink's format
+struct Encoder {
+    format: SinkFormat,
+    units: UnitProfile,
+    leds: LedConfig,
+}
+
+impl Encoder {
+    fn new(format: SinkFormat, units: UnitProfile) -> Self {
+        Self {
+            format,
+            units,
+            leds: LedConfig::default(),
+        }
+    }
+
+    fn encode(&self, frame: &TelemetryFrame, mask: Option<&MetricMask>) -> Result<Vec<u8>> {
+        Ok(match self.format {
+            SinkFormat::Json if self.units.is_metric() => {
+                frame.to_json_filtered(mask)?.into_bytes()
+            }
+            SinkFormat::Json => serde_json::to_vec(&frame.to_json_value_in(mask, self.units)?)?,
+            // Binary formats carry the model's units
+            SinkFormat::Msgpack => frame.to_bytes_filtered(mask)?,
+            SinkFormat::Protobuf => ost_proto::encode(frame, mask),
+            // SimHub has fixed property names and units of its own
+            SinkFormat::Simhub => serde_json::to_vec(&crate::simhub::game_data(frame))?,
+            SinkFormat::Dash => crate::dash::encode(frame).to_vec(),
+            SinkFormat::Leds => crate::leds::encode(frame, &self.leds),
+        })
+    }
+}
+
 /// Create a sink from configuration
 pub fn create_sink(config: &SinkConfig) -> Result<Box<dyn Sink>> {
-    Ok(Box::new(UdpSink::new(config.host.clone(), config.port)?))
+    let leds = config.leds.clone().unwrap_or_default();
+    leds.validate()?;
+    Ok(match &config.sink_type {
+        SinkType::Udp { host, port } => Box::new(
+            UdpSink::new(host.clone(), *port, config.format, config.units)?.with_leds(leds),
+        ),
+        SinkType::Serial { port, baud } => {
+            Box::new(SerialSink::new(port, *baud, config.format, config.units)?.with_leds(leds))
+        }
+        SinkType::Relay { url, token } => Box::new(RelaySink::new(url, token.as_deref())?),
+    })
+}
+
+/// A configured sink and when it last sent
+struct ActiveSink {
+    config: SinkConfig,
+    sink: Box<dyn Sink>,
+    mask: Option<MetricMask>,
+    interval: Option<Duration>,
+    last_sent: Option<Instant>,
+}
+
+/// The sinks built from the current configuration
+#[derive(Default)]
+pub struct Sinks {
+    active: HashMap<String, ActiveSink>,
+    /// Configs that could not be turned into a sink, so the failure is only
+    /// logged once per config
+    failed: HashMap<String, SinkConfig>,
+    /// Sinks that stopped, created again once their delay has passed
+    restarting: HashMap<String, Restart>,
+    latency: Arc<Latency>,
+}
+
+/// A stopped sink waiting to be created again
+struct Restart {
+    config: SinkConfig,
+    at: Instant,
+    delay: Duration,
+}
+
+impl Sinks {
+    /// Sinks recording their frames' latency in `latency`
+    pub fn new(latency: Arc<Latency>) -> Self {
+        Self {
+            latency,
+            ..Default::default()
+        }
+    }
+
+    /// Create, replace and drop sinks to match `configs`
+    pub async fn sync(&mut self, configs: &[SinkConfig]) {
+        self.active
+            .retain(|id, a| configs.iter().any(|c| &c.id == id && *c == a.config));
+        self.failed
+            .retain(|id, f| configs.iter().any(|c| &c.id == id && c == f));
+        self.restarting
+            .retain(|id, r| configs.iter().any(|c| &c.id == id && *c == r.config));
+
+        let now = Instant::now();
+        for config in configs {
+            if self.active.contains_key(&config.id) || self.failed.contains_key(&config.id) {
+                continue;
+            }
+            if self.restarting.get(&config.id).is_some_and(|r| now < r.at) {
+                continue;
+            }
+            // Opening a serial port can block, so sinks are created off the runtime
+            let created = {
+                let config = config.clone();
+                tokio::task::spawn_blocking(move || create_sink(&config))
+                    .await
+                    .unwrap_or_else(|e| Err(e.into()))
+            };
+            match created {
+                Ok(sink) => {
+                    self.restarting.remove(&config.id);
+                    let interval = config
+                        .update_rate_hz
+                        .filter(|hz| *hz > 0.0)
+                        .map(|hz| Duration::from_secs_f64(1.0 / hz));
+                    self.active.insert(
+                        config.id.clone(),
+                        ActiveSink {
+                            config: config.clone(),
+                            sink,
+                            mask: config.metric_mask.as_deref().map(MetricMask::parse),
+                            interval,
+                            last_sent: None,
+                        },
+                    );
+                }
+                // A stopped sink's device may come back, so keep trying
+                Err(e) => match self.restarting.get_mut(&config.id) {
+                    Some(restart) => {
+                        debug!("Sink {}: {:#}", config.id, e);
+                        restart.delay = (restart.delay * 2).min(RESTART_MAX);
+                        restart.at = now + restart.delay;
+                    }
+                    None => {
+                        warn!("Sink {}: {:#}", config.id, e);
+                        self.failed.insert(config.id.clone(), config.clone());
+                    }
+                },
+            }
+        }
+    }
+
+    /// Send `frame` to every sink that is due
+    pub fn send(&mut self, frame: &TelemetryFrame, now: Instant) {
+        let mut stopped = Vec::new();
+        for (id, a) in &mut self.active {
+            let due = match (a.interval, a.last_sent) {
+                (Some(interval), Some(last)) => now.duration_since(last) >= interval,
+                _ => true,
+            };
+            if !due {
+                continue;
+            }
+            a.last_sent = Some(now);
+            match a.sink.send(frame, a.mask.as_ref()) {
+                Ok(()) => self.latency.record(Stage::Sink, frame),
+                Err(e) if a.sink.stopped() => {
+                    warn!("Sink {} stopped, restarting: {:#}", id, e);
+                    stopped.push(id.clone());
+                }
+                // Nobody listening is normal for UDP; don't flood the log
+                Err(e) => debug!("Sink {}: {:#}", id, e),
+            }
+        }
+        for id in stopped {
+            if let Some(a) = self.active.remove(&id) {
+                let restart = Restart {
+                    config: a.config,
+                    at: now + RESTART_MIN,
+                    delay: RESTART_MIN,
+                };
+                self.restarting.insert(id, restart);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.active.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+}
+
+/// Forward telemetry to the configured sinks until the channel closes
+pub async fn run(
+    configs: Arc<RwLock<Vec<SinkConfig>>>,
+    latency: Arc<Latency>,
+    mut rx: broadcast::Receiver<TelemetryFrame>,
+) {
+    let mut sinks = Sinks::new(latency);
+    loop {
+        let frame = match rx.recv().await {
+            Ok(f) => f,
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!("Sinks: skipped {} frames (lagged)", n);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        sinks.sync(&configs.read().await).await;
+        sinks.send(&frame, Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ost_core::adapter::TelemetryAdapter;
+
+    /// Send one demo frame through a sink and return it with the datagram
+    fn receive(format: SinkFormat, units: UnitProfile) -> (TelemetryFrame, Vec<u8>) {
+        let listener = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener
+            .set_read_timeout(Some(std::time::Duration::from_secs(2)))
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let mut sink = UdpSink::new("127.0.0.1".to_string(), port, format, units).unwrap();
+        let mut adapter = ost_adapters::DemoAdapter::new();
+        adapter.start().unwrap();
+        let frame = adapter.read_frame().unwrap().unwrap();
+        sink.send(&frame, None).unwrap();
+
+        let mut buf = vec![0u8; 65536];
+        let n = listener.recv(&mut buf).unwrap();
+        buf.truncate(n);
+        (frame, buf)
+    }
+
+    #[test]
+    fn test_udp_sink_json() {
+        let (_, bytes) = receive(SinkFormat::Json, UnitProfile::METRIC);
+        let value: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(value["meta"]["game"], "Demo");
+    }
+
+    #[test]
+    fn test_udp_sink_msgpack() {
+        let (_, bytes) = receive(SinkFormat::Msgpack, UnitProfile::METRIC);
+        let frame = TelemetryFrame::from_bytes(&bytes).unwrap();
+        assert_eq!(frame.meta.game, "Demo");
+    }
+
+    #[test]
+    fn test_udp_sink_protobuf() {
+        let (_, bytes) = receive(SinkFormat::Protobuf, UnitProfile::METRIC);
+        let frame = ost_proto::decode(&bytes).unwrap();
+        assert_eq!(frame.meta.game, "Demo");
+    }
+
+    #[test]
+    fn test_udp_sink_simhub() {
+        let (_, bytes) = receive(SinkFormat::Simhub, UnitProfile::IMPERIAL);
+        let value: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(value["GameName"], "Demo");
+        // SimHub's units regardless of the profile
+        assert!(value["NewData"]["SpeedKmh"].is_number());
+    }
+
+    #[test]
+    fn test_udp_sink_dash() {
+        let (_, bytes) = receive(SinkFormat::Dash, UnitProfile::METRIC);
+        assert_eq!(bytes.len(), crate::dash::PACKET_LEN);
+        assert_eq!(&bytes[..2], b"OD");
+    }
+
+    #[tokio::test]
+    async fn test_sinks_follow_config_and_rate() {
+        let listener = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener
+            .set_read_timeout(Some(std::time::Duration::from_millis(200)))
+            .unwrap();
+        let mut config = SinkConfig {
+            id: "sink-1".to_string(),
+            sink_type: SinkType::Udp {
+                host: "127.0.0.1".to_string(),
+                port: listener.local_addr().unwrap().port(),
+            },
+            update_rate_hz: Some(10.0),
+            metric_mask: Some("vehicle".to_string()),
+            format: SinkFormat::Json,
+            units: UnitProfile::METRIC,
+            leds: None,
+        };
+        let bad = SinkConfig {
+            id: "sink-2".to_string(),
+            sink_type: SinkType::Udp {
+                host: "not a host".to_string(),
+                port: 9200,
+            },
+            ..config.clone()
+        };
+        let mut sinks = Sinks::default();
+        sinks.sync(&[config.clone(), bad]).await;
+        assert_eq!(sinks.len(), 1);
+
+        let mut adapter = ost_adapters::DemoAdapter::new();
+        adapter.start().unwrap();
+        let frame = adapter.read_frame().unwrap().unwrap();
+        let start = Instant::now();
+        sinks.send(&frame, start);
+        // Within the 100ms interval: skipped
+        sinks.send(&frame, start + Duration::from_millis(50));
+        sinks.send(&frame, start + Duration::from_millis(100));
+
+        let mut buf = vec![0u8; 65536];
+        let mut received = Vec::new();
+        while let Ok(n) = listener.recv(&mut buf) {
+            received.push(serde_json::from_slice::<serde_json::Value>(&buf[..n]).unwrap());
+        }
+        assert_eq!(received.len(), 2);
+        assert!(received[0].get("vehicle").is_some());
+        assert!(received[0].get("engine").is_none());
+
+        // Changing a config rebuilds its sink; removing it drops the sink
+        config.metric_mask = None;
+        sinks.sync(&[config.clone()]).await;
+        assert!(sinks.active["sink-1"].mask.is_none());
+        sinks.sync(&[]).await;
+        assert!(sinks.is_empty());
+    }
+
+    #[test]
+    fn test_udp_sink_leds() {
+        let (_, bytes) = receive(SinkFormat::Leds, UnitProfile::METRIC);
+        assert_eq!(&bytes[..2], b"OL");
+        assert_eq!(bytes.len(), 4 + 16 * 3);
+    }
+
+    #[test]
+    fn test_sink_type_defaults_to_udp() {
+        let config: SinkConfig = serde_json::from_value(
+            serde_json::json!({"id": "a", "host": "10.0.0.2", "port": 9200}),
+        )
+        .unwrap();
+        assert_eq!(
+            config.sink_type,
+            SinkType::Udp {
+                host: "10.0.0.2".to_string(),
+                port: 9200
+            }
+        );
+        let config: SinkConfig = serde_json::from_value(serde_json::json!({
+            "id": "b", "type": "serial", "port": "/dev/ttyUSB0", "baud": 115200, "format": "dash"
+        }))
+        .unwrap();
+        assert!(matches!(
+            config.sink_type,
+            SinkType::Serial { baud: 115200, .. }
+        ));
+        let value = serde_json::to_value(&config).unwrap();
+        assert_eq!(value["type"], "serial");
+        assert_eq!(value["port"], "/dev/ttyUSB0");
+    }
+
+    /// Write through a serial sink to a pseudo-terminal and read it back
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_serial_sink() {
+        use std::io::Read;
+        use std::os::unix::io::FromRawFd;
+
+        // SAFETY: standard pty setup; the master fd is handed to a File that
+        // owns it from then on
+        let (mut master, slave) = unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            assert!(fd >= 0);
+            assert_eq!(libc::grantpt(fd), 0);
+            assert_eq!(libc::unlockpt(fd), 0);
+            let name = std::ffi::CStr::from_ptr(libc::ptsname(fd));
+            let slave = name.to_str().unwrap().to_string();
+            (std::fs::File::from_raw_fd(fd), slave)
+        };
+
+        let mut sink =
+            SerialSink::new(&slave, 115200, SinkFormat::Json, UnitProfile::METRIC).unwrap();
+        let mut adapter = ost_adapters::DemoAdapter::new();
+        adapter.start().unwrap();
+        let frame = adapter.read_frame().unwrap().unwrap();
+        let mask = MetricMask::parse("vehicle.speed");
+        sink.send(&frame, Some(&mask)).unwrap();
+
+        let mut line = Vec::new();
+        let mut byte = [0u8];
+        while line.last() != Some(&b'\n') {
+            master.read_exact(&mut byte).unwrap();
+            line.push(byte[0]);
+        }
+        let value: serde_json::Value = serde_json::from_slice(&line).unwrap();
+        assert!(value["vehicle"]["speed"].is_number());
+
+        assert!(SerialSink::new(&slave, 12345, SinkFormat::Json, UnitProfile::METRIC).is_err());
+    }
+
+    /// A serial sink whose device goes away stops, and is created again
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_serial_sink_restarts() {
+        use std::os::unix::io::FromRawFd;
+
+        // SAFETY: as in test_serial_sink
+        let (master, slave) = unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            assert!(fd >= 0);
+            assert_eq!(libc::grantpt(fd), 0);
+            assert_eq!(libc::unlockpt(fd), 0);
+            let name = std::ffi::CStr::from_ptr(libc::ptsname(fd));
+            let slave = name.to_str().unwrap().to_string();
+            (std::fs::File::from_raw_fd(fd), slave)
+        };
+        let config = SinkConfig {
+            id: "serial".to_string(),
+            sink_type: SinkType::Serial {
+                port: slave,
+                baud: 115200,
+            },
+            update_rate_hz: None,
+            metric_mask: None,
+            format: SinkFormat::Json,
+            units: UnitProfile::METRIC,
+            leds: None,
+        };
+        let mut sinks = Sinks::default();
+        sinks.sync(std::slice::from_ref(&config)).await;
+        assert_eq!(sinks.len(), 1);
+
+        // Writes to a pty fail once its master is closed
+        drop(master);
+        let frame = TelemetryFrame::new("Demo");
+        for _ in 0..100 {
+            sinks.send(&frame, Instant::now());
+            if sinks.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(sinks.is_empty(), "a failed writer should stop the sink");
+        assert!(sinks.restarting.contains_key("serial"));
+
+        // Not created again until the delay passes, then retried with backoff
+        sinks.sync(std::slice::from_ref(&config)).await;
+        assert_eq!(sinks.restarting["serial"].delay, RESTART_MIN);
+        sinks.restarting.get_mut("serial").unwrap().at = Instant::now();
+        sinks.sync(std::slice::from_ref(&config)).await;
+        assert!(sinks.is_empty());
+        assert_eq!(sinks.restarting["serial"].delay, RESTART_MIN * 2);
+        assert!(!sinks.failed.contains_key("serial"));
+    }
+
+    #[test]
+    fn test_udp_sink_units() {
+        let (frame, bytes) = receive(SinkFormat::Json, UnitProfile::IMPERIAL);
+        let value: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let mps = frame.vehicle.unwrap().speed.unwrap().0;
+        let mph = value["vehicle"]["speed"].as_f64().unwrap() as f32;
+        assert!((mph - mps * 2.236_936).abs() < 0.01);
+    }
 }
@@ -0,0 +1,187 @@
+//! Spotter calls from lap distance
+//!
+//! Backs the `spotter` event on `/api/stream`: cars whose lap distance puts
+//! them within a car length of the player are called out, so audio spotter
+//! integrations work with sims that don't have a spotter of their own.
+//!
+//! Lap distance says nothing about which side a car is on. When the sim
+//! reports it (iRacing's `CarLeftRight`), the side is taken from there,
+//! otherwise overlapping cars are called as `alongside`. The call is only
+//! ever `clear` with no cars, and never `clear` with some, whatever the sim
+//! says.
+
+use ost_core::model::{TelemetryFrame, TrackSurface};
+use serde::Serialize;
+
+/// Cars closer than this along the track overlap the player (meters)
+pub const CAR_LENGTH: f32 = 5.0;
+
+/// What the spotter says
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpotterCall {
+    #[default]
+    Clear,
+    CarLeft,
+    CarRight,
+    CarsBothSides,
+    /// Overlapping, side unknown
+    Alongside,
+}
+
+/// A change of spotter call, sent as the `spotter` event
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SpotterUpdate {
+    pub call: SpotterCall,
+    /// Indices of the overlapping cars, nearest first
+    pub cars: Vec<u32>,
+}
+
+/// Tracks the current call so only changes are published
+#[derive(Debug, Default)]
+pub struct Spotter {
+    current: SpotterUpdate,
+}
+
+impl Spotter {
+    /// Work out the call for `frame`, returning it if it changed
+    pub fn update(&mut self, frame: &TelemetryFrame) -> Option<SpotterUpdate> {
+        let cars = overlapping_cars(frame);
+        let call = match reported_side(frame) {
+            _ if cars.is_empty() => SpotterCall::Clear,
+            Some(SpotterCall::Clear) | None => SpotterCall::Alongside,
+            Some(side) => side,
+        };
+        self.set(SpotterUpdate { call, cars })
+    }
+
+    /// Go back to clear, e.g. when the adapter stops. Returns the clear call
+    /// if something was being called.
+    pub fn reset(&mut self) -> Option<SpotterUpdate> {
+        self.set(SpotterUpdate::default())
+    }
+
+    fn set(&mut self, update: SpotterUpdate) -> Option<SpotterUpdate> {
+        if update == self.current {
+            return None;
+        }
+        self.current = update.clone();
+        Some(update)
+    }
+}
+
+/// Cars within [`CAR_LENGTH`] of the player along the track, nearest first.
+/// Cars in the pits or not in the world are ignored, as is everything while
+/// the player is on pit road.
+fn overlapping_cars(frame: &TelemetryFrame) -> Vec<u32> {
+    let player_pct = frame.timing.as_ref().and_then(|t| t.lap_distance_pct);
+    let track_length = frame.session.as_ref().and_then(|s| s.track_length);
+    let (Some(player_pct), Some(track_length), Some(competitors)) =
+        (player_pct, track_length, frame.competitors.as_ref())
+    else {
+        return Vec::new();
+    };
+    if frame.pit.as_ref().and_then(|p| p.on_pit_road) == Some(true) {
+        return Vec::new();
+    }
+    let player_index = frame.driver.as_ref().and_then(|d| d.car_index);
+
+    let mut cars: Vec<(u32, f32)> = competitors
+        .iter()
+        .filter(|c| Some(c.car_index) != player_index)
+        .filter(|c| c.on_pit_road != Some(true))
+        .filter(|c| c.track_surface != Some(TrackSurface::NotInWorld))
+        .filter_map(|c| {
+            // Shortest way around the lap, either direction
+            let d = (c.lap_distance_pct?.0 - player_pct.0).rem_euclid(1.0);
+            let meters = d.min(1.0 - d) * track_length.0;
+            (meters < CAR_LENGTH).then_some((c.car_index, meters))
+        })
+        .collect();
+    cars.sort_by(|a, b| a.1.total_cmp(&b.1));
+    cars.into_iter().map(|(car, _)| car).collect()
+}
+
+/// The sim's own left/right indication, if it has one
+fn reported_side(frame: &TelemetryFrame) -> Option<SpotterCall> {
+    let value = frame.extras.get("iracing")?.get("CarLeftRight")?.as_i64()?;
+    // irsdk_CarLeftRight: off, clear, left, right, both, two left, two right
+    match value {
+        1 => Some(SpotterCall::Clear),
+        2 | 5 => Some(SpotterCall::CarLeft),
+        3 | 6 => Some(SpotterCall::CarRight),
+        4 => Some(SpotterCall::CarsBothSides),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ost_core::units::Percentage;
+
+    fn frame(others: serde_json::Value) -> TelemetryFrame {
+        serde_json::from_value(serde_json::json!({
+            "meta": {"timestamp": "2026-01-01T00:00:00Z", "game": "test", "tick": 1},
+            "timing": {"lap_distance_pct": 0.999},
+            "session": {"track_length": 4000.0},
+            "driver": {"car_index": 0},
+            "competitors": others
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_calls_overlap_across_the_line_then_clear() {
+        let mut spotter = Spotter::default();
+        let alongside = frame(serde_json::json!([
+            {"car_index": 0, "lap_distance_pct": 0.999},
+            // 6 m ahead across the line: not overlapping
+            {"car_index": 1, "lap_distance_pct": 0.0005},
+            // 2 m behind
+            {"car_index": 2, "lap_distance_pct": 0.9985},
+            {"car_index": 3, "lap_distance_pct": 0.999, "on_pit_road": true}
+        ]));
+        assert_eq!(
+            spotter.update(&alongside),
+            Some(SpotterUpdate {
+                call: SpotterCall::Alongside,
+                cars: vec![2],
+            })
+        );
+        assert_eq!(spotter.update(&alongside), None, "unchanged call repeated");
+
+        let clear = frame(serde_json::json!([{"car_index": 2, "lap_distance_pct": 0.5}]));
+        assert_eq!(spotter.update(&clear), Some(SpotterUpdate::default()));
+        assert_eq!(spotter.reset(), None);
+    }
+
+    #[test]
+    fn test_uses_side_reported_by_sim() {
+        let mut spotter = Spotter::default();
+        let mut frame = frame(serde_json::json!([{"car_index": 2, "lap_distance_pct": 0.999}]));
+        frame.extras.insert(
+            "iracing".to_string(),
+            serde_json::json!({"CarLeftRight": 3}),
+        );
+        let update = spotter.update(&frame).unwrap();
+        assert_eq!(update.call, SpotterCall::CarRight);
+        assert_eq!(update.cars, vec![2]);
+        assert_eq!(spotter.reset(), Some(SpotterUpdate::default()));
+
+        // Calls always agree with the cars sent along
+        frame.extras.insert(
+            "iracing".to_string(),
+            serde_json::json!({"CarLeftRight": 1}),
+        );
+        let update = spotter.update(&frame).unwrap();
+        assert_eq!(update.call, SpotterCall::Alongside);
+        assert_eq!(update.cars, vec![2]);
+        frame.extras.insert(
+            "iracing".to_string(),
+            serde_json::json!({"CarLeftRight": 2}),
+        );
+        frame.competitors.as_mut().unwrap()[0].lap_distance_pct = Some(Percentage::new(0.5));
+        assert_eq!(spotter.update(&frame), Some(SpotterUpdate::default()));
+    }
+}
@@ -1,11 +1,31 @@
 //! Application state management
 
+use crate::archive::Archive;
+use crate::config::AuthConfig;
+use crate::events::{EventDetector, SessionEvent};
+use crate::hardware::HardwareConfig;
 use crate::history::HistoryBuffer;
+use crate::ingest::IngestHub;
+use crate::latency::Latency;
 use crate::persistence::PersistenceConfig;
+use crate::registry::AdapterRegistry;
+use crate::relay::RelayHub;
+use crate::remote::RemoteConfig;
 use crate::replay::ReplayState;
+use crate::replay_store::ReplayStore;
 use crate::sessions::SessionStore;
-use ost_core::{adapter::TelemetryAdapter, model::TelemetryFrame};
+use crate::shared_frames::SharedFrames;
+use crate::spotter::Spotter;
+use crate::subscribers::Subscribers;
+use crate::transforms::TransformConfig;
+use ost_core::adapter::{AdapterError, Capabilities, ConnectionState, ErrorKind, TelemetryAdapter};
+use ost_core::model::{DriverData, SessionType};
+use ost_core::tracks::TrackDatabase;
+use ost_core::units::Meters;
+use ost_core::validate::{ValidationIssue, ValidationMode};
+use ost_core::{model::TelemetryFrame, units::UnitProfile};
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::{broadcast, RwLock};
 use tokio_util::sync::CancellationToken;
@@ -14,27 +34,46 @@ use tokio_util::sync::CancellationToken;
 #[derive(Clone)]
 pub struct AppState {
     /// All registered adapters
-    pub adapters: Arc<RwLock<Vec<Box<dyn TelemetryAdapter>>>>,
+    pub adapters: Arc<RwLock<Vec<AdapterSlot>>>,
 
     /// Key of the currently active adapter
     pub active_adapter: Arc<RwLock<Option<String>>>,
 
+    /// Cancellation token for the active adapter's reader task
+    pub reader_cancel: Arc<RwLock<Option<CancellationToken>>>,
+
     /// Broadcast channel for telemetry frames
     /// Multiple consumers can subscribe to receive frames
     pub telemetry_tx: broadcast::Sender<TelemetryFrame>,
 
+    /// Sequence number of the last broadcast frame (see `meta.seq`)
+    pub frame_seq: Arc<AtomicU64>,
+
+    /// Recent frame latencies, for `/metrics`
+    pub latency: Arc<Latency>,
+
     /// Sinks for forwarding telemetry data
     pub sinks: Arc<RwLock<Vec<SinkConfig>>>,
 
     /// Active replay state (None when not in replay mode)
     pub replay: Arc<RwLock<Option<ReplayState>>>,
 
+    /// Directory and limits for uploaded replays
+    pub replay_store: Arc<ReplayStore>,
+
     /// Cancellation token for the replay playback task
     pub replay_cancel: Arc<RwLock<Option<CancellationToken>>>,
 
     /// Adapter keys that should not auto-start (e.g. "demo")
     pub disabled_adapters: Arc<RwLock<HashSet<String>>>,
 
+    /// Adapter keys tried before the rest when several games are detected,
+    /// from the config file's `[adapters]` and `/api/adapters/:key/activate`
+    pub adapter_priority: Arc<RwLock<Vec<String>>>,
+
+    /// Built-in adapters unregistered through the API
+    pub adapter_registry: Arc<AdapterRegistry>,
+
     /// Broadcast channel for status updates (serialized JSON strings)
     pub status_tx: broadcast::Sender<String>,
 
@@ -47,8 +86,11 @@ pub struct AppState {
     /// Persistence configuration for auto-saving telemetry to disk
     pub persistence_config: Arc<RwLock<PersistenceConfig>>,
 
-    /// Optional API authentication token (from OST_AUTH_TOKEN env var)
-    pub auth_token: Option<String>,
+    /// Index of recorded sessions (std RwLock, updated by the persistence task)
+    pub archive: Arc<std::sync::RwLock<Archive>>,
+
+    /// API token authentication (config file `[auth]`, or the OST_AUTH_TOKEN env var)
+    pub auth: AuthConfig,
 
     /// User-submitted custom metrics (std RwLock for sync access in SSE filter_map)
     pub custom_metrics: Arc<std::sync::RwLock<CustomMetrics>>,
@@ -59,6 +101,60 @@ pub struct AppState {
     /// Broadcast channel for annotation updates (serialized JSON strings)
     pub annotations_tx: broadcast::Sender<String>,
 
+    /// Session metadata of the active adapter (std RwLock, updated from the reader thread)
+    pub session_info: Arc<std::sync::RwLock<Option<SessionInfo>>>,
+
+    /// Broadcast channel for session metadata changes (serialized JSON strings)
+    pub session_info_tx: broadcast::Sender<String>,
+
+    /// Spotter state of the active adapter (std Mutex, updated from the reader thread)
+    pub spotter: Arc<std::sync::Mutex<Spotter>>,
+
+    /// Broadcast channel for spotter call changes (serialized JSON strings)
+    pub spotter_tx: broadcast::Sender<String>,
+
+    /// Session event detection for the active adapter (std Mutex, updated
+    /// from the reader thread)
+    pub events: Arc<std::sync::Mutex<EventDetector>>,
+
+    /// Broadcast channel for session events (lap records, incidents, results)
+    pub events_tx: broadcast::Sender<SessionEvent>,
+
+    /// Broadcast channel for adapters connecting to and losing their sims
+    pub connection_tx: broadcast::Sender<ConnectionEvent>,
+
+    /// Transforms from the config file, applied to live frames after the
+    /// derived channels
+    pub transforms: Arc<Vec<TransformConfig>>,
+
+    /// Frames from other servers' relay sinks, read by the `relay` adapter
+    pub relay: RelayHub,
+
+    /// Samples posted to `/api/ingest`, read by the `ingest` adapter
+    pub ingest: IngestHub,
+
+    /// Another server to follow as the `remote` adapter, from the config file
+    pub remote: Option<RemoteConfig>,
+
+    /// OBD-II and GPS devices of a real car, read by the `hardware` adapter,
+    /// from the config file
+    pub hardware: Option<HardwareConfig>,
+
+    /// Sectors, corners and pit lanes of known tracks, with the config
+    /// file's entries, for filling in live frames
+    pub tracks: Arc<TrackDatabase>,
+
+    /// Delivery stats of the connected telemetry stream clients
+    pub subscribers: Arc<Subscribers>,
+
+    /// Broadcast channel for subscriber stats, sent with adapter status
+    /// (serialized JSON strings)
+    pub subscribers_tx: broadcast::Sender<String>,
+
+    /// Frames serialized for the telemetry stream clients, each shared by
+    /// the clients that asked for the same mask and units
+    pub shared_frames: Arc<SharedFrames>,
+
     /// Whether the server is running in serve mode (--serve flag)
     pub serve_mode: bool,
 
@@ -70,6 +166,286 @@ pub struct AppState {
     pub admin_pass: Option<String>,
 }
 
+/// An adapter shared between the adapter list and its reader task
+pub type SharedAdapter = Arc<std::sync::Mutex<Box<dyn TelemetryAdapter>>>;
+
+/// A registered adapter.
+///
+/// The adapter sits behind its own mutex so the reader task can hold it while
+/// blocked in `read_frame()` without locking the adapter list for the REST API.
+/// Key and name are cached so listing adapters never has to wait on a read.
+/// Cloning is cheap and shares the same adapter, stats and config.
+#[derive(Clone)]
+pub struct AdapterSlot {
+    key: String,
+    name: String,
+    scenarios: &'static [&'static str],
+    capabilities: Option<Capabilities>,
+    unsupported: Option<String>,
+    adapter: SharedAdapter,
+    stats: Arc<std::sync::Mutex<AdapterStats>>,
+    config: Arc<std::sync::RwLock<AdapterConfig>>,
+}
+
+impl AdapterSlot {
+    pub fn new(adapter: Box<dyn TelemetryAdapter>) -> Self {
+        Self {
+            key: adapter.key().to_string(),
+            name: adapter.name().to_string(),
+            scenarios: adapter.scenarios(),
+            capabilities: Capabilities::from_patterns(adapter.capabilities()),
+            unsupported: adapter.platform_support().err().map(|e| e.to_string()),
+            adapter: Arc::new(std::sync::Mutex::new(adapter)),
+            stats: Arc::new(std::sync::Mutex::new(AdapterStats::default())),
+            config: Arc::new(std::sync::RwLock::new(AdapterConfig::default())),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Scenarios a simulating adapter offers (empty for real sims)
+    pub fn scenarios(&self) -> &'static [&'static str] {
+        self.scenarios
+    }
+
+    /// What the adapter's frames can contain, if it says
+    pub fn capabilities(&self) -> Option<&Capabilities> {
+        self.capabilities.as_ref()
+    }
+
+    /// Why the adapter can't run on this platform, `None` if it can
+    pub fn unsupported(&self) -> Option<&str> {
+        self.unsupported.as_deref()
+    }
+
+    /// Lock the adapter. While it is active, the reader task holds this lock for
+    /// the duration of each `read_frame()` call.
+    pub fn lock(&self) -> std::sync::MutexGuard<'_, Box<dyn TelemetryAdapter>> {
+        self.adapter.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Lock this adapter's health statistics
+    pub fn lock_stats(&self) -> std::sync::MutexGuard<'_, AdapterStats> {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Snapshot of this adapter's health statistics
+    pub fn stats(&self) -> AdapterStats {
+        self.lock_stats().clone()
+    }
+
+    /// Current user configuration for this adapter
+    pub fn config(&self) -> AdapterConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replace this adapter's configuration. Takes effect on the reader's next frame.
+    pub fn set_config(&self, config: AdapterConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+}
+
+/// User-configurable per-adapter settings
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct AdapterConfig {
+    /// Maximum rate (Hz) at which frames are published to history and streams.
+    /// `None` publishes at the adapter's native rate.
+    #[serde(default)]
+    pub max_rate_hz: Option<f64>,
+    /// How frames are sanity-checked before publishing (default: annotate)
+    #[serde(default)]
+    pub validation: ValidationMode,
+    /// When an adapter whose reads keep failing is restarted
+    #[serde(default)]
+    pub restart: RestartPolicy,
+}
+
+impl AdapterConfig {
+    /// Minimum time between published frames, if rate-limited
+    pub fn min_frame_interval(&self) -> Option<std::time::Duration> {
+        self.max_rate_hz
+            .filter(|hz| *hz > 0.0)
+            .map(|hz| std::time::Duration::from_secs_f64(1.0 / hz))
+    }
+}
+
+/// Restarting an adapter stuck failing its reads: after `max_errors` in a
+/// row it is stopped, then started again after a wait that doubles with each
+/// restart until a frame is read
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RestartPolicy {
+    /// Read errors in a row before restarting; 0 never restarts
+    pub max_errors: u32,
+    /// Wait before the first restart
+    pub backoff_secs: f64,
+    /// Longest wait between restarts
+    pub max_backoff_secs: f64,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_errors: 50,
+            backoff_secs: 1.0,
+            max_backoff_secs: 60.0,
+        }
+    }
+}
+
+impl RestartPolicy {
+    pub fn check(&self) -> anyhow::Result<()> {
+        if !(self.backoff_secs > 0.0 && self.backoff_secs <= self.max_backoff_secs) {
+            anyhow::bail!("restart backoff_secs must be above 0 and at most max_backoff_secs");
+        }
+        if self.max_backoff_secs > 86_400.0 {
+            anyhow::bail!("restart max_backoff_secs must be at most a day");
+        }
+        Ok(())
+    }
+
+    /// Whether `errors` read errors in a row call for a restart
+    pub fn exceeded(&self, errors: u32) -> bool {
+        self.max_errors > 0 && errors >= self.max_errors
+    }
+
+    /// Wait before restart number `attempt`, counting from 0
+    pub fn backoff(&self, attempt: u32) -> std::time::Duration {
+        let secs = self.backoff_secs * 2f64.powi(attempt.min(32) as i32);
+        std::time::Duration::from_secs_f64(secs.min(self.max_backoff_secs))
+    }
+}
+
+/// Health statistics for an adapter, gathered by its reader task.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct AdapterStats {
+    /// Frames per second over the last measurement window
+    pub fps: f64,
+    /// Total frames read since the server started
+    pub frames_total: u64,
+    /// Wall-clock time of the most recent frame
+    pub last_frame_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Frames skipped by the game, inferred from gaps in `meta.tick`
+    pub dropped_frames: u64,
+    /// Frames with at least one value flagged by validation
+    pub invalid_frames: u64,
+    /// Total values flagged by validation
+    pub invalid_values: u64,
+    /// Most recent validation issue, e.g. "vehicle.speed = -5 is outside 0..=200"
+    pub last_invalid: Option<String>,
+    /// Most recent `read_frame()` or `start()` error
+    pub last_error: Option<String>,
+    pub last_error_kind: Option<ErrorKind>,
+    /// Errors since the server started, by kind
+    pub errors: BTreeMap<ErrorKind, u64>,
+    /// `read_frame()` errors since the last frame
+    pub consecutive_errors: u32,
+    /// Restarts after repeated read errors, since the server started
+    pub restarts: u64,
+    /// When an adapter stopped for repeated read errors may start again
+    pub restart_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Restarts since the last frame, doubling the backoff
+    #[serde(skip)]
+    restart_attempt: u32,
+    /// Where the adapter is in connecting to its sim
+    pub connection: ConnectionState,
+    #[serde(skip)]
+    last_tick: Option<u32>,
+    #[serde(skip)]
+    window_frames: u32,
+    #[serde(skip)]
+    window_start: Option<std::time::Instant>,
+}
+
+impl AdapterStats {
+    /// Record a successfully read frame
+    pub fn record_frame(&mut self, frame: &TelemetryFrame) {
+        self.frames_total += 1;
+        self.consecutive_errors = 0;
+        self.restart_attempt = 0;
+        self.window_frames += 1;
+        self.last_frame_at = Some(chrono::Utc::now());
+        if let Some(tick) = frame.meta.tick {
+            if let Some(last) = self.last_tick {
+                if tick > last.saturating_add(1) {
+                    self.dropped_frames += u64::from(tick - last - 1);
+                }
+            }
+            self.last_tick = Some(tick);
+        }
+    }
+
+    /// Record the issues `TelemetryFrame::validate` found in a frame
+    pub fn record_validation(&mut self, issues: &[ValidationIssue]) {
+        if let Some(last) = issues.last() {
+            self.invalid_frames += 1;
+            self.invalid_values += issues.len() as u64;
+            self.last_invalid = Some(last.to_string());
+        }
+    }
+
+    /// Record an adapter error
+    pub fn record_error(&mut self, error: &AdapterError) {
+        self.last_error = Some(error.to_string());
+        self.last_error_kind = Some(error.kind());
+        *self.errors.entry(error.kind()).or_default() += 1;
+    }
+
+    /// Record a `read_frame()` error, counting it towards a restart
+    pub fn record_read_error(&mut self, error: &AdapterError) {
+        self.record_error(error);
+        self.consecutive_errors += 1;
+    }
+
+    /// Hold off starting the adapter again for the next backoff under
+    /// `policy`, returning the wait
+    pub fn schedule_restart(&mut self, policy: &RestartPolicy) -> std::time::Duration {
+        let backoff = policy.backoff(self.restart_attempt);
+        self.restart_attempt += 1;
+        self.restarts += 1;
+        self.consecutive_errors = 0;
+        self.restart_at = chrono::Duration::from_std(backoff)
+            .ok()
+            .map(|backoff| chrono::Utc::now() + backoff);
+        backoff
+    }
+
+    /// Whether the adapter may be started, i.e. isn't waiting out a backoff
+    pub fn restart_due(&self) -> bool {
+        self.restart_at.is_none_or(|at| at <= chrono::Utc::now())
+    }
+
+    /// Close the current measurement window and update `fps`
+    pub fn roll_window(&mut self) {
+        let now = std::time::Instant::now();
+        if let Some(start) = self.window_start {
+            let secs = now.duration_since(start).as_secs_f64();
+            if secs > 0.0 {
+                self.fps = self.window_frames as f64 / secs;
+            }
+        }
+        self.window_frames = 0;
+        self.window_start = Some(now);
+    }
+
+    /// Reset the rate tracking when the reader starts or stops
+    pub fn reset_window(&mut self) {
+        self.fps = 0.0;
+        self.window_frames = 0;
+        self.window_start = None;
+        self.last_tick = None;
+    }
+}
+
 /// Storage for user-submitted custom metrics.
 ///
 /// Sticky metrics (no tick) are merged into every frame.
@@ -80,6 +456,9 @@ pub struct CustomMetrics {
     pub sticky: HashMap<String, serde_json::Value>,
     /// Tick → Namespace → JSON object, merged into frames with matching tick
     pub by_tick: BTreeMap<u32, HashMap<String, serde_json::Value>>,
+    /// Bumped on every change, so output merged with these metrics (e.g. the
+    /// replay frame cache) can tell it is stale
+    pub generation: u64,
 }
 
 impl CustomMetrics {
@@ -140,13 +519,82 @@ pub struct Annotation {
 }
 
 /// Configuration for an output sink
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct SinkConfig {
     pub id: String,
-    pub host: String,
-    pub port: u16,
+    /// Where the data goes, e.g. `"type": "udp", "host": ..., "port": ...`
+    #[serde(flatten)]
+    pub sink_type: SinkType,
     pub update_rate_hz: Option<f64>,
     pub metric_mask: Option<String>, // Comma-separated metric names
+    /// Wire encoding for datagrams (defaults to JSON)
+    #[serde(default)]
+    pub format: SinkFormat,
+    /// Display units of `json` datagrams (defaults to metric); the other
+    /// formats have units of their own
+    #[serde(default)]
+    pub units: UnitProfile,
+    /// Strip layout and colors for the `leds` format (defaults apply when
+    /// absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leds: Option<crate::leds::LedConfig>,
+}
+
+/// Destination of a sink. Configs without a `type` are UDP sinks, as
+/// before serial sinks existed.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(remote = "Self")]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkType {
+    Udp {
+        host: String,
+        port: u16,
+    },
+    /// A serial device such as `/dev/ttyUSB0` or `COM3`
+    Serial {
+        port: String,
+        baud: u32,
+    },
+    /// Another server's `/api/relay`, see [`crate::relay`]. The token is
+    /// never echoed back.
+    Relay {
+        url: String,
+        #[serde(default, skip_serializing)]
+        token: Option<String>,
+    },
+}
+
+impl serde::Serialize for SinkType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SinkType::serialize(self, serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for SinkType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let mut fields = serde_json::Map::deserialize(deserializer)?;
+        fields.entry("type").or_insert_with(|| "udp".into());
+        SinkType::deserialize(serde_json::Value::Object(fields)).map_err(D::Error::custom)
+    }
+}
+
+/// Datagram encoding used by a sink
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkFormat {
+    #[default]
+    Json,
+    /// MessagePack via `TelemetryFrame::to_bytes_filtered`
+    Msgpack,
+    /// Protocol Buffers, see `ost-proto/proto/telemetry.proto`
+    Protobuf,
+    /// SimHub game data JSON, see [`crate::simhub`]
+    Simhub,
+    /// Fixed 40-byte packets for microcontroller dashboards, see [`crate::dash`]
+    Dash,
+    /// Shift-light LED colors, see [`crate::leds`]
+    Leds,
 }
 
 impl AppState {
@@ -156,6 +604,11 @@ impl AppState {
         let (status_tx, _) = broadcast::channel(16);
         let (sinks_tx, _) = broadcast::channel(16);
         let (annotations_tx, _) = broadcast::channel(16);
+        let (session_info_tx, _) = broadcast::channel(16);
+        let (spotter_tx, _) = broadcast::channel(16);
+        let (events_tx, _) = broadcast::channel(64);
+        let (connection_tx, _) = broadcast::channel(16);
+        let (subscribers_tx, _) = broadcast::channel(16);
 
         let mut disabled = HashSet::new();
         disabled.insert("demo".to_string());
@@ -163,21 +616,44 @@ impl AppState {
         Self {
             adapters: Arc::new(RwLock::new(Vec::new())),
             active_adapter: Arc::new(RwLock::new(None)),
+            reader_cancel: Arc::new(RwLock::new(None)),
             telemetry_tx,
+            frame_seq: Arc::new(AtomicU64::new(0)),
+            latency: Arc::new(Latency::default()),
             sinks: Arc::new(RwLock::new(Vec::new())),
             replay: Arc::new(RwLock::new(None)),
+            replay_store: Arc::new(ReplayStore::default()),
             replay_cancel: Arc::new(RwLock::new(None)),
             disabled_adapters: Arc::new(RwLock::new(disabled)),
+            adapter_priority: Arc::new(RwLock::new(Vec::new())),
+            adapter_registry: Arc::new(AdapterRegistry::default()),
             status_tx,
             sinks_tx,
             history: Arc::new(RwLock::new(HistoryBuffer::new(600))),
             persistence_config: Arc::new(RwLock::new(PersistenceConfig::default())),
-            auth_token: std::env::var("OST_AUTH_TOKEN")
-                .ok()
-                .filter(|s| !s.is_empty()),
+            archive: Arc::new(std::sync::RwLock::new(Archive::open(
+                crate::persistence::telemetry_dir(),
+            ))),
+            auth: AuthConfig::from_env(),
             custom_metrics: Arc::new(std::sync::RwLock::new(CustomMetrics::default())),
             annotations: Arc::new(std::sync::RwLock::new(Vec::new())),
             annotations_tx,
+            session_info: Arc::new(std::sync::RwLock::new(None)),
+            session_info_tx,
+            spotter: Arc::new(std::sync::Mutex::new(Spotter::default())),
+            spotter_tx,
+            events: Arc::new(std::sync::Mutex::new(EventDetector::default())),
+            events_tx,
+            connection_tx,
+            transforms: Arc::new(Vec::new()),
+            relay: RelayHub::default(),
+            ingest: IngestHub::default(),
+            remote: None,
+            hardware: None,
+            tracks: Arc::new(TrackDatabase::builtin()),
+            subscribers: Arc::new(Subscribers::default()),
+            subscribers_tx,
+            shared_frames: Arc::new(SharedFrames::default()),
             serve_mode: false,
             session_store: None,
             admin_user: None,
@@ -188,13 +664,149 @@ impl AppState {
     /// Register an adapter
     pub async fn register_adapter(&self, adapter: Box<dyn TelemetryAdapter>) {
         let mut adapters = self.adapters.write().await;
-        adapters.push(adapter);
+        adapters.push(AdapterSlot::new(adapter));
+    }
+
+    /// Stop `key` if it's the active adapter, leaving the next detection
+    /// cycle to start whichever detected adapter comes first. Returns whether
+    /// it was active.
+    pub async fn deactivate_adapter(&self, key: &str) -> bool {
+        let mut active = self.active_adapter.write().await;
+        if active.as_deref() != Some(key) {
+            return false;
+        }
+        // The reader task stops the adapter once it sees the cancellation
+        if let Some(cancel) = self.reader_cancel.write().await.take() {
+            cancel.cancel();
+        }
+        *active = None;
+        true
+    }
+
+    /// Stamp a frame with the next sequence number and the broadcast time
+    /// before it's broadcast
+    pub fn assign_seq(&self, frame: &mut TelemetryFrame) {
+        frame.meta.seq = Some(self.frame_seq.fetch_add(1, Ordering::Relaxed) + 1);
+        frame.meta.broadcast_at = Some(chrono::Utc::now());
     }
 
     /// Subscribe to telemetry frames
     pub fn subscribe(&self) -> broadcast::Receiver<TelemetryFrame> {
         self.telemetry_tx.subscribe()
     }
+
+    /// Replace the active session's metadata, publishing it on the session
+    /// stream only if it changed. `None` clears it when the adapter stops.
+    pub fn update_session_info(&self, info: Option<SessionInfo>) {
+        let mut current = self.session_info.write().unwrap_or_else(|e| e.into_inner());
+        if *current == info {
+            return;
+        }
+        if let Ok(json) = serde_json::to_string(&info) {
+            let _ = self.session_info_tx.send(json);
+        }
+        *current = info;
+    }
+
+    /// Run the spotter on a live frame, publishing the call if it changed.
+    /// `None` resets it to clear when the adapter stops.
+    pub fn update_spotter(&self, frame: Option<&TelemetryFrame>) {
+        let mut spotter = self.spotter.lock().unwrap_or_else(|e| e.into_inner());
+        let update = match frame {
+            Some(frame) => spotter.update(frame),
+            None => spotter.reset(),
+        };
+        if let Some(json) = update.and_then(|u| serde_json::to_string(&u).ok()) {
+            let _ = self.spotter_tx.send(json);
+        }
+    }
+
+    /// Look for session events in a live frame and publish them. `None`
+    /// forgets the session when the adapter stops.
+    pub fn update_events(&self, frame: Option<&TelemetryFrame>) {
+        let mut detector = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(frame) = frame else {
+            detector.reset();
+            return;
+        };
+        for event in detector.update(frame) {
+            let _ = self.events_tx.send(event);
+        }
+    }
+
+    /// Record an adapter's connection state, publishing a [`ConnectionEvent`]
+    /// if it changed. Returns whether it did.
+    pub fn update_connection(&self, slot: &AdapterSlot, state: ConnectionState) -> bool {
+        let previous = std::mem::replace(&mut slot.lock_stats().connection, state);
+        if previous == state {
+            return false;
+        }
+        let _ = self.connection_tx.send(ConnectionEvent {
+            timestamp: chrono::Utc::now(),
+            adapter: slot.key().to_string(),
+            state,
+            previous,
+        });
+        true
+    }
+
+    /// Whether the `redact` transform is configured, so personal details
+    /// stay out of everything the server sends
+    pub fn redacting(&self) -> bool {
+        self.transforms.contains(&TransformConfig::Redact)
+    }
+}
+
+/// An adapter's connection to its sim changing state, published as the
+/// `connection` event on `/api/stream`
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct ConnectionEvent {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub adapter: String,
+    pub state: ConnectionState,
+    pub previous: ConnectionState,
+}
+
+/// Session metadata that only changes between sessions (or when the sim
+/// republishes its session info), published on `/api/session/stream`.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct SessionInfo {
+    /// Key of the adapter that reported this session
+    pub adapter: String,
+    pub game: String,
+    pub session_type: Option<SessionType>,
+    pub session_laps: Option<u32>,
+    pub track_name: Option<String>,
+    pub track_config: Option<String>,
+    pub track_length: Option<Meters>,
+    pub track_type: Option<String>,
+    pub car_name: Option<String>,
+    pub car_class: Option<String>,
+    pub driver: Option<DriverData>,
+    /// Session info as published by the sim (iRacing's session YAML)
+    pub raw: Option<String>,
+}
+
+impl SessionInfo {
+    /// Pick the static fields out of a frame
+    pub fn from_frame(adapter: &str, frame: &TelemetryFrame, raw: Option<String>) -> Self {
+        let session = frame.session.as_ref();
+        let vehicle = frame.vehicle.as_ref();
+        Self {
+            adapter: adapter.to_string(),
+            game: frame.meta.game.clone(),
+            session_type: session.and_then(|s| s.session_type),
+            session_laps: session.and_then(|s| s.session_laps),
+            track_name: session.and_then(|s| s.track_name.clone()),
+            track_config: session.and_then(|s| s.track_config.clone()),
+            track_length: session.and_then(|s| s.track_length),
+            track_type: session.and_then(|s| s.track_type.clone()),
+            car_name: vehicle.and_then(|v| v.car_name.clone()),
+            car_class: vehicle.and_then(|v| v.car_class.clone()),
+            driver: frame.driver.clone(),
+            raw,
+        }
+    }
 }
 
 impl Default for AppState {
@@ -202,3 +814,123 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_with_tick(tick: u32) -> TelemetryFrame {
+        let mut frame = TelemetryFrame::new("test");
+        frame.meta.tick = Some(tick);
+        frame
+    }
+
+    #[test]
+    fn test_adapter_stats_counts_tick_gaps_as_dropped() {
+        let mut stats = AdapterStats::default();
+        for tick in [10, 11, 12, 15, 16] {
+            stats.record_frame(&frame_with_tick(tick));
+        }
+        assert_eq!(stats.frames_total, 5);
+        assert_eq!(stats.dropped_frames, 2);
+        assert!(stats.last_frame_at.is_some());
+
+        // A tick reset (new session) is not counted as dropped
+        stats.record_frame(&frame_with_tick(1));
+        assert_eq!(stats.dropped_frames, 2);
+
+        // Repeating the highest tick doesn't overflow
+        stats.record_frame(&frame_with_tick(u32::MAX));
+        stats.record_frame(&frame_with_tick(u32::MAX));
+        assert_eq!(stats.dropped_frames, 2 + u64::from(u32::MAX) - 2);
+    }
+
+    #[test]
+    fn test_adapter_stats_records_last_error() {
+        let mut stats = AdapterStats::default();
+        stats.record_error(&anyhow::anyhow!("shared memory unavailable").into());
+        stats.record_error(&AdapterError::Parse("short packet".into()));
+        stats.record_error(&AdapterError::Parse("bad checksum".into()));
+        assert_eq!(stats.last_error.as_deref(), Some("bad checksum"));
+        assert_eq!(stats.last_error_kind, Some(ErrorKind::Parse));
+        assert_eq!(
+            stats.errors,
+            BTreeMap::from([(ErrorKind::Parse, 2), (ErrorKind::Other, 1)])
+        );
+    }
+
+    #[test]
+    fn test_restart_backoff_doubles_until_a_frame() {
+        use std::time::Duration;
+        let policy = RestartPolicy {
+            max_errors: 3,
+            backoff_secs: 0.5,
+            max_backoff_secs: 1.5,
+        };
+        let mut stats = AdapterStats::default();
+        let error = AdapterError::Parse("bad packet".into());
+        for _ in 0..2 {
+            stats.record_read_error(&error);
+        }
+        assert!(!policy.exceeded(stats.consecutive_errors));
+        stats.record_read_error(&error);
+        assert!(policy.exceeded(stats.consecutive_errors));
+
+        let waits: Vec<_> = (0..3).map(|_| stats.schedule_restart(&policy)).collect();
+        let waits: Vec<f64> = waits.iter().map(Duration::as_secs_f64).collect();
+        assert_eq!(waits, [0.5, 1.0, 1.5]);
+        assert_eq!((stats.restarts, stats.consecutive_errors), (3, 0));
+        assert!(!stats.restart_due());
+
+        // A frame resets the backoff
+        stats.record_frame(&frame_with_tick(1));
+        assert_eq!(stats.schedule_restart(&policy), Duration::from_millis(500));
+
+        let never = RestartPolicy {
+            max_errors: 0,
+            ..policy
+        };
+        assert!(!never.exceeded(1000));
+        let inverted = RestartPolicy {
+            backoff_secs: 2.0,
+            ..policy
+        };
+        assert!(inverted.check().is_err());
+    }
+
+    #[test]
+    fn test_session_info_published_only_on_change() {
+        let state = AppState::new();
+        let mut rx = state.session_info_tx.subscribe();
+        let mut frame = frame_with_tick(1);
+        frame.session = serde_json::from_value(serde_json::json!({"track_name": "Spa"})).unwrap();
+
+        state.update_session_info(Some(SessionInfo::from_frame("demo", &frame, None)));
+        let json: serde_json::Value = serde_json::from_str(&rx.try_recv().unwrap()).unwrap();
+        assert_eq!(json["track_name"], "Spa");
+
+        // Dynamic fields don't count as a change
+        frame.meta.tick = Some(2);
+        frame.session.as_mut().unwrap().session_time = Some(ost_core::units::Seconds(12.0));
+        state.update_session_info(Some(SessionInfo::from_frame("demo", &frame, None)));
+        assert!(rx.try_recv().is_err());
+
+        state.update_session_info(None);
+        assert_eq!(rx.try_recv().unwrap(), "null");
+    }
+
+    #[test]
+    fn test_adapter_stats_records_validation_issues() {
+        let mut stats = AdapterStats::default();
+        let mut frame = frame_with_tick(1);
+        frame.vehicle = serde_json::from_value(serde_json::json!({"speed": -3.0})).unwrap();
+
+        stats.record_validation(&frame_with_tick(2).validate(ValidationMode::Annotate));
+        assert_eq!(stats.invalid_frames, 0);
+
+        stats.record_validation(&frame.validate(ValidationMode::Annotate));
+        assert_eq!(stats.invalid_frames, 1);
+        assert_eq!(stats.invalid_values, 1);
+        assert!(stats.last_invalid.unwrap().starts_with("vehicle.speed"));
+    }
+}
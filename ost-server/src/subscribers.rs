@@ -0,0 +1,355 @@
+//! Per-client delivery statistics for the telemetry streams
+//!
+//! Every SSE client reads frames from its own position in the broadcast
+//! channel. A client that falls more than the channel's capacity behind
+//! loses the oldest frames it hadn't read yet; tokio reports how many as a
+//! lag. [`Subscribers`] counts what each connected client was sent and lost
+//! so slow consumers show up in `/metrics`, `/api/clients` and on the status
+//! stream, and the client's [`LagPolicy`] decides whether it keeps going or
+//! is disconnected. Any client can also be disconnected from `/api/clients`.
+
+use crate::metrics::labelled_counter;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+
+/// How often the stats are pushed to the status streams
+pub const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What a stream does when its client falls behind the broadcast channel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LagPolicy {
+    /// Skip the frames that were overwritten and carry on (throttling the
+    /// stream for a while)
+    #[default]
+    DropOldest,
+    /// End the stream, so the client reconnects and can backfill by `seq`
+    Disconnect,
+}
+
+/// What a client asked for when it connected
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ClientRequest {
+    pub metric_mask: Option<String>,
+    /// Frames per second
+    pub rate: Option<f64>,
+    pub format: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// Delivery counters of one connected client
+#[derive(Clone, Debug, Serialize)]
+pub struct SubscriberStats {
+    pub id: u64,
+    /// Route the client is connected to, e.g. `/api/stream`
+    pub endpoint: &'static str,
+    pub policy: LagPolicy,
+    #[serde(flatten)]
+    pub request: ClientRequest,
+    pub connected_at: DateTime<Utc>,
+    pub frames_sent: u64,
+    /// Frames lost to lag
+    pub frames_dropped: u64,
+    /// Number of times the client fell behind
+    pub lag_events: u64,
+    /// `meta.seq` of the last frame sent
+    pub last_seq: Option<u64>,
+    pub last_sent_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+struct Connected {
+    stats: SubscriberStats,
+    disconnect: CancellationToken,
+}
+
+/// Registry of connected stream clients
+#[derive(Debug, Default)]
+pub struct Subscribers {
+    next_id: AtomicU64,
+    connected: Mutex<BTreeMap<u64, Connected>>,
+    /// Frames dropped across all clients, including disconnected ones
+    dropped_total: AtomicU64,
+    /// Clients disconnected by [`LagPolicy::Disconnect`]
+    lag_disconnects: AtomicU64,
+}
+
+impl Subscribers {
+    /// Start tracking a client; it is forgotten when the handle is dropped
+    pub fn register(
+        self: &Arc<Self>,
+        endpoint: &'static str,
+        policy: LagPolicy,
+        request: ClientRequest,
+    ) -> Subscriber {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let disconnect = CancellationToken::new();
+        self.lock().insert(
+            id,
+            Connected {
+                stats: SubscriberStats {
+                    id,
+                    endpoint,
+                    policy,
+                    request,
+                    connected_at: Utc::now(),
+                    frames_sent: 0,
+                    frames_dropped: 0,
+                    lag_events: 0,
+                    last_seq: None,
+                    last_sent_at: None,
+                },
+                disconnect: disconnect.clone(),
+            },
+        );
+        Subscriber {
+            id,
+            policy,
+            disconnect,
+            registry: self.clone(),
+        }
+    }
+
+    /// Stats of the connected clients, oldest connection first
+    pub fn snapshot(&self) -> Vec<SubscriberStats> {
+        self.lock().values().map(|c| c.stats.clone()).collect()
+    }
+
+    /// End client `id`'s stream. False if it isn't connected.
+    pub fn disconnect(&self, id: u64) -> bool {
+        match self.lock().get(&id) {
+            Some(client) => {
+                client.disconnect.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Frames dropped across all clients since the server started
+    pub fn dropped_total(&self) -> u64 {
+        self.dropped_total.load(Ordering::Relaxed)
+    }
+
+    /// Clients disconnected for lagging since the server started
+    pub fn lag_disconnects(&self) -> u64 {
+        self.lag_disconnects.load(Ordering::Relaxed)
+    }
+
+    /// Prometheus text exposition of the counters
+    pub fn prometheus(&self) -> String {
+        let clients = self.snapshot();
+        let client = |s: &SubscriberStats| {
+            vec![
+                ("id", s.id.to_string()),
+                ("endpoint", s.endpoint.to_string()),
+            ]
+        };
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# HELP ost_stream_subscribers Connected telemetry stream clients\n\
+             # TYPE ost_stream_subscribers gauge\n\
+             ost_stream_subscribers {}",
+            clients.len()
+        );
+        let _ = writeln!(
+            out,
+            "# HELP ost_stream_dropped_frames_total Frames dropped by lagging clients\n\
+             # TYPE ost_stream_dropped_frames_total counter\n\
+             ost_stream_dropped_frames_total {}",
+            self.dropped_total()
+        );
+        let _ = writeln!(
+            out,
+            "# HELP ost_stream_lag_disconnects_total Clients disconnected for lagging\n\
+             # TYPE ost_stream_lag_disconnects_total counter\n\
+             ost_stream_lag_disconnects_total {}",
+            self.lag_disconnects()
+        );
+        labelled_counter(
+            &mut out,
+            "ost_subscriber_frames_sent_total",
+            "Frames sent to the client",
+            &clients,
+            client,
+            |s| s.frames_sent,
+        );
+        labelled_counter(
+            &mut out,
+            "ost_subscriber_frames_dropped_total",
+            "Frames the client lost to lag",
+            &clients,
+            client,
+            |s| s.frames_dropped,
+        );
+        labelled_counter(
+            &mut out,
+            "ost_subscriber_lag_events_total",
+            "Times the client fell behind",
+            &clients,
+            client,
+            |s| s.lag_events,
+        );
+        out
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<u64, Connected>> {
+        self.connected.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Send the stats of the connected clients to `tx` every [`STATS_INTERVAL`]
+/// while anyone is listening, so the status streams show live counts
+pub async fn run(subscribers: Arc<Subscribers>, tx: broadcast::Sender<String>) {
+    let mut interval = tokio::time::interval(STATS_INTERVAL);
+    loop {
+        interval.tick().await;
+        if tx.receiver_count() == 0 {
+            continue;
+        }
+        if let Ok(json) = serde_json::to_string(&subscribers.snapshot()) {
+            let _ = tx.send(json);
+        }
+    }
+}
+
+/// A connected client's handle for recording deliveries
+#[derive(Debug)]
+pub struct Subscriber {
+    id: u64,
+    policy: LagPolicy,
+    disconnect: CancellationToken,
+    registry: Arc<Subscribers>,
+}
+
+impl Subscriber {
+    /// Record sending the frame numbered `seq`
+    pub fn record_sent(&self, seq: Option<u64>) {
+        if let Some(c) = self.registry.lock().get_mut(&self.id) {
+            c.stats.frames_sent += 1;
+            c.stats.last_seq = seq.or(c.stats.last_seq);
+            c.stats.last_sent_at = Some(Utc::now());
+        }
+    }
+
+    /// Completes when the client is to be disconnected, by its lag policy
+    /// or from `/api/clients`
+    pub fn disconnected(&self) -> WaitForCancellationFutureOwned {
+        self.disconnect.clone().cancelled_owned()
+    }
+
+    /// Record `dropped` frames lost to lag. Returns true, and disconnects
+    /// the client, when its policy is to disconnect it.
+    pub fn record_lag(&self, dropped: u64) -> bool {
+        if let Some(c) = self.registry.lock().get_mut(&self.id) {
+            c.stats.frames_dropped += dropped;
+            c.stats.lag_events += 1;
+        }
+        self.registry
+            .dropped_total
+            .fetch_add(dropped, Ordering::Relaxed);
+        let disconnect = self.policy == LagPolicy::Disconnect;
+        if disconnect {
+            self.registry
+                .lag_disconnects
+                .fetch_add(1, Ordering::Relaxed);
+            self.disconnect.cancel();
+        }
+        disconnect
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        self.registry.lock().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_per_client_and_forgets_on_drop() {
+        let subscribers = Arc::new(Subscribers::default());
+        let a = subscribers.register(
+            "/api/stream",
+            LagPolicy::DropOldest,
+            ClientRequest::default(),
+        );
+        let b = subscribers.register(
+            "/api/telemetry/stream",
+            LagPolicy::Disconnect,
+            ClientRequest {
+                rate: Some(10.0),
+                ..Default::default()
+            },
+        );
+
+        a.record_sent(Some(7));
+        a.record_sent(None);
+        assert!(!a.record_lag(5));
+        assert!(b.record_lag(3));
+        assert!(b.disconnect.is_cancelled());
+
+        let stats = subscribers.snapshot();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            (
+                stats[0].frames_sent,
+                stats[0].frames_dropped,
+                stats[0].lag_events
+            ),
+            (2, 5, 1)
+        );
+        assert_eq!(stats[0].last_seq, Some(7));
+        assert_eq!(stats[1].frames_dropped, 3);
+        assert_eq!(stats[1].request.rate, Some(10.0));
+
+        let text = subscribers.prometheus();
+        assert!(text.contains("ost_stream_subscribers 2\n"));
+        assert!(text.contains(
+            "ost_subscriber_frames_dropped_total{id=\"1\",endpoint=\"/api/stream\"} 5\n"
+        ));
+
+        assert!(!a.disconnect.is_cancelled());
+        assert!(subscribers.disconnect(1));
+        assert!(a.disconnect.is_cancelled());
+
+        drop(b);
+        assert_eq!(subscribers.snapshot().len(), 1);
+        assert!(!subscribers.disconnect(2));
+        // Totals outlive the clients
+        assert_eq!(subscribers.dropped_total(), 8);
+        assert_eq!(subscribers.lag_disconnects(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_pushes_stats() {
+        let subscribers = Arc::new(Subscribers::default());
+        let (tx, mut rx) = broadcast::channel(4);
+        let _client = subscribers.register(
+            "/api/stream",
+            LagPolicy::DropOldest,
+            ClientRequest::default(),
+        );
+        let task = tokio::spawn(run(subscribers.clone(), tx));
+
+        let json = tokio::time::timeout(STATS_INTERVAL * 3, rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let stats: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(stats[0]["endpoint"], "/api/stream");
+        assert_eq!(stats[0]["frames_sent"], 0);
+        task.abort();
+    }
+}
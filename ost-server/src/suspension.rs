@@ -0,0 +1,283 @@
+//! Suspension analysis
+//!
+//! Shock velocity histograms and spectra for each corner of the car over a
+//! lap or stint of a replay, the usual starting point for damper and spring
+//! setup. The histogram shows how long each damper spends at each speed, with
+//! bump (positive, compressing) and rebound totals; the spectrum shows what
+//! frequencies the corner moves at, ride frequency being the low peak.
+//!
+//! Works on [`FrameColumns`] of the four `shock_velocity` channels, so any
+//! run of frames can be analysed, not just replays.
+
+use ost_core::channels;
+use ost_core::columns::FrameColumns;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+
+/// Corners of the car, as in `wheels.<corner>`
+pub const CORNERS: [&str; 4] = ["front_left", "front_right", "rear_left", "rear_right"];
+
+/// Histogram bins cover this many mm/s either side of zero; faster samples
+/// count in the outermost bins
+pub const HISTOGRAM_RANGE: f32 = 500.0;
+
+/// Histogram bin width when none is asked for, mm/s
+pub const DEFAULT_BIN_WIDTH: f32 = 25.0;
+
+/// Samples per spectrum segment. Segments overlap by half and are averaged;
+/// at 60 Hz each covers about 2 seconds, with bins about 0.47 Hz apart.
+const SEGMENT: usize = 128;
+
+/// Shock velocity histogram and spectrum of one corner
+#[derive(Debug, Clone, Serialize)]
+pub struct CornerAnalysis {
+    /// Frames with a shock velocity
+    pub samples: usize,
+    pub histogram: Histogram,
+    pub spectrum: Spectrum,
+}
+
+/// Share of samples at each shock velocity
+#[derive(Debug, Clone, Serialize)]
+pub struct Histogram {
+    /// mm/s
+    pub bin_width: f32,
+    /// Lower edge of the first bin, mm/s
+    pub min: f32,
+    /// Share of samples in each bin, summing to 1
+    pub fractions: Vec<f32>,
+    /// Share of samples compressing
+    pub bump: f32,
+    /// Share of samples extending
+    pub rebound: f32,
+}
+
+/// Shock velocity amplitude by frequency
+#[derive(Debug, Clone, Serialize)]
+pub struct Spectrum {
+    /// Hz between bins; bin `i` is at `i * resolution_hz`
+    pub resolution_hz: f64,
+    /// mm/s at each frequency from 0 Hz up to half the sample rate; empty
+    /// when there's too little unbroken data
+    pub amplitudes: Vec<f32>,
+    /// Frequency of the largest amplitude above 0 Hz
+    pub peak_hz: Option<f64>,
+}
+
+/// Empty columns of the channels [`analyze`] reads
+pub fn columns() -> FrameColumns {
+    let paths = CORNERS.map(|corner| format!("wheels.{}.shock_velocity", corner));
+    FrameColumns::with_channels(paths.iter().filter_map(|path| channels::find(path)))
+}
+
+/// Analyse each corner with data in `columns`, sampled at `sample_rate_hz`
+pub fn analyze(
+    columns: &FrameColumns,
+    sample_rate_hz: f64,
+    bin_width: f32,
+) -> BTreeMap<&'static str, CornerAnalysis> {
+    CORNERS
+        .iter()
+        .filter_map(|&corner| {
+            let values = columns.column(&format!("wheels.{}.shock_velocity", corner))?;
+            let samples = values.iter().filter(|v| !v.is_nan()).count();
+            (samples > 0).then(|| {
+                let analysis = CornerAnalysis {
+                    samples,
+                    histogram: histogram(values, bin_width),
+                    spectrum: spectrum(values, sample_rate_hz),
+                };
+                (corner, analysis)
+            })
+        })
+        .collect()
+}
+
+fn histogram(values: &[f32], bin_width: f32) -> Histogram {
+    let bins = ((2.0 * HISTOGRAM_RANGE / bin_width).ceil() as usize).max(1);
+    let min = -(bins as f32 * bin_width) / 2.0;
+    let mut counts = vec![0usize; bins];
+    let (mut total, mut bump, mut rebound) = (0usize, 0usize, 0usize);
+    for &v in values.iter().filter(|v| !v.is_nan()) {
+        let bin = ((v - min) / bin_width)
+            .floor()
+            .clamp(0.0, (bins - 1) as f32);
+        counts[bin as usize] += 1;
+        total += 1;
+        if v > 0.0 {
+            bump += 1;
+        } else if v < 0.0 {
+            rebound += 1;
+        }
+    }
+    let share = |n: usize| {
+        if total > 0 {
+            n as f32 / total as f32
+        } else {
+            0.0
+        }
+    };
+    Histogram {
+        bin_width,
+        min,
+        fractions: counts.into_iter().map(share).collect(),
+        bump: share(bump),
+        rebound: share(rebound),
+    }
+}
+
+/// Welch's method: Hann-windowed, half-overlapping segments of unbroken data,
+/// their amplitudes averaged
+fn spectrum(values: &[f32], sample_rate_hz: f64) -> Spectrum {
+    let window: Vec<f64> = (0..SEGMENT)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / SEGMENT as f64).cos())
+        .collect();
+    let gain: f64 = window.iter().sum();
+    let mut sums = vec![0.0f64; SEGMENT / 2 + 1];
+    let mut segments = 0usize;
+
+    for run in values.split(|v| v.is_nan()) {
+        let mut start = 0;
+        while start + SEGMENT <= run.len() {
+            let segment = &run[start..start + SEGMENT];
+            let mean = segment.iter().map(|&v| v as f64).sum::<f64>() / SEGMENT as f64;
+            let mut re: Vec<f64> = segment
+                .iter()
+                .zip(&window)
+                .map(|(&v, w)| (v as f64 - mean) * w)
+                .collect();
+            let mut im = vec![0.0; SEGMENT];
+            fft(&mut re, &mut im);
+            for (k, sum) in sums.iter_mut().enumerate() {
+                // Both halves of the spectrum, except at 0 Hz and Nyquist
+                let scale = if k == 0 || k == SEGMENT / 2 { 1.0 } else { 2.0 };
+                *sum += scale * re[k].hypot(im[k]) / gain;
+            }
+            segments += 1;
+            start += SEGMENT / 2;
+        }
+    }
+
+    let resolution_hz = sample_rate_hz / SEGMENT as f64;
+    if segments == 0 {
+        return Spectrum {
+            resolution_hz,
+            amplitudes: Vec::new(),
+            peak_hz: None,
+        };
+    }
+    let amplitudes: Vec<f32> = sums
+        .iter()
+        .map(|sum| (sum / segments as f64) as f32)
+        .collect();
+    let peak_hz = amplitudes
+        .iter()
+        .enumerate()
+        .skip(1)
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(k, _)| k as f64 * resolution_hz);
+    Spectrum {
+        resolution_hz,
+        amplitudes,
+        peak_hz,
+    }
+}
+
+/// In-place radix-2 FFT; the length must be a power of two
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let step = -2.0 * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (step * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ost_core::model::{TelemetryFrame, WheelData, WheelInfo};
+    use ost_core::units::MillimetersPerSecond;
+
+    fn frame(front_left: f32) -> TelemetryFrame {
+        let mut frame = TelemetryFrame::new("test");
+        frame.wheels = Some(WheelData {
+            front_left: WheelInfo {
+                shock_velocity: Some(MillimetersPerSecond(front_left)),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        frame
+    }
+
+    #[test]
+    fn test_analyze() {
+        // 20 seconds at 60 Hz of a 2 Hz, 100 mm/s oscillation
+        let mut columns = columns();
+        assert_eq!(columns.channels().len(), 4);
+        for i in 0..1200 {
+            let t = i as f64 / 60.0;
+            columns.push(&frame((100.0 * (2.0 * PI * 2.0 * t).sin()) as f32));
+        }
+        let corners = analyze(&columns, 60.0, DEFAULT_BIN_WIDTH);
+        assert_eq!(corners.keys().copied().collect::<Vec<_>>(), ["front_left"]);
+
+        let front_left = &corners["front_left"];
+        assert_eq!(front_left.samples, 1200);
+        let histogram = &front_left.histogram;
+        assert_eq!((histogram.min, histogram.fractions.len()), (-500.0, 40));
+        assert!((histogram.fractions.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+        assert!((histogram.bump - 0.5).abs() < 0.01);
+        assert!((histogram.rebound - 0.5).abs() < 0.01);
+        // Nothing beyond the oscillation's amplitude
+        assert_eq!(histogram.fractions[..15].iter().sum::<f32>(), 0.0);
+
+        let spectrum = &front_left.spectrum;
+        assert_eq!(spectrum.amplitudes.len(), SEGMENT / 2 + 1);
+        let peak = spectrum.peak_hz.unwrap();
+        assert!(
+            (peak - 2.0).abs() <= spectrum.resolution_hz,
+            "peak at {peak} Hz"
+        );
+        let amplitude = spectrum.amplitudes[(peak / spectrum.resolution_hz) as usize];
+        assert!((70.0..=100.0).contains(&amplitude), "amplitude {amplitude}");
+    }
+
+    #[test]
+    fn test_spectrum_needs_unbroken_data() {
+        let mut values = vec![1.0f32; SEGMENT * 2];
+        for v in values.iter_mut().step_by(SEGMENT / 2) {
+            *v = f32::NAN;
+        }
+        let spectrum = spectrum(&values, 60.0);
+        assert!(spectrum.amplitudes.is_empty());
+        assert_eq!(spectrum.peak_hz, None);
+    }
+}
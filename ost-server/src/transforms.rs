@@ -0,0 +1,461 @@
+//! Frame transforms
+//!
+//! Every live frame passes through a chain of [`FrameTransform`]s between the
+//! adapter and the broadcast, so streams, sinks, recordings and history all
+//! see the same result. The chain starts with the derived channels the data
+//! model promises (`relative`, `timing.lap_valid`, estimated brake
+//! temperatures, `predictions`), followed by the transforms in the `[[transforms]]`
+//! sections of the config file, in order:
+//!
+//! ```toml
+//! # Leave sections or game-specific namespaces out of every frame
+//! [[transforms]]
+//! type = "drop"
+//! sections = ["competitors", "iracing"]
+//!
+//! # Smooth jittery channels (see `/api/channels`; `*` matches any wheel)
+//! # with an exponential moving average, `alpha` being the weight of the
+//! # newest sample
+//! [[transforms]]
+//! type = "ema"
+//! channels = ["vehicle.steering_torque"]
+//! alpha = 0.3
+//!
+//! # ...or the median of the last `window` samples, which rejects spikes
+//! [[transforms]]
+//! type = "median"
+//! channels = ["wheels.*.shock_velocity"]
+//! window = 5
+//!
+//! # Strip personal details for public streams (see `redact`)
+//! [[transforms]]
+//! type = "redact"
+//! ```
+//!
+//! Each reader task builds its own chain, so transforms keep state across
+//! frames of one adapter run and start afresh with the next. Display units
+//! aren't a transform: they stay a per-client `units` option, so the frames
+//! themselves are always in data model units.
+
+use anyhow::{bail, Result};
+use ost_core::brakes::BrakeTempEstimator;
+use ost_core::channels::{self, Channel};
+use ost_core::laps::LapValidity;
+use ost_core::model::{MetricMask, TelemetryFrame};
+use ost_core::strategy::StrategyEstimator;
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+/// One step of the frame pipeline
+pub trait FrameTransform: Send {
+    fn apply(&mut self, frame: &mut TelemetryFrame);
+}
+
+/// A `[[transforms]]` config file section
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TransformConfig {
+    /// Remove whole sections (`competitors`), game-specific namespaces
+    /// (`iracing`) or all of them (`extras`)
+    Drop { sections: Vec<String> },
+    /// Exponential moving average of numeric channels
+    Ema { channels: Vec<String>, alpha: f64 },
+    /// Median of the last `window` samples of numeric channels
+    Median {
+        channels: Vec<String>,
+        window: usize,
+    },
+    /// Strip personal details, see [`redact`]
+    Redact,
+}
+
+impl TransformConfig {
+    /// Reject settings that can't work, for config loading
+    pub fn check(&self) -> Result<()> {
+        match self {
+            TransformConfig::Drop { .. } | TransformConfig::Redact => {}
+            TransformConfig::Ema { channels, alpha } => {
+                numeric_channels(channels)?;
+                if !(*alpha > 0.0 && *alpha <= 1.0) {
+                    bail!("ema alpha must be above 0 and at most 1, not {}", alpha);
+                }
+            }
+            TransformConfig::Median { channels, window } => {
+                numeric_channels(channels)?;
+                if *window == 0 {
+                    bail!("median window must be at least 1");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn build(&self) -> Box<dyn FrameTransform> {
+        match self {
+            TransformConfig::Drop { sections } => Box::new(DropSections {
+                sections: sections.iter().map(|s| s.to_lowercase()).collect(),
+            }),
+            TransformConfig::Ema { channels, alpha } => Box::new(Ema {
+                alpha: *alpha,
+                channels: numeric_channels(channels)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|c| (c, None))
+                    .collect(),
+            }),
+            TransformConfig::Median { channels, window } => Box::new(Median {
+                window: *window,
+                channels: numeric_channels(channels)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|c| (c, VecDeque::new()))
+                    .collect(),
+            }),
+            TransformConfig::Redact => Box::new(Redact),
+        }
+    }
+}
+
+/// The numeric channels matching mask-style `patterns`, e.g.
+/// `wheels.*.shock_velocity`
+fn numeric_channels(patterns: &[String]) -> Result<Vec<&'static Channel>> {
+    let mut matched: Vec<&'static Channel> = Vec::new();
+    for pattern in patterns {
+        let mask = MetricMask::parse(pattern);
+        let mut found = false;
+        for channel in channels::all()
+            .iter()
+            .filter(|c| c.is_numeric() && mask.includes(c.path))
+        {
+            found = true;
+            if !matched.contains(&channel) {
+                matched.push(channel);
+            }
+        }
+        if !found {
+            bail!("'{}' matches no numeric channel", pattern);
+        }
+    }
+    Ok(matched)
+}
+
+/// The transforms applied to an adapter's frames
+pub struct TransformChain {
+    transforms: Vec<Box<dyn FrameTransform>>,
+}
+
+impl TransformChain {
+    /// The derived channels followed by the configured transforms
+    pub fn new(configured: &[TransformConfig]) -> Self {
+        let mut transforms: Vec<Box<dyn FrameTransform>> = vec![
+            Box::new(Relative),
+            Box::new(LapValidity::default()),
+            Box::new(BrakeTempEstimator::default()),
+            Box::new(StrategyEstimator::default()),
+        ];
+        transforms.extend(configured.iter().map(TransformConfig::build));
+        Self { transforms }
+    }
+
+    pub fn apply(&mut self, frame: &mut TelemetryFrame) {
+        for transform in &mut self.transforms {
+            transform.apply(frame);
+        }
+    }
+}
+
+/// Fills in `relative` from `competitors` unless the adapter did
+struct Relative;
+
+impl FrameTransform for Relative {
+    fn apply(&mut self, frame: &mut TelemetryFrame) {
+        if frame.relative.is_none() {
+            frame.relative = frame.compute_relative();
+        }
+    }
+}
+
+impl FrameTransform for LapValidity {
+    fn apply(&mut self, frame: &mut TelemetryFrame) {
+        LapValidity::apply(self, frame);
+    }
+}
+
+impl FrameTransform for BrakeTempEstimator {
+    fn apply(&mut self, frame: &mut TelemetryFrame) {
+        BrakeTempEstimator::apply(self, frame);
+    }
+}
+
+impl FrameTransform for StrategyEstimator {
+    fn apply(&mut self, frame: &mut TelemetryFrame) {
+        StrategyEstimator::apply(self, frame);
+    }
+}
+
+struct DropSections {
+    sections: Vec<String>,
+}
+
+impl FrameTransform for DropSections {
+    fn apply(&mut self, frame: &mut TelemetryFrame) {
+        for section in &self.sections {
+            match section.as_str() {
+                "motion" => frame.motion = None,
+                "vehicle" => frame.vehicle = None,
+                "engine" => frame.engine = None,
+                "wheels" => frame.wheels = None,
+                "timing" => frame.timing = None,
+                "session" => frame.session = None,
+                "weather" => frame.weather = None,
+                "pit" => frame.pit = None,
+                "electronics" => frame.electronics = None,
+                "damage" => frame.damage = None,
+                "energy" => frame.energy = None,
+                "competitors" => frame.competitors = None,
+                "driver" => frame.driver = None,
+                "relative" => frame.relative = None,
+                "predictions" => frame.predictions = None,
+                "extras" => frame.extras.clear(),
+                namespace => {
+                    frame.extras.remove(namespace);
+                }
+            }
+        }
+    }
+}
+
+/// Smoothing state is dropped whenever a channel has no value, so it picks
+/// up afresh when the value returns
+struct Ema {
+    alpha: f64,
+    channels: Vec<(&'static Channel, Option<f64>)>,
+}
+
+impl FrameTransform for Ema {
+    fn apply(&mut self, frame: &mut TelemetryFrame) {
+        for (channel, average) in &mut self.channels {
+            let Some(value) = channel.value(frame) else {
+                *average = None;
+                continue;
+            };
+            let smoothed = average.map_or(value, |avg| avg + self.alpha * (value - avg));
+            *average = Some(smoothed);
+            channel.set_value(frame, smoothed);
+        }
+    }
+}
+
+struct Median {
+    window: usize,
+    channels: Vec<(&'static Channel, VecDeque<f64>)>,
+}
+
+impl FrameTransform for Median {
+    fn apply(&mut self, frame: &mut TelemetryFrame) {
+        for (channel, samples) in &mut self.channels {
+            let Some(value) = channel.value(frame) else {
+                samples.clear();
+                continue;
+            };
+            if samples.len() == self.window {
+                samples.pop_front();
+            }
+            samples.push_back(value);
+            let mut sorted: Vec<f64> = samples.iter().copied().collect();
+            sorted.sort_by(f64::total_cmp);
+            let mid = sorted.len() / 2;
+            let median = if sorted.len().is_multiple_of(2) {
+                (sorted[mid - 1] + sorted[mid]) / 2.0
+            } else {
+                sorted[mid]
+            };
+            channel.set_value(frame, median);
+        }
+    }
+}
+
+/// Extras keys holding personal details, in any namespace
+const REDACTED_EXTRAS: &[&str] = &[
+    "iRating",
+    "LicenseLevel",
+    "LicenseSubLevel",
+    "LicenseString",
+];
+
+/// Remove what identifies people from a frame: driver and team names, car
+/// numbers, ratings and licences, for the player and every competitor.
+/// Car indices stay, so standings and gaps still line up.
+pub fn redact(frame: &mut TelemetryFrame) {
+    if let Some(driver) = frame.driver.as_mut() {
+        driver.name = None;
+        driver.car_number = None;
+        driver.team_name = None;
+    }
+    for competitor in frame.competitors.iter_mut().flatten() {
+        competitor.driver_name = None;
+        competitor.car_number = None;
+        competitor.team_name = None;
+    }
+    for namespace in frame.extras.values_mut() {
+        if let Some(vars) = namespace.as_object_mut() {
+            for key in REDACTED_EXTRAS {
+                vars.remove(*key);
+            }
+        }
+    }
+}
+
+struct Redact;
+
+impl FrameTransform for Redact {
+    fn apply(&mut self, frame: &mut TelemetryFrame) {
+        redact(frame);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ost_core::model::{CompetitorData, DriverData, TimingData, VehicleData};
+    use ost_core::units::{MetersPerSecond, Rpm};
+
+    #[derive(Deserialize)]
+    struct Config {
+        transforms: Vec<TransformConfig>,
+    }
+
+    #[test]
+    fn test_chain() {
+        let config: Config = toml::from_str(
+            "[[transforms]]\ntype = \"drop\"\nsections = [\"Competitors\", \"iracing\"]",
+        )
+        .unwrap();
+        let mut chain = TransformChain::new(&config.transforms);
+
+        let mut frame = TelemetryFrame::new("iRacing");
+        frame.timing = Some(TimingData {
+            lap_number: Some(3),
+            ..Default::default()
+        });
+        frame.driver = Some(DriverData {
+            car_index: Some(0),
+            ..Default::default()
+        });
+        frame.competitors = Some(vec![CompetitorData::new(0), CompetitorData::new(1)]);
+        frame
+            .extras
+            .insert("iracing".into(), serde_json::json!({"SessionTick": 1}));
+        frame
+            .extras
+            .insert("custom".into(), serde_json::json!({"a": 1}));
+        chain.apply(&mut frame);
+
+        // Derived channels are filled in before the configured transforms
+        // run, so `relative` survives dropping the competitors it came from
+        assert!(frame.relative.is_some());
+        assert_eq!(frame.timing.unwrap().lap_valid, Some(false));
+        assert!(frame.competitors.is_none());
+        assert!(!frame.extras.contains_key("iracing"));
+        assert!(frame.extras.contains_key("custom"));
+
+        assert!(toml::from_str::<Config>("[[transforms]]\ntype = \"nope\"").is_err());
+        assert!(toml::from_str::<Config>("[[transforms]]\ntype = \"ema\"\nchannels = []").is_err());
+        assert!(toml::from_str::<Config>(
+            "[[transforms]]\ntype = \"drop\"\nsections = []\nfields = []"
+        )
+        .is_err());
+    }
+
+    fn speeds(config: &str, speeds: &[Option<f32>]) -> Vec<Option<f32>> {
+        let config: Config = toml::from_str(config).unwrap();
+        for transform in &config.transforms {
+            transform.check().unwrap();
+        }
+        let mut chain = TransformChain::new(&config.transforms);
+        speeds
+            .iter()
+            .map(|speed| {
+                let mut frame = TelemetryFrame::new("test");
+                frame.vehicle = Some(VehicleData {
+                    speed: speed.map(MetersPerSecond),
+                    rpm: Some(Rpm(5000.0)),
+                    ..Default::default()
+                });
+                chain.apply(&mut frame);
+                assert_eq!(frame.vehicle.as_ref().unwrap().rpm, Some(Rpm(5000.0)));
+                frame.vehicle.unwrap().speed.map(|s| s.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_smoothing() {
+        let ema = "[[transforms]]\ntype = \"ema\"\nchannels = [\"vehicle.speed\"]\nalpha = 0.5";
+        assert_eq!(
+            speeds(ema, &[Some(10.0), Some(20.0), Some(20.0), None, Some(40.0)]),
+            [Some(10.0), Some(15.0), Some(17.5), None, Some(40.0)]
+        );
+
+        // A single spike doesn't get through a median filter
+        let median =
+            "[[transforms]]\ntype = \"median\"\nchannels = [\"vehicle.speed\"]\nwindow = 3";
+        assert_eq!(
+            speeds(median, &[Some(10.0), Some(12.0), Some(90.0), Some(11.0)]),
+            [Some(10.0), Some(11.0), Some(12.0), Some(12.0)]
+        );
+
+        let check = |config: &str| {
+            toml::from_str::<Config>(config).unwrap().transforms[0]
+                .check()
+                .map_err(|e| e.to_string())
+        };
+        assert!(check(
+            "[[transforms]]\ntype = \"ema\"\nchannels = [\"wheels.*.shock_velocity\"]\nalpha = 0.2"
+        )
+        .is_ok());
+        assert!(
+            check("[[transforms]]\ntype = \"ema\"\nchannels = [\"vehicle.speed\"]\nalpha = 0")
+                .is_err()
+        );
+        assert!(check(
+            "[[transforms]]\ntype = \"median\"\nchannels = [\"vehicle.speed\"]\nwindow = 0"
+        )
+        .is_err());
+        // Not numeric, or not a channel at all
+        assert!(check(
+            "[[transforms]]\ntype = \"ema\"\nchannels = [\"vehicle.car_name\"]\nalpha = 0.2"
+        )
+        .is_err());
+        assert!(check(
+            "[[transforms]]\ntype = \"ema\"\nchannels = [\"vehicle.sped\"]\nalpha = 0.2"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_redact() {
+        let config: Config = toml::from_str("[[transforms]]\ntype = \"redact\"").unwrap();
+        let mut frame: TelemetryFrame = serde_json::from_value(serde_json::json!({
+            "meta": {"timestamp": "2026-01-01T00:00:00Z", "game": "iRacing"},
+            "driver": {"name": "Alex Example", "car_index": 0, "car_number": "7"},
+            "competitors": [
+                {"car_index": 0, "driver_name": "Alex Example", "team_name": "Team", "position": 2},
+                {"car_index": 1, "driver_name": "Sam Sample", "car_number": "12", "position": 1},
+            ],
+            "iracing": {"iRating": 2500, "LicenseString": "A 4.99", "SessionID": 9, "Speed": 30.0},
+        }))
+        .unwrap();
+        TransformChain::new(&config.transforms).apply(&mut frame);
+
+        let json = serde_json::to_string(&frame).unwrap();
+        for personal in ["Alex", "Sam", "Team", "\"12\"", "iRating", "A 4.99"] {
+            assert!(!json.contains(personal), "{} in {}", personal, json);
+        }
+        let competitors = frame.competitors.as_ref().unwrap();
+        assert_eq!(competitors[1].position, Some(1));
+        assert_eq!(frame.driver.unwrap().car_index, Some(0));
+        assert_eq!(frame.extras["iracing"]["SessionID"], 9);
+        assert!(frame.relative.is_some());
+    }
+}
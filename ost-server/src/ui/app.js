@@ -163,12 +163,24 @@ _updateRemoteState();
 // Single unified SSE connection (avoids exhausting HTTP/1.1 connection slots)
 let sseSource = null;
 let _fullFrame = null; // Accumulated frame state for delta merging
+// Apply a JSON merge patch, copying only the branches it touches so the
+// previous frame object (already pushed to the store) is left untouched.
+function applyMergePatch(target, patch) {
+    if (patch === null || typeof patch !== 'object' || Array.isArray(patch)) return patch;
+    const out = (target && typeof target === 'object' && !Array.isArray(target)) ? { ...target } : {};
+    for (const [key, value] of Object.entries(patch)) {
+        if (value === null) delete out[key];
+        else out[key] = applyMergePatch(out[key], value);
+    }
+    return out;
+}
+
 function connectSSE() {
     if (sseSource) sseSource.close();
     sseConnected = false;
     _fullFrame = null;
     _updateRemoteState();
-    const es = new EventSource(apiBase() + '/api/stream');
+    const es = new EventSource(apiBase() + '/api/stream?mode=delta');
     sseSource = es;
     es.onopen = () => {
         sseConnected = true; sseEverConnected = true; updateStatus();
@@ -185,7 +197,11 @@ function connectSSE() {
         try {
             const data = JSON.parse(e.data);
             let frame;
-            if (data._delta && _fullFrame) {
+            if (data._delta === 'merge-patch' && _fullFrame) {
+                // Field-level JSON merge patch (RFC 7386)
+                delete data._delta;
+                frame = applyMergePatch(_fullFrame, data);
+            } else if (data._delta && _fullFrame) {
                 // Merge delta into accumulated state
                 frame = {};
                 for (const key of Object.keys(_fullFrame)) {
@@ -211,7 +227,15 @@ function connectSSE() {
         catch (err) { console.error('Parse error:', err); }
     });
     es.addEventListener('status', (e) => {
-        try { store.adapters = JSON.parse(e.data); updateHeaderAdapters(); updateStatus(); }
+        try {
+            const data = JSON.parse(e.data);
+            // Replay notices share the stream with the adapter list
+            if (!Array.isArray(data)) {
+                if (data.replay_index) { store.replayIndex = data.replay_index; requestRedraw(); }
+                return;
+            }
+            store.adapters = data; updateHeaderAdapters(); updateStatus();
+        }
         catch (err) { console.error('Parse error:', err); }
     });
     es.addEventListener('sinks', (e) => {
@@ -306,7 +330,14 @@ function updateHeaderAdapters() {
     sourcesMenu.innerHTML = store.adapters.map(a => {
         const dotClass = a.active ? 'dot-active' : a.detected ? 'dot-detected' : 'dot-inactive';
         const checked = a.enabled ? 'checked' : '';
-        return `<label class="sources-item" data-key="${a.key}">
+        const stats = a.stats || {};
+        const health = [
+            a.unsupported ? `Unsupported: ${a.unsupported}` : null,
+            a.active ? `${(stats.fps || 0).toFixed(0)} fps` : null,
+            stats.dropped_frames ? `${stats.dropped_frames} dropped` : null,
+            stats.last_error ? `Last error: ${stats.last_error}` : null,
+        ].filter(Boolean).join(' · ');
+        return `<label class="sources-item" data-key="${a.key}" title="${health.replace(/"/g, '&quot;')}">
             <span class="status-dot ${dotClass}"></span>
             <span class="sources-name">${a.name}</span>
             <input type="checkbox" class="sources-toggle" ${checked}>
@@ -414,8 +445,8 @@ document.getElementById('menu-browse-replays').addEventListener('click', async (
             const info = document.createElement('div');
             info.style.flex = '1';
             info.style.minWidth = '0';
-            // Parse filename for display: YYYY-MM-DD_HH-MM-SS_track_car.ost.ndjson.zstd
-            const parts = f.name.replace('.ost.ndjson.zstd', '').split('_');
+            // Parse filename for display: YYYY-MM-DD_HH-MM-SS_track_car.ost.ndjson[.zstd|.gz]
+            const parts = f.name.replace(/\.ost\.ndjson(\.zstd|\.gz)?$/, '').split('_');
             const date = parts.length >= 2 ? parts[0] : '';
             const time = parts.length >= 2 ? parts[1].replace(/-/g, ':') : '';
             const rest = parts.slice(2).join(' ');
@@ -1190,7 +1221,7 @@ function openSettingsModal() {
                 <div class="api-endpoint"><code>POST /api/adapters/:name/toggle</code> — Enable/disable an adapter</div>
                 <div class="api-endpoint"><code>GET /api/metrics</code> — Latest telemetry frame</div>
                 <div class="api-endpoint"><code>GET /api/sinks</code> — List output sinks</div>
-                <div class="api-endpoint"><code>POST /api/sinks</code> — Create UDP sink</div>
+                <div class="api-endpoint"><code>POST /api/sinks</code> — Create UDP or serial sink</div>
                 <div class="api-endpoint"><code>DELETE /api/sinks/:id</code> — Remove a sink</div>
             </div>
             <div class="api-section">
@@ -1306,7 +1337,7 @@ function openSettingsModal() {
         } else {
             sinksListEl.innerHTML = store.sinks.map(s => {
                 const rate = s.update_rate_hz || 60;
-                return `<div class="sink-item"><div><strong>UDP</strong> ${s.host}:${s.port} <span style="color:var(--text-muted);font-size:0.6rem">@ ${rate} Hz</span>${s.metric_mask ? `<br><span style="color:var(--text-muted);font-size:0.6rem">Metrics: ${s.metric_mask}</span>` : ''}</div><button class="btn-delete" data-id="${s.id}">Delete</button></div>`;
+                return `<div class="sink-item"><div>${s.type === 'serial' ? `<strong>Serial</strong> ${s.port} @ ${s.baud} baud` : `<strong>UDP</strong> ${s.host}:${s.port}`} <span style="color:var(--text-muted);font-size:0.6rem">@ ${rate} Hz</span>${s.metric_mask ? `<br><span style="color:var(--text-muted);font-size:0.6rem">Metrics: ${s.metric_mask}</span>` : ''}</div><button class="btn-delete" data-id="${s.id}">Delete</button></div>`;
             }).join('');
             sinksListEl.querySelectorAll('.btn-delete').forEach(btn => {
                 btn.addEventListener('click', async () => {
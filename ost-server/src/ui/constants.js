@@ -107,7 +107,7 @@ const GRAPH_PRESETS = [
     { name: 'Tire Temps', metrics: ['wheels.*.surface_temp_*'] },
     { name: 'Tire Wear', metrics: ['wheels.*.tyre_wear'] },
     { name: 'Tire Pressure', metrics: ['wheels.*.tyre_pressure', 'wheels.*.tyre_cold_pressure'] },
-    { name: 'Brake Temps', metrics: ['wheels.*.brake_temp'] },
+    { name: 'Brakes', metrics: ['wheels.*.brake_temp', 'wheels.*.brake_pad_wear'] },
     { name: 'Engine', metrics: ['vehicle.rpm', 'wheels.*.wheel_speed'] },
 ];
 
@@ -187,6 +187,7 @@ const METRIC_UNIT_MAP = {
     '*.wind_direction':        { unit: '\u00B0', norm: 'autoscale' },
     // Percentages
     '*.tyre_wear':             { unit: '%', norm: 'pct' },
+    '*.brake_pad_wear':        { unit: '%', norm: 'pct' },
     '*.oil_level':             { unit: '%', norm: 'pct' },
     '*.fuel_level_pct':        { unit: '%', norm: 'pct' },
     '*.lap_distance_pct':      { unit: '%', norm: 'pct' },
@@ -378,7 +379,7 @@ const LABEL_ABBREVS = {
     steering_torque: 'Steer Torque', steering_torque_pct: 'Steer %',
     manifold_pressure: 'Manifold', oil_pressure: 'Oil Press',
     fuel_pressure: 'Fuel Press', water_temp: 'Water Temp',
-    oil_temp: 'Oil Temp', brake_temp: 'Brake Temp',
+    oil_temp: 'Oil Temp', brake_temp: 'Brake Temp', brake_pad_wear: 'Pad Wear',
     current_lap_time: 'Cur Lap', last_lap_time: 'Last Lap',
     best_lap_time: 'Best Lap', delta_best: '\u0394 Best',
     delta_session_best: '\u0394 Sess Best', delta_optimal: '\u0394 Optimal',
@@ -433,7 +434,7 @@ function _matchFilter(text, filter) {
 
 const TOP_LEVEL_SECTIONS = new Set([
     'vehicle', 'motion', 'engine', 'wheels', 'timing', 'session',
-    'weather', 'pit', 'electronics', 'damage',
+    'weather', 'pit', 'electronics', 'damage', 'energy', 'predictions',
     // Game-specific namespaces are also top-level (e.g. "iracing", "demo")
     'iracing', 'demo',
 ]);
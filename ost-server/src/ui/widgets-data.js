@@ -212,7 +212,7 @@ class OutputSinksWidget extends Widget {
             } else {
                 this.listEl.innerHTML = store.sinks.map(s => {
                     const rate = s.update_rate_hz || 60;
-                    return `<div class="sink-item"><div><strong>UDP</strong> ${s.host}:${s.port} <span style="color:var(--text-muted);font-size:0.6rem">@ ${rate} Hz</span>${s.metric_mask ? `<br><span style="color:var(--text-muted);font-size:0.6rem">Metrics: ${s.metric_mask}</span>` : ''}</div><button class="btn-delete" data-id="${s.id}">Delete</button></div>`;
+                    return `<div class="sink-item"><div>${s.type === 'serial' ? `<strong>Serial</strong> ${s.port} @ ${s.baud} baud` : `<strong>UDP</strong> ${s.host}:${s.port}`} <span style="color:var(--text-muted);font-size:0.6rem">@ ${rate} Hz</span>${s.metric_mask ? `<br><span style="color:var(--text-muted);font-size:0.6rem">Metrics: ${s.metric_mask}</span>` : ''}</div><button class="btn-delete" data-id="${s.id}">Delete</button></div>`;
                 }).join('');
                 this.listEl.querySelectorAll('.btn-delete').forEach(btn => {
                     btn.addEventListener('click', async () => {
@@ -246,7 +246,7 @@ class ApiWidget extends Widget {
                     <div class="api-endpoint"><code>POST /api/adapters/:name/toggle</code> — Enable/disable an adapter</div>
                     <div class="api-endpoint"><code>GET /api/metrics</code> — Latest telemetry frame (supports <code>metric_mask</code>)</div>
                     <div class="api-endpoint"><code>GET /api/sinks</code> — List output sinks</div>
-                    <div class="api-endpoint"><code>POST /api/sinks</code> — Create UDP sink</div>
+                    <div class="api-endpoint"><code>POST /api/sinks</code> — Create UDP or serial sink</div>
                     <div class="api-endpoint"><code>DELETE /api/sinks/:id</code> — Remove a sink</div>
                 </div>
                 <div class="api-section">
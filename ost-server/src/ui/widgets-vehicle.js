@@ -63,7 +63,7 @@ class VehicleWidget extends Widget {
             this.els.speed.textContent = '---';
         }
         this.els.rpm.textContent = v?.rpm != null ? Math.round(v.rpm) : '---';
-        this.els.gear.textContent = v?.gear != null ? (v.gear === -1 ? 'R' : v.gear === 0 ? 'N' : v.gear) : 'N';
+        this.els.gear.textContent = v?.gear != null ? (v.gear === -1 ? 'R' : v.gear === 0 ? 'N' : v.gear) : '-';
 
         const thr = (v?.throttle ?? 0) * 100;
         this.els.thrBar.style.height = thr + '%';
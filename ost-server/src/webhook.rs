@@ -0,0 +1,125 @@
+//! HTTP(S) client for webhooks
+//!
+//! Posts a JSON body and reads back the response status, which is all
+//! webhook notifications need. [`client`] is shared with remote server
+//! connections. HTTPS servers
+//! are verified against the platform's root certificates: the system store
+//! on Windows and macOS, and the CA bundle (or `SSL_CERT_FILE`) elsewhere.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Give up on a request after this long
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What came back
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    /// From a `Retry-After` header, when rate limited
+    pub retry_after: Option<Duration>,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// POST `body` as JSON to an `http://` or `https://` URL
+pub async fn post_json(url: &str, body: &serde_json::Value) -> Result<Response> {
+    let url = reqwest::Url::parse(url).context("Invalid webhook URL")?;
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!("Webhook URL must be http:// or https://");
+    }
+    let response = client()?
+        .post(url)
+        .json(body)
+        .timeout(TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                anyhow!("Timed out posting to webhook")
+            } else {
+                anyhow!("Failed to post to webhook: {:#}", anyhow::Error::from(e))
+            }
+        })?;
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .map(Duration::from_secs_f64);
+    Ok(Response {
+        status: response.status().as_u16(),
+        retry_after,
+    })
+}
+
+/// The HTTP client for outbound requests, built once
+pub(crate) fn client() -> Result<&'static reqwest::Client> {
+    static CLIENT: OnceLock<Result<reqwest::Client, String>> = OnceLock::new();
+    CLIENT
+        .get_or_init(|| {
+            reqwest::Client::builder()
+                .use_rustls_tls()
+                .user_agent(concat!("OpenSimTelemetry/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .map_err(|e| format!("{:#}", anyhow::Error::from(e)))
+        })
+        .as_ref()
+        .map_err(|e| anyhow!("Failed to set up the HTTP client: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_post_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/hooks/abc?wait=true",
+            listener.local_addr().unwrap()
+        );
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"}") {
+                let mut chunk = [0u8; 1024];
+                let n = socket.read(&mut chunk).await.unwrap();
+                request.extend_from_slice(&chunk[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 1.5\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let response = post_json(&url, &serde_json::json!({"content": "hi"}))
+            .await
+            .unwrap();
+        assert_eq!(
+            response,
+            Response {
+                status: 429,
+                retry_after: Some(Duration::from_millis(1500)),
+            }
+        );
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hooks/abc?wait=true HTTP/1.1\r\n"));
+        assert!(request
+            .to_ascii_lowercase()
+            .contains("content-type: application/json\r\n"));
+        assert!(request.ends_with("\r\n\r\n{\"content\":\"hi\"}"));
+
+        assert!(post_json("ftp://example.com/", &serde_json::json!({}))
+            .await
+            .is_err());
+    }
+}
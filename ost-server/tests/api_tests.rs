@@ -9,7 +9,7 @@ use ost_core::TelemetryAdapter;
 use ost_server::{
     api::create_router,
     sessions::SessionStore,
-    state::{AppState, SinkConfig},
+    state::{AppState, SinkConfig, SinkType},
 };
 use std::path::Path;
 use std::sync::Arc;
@@ -156,6 +156,408 @@ async fn test_get_adapters_with_demo_adapter_registered() {
     );
 }
 
+#[tokio::test]
+async fn test_get_adapters_includes_stats() {
+    let (app, state) = app_with_state();
+    state
+        .register_adapter(Box::new(ost_adapters::DemoAdapter::new()))
+        .await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/adapters")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let body = body_string(response.into_body()).await;
+    let parsed: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let stats = &parsed[0]["stats"];
+    assert_eq!(stats["fps"], 0.0);
+    assert_eq!(stats["frames_total"], 0);
+    assert_eq!(stats["dropped_frames"], 0);
+    assert!(stats["last_frame_at"].is_null());
+    assert!(stats["last_error"].is_null());
+    assert!(stats["last_error_kind"].is_null());
+    assert_eq!(stats["errors"], serde_json::json!({}));
+}
+
+#[tokio::test]
+async fn test_adapter_config_roundtrip() {
+    let (app, state) = app_with_state();
+    state
+        .register_adapter(Box::new(ost_adapters::DemoAdapter::new()))
+        .await;
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/adapters/demo/config")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    r#"{"max_rate_hz": 20, "restart": {"max_errors": 5}}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/adapters/demo/config")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = body_string(response.into_body()).await;
+    let parsed: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(parsed["max_rate_hz"], 20.0);
+    // Unset restart settings keep their defaults
+    assert_eq!(
+        parsed["restart"],
+        serde_json::json!({"max_errors": 5, "backoff_secs": 1.0, "max_backoff_secs": 60.0})
+    );
+
+    let adapters = state.adapters.read().await;
+    assert_eq!(
+        adapters[0].config().min_frame_interval(),
+        Some(std::time::Duration::from_millis(50))
+    );
+}
+
+#[tokio::test]
+async fn test_control_commands_unsupported_adapter() {
+    let (app, state) = app_with_state();
+    state
+        .register_adapter(Box::new(ost_adapters::DemoAdapter::new()))
+        .await;
+
+    let post = |uri: &str, body: &'static str| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            "/api/adapters/demo/pit",
+            r#"{"command": "fuel", "liters": 40}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 501);
+    let body = body_string(response.into_body()).await;
+    assert!(body.contains("pit commands"), "{}", body);
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            "/api/adapters/demo/pit",
+            r#"{"command": "tire", "tire": "middle"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 422);
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            "/api/adapters/demo/camera",
+            r#"{"focus": {"car_number": 12}}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 501);
+
+    let response = app
+        .clone()
+        .oneshot(post("/api/adapters/demo/replay/speed", r#"{"speed": 2}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 501);
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            "/api/adapters/demo/chat",
+            r#"{"command": "macro", "number": 3}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 501);
+
+    let response = app
+        .oneshot(post("/api/adapters/missing/pit", r#"{"command": "clear"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_activate_adapter() {
+    let (app, state) = app_with_state();
+    state
+        .register_adapter(Box::new(ost_adapters::DemoAdapter::new()))
+        .await;
+    for key in ["first", "second"] {
+        state
+            .register_adapter(Box::new(ost_adapters::ScriptedAdapter::new(key)))
+            .await;
+    }
+    *state.adapter_priority.write().await = vec!["first".into()];
+    *state.active_adapter.write().await = Some("first".into());
+    let activate = |key: &str| {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/api/adapters/{}/activate", key))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // Goes first, and the active adapter makes way for it
+    let response = app.clone().oneshot(activate("second")).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body = body_string(response.into_body()).await;
+    let info: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(info["key"], "second");
+    assert_eq!(info["detected"], true);
+    assert_eq!(*state.adapter_priority.read().await, ["second", "first"]);
+    assert!(state.active_adapter.read().await.is_none());
+
+    // The demo adapter starts disabled
+    let response = app.clone().oneshot(activate("demo")).await.unwrap();
+    assert_eq!(response.status(), 409);
+    let response = app.oneshot(activate("missing")).await.unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[cfg(all(feature = "kartkraft", feature = "demo"))]
+#[tokio::test]
+async fn test_register_and_unregister_adapter() {
+    let (app, state) = app_with_state();
+    state
+        .register_adapter(Box::new(ost_adapters::DemoAdapter::new()))
+        .await;
+    let register = |key: &str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/adapters/register")
+            .header("content-type", "application/json")
+            .body(Body::from(format!(r#"{{"key": "{}"}}"#, key)))
+            .unwrap()
+    };
+    let unregister = |key: &str| {
+        Request::builder()
+            .method("DELETE")
+            .uri(format!("/api/adapters/{}", key))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let keys = || async {
+        state
+            .adapters
+            .read()
+            .await
+            .iter()
+            .map(|slot| slot.key().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // Goes before the demo adapter, as it would at startup
+    let response = app.clone().oneshot(register("kartkraft")).await.unwrap();
+    assert_eq!(response.status(), 201);
+    let body = body_string(response.into_body()).await;
+    let info: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(info["key"], "kartkraft");
+    assert_eq!(keys().await, ["kartkraft", "demo"]);
+    let response = app.clone().oneshot(register("kartkraft")).await.unwrap();
+    assert_eq!(response.status(), 409);
+    // Not built in, or not configured
+    for key in ["missing", "remote"] {
+        let response = app.clone().oneshot(register(key)).await.unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    let response = app.clone().oneshot(unregister("demo")).await.unwrap();
+    assert_eq!(response.status(), 204);
+    assert_eq!(keys().await, ["kartkraft"]);
+    assert!(!state.adapter_registry.is_registered("demo"));
+    let response = app.clone().oneshot(unregister("demo")).await.unwrap();
+    assert_eq!(response.status(), 404);
+
+    let response = app.oneshot(register("demo")).await.unwrap();
+    assert_eq!(response.status(), 201);
+    assert_eq!(keys().await, ["kartkraft", "demo"]);
+    assert!(state.adapter_registry.is_registered("demo"));
+}
+
+#[tokio::test]
+async fn test_adapter_features() {
+    let response = app()
+        .oneshot(
+            Request::get("/api/adapters/features")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body = body_string(response.into_body()).await;
+    let features: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let iracing = &features[0];
+    assert_eq!(iracing["name"], "iracing");
+    assert_eq!(iracing["enabled"], cfg!(feature = "iracing"));
+    assert_eq!(
+        iracing["adapters"],
+        serde_json::json!(["iracing", "ibt-tail"])
+    );
+}
+
+#[tokio::test]
+async fn test_adapter_scenario() {
+    let (app, state) = app_with_state();
+    state
+        .register_adapter(Box::new(ost_adapters::DemoAdapter::new()))
+        .await;
+    state
+        .register_adapter(Box::new(ost_adapters::ScriptedAdapter::new("scripted")))
+        .await;
+    state.adapters.read().await[0].lock().start().unwrap();
+
+    let post = |uri: &str, body: &'static str| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/api/adapters").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = body_string(response.into_body()).await;
+    let adapters: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert!(adapters[0]["scenarios"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("rain_onset")));
+    assert_eq!(adapters[1]["scenarios"], serde_json::json!([]));
+    // Capabilities resolve to sections and channels, null when undeclared
+    let capabilities = &adapters[0]["capabilities"];
+    assert!(capabilities["sections"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("wheels")));
+    assert!(capabilities["channels"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("vehicle.speed")));
+    assert_eq!(capabilities["extras"], serde_json::json!(["demo"]));
+    assert!(adapters[1]["capabilities"].is_null());
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            "/api/adapters/demo/scenario",
+            r#"{"scenario": "qualifying"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+    let frame = state.adapters.read().await[0]
+        .lock()
+        .read_frame()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        frame.session.unwrap().session_type,
+        Some(ost_core::model::SessionType::Qualifying)
+    );
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            "/api/adapters/demo/scenario",
+            r#"{"scenario": "sharknado"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = app
+        .oneshot(post(
+            "/api/adapters/scripted/scenario",
+            r#"{"scenario": "race"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 501);
+}
+
+#[tokio::test]
+async fn test_adapter_config_rejects_invalid_rate() {
+    let (app, state) = app_with_state();
+    state
+        .register_adapter(Box::new(ost_adapters::DemoAdapter::new()))
+        .await;
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/adapters/demo/config")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"max_rate_hz": 0}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/adapters/demo/config")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"restart": {"backoff_secs": 0}}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn test_adapter_config_unknown_adapter_returns_404() {
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri("/api/adapters/nope/config")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
 // ==================== GET /api/sinks ====================
 
 #[tokio::test]
@@ -254,6 +656,52 @@ async fn test_create_sink_generates_id_when_empty() {
     );
 }
 
+#[tokio::test]
+async fn test_create_sink_rejects_invalid_config() {
+    let (app, state) = app_with_state();
+    let post = |sink: serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/sinks")
+            .header("content-type", "application/json")
+            .body(Body::from(sink.to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(post(serde_json::json!({
+            "id": "strip",
+            "host": "127.0.0.1",
+            "port": 9200,
+            "update_rate_hz": 30.0,
+            "metric_mask": null,
+            "format": "leds",
+            "leds": {"count": 0}
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    assert!(body_string(response.into_body())
+        .await
+        .contains("LED count"));
+
+    let response = app
+        .oneshot(post(serde_json::json!({
+            "id": "dash",
+            "type": "serial",
+            "port": "/dev/ost-no-such-port",
+            "baud": 115200,
+            "update_rate_hz": 30.0,
+            "metric_mask": null
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    assert!(state.sinks.read().await.is_empty());
+}
+
 // ==================== POST then GET /api/sinks ====================
 
 #[tokio::test]
@@ -265,10 +713,15 @@ async fn test_create_then_list_sinks() {
         let mut sinks = state.sinks.write().await;
         sinks.push(SinkConfig {
             id: "test-sink-1".to_string(),
-            host: "127.0.0.1".to_string(),
-            port: 9200,
+            sink_type: SinkType::Udp {
+                host: "127.0.0.1".to_string(),
+                port: 9200,
+            },
             update_rate_hz: Some(60.0),
             metric_mask: None,
+            format: Default::default(),
+            units: Default::default(),
+            leds: None,
         });
     }
 
@@ -304,10 +757,15 @@ async fn test_delete_sink_returns_204() {
         let mut sinks = state.sinks.write().await;
         sinks.push(SinkConfig {
             id: "to-delete".to_string(),
-            host: "127.0.0.1".to_string(),
-            port: 9200,
+            sink_type: SinkType::Udp {
+                host: "127.0.0.1".to_string(),
+                port: 9200,
+            },
             update_rate_hz: Some(60.0),
             metric_mask: None,
+            format: Default::default(),
+            units: Default::default(),
+            leds: None,
         });
     }
 
@@ -463,22 +921,398 @@ async fn test_telemetry_stream_receives_broadcast_frame() {
 }
 
 #[tokio::test]
-async fn test_telemetry_stream_with_metric_filter() {
+async fn test_session_raw_returns_adapter_yaml() {
     let (app, state) = app_with_state();
+    let get = || {
+        Request::builder()
+            .uri("/api/session/raw")
+            .body(Body::empty())
+            .unwrap()
+    };
 
-    let tx = state.telemetry_tx.clone();
-    tokio::spawn(async move {
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-        let mut adapter = ost_adapters::DemoAdapter::new();
-        adapter.start().unwrap();
-        let frame = adapter.read_frame().unwrap().unwrap();
-        let _ = tx.send(frame);
-    });
+    let response = app.clone().oneshot(get()).await.unwrap();
+    assert_eq!(response.status(), 404);
 
-    let response = app
-        .oneshot(
-            Request::builder()
-                .uri("/api/telemetry/stream?metric_mask=vehicle,timing")
+    let mut adapter = ost_adapters::DemoAdapter::new();
+    adapter.start().unwrap();
+    let frame = adapter.read_frame().unwrap().unwrap();
+    let yaml = "---\nWeekendInfo:\n TrackName: spa\n";
+    state.update_session_info(Some(ost_server::state::SessionInfo::from_frame(
+        "demo",
+        &frame,
+        Some(yaml.to_string()),
+    )));
+
+    let response = app.oneshot(get()).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(body_string(response.into_body()).await, yaml);
+
+    // The YAML names every driver, so it's withheld while redacting
+    let mut state = AppState::new();
+    state.transforms = Arc::new(vec![ost_server::transforms::TransformConfig::Redact]);
+    state.update_session_info(Some(ost_server::state::SessionInfo::from_frame(
+        "demo",
+        &frame,
+        Some(yaml.to_string()),
+    )));
+    let response = create_router(state).oneshot(get()).await.unwrap();
+    assert_eq!(response.status(), 403);
+}
+
+#[tokio::test]
+async fn test_adapter_variables() {
+    let (app, state) = app_with_state();
+    state
+        .register_adapter(Box::new(ost_adapters::DemoAdapter::new()))
+        .await;
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    // Nothing to list: the demo publishes no raw variables
+    let response = app
+        .clone()
+        .oneshot(get("/api/adapters/demo/variables"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+    let response = app
+        .clone()
+        .oneshot(get("/api/adapters/nope/variables"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // A replayed .ibt lists its variables under iRacing
+    let mut replay = ost_server::replay::ReplayState::from_file(
+        &Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt"),
+    )
+    .unwrap();
+    replay.set_persistent();
+    *state.replay.write().await = Some(replay);
+
+    let response = app
+        .oneshot(get("/api/adapters/iracing/variables"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let variables: Vec<serde_json::Value> =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    let rpm = variables.iter().find(|v| v["name"] == "RPM").unwrap();
+    assert_eq!(rpm["type"], "float");
+    assert_eq!(rpm["unit"], "revs/min");
+    assert_eq!(rpm["count"], 1);
+    assert!(rpm["description"].as_str().is_some_and(|d| !d.is_empty()));
+}
+
+#[tokio::test]
+async fn test_list_channels() {
+    let (app, state) = app_with_state();
+    // Games come from what the registered adapters declare, before any frames
+    state
+        .register_adapter(Box::new(ost_adapters::AccAdapter::new()))
+        .await;
+    state
+        .register_adapter(Box::new(ost_adapters::DemoAdapter::new()))
+        .await;
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let channels = |response: axum::response::Response| async move {
+        assert_eq!(response.status(), 200);
+        serde_json::from_str::<Vec<serde_json::Value>>(&body_string(response.into_body()).await)
+            .unwrap()
+    };
+
+    let all = channels(app.clone().oneshot(get("/api/channels")).await.unwrap()).await;
+    let speed = all.iter().find(|c| c["path"] == "vehicle.speed").unwrap();
+    assert_eq!(speed["type"], "number");
+    assert_eq!(speed["unit"], "m/s");
+    assert_eq!(
+        speed["games"],
+        serde_json::json!(["Assetto Corsa Competizione", "Demo"])
+    );
+    let rpm = all.iter().find(|c| c["path"] == "vehicle.rpm").unwrap();
+    assert_eq!(rpm["games"], serde_json::json!(["Demo"]));
+    let soc = all
+        .iter()
+        .find(|c| c["path"] == "energy.battery_soc")
+        .unwrap();
+    assert_eq!(soc["games"], serde_json::json!([]));
+
+    let found = channels(
+        app.clone()
+            .oneshot(get("/api/channels?q=speed"))
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert!(found.len() < all.len());
+    assert!(found.iter().any(|c| c["path"] == "vehicle.speed"));
+
+    let provided = channels(
+        app.oneshot(get("/api/channels?game=assetto%20corsa%20competizione"))
+            .await
+            .unwrap(),
+    )
+    .await;
+    let paths: Vec<_> = provided
+        .iter()
+        .map(|c| c["path"].as_str().unwrap())
+        .collect();
+    assert!(paths.contains(&"vehicle.speed"));
+    assert!(!paths.contains(&"vehicle.rpm"));
+}
+
+/// Read the next SSE chunk, failing the test if none arrives
+async fn next_event(stream: &mut axum::body::BodyDataStream) -> String {
+    use futures::StreamExt;
+    let chunk = tokio::time::timeout(std::time::Duration::from_secs(3), stream.next())
+        .await
+        .expect("SSE event")
+        .unwrap()
+        .unwrap();
+    String::from_utf8(chunk.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_session_stream_sends_current_then_changes() {
+    let (app, state) = app_with_state();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/session/stream")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let mut stream = response.into_body().into_data_stream();
+    assert_eq!(next_event(&mut stream).await.trim(), "data: null");
+
+    let mut adapter = ost_adapters::DemoAdapter::new();
+    adapter.start().unwrap();
+    let frame = adapter.read_frame().unwrap().unwrap();
+    let info = ost_server::state::SessionInfo::from_frame("demo", &frame, None);
+    state.update_session_info(Some(info.clone()));
+    // Unchanged metadata is not sent again
+    state.update_session_info(Some(info));
+    state.update_session_info(None);
+
+    let text = next_event(&mut stream).await;
+    assert!(text.contains(r#""track_name":"Demo Circuit""#), "{}", text);
+    assert_eq!(next_event(&mut stream).await.trim(), "data: null");
+}
+
+#[tokio::test]
+async fn test_unified_stream_sends_spotter_calls() {
+    let (app, state) = app_with_state();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/stream")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let mut stream = response.into_body().into_data_stream();
+
+    let frame: ost_core::model::TelemetryFrame = serde_json::from_value(serde_json::json!({
+        "meta": {"timestamp": "2026-01-01T00:00:00Z", "game": "test", "tick": 1},
+        "timing": {"lap_distance_pct": 0.5},
+        "session": {"track_length": 4000.0},
+        "competitors": [{"car_index": 4, "lap_distance_pct": 0.5005}]
+    }))
+    .unwrap();
+    state.update_spotter(Some(&frame));
+    state.update_spotter(None);
+
+    let mut calls = Vec::new();
+    while calls.len() < 2 {
+        let text = next_event(&mut stream).await;
+        if text.starts_with("event: spotter") {
+            calls.push(text);
+        }
+    }
+    assert!(
+        calls[0].contains(r#"{"call":"alongside","cars":[4]}"#),
+        "{}",
+        calls[0]
+    );
+    assert!(
+        calls[1].contains(r#"{"call":"clear","cars":[]}"#),
+        "{}",
+        calls[1]
+    );
+}
+
+#[tokio::test]
+async fn test_competitors_stream_sends_list_then_standings() {
+    let (app, state) = app_with_state();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/competitors/stream?rate=1")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let mut stream = response.into_body().into_data_stream();
+
+    let mut adapter = ost_adapters::DemoAdapter::new();
+    adapter.start().unwrap();
+    let mut frame = adapter.read_frame().unwrap().unwrap();
+    state.telemetry_tx.send(frame.clone()).unwrap();
+    let text = next_event(&mut stream).await;
+    assert!(text.starts_with("event: competitors"), "{}", text);
+
+    // Swap the first two cars; the full list isn't due again yet at 1 Hz
+    let competitors = frame.competitors.as_mut().unwrap();
+    let (a, b) = (competitors[0].position, competitors[1].position);
+    competitors[0].position = b;
+    competitors[1].position = a;
+    state.telemetry_tx.send(frame).unwrap();
+    let text = next_event(&mut stream).await;
+    assert!(text.starts_with("event: standings"), "{}", text);
+    let json: serde_json::Value =
+        serde_json::from_str(text.lines().nth(1).unwrap().trim_start_matches("data: ")).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_telemetry_stream_without_competitors() {
+    let (app, state) = app_with_state();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/telemetry/stream?competitors=false&mode=full")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let mut stream = response.into_body().into_data_stream();
+
+    let mut adapter = ost_adapters::DemoAdapter::new();
+    adapter.start().unwrap();
+    let frame = adapter.read_frame().unwrap().unwrap();
+    assert!(frame.competitors.is_some());
+    state.telemetry_tx.send(frame).unwrap();
+
+    let text = next_event(&mut stream).await;
+    assert!(text.contains(r#""vehicle""#), "{}", text);
+    assert!(!text.contains(r#""competitors""#), "{}", text);
+}
+
+#[tokio::test]
+async fn test_telemetry_stream_msgpack_layouts() {
+    use base64::Engine;
+    let (app, state) = app_with_state();
+
+    let mut streams = Vec::new();
+    for format in ["msgpack", "msgpack_named"] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/telemetry/stream?format={}", format))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        streams.push(response.into_body().into_data_stream());
+    }
+    state
+        .telemetry_tx
+        .send(ost_core::model::TelemetryFrame::new("Test"))
+        .unwrap();
+
+    let mut values = Vec::new();
+    for stream in &mut streams {
+        let text = next_event(stream).await;
+        assert!(text.starts_with("event: msgpack"), "{}", text);
+        let data = text.lines().nth(1).unwrap().trim_start_matches("data: ");
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .unwrap();
+        values.push(rmp_serde::from_slice::<serde_json::Value>(&bytes).unwrap());
+    }
+    // `msgpack` keeps its positional arrays; `msgpack_named` is keyed by name
+    assert!(values[0]["meta"].is_array(), "{}", values[0]);
+    assert_eq!(values[1]["meta"]["game"], "Test");
+}
+
+#[tokio::test]
+async fn test_telemetry_ws() {
+    use futures::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let state = AppState::new();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = create_router(state.clone());
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    let connect = |query: &'static str| async move {
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let url = format!("ws://{}/api/telemetry/ws{}", addr, query);
+        tokio_tungstenite::client_async(url, stream)
+            .await
+            .map(|(socket, _)| socket)
+    };
+
+    assert!(connect("?format=csv").await.is_err());
+    let mut binary = connect("?format=binary&metric_mask=vehicle").await.unwrap();
+    let mut json = connect("").await.unwrap();
+
+    let mut adapter = ost_adapters::DemoAdapter::new();
+    adapter.start().unwrap();
+    let frame = adapter.read_frame().unwrap().unwrap();
+    state.telemetry_tx.send(frame).unwrap();
+
+    let next = |message: Option<Result<Message, _>>| message.unwrap().unwrap();
+    let timeout = std::time::Duration::from_secs(2);
+    let Message::Binary(bytes) = next(tokio::time::timeout(timeout, binary.next()).await.unwrap())
+    else {
+        panic!("expected a binary message");
+    };
+    let value: serde_json::Value = rmp_serde::from_slice(&bytes).unwrap();
+    assert_eq!(value["meta"]["game"], "Demo");
+    assert!(value.get("vehicle").is_some());
+    assert!(value.get("timing").is_none());
+
+    let Message::Text(text) = next(tokio::time::timeout(timeout, json.next()).await.unwrap())
+    else {
+        panic!("expected a text message");
+    };
+    let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(value["meta"]["game"], "Demo");
+    assert!(value.get("timing").is_some());
+}
+
+#[tokio::test]
+async fn test_telemetry_stream_with_metric_filter() {
+    let (app, state) = app_with_state();
+
+    let tx = state.telemetry_tx.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let mut adapter = ost_adapters::DemoAdapter::new();
+        adapter.start().unwrap();
+        let frame = adapter.read_frame().unwrap().unwrap();
+        let _ = tx.send(frame);
+    });
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/telemetry/stream?metric_mask=vehicle,timing")
                 .body(Body::empty())
                 .unwrap(),
         )
@@ -649,6 +1483,59 @@ async fn test_telemetry_stream_delta_false_no_marker() {
     }
 }
 
+#[tokio::test]
+async fn test_telemetry_stream_merge_patch_mode() {
+    let (app, state) = app_with_state();
+
+    let tx = state.telemetry_tx.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let mut adapter = ost_adapters::DemoAdapter::new();
+        adapter.start().unwrap();
+        for _ in 0..2 {
+            let frame = adapter.read_frame().unwrap().unwrap();
+            let _ = tx.send(frame);
+        }
+    });
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/telemetry/stream?mode=delta")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let body = response.into_body();
+    let frames = tokio::time::timeout(std::time::Duration::from_secs(3), async {
+        let mut stream = body.into_data_stream();
+        use futures::StreamExt;
+        let mut frames = Vec::new();
+        while frames.len() < 2 {
+            let Some(Ok(chunk)) = stream.next().await else {
+                break;
+            };
+            let text = String::from_utf8(chunk.to_vec()).unwrap();
+            for line in text.lines().filter(|l| l.starts_with("data:")) {
+                let json_str = line.trim_start_matches("data:").trim();
+                frames.push(serde_json::from_str::<serde_json::Value>(json_str).unwrap());
+            }
+        }
+        frames
+    })
+    .await
+    .expect("should receive two frames");
+
+    // First frame is full, second is a field-level merge patch
+    assert!(frames[0].get("_delta").is_none());
+    assert_eq!(frames[1]["_delta"], "merge-patch");
+    // Static fields like the car name are not resent
+    assert!(frames[1]["vehicle"].get("car_name").is_none());
+}
+
 // ==================== AppState unit tests ====================
 
 #[tokio::test]
@@ -880,10 +1767,19 @@ async fn test_replay_upload_parses_ibt_and_returns_info() {
 }
 
 #[tokio::test]
-async fn test_replay_upload_rejects_non_ibt() {
-    let app = app();
-    let (boundary, body) = multipart_body("data.csv", b"not an ibt file");
+async fn test_replay_upload_accepts_gzip_recording() {
+    use std::io::Write;
+
+    let mut adapter = ost_adapters::DemoAdapter::new();
+    adapter.start().unwrap();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    for _ in 0..5 {
+        let frame = adapter.read_frame().unwrap().unwrap();
+        writeln!(encoder, "{}", serde_json::to_string(&frame).unwrap()).unwrap();
+    }
+    let (boundary, body) = multipart_body("session.ost.ndjson.gz", &encoder.finish().unwrap());
 
+    let (app, _state) = app_with_state();
     let response = app
         .oneshot(
             Request::builder()
@@ -899,45 +1795,596 @@ async fn test_replay_upload_rejects_non_ibt() {
         .await
         .unwrap();
 
-    assert_eq!(response.status(), 400, "Non-.ibt upload should return 400");
+    assert_eq!(response.status(), 200);
+    let parsed: serde_json::Value =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    assert_eq!(parsed["info"]["total_frames"], 5);
+    assert_eq!(parsed["info"]["track_name"], "Demo Circuit");
 }
 
-// ==================== Persistence download round-trip ====================
-
 #[tokio::test]
-async fn test_persistence_download_round_trip() {
+async fn test_replay_overview_summarises_each_second() {
     let (app, state) = app_with_state();
-
-    // Push some frames into the history buffer
-    let mut adapter = ost_adapters::DemoAdapter::new();
-    adapter.start().unwrap();
-    {
-        let mut history = state.history.write().await;
-        for _ in 0..10 {
-            let frame = adapter.read_frame().unwrap().unwrap();
-            history.push(frame);
-        }
-    }
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
 
     let response = app
-        .oneshot(
-            Request::builder()
-                .uri("/api/persistence/download")
-                .body(Body::empty())
-                .unwrap(),
-        )
+        .clone()
+        .oneshot(get("/api/replay/overview"))
         .await
         .unwrap();
+    assert_eq!(response.status(), 404, "no replay loaded");
 
-    assert_eq!(
-        response.status(),
-        200,
-        "GET /api/persistence/download should return 200"
-    );
-
-    let content_type = response
-        .headers()
-        .get("content-type")
+    // 2.5 seconds at 60 Hz, speeding up by 1 m/s per frame
+    let path = std::env::temp_dir().join(format!("ost-overview-{}.ost.ndjson", std::process::id()));
+    let lines: Vec<String> = (0..150)
+        .map(|i| {
+            serde_json::json!({
+                "meta": {
+                    "timestamp": chrono::DateTime::from_timestamp_millis(1_767_225_600_000 + i * 1000 / 60).unwrap(),
+                    "game": "test",
+                    "tick": i
+                },
+                "vehicle": {"speed": i as f32, "throttle": 1.0}
+            })
+            .to_string()
+        })
+        .collect();
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    let replay = ost_server::replay::ReplayState::from_ndjson(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    *state.replay.write().await = Some(replay);
+
+    let response = app.oneshot(get("/api/replay/overview")).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let overview: serde_json::Value =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    assert_eq!(overview["frames_per_bucket"], 60);
+    let speed = overview["channels"]["vehicle.speed"].as_array().unwrap();
+    assert_eq!(speed.len(), 3);
+    assert_eq!(speed[1]["min"], 60.0);
+    assert_eq!(speed[1]["max"], 119.0);
+    assert_eq!(overview["channels"]["vehicle.throttle"][2]["avg"], 1.0);
+    assert!(overview["channels"].get("vehicle.rpm").is_none());
+}
+
+#[tokio::test]
+async fn test_replay_session_limits_playback() {
+    let (app, state) = app_with_state();
+    let mut replay = ost_server::replay::ReplayState::from_file(
+        &Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt"),
+    )
+    .unwrap();
+    replay.set_persistent();
+    let last = replay.total_frames() - 1;
+    *state.replay.write().await = Some(replay);
+    let control = |body: &'static str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/replay/control")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let json = |response: axum::response::Response| async move {
+        assert_eq!(response.status(), 200);
+        serde_json::from_str::<serde_json::Value>(&body_string(response.into_body()).await).unwrap()
+    };
+
+    let info = json(
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/replay/info")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(
+        info["sessions"],
+        serde_json::json!([{"session_num": 1, "session_type": "Race", "start_frame": 0, "end_frame": last}])
+    );
+    assert!(info.get("session").is_none());
+
+    // Qualifying is in the session info but wasn't recorded
+    let response = app
+        .clone()
+        .oneshot(control(r#"{"action": "session", "value": 0}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let set = json(
+        app.clone()
+            .oneshot(control(r#"{"action": "session", "value": 1}"#))
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(set["session"], 1);
+    assert_eq!(set["frame"], 0);
+    let seeked = json(
+        app.clone()
+            .oneshot(control(r#"{"action": "seek", "value": 1e9}"#))
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(seeked["frame"], last);
+    assert_eq!(
+        state.replay.read().await.as_ref().unwrap().info().session,
+        Some(1)
+    );
+
+    let set = json(
+        app.oneshot(control(r#"{"action": "session"}"#))
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert!(set["session"].is_null());
+    assert_eq!(set["frame"], 0);
+}
+
+#[tokio::test]
+async fn test_replay_suspension_analyses_each_corner() {
+    let (app, state) = app_with_state();
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(get("/api/replay/suspension"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404, "no replay loaded");
+
+    // 10 seconds at 60 Hz of the rear left bouncing at 3 Hz
+    let path =
+        std::env::temp_dir().join(format!("ost-suspension-{}.ost.ndjson", std::process::id()));
+    let lines: Vec<String> = (0..600)
+        .map(|i| {
+            let t = i as f64 / 60.0;
+            serde_json::json!({
+                "meta": {
+                    "timestamp": chrono::DateTime::from_timestamp_millis(1_767_225_600_000 + i * 1000 / 60).unwrap(),
+                    "game": "test",
+                    "tick": i
+                },
+                "wheels": {
+                    "front_left": {}, "front_right": {}, "rear_right": {},
+                    "rear_left": {"shock_velocity": 80.0 * (2.0 * std::f64::consts::PI * 3.0 * t).sin()}
+                }
+            })
+            .to_string()
+        })
+        .collect();
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    let replay = ost_server::replay::ReplayState::from_ndjson(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    *state.replay.write().await = Some(replay);
+
+    let response = app
+        .clone()
+        .oneshot(get("/api/replay/suspension?bin_width=0"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = app
+        .oneshot(get("/api/replay/suspension?start=100&bin_width=50"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let analysis: serde_json::Value =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    assert_eq!(analysis["start_frame"], 100);
+    assert_eq!(analysis["end_frame"], 599);
+    let corners = analysis["corners"].as_object().unwrap();
+    assert_eq!(corners.keys().collect::<Vec<_>>(), ["rear_left"]);
+    let rear_left = &corners["rear_left"];
+    assert_eq!(rear_left["samples"], 500);
+    assert_eq!(
+        rear_left["histogram"]["fractions"]
+            .as_array()
+            .unwrap()
+            .len(),
+        20
+    );
+    let peak = rear_left["spectrum"]["peak_hz"].as_f64().unwrap();
+    assert!((peak - 3.0).abs() < 0.5, "peak at {peak} Hz");
+}
+
+#[tokio::test]
+async fn test_analytics_corners_compares_laps() {
+    let (app, state) = app_with_state();
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(get("/api/analytics/corners"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404, "no replay loaded");
+
+    // Three 10 second laps at 10 Hz with a right-hander from 40% to 60%,
+    // taken at 20 m/s on lap 2 and 25 m/s on lap 3
+    let path = std::env::temp_dir().join(format!("ost-corners-{}.ost.ndjson", std::process::id()));
+    let lines: Vec<String> = (0..300)
+        .map(|i| {
+            let (lap, pct) = (i / 100 + 1, (i % 100) as f32 / 100.0);
+            let corner = (40..60).contains(&(i % 100));
+            let speed = match (corner, lap) {
+                (false, _) => 40.0,
+                (true, 3) => 25.0,
+                (true, _) => 20.0,
+            };
+            serde_json::json!({
+                "meta": {
+                    "timestamp": chrono::DateTime::from_timestamp_millis(1_767_225_600_000 + i * 100).unwrap(),
+                    "game": "test",
+                    "tick": i
+                },
+                "motion": {"g_force": {"x": if corner { 1.0 } else { 0.0 }, "y": 1.0, "z": 0.0}},
+                "vehicle": {"speed": speed, "gear": 3},
+                "timing": {"lap_number": lap, "lap_distance_pct": pct, "lap_valid": true, "last_lap_time": 10.0}
+            })
+            .to_string()
+        })
+        .collect();
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    let replay = ost_server::replay::ReplayState::from_ndjson(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    *state.replay.write().await = Some(replay);
+
+    let response = app
+        .clone()
+        .oneshot(get("/api/analytics/corners?lap=9"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // Lap 2 is the only complete valid lap, so it's the reference
+    let response = app
+        .oneshot(get("/api/analytics/corners?compare=3"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    assert_eq!(body["lap"], 2);
+    let corners = body["corners"].as_array().unwrap();
+    assert_eq!(corners.len(), 1);
+    assert_eq!(corners[0]["number"], 1);
+    assert_eq!(corners[0]["direction"], "right");
+    assert_eq!(corners[0]["min_speed"], 20.0);
+    assert_eq!(corners[0]["entry_speed"], 40.0);
+    assert_eq!(corners[0]["gear"], 3);
+    assert_eq!(body["compare"]["lap"], 3);
+    assert_eq!(body["compare"]["corners"][0]["min_speed"], 25.0);
+}
+
+#[tokio::test]
+async fn test_analytics_pace_of_replay() {
+    let (app, state) = app_with_state();
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let json = |body: String| serde_json::from_str::<serde_json::Value>(&body).unwrap();
+
+    // Nothing loaded: the live session, with no laps yet
+    let response = app
+        .clone()
+        .oneshot(get("/api/analytics/pace"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body = json(body_string(response.into_body()).await);
+    assert_eq!(body["source"], "live");
+    assert_eq!(body["pace"]["laps"], 0);
+    let response = app
+        .clone()
+        .oneshot(get("/api/analytics/pace?source=replay"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // Laps at 10 Hz, each half way round after 5 seconds; laps 2 and 3
+    // complete and valid in 10.2 and 10.3 seconds
+    let path = std::env::temp_dir().join(format!("ost-pace-{}.ost.ndjson", std::process::id()));
+    let lines: Vec<String> = (0..400)
+        .map(|i| {
+            let (lap, pct) = (i / 100 + 1, (i % 100) as f32 / 100.0);
+            serde_json::json!({
+                "meta": {
+                    "timestamp": chrono::DateTime::from_timestamp_millis(1_767_225_600_000 + i * 100).unwrap(),
+                    "game": "test",
+                    "tick": i
+                },
+                "session": {"sector_starts": [0.0, 0.5]},
+                "timing": {"lap_number": lap, "lap_distance_pct": pct, "lap_valid": true,
+                           "last_lap_time": 10.0 + lap as f32 / 10.0}
+            })
+            .to_string()
+        })
+        .collect();
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    let replay = ost_server::replay::ReplayState::from_ndjson(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    *state.replay.write().await = Some(replay);
+
+    let response = app
+        .clone()
+        .oneshot(get("/api/analytics/pace?window=1"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = app.oneshot(get("/api/analytics/pace")).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body = json(body_string(response.into_body()).await);
+    assert_eq!(body["source"], "replay");
+    let pace = &body["pace"];
+    assert_eq!(
+        (pace["laps"].as_u64(), pace["valid_laps"].as_u64()),
+        (Some(3), Some(2))
+    );
+    let secs = |v: &serde_json::Value| v.as_f64().unwrap();
+    assert!((secs(&pace["consistency"]["mean"]) - 10.25).abs() < 1e-3);
+    assert!((secs(&pace["consistency"]["stddev"]) - 0.05).abs() < 1e-3);
+    assert!((secs(&pace["best_sectors"][0]) - 5.0).abs() < 1e-3);
+    assert!((secs(&pace["theoretical_best"]) - 10.2).abs() < 1e-3);
+    assert!(pace["pace_vs_leader"].is_null());
+}
+
+#[tokio::test]
+async fn test_replay_upload_recovers_truncated_ibt() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt");
+    let ibt_data = std::fs::read(path).unwrap();
+    let int_at = |at: usize| i32::from_le_bytes(ibt_data[at..at + 4].try_into().unwrap()) as usize;
+    // Cut off half way through sample 10000
+    let (buf_len, buf_offset) = (int_at(36), int_at(52));
+    let truncated = &ibt_data[..buf_offset + 10_000 * buf_len + buf_len / 2];
+    let upload = |name: &str, data: &[u8]| {
+        let (boundary, body) = multipart_body(name, data);
+        Request::builder()
+            .method("POST")
+            .uri("/api/replay/upload")
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let app = app();
+    let response = app
+        .clone()
+        .oneshot(upload("truncated.ibt", truncated))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let parsed: serde_json::Value =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    let info = &parsed["info"];
+    assert_eq!(info["total_frames"], 10_000);
+    assert_eq!(info["recovery"]["expected_records"], 38_648);
+    assert_eq!(info["recovery"]["records"], 10_000);
+    let percent = info["recovery"]["percent"].as_f64().unwrap();
+    assert!((percent - 25.87).abs() < 0.01, "{percent}");
+
+    let response = app
+        .oneshot(upload("header.ibt", &ibt_data[..100]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    assert_eq!(
+        body_string(response.into_body()).await,
+        "Failed to parse .ibt file: Not an .ibt file: only 100 bytes"
+    );
+}
+
+#[tokio::test]
+async fn test_replay_upload_indexes_ibt_in_background() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt");
+    let (boundary, body) = multipart_body("indexed.ibt", &std::fs::read(path).unwrap());
+
+    let (app, state) = app_with_state();
+    let mut status_rx = state.status_tx.subscribe();
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/replay/upload")
+                .header(
+                    "content-type",
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // Progress arrives on the status stream until the scan is done
+    let mut progress = Vec::new();
+    loop {
+        let json = tokio::time::timeout(std::time::Duration::from_secs(30), status_rx.recv())
+            .await
+            .expect("index finished")
+            .unwrap();
+        let update: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let index = &update["replay_index"];
+        progress.push(index["progress"].as_f64().unwrap());
+        if index["status"] == "ready" {
+            break;
+        }
+        assert_eq!(index["status"], "indexing");
+    }
+    assert!(progress.len() > 2);
+    assert_eq!(progress.last(), Some(&1.0));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/replay/info")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let info: serde_json::Value =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    let index = &info["index"];
+    assert_eq!(index["status"], "ready");
+    assert_eq!(
+        index["laps"].as_array().unwrap().len(),
+        info["laps"].as_array().unwrap().len()
+    );
+    assert!(
+        index["laps"][1]["channels"]["vehicle.speed"]["max"]
+            .as_f64()
+            .unwrap()
+            > 0.0
+    );
+    assert!(index["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|e| e["kind"] == "off_track"));
+    assert!(index["channels"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|c| c == "Throttle"));
+}
+
+#[tokio::test]
+async fn test_replay_upload_rejects_non_ibt() {
+    let app = app();
+    let (boundary, body) = multipart_body("data.csv", b"not an ibt file");
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/replay/upload")
+                .header(
+                    "content-type",
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400, "Non-.ibt upload should return 400");
+}
+
+#[tokio::test]
+async fn test_replay_upload_limits() {
+    use ost_server::replay_store::{ReplayStorageConfig, ReplayStore};
+
+    let dir = std::env::temp_dir().join(format!("ost-upload-limits-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut state = AppState::new();
+    state.replay_store = Arc::new(ReplayStore::new(
+        dir.clone(),
+        ReplayStorageConfig {
+            max_upload_mb: 1,
+            quota_mb: 2,
+            ..Default::default()
+        },
+    ));
+    let app = create_router(state);
+    let upload = |data: &[u8]| {
+        let (boundary, body) = multipart_body("big.ibt", data);
+        Request::builder()
+            .method("POST")
+            .uri("/api/replay/upload")
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(upload(&vec![0u8; 1024 * 1024 + 1]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 413);
+    let response = app
+        .clone()
+        .oneshot(upload(b"not an ibt file"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    // Neither upload is left behind
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    let response = app
+        .oneshot(
+            Request::get("/api/replay/storage")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let storage: serde_json::Value =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    assert_eq!(
+        storage["usage"],
+        serde_json::json!({"files": 0, "bytes": 0})
+    );
+    assert_eq!(storage["limits"]["quota_mb"], 2);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ==================== Persistence download round-trip ====================
+
+#[tokio::test]
+async fn test_persistence_download_round_trip() {
+    let (app, state) = app_with_state();
+
+    // Push some frames into the history buffer
+    let mut adapter = ost_adapters::DemoAdapter::new();
+    adapter.start().unwrap();
+    {
+        let mut history = state.history.write().await;
+        for _ in 0..10 {
+            let frame = adapter.read_frame().unwrap().unwrap();
+            history.push(frame);
+        }
+    }
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/persistence/download")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        response.status(),
+        200,
+        "GET /api/persistence/download should return 200"
+    );
+
+    let content_type = response
+        .headers()
+        .get("content-type")
         .unwrap()
         .to_str()
         .unwrap();
@@ -977,8 +2424,166 @@ async fn test_persistence_download_empty_returns_404() {
     );
 }
 
+// ==================== Session archive ====================
+
+#[tokio::test]
+async fn test_archive_list_tag_load_delete() {
+    let mut state = AppState::new();
+    let dir = std::env::temp_dir().join(format!("ost-archive-api-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut adapter = ost_adapters::DemoAdapter::new();
+    adapter.start().unwrap();
+    let frames: Vec<_> = (0..5)
+        .map(|_| adapter.read_frame().unwrap().unwrap())
+        .collect();
+    let file = "2026-01-01_12-00-00_Demo_Circuit_Formula_Demo.ost.ndjson.zstd";
+    std::fs::write(
+        dir.join(file),
+        ost_server::persistence::compress_frames(&frames).unwrap(),
+    )
+    .unwrap();
+    state.archive = Arc::new(std::sync::RwLock::new(ost_server::archive::Archive::open(
+        dir.clone(),
+    )));
+    ost_server::archive::index_unindexed(&state.archive);
+    let app = create_router(state.clone());
+
+    let request = |method: &str, uri: String, body: Body| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body)
+            .unwrap()
+    };
+
+    let resp = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/api/archive?game=demo".into(),
+            Body::empty(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let list: serde_json::Value =
+        serde_json::from_str(&body_string(resp.into_body()).await).unwrap();
+    assert_eq!(list.as_array().unwrap().len(), 1);
+    assert_eq!(list[0]["file"], file);
+    assert_eq!(list[0]["track"], "Demo Circuit");
+    assert_eq!(list[0]["frame_count"], 5);
+
+    let resp = app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            format!("/api/archive/{file}/tags"),
+            Body::from(r#"{"tags": ["race", " quali ", "race"]}"#),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/api/archive?tag=quali".into(),
+            Body::empty(),
+        ))
+        .await
+        .unwrap();
+    let list: serde_json::Value =
+        serde_json::from_str(&body_string(resp.into_body()).await).unwrap();
+    assert_eq!(list[0]["tags"], serde_json::json!(["race", "quali"]));
+
+    let resp = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            format!("/api/archive/{file}/load"),
+            Body::empty(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(state.replay.read().await.is_some());
+
+    let resp = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/api/archive/..%2Fsecret.ost.ndjson.zstd/load".into(),
+            Body::empty(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let resp = app
+        .clone()
+        .oneshot(request(
+            "DELETE",
+            format!("/api/archive/{file}"),
+            Body::empty(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 204);
+    assert!(!dir.join(file).exists());
+    let resp = app
+        .oneshot(request("GET", "/api/archive".into(), Body::empty()))
+        .await
+        .unwrap();
+    assert_eq!(body_string(resp.into_body()).await, "[]");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
 // ==================== DELETE /api/persistence/files/:name ====================
 
+#[tokio::test]
+async fn test_delete_persistence_file_drops_archive_entry() {
+    let mut state = AppState::new();
+    let dir = std::env::temp_dir().join(format!("ost-delete-file-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut adapter = ost_adapters::DemoAdapter::new();
+    adapter.start().unwrap();
+    let frames = vec![adapter.read_frame().unwrap().unwrap()];
+    let file = "2026-01-01_12-00-00_Demo_Circuit_Formula_Demo.ost.ndjson.zstd";
+    std::fs::write(
+        dir.join(file),
+        ost_server::persistence::compress_frames(&frames).unwrap(),
+    )
+    .unwrap();
+    state.archive = Arc::new(std::sync::RwLock::new(ost_server::archive::Archive::open(
+        dir.clone(),
+    )));
+    ost_server::archive::index_unindexed(&state.archive);
+    assert_eq!(state.archive.read().unwrap().entries().len(), 1);
+    let app = create_router(state.clone());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/api/persistence/files/{file}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+    assert!(!dir.join(file).exists());
+    assert!(state.archive.read().unwrap().entries().is_empty());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
 #[tokio::test]
 async fn test_delete_persistence_file_nonexistent_returns_404() {
     let app = app();
@@ -1028,8 +2633,7 @@ async fn test_ibt_frame_golden_structure() {
     let ibt = IbtFile::open(&fixture_path()).expect("Failed to open fixture");
 
     // Read frame at index 1800 (~30s in, car on track)
-    let sample = ibt.read_sample(1800).expect("Failed to read sample");
-    let frame = ibt.sample_to_frame(&sample);
+    let frame = ibt.read_frame(1800).expect("Failed to read frame");
     let json = serde_json::to_value(&frame).expect("Frame should serialize");
 
     // Verify top-level structure
@@ -1098,6 +2702,259 @@ async fn test_ibt_frame_golden_structure() {
     );
 }
 
+#[tokio::test]
+async fn test_telemetry_stream_disconnects_lagging_client_on_request() {
+    let (app, state) = app_with_state();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/telemetry/stream?on_lag=disconnect")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(state.subscribers.snapshot().len(), 1);
+
+    // Overrun the broadcast channel before the client reads anything
+    for _ in 0..150 {
+        let _ = state
+            .telemetry_tx
+            .send(ost_core::model::TelemetryFrame::new("test"));
+    }
+
+    // The stream ends instead of carrying on
+    tokio::time::timeout(
+        std::time::Duration::from_secs(2),
+        body_bytes(response.into_body()),
+    )
+    .await
+    .expect("lagging stream should be closed");
+    assert_eq!(state.subscribers.lag_disconnects(), 1);
+    assert!(state.subscribers.dropped_total() > 0);
+    assert!(state.subscribers.snapshot().is_empty());
+}
+
+#[tokio::test]
+async fn test_list_and_disconnect_clients() {
+    let (app, state) = app_with_state();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/stream?rate=60&metric_mask=vehicle")
+                .header("user-agent", "pit-wall/1.0")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let mut stream = response.into_body().into_data_stream();
+    let mut frame = ost_core::model::TelemetryFrame::new("test");
+    state.assign_seq(&mut frame);
+    state.telemetry_tx.send(frame).unwrap();
+    // Initial status, sinks and annotations, then the frame
+    for _ in 0..4 {
+        next_event(&mut stream).await;
+    }
+    let mut frame = ost_core::model::TelemetryFrame::new("test");
+    state.assign_seq(&mut frame);
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/api/clients").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let clients: serde_json::Value =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    let client = &clients[0];
+    assert_eq!(client["endpoint"], "/api/stream");
+    assert_eq!(client["rate"], 60.0);
+    assert_eq!(client["metric_mask"], "vehicle");
+    assert_eq!(client["user_agent"], "pit-wall/1.0");
+    assert_eq!(client["frames_sent"], 1);
+    assert_eq!(client["last_seq"], 1);
+    assert_eq!(client["behind"], 1);
+
+    let uri = format!("/api/clients/{}", client["id"]);
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(&uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+    // The stream ends
+    tokio::time::timeout(std::time::Duration::from_secs(2), async {
+        use futures::StreamExt;
+        while stream.next().await.is_some() {}
+    })
+    .await
+    .expect("disconnected stream should be closed");
+    drop(stream);
+    assert!(state.subscribers.snapshot().is_empty());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(&uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+// ==================== GET /metrics ====================
+
+#[tokio::test]
+async fn test_prometheus_metrics() {
+    let (app, state) = app_with_state();
+    let mut frame = ost_core::model::TelemetryFrame::new("test");
+    state.assign_seq(&mut frame);
+    assert!(frame.meta.broadcast_at >= frame.meta.acquired_at);
+    state
+        .latency
+        .record(ost_server::latency::Stage::Broadcast, &frame);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/metrics")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body = body_string(response.into_body()).await;
+    assert!(body.contains("ost_stream_subscribers 0\n"));
+    assert!(body.contains("ost_frames_broadcast_total 1\n"));
+    assert!(body.contains("ost_frame_latency_seconds{stage=\"broadcast\",quantile=\"0.99\"} "));
+    assert!(body.contains("ost_frame_latency_seconds_count{stage=\"sink\"} 0\n"));
+}
+
+#[tokio::test]
+async fn test_prometheus_validation_counters() {
+    use ost_core::validate::ValidationMode;
+
+    let (app, state) = app_with_state();
+    state
+        .register_adapter(Box::new(ost_adapters::DemoAdapter::new()))
+        .await;
+    let mut frame = ost_core::model::TelemetryFrame::new("test");
+    frame.vehicle = serde_json::from_value(serde_json::json!({"speed": -3.0})).unwrap();
+    state.adapters.read().await[0]
+        .lock_stats()
+        .record_validation(&frame.validate(ValidationMode::Annotate));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/metrics")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = body_string(response.into_body()).await;
+    assert!(body.contains("# TYPE ost_validation_invalid_frames_total counter\n"));
+    assert!(body.contains("ost_validation_invalid_frames_total{adapter=\"demo\"} 1\n"));
+    assert!(body.contains("ost_validation_invalid_values_total{adapter=\"demo\"} 1\n"));
+}
+
+// ==================== GET /api/history/frames ====================
+
+#[tokio::test]
+async fn test_history_frames_since_seq() {
+    let (app, state) = app_with_state();
+
+    {
+        let mut history = state.history.write().await;
+        for seq in 1..=5 {
+            let mut frame = ost_core::model::TelemetryFrame::new("test");
+            state.assign_seq(&mut frame);
+            assert_eq!(frame.meta.seq, Some(seq));
+            history.push(frame);
+        }
+    }
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/history/frames?since_seq=3")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let body = body_string(response.into_body()).await;
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let seqs: Vec<_> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["f"]["meta"]["seq"].as_u64().unwrap())
+        .collect();
+    assert_eq!(seqs, [4, 5]);
+}
+
+#[tokio::test]
+async fn test_stream_resumes_from_last_event_id() {
+    let (app, state) = app_with_state();
+    let frame = |state: &AppState| {
+        let mut frame = ost_core::model::TelemetryFrame::new("test");
+        state.assign_seq(&mut frame);
+        frame
+    };
+    {
+        let mut history = state.history.write().await;
+        for _ in 1..=20 {
+            history.push(frame(&state));
+        }
+    }
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/telemetry/stream")
+                .header("last-event-id", "15")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let mut stream = response.into_body().into_data_stream();
+    let event_id = |text: String| {
+        text.lines()
+            .find_map(|line| line.strip_prefix("id: "))
+            .and_then(|id| id.parse::<u64>().ok())
+    };
+    for seq in 16..=20 {
+        assert_eq!(event_id(next_event(&mut stream).await), Some(seq));
+    }
+
+    // Live frames already sent while resuming are skipped
+    let history = state.history.read().await;
+    let sent = history.latest_frame().unwrap().clone();
+    drop(history);
+    state.telemetry_tx.send(sent).unwrap();
+    state.telemetry_tx.send(frame(&state)).unwrap();
+    assert_eq!(event_id(next_event(&mut stream).await), Some(21));
+}
+
 // ==================== GET /api/history/aggregate ====================
 
 #[tokio::test]
@@ -1215,6 +3072,120 @@ async fn test_history_aggregate_unknown_metric() {
     assert!(json.as_object().unwrap().is_empty());
 }
 
+#[tokio::test]
+async fn test_get_metrics_with_units() {
+    let (app, state) = app_with_state();
+
+    {
+        let mut history = state.history.write().await;
+        let json = serde_json::json!({
+            "meta": {
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+                "game": "test",
+                "tick": 0
+            },
+            "vehicle": {"speed": 10.0},
+            "engine": {"water_temp": 100.0}
+        });
+        let frame: ost_core::model::TelemetryFrame = serde_json::from_value(json).unwrap();
+        history.push(frame);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/metrics?metric_mask=vehicle.speed,engine.water_temp&units=imperial")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let json: serde_json::Value =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    assert_eq!(json["vehicle"]["speed"].as_f64().unwrap() as f32, 22.3694);
+    assert_eq!(json["engine"]["water_temp"], 212.0);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/metrics?units=furlongs")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // MessagePack frames are always in model units
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/telemetry/stream?format=msgpack&units=imperial")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn test_overlay_snapshot() {
+    let (app, state) = app_with_state();
+
+    // Before any frame: the same shape, with nothing known
+    let response = app
+        .clone()
+        .oneshot(Request::get("/overlay").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let json: serde_json::Value =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    assert_eq!(json["schema"], 1);
+    assert!(json["lap"]["number"].is_null());
+
+    {
+        let mut history = state.history.write().await;
+        let json = serde_json::json!({
+            "meta": {
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+                "game": "test",
+                "tick": 0
+            },
+            "timing": {"lap_number": 3, "race_position": 2},
+            "wheels": {
+                "front_left": {"surface_temp_middle": 100.0},
+                "front_right": {}, "rear_left": {}, "rear_right": {}
+            }
+        });
+        let frame: ost_core::model::TelemetryFrame = serde_json::from_value(json).unwrap();
+        history.push(frame);
+    }
+
+    let response = app
+        .oneshot(
+            Request::get("/overlay?units=imperial")
+                .header("Origin", "http://obs.local")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response
+        .headers()
+        .contains_key("access-control-allow-origin"));
+    let json: serde_json::Value =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    assert_eq!(json["lap"]["number"], 3);
+    assert_eq!(json["position"]["overall"], 2);
+    assert_eq!(json["tyres"]["front_left"]["temp"], 212.0);
+}
+
 // ==================== Custom Metrics API ====================
 
 #[tokio::test]
@@ -1586,6 +3557,63 @@ async fn test_landing_page_in_serve_mode() {
     let _ = std::fs::remove_dir_all(&dir);
 }
 
+#[tokio::test]
+async fn test_api_tokens_guard_mutations_only_by_default() {
+    let mut state = AppState::new();
+    state.auth = ost_server::config::AuthConfig {
+        tokens: vec!["engineer".to_string()],
+        scope: ost_server::config::AuthScope::Mutations,
+    };
+    let app = create_router(state.clone());
+
+    let request = |method: &str, uri: &str, token: Option<&str>| {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header("Authorization", format!("Bearer {token}"));
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let resp = app
+        .clone()
+        .oneshot(request("GET", "/api/adapters", None))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = app
+        .clone()
+        .oneshot(request("DELETE", "/api/replay", None))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+    let resp = app
+        .clone()
+        .oneshot(request("DELETE", "/api/replay", Some("wrong")))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+    let resp = app
+        .oneshot(request("DELETE", "/api/replay", Some("engineer")))
+        .await
+        .unwrap();
+    assert_ne!(resp.status(), 401);
+
+    // scope = "all" also guards streams and reads
+    state.auth.scope = ost_server::config::AuthScope::All;
+    let app = create_router(state);
+    let resp = app
+        .clone()
+        .oneshot(request("GET", "/api/adapters", None))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+    let resp = app
+        .oneshot(request("GET", "/api/adapters?token=engineer", None))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
 #[tokio::test]
 async fn test_session_list_requires_admin_auth() {
     let (app, _state, dir) = serve_app();
@@ -2009,3 +4037,304 @@ async fn test_session_view_page_with_wrong_token() {
 
     let _ = std::fs::remove_dir_all(&dir);
 }
+
+#[tokio::test]
+async fn test_ingest_phone_samples() {
+    let (app, state) = app_with_state();
+
+    let post = |body: &'static str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/ingest")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let response = app
+        .clone()
+        .oneshot(post(
+            r#"[{"accel": {"x": 0.0, "y": 9.81, "z": 4.905}},
+                {"gps": {"latitude": 51.5, "longitude": -0.1, "speed": 20.0}}]"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value =
+        serde_json::from_str(&body_string(response.into_body()).await).unwrap();
+    assert_eq!(body["accepted"], 2);
+    assert!(state.ingest.is_receiving());
+
+    let timeout = std::time::Duration::ZERO;
+    let frame = state.ingest.next_frame(timeout).unwrap();
+    assert_eq!(frame.meta.game, "Phone Sensors");
+    assert!((frame.motion.unwrap().g_force.unwrap().z.0 - 0.5).abs() < 1e-6);
+    let frame = state.ingest.next_frame(timeout).unwrap();
+    assert_eq!(frame.motion.unwrap().latitude, Some(51.5));
+    assert!(state.ingest.next_frame(timeout).is_none());
+
+    let response = app.clone().oneshot(post("[]")).await.unwrap();
+    assert_eq!(response.status(), 400);
+    // A sample without a reading is refused rather than turned into a blank frame
+    let response = app
+        .clone()
+        .oneshot(post(r#"{"source": "Karting"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    assert!(state.ingest.next_frame(timeout).is_none());
+}
+
+#[tokio::test]
+async fn test_replay_ghost_stream() {
+    let (app, state) = app_with_state();
+    let json = |body: String| serde_json::from_str::<serde_json::Value>(&body).unwrap();
+    let post = |uri: &str, body: &'static str| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(post("/api/replay/ghost", r#"{"lap": 2}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // 10 Hz; lap 2 takes 100 frames and lap 3 120
+    let path = std::env::temp_dir().join(format!("ost-ghost-{}.ost.ndjson", std::process::id()));
+    let lines: Vec<String> = (0..340)
+        .map(|i| {
+            let (lap, pct) = match i {
+                0..=99 => (1, i as f32 / 100.0),
+                100..=199 => (2, (i - 100) as f32 / 100.0),
+                200..=319 => (3, (i - 200) as f32 / 120.0),
+                _ => (4, (i - 320) as f32 / 100.0),
+            };
+            serde_json::json!({
+                "meta": {
+                    "timestamp": chrono::DateTime::from_timestamp_millis(1_767_225_600_000 + i * 100).unwrap(),
+                    "game": "test",
+                    "tick": i
+                },
+                "timing": {"lap_number": lap, "lap_distance_pct": pct}
+            })
+            .to_string()
+        })
+        .collect();
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    let mut replay = ost_server::replay::ReplayState::from_ndjson(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    replay.seek(260);
+    *state.replay.write().await = Some(replay);
+
+    let response = app
+        .clone()
+        .oneshot(post("/api/replay/ghost", r#"{"lap": 9}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+    let response = app
+        .clone()
+        .oneshot(post("/api/replay/ghost", r#"{"lap": 2}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        json(body_string(response.into_body()).await),
+        serde_json::json!({"lap": 2, "align": "distance"})
+    );
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/replay/ghost/stream?metric_mask=timing")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let mut stream = response.into_body().into_data_stream();
+    let text = next_event(&mut stream).await;
+    assert!(text.starts_with("event: ghost"), "{}", text);
+    let event = json(
+        text.lines()
+            .nth(1)
+            .unwrap()
+            .trim_start_matches("data: ")
+            .to_string(),
+    );
+    // Half way round lap 3 matches half way round lap 2, a second earlier
+    assert_eq!(event["player"]["i"], 260);
+    assert_eq!(event["reference"]["i"], 150);
+    assert_eq!(event["reference"]["f"]["timing"]["lap_number"], 2);
+    assert!((event["delta"].as_f64().unwrap() - 1.0).abs() < 1e-9);
+
+    // Scrubbing moves the pair along
+    state.replay.write().await.as_mut().unwrap().seek(230);
+    let text = next_event(&mut stream).await;
+    let event = json(
+        text.lines()
+            .nth(1)
+            .unwrap()
+            .trim_start_matches("data: ")
+            .to_string(),
+    );
+    assert_eq!(event["player"]["i"], 230);
+    assert_eq!(event["reference"]["i"], 125);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get("/api/replay/info")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let info = json(body_string(response.into_body()).await);
+    assert_eq!(info["ghost"]["lap"], 2);
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri("/api/replay/ghost")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+    assert!(state
+        .replay
+        .read()
+        .await
+        .as_ref()
+        .unwrap()
+        .ghost()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_replay_extract() {
+    let (app, state) = app_with_state();
+    let extract = |body: &'static str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/replay/extract")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(extract(r#"{"start": 0, "end": 9}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../fixtures/bmwm4gt3_bathurst 2026-02-20 21-45-59.ibt");
+    let mut replay = ost_server::replay::ReplayState::from_file(&path).unwrap();
+    replay.set_persistent();
+    let expected = replay.get_frame(100).unwrap();
+    let total = replay.total_frames();
+    *state.replay.write().await = Some(replay);
+
+    let response = app
+        .clone()
+        .oneshot(extract(r#"{"start": 100, "end": 699}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let disposition = response.headers()["content-disposition"].to_str().unwrap();
+    assert!(
+        disposition.ends_with("_frames100-699.ibt\""),
+        "{}",
+        disposition
+    );
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let out = std::env::temp_dir().join(format!("ost-extract-{}.ibt", std::process::id()));
+    std::fs::write(&out, &bytes).unwrap();
+    let ibt = ost_adapters::ibt_parser::IbtFile::open(&out).unwrap();
+    let _ = std::fs::remove_file(&out);
+    assert_eq!(ibt.record_count(), 600);
+    let first = ibt.read_frame(0).unwrap();
+    let drift = (first.meta.timestamp - expected.meta.timestamp).num_milliseconds();
+    assert!(drift.abs() <= 1, "{}", drift);
+
+    for body in [
+        r#"{"start": 10, "end": 9}"#,
+        r#"{"start": 0}"#,
+        r#"{"lap": 1, "start": 0, "end": 9}"#,
+    ] {
+        let response = app.clone().oneshot(extract(body)).await.unwrap();
+        assert_eq!(response.status(), 400, "{}", body);
+    }
+    let body = format!(r#"{{"start": 0, "end": {}}}"#, total);
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/replay/extract")
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // A lap of a recording, as NDJSON
+    let path = std::env::temp_dir().join(format!("ost-extract-{}.ost.ndjson", std::process::id()));
+    let lines: Vec<String> = (0..300)
+        .map(|i| {
+            serde_json::json!({
+                "meta": {
+                    "timestamp": chrono::DateTime::from_timestamp_millis(1_767_225_600_000 + i * 100).unwrap(),
+                    "game": "test",
+                    "tick": i
+                },
+                "timing": {"lap_number": 1 + i / 100, "lap_distance_pct": (i % 100) as f32 / 100.0}
+            })
+            .to_string()
+        })
+        .collect();
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    let replay = ost_server::replay::ReplayState::from_ndjson(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    *state.replay.write().await = Some(replay);
+
+    let response = app
+        .clone()
+        .oneshot(extract(r#"{"lap": 9, "format": "ndjson"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+    let response = app
+        .oneshot(extract(r#"{"lap": 2, "format": "ndjson"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    let body = body_string(response.into_body()).await;
+    let ticks: Vec<i64> = body
+        .lines()
+        .map(|line| {
+            serde_json::from_str::<serde_json::Value>(line).unwrap()["meta"]["tick"]
+                .as_i64()
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(ticks.first(), Some(&100));
+    assert!(ticks.iter().all(|tick| (100..200).contains(tick)));
+}